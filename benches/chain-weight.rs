@@ -0,0 +1,80 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! ```console
+//! $ cargo bench --features benchmark-private --bench chain-weight
+//! ```
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use forest::benchmark_private::{
+    Address, CachingBlockHeader, ChainConfig, ChainStore, MemoryDB, RawBlockHeader, Tipset,
+};
+use fvm_ipld_blockstore::Blockstore as _;
+use std::{hint::black_box, sync::Arc};
+
+const CHAIN_DEPTH: i64 = 1000;
+
+/// Creates a chain of `CHAIN_DEPTH` tipsets on top of a genesis block, and
+/// returns the genesis block and all tipsets, in chronological order.
+fn make_chain(db: &MemoryDB) -> (RawBlockHeader, Vec<Arc<Tipset>>) {
+    let genesis = RawBlockHeader {
+        miner_address: Address::new_id(0),
+        ..Default::default()
+    };
+    let mut tipsets = vec![Arc::new(Tipset::from(genesis.clone()))];
+    let mut header = genesis.clone();
+    for epoch in 1..=CHAIN_DEPTH {
+        db.put_keyed(&header.cid(), &fvm_ipld_encoding::to_vec(&header).unwrap())
+            .unwrap();
+        header = RawBlockHeader {
+            miner_address: Address::new_id(0),
+            parents: tipsets.last().unwrap().key().clone(),
+            epoch,
+            weight: epoch.into(),
+            ..Default::default()
+        };
+        tipsets.push(Arc::new(Tipset::from(header.clone())));
+    }
+    db.put_keyed(&header.cid(), &fvm_ipld_encoding::to_vec(&header).unwrap())
+        .unwrap();
+    (genesis, tipsets)
+}
+
+fn make_chain_store(db: &Arc<MemoryDB>, genesis: &RawBlockHeader) -> ChainStore<MemoryDB> {
+    ChainStore::new(
+        db.clone(),
+        db.clone(),
+        db.clone(),
+        Arc::new(ChainConfig::default()),
+        CachingBlockHeader::new(genesis.clone()),
+    )
+    .unwrap()
+}
+
+fn bench_chain_weight(c: &mut Criterion) {
+    let db = Arc::new(MemoryDB::default());
+    let (genesis, tipsets) = make_chain(&db);
+    let head = tipsets.last().unwrap();
+
+    let mut group = c.benchmark_group("chain-weight");
+
+    // Walks back the full chain, with empty caches.
+    group.bench_function("compute_chain_weight_at/genesis", |b| {
+        b.iter_batched(
+            || make_chain_store(&db, &genesis),
+            |cs| cs.compute_chain_weight_at(black_box(head), 0).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+
+    let cs = make_chain_store(&db, &genesis);
+    cs.compute_chain_weight_at(head, 0).unwrap();
+    let (ts1, ts2) = (&tipsets[CHAIN_DEPTH as usize / 2], &tipsets[1]);
+    group.bench_function("is_heavier_than/cached", |b| {
+        b.iter(|| cs.is_heavier_than(black_box(ts1), black_box(ts2)))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_chain_weight);
+criterion_main!(benches);
@@ -0,0 +1,101 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! ```console
+//! $ cargo bench --features benchmark-private --bench message-index
+//! ```
+
+use cid::Cid;
+use criterion::{criterion_group, criterion_main, Criterion};
+use forest::benchmark_private::{
+    cid::CidCborExt as _, read_msg_cids, Address, CachingBlockHeader, ChainConfig, ChainStore,
+    MemoryDB, RawBlockHeader, Tipset, TipsetValidator,
+};
+use fvm_ipld_blockstore::Blockstore as _;
+use std::{hint::black_box, sync::Arc};
+
+const CHAIN_DEPTH: i64 = 1000;
+const MESSAGES_PER_TIPSET: i64 = 100;
+
+/// Returns the CID of the `n`-th message of the corpus. The messages themselves
+/// are not needed to index them.
+fn message_cid(n: i64) -> Cid {
+    Cid::from_cbor_blake2b256(&n).unwrap()
+}
+
+/// Creates a chain of `CHAIN_DEPTH` tipsets on top of a genesis block, each
+/// including `MESSAGES_PER_TIPSET` messages, and returns the genesis block
+/// and all tipsets, in chronological order.
+fn make_chain(db: &MemoryDB) -> (RawBlockHeader, Vec<Arc<Tipset>>) {
+    let genesis = RawBlockHeader {
+        miner_address: Address::new_id(0),
+        messages: TipsetValidator::compute_msg_root_from_cids(db, vec![], vec![]).unwrap(),
+        ..Default::default()
+    };
+    let mut tipsets = vec![Arc::new(Tipset::from(genesis.clone()))];
+    let mut header = genesis.clone();
+    for epoch in 1..=CHAIN_DEPTH {
+        db.put_keyed(&header.cid(), &fvm_ipld_encoding::to_vec(&header).unwrap())
+            .unwrap();
+        let messages = (0..MESSAGES_PER_TIPSET)
+            .map(|i| message_cid(epoch * MESSAGES_PER_TIPSET + i))
+            .collect();
+        header = RawBlockHeader {
+            miner_address: Address::new_id(0),
+            parents: tipsets.last().unwrap().key().clone(),
+            epoch,
+            weight: epoch.into(),
+            messages: TipsetValidator::compute_msg_root_from_cids(db, messages, vec![]).unwrap(),
+            ..Default::default()
+        };
+        tipsets.push(Arc::new(Tipset::from(header.clone())));
+    }
+    db.put_keyed(&header.cid(), &fvm_ipld_encoding::to_vec(&header).unwrap())
+        .unwrap();
+    (genesis, tipsets)
+}
+
+/// Finds the tipset including the message by walking back the chain from
+/// `head`, reading the messages of every block.
+fn scan_for_message(db: &MemoryDB, head: &Tipset, msg_cid: &Cid) -> Option<Tipset> {
+    head.clone().chain(db).find(|ts| {
+        ts.block_headers().iter().any(|header| {
+            let (bls_cids, secp_cids) = read_msg_cids(db, &header.messages).unwrap();
+            bls_cids.contains(msg_cid) || secp_cids.contains(msg_cid)
+        })
+    })
+}
+
+fn bench_message_index(c: &mut Criterion) {
+    let db = Arc::new(MemoryDB::default());
+    let (genesis, tipsets) = make_chain(&db);
+    let head = tipsets.last().unwrap();
+    let cs = ChainStore::new(
+        db.clone(),
+        db.clone(),
+        db.clone(),
+        Arc::new(ChainConfig::default()),
+        CachingBlockHeader::new(genesis),
+    )
+    .unwrap();
+    for ts in &tipsets {
+        cs.put_message_tipset_index(ts).unwrap();
+    }
+
+    // A message included halfway down the chain.
+    let msg_cid = message_cid(CHAIN_DEPTH / 2 * MESSAGES_PER_TIPSET);
+
+    let mut group = c.benchmark_group("message-index");
+
+    group.bench_function("tipset_for_message/index", |b| {
+        b.iter(|| cs.tipset_for_message(black_box(&msg_cid)).unwrap())
+    });
+
+    group.bench_function("tipset_for_message/scan", |b| {
+        b.iter(|| scan_for_message(&db, black_box(head), black_box(&msg_cid)).unwrap())
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_message_index);
+criterion_main!(benches);
@@ -29,13 +29,17 @@ generate_markdown_section "forest-wallet" "validate-address"
 generate_markdown_section "forest-wallet" "verify"
 generate_markdown_section "forest-wallet" "delete"
 generate_markdown_section "forest-wallet" "send"
+generate_markdown_section "forest-wallet" "rotate-passphrase"
+generate_markdown_section "forest-wallet" "keystore-info"
 
 generate_markdown_section "forest-cli"
 
 generate_markdown_section "forest-cli" "chain"
 generate_markdown_section "forest-cli" "chain block"
 generate_markdown_section "forest-cli" "chain message"
+generate_markdown_section "forest-cli" "chain params"
 generate_markdown_section "forest-cli" "chain read-obj"
+generate_markdown_section "forest-cli" "chain report-fault"
 generate_markdown_section "forest-cli" "chain set-head"
 
 generate_markdown_section "forest-cli" "auth"
@@ -46,6 +50,7 @@ generate_markdown_section "forest-cli" "net"
 generate_markdown_section "forest-cli" "net peers"
 generate_markdown_section "forest-cli" "net connect"
 generate_markdown_section "forest-cli" "net disconnect"
+generate_markdown_section "forest-cli" "net ping"
 
 generate_markdown_section "forest-cli" "sync"
 generate_markdown_section "forest-cli" "sync wait"
@@ -55,10 +60,17 @@ generate_markdown_section "forest-cli" "sync mark-bad"
 generate_markdown_section "forest-cli" "mpool"
 generate_markdown_section "forest-cli" "mpool pending"
 generate_markdown_section "forest-cli" "mpool stat"
+generate_markdown_section "forest-cli" "mpool locals"
+generate_markdown_section "forest-cli" "mpool export"
+generate_markdown_section "forest-cli" "mpool import"
 
 generate_markdown_section "forest-cli" "state"
 generate_markdown_section "forest-cli" "state fetch"
 generate_markdown_section "forest-cli" "state compute"
+generate_markdown_section "forest-cli" "state proving-schedule"
+generate_markdown_section "forest-cli" "state sector-penalties"
+generate_markdown_section "forest-cli" "state export-actor"
+generate_markdown_section "forest-cli" "state msg-cost"
 
 generate_markdown_section "forest-cli" "config"
 
@@ -67,11 +79,14 @@ generate_markdown_section "forest-cli" "snapshot export"
 
 generate_markdown_section "forest-cli" "send"
 generate_markdown_section "forest-cli" "info"
+generate_markdown_section "forest-cli" "info stats"
 generate_markdown_section "forest-cli" "shutdown"
 
 generate_markdown_section "forest-cli" "healthcheck"
 generate_markdown_section "forest-cli" "healthcheck ready"
 
+generate_markdown_section "forest-cli" "debug state-tree-depth"
+
 
 generate_markdown_section "forest-tool" ""
 
@@ -85,15 +100,18 @@ generate_markdown_section "forest-tool" "benchmark graph-traversal"
 generate_markdown_section "forest-tool" "benchmark unordered-graph-traversal"
 generate_markdown_section "forest-tool" "benchmark forest-encoding"
 generate_markdown_section "forest-tool" "benchmark export"
+generate_markdown_section "forest-tool" "benchmark replay"
 
 generate_markdown_section "forest-tool" "state-migration"
 generate_markdown_section "forest-tool" "state-migration actor-bundle"
+generate_markdown_section "forest-tool" "state-migration dry-run"
 
 generate_markdown_section "forest-tool" "snapshot"
 generate_markdown_section "forest-tool" "snapshot fetch"
 generate_markdown_section "forest-tool" "snapshot validate-diffs"
 generate_markdown_section "forest-tool" "snapshot validate"
 generate_markdown_section "forest-tool" "snapshot compress"
+generate_markdown_section "forest-tool" "snapshot merge"
 generate_markdown_section "forest-tool" "snapshot compute-state"
 
 generate_markdown_section "forest-tool" "fetch-params"
@@ -109,9 +127,16 @@ generate_markdown_section "forest-tool" "db"
 generate_markdown_section "forest-tool" "db stats"
 generate_markdown_section "forest-tool" "db destroy"
 
+generate_markdown_section "forest-tool" "index"
+generate_markdown_section "forest-tool" "index rebuild"
+generate_markdown_section "forest-tool" "index backfill-events"
+generate_markdown_section "forest-tool" "journal"
+generate_markdown_section "forest-tool" "journal grep"
+
 generate_markdown_section "forest-tool" "car"
 generate_markdown_section "forest-tool" "car concat"
 generate_markdown_section "forest-tool" "car validate"
+generate_markdown_section "forest-tool" "car verify"
 
 generate_markdown_section "forest-tool" "api"
 generate_markdown_section "forest-tool" "api serve"
@@ -120,6 +145,8 @@ generate_markdown_section "forest-tool" "api dump-tests"
 
 generate_markdown_section "forest-tool" "net ping"
 
+generate_markdown_section "forest-tool" "miner-state"
+
 generate_markdown_section "forest-tool" "shed"
 generate_markdown_section "forest-tool" "shed summarize-tipsets"
 generate_markdown_section "forest-tool" "shed peer-id-from-key-pair"
@@ -4,8 +4,10 @@
 pub mod beacon_entries;
 mod drand;
 pub mod signatures;
+mod verified_rounds;
 pub use beacon_entries::*;
 pub use drand::*;
+pub use verified_rounds::*;
 
 #[cfg(test)]
 pub mod mock_beacon;
@@ -0,0 +1,130 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Persisted record of the beacon rounds whose signatures have been verified,
+//! letting headers validated again, e.g. after a restart, skip the BLS
+//! verification.
+
+use super::BeaconEntry;
+use crate::db::{
+    setting_keys::{VERIFIED_BEACON_ROUND_HIGHEST_KEY, VERIFIED_BEACON_ROUND_KEY_PREFIX},
+    SettingsStore, SettingsStoreExt as _,
+};
+use crate::shim::clock::EPOCHS_IN_DAY;
+use crate::utils::encoding::blake2b_256;
+
+/// Number of rounds kept behind the highest verified one, 90 days at the ten
+/// rounds per epoch of the quicknet beacon, and longer for the slower beacons
+/// of older epochs. Headers carry about one entry per epoch, so this holds
+/// around 260 thousand digests, a few MiB.
+const VERIFIED_BEACON_ROUND_RETENTION: u64 = 10 * 90 * EPOCHS_IN_DAY as u64;
+
+fn verified_round_key(round: u64) -> String {
+    format!("{VERIFIED_BEACON_ROUND_KEY_PREFIX}{round}")
+}
+
+/// Checks whether the round of the entry was verified before with the same
+/// signature.
+pub fn is_beacon_entry_verified(settings: &dyn SettingsStore, entry: &BeaconEntry) -> bool {
+    settings
+        .read_bin(&verified_round_key(entry.round()))
+        .ok()
+        .flatten()
+        .is_some_and(|digest| digest == blake2b_256(entry.signature()))
+}
+
+/// Persists the digests of the entry signatures. This should only be called
+/// once the entries have been verified. The digests of old rounds are dropped
+/// by [`prune_verified_beacon_rounds`].
+pub fn mark_beacon_entries_verified(
+    settings: &dyn SettingsStore,
+    entries: &[BeaconEntry],
+) -> anyhow::Result<()> {
+    let Some(highest) = entries.iter().map(BeaconEntry::round).max() else {
+        return Ok(());
+    };
+    for entry in entries {
+        settings.write_bin(
+            &verified_round_key(entry.round()),
+            &blake2b_256(entry.signature()),
+        )?;
+    }
+    let previous: Option<u64> = settings.read_obj(VERIFIED_BEACON_ROUND_HIGHEST_KEY)?;
+    if previous.is_none_or(|previous| previous < highest) {
+        settings.write_obj(VERIFIED_BEACON_ROUND_HIGHEST_KEY, &highest)?;
+    }
+    Ok(())
+}
+
+/// Deletes the digests of the rounds older than the retention window behind
+/// the highest verified round, returning how many were deleted. This walks the
+/// settings keys, so it is meant to run periodically in the background rather
+/// than on the validation path.
+pub fn prune_verified_beacon_rounds(settings: &dyn SettingsStore) -> anyhow::Result<usize> {
+    let Some(highest) = settings.read_obj::<u64>(VERIFIED_BEACON_ROUND_HIGHEST_KEY)? else {
+        return Ok(0);
+    };
+    let keep_from = highest.saturating_sub(VERIFIED_BEACON_ROUND_RETENTION);
+    let mut pruned = 0;
+    for key in settings.setting_keys()? {
+        let stale = key
+            .strip_prefix(VERIFIED_BEACON_ROUND_KEY_PREFIX)
+            .and_then(|round| round.parse::<u64>().ok())
+            .is_some_and(|round| round < keep_from);
+        if stale {
+            settings.delete(&key)?;
+            pruned += 1;
+        }
+    }
+    Ok(pruned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MemoryDB;
+
+    #[test]
+    fn verified_rounds_pruning() {
+        let settings = MemoryDB::default();
+        let entry = |round: u64| BeaconEntry::new(round, round.to_be_bytes().to_vec());
+        let first = VERIFIED_BEACON_ROUND_RETENTION + 10;
+        let count = || {
+            settings
+                .setting_keys()
+                .unwrap()
+                .into_iter()
+                .filter(|key| key.starts_with(VERIFIED_BEACON_ROUND_KEY_PREFIX))
+                .count()
+        };
+
+        // Nothing to prune before any round is verified
+        assert_eq!(prune_verified_beacon_rounds(&settings).unwrap(), 0);
+
+        mark_beacon_entries_verified(&settings, &[entry(first), entry(first + 1)]).unwrap();
+        assert!(is_beacon_entry_verified(&settings, &entry(first)));
+        assert!(is_beacon_entry_verified(&settings, &entry(first + 1)));
+        assert!(!is_beacon_entry_verified(
+            &settings,
+            &BeaconEntry::new(first, vec![0])
+        ));
+
+        // Marking a round more than the retention ahead leaves the first one
+        // until the next pruning
+        let ahead = first + 1 + VERIFIED_BEACON_ROUND_RETENTION;
+        mark_beacon_entries_verified(&settings, &[entry(ahead)]).unwrap();
+        assert!(is_beacon_entry_verified(&settings, &entry(first)));
+        assert_eq!(prune_verified_beacon_rounds(&settings).unwrap(), 1);
+        assert!(!is_beacon_entry_verified(&settings, &entry(first)));
+        assert!(is_beacon_entry_verified(&settings, &entry(first + 1)));
+
+        // Older rounds verified again, e.g. by a historical validation, are
+        // recorded and pruned alike without lowering the highest round
+        mark_beacon_entries_verified(&settings, &[entry(1)]).unwrap();
+        assert!(is_beacon_entry_verified(&settings, &entry(1)));
+        assert_eq!(count(), 3);
+        assert_eq!(prune_verified_beacon_rounds(&settings).unwrap(), 1);
+        assert!(!is_beacon_entry_verified(&settings, &entry(1)));
+        assert_eq!(count(), 2);
+    }
+}
@@ -5,13 +5,16 @@ use std::ops::Deref;
 use std::sync::atomic::{AtomicBool, Ordering};
 
 use super::{ElectionProof, Error, Ticket, TipsetKey};
-use crate::beacon::{BeaconEntry, BeaconSchedule};
+use crate::beacon::{
+    is_beacon_entry_verified, mark_beacon_entries_verified, Beacon, BeaconEntry, BeaconSchedule,
+};
+use crate::db::SettingsStore;
 use crate::shim::clock::ChainEpoch;
 use crate::shim::{
     address::Address, crypto::Signature, econ::TokenAmount, sector::PoStProof,
     version::NetworkVersion,
 };
-use crate::utils::{cid::CidCborExt as _, encoding::blake2b_256};
+use crate::utils::{cache::EstimateSize, cid::CidCborExt as _, encoding::blake2b_256};
 use cid::Cid;
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::CborStore as _;
@@ -22,14 +25,14 @@ use serde_tuple::{Deserialize_tuple, Serialize_tuple};
 
 // See <https://github.com/filecoin-project/lotus/blob/d3ca54d617f4783a1a492993f06e737ea87a5834/chain/gen/genesis/genesis.go#L627>
 // and <https://github.com/filecoin-project/lotus/commit/13e5b72cdbbe4a02f3863c04f9ecb69c21c3f80f#diff-fda2789d966ea533e74741c076f163070cbc7eb265b5513cd0c0f3bdee87245cR437>
-#[cfg(test)]
+#[cfg(any(test, feature = "benchmark-private"))]
 static FILECOIN_GENESIS_CID: once_cell::sync::Lazy<Cid> = once_cell::sync::Lazy::new(|| {
     "bafyreiaqpwbbyjo4a42saasj36kkrpv4tsherf2e7bvezkert2a7dhonoi"
         .parse()
         .expect("Infallible")
 });
 
-#[cfg(test)]
+#[cfg(any(test, feature = "benchmark-private"))]
 pub static GENESIS_BLOCK_PARENTS: once_cell::sync::Lazy<TipsetKey> =
     once_cell::sync::Lazy::new(|| nunny::vec![*FILECOIN_GENESIS_CID].into());
 
@@ -68,7 +71,7 @@ pub struct RawBlockHeader {
     pub parent_base_fee: TokenAmount,
 }
 
-#[cfg(test)]
+#[cfg(any(test, feature = "benchmark-private"))]
 impl Default for RawBlockHeader {
     fn default() -> Self {
         Self {
@@ -115,13 +118,16 @@ impl RawBlockHeader {
     }
 
     /// Validates if the current header's Beacon entries are valid to ensure
-    /// randomness was generated correctly
+    /// randomness was generated correctly. When `verified_rounds` is given, the
+    /// signatures of the rounds recorded there are not verified again, and the
+    /// newly verified rounds are recorded.
     pub fn validate_block_drand(
         &self,
         network_version: NetworkVersion,
         b_schedule: &BeaconSchedule,
         parent_epoch: ChainEpoch,
         prev_entry: &BeaconEntry,
+        verified_rounds: Option<&(dyn SettingsStore + Sync + Send)>,
     ) -> Result<(), Error> {
         let (cb_epoch, curr_beacon) = b_schedule
             .beacon_for_epoch(self.epoch)
@@ -147,9 +153,12 @@ impl RawBlockHeader {
                 }
 
                 #[allow(clippy::indexing_slicing)]
-                curr_beacon
-                    .verify_entries(&self.beacon_entries[1..], &self.beacon_entries[0])
-                    .map_err(|e| Error::Validation(e.to_string()))?;
+                verify_beacon_entries(
+                    curr_beacon,
+                    &self.beacon_entries[1..],
+                    &self.beacon_entries[0],
+                    verified_rounds,
+                )?;
 
                 return Ok(());
             }
@@ -191,10 +200,12 @@ impl RawBlockHeader {
             )));
         }
 
-        if !curr_beacon
-            .verify_entries(&self.beacon_entries, prev_entry)
-            .map_err(|e| Error::Validation(e.to_string()))?
-        {
+        if !verify_beacon_entries(
+            curr_beacon,
+            &self.beacon_entries,
+            prev_entry,
+            verified_rounds,
+        )? {
             return Err(Error::Validation("beacon entry was invalid".into()));
         }
 
@@ -217,6 +228,33 @@ impl RawBlockHeader {
     }
 }
 
+/// Verifies the beacon entries, skipping the BLS verification when every
+/// round was already verified with the same signature.
+fn verify_beacon_entries(
+    beacon: &dyn Beacon,
+    entries: &[BeaconEntry],
+    prev: &BeaconEntry,
+    verified_rounds: Option<&(dyn SettingsStore + Sync + Send)>,
+) -> Result<bool, Error> {
+    if let Some(verified_rounds) = verified_rounds {
+        if entries
+            .iter()
+            .all(|entry| is_beacon_entry_verified(verified_rounds, entry))
+        {
+            return Ok(true);
+        }
+    }
+    let valid = beacon
+        .verify_entries(entries, prev)
+        .map_err(|e| Error::Validation(e.to_string()))?;
+    if let (true, Some(verified_rounds)) = (valid, verified_rounds) {
+        if let Err(e) = mark_beacon_entries_verified(verified_rounds, entries) {
+            tracing::warn!("failed to record verified beacon rounds: {e}");
+        }
+    }
+    Ok(valid)
+}
+
 /// A [`RawBlockHeader`] which caches calls to [`RawBlockHeader::cid`] and [`RawBlockHeader::verify_signature_against`]
 #[cfg_attr(test, derive(Default))]
 #[derive(Debug)]
@@ -260,6 +298,31 @@ impl From<RawBlockHeader> for CachingBlockHeader {
     }
 }
 
+impl EstimateSize for CachingBlockHeader {
+    fn estimated_size(&self) -> usize {
+        std::mem::size_of::<Self>() - std::mem::size_of::<TipsetKey>()
+            + self.parents.estimated_size()
+            + self
+                .beacon_entries
+                .iter()
+                .map(|entry| std::mem::size_of_val(entry) + entry.signature().len())
+                .sum::<usize>()
+            + self
+                .winning_post_proof
+                .iter()
+                .map(|proof| std::mem::size_of_val(proof) + proof.proof_bytes.len())
+                .sum::<usize>()
+            + self
+                .ticket
+                .as_ref()
+                .map_or(0, |ticket| ticket.vrfproof.as_bytes().len())
+            + self
+                .election_proof
+                .as_ref()
+                .map_or(0, |proof| proof.vrfproof.as_bytes().len())
+    }
+}
+
 impl CachingBlockHeader {
     pub fn new(uncached: RawBlockHeader) -> Self {
         Self {
@@ -331,10 +394,17 @@ impl<'de> Deserialize<'de> for CachingBlockHeader {
 
 #[cfg(test)]
 mod tests {
-    use crate::beacon::{mock_beacon::MockBeacon, BeaconEntry, BeaconPoint, BeaconSchedule};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use crate::beacon::{
+        mock_beacon::MockBeacon, Beacon, BeaconEntry, BeaconPoint, BeaconSchedule, DrandNetwork,
+    };
+    use crate::db::{MemoryDB, SettingsStore};
     use crate::shim::clock::ChainEpoch;
     use crate::shim::{address::Address, version::NetworkVersion};
-    use crate::utils::encoding::from_slice_with_fallback;
+    use crate::utils::encoding::{blake2b_256, from_slice_with_fallback};
+    use async_trait::async_trait;
     use fvm_ipld_encoding::to_vec;
 
     use crate::blocks::{CachingBlockHeader, Error};
@@ -389,6 +459,7 @@ mod tests {
             &beacon_schedule,
             chain_epoch,
             &beacon_entry,
+            None,
         ) {
             // Assert error is for not including a beacon entry in the block
             match e {
@@ -401,4 +472,97 @@ mod tests {
             }
         }
     }
+
+    /// Counts the verifications delegated to a [`MockBeacon`]
+    #[derive(Default)]
+    struct CountingBeacon {
+        inner: MockBeacon,
+        verifications: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Beacon for CountingBeacon {
+        fn network(&self) -> DrandNetwork {
+            self.inner.network()
+        }
+
+        fn verify_entries(
+            &self,
+            entries: &[BeaconEntry],
+            prev: &BeaconEntry,
+        ) -> Result<bool, anyhow::Error> {
+            self.verifications.fetch_add(1, Ordering::SeqCst);
+            self.inner.verify_entries(entries, prev)
+        }
+
+        async fn entry(&self, round: u64) -> anyhow::Result<BeaconEntry> {
+            self.inner.entry(round).await
+        }
+
+        fn max_beacon_round_for_epoch(
+            &self,
+            network_version: NetworkVersion,
+            fil_epoch: ChainEpoch,
+        ) -> u64 {
+            self.inner
+                .max_beacon_round_for_epoch(network_version, fil_epoch)
+        }
+    }
+
+    #[test]
+    fn verified_beacon_rounds_are_not_verified_again() {
+        let verifications = Arc::new(AtomicUsize::new(0));
+        // A fresh beacon for every validation, like after a restart
+        let beacon_schedule = || {
+            BeaconSchedule(vec![BeaconPoint {
+                height: 0,
+                beacon: Box::new(CountingBeacon {
+                    verifications: verifications.clone(),
+                    ..Default::default()
+                }),
+            }])
+        };
+        let verified_rounds = MemoryDB::default();
+        let header = |epoch: ChainEpoch, signature: Vec<u8>| {
+            CachingBlockHeader::new(RawBlockHeader {
+                miner_address: Address::new_id(0),
+                epoch,
+                beacon_entries: vec![BeaconEntry::new(2, signature)],
+                ..Default::default()
+            })
+        };
+        // The mock beacon expects the signature of an entry to be the digest of the previous round
+        let prev_entry = BeaconEntry::new(1, vec![]);
+        let valid = header(2, blake2b_256(&1_u64.to_be_bytes()).to_vec());
+        let validate =
+            |header: &CachingBlockHeader,
+             verified_rounds: Option<&(dyn SettingsStore + Sync + Send)>| {
+                header.validate_block_drand(
+                    NetworkVersion::V16,
+                    &beacon_schedule(),
+                    1,
+                    &prev_entry,
+                    verified_rounds,
+                )
+            };
+
+        validate(&valid, Some(&verified_rounds)).unwrap();
+        assert_eq!(verifications.load(Ordering::SeqCst), 1);
+        validate(&valid, Some(&verified_rounds)).unwrap();
+        assert_eq!(verifications.load(Ordering::SeqCst), 1);
+
+        // Without the record, the entries are verified
+        validate(&valid, None).unwrap();
+        assert_eq!(verifications.load(Ordering::SeqCst), 2);
+
+        // A different signature for a verified round is verified
+        let tampered = header(2, vec![0; 32]);
+        assert!(validate(&tampered, Some(&verified_rounds)).is_err());
+        assert_eq!(verifications.load(Ordering::SeqCst), 3);
+
+        // The round of a verified entry must still match the epoch
+        let misaligned = header(3, blake2b_256(&1_u64.to_be_bytes()).to_vec());
+        assert!(validate(&misaligned, Some(&verified_rounds)).is_err());
+        assert_eq!(verifications.load(Ordering::SeqCst), 3);
+    }
 }
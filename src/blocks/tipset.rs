@@ -8,7 +8,7 @@ use crate::cid_collections::SmallCidNonEmptyVec;
 use crate::db::{SettingsStore, SettingsStoreExt};
 use crate::networks::{calibnet, mainnet};
 use crate::shim::clock::ChainEpoch;
-use crate::utils::cid::CidCborExt;
+use crate::utils::{cache::EstimateSize, cid::CidCborExt};
 use ahash::HashMap;
 use anyhow::Context as _;
 use cid::Cid;
@@ -184,6 +184,24 @@ pub enum CreateTipsetError {
     DuplicateMiner,
 }
 
+impl EstimateSize for TipsetKey {
+    fn estimated_size(&self) -> usize {
+        self.0.estimated_size()
+    }
+}
+
+impl EstimateSize for Tipset {
+    fn estimated_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.key().estimated_size()
+            + self
+                .headers
+                .iter()
+                .map(EstimateSize::estimated_size)
+                .sum::<usize>()
+    }
+}
+
 #[allow(clippy::len_without_is_empty)]
 impl Tipset {
     /// Builds a new Tipset from a collection of blocks.
@@ -496,6 +514,10 @@ impl FullTipset {
         self.key
             .get_or_init(|| TipsetKey::from(self.blocks.iter_ne().map(|b| *b.cid()).collect_vec()))
     }
+    /// Returns the keys of the parents of the blocks in the tipset.
+    pub fn parents(&self) -> &TipsetKey {
+        &self.first_block().header().parents
+    }
     /// Returns the state root for the tipset parent.
     pub fn parent_state(&self) -> &Cid {
         &self.first_block().header().state_root
@@ -2,48 +2,57 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 pub mod store;
 mod weight;
-use crate::blocks::Tipset;
+use crate::blocks::{RawBlockHeader, Tipset};
 use crate::cid_collections::CidHashSet;
-use crate::db::car::forest;
-use crate::ipld::stream_chain;
+use crate::db::car::{forest, indexed::write_carv2};
+use crate::ipld::{
+    should_save_block_to_snapshot, stream_chain, unordered_stream_chain, DfsIter, Ipld,
+};
+use crate::shim::clock::ChainEpoch;
+use crate::utils::db::car_stream::{CarBlock, CarStream, CarWriter};
 use crate::utils::io::{AsyncWriterWithChecksum, Checksum};
 use crate::utils::stream::par_buffer;
 use anyhow::Context as _;
+use cid::Cid;
 use digest::Digest;
+use futures::future::Either;
+use futures::{SinkExt as _, Stream, TryStreamExt as _};
 use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::DAG_CBOR;
+use std::collections::VecDeque;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::io::{AsyncWrite, AsyncWriteExt, BufWriter};
+use tokio::fs::File;
+use tokio::io::{AsyncSeek, AsyncWrite, AsyncWriteExt, BufReader, BufWriter};
+use tracing::info;
 
 pub use self::{store::*, weight::*};
 
+/// Exports the chain from `tipset` back to genesis, including the state trees of the
+/// `lookup_depth` most recent tipsets and of the tipsets in `include_state`.
+///
+/// When `deterministic` is set, the graph is walked by a single task so that
+/// exporting the same tipset twice writes the same file. Otherwise it is walked
+/// in parallel, in an order that varies from an export to the next, see
+/// [`export_blocks`].
+#[allow(clippy::too_many_arguments)]
 pub async fn export<D: Digest>(
     db: Arc<impl Blockstore + Send + Sync + 'static>,
     tipset: &Tipset,
     lookup_depth: ChainEpochDelta,
+    include_state: Option<RangeInclusive<ChainEpoch>>,
     writer: impl AsyncWrite + Unpin,
     seen: CidHashSet,
     skip_checksum: bool,
+    deterministic: bool,
 ) -> anyhow::Result<Option<digest::Output<D>>, Error> {
-    let stateroot_lookup_limit = tipset.epoch() - lookup_depth;
     let roots = tipset.key().to_cids();
 
     // Wrap writer in optional checksum calculator
     let mut writer = AsyncWriterWithChecksum::<D, _>::new(BufWriter::new(writer), !skip_checksum);
 
-    // Stream stateroots in range (stateroot_lookup_limit+1)..=tipset.epoch(). Also
-    // stream all block headers until genesis.
-    let blocks = par_buffer(
-        // Queue 1k blocks. This is enough to saturate the compressor and blocks
-        // are small enough that keeping 1k in memory isn't a problem. Average
-        // block size is between 1kb and 2kb.
-        1024,
-        stream_chain(
-            Arc::clone(&db),
-            tipset.clone().chain_owned(Arc::clone(&db)),
-            stateroot_lookup_limit,
-        )
-        .with_seen(seen),
-    );
+    let blocks = export_blocks(db, tipset, lookup_depth, include_state, seen, deterministic);
 
     // Encode Ipld key-value pairs in zstd frames
     let frames = forest::Encoder::compress_stream_default(blocks);
@@ -58,3 +67,601 @@ pub async fn export<D: Digest>(
 
     Ok(digest)
 }
+
+/// Exports the same blocks as [`export`] to an uncompressed, indexed CARv2
+/// file, see [`crate::db::car::IndexedCar`].
+pub async fn export_v2(
+    db: Arc<impl Blockstore + Send + Sync + 'static>,
+    tipset: &Tipset,
+    lookup_depth: ChainEpochDelta,
+    include_state: Option<RangeInclusive<ChainEpoch>>,
+    writer: impl AsyncWrite + AsyncSeek + Unpin,
+    seen: CidHashSet,
+    deterministic: bool,
+) -> anyhow::Result<()> {
+    let roots = tipset.key().to_cids();
+    let blocks = export_blocks(db, tipset, lookup_depth, include_state, seen, deterministic);
+    write_carv2(BufWriter::new(writer), roots, blocks).await
+}
+
+/// Streams the state roots of the `lookup_depth` most recent tipsets and of
+/// the tipsets in `include_state`, and all the block headers until genesis.
+///
+/// The graph is walked by all the CPUs, see [`unordered_stream_chain`]. The
+/// CAR format puts no constraint on the order of the blocks, but the order of
+/// a parallel walk differs from a run to the next. When `deterministic` is
+/// set, the graph is walked depth-first by a single task instead, so that
+/// exporting the same tipset twice writes the same file.
+fn export_blocks(
+    db: Arc<impl Blockstore + Send + Sync + 'static>,
+    tipset: &Tipset,
+    lookup_depth: ChainEpochDelta,
+    include_state: Option<RangeInclusive<ChainEpoch>>,
+    seen: CidHashSet,
+    deterministic: bool,
+) -> impl Stream<Item = anyhow::Result<CarBlock>> {
+    let stateroot_lookup_limit = tipset.epoch() - lookup_depth;
+    let tipsets = tipset
+        .clone()
+        .chain_owned(Arc::clone(&db))
+        .inspect(epoch_progress(tipset.epoch()));
+
+    // Stream stateroots in range (stateroot_lookup_limit+1)..=tipset.epoch(). Also
+    // stream all block headers until genesis.
+    let blocks = if deterministic {
+        let stream = stream_chain(Arc::clone(&db), tipsets, stateroot_lookup_limit).with_seen(seen);
+        Either::Left(match include_state {
+            Some(range) => stream.with_state_range(range),
+            None => stream,
+        })
+    } else {
+        let stream = unordered_stream_chain(Arc::clone(&db), tipsets, stateroot_lookup_limit)
+            .with_seen(seen);
+        Either::Right(match include_state {
+            Some(range) => stream.with_state_range(range),
+            None => stream,
+        })
+    };
+    // Queue 1k blocks. This is enough to saturate the compressor and blocks
+    // are small enough that keeping 1k in memory isn't a problem. Average
+    // block size is between 1kb and 2kb.
+    par_buffer(1024, blocks)
+}
+
+/// Counts of the blocks written by [`merge_car_files`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MergeStats {
+    /// Distinct blocks written to the output
+    pub blocks_written: u64,
+    /// Blocks skipped because another input already contained them
+    pub blocks_deduplicated: u64,
+    /// Size of the output file
+    pub total_bytes: u64,
+}
+
+/// Merges CAR files, which may be zstd compressed and may overlap, into a
+/// single CARv1 file, writing each block once. The output roots are those of
+/// the input with the most recent head. Fails if a block header references a
+/// parent that none of the inputs contains.
+///
+/// The inputs are streamed twice, only their CIDs are kept in memory. The
+/// output is written to a temporary file next to it, and only replaced once
+/// complete.
+pub async fn merge_car_files(inputs: &[PathBuf], output: &Path) -> anyhow::Result<MergeStats> {
+    anyhow::ensure!(!inputs.is_empty(), "no CAR files to merge");
+
+    // Index the blocks first, so that missing parents are reported before
+    // anything is written
+    let mut present = CidHashSet::default();
+    // Parents not found in the inputs indexed so far
+    let mut parents = vec![];
+    let mut roots = None;
+    for input in inputs {
+        let mut stream = open_car(input).await?;
+        let input_roots = stream.header.roots.clone();
+        let mut head_epoch = None;
+        while let Some(block) = stream.try_next().await? {
+            present.insert(block.cid);
+            if block.cid.codec() != DAG_CBOR {
+                continue;
+            }
+            let Ok(header) = fvm_ipld_encoding::from_slice::<RawBlockHeader>(&block.data) else {
+                continue;
+            };
+            if input_roots.contains(&block.cid) {
+                head_epoch = head_epoch.max(Some(header.epoch));
+            }
+            // The parents of genesis are not part of the chain
+            if header.epoch > 0 {
+                parents.extend(header.parents.iter().map(|parent| (parent, block.cid)));
+            }
+        }
+        let head_epoch =
+            head_epoch.with_context(|| format!("{}: no root block header", input.display()))?;
+        parents.retain(|(parent, _)| !present.contains(parent));
+        if roots
+            .as_ref()
+            .map_or(true, |(epoch, _)| head_epoch > *epoch)
+        {
+            roots = Some((head_epoch, input_roots));
+        }
+    }
+    if let Some((parent, child)) = parents.iter().find(|(parent, _)| !present.contains(parent)) {
+        anyhow::bail!("parent {parent} of block header {child} is missing from the CAR files");
+    }
+    let (_, roots) = roots.expect("inputs are not empty");
+
+    let output_dir = match output.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let temp_path = tempfile::NamedTempFile::new_in(output_dir)?.into_temp_path();
+    let mut writer = CarWriter::new_carv1(roots, BufWriter::new(File::create(&temp_path).await?))?;
+    let mut stats = MergeStats::default();
+    for input in inputs {
+        let mut stream = open_car(input).await?;
+        while let Some(block) = stream.try_next().await? {
+            // Each block is written the first time it is met
+            if present.remove(&block.cid) {
+                writer.feed(block).await?;
+                stats.blocks_written += 1;
+            } else {
+                stats.blocks_deduplicated += 1;
+            }
+        }
+    }
+    writer.close().await?;
+    stats.total_bytes = tokio::fs::metadata(&temp_path).await?.len();
+    temp_path.persist(output)?;
+    Ok(stats)
+}
+
+async fn open_car(path: &Path) -> anyhow::Result<CarStream<BufReader<File>>> {
+    let file = File::open(path)
+        .await
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    Ok(CarStream::new(BufReader::new(file)).await?)
+}
+
+/// Bounds of the subgraph written by [`export_subgraph`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubgraphLimits {
+    /// Levels of links followed from the root, all of them if `None`
+    pub depth_limit: Option<u32>,
+    /// Links followed from each block, in the order they appear in the
+    /// block, all of them if `None`
+    pub links_per_block: Option<usize>,
+    /// Blocks written at most, the export failing on larger subgraphs
+    pub max_blocks: u64,
+}
+
+/// Exports the blocks reachable from `root`, breadth-first and within
+/// `limits`, to a CARv1 file whose root is `root`. Only the links of the
+/// `DAG-CBOR` blocks are followed, and identity CIDs are not written. Returns
+/// the number of blocks written.
+pub async fn export_subgraph(
+    db: &impl Blockstore,
+    root: Cid,
+    limits: SubgraphLimits,
+    writer: impl AsyncWrite + Unpin,
+) -> anyhow::Result<u64> {
+    let mut writer = CarWriter::new_carv1(nunny::vec![root], BufWriter::new(writer))?;
+    let mut seen = CidHashSet::default();
+    let mut queue = VecDeque::from([(root, 0)]);
+    let mut blocks = 0;
+    while let Some((cid, depth)) = queue.pop_front() {
+        if !seen.insert(cid) || !should_save_block_to_snapshot(cid) {
+            continue;
+        }
+        anyhow::ensure!(
+            blocks < limits.max_blocks,
+            "the subgraph of {root} has more than {} blocks",
+            limits.max_blocks
+        );
+        let data = db
+            .get(&cid)?
+            .with_context(|| format!("block {cid} is missing"))?;
+        if cid.codec() == DAG_CBOR && limits.depth_limit.map_or(true, |limit| depth < limit) {
+            let links = DfsIter::new(fvm_ipld_encoding::from_slice(&data)?)
+                .filter_map(|ipld| match ipld {
+                    Ipld::Link(link) => Some((link, depth + 1)),
+                    _ => None,
+                })
+                .take(limits.links_per_block.unwrap_or(usize::MAX));
+            queue.extend(links);
+        }
+        writer.feed(CarBlock { cid, data }).await?;
+        blocks += 1;
+    }
+    writer.close().await?;
+    Ok(blocks)
+}
+
+/// Logs the percentage of epochs processed while walking the chain from `head` to genesis.
+fn epoch_progress(head: ChainEpoch) -> impl FnMut(&Tipset) {
+    let mut last_logged = None;
+    move |ts| {
+        let percent = (head - ts.epoch()) * 100 / head.max(1);
+        if last_logged != Some(percent) {
+            last_logged = Some(percent);
+            info!("Exported {percent}% of epochs");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::{CachingBlockHeader, RawBlockHeader, TipsetKey};
+    use crate::db::MemoryDB;
+    use crate::shim::address::Address;
+    use crate::shim::econ::TokenAmount;
+    use crate::shim::state_tree::{ActorState, StateTree, StateTreeVersion};
+    use crate::utils::db::car_util::load_car;
+    use crate::utils::db::CborStoreExt as _;
+    use nunny::vec as nonempty;
+    use sha2::Sha256;
+
+    /// Creates a state tree with a single actor whose balance is `epoch` FIL.
+    fn state(db: &Arc<MemoryDB>, epoch: ChainEpoch) -> Cid {
+        let mut state_tree = StateTree::new(db.clone(), StateTreeVersion::V5).unwrap();
+        state_tree
+            .set_actor(
+                &Address::new_id(1000),
+                ActorState::new(
+                    Cid::default(),
+                    Cid::default(),
+                    TokenAmount::from_whole(epoch),
+                    0,
+                    None,
+                ),
+            )
+            .unwrap();
+        state_tree.flush().unwrap()
+    }
+
+    #[tokio::test]
+    async fn export_with_state_range() {
+        let db = Arc::new(MemoryDB::default());
+        let mut parents =
+            TipsetKey::from(nonempty![db.put_cbor_default(&"genesis parent").unwrap()]);
+        let mut state_roots = vec![];
+        let mut head = None;
+        for epoch in 0..6 {
+            let state_root = state(&db, epoch);
+            let header = CachingBlockHeader::new(RawBlockHeader {
+                parents,
+                epoch,
+                state_root,
+                ..Default::default()
+            });
+            db.put_cbor_default(&header).unwrap();
+            parents = TipsetKey::from(nonempty![*header.cid()]);
+            state_roots.push(state_root);
+            head = Some(Tipset::from(&header));
+        }
+        let head = head.unwrap();
+
+        // Recent state roots cover epoch 5 only, the range adds epochs 1 and 2
+        let mut car = vec![];
+        export::<Sha256>(
+            db.clone(),
+            &head,
+            1,
+            Some(1..=2),
+            &mut car,
+            CidHashSet::default(),
+            true,
+            false,
+        )
+        .await
+        .unwrap();
+
+        let imported = Arc::new(MemoryDB::default());
+        load_car(&imported, car.as_slice()).await.unwrap();
+        for (epoch, state_root) in state_roots.iter().enumerate() {
+            let epoch = epoch as ChainEpoch;
+            if [0, 1, 2, 5].contains(&epoch) {
+                let state_tree = StateTree::new_from_root(imported.clone(), state_root).unwrap();
+                let actor = state_tree
+                    .get_required_actor(&Address::new_id(1000))
+                    .unwrap();
+                assert_eq!(
+                    TokenAmount::from(&actor.balance),
+                    TokenAmount::from_whole(epoch)
+                );
+            } else {
+                assert!(!imported.has(state_root).unwrap());
+            }
+        }
+    }
+
+    /// Creates a chain of `len` tipsets with a state tree each, and returns
+    /// them from genesis to head.
+    fn chain(db: &Arc<MemoryDB>, len: ChainEpoch) -> Vec<Tipset> {
+        let mut parents =
+            TipsetKey::from(nonempty![db.put_cbor_default(&"genesis parent").unwrap()]);
+        (0..len)
+            .map(|epoch| {
+                let header = CachingBlockHeader::new(RawBlockHeader {
+                    parents: parents.clone(),
+                    epoch,
+                    state_root: state(db, epoch),
+                    ..Default::default()
+                });
+                db.put_cbor_default(&header).unwrap();
+                parents = TipsetKey::from(nonempty![*header.cid()]);
+                Tipset::from(&header)
+            })
+            .collect()
+    }
+
+    async fn export_to(db: &Arc<MemoryDB>, head: &Tipset, seen: CidHashSet, path: &Path) {
+        let mut car = vec![];
+        export::<Sha256>(db.clone(), head, 1, None, &mut car, seen, true, false)
+            .await
+            .unwrap();
+        std::fs::write(path, car).unwrap();
+    }
+
+    async fn car_cids(path: &Path) -> (Vec<Cid>, Vec<Cid>) {
+        let stream = open_car(path).await.unwrap();
+        let roots = stream.header.roots.iter().copied().collect();
+        let cids = stream
+            .map_ok(|block| block.cid)
+            .try_collect()
+            .await
+            .unwrap();
+        (roots, cids)
+    }
+
+    /// Exports the state trees of the `depth` most recent tipsets, and returns
+    /// the roots and the sorted blocks of the CAR file.
+    async fn exported_blocks(
+        db: &Arc<MemoryDB>,
+        head: &Tipset,
+        depth: ChainEpochDelta,
+        deterministic: bool,
+    ) -> (Vec<Cid>, Vec<(Cid, Vec<u8>)>) {
+        let mut car = vec![];
+        export::<Sha256>(
+            db.clone(),
+            head,
+            depth,
+            None,
+            &mut car,
+            CidHashSet::default(),
+            true,
+            deterministic,
+        )
+        .await
+        .unwrap();
+        let stream = CarStream::new(car.as_slice()).await.unwrap();
+        let roots = stream.header.roots.iter().copied().collect();
+        let mut blocks: Vec<_> = stream
+            .map_ok(|block| (block.cid, block.data))
+            .try_collect()
+            .await
+            .unwrap();
+        blocks.sort();
+        (roots, blocks)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn export_determinism() {
+        let db = Arc::new(MemoryDB::default());
+        let tipsets = chain(&db, 6);
+        let head = &tipsets[5];
+        let export = |deterministic| {
+            let db = db.clone();
+            async move {
+                let mut car = vec![];
+                export::<Sha256>(
+                    db,
+                    head,
+                    6,
+                    None,
+                    &mut car,
+                    CidHashSet::default(),
+                    true,
+                    deterministic,
+                )
+                .await
+                .unwrap();
+                car
+            }
+        };
+
+        // Deterministic exports are identical
+        assert_eq!(export(true).await, export(true).await);
+
+        // Parallel exports write the same blocks, possibly in another order
+        let (roots, expected) = exported_blocks(&db, head, 6, true).await;
+        assert_eq!(roots, head.key().iter().collect::<Vec<_>>());
+        for _ in 0..3 {
+            assert_eq!(
+                exported_blocks(&db, head, 6, false).await,
+                (roots.clone(), expected.clone())
+            );
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn export_wide_graph() {
+        // A state with more links than a worker walks alone, so that its walk
+        // is shared with the other workers
+        const LINKS: u64 = 4 * 1024;
+        let db = Arc::new(MemoryDB::default());
+        let leaves: Vec<Cid> = (0..LINKS)
+            .map(|i| db.put_cbor_default(&i).unwrap())
+            .collect();
+        let links: Vec<Cid> = leaves
+            .iter()
+            .map(|leaf| db.put_cbor_default(&vec![*leaf]).unwrap())
+            .collect();
+        let header = CachingBlockHeader::new(RawBlockHeader {
+            parents: TipsetKey::from(nonempty![db.put_cbor_default(&"genesis parent").unwrap()]),
+            state_root: db.put_cbor_default(&links).unwrap(),
+            ..Default::default()
+        });
+        db.put_cbor_default(&header).unwrap();
+        let head = Tipset::from(&header);
+
+        let (roots, expected) = exported_blocks(&db, &head, 1, true).await;
+        for link in leaves.iter().chain(&links) {
+            assert!(expected.binary_search_by_key(link, |(cid, _)| *cid).is_ok());
+        }
+        for _ in 0..3 {
+            assert_eq!(
+                exported_blocks(&db, &head, 1, false).await,
+                (roots.clone(), expected.clone())
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn merge_overlapping_car_files() {
+        let db = Arc::new(MemoryDB::default());
+        let tipsets = chain(&db, 6);
+        let dir = tempfile::tempdir().unwrap();
+        let older = dir.path().join("older.forest.car.zst");
+        let newer = dir.path().join("newer.forest.car.zst");
+        let merged = dir.path().join("merged.car");
+        export_to(&db, &tipsets[3], CidHashSet::default(), &older).await;
+        export_to(&db, &tipsets[5], CidHashSet::default(), &newer).await;
+        // An existing output is replaced
+        std::fs::write(&merged, b"stale").unwrap();
+
+        // The order of the inputs does not matter for the roots
+        let stats = merge_car_files(&[newer.clone(), older.clone()], &merged)
+            .await
+            .unwrap();
+
+        let (_, older_cids) = car_cids(&older).await;
+        let (_, newer_cids) = car_cids(&newer).await;
+        let (roots, merged_cids) = car_cids(&merged).await;
+        assert_eq!(roots, tipsets[5].key().iter().collect::<Vec<_>>());
+        let mut expected = CidHashSet::default();
+        for cid in older_cids.iter().chain(&newer_cids) {
+            expected.insert(*cid);
+        }
+        assert_eq!(merged_cids.len(), expected.len());
+        assert!(merged_cids.iter().all(|cid| expected.contains(cid)));
+        assert_eq!(stats.blocks_written, expected.len() as u64);
+        assert_eq!(
+            stats.blocks_deduplicated,
+            (older_cids.len() + newer_cids.len() - expected.len()) as u64
+        );
+        // Headers of epochs 0 to 3 are in both inputs
+        assert!(stats.blocks_deduplicated >= 4);
+        assert_eq!(stats.total_bytes, std::fs::metadata(&merged).unwrap().len());
+        // Without leaving the temporary file behind
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 3);
+    }
+
+    #[tokio::test]
+    async fn merge_car_files_with_missing_parent() {
+        let db = Arc::new(MemoryDB::default());
+        let tipsets = chain(&db, 6);
+        let dir = tempfile::tempdir().unwrap();
+        let gapped = dir.path().join("gapped.forest.car.zst");
+        let merged = dir.path().join("merged.car");
+        // Leave out the header at epoch 2
+        let mut seen = CidHashSet::default();
+        seen.insert(*tipsets[2].min_ticket_block().cid());
+        export_to(&db, &tipsets[5], seen, &gapped).await;
+
+        let err = merge_car_files(&[gapped], &merged).await.unwrap_err();
+        assert!(err.to_string().contains("is missing"), "{err}");
+        assert!(!merged.exists());
+    }
+
+    #[tokio::test]
+    async fn export_miner_actor_subgraph() {
+        use crate::db::car::PlainCar;
+        use crate::networks::{ChainConfig, NetworkChain, ACTOR_BUNDLES_METADATA};
+        use crate::shim::actors::{miner, state_load::*};
+        use crate::shim::machine::BuiltinActor;
+        use fil_actor_miner_state::v13::{State as MinerStateV13, VestingFund, VestingFunds};
+        use fvm_shared2::econ::TokenAmount as TokenAmountV2;
+        use fvm_shared4::econ::TokenAmount as TokenAmountV4;
+
+        let db = MemoryDB::default();
+        let policy = &ChainConfig::calibnet().policy;
+        let code = ACTOR_BUNDLES_METADATA
+            .get(&(NetworkChain::Calibnet, "v13.0.0".into()))
+            .unwrap()
+            .manifest
+            .get(BuiltinActor::Miner)
+            .unwrap();
+        let mut vesting_funds = VestingFunds::new();
+        vesting_funds.funds = vec![
+            VestingFund {
+                epoch: 100,
+                amount: TokenAmountV4::from_atto(10),
+            },
+            VestingFund {
+                epoch: 200,
+                amount: TokenAmountV4::from_atto(20),
+            },
+        ];
+        let mut miner_state = MinerStateV13::new(policy, &db, Cid::default(), 0, 0).unwrap();
+        miner_state.vesting_funds = db.put_cbor_default(&vesting_funds).unwrap();
+        let root = db.put_cbor_default(&miner_state).unwrap();
+        let unrelated = db.put_cbor_default(&"unrelated").unwrap();
+
+        let unlimited = SubgraphLimits {
+            depth_limit: None,
+            links_per_block: None,
+            max_blocks: u64::MAX,
+        };
+        let mut car = vec![];
+        let blocks = export_subgraph(&db, root, unlimited, &mut car)
+            .await
+            .unwrap();
+
+        let car = PlainCar::new(car).unwrap();
+        assert_eq!(car.roots(), &nonempty![root]);
+        assert!(!car.has(&unrelated).unwrap());
+        let state = miner::State::load(&car, code, root).unwrap();
+        assert_eq!(
+            state.load_vesting_schedule(&car).unwrap(),
+            vec![
+                (100, TokenAmountV2::from_atto(10)),
+                (200, TokenAmountV2::from_atto(20))
+            ]
+        );
+        let mut deadlines = 0;
+        state
+            .for_each_deadline(policy, &car, |_, _| {
+                deadlines += 1;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(deadlines, policy.wpost_period_deadlines);
+
+        // Only the root with a depth limit of 0
+        let mut car = vec![];
+        let limits = SubgraphLimits {
+            depth_limit: Some(0),
+            ..unlimited
+        };
+        assert_eq!(
+            export_subgraph(&db, root, limits, &mut car).await.unwrap(),
+            1
+        );
+        let car = PlainCar::new(car).unwrap();
+        let state = miner::State::load(&car, code, root).unwrap();
+        assert!(state.load_vesting_schedule(&car).is_err());
+
+        // No more blocks than the cap
+        let limits = SubgraphLimits {
+            max_blocks: blocks - 1,
+            ..unlimited
+        };
+        let err = export_subgraph(&db, root, limits, &mut vec![])
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("more than"), "{err}");
+    }
+}
@@ -2,16 +2,24 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use super::{
-    index::{ChainIndex, ResolveNullTipset},
+    index::{ChainIndex, ResolveNullTipset, TipsetOrNull},
+    index_retention_epochs,
     tipset_tracker::TipsetTracker,
-    Error,
+    EpochCheckpointIndex, Error, MessageFeeIndex, TipsetEventIndex, ValidationMarkers,
 };
 use crate::blocks::{CachingBlockHeader, Tipset, TipsetKey, TxMeta};
+use crate::chain_sync::{SyncState, SyncStateSnapshot};
+use crate::cid_collections::{CidHashMap, CidHashSet};
 use crate::db::setting_keys::HEAD_KEY;
-use crate::db::{EthMappingsStore, EthMappingsStoreExt, SettingsStore, SettingsStoreExt};
+use crate::db::{
+    EthMappingsStore, EthMappingsStoreExt, GarbageCollectable, SettingsStore, SettingsStoreExt,
+};
 use crate::fil_cns;
 use crate::interpreter::{BlockMessages, VMEvent, VMTrace};
+use crate::journal::JournalEvent;
+use crate::libp2p::PeerManager;
 use crate::libp2p_bitswap::{BitswapStoreRead, BitswapStoreReadWrite};
+use crate::lotus_json::LotusJson;
 use crate::message::{ChainMessage, Message as MessageTrait, SignedMessage};
 use crate::networks::{ChainConfig, Height};
 use crate::rpc::eth::{eth_tx_from_signed_eth_message, types::EthHash};
@@ -21,7 +29,10 @@ use crate::shim::{
     state_tree::StateTree, version::NetworkVersion,
 };
 use crate::state_manager::StateOutput;
-use crate::utils::db::{BlockstoreExt, CborStoreExt};
+use crate::utils::{
+    cache::{CacheConfig, SizeTrackingLruCache},
+    db::{BlockstoreExt, CborStoreExt},
+};
 use ahash::{HashMap, HashMapExt, HashSet};
 use anyhow::Context as _;
 use cid::Cid;
@@ -29,10 +40,16 @@ use fil_actors_shared::fvm_ipld_amt::Amtv0 as Amt;
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::CborStore;
 use itertools::Itertools;
+use num::BigInt;
 use nunny::vec as nonempty;
 use parking_lot::Mutex;
 use serde::{de::DeserializeOwned, Serialize};
-use std::sync::Arc;
+use std::{
+    cmp,
+    io::Write,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use tokio::sync::broadcast::{self, Sender as Publisher};
 use tracing::{debug, info, trace, warn};
 
@@ -50,6 +67,17 @@ pub enum HeadChange {
     Apply(Arc<Tipset>),
 }
 
+/// Divergence of two tipsets, see [`ChainStore::compute_fork_length`].
+#[derive(Clone, Debug)]
+pub struct ForkInfo {
+    /// The most recent tipset both tipsets descend from, possibly one of them
+    pub common_ancestor: Arc<Tipset>,
+    /// Number of tipsets from the first tipset back to the common ancestor
+    pub ts1_depth: u32,
+    /// Number of tipsets from the second tipset back to the common ancestor
+    pub ts2_depth: u32,
+}
+
 /// Stores chain data such as heaviest tipset and cached tipset info at each
 /// epoch. This structure is thread-safe, and all caches are wrapped in a mutex
 /// to allow a consistent `ChainStore` to be shared across tasks.
@@ -74,9 +102,27 @@ pub struct ChainStore<DB> {
     /// validated blocks
     validated_blocks: Mutex<HashSet<Cid>>,
 
+    /// Persisted markers of the fully validated blocks
+    validation_markers: ValidationMarkers,
+
+    /// Chain weights of recently visited tipsets.
+    weight_cache: Mutex<SizeTrackingLruCache<TipsetKey, BigInt>>,
+
     /// Ethereum mappings store
     eth_mappings: Arc<dyn EthMappingsStore + Sync + Send>,
 
+    /// Base fee of each indexed epoch
+    fee_index: MessageFeeIndex,
+
+    /// Tipset checkpoints of the imported chain
+    checkpoint_index: EpochCheckpointIndex,
+
+    /// Tipset including each indexed message
+    message_index: MessageTipsetIndex,
+
+    /// Actor events of each indexed epoch
+    event_index: TipsetEventIndex,
+
     /// Needed by the Ethereum mapping.
     pub chain_config: Arc<ChainConfig>,
 }
@@ -117,7 +163,9 @@ where
         genesis_block_header: CachingBlockHeader,
     ) -> anyhow::Result<Self> {
         let (publisher, _) = broadcast::channel(SINK_CAP);
-        let chain_index = Arc::new(ChainIndex::new(Arc::clone(&db)));
+        let checkpoint_index = EpochCheckpointIndex::new(settings.clone());
+        let chain_index =
+            Arc::new(ChainIndex::new(Arc::clone(&db)).with_checkpoints(checkpoint_index.clone()));
 
         if settings
             .read_obj::<TipsetKey>(HEAD_KEY)?
@@ -133,10 +181,31 @@ where
             publisher,
             chain_index,
             tipset_tracker: TipsetTracker::new(Arc::clone(&db), chain_config.clone()),
+            event_index: TipsetEventIndex::new(
+                settings.clone(),
+                index_retention_epochs(&chain_config),
+            ),
             db,
+            fee_index: MessageFeeIndex::new(
+                settings.clone(),
+                index_retention_epochs(&chain_config),
+            ),
+            checkpoint_index,
+            message_index: MessageTipsetIndex::new(
+                settings.clone(),
+                index_retention_epochs(&chain_config),
+            ),
+            validation_markers: ValidationMarkers::new(
+                settings.clone(),
+                chain_config.policy.chain_finality,
+            ),
             settings,
             genesis_block_header,
             validated_blocks,
+            weight_cache: Mutex::new(SizeTrackingLruCache::new(
+                crate::metrics::values::TIPSET_WEIGHT.kind(),
+                CacheConfig::global().tipset_weight_bytes,
+            )),
             eth_mappings,
             chain_config,
         };
@@ -148,6 +217,12 @@ where
     /// the settings store under the [`crate::db::setting_keys::HEAD_KEY`] key.
     pub fn set_heaviest_tipset(&self, ts: Arc<Tipset>) -> Result<(), Error> {
         self.settings.write_obj(HEAD_KEY, ts.key())?;
+        self.fee_index
+            .put_chain(self.chain_index.chain(ts.clone()))?;
+        crate::journal::record(JournalEvent::HeadChange {
+            key: ts.key().clone(),
+            epoch: ts.epoch(),
+        });
         if self.publisher.send(HeadChange::Apply(ts)).is_err() {
             debug!("did not publish head change, no active receivers");
         }
@@ -163,6 +238,7 @@ where
     /// with other compatible tracked headers.
     pub fn put_tipset(&self, ts: &Tipset) -> Result<(), Error> {
         persist_objects(self.blockstore(), ts.block_headers().iter())?;
+        self.message_index.put(self.blockstore(), ts)?;
 
         // Expand tipset to include other compatible blocks at the epoch.
         let expanded = self.expand_tipset(ts.min_ticket_block().clone())?;
@@ -172,6 +248,97 @@ where
         Ok(())
     }
 
+    /// Returns the index of base fees by epoch.
+    pub fn message_fee_history_index(&self) -> &MessageFeeIndex {
+        &self.fee_index
+    }
+
+    /// Returns the index of tipset checkpoints by epoch.
+    pub fn checkpoint_index(&self) -> &EpochCheckpointIndex {
+        &self.checkpoint_index
+    }
+
+    /// Returns the index of actor events by epoch.
+    pub fn event_index(&self) -> &TipsetEventIndex {
+        &self.event_index
+    }
+
+    /// Returns the index of the tipsets including each message.
+    pub fn message_tipset_index(&self) -> &MessageTipsetIndex {
+        &self.message_index
+    }
+
+    /// Indexes the messages of the tipset, skipping blocks whose messages are
+    /// not stored yet, and prunes the index behind the tipset.
+    pub fn put_message_tipset_index(&self, ts: &Tipset) -> Result<(), Error> {
+        self.message_index.put(self.blockstore(), ts)?;
+        Ok(())
+    }
+
+    /// Returns the tipset of the heaviest chain including the message, looked
+    /// up in the message index rather than by walking the chain. Messages
+    /// last indexed in a tipset reverted since are reported as not included.
+    pub fn tipset_for_message(&self, msg_cid: &Cid) -> Result<Option<Arc<Tipset>>, Error> {
+        let Some(tsk) = self.message_index.get(msg_cid)? else {
+            return Ok(None);
+        };
+        let ts = self.chain_index.load_required_tipset(&tsk)?;
+        let head = self.heaviest_tipset();
+        let canonical = if ts.epoch() >= head.epoch() {
+            ts.key() == head.key()
+        } else {
+            self.chain_index
+                .tipset_by_height(ts.epoch(), head, ResolveNullTipset::TakeOlder)?
+                .key()
+                == ts.key()
+        };
+        Ok(canonical.then_some(ts))
+    }
+
+    /// Indexes the messages of the tipsets of the heaviest chain within the
+    /// retention window of the index, using `concurrency` threads, and marks
+    /// the index as built. Tipsets whose messages are not stored are skipped.
+    /// Returns the number of indexed messages.
+    pub fn rebuild_message_tipset_index(&self, concurrency: usize) -> Result<usize, Error>
+    where
+        DB: Send + Sync,
+    {
+        use rayon::iter::ParallelBridge as _;
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(concurrency)
+            .build()
+            .map_err(|e| Error::Other(e.to_string()))?;
+        let head = self.heaviest_tipset();
+        let keep_from = head.epoch() - self.message_index.retention();
+        let indexed = pool.install(|| {
+            self.message_index.put_all(
+                self.blockstore(),
+                self.chain_index
+                    .chain(head)
+                    .take_while(|ts| ts.epoch() >= keep_from)
+                    .par_bridge(),
+                keep_from,
+            )
+        })?;
+        self.message_index.set_built()?;
+        Ok(indexed)
+    }
+
+    /// Returns the indexed base fee at the epoch.
+    pub fn get_fee_at_epoch(&self, epoch: ChainEpoch) -> Result<Option<TokenAmount>, Error> {
+        self.fee_index.get(epoch)
+    }
+
+    /// Returns the indexed base fees in the inclusive epoch range.
+    pub fn get_fee_range(
+        &self,
+        from: ChainEpoch,
+        to: ChainEpoch,
+    ) -> Result<Vec<(ChainEpoch, TokenAmount)>, Error> {
+        self.fee_index.range(from, to)
+    }
+
     /// Writes the `TipsetKey` to the blockstore for `EthAPI` queries.
     pub fn put_tipset_key(&self, tsk: &TipsetKey) -> Result<(), Error> {
         let hash = tsk.cid()?.into();
@@ -239,6 +406,38 @@ where
             .expect("failed to load heaviest tipset")
     }
 
+    /// Returns a [`SyncStateSnapshot`] of the current head and the given
+    /// syncer state.
+    pub fn sync_state_snapshot(
+        &self,
+        peer_manager: &PeerManager,
+        sync_state: &SyncState,
+        pending_blocks: usize,
+    ) -> SyncStateSnapshot {
+        let heaviest = self.heaviest_tipset();
+        SyncStateSnapshot {
+            heaviest_tipset: heaviest.key().clone(),
+            epoch: heaviest.epoch(),
+            peer_heads: peer_manager.peer_heads(),
+            pending_blocks,
+            sync_target: sync_state.target().as_ref().map(|ts| ts.key().clone()),
+        }
+    }
+
+    /// Writes a [`SyncStateSnapshot`] to `writer` as JSON, to investigate
+    /// sync stalls.
+    pub fn export_sync_state(
+        &self,
+        writer: impl Write,
+        peer_manager: &PeerManager,
+        sync_state: &SyncState,
+        pending_blocks: usize,
+    ) -> Result<(), Error> {
+        let snapshot = self.sync_state_snapshot(peer_manager, sync_state, pending_blocks);
+        serde_json::to_writer_pretty(writer, &LotusJson(snapshot))
+            .map_err(|e| Error::Other(format!("failed to export sync state: {e}")))
+    }
+
     /// Returns the genesis tipset.
     pub fn genesis_tipset(&self) -> Tipset {
         Tipset::from(self.genesis_block_header())
@@ -272,19 +471,102 @@ where
     /// Determines if provided tipset is heavier than existing known heaviest
     /// tipset
     fn update_heaviest(&self, ts: Arc<Tipset>) -> Result<(), Error> {
-        // Calculate heaviest weight before matching to avoid deadlock with mutex
-        let heaviest_weight = fil_cns::weight(self.blockstore(), &self.heaviest_tipset())?;
-
-        let new_weight = fil_cns::weight(self.blockstore(), ts.as_ref())?;
-        let curr_weight = heaviest_weight;
-
-        if new_weight > curr_weight {
+        if self.is_heavier_than(&ts, &self.heaviest_tipset())? {
             info!("New heaviest tipset! {} (EPOCH = {})", ts.key(), ts.epoch());
             self.set_heaviest_tipset(ts)?;
         }
         Ok(())
     }
 
+    /// Returns the chain weight of `ts`, computing it from the power table
+    /// unless it is cached. Only the weights of validated tipsets are cached.
+    fn weight(&self, ts: &Tipset) -> Result<BigInt, Error> {
+        if let Some(weight) = self.weight_cache.lock().get(ts.key()) {
+            return Ok(weight.clone());
+        }
+        let weight = fil_cns::weight(self.blockstore(), ts)?;
+        if self.is_tipset_validated(ts.key()) {
+            self.weight_cache
+                .lock()
+                .put(ts.key().clone(), weight.clone());
+        }
+        Ok(weight)
+    }
+
+    /// Returns the chain weight of the ancestor of `tipset` at `target_epoch`,
+    /// or of the closest ancestor before it if `target_epoch` is a null round.
+    ///
+    /// The weight of a tipset is recorded in the headers of its children, so
+    /// walking back from `tipset` yields the weights of all visited ancestors
+    /// without loading their states. The weights recorded by validated
+    /// children are cached along the way.
+    pub fn compute_chain_weight_at(
+        &self,
+        tipset: &Arc<Tipset>,
+        target_epoch: ChainEpoch,
+    ) -> Result<BigInt, Error> {
+        if target_epoch < 0 {
+            return Err(Error::Other(format!("Invalid target epoch {target_epoch}")));
+        }
+        if tipset.epoch() <= target_epoch {
+            return self.weight(tipset);
+        }
+        let mut child = Arc::clone(tipset);
+        loop {
+            let parent = self.chain_index.load_required_tipset(child.parents())?;
+            let weight = child.weight().clone();
+            if self.is_tipset_validated(child.key()) {
+                self.weight_cache
+                    .lock()
+                    .put(parent.key().clone(), weight.clone());
+            }
+            if parent.epoch() <= target_epoch {
+                return Ok(weight);
+            }
+            child = parent;
+        }
+    }
+
+    /// Returns `true` if `ts1` has a greater chain weight than `ts2`.
+    pub fn is_heavier_than(&self, ts1: &Arc<Tipset>, ts2: &Arc<Tipset>) -> Result<bool, Error> {
+        Ok(self.weight(ts1)? > self.weight(ts2)?)
+    }
+
+    /// Walks `ts1` and `ts2` back to their common ancestor, always stepping
+    /// the higher one (or both on a tie), and returns the number of tipsets
+    /// each of them is ahead of it. Null rounds are not counted.
+    pub fn compute_fork_length(
+        &self,
+        ts1: &Arc<Tipset>,
+        ts2: &Arc<Tipset>,
+    ) -> Result<ForkInfo, Error> {
+        let (mut ts1, mut ts2) = (Arc::clone(ts1), Arc::clone(ts2));
+        let (mut ts1_depth, mut ts2_depth) = (0, 0);
+        while ts1.key() != ts2.key() {
+            if ts1.epoch() == 0 && ts2.epoch() == 0 {
+                return Err(Error::Other(format!(
+                    "tipsets {} and {} do not share a genesis",
+                    ts1.key(),
+                    ts2.key()
+                )));
+            }
+            let epoch = cmp::max(ts1.epoch(), ts2.epoch());
+            if ts1.epoch() == epoch {
+                ts1 = self.chain_index.load_required_tipset(ts1.parents())?;
+                ts1_depth += 1;
+            }
+            if ts2.epoch() == epoch {
+                ts2 = self.chain_index.load_required_tipset(ts2.parents())?;
+                ts2_depth += 1;
+            }
+        }
+        Ok(ForkInfo {
+            common_ancestor: ts1,
+            ts1_depth,
+            ts2_depth,
+        })
+    }
+
     /// Checks metadata file if block has already been validated.
     pub fn is_block_validated(&self, cid: &Cid) -> bool {
         let validated = self.validated_blocks.lock().contains(cid);
@@ -305,6 +587,30 @@ where
         let _did_work = file.remove(cid);
     }
 
+    /// Checks whether the tipset has persisted validation markers, i.e. it
+    /// passed full validation before, possibly in a previous run.
+    pub fn is_tipset_validated(&self, tsk: &TipsetKey) -> bool {
+        self.validation_markers.contains(tsk).unwrap_or_default()
+    }
+
+    /// Persists validation markers for the blocks of the tipset at `epoch`,
+    /// pruning the markers older than the finality. This should only be
+    /// called once the parent state has been executed and the messages of
+    /// every block in the tipset verified.
+    pub fn mark_tipset_as_validated(
+        &self,
+        tsk: &TipsetKey,
+        epoch: ChainEpoch,
+    ) -> Result<(), Error> {
+        self.validation_markers.mark(tsk, epoch)
+    }
+
+    /// Removes the validation markers of all tipsets that include the block or
+    /// one of its descendants.
+    pub fn unmark_tipsets_with_block(&self, cid: &Cid) -> Result<(), Error> {
+        self.validation_markers.unmark_block(self.blockstore(), cid)
+    }
+
     /// Retrieves ordered valid messages from a `Tipset`. This will only include
     /// messages that will be passed through the VM.
     pub fn messages_for_tipset(&self, ts: &Tipset) -> Result<Vec<ChainMessage>, Error> {
@@ -312,6 +618,113 @@ where
         Ok(bmsgs.into_iter().flat_map(|bm| bm.messages).collect())
     }
 
+    /// Retrieves the messages of a `Tipset` in the order the VM applies them,
+    /// along with the indices of the blocks including each of them. A message
+    /// included in several blocks is returned once, so the position of a
+    /// message is the index of its receipt.
+    pub fn unique_messages_for_tipset(
+        &self,
+        ts: &Tipset,
+    ) -> Result<Vec<(ChainMessage, Vec<usize>)>, Error> {
+        let mut applied = HashMap::new();
+        let mut positions = CidHashMap::new();
+        let mut unique: Vec<(ChainMessage, Vec<usize>)> = vec![];
+        for (block_index, header) in ts.block_headers().iter().enumerate() {
+            let (bls_msgs, secp_msgs) = block_messages(&self.db, header)?;
+            let messages = bls_msgs
+                .into_iter()
+                .map(ChainMessage::Unsigned)
+                .chain(secp_msgs.into_iter().map(ChainMessage::Signed));
+            for message in messages {
+                let cid = message.cid();
+                if let Some((_, origins)) = positions
+                    .get(&cid)
+                    .and_then(|&position| unique.get_mut(position))
+                {
+                    if origins.last() != Some(&block_index) {
+                        origins.push(block_index);
+                    }
+                    continue;
+                }
+                // Same selection as `BlockMessages::for_tipset`
+                let sequence = applied
+                    .entry(message.from())
+                    .or_insert_with(|| message.sequence());
+                if *sequence != message.sequence() {
+                    continue;
+                }
+                *sequence += 1;
+                positions.insert(cid, unique.len());
+                unique.push((message, vec![block_index]));
+            }
+        }
+        Ok(unique)
+    }
+
+    /// Returns the CIDs of the messages sent by `from` with sequence `nonce` in
+    /// the blocks of the inclusive epoch range, on the heaviest chain or on
+    /// the forks known to the tipset tracker. The same message included on
+    /// both sides of a fork is returned once, so more than one CID means that
+    /// the nonce was double spent. The sender is compared as given, without
+    /// resolving it to an ID address.
+    pub fn check_double_spend(
+        &self,
+        from: &Address,
+        nonce: u64,
+        from_epoch: ChainEpoch,
+        to_epoch: ChainEpoch,
+    ) -> Result<Vec<Cid>, Error> {
+        let canonical = self
+            .heaviest_tipset()
+            .chain_arc(&self.db)
+            .skip_while(|ts| ts.epoch() > to_epoch)
+            .take_while(|ts| ts.epoch() >= from_epoch)
+            .flat_map(|ts| ts.block_headers().iter().map(|h| *h.cid()).collect_vec())
+            .collect_vec();
+        let forks = self.tipset_tracker.blocks_in_range(from_epoch, to_epoch);
+
+        let mut seen = CidHashSet::new();
+        let mut found = vec![];
+        for block in canonical.into_iter().chain(forks) {
+            if !seen.insert(block) {
+                continue;
+            }
+            let header = CachingBlockHeader::load(&self.db, block)?
+                .ok_or_else(|| Error::NotFound(format!("block {block}")))?;
+            let (bls_msgs, secp_msgs) = block_messages(&self.db, &header)?;
+            let messages = bls_msgs
+                .into_iter()
+                .map(|msg| (msg.cid(), msg))
+                .chain(secp_msgs.into_iter().map(|msg| (msg.cid(), msg.message)));
+            for (cid, msg) in messages {
+                if &msg.from == from && msg.sequence == nonce && !found.contains(&cid) {
+                    found.push(cid);
+                }
+            }
+        }
+
+        if found.len() > 1 {
+            warn!(
+                "Double spend of nonce {nonce} from {from} in epochs {from_epoch}..={to_epoch}: {}",
+                found.iter().join(", ")
+            );
+            super::metrics::DOUBLE_SPEND_DETECTED.inc();
+        }
+        Ok(found)
+    }
+
+    /// Returns the tipset at `height` in the chain of ancestors of
+    /// `chain_head`, or the previous non-null tipset if `height` is a null
+    /// round.
+    pub fn get_tipset_by_height_with_fallback(
+        &self,
+        height: ChainEpoch,
+        chain_head: &Arc<Tipset>,
+    ) -> Result<TipsetOrNull, Error> {
+        self.chain_index
+            .tipset_by_height_with_fallback(height, chain_head.clone())
+    }
+
     /// Gets look-back tipset (and state-root of that tipset) for block
     /// validations.
     ///
@@ -362,14 +775,21 @@ where
             return Ok((heaviest_tipset, state_root));
         }
 
+        // On null rounds, the look-back tipset is the previous non-null one.
+        let lbts = chain_index
+            .tipset_by_height_with_fallback(lbr, heaviest_tipset.clone())
+            .map_err(|e| Error::Other(format!("Could not get tipset by height {e:?}")))?
+            .tipset()
+            .clone();
+        // Its state root is the parent state of its child.
         let next_ts = chain_index
             .tipset_by_height(
-                lbr + 1,
+                lbts.epoch() + 1,
                 heaviest_tipset.clone(),
                 ResolveNullTipset::TakeNewer,
             )
             .map_err(|e| Error::Other(format!("Could not get tipset by height {e:?}")))?;
-        if lbr > next_ts.epoch() {
+        if next_ts.parents() != lbts.key() {
             return Err(Error::Other(format!(
                 "failed to find non-null tipset {:?} {} which is known to exist, found {:?} {}",
                 heaviest_tipset.key(),
@@ -378,9 +798,6 @@ where
                 next_ts.epoch()
             )));
         }
-        let lbts = chain_index
-            .load_required_tipset(next_ts.parents())
-            .map_err(|e| Error::Other(format!("Could not get tipset from keys {e:?}")))?;
         Ok((lbts, *next_ts.parent_state()))
     }
 
@@ -452,6 +869,97 @@ where
     }
 }
 
+/// Outcome of a [`ChainStore::orphan_block_cleanup`] run.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct OrphanCleanupStats {
+    /// Number of block headers removed from the database
+    pub blocks_removed: u32,
+    /// Total encoded size of the removed block headers
+    pub bytes_freed: u64,
+    pub time_elapsed: Duration,
+}
+
+impl<DB> ChainStore<DB>
+where
+    DB: Blockstore + GarbageCollectable<CidHashSet>,
+{
+    /// Removes the block headers of abandoned forks, e.g. blocks that never
+    /// made it into the canonical chain, once they are strictly older than
+    /// the head minus `finality` epochs.
+    ///
+    /// Only headers the tipset tracker pruned past the finality are
+    /// candidates for removal. Headers on the canonical chain, or reachable
+    /// from the blocks the tracker still holds, are kept.
+    pub fn orphan_block_cleanup(
+        &self,
+        finality: ChainEpochDelta,
+    ) -> Result<OrphanCleanupStats, Error> {
+        let start = Instant::now();
+        let head = self.heaviest_tipset();
+        let cut_off = head.epoch() - finality;
+        let candidates = self.tipset_tracker.take_retired(cut_off);
+        let Some(lowest) = candidates.iter().map(|(epoch, _)| *epoch).min() else {
+            return Ok(OrphanCleanupStats {
+                time_elapsed: start.elapsed(),
+                ..Default::default()
+            });
+        };
+
+        // Walk back from the head and the blocks still tracked, down to the
+        // oldest candidate
+        let mut reachable = CidHashSet::new();
+        let mut pending = self.tipset_tracker.blocks_in_range(lowest, ChainEpoch::MAX);
+        pending.extend(head.key().iter());
+        while let Some(cid) = pending.pop() {
+            if !reachable.insert(cid) {
+                continue;
+            }
+            if let Some(header) = CachingBlockHeader::load(&self.db, cid)? {
+                if header.epoch > lowest {
+                    pending.extend(header.parents.iter());
+                }
+            }
+        }
+
+        let (kept, orphans): (Vec<_>, Vec<_>) = candidates
+            .into_iter()
+            .partition(|(_, cid)| reachable.contains(cid));
+        // Blocks only reachable from tracked forks may become orphans once
+        // those forks are pruned too
+        let head_chain: CidHashSet = head
+            .chain_arc(&self.db)
+            .take_while(|ts| ts.epoch() >= lowest)
+            .flat_map(|ts| ts.key().iter().collect_vec())
+            .collect();
+        self.tipset_tracker.restore_retired(
+            kept.into_iter()
+                .filter(|(_, cid)| !head_chain.contains(cid)),
+        );
+
+        let mut removed = CidHashSet::new();
+        let mut bytes_freed = 0;
+        for (epoch, cid) in orphans {
+            trace!("Removing orphan block {cid} at epoch {epoch}");
+            if let Some(bytes) = self.db.get(&cid)? {
+                bytes_freed += bytes.len() as u64;
+                removed.insert(cid);
+            }
+            self.unmark_block_as_validated(&cid);
+        }
+        let blocks_removed = if removed.is_empty() {
+            0
+        } else {
+            self.db.remove_keys(removed)?
+        };
+
+        Ok(OrphanCleanupStats {
+            blocks_removed,
+            bytes_freed,
+            time_elapsed: start.elapsed(),
+        })
+    }
+}
+
 fn filter_lowest_index(values: Vec<(EthHash, Cid, u64, usize)>) -> Vec<(EthHash, Cid, u64)> {
     let map: HashMap<EthHash, (Cid, u64, usize)> = values.into_iter().fold(
         HashMap::default(),
@@ -536,7 +1044,7 @@ where
 }
 
 /// Returns a vector of CIDs from provided root CID
-fn read_amt_cids<DB>(db: &DB, root: &Cid) -> Result<Vec<Cid>, Error>
+pub(super) fn read_amt_cids<DB>(db: &DB, root: &Cid) -> Result<Vec<Cid>, Error>
 where
     DB: Blockstore,
 {
@@ -639,6 +1147,31 @@ where
         .ok_or_else(|| Error::UndefinedKey(key.to_string()))
 }
 
+/// Pairs each of `block_messages` with its receipt.
+///
+/// Receipts are committed to by the child tipset, one per message executed in
+/// the parent tipset. `tipset_messages` lists those messages in execution
+/// order, deduplicated across blocks (as returned by
+/// `Filecoin.ChainGetParentMessages`), so a message included by several blocks
+/// maps to the receipt of its first inclusion. Messages that were not executed
+/// map to [`None`].
+pub fn align_block_receipts<'a, R>(
+    block_messages: &[Cid],
+    tipset_messages: &[Cid],
+    receipts: &'a [R],
+) -> Vec<Option<&'a R>> {
+    let index: HashMap<&Cid, usize> = tipset_messages
+        .iter()
+        .enumerate()
+        .rev()
+        .map(|(i, cid)| (cid, i))
+        .collect();
+    block_messages
+        .iter()
+        .map(|cid| index.get(cid).and_then(|&i| receipts.get(i)))
+        .collect()
+}
+
 /// Returns parent message receipt given `block_header` and message index.
 pub fn get_parent_receipt(
     db: &impl Blockstore,
@@ -677,6 +1210,7 @@ pub mod headchange_json {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::chain::clear_validated_tipsets;
     use crate::utils::multihash::prelude::*;
     use crate::{blocks::RawBlockHeader, shim::address::Address};
     use cid::Cid;
@@ -719,4 +1253,549 @@ mod tests {
         cs.mark_block_as_validated(&cid);
         assert!(cs.is_block_validated(&cid));
     }
+
+    #[test]
+    fn tipset_validation_markers_persist() {
+        let db = Arc::new(crate::db::MemoryDB::default());
+        let chain_config = Arc::new(ChainConfig::default());
+        let gen_block = CachingBlockHeader::new(RawBlockHeader {
+            miner_address: Address::new_id(0),
+            ..Default::default()
+        });
+        let new_chain_store = || {
+            ChainStore::new(
+                db.clone(),
+                db.clone(),
+                db.clone(),
+                chain_config.clone(),
+                gen_block.clone(),
+            )
+            .unwrap()
+        };
+
+        let block = |seed| Cid::new_v1(DAG_CBOR, MultihashCode::Blake2b256.digest(&[seed]));
+        let ts1 = TipsetKey::from(nonempty![block(1), block(2)]);
+        let ts2 = TipsetKey::from(nonempty![block(3)]);
+
+        let cs = new_chain_store();
+        assert!(!cs.is_tipset_validated(&ts1));
+        cs.mark_tipset_as_validated(&ts1, 1).unwrap();
+        cs.mark_tipset_as_validated(&ts2, 2).unwrap();
+        drop(cs);
+
+        // Markers survive a restart
+        let cs = new_chain_store();
+        assert!(cs.is_tipset_validated(&ts1));
+        assert!(cs.is_tipset_validated(&ts2));
+
+        cs.unmark_tipsets_with_block(&block(2)).unwrap();
+        assert!(!cs.is_tipset_validated(&ts1));
+        assert!(cs.is_tipset_validated(&ts2));
+
+        clear_validated_tipsets(&db).unwrap();
+        assert!(!cs.is_tipset_validated(&ts2));
+    }
+
+    #[test]
+    fn align_block_receipts_test() {
+        let cids = (0..4)
+            .map(|sequence| {
+                Message {
+                    sequence,
+                    ..Default::default()
+                }
+                .cid()
+            })
+            .collect::<Vec<_>>();
+        // Both blocks of the tipset include `cids[1]`, which is only executed once.
+        let block_a = [cids[0], cids[1]];
+        let block_b = [cids[1], cids[2], cids[3]];
+        let tipset_messages = [cids[0], cids[1], cids[2]];
+        let receipts = ["r0", "r1", "r2"];
+
+        assert_eq!(
+            align_block_receipts(&block_a, &tipset_messages, &receipts),
+            vec![Some(&"r0"), Some(&"r1")]
+        );
+        assert_eq!(
+            align_block_receipts(&block_b, &tipset_messages, &receipts),
+            vec![Some(&"r1"), Some(&"r2"), None]
+        );
+        // No receipts, e.g. a truncated receipts AMT.
+        assert_eq!(
+            align_block_receipts::<&str>(&block_a, &tipset_messages, &[]),
+            vec![None, None]
+        );
+    }
+
+    #[test]
+    #[allow(unused_variables)]
+    fn fee_index_matches_headers() {
+        use crate::blocks::{chain4u, Chain4U, HeaderBuilder};
+
+        let db = Arc::new(crate::db::MemoryDB::default());
+        let c4u = Chain4U::with_blockstore(db.clone());
+        let fee = TokenAmount::from_atto;
+        chain4u! {
+            in c4u;
+            t0 @ [genesis]
+            -> t1 @ [b1 = HeaderBuilder::new().with_epoch(1).with_parent_base_fee(fee(100))]
+            -> t2 @ [b2 = HeaderBuilder::new().with_epoch(2).with_parent_base_fee(fee(112))]
+            -> t3 @ [b3 = HeaderBuilder::new().with_epoch(4).with_parent_base_fee(fee(98))]
+        };
+        let cs = ChainStore::new(
+            db.clone(),
+            db.clone(),
+            db,
+            Arc::new(ChainConfig::default()),
+            CachingBlockHeader::new(genesis.clone()),
+        )
+        .unwrap();
+        for ts in [t1, t2, t3] {
+            cs.set_heaviest_tipset(Arc::new(ts.clone())).unwrap();
+        }
+
+        for header in [b1, b2, b3] {
+            assert_eq!(
+                cs.get_fee_at_epoch(header.epoch).unwrap(),
+                Some(header.parent_base_fee.clone())
+            );
+        }
+        assert_eq!(cs.get_fee_at_epoch(3).unwrap(), None);
+        assert_eq!(
+            cs.get_fee_range(2, 5).unwrap(),
+            vec![
+                (2, b2.parent_base_fee.clone()),
+                (4, b3.parent_base_fee.clone())
+            ]
+        );
+
+        // A fork replacing the tipsets after `t1`, with a null round at
+        // epoch 2, is indexed up to the common ancestor
+        chain4u! {
+            from [b1] in c4u;
+            f3 @ [_f3 = HeaderBuilder::new().with_epoch(3).with_parent_base_fee(fee(50))]
+        };
+        cs.set_heaviest_tipset(Arc::new(f3.clone())).unwrap();
+        assert_eq!(
+            cs.get_fee_range(1, 5).unwrap(),
+            vec![(1, b1.parent_base_fee.clone()), (3, fee(50))]
+        );
+    }
+
+    #[test]
+    #[allow(unused_variables)]
+    fn tipset_by_height_with_fallback_test() {
+        use crate::blocks::{chain4u, Chain4U, HeaderBuilder};
+
+        let db = Arc::new(crate::db::MemoryDB::default());
+        let c4u = Chain4U::with_blockstore(db.clone());
+        // Epochs 3, 4 and 5 are null rounds
+        chain4u! {
+            in c4u;
+            t0 @ [genesis]
+            -> t1 @ [b1 = HeaderBuilder::new().with_epoch(1)]
+            -> t2 @ [b2 = HeaderBuilder::new().with_epoch(2)]
+            -> t6 @ [b6 = HeaderBuilder::new().with_epoch(6)]
+            -> t7 @ [b7 = HeaderBuilder::new().with_epoch(7)]
+        };
+        let cs = ChainStore::new(
+            db.clone(),
+            db.clone(),
+            db,
+            Arc::new(ChainConfig::default()),
+            CachingBlockHeader::new(genesis.clone()),
+        )
+        .unwrap();
+        let head = Arc::new(t7.clone());
+
+        for epoch in 3..=5 {
+            assert_eq!(
+                cs.get_tipset_by_height_with_fallback(epoch, &head).unwrap(),
+                TipsetOrNull::Null {
+                    actual_epoch: epoch,
+                    prev_tipset: Arc::new(t2.clone()),
+                }
+            );
+        }
+        for ts in [t1, t2, t6, t7] {
+            assert_eq!(
+                cs.get_tipset_by_height_with_fallback(ts.epoch(), &head)
+                    .unwrap(),
+                TipsetOrNull::Tipset(Arc::new(ts.clone()))
+            );
+        }
+    }
+
+    #[test]
+    #[allow(unused_variables)]
+    fn compute_chain_weight_at_test() {
+        use crate::blocks::{chain4u, Chain4U, HeaderBuilder};
+
+        let db = Arc::new(crate::db::MemoryDB::default());
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            t0 @ [genesis]
+            -> t1 @ [b1 = HeaderBuilder::new().with_weight(10.into())]
+            -> t2 @ [b2 = HeaderBuilder::new().with_weight(20.into())]
+            -> t3 @ [b3 = HeaderBuilder::new().with_weight(30.into())]
+            -> t4 @ [b4 = HeaderBuilder::new().with_weight(40.into())]
+        };
+        let cs = ChainStore::new(
+            db.clone(),
+            db.clone(),
+            db,
+            Arc::new(ChainConfig::default()),
+            CachingBlockHeader::new(genesis.clone()),
+        )
+        .unwrap();
+        let [t0, t1, t2, t3, t4] = [t0, t1, t2, t3, t4].map(|ts| Arc::new(ts.clone()));
+        // The head is not validated yet
+        for ts in [&t1, &t2, &t3] {
+            cs.mark_tipset_as_validated(ts.key(), ts.epoch()).unwrap();
+        }
+
+        // The weight of a tipset is the parent weight of its child.
+        for (epoch, weight) in [(0, 10), (1, 20), (2, 30), (3, 40)] {
+            assert_eq!(
+                cs.compute_chain_weight_at(&t4, epoch).unwrap(),
+                BigInt::from(weight)
+            );
+        }
+        // The head has no child, and there is no power table to compute its
+        // weight from.
+        assert!(cs.compute_chain_weight_at(&t4, 4).is_err());
+        assert!(cs.compute_chain_weight_at(&t4, -1).is_err());
+
+        // The weights recorded by validated tipsets are cached by the walk
+        // above.
+        assert!(cs.is_heavier_than(&t2, &t1).unwrap());
+        assert!(!cs.is_heavier_than(&t1, &t2).unwrap());
+        assert!(!cs.is_heavier_than(&t0, &t0).unwrap());
+        // The weight of `t3` is only recorded by the unvalidated head, and
+        // there is no power table to compute it from.
+        assert!(cs.is_heavier_than(&t3, &t1).is_err());
+        assert!(cs.is_heavier_than(&t4, &t3).is_err());
+    }
+
+    #[test]
+    #[allow(unused_variables)]
+    fn orphan_block_cleanup_test() {
+        use crate::blocks::{chain4u, Chain4U};
+
+        let db = Arc::new(crate::db::MemoryDB::default());
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            [genesis]
+            -> [b1] -> [b2] -> [b3] -> [b4] -> [b5] -> [b6]
+            -> t7 @ [b7] -> [b8] -> [b9] -> t10 @ [b10]
+        };
+        // An abandoned fork of 2 blocks, and a fork still in the tracker
+        // branching off it
+        chain4u! {
+            from [b1] in c4u;
+            [o2] -> [o3]
+        };
+        chain4u! {
+            from [o2] in c4u;
+            [f3] -> [f4] -> [f5] -> [f6]
+        };
+        let mut chain_config = ChainConfig::default();
+        chain_config.policy.chain_finality = 3;
+        let cs = ChainStore::new(
+            db.clone(),
+            db.clone(),
+            db.clone(),
+            Arc::new(chain_config),
+            CachingBlockHeader::new(genesis.clone()),
+        )
+        .unwrap();
+        cs.set_heaviest_tipset(Arc::new(t7.clone())).unwrap();
+
+        let header = |h: &RawBlockHeader| CachingBlockHeader::new(h.clone());
+        for h in [b1, b2, o2, b3, o3, f3, b4, f4, b5, f5, b6, f6, b7] {
+            cs.add_to_tipset_tracker(&header(h));
+        }
+
+        // Within the finality of the head, nothing is removed
+        let stats = cs.orphan_block_cleanup(10).unwrap();
+        assert_eq!(stats.blocks_removed, 0);
+
+        // Blocks at epochs 1 to 3 were pruned from the tracker. `o3` is
+        // orphaned, `o2` is the parent of the tracked `f4`
+        let stats = cs.orphan_block_cleanup(3).unwrap();
+        assert_eq!(stats.blocks_removed, 1);
+        assert!(stats.bytes_freed > 0);
+        assert!(!db.has(header(o3).cid()).unwrap());
+        for h in [b1, b2, b3, o2, f3, b7] {
+            assert!(db.has(header(h).cid()).unwrap());
+        }
+        assert_eq!(cs.heaviest_tipset().key(), t7.key());
+
+        // Once the fork leaves the tracker, it is removed down to `o2`
+        for h in [b8, b9, b10] {
+            cs.add_to_tipset_tracker(&header(h));
+        }
+        cs.set_heaviest_tipset(Arc::new(t10.clone())).unwrap();
+        let stats = cs.orphan_block_cleanup(3).unwrap();
+        assert_eq!(stats.blocks_removed, 5);
+        for h in [o2, f3, f4, f5, f6] {
+            assert!(!db.has(header(h).cid()).unwrap());
+        }
+        for h in [b1, b2, b3, b4, b5, b6, b7, b8, b9, b10] {
+            assert!(db.has(header(h).cid()).unwrap());
+        }
+    }
+
+    #[test]
+    fn compute_fork_length_test() {
+        use crate::blocks::{chain4u, Chain4U};
+
+        let db = Arc::new(crate::db::MemoryDB::default());
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            [genesis] -> t1 @ [b1]
+            -> [a2] -> [a3] -> [a4] -> [a5] -> t6 @ [a6]
+        };
+        chain4u! {
+            from [b1] in c4u;
+            [c2] -> u3 @ [c3] -> [c4] -> [c5] -> u6 @ [c6]
+        };
+        let cs = ChainStore::new(
+            db.clone(),
+            db.clone(),
+            db.clone(),
+            Arc::new(ChainConfig::default()),
+            CachingBlockHeader::new(genesis.clone()),
+        )
+        .unwrap();
+        let (t1, t6, u3, u6) = (
+            Arc::new(t1.clone()),
+            Arc::new(t6.clone()),
+            Arc::new(u3.clone()),
+            Arc::new(u6.clone()),
+        );
+
+        let fork = cs.compute_fork_length(&t6, &u6).unwrap();
+        assert_eq!(fork.common_ancestor.key(), t1.key());
+        assert_eq!((fork.ts1_depth, fork.ts2_depth), (5, 5));
+
+        let fork = cs.compute_fork_length(&u3, &t6).unwrap();
+        assert_eq!(fork.common_ancestor.key(), t1.key());
+        assert_eq!((fork.ts1_depth, fork.ts2_depth), (2, 5));
+
+        // An ancestor is its own common ancestor with its descendants
+        let fork = cs.compute_fork_length(&u6, &u3).unwrap();
+        assert_eq!(fork.common_ancestor.key(), u3.key());
+        assert_eq!((fork.ts1_depth, fork.ts2_depth), (3, 0));
+
+        let fork = cs.compute_fork_length(&t6, &t6).unwrap();
+        assert_eq!((fork.ts1_depth, fork.ts2_depth), (0, 0));
+    }
+
+    #[test]
+    #[allow(unused_variables)]
+    fn check_double_spend_test() {
+        use crate::blocks::{chain4u, Chain4U, HeaderBuilder};
+        use crate::chain_sync::TipsetValidator;
+        use crate::utils::db::CborStoreExt as _;
+
+        let db = Arc::new(crate::db::MemoryDB::default());
+        let sender = Address::new_id(1000);
+        let message = |to: u64, sequence: u64| Message {
+            from: sender,
+            to: Address::new_id(to),
+            sequence,
+            ..Default::default()
+        };
+        let msg_root = |messages: &[Message]| {
+            let cids = messages
+                .iter()
+                .map(|msg| db.put_cbor_default(msg).unwrap())
+                .collect();
+            TipsetValidator::compute_msg_root_from_cids(&db, cids, vec![]).unwrap()
+        };
+        let spend = message(1001, 7);
+        let conflicting = message(1002, 7);
+        let shared = message(1001, 8);
+        let empty = msg_root(&[]);
+        let canonical_msgs = msg_root(&[spend.clone(), shared.clone()]);
+        let fork_msgs = msg_root(&[conflicting.clone(), shared.clone()]);
+
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            [genesis = HeaderBuilder::new().with_messages(empty)]
+            -> [b1 = HeaderBuilder::new().with_messages(empty)]
+            -> [b2 = HeaderBuilder::new().with_messages(canonical_msgs)]
+            -> t3 @ [b3 = HeaderBuilder::new().with_messages(empty)]
+        };
+        // A competing fork including the same nonce in another message
+        chain4u! {
+            from [b1] in c4u;
+            [f2 = HeaderBuilder::new().with_messages(fork_msgs)]
+        };
+        let cs = ChainStore::new(
+            db.clone(),
+            db.clone(),
+            db.clone(),
+            Arc::new(ChainConfig::default()),
+            CachingBlockHeader::new(genesis.clone()),
+        )
+        .unwrap();
+        cs.set_heaviest_tipset(Arc::new(t3.clone())).unwrap();
+        cs.add_to_tipset_tracker(&CachingBlockHeader::new(f2.clone()));
+
+        let mut found = cs.check_double_spend(&sender, 7, 0, 3).unwrap();
+        found.sort();
+        let mut expected = vec![spend.cid(), conflicting.cid()];
+        expected.sort();
+        assert_eq!(found, expected);
+
+        // The same message on both sides of the fork is not a double spend
+        assert_eq!(
+            cs.check_double_spend(&sender, 8, 0, 3).unwrap(),
+            vec![shared.cid()]
+        );
+        assert!(cs.check_double_spend(&sender, 9, 0, 3).unwrap().is_empty());
+        // Outside of the epoch range
+        assert!(cs.check_double_spend(&sender, 7, 3, 3).unwrap().is_empty());
+    }
+
+    #[test]
+    fn message_tipset_index() {
+        use crate::blocks::{chain4u, Chain4U, HeaderBuilder};
+        use crate::chain_sync::TipsetValidator;
+        use crate::utils::db::CborStoreExt as _;
+
+        let db = Arc::new(crate::db::MemoryDB::default());
+        let message = |sequence: u64| {
+            db.put_cbor_default(&Message {
+                from: Address::new_id(1000),
+                to: Address::new_id(1001),
+                sequence,
+                ..Default::default()
+            })
+            .unwrap()
+        };
+        let (m0, m1, m2) = (message(0), message(1), message(2));
+        let msg_root = |cids: Vec<Cid>| {
+            TipsetValidator::compute_msg_root_from_cids(&db, cids, vec![]).unwrap()
+        };
+
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            [genesis = HeaderBuilder::new().with_messages(msg_root(vec![]))]
+            -> t1 @ [_b1 = HeaderBuilder::new().with_messages(msg_root(vec![m0, m1]))]
+            -> t2 @ [_b2 = HeaderBuilder::new().with_messages(msg_root(vec![m2]))]
+        };
+        chain4u! {
+            from [_b1] in c4u;
+            fork @ [_f = HeaderBuilder::new().with_messages(msg_root(vec![m2]))]
+        };
+        let cs = ChainStore::new(
+            db.clone(),
+            db.clone(),
+            db,
+            Arc::new(ChainConfig::default()),
+            CachingBlockHeader::new(genesis.clone()),
+        )
+        .unwrap();
+        assert!(!cs.message_tipset_index().is_built().unwrap());
+
+        cs.put_message_tipset_index(t1).unwrap();
+        cs.set_heaviest_tipset(Arc::new(t1.clone())).unwrap();
+        for msg_cid in [m0, m1] {
+            assert_eq!(
+                cs.tipset_for_message(&msg_cid).unwrap().unwrap().key(),
+                t1.key()
+            );
+        }
+        assert!(cs.tipset_for_message(&m2).unwrap().is_none());
+
+        cs.set_heaviest_tipset(Arc::new(t2.clone())).unwrap();
+        assert_eq!(cs.rebuild_message_tipset_index(2).unwrap(), 3);
+        assert!(cs.message_tipset_index().is_built().unwrap());
+        assert_eq!(cs.tipset_for_message(&m2).unwrap().unwrap().key(), t2.key());
+        assert_eq!(cs.tipset_for_message(&m0).unwrap().unwrap().key(), t1.key());
+
+        // Messages last indexed on a fork are not reported as included
+        cs.put_message_tipset_index(fork).unwrap();
+        assert!(cs.tipset_for_message(&m2).unwrap().is_none());
+        cs.set_heaviest_tipset(Arc::new(fork.clone())).unwrap();
+        assert_eq!(
+            cs.tipset_for_message(&m2).unwrap().unwrap().key(),
+            fork.key()
+        );
+        assert_eq!(cs.tipset_for_message(&m0).unwrap().unwrap().key(), t1.key());
+    }
+
+    #[test]
+    fn unique_messages_for_tipset_test() {
+        use crate::blocks::{chain4u, Chain4U, HeaderBuilder};
+        use crate::chain_sync::TipsetValidator;
+        use crate::utils::db::CborStoreExt as _;
+
+        let db = Arc::new(crate::db::MemoryDB::default());
+        let message = |from: u64, sequence: u64| {
+            db.put_cbor_default(&Message {
+                from: Address::new_id(from),
+                to: Address::new_id(1001),
+                sequence,
+                ..Default::default()
+            })
+            .unwrap()
+        };
+        let (m0, m1, other) = (message(1000, 0), message(1000, 1), message(2000, 0));
+        let msg_root = |cids: Vec<Cid>| {
+            TipsetValidator::compute_msg_root_from_cids(&db, cids, vec![]).unwrap()
+        };
+        let (left_msgs, right_msgs) = (msg_root(vec![m0, m1]), msg_root(vec![m1, other]));
+
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            [genesis = HeaderBuilder::new().with_messages(msg_root(vec![]))]
+            -> t1 @ [
+                _left = HeaderBuilder::new().with_messages(left_msgs),
+                _right = HeaderBuilder::new().with_messages(right_msgs),
+            ]
+        };
+        let cs = ChainStore::new(
+            db.clone(),
+            db.clone(),
+            db,
+            Arc::new(ChainConfig::default()),
+            CachingBlockHeader::new(genesis.clone()),
+        )
+        .unwrap();
+
+        let unique = cs
+            .unique_messages_for_tipset(t1)
+            .unwrap()
+            .into_iter()
+            .map(|(msg, origins)| (msg.cid(), origins))
+            .collect_vec();
+        // The blocks of a tipset are ordered by ticket
+        let expected = if t1.block_headers().first().messages == left_msgs {
+            vec![(m0, vec![0]), (m1, vec![0, 1]), (other, vec![1])]
+        } else {
+            // `m0` is skipped as `m1` was applied first
+            vec![(m1, vec![0, 1]), (other, vec![0])]
+        };
+        assert_eq!(unique, expected);
+        // The shared message is applied once
+        assert_eq!(
+            unique.iter().map(|(cid, _)| *cid).collect_vec(),
+            cs.messages_for_tipset(t1)
+                .unwrap()
+                .iter()
+                .map(|msg| msg.cid())
+                .collect_vec()
+        );
+    }
 }
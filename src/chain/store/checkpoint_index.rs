@@ -0,0 +1,221 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Index of tipset checkpoints, keyed by epoch, used to look up old tipsets
+//! without walking the whole chain from the head.
+
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+
+use super::Error;
+use crate::blocks::{Tipset, TipsetKey};
+use crate::db::{SettingsStore, SettingsStoreExt as _};
+use crate::shim::clock::ChainEpoch;
+use lru::LruCache;
+use nonzero_ext::nonzero;
+use parking_lot::Mutex;
+
+/// Number of epochs covered by each checkpoint.
+pub const CHECKPOINT_INTERVAL: ChainEpoch = 60;
+
+/// Number of checkpoints stored under a single settings key.
+const CHECKPOINTS_PER_PAGE: ChainEpoch = 256;
+
+/// Number of pages kept in memory, covering close to a year of epochs.
+const CHECKPOINT_PAGE_CACHE_SIZE: NonZeroUsize = nonzero!(64usize);
+
+/// Depth below a walked tipset from which its ancestors are deemed final, and
+/// may be checkpointed.
+pub const CHECKPOINT_FINALITY: ChainEpoch = 900;
+
+/// Prefix of the index keys in the settings store, followed by the page number.
+const CHECKPOINT_PAGE_KEY_PREFIX: &str = "/epoch_checkpoints/";
+
+/// Checkpoints of a page, keyed by window number.
+type Page = BTreeMap<ChainEpoch, TipsetKey>;
+
+/// Maps each window of [`CHECKPOINT_INTERVAL`] epochs to the lowest tipset of
+/// the indexed chain within that window. All the checkpoints are expected to
+/// be taken from a single, finalized, chain so that any checkpoint below an
+/// indexed tipset is one of its ancestors.
+///
+/// Clones share the pages cached in memory, so that the index is read from the
+/// settings store once.
+#[derive(Clone)]
+pub struct EpochCheckpointIndex {
+    settings: Arc<dyn SettingsStore + Sync + Send>,
+    /// Pages read from or written to the settings store.
+    pages: Arc<Mutex<LruCache<ChainEpoch, Page>>>,
+}
+
+impl EpochCheckpointIndex {
+    pub fn new(settings: Arc<dyn SettingsStore + Sync + Send>) -> Self {
+        Self {
+            settings,
+            pages: Arc::new(Mutex::new(LruCache::new(CHECKPOINT_PAGE_CACHE_SIZE))),
+        }
+    }
+
+    /// Returns the checkpoint of the window containing `epoch`, if indexed.
+    pub fn get(&self, epoch: ChainEpoch) -> Result<Option<TipsetKey>, Error> {
+        let window = window(epoch);
+        self.with_page(page(window), |checkpoints| {
+            checkpoints.get(&window).cloned()
+        })
+    }
+
+    /// Returns `true` if the tipset is the checkpoint of its window.
+    pub fn contains(&self, ts: &Tipset) -> Result<bool, Error> {
+        Ok(self.get(ts.epoch())?.as_ref() == Some(ts.key()))
+    }
+
+    /// Returns a writer to index a chain walked from its head down.
+    pub fn writer(&self) -> CheckpointWriter<'_> {
+        CheckpointWriter {
+            index: self,
+            page: None,
+        }
+    }
+
+    /// Calls `f` on the checkpoints of the page, read from the settings store
+    /// on a cache miss.
+    fn with_page<T>(&self, page: ChainEpoch, f: impl FnOnce(&Page) -> T) -> Result<T, Error> {
+        if let Some(checkpoints) = self.pages.lock().get(&page) {
+            return Ok(f(checkpoints));
+        }
+        let checkpoints: Page = self
+            .settings
+            .read_obj(&checkpoint_page_key(page))?
+            .unwrap_or_default();
+        let result = f(&checkpoints);
+        self.pages.lock().put(page, checkpoints);
+        Ok(result)
+    }
+
+    fn write_page(&self, page: ChainEpoch, checkpoints: Page) -> Result<(), Error> {
+        let mut merged = self.with_page(page, Page::clone)?;
+        merged.extend(checkpoints);
+        self.settings
+            .write_obj(&checkpoint_page_key(page), &merged)?;
+        self.pages.lock().put(page, merged);
+        Ok(())
+    }
+}
+
+/// Accumulates the checkpoints of tipsets pushed by decreasing epoch, and
+/// writes them a page at a time.
+pub struct CheckpointWriter<'a> {
+    index: &'a EpochCheckpointIndex,
+    page: Option<(ChainEpoch, Page)>,
+}
+
+impl CheckpointWriter<'_> {
+    /// Records the tipset as the checkpoint of its window, replacing the
+    /// higher tipsets of the window pushed before it.
+    pub fn push(&mut self, ts: &Tipset) -> Result<(), Error> {
+        let window = window(ts.epoch());
+        let page = page(window);
+        if self
+            .page
+            .as_ref()
+            .is_some_and(|(current, _)| *current != page)
+        {
+            self.flush()?;
+        }
+        self.page
+            .get_or_insert_with(|| (page, Page::new()))
+            .1
+            .insert(window, ts.key().clone());
+        Ok(())
+    }
+
+    /// Writes the pending checkpoints.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        if let Some((page, checkpoints)) = self.page.take() {
+            self.index.write_page(page, checkpoints)?;
+        }
+        Ok(())
+    }
+}
+
+fn window(epoch: ChainEpoch) -> ChainEpoch {
+    epoch.div_euclid(CHECKPOINT_INTERVAL)
+}
+
+fn page(window: ChainEpoch) -> ChainEpoch {
+    window.div_euclid(CHECKPOINTS_PER_PAGE)
+}
+
+fn checkpoint_page_key(page: ChainEpoch) -> String {
+    format!("{CHECKPOINT_PAGE_KEY_PREFIX}{page}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::{CachingBlockHeader, RawBlockHeader};
+    use crate::db::MemoryDB;
+
+    fn tipset(epoch: ChainEpoch) -> Tipset {
+        Tipset::from(CachingBlockHeader::new(RawBlockHeader {
+            epoch,
+            ..Default::default()
+        }))
+    }
+
+    #[test]
+    fn lowest_tipset_of_each_window_is_kept() {
+        let index = EpochCheckpointIndex::new(Arc::new(MemoryDB::default()));
+        let epochs = (1..=CHECKPOINT_INTERVAL * CHECKPOINTS_PER_PAGE * 2)
+            .rev()
+            // Skip some epochs as null rounds
+            .filter(|epoch| epoch % 7 != 0);
+        let mut writer = index.writer();
+        for epoch in epochs {
+            writer.push(&tipset(epoch)).unwrap();
+        }
+        writer.flush().unwrap();
+
+        assert_eq!(index.get(0).unwrap(), Some(tipset(1).key().clone()));
+        assert_eq!(
+            index.get(CHECKPOINT_INTERVAL + 5).unwrap(),
+            Some(tipset(CHECKPOINT_INTERVAL + 1).key().clone())
+        );
+        // The first epoch of the window is a null round
+        assert_eq!(
+            index.get(CHECKPOINT_INTERVAL * 7).unwrap(),
+            Some(tipset(CHECKPOINT_INTERVAL * 7 + 1).key().clone())
+        );
+        assert!(index.contains(&tipset(CHECKPOINT_INTERVAL * 3)).unwrap());
+        assert!(!index
+            .contains(&tipset(CHECKPOINT_INTERVAL * 3 + 1))
+            .unwrap());
+        assert_eq!(
+            index
+                .get(CHECKPOINT_INTERVAL * CHECKPOINTS_PER_PAGE * 3)
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn pages_are_merged() {
+        let index = EpochCheckpointIndex::new(Arc::new(MemoryDB::default()));
+        let mut writer = index.writer();
+        writer.push(&tipset(CHECKPOINT_INTERVAL * 2)).unwrap();
+        writer.flush().unwrap();
+        let mut writer = index.writer();
+        writer.push(&tipset(CHECKPOINT_INTERVAL)).unwrap();
+        writer.flush().unwrap();
+
+        assert_eq!(
+            index.get(CHECKPOINT_INTERVAL * 2).unwrap(),
+            Some(tipset(CHECKPOINT_INTERVAL * 2).key().clone())
+        );
+        assert_eq!(
+            index.get(CHECKPOINT_INTERVAL).unwrap(),
+            Some(tipset(CHECKPOINT_INTERVAL).key().clone())
+        );
+    }
+}
@@ -0,0 +1,193 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Index of the actor events emitted by the messages of each tipset, keyed by
+//! epoch, so that they need not be recomputed by executing the tipset.
+
+use std::sync::Arc;
+
+use super::{index_retention::PruneWatermark, Error};
+use crate::blocks::{Tipset, TipsetKey};
+use crate::db::SettingsStore;
+use crate::shim::clock::{ChainEpoch, ChainEpochDelta};
+use crate::shim::executor::StampedEvent;
+use fvm_ipld_encoding::tuple::{Deserialize_tuple, Serialize_tuple};
+
+/// Prefix of the index keys in the settings store, followed by the epoch.
+const EVENT_INDEX_KEY_PREFIX: &str = "/event_index/";
+
+/// Key of the lowest epoch the index may hold an entry for.
+const EVENT_INDEX_WATERMARK_KEY: &str = "/event_index_watermark";
+
+/// Events of a tipset. They are stored in the settings store along with the
+/// key rather than in the blockstore, where nothing on the chain graph would
+/// keep them from the garbage collector.
+#[derive(Debug, Clone, PartialEq, Serialize_tuple, Deserialize_tuple)]
+struct IndexEntry {
+    tipset_key: TipsetKey,
+    /// Events emitted by each message of the tipset, in execution order
+    events: Vec<Vec<StampedEvent>>,
+}
+
+/// Maps each indexed epoch to the events emitted by the tipset at that epoch,
+/// for the epochs within `retention` of the last indexed one and those
+/// backfilled.
+pub struct TipsetEventIndex {
+    settings: Arc<dyn SettingsStore + Sync + Send>,
+    watermark: PruneWatermark,
+    retention: ChainEpochDelta,
+}
+
+impl TipsetEventIndex {
+    pub fn new(settings: Arc<dyn SettingsStore + Sync + Send>, retention: ChainEpochDelta) -> Self {
+        Self {
+            watermark: PruneWatermark::new(settings.clone(), EVENT_INDEX_WATERMARK_KEY),
+            settings,
+            retention,
+        }
+    }
+
+    /// Records the events emitted by each message of the tipset, replacing
+    /// the entry of another tipset at the same epoch, and prunes the entries
+    /// older than the retention window.
+    pub fn put(&self, ts: &Tipset, events: &[Vec<StampedEvent>]) -> Result<(), Error> {
+        if self.contains(ts)? {
+            return Ok(());
+        }
+        self.write(ts, events)?;
+        self.watermark.include(ts.epoch())?;
+        self.watermark.prune(ts.epoch() - self.retention, |epoch| {
+            Ok(self.settings.delete(&event_index_key(epoch))?)
+        })
+    }
+
+    /// Records the events emitted by each message of a tipset of a backfilled
+    /// range. Nothing is pruned, and the entry is not accounted in the pruned
+    /// range, so that backfilling past the retention window keeps the older
+    /// entries requested.
+    pub fn put_backfilled(&self, ts: &Tipset, events: &[Vec<StampedEvent>]) -> Result<(), Error> {
+        if self.contains(ts)? {
+            return Ok(());
+        }
+        self.write(ts, events)
+    }
+
+    /// Returns the events of the tipset, if indexed.
+    pub fn get(&self, ts: &Tipset) -> Result<Option<Vec<Vec<StampedEvent>>>, Error> {
+        Ok(self.entry(ts)?.map(|entry| entry.events))
+    }
+
+    /// Returns `true` if the events of the tipset are indexed.
+    pub fn contains(&self, ts: &Tipset) -> Result<bool, Error> {
+        Ok(self.entry(ts)?.is_some())
+    }
+
+    fn write(&self, ts: &Tipset, events: &[Vec<StampedEvent>]) -> Result<(), Error> {
+        let entry = IndexEntry {
+            tipset_key: ts.key().clone(),
+            events: events.to_vec(),
+        };
+        Ok(self.settings.write_bin(
+            &event_index_key(ts.epoch()),
+            &fvm_ipld_encoding::to_vec(&entry)?,
+        )?)
+    }
+
+    /// Returns the entry at the tipset epoch, unless it belongs to another
+    /// tipset, e.g. on a fork.
+    fn entry(&self, ts: &Tipset) -> Result<Option<IndexEntry>, Error> {
+        let Some(bytes) = self.settings.read_bin(&event_index_key(ts.epoch()))? else {
+            return Ok(None);
+        };
+        let entry: IndexEntry = fvm_ipld_encoding::from_slice(&bytes)?;
+        Ok(Some(entry).filter(|entry| &entry.tipset_key == ts.key()))
+    }
+}
+
+fn event_index_key(epoch: ChainEpoch) -> String {
+    format!("{EVENT_INDEX_KEY_PREFIX}{epoch}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::{CachingBlockHeader, RawBlockHeader};
+    use crate::db::MemoryDB;
+    use crate::shim::executor::Entry;
+    use crate::shim::fvm_shared_latest::event::Flags;
+
+    fn tipset(epoch: ChainEpoch, timestamp: u64) -> Tipset {
+        Tipset::from(CachingBlockHeader::new(RawBlockHeader {
+            epoch,
+            timestamp,
+            ..Default::default()
+        }))
+    }
+
+    #[test]
+    fn indexed_events_roundtrip() {
+        let index = TipsetEventIndex::new(Arc::new(MemoryDB::default()), 100);
+        let ts = tipset(10, 0);
+        let events = vec![
+            vec![],
+            vec![
+                StampedEvent::new(
+                    1000,
+                    vec![Entry::new(
+                        Flags::FLAG_INDEXED_ALL,
+                        "t1".into(),
+                        0x55,
+                        vec![1, 2],
+                    )],
+                ),
+                StampedEvent::new(1001, vec![]),
+            ],
+        ];
+        assert_eq!(index.get(&ts).unwrap(), None);
+        assert!(!index.contains(&ts).unwrap());
+
+        index.put(&ts, &events).unwrap();
+        assert!(index.contains(&ts).unwrap());
+        assert_eq!(index.get(&ts).unwrap(), Some(events));
+
+        // Another tipset at the same epoch is not indexed
+        let fork = tipset(10, 1);
+        assert!(!index.contains(&fork).unwrap());
+        assert_eq!(index.get(&fork).unwrap(), None);
+
+        // Indexing again is a no-op
+        index.put(&ts, &[]).unwrap();
+        assert_eq!(index.get(&ts).unwrap(), Some(events.clone()));
+
+        // Entries older than the retention window are pruned
+        let later = tipset(110, 0);
+        index.put(&later, &events).unwrap();
+        assert_eq!(index.get(&ts).unwrap(), Some(events.clone()));
+        index.put(&tipset(111, 0), &events).unwrap();
+        assert_eq!(index.get(&ts).unwrap(), None);
+        assert_eq!(index.get(&later).unwrap(), Some(events));
+    }
+
+    #[test]
+    fn backfilled_events_are_kept() {
+        let index = TipsetEventIndex::new(Arc::new(MemoryDB::default()), 100);
+        let events = vec![vec![StampedEvent::new(1000, vec![])]];
+        index.put(&tipset(300, 0), &events).unwrap();
+
+        // Backfill a range wider than the retention window, behind the head
+        let backfilled = (0..250).map(|epoch| tipset(epoch, 0)).collect::<Vec<_>>();
+        for ts in &backfilled {
+            index.put_backfilled(ts, &events).unwrap();
+        }
+        for ts in &backfilled {
+            assert!(index.contains(ts).unwrap());
+        }
+
+        // Only the entries indexed as the head advances are pruned
+        index.put(&tipset(401, 0), &events).unwrap();
+        assert!(!index.contains(&tipset(300, 0)).unwrap());
+        for ts in &backfilled {
+            assert!(index.contains(ts).unwrap());
+        }
+    }
+}
@@ -0,0 +1,188 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Time-series index of base fees, keyed by epoch.
+
+use std::sync::Arc;
+
+use super::{index_retention::PruneWatermark, Error};
+use crate::blocks::Tipset;
+use crate::db::{SettingsStore, SettingsStoreExt as _};
+use crate::shim::{
+    clock::{ChainEpoch, ChainEpochDelta},
+    econ::TokenAmount,
+};
+use cid::Cid;
+use num::{BigInt, Signed as _};
+use num_bigint::Sign;
+
+/// Prefix of the index keys in the settings store, followed by the epoch.
+const EPOCH_FEE_KEY_PREFIX: &str = "/epoch_fee/";
+
+/// Key of the lowest epoch the index may hold entries for.
+const EPOCH_FEE_WATERMARK_KEY: &str = "/epoch_fee_watermark";
+
+/// Key of the epoch of the last indexed head.
+const EPOCH_FEE_HEAD_KEY: &str = "/epoch_fee_head";
+
+/// Size of an encoded base fee, in bytes.
+const FEE_SIZE: usize = 32;
+
+/// Maps each epoch of the heaviest chain to the base fee, in attoFIL, paid by
+/// the messages of the tipset at that epoch, for the epochs within
+/// `retention` of the head.
+pub struct MessageFeeIndex {
+    settings: Arc<dyn SettingsStore + Sync + Send>,
+    watermark: PruneWatermark,
+    retention: ChainEpochDelta,
+}
+
+impl MessageFeeIndex {
+    pub fn new(settings: Arc<dyn SettingsStore + Sync + Send>, retention: ChainEpochDelta) -> Self {
+        Self {
+            watermark: PruneWatermark::new(settings.clone(), EPOCH_FEE_WATERMARK_KEY),
+            settings,
+            retention,
+        }
+    }
+
+    /// Indexes the heaviest chain, given from its head back, down to the
+    /// first tipset already indexed. The entries left by a reverted chain
+    /// past the head or at the null rounds of this one are deleted, and the
+    /// entries older than the retention window pruned.
+    pub fn put_chain(&self, chain: impl IntoIterator<Item = Arc<Tipset>>) -> Result<(), Error> {
+        let mut chain = chain.into_iter().peekable();
+        let Some(head_epoch) = chain.peek().map(|ts| ts.epoch()) else {
+            return Ok(());
+        };
+        if let Some(last_head_epoch) = self.settings.read_obj::<ChainEpoch>(EPOCH_FEE_HEAD_KEY)? {
+            for epoch in head_epoch + 1..=last_head_epoch {
+                self.settings.delete(&epoch_fee_key(epoch))?;
+            }
+        }
+        self.settings.write_obj(EPOCH_FEE_HEAD_KEY, &head_epoch)?;
+        let keep_from = head_epoch - self.retention;
+        let mut lowest = head_epoch + 1;
+        for ts in chain.take_while(|ts| ts.epoch() >= keep_from) {
+            for null_round in ts.epoch() + 1..lowest {
+                self.settings.delete(&epoch_fee_key(null_round))?;
+            }
+            lowest = ts.epoch();
+            let tipset = ts.key().cid()?;
+            if self
+                .entry(ts.epoch())?
+                .is_some_and(|(_, indexed)| indexed == Some(tipset))
+            {
+                break;
+            }
+            let base_fee = &ts.block_headers().first().parent_base_fee;
+            self.settings.write_bin(
+                &epoch_fee_key(ts.epoch()),
+                &encode_entry(base_fee, &tipset)?,
+            )?;
+        }
+        self.watermark.include(lowest)?;
+        self.watermark.prune(keep_from, |epoch| {
+            self.settings.delete(&epoch_fee_key(epoch))?;
+            Ok(())
+        })
+    }
+
+    /// Returns the base fee at the epoch, if indexed.
+    pub fn get(&self, epoch: ChainEpoch) -> Result<Option<TokenAmount>, Error> {
+        Ok(self.entry(epoch)?.map(|(fee, _)| fee))
+    }
+
+    /// Returns the indexed base fees in the inclusive epoch range, skipping
+    /// epochs without an entry (e.g. null rounds).
+    pub fn range(
+        &self,
+        from: ChainEpoch,
+        to: ChainEpoch,
+    ) -> Result<Vec<(ChainEpoch, TokenAmount)>, Error> {
+        let mut fees = vec![];
+        for epoch in from..=to {
+            if let Some(fee) = self.get(epoch)? {
+                fees.push((epoch, fee));
+            }
+        }
+        Ok(fees)
+    }
+
+    /// Returns the base fee at the epoch and the CID of the key of its
+    /// tipset, missing from the entries written by earlier versions.
+    fn entry(&self, epoch: ChainEpoch) -> Result<Option<(TokenAmount, Option<Cid>)>, Error> {
+        self.settings
+            .read_bin(&epoch_fee_key(epoch))?
+            .map(|bytes| decode_entry(&bytes))
+            .transpose()
+    }
+}
+
+fn epoch_fee_key(epoch: ChainEpoch) -> String {
+    format!("{EPOCH_FEE_KEY_PREFIX}{epoch}")
+}
+
+fn encode_fee(fee: &TokenAmount) -> Result<Vec<u8>, Error> {
+    let atto = fee.atto();
+    if atto.is_negative() {
+        return Err(Error::Other(format!("negative base fee: {fee}")));
+    }
+    let (_, bytes) = atto.to_bytes_be();
+    let offset = FEE_SIZE
+        .checked_sub(bytes.len())
+        .ok_or_else(|| Error::Other(format!("base fee too large: {fee}")))?;
+    let mut encoded = vec![0; offset];
+    encoded.extend_from_slice(&bytes);
+    Ok(encoded)
+}
+
+fn decode_fee(bytes: &[u8]) -> Result<TokenAmount, Error> {
+    if bytes.len() != FEE_SIZE {
+        return Err(Error::Encoding(format!(
+            "invalid base fee length: {}",
+            bytes.len()
+        )));
+    }
+    Ok(TokenAmount::from_atto(BigInt::from_bytes_be(
+        Sign::Plus,
+        bytes,
+    )))
+}
+
+/// Encodes the base fee followed by the CID of the tipset key.
+fn encode_entry(fee: &TokenAmount, tipset: &Cid) -> Result<Vec<u8>, Error> {
+    let mut encoded = encode_fee(fee)?;
+    encoded.extend(tipset.to_bytes());
+    Ok(encoded)
+}
+
+fn decode_entry(bytes: &[u8]) -> Result<(TokenAmount, Option<Cid>), Error> {
+    let (fee, tipset) = bytes.split_at(bytes.len().min(FEE_SIZE));
+    let tipset = match tipset.is_empty() {
+        true => None,
+        false => Some(Cid::try_from(tipset).map_err(|e| Error::Encoding(e.to_string()))?),
+    };
+    Ok((decode_fee(fee)?, tipset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    #[quickcheck]
+    fn fee_roundtrip(atto: u128) {
+        let fee = TokenAmount::from_atto(atto);
+        assert_eq!(decode_fee(&encode_fee(&fee).unwrap()).unwrap(), fee);
+        let tipset = Cid::default();
+        assert_eq!(
+            decode_entry(&encode_entry(&fee, &tipset).unwrap()).unwrap(),
+            (fee.clone(), Some(tipset))
+        );
+        assert_eq!(
+            decode_entry(&encode_fee(&fee).unwrap()).unwrap(),
+            (fee, None)
+        );
+    }
+}
@@ -0,0 +1,188 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Proofs that a block includes a message, for light clients holding the
+//! block header only.
+
+use super::chain_store::read_amt_cids;
+use super::{ChainStore, Error};
+use crate::blocks::{CachingBlockHeader, Tipset, TxMeta};
+use crate::ipld::amt_proof::{amt_v0_proof, block_cid, AmtProof};
+use crate::ipld::Ipld;
+use crate::lotus_json::lotus_json;
+use anyhow::{bail, ensure};
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+
+lotus_json! {
+    /// Proof that a block includes a message, see
+    /// [`ChainStore::message_inclusion_proof`].
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct MessageInclusionProof {
+        /// The block including the message
+        pub block: Cid,
+        /// The encoded `TxMeta` the `Messages` field of the block header links to
+        pub tx_meta: Vec<u8>,
+        /// Whether the message is a SECP one rather than a BLS one
+        pub secp: bool,
+        /// The path to the message in the message list
+        pub proof: AmtProof,
+    }
+    snapshots {
+        vec![(
+            serde_json::json!({
+                "Block": {"/": "baeaaaaa"},
+                "TxMeta": "gg==",
+                "Secp": true,
+                "Proof": {"Index": 0, "Blocks": ["gQ=="]},
+            }),
+            MessageInclusionProof {
+                block: Cid::default(),
+                tx_meta: vec![0x82],
+                secp: true,
+                proof: AmtProof {
+                    index: 0,
+                    blocks: vec![vec![0x81]],
+                },
+            },
+        )]
+    }
+}
+
+impl MessageInclusionProof {
+    /// Checks that the block of `header` includes `message`.
+    pub fn verify(&self, header: &CachingBlockHeader, message: &Cid) -> anyhow::Result<()> {
+        ensure!(
+            header.cid() == &self.block,
+            "proof is for block {}, not {}",
+            self.block,
+            header.cid()
+        );
+        ensure!(
+            block_cid(&self.tx_meta) == header.messages,
+            "TxMeta does not match the messages of block {}",
+            self.block
+        );
+        let tx_meta: TxMeta = fvm_ipld_encoding::from_slice(&self.tx_meta)?;
+        let root = match self.secp {
+            true => tx_meta.secp_message_root,
+            false => tx_meta.bls_message_root,
+        };
+        match self.proof.verify(&root)? {
+            Ipld::Link(cid) if &cid == message => Ok(()),
+            other => bail!("proof is for {other:?}, not message {message}"),
+        }
+    }
+}
+
+impl<DB: Blockstore> ChainStore<DB> {
+    /// Returns the proof that the first block of `tipset` including
+    /// `message` does.
+    pub fn message_inclusion_proof(
+        &self,
+        tipset: &Tipset,
+        message: &Cid,
+    ) -> Result<MessageInclusionProof, Error> {
+        for header in tipset.block_headers() {
+            let tx_meta_bytes = self
+                .db
+                .get(&header.messages)?
+                .ok_or_else(|| Error::UndefinedKey(header.messages.to_string()))?;
+            let tx_meta: TxMeta = fvm_ipld_encoding::from_slice(&tx_meta_bytes)?;
+            for (root, secp) in [
+                (tx_meta.bls_message_root, false),
+                (tx_meta.secp_message_root, true),
+            ] {
+                let Some(index) = read_amt_cids(&self.db, &root)?
+                    .iter()
+                    .position(|cid| cid == message)
+                else {
+                    continue;
+                };
+                let proof = amt_v0_proof(&self.db, &root, index as u64)?.ok_or_else(|| {
+                    Error::Other(format!("no proof of message {message} in AMT {root}"))
+                })?;
+                return Ok(MessageInclusionProof {
+                    block: *header.cid(),
+                    tx_meta: tx_meta_bytes,
+                    secp,
+                    proof,
+                });
+            }
+        }
+        Err(Error::NotFound(format!(
+            "message {message} in tipset {}",
+            tipset.key()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::{chain4u, Chain4U, HeaderBuilder};
+    use crate::chain_sync::TipsetValidator;
+    use crate::db::MemoryDB;
+    use crate::lotus_json::assert_all_snapshots;
+    use crate::networks::ChainConfig;
+    use std::sync::Arc;
+
+    #[test]
+    fn snapshots() {
+        assert_all_snapshots::<MessageInclusionProof>();
+    }
+
+    #[test]
+    fn message_inclusion_proof_test() {
+        let db = Arc::new(MemoryDB::default());
+        let message = |i: u32| block_cid(&i.to_be_bytes());
+        let (bls, secp) = ((0..20).map(message).collect::<Vec<_>>(), message(100));
+        let msg_root = |bls: Vec<Cid>, secp: Vec<Cid>| {
+            TipsetValidator::compute_msg_root_from_cids(&db, bls, secp).unwrap()
+        };
+
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            [genesis = HeaderBuilder::new().with_messages(msg_root(vec![], vec![]))]
+            -> t1 @ [b1 = HeaderBuilder::new().with_messages(msg_root(bls.clone(), vec![secp]))]
+        };
+        let cs = ChainStore::new(
+            db.clone(),
+            db.clone(),
+            db,
+            Arc::new(ChainConfig::default()),
+            CachingBlockHeader::new(genesis.clone()),
+        )
+        .unwrap();
+        let b1 = CachingBlockHeader::new(b1.clone());
+
+        let proof = cs.message_inclusion_proof(t1, &bls[13]).unwrap();
+        assert!(!proof.secp);
+        proof.verify(&b1, &bls[13]).unwrap();
+        assert!(proof.verify(&b1, &bls[12]).is_err());
+        assert!(proof
+            .verify(&CachingBlockHeader::new(genesis.clone()), &bls[13])
+            .is_err());
+
+        let proof = cs.message_inclusion_proof(t1, &secp).unwrap();
+        assert!(proof.secp);
+        proof.verify(&b1, &secp).unwrap();
+
+        // Claiming the message is in the other list
+        let mut tampered = proof.clone();
+        tampered.secp = false;
+        assert!(tampered.verify(&b1, &secp).is_err());
+
+        // Tampering with the TxMeta
+        let mut tampered = proof.clone();
+        let last = tampered.tx_meta.len() - 1;
+        tampered.tx_meta[last] ^= 1;
+        assert!(tampered.verify(&b1, &secp).is_err());
+
+        assert!(matches!(
+            cs.message_inclusion_proof(t1, &message(200)),
+            Err(Error::NotFound(_))
+        ));
+    }
+}
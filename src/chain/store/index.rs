@@ -1,24 +1,25 @@
 // Copyright 2019-2025 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use std::{num::NonZeroUsize, sync::Arc};
+use std::sync::Arc;
 
 use crate::beacon::{BeaconEntry, IGNORE_DRAND_VAR};
 use crate::blocks::{Tipset, TipsetKey};
 use crate::metrics;
 use crate::shim::clock::ChainEpoch;
-use crate::utils::misc::env::is_env_truthy;
+use crate::utils::{
+    cache::{CacheConfig, SizeTrackingLruCache},
+    misc::env::is_env_truthy,
+};
 use fvm_ipld_blockstore::Blockstore;
 use itertools::Itertools;
-use lru::LruCache;
-use nonzero_ext::nonzero;
 use parking_lot::Mutex;
 
 use crate::chain::Error;
 
-const DEFAULT_TIPSET_CACHE_SIZE: NonZeroUsize = nonzero!(131072_usize);
+use super::checkpoint_index::{EpochCheckpointIndex, CHECKPOINT_FINALITY, CHECKPOINT_INTERVAL};
 
-type TipsetCache = Mutex<LruCache<TipsetKey, Arc<Tipset>>>;
+type TipsetCache = Mutex<SizeTrackingLruCache<TipsetKey, Arc<Tipset>>>;
 
 /// Keeps look-back tipsets in cache at a given interval `skip_length` and can
 /// be used to look-back at the chain to retrieve an old tipset.
@@ -28,6 +29,9 @@ pub struct ChainIndex<DB> {
 
     /// `Blockstore` pointer needed to load tipsets from cold storage.
     pub db: DB,
+
+    /// Checkpoints used to skip most of the chain when looking back far.
+    checkpoints: Option<EpochCheckpointIndex>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -39,10 +43,49 @@ pub enum ResolveNullTipset {
     TakeOlder,
 }
 
+/// Tipset found at a requested epoch, see
+/// [`ChainIndex::tipset_by_height_with_fallback`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum TipsetOrNull {
+    /// The tipset produced at the requested epoch.
+    Tipset(Arc<Tipset>),
+    /// No tipset was produced at the requested epoch.
+    Null {
+        /// The requested epoch
+        actual_epoch: ChainEpoch,
+        /// The nearest tipset before the null round
+        prev_tipset: Arc<Tipset>,
+    },
+}
+
+impl TipsetOrNull {
+    /// Returns the tipset at the requested epoch or, for null rounds, the
+    /// previous non-null tipset.
+    pub fn tipset(&self) -> &Arc<Tipset> {
+        match self {
+            Self::Tipset(ts) => ts,
+            Self::Null { prev_tipset, .. } => prev_tipset,
+        }
+    }
+}
+
 impl<DB: Blockstore> ChainIndex<DB> {
     pub fn new(db: DB) -> Self {
-        let ts_cache = Mutex::new(LruCache::new(DEFAULT_TIPSET_CACHE_SIZE));
-        Self { ts_cache, db }
+        let ts_cache = Mutex::new(SizeTrackingLruCache::new(
+            metrics::values::TIPSET.kind(),
+            CacheConfig::global().tipset_bytes,
+        ));
+        Self {
+            ts_cache,
+            db,
+            checkpoints: None,
+        }
+    }
+
+    /// Uses the checkpoints to speed up [`ChainIndex::tipset_by_height`].
+    pub fn with_checkpoints(mut self, checkpoints: EpochCheckpointIndex) -> Self {
+        self.checkpoints = Some(checkpoints);
+        self
     }
 
     /// Loads a tipset from memory given the tipset keys and cache. Semantically
@@ -131,6 +174,7 @@ impl<DB: Blockstore> ChainIndex<DB> {
             )));
         }
 
+        let from = self.skip_to_checkpoint(to, from)?;
         for (child, parent) in self.chain(from).tuple_windows() {
             if to == child.epoch() {
                 return Ok(child);
@@ -148,6 +192,76 @@ impl<DB: Blockstore> ChainIndex<DB> {
         )))
     }
 
+    /// Finds the tipset at epoch `to` in the chain of ancestors starting at
+    /// `from`, like [`ChainIndex::tipset_by_height`], telling null rounds apart
+    /// from produced tipsets.
+    pub fn tipset_by_height_with_fallback(
+        &self,
+        to: ChainEpoch,
+        from: Arc<Tipset>,
+    ) -> Result<TipsetOrNull, Error> {
+        let ts = self.tipset_by_height(to, from, ResolveNullTipset::TakeOlder)?;
+        Ok(if ts.epoch() == to {
+            TipsetOrNull::Tipset(ts)
+        } else {
+            TipsetOrNull::Null {
+                actual_epoch: to,
+                prev_tipset: ts,
+            }
+        })
+    }
+
+    /// Returns a checkpointed ancestor of `from` above `to`, or `from` itself if
+    /// it is not on the indexed chain or there is no such checkpoint.
+    fn skip_to_checkpoint(&self, to: ChainEpoch, from: Arc<Tipset>) -> Result<Arc<Tipset>, Error> {
+        let Some(checkpoints) = &self.checkpoints else {
+            return Ok(from);
+        };
+        // Any tipset of the next window is above `to`.
+        let Some(key) = checkpoints.get(to + CHECKPOINT_INTERVAL)? else {
+            return Ok(from);
+        };
+        let target = self.load_required_tipset(&key)?;
+        if target.epoch() >= from.epoch() {
+            return Ok(from);
+        }
+        // Walk down to the first checkpoint, at most a window away if `from`
+        // is within the indexed range, to make sure `from` descends from it.
+        // The lowest tipset of each final window walked past on the way is
+        // checkpointed once the walk joins the indexed chain.
+        let window = |ts: &Tipset| ts.epoch().div_euclid(CHECKPOINT_INTERVAL);
+        let final_window = (from.epoch() - CHECKPOINT_FINALITY).div_euclid(CHECKPOINT_INTERVAL);
+        // Lowest tipset walked in each final window
+        let mut walked: Vec<Arc<Tipset>> = vec![];
+        for ts in self.chain(from.clone()) {
+            if checkpoints.contains(&ts)? {
+                // The window of the checkpoint is already indexed
+                if walked
+                    .last()
+                    .is_some_and(|last| window(last) == window(&ts))
+                {
+                    walked.pop();
+                }
+                let mut writer = checkpoints.writer();
+                for ts in walked {
+                    writer.push(&ts)?;
+                }
+                writer.flush()?;
+                return Ok(target);
+            }
+            if ts.epoch() <= target.epoch() {
+                break;
+            }
+            if window(&ts) < final_window {
+                match walked.last_mut() {
+                    Some(last) if window(last) == window(&ts) => *last = ts,
+                    _ => walked.push(ts),
+                }
+            }
+        }
+        Ok(from)
+    }
+
     /// Iterate from the given tipset to genesis. Missing tipsets cut the chain
     /// short. Semantically identical to [`Tipset::chain`] but the results are
     /// cached.
@@ -283,4 +397,119 @@ mod tests {
             &epoch2b
         );
     }
+
+    /// Counts the blocks read from the inner store.
+    #[derive(Default)]
+    struct CountingBlockstore {
+        db: MemoryDB,
+        reads: AtomicU64,
+    }
+
+    impl Blockstore for CountingBlockstore {
+        fn get(&self, k: &cid::Cid) -> anyhow::Result<Option<Vec<u8>>> {
+            self.reads.fetch_add(1, Ordering::Relaxed);
+            self.db.get(k)
+        }
+
+        fn put_keyed(&self, k: &cid::Cid, block: &[u8]) -> anyhow::Result<()> {
+            self.db.put_keyed(k, block)
+        }
+    }
+
+    #[test]
+    fn checkpoints_skip_the_chain_walk() {
+        let db = Arc::new(CountingBlockstore::default());
+        let settings = Arc::new(MemoryDB::default());
+        let mut tipsets = vec![genesis_tipset()];
+        for epoch in 1..=5000 {
+            // Leave some null rounds
+            if epoch % 13 != 0 {
+                tipsets.push(tipset_child(tipsets.last().unwrap(), epoch));
+            }
+        }
+        let checkpoints = EpochCheckpointIndex::new(settings.clone());
+        let mut writer = checkpoints.writer();
+        for ts in tipsets.iter().rev() {
+            persist_tipset(ts, &db);
+            writer.push(ts).unwrap();
+        }
+        writer.flush().unwrap();
+        let head = Arc::new(tipsets.last().unwrap().clone());
+
+        let index =
+            ChainIndex::new(db.clone()).with_checkpoints(EpochCheckpointIndex::new(settings));
+        for (to, resolve, expected) in [
+            (12, ResolveNullTipset::TakeOlder, 12),
+            (26, ResolveNullTipset::TakeOlder, 25),
+            (26, ResolveNullTipset::TakeNewer, 27),
+            (CHECKPOINT_INTERVAL * 3, ResolveNullTipset::TakeOlder, 180),
+            (4990, ResolveNullTipset::TakeOlder, 4990),
+        ] {
+            db.reads.store(0, Ordering::Relaxed);
+            let ts = index.tipset_by_height(to, head.clone(), resolve).unwrap();
+            assert_eq!(
+                Some(ts.as_ref()),
+                tipsets.iter().find(|ts| ts.epoch() == expected)
+            );
+            // A window to the first checkpoint and another one from the target
+            assert!(db.reads.load(Ordering::Relaxed) <= 3 * CHECKPOINT_INTERVAL as u64);
+        }
+
+        // Without the checkpoints, the whole chain is walked
+        let index = ChainIndex::new(db.clone());
+        db.reads.store(0, Ordering::Relaxed);
+        index
+            .tipset_by_height(12, head, ResolveNullTipset::TakeOlder)
+            .unwrap();
+        assert!(db.reads.load(Ordering::Relaxed) > 4000);
+    }
+
+    #[test]
+    fn checkpoints_are_extended_past_the_last_one() {
+        let db = Arc::new(CountingBlockstore::default());
+        let settings = Arc::new(MemoryDB::default());
+        let mut tipsets = vec![genesis_tipset()];
+        for epoch in 1..=5000 {
+            tipsets.push(tipset_child(tipsets.last().unwrap(), epoch));
+        }
+        // Only the chain up to epoch 1000 is indexed
+        let checkpoints = EpochCheckpointIndex::new(settings.clone());
+        let mut writer = checkpoints.writer();
+        for ts in tipsets.iter().rev() {
+            persist_tipset(ts, &db);
+            if ts.epoch() <= 1000 {
+                writer.push(ts).unwrap();
+            }
+        }
+        writer.flush().unwrap();
+        let head = Arc::new(tipsets.last().unwrap().clone());
+
+        let index = ChainIndex::new(db.clone()).with_checkpoints(checkpoints.clone());
+        let ts = index
+            .tipset_by_height(12, head.clone(), ResolveNullTipset::TakeOlder)
+            .unwrap();
+        assert_eq!(ts.epoch(), 12);
+
+        // The final windows walked past are checkpointed, the recent ones are
+        // left out
+        let final_epoch = 5000 - CHECKPOINT_FINALITY;
+        for epoch in (0..=5000).step_by(CHECKPOINT_INTERVAL as usize) {
+            let expected = (epoch + CHECKPOINT_INTERVAL <= final_epoch)
+                .then(|| tipsets[epoch as usize].key().clone());
+            assert_eq!(checkpoints.get(epoch).unwrap(), expected, "epoch {epoch}");
+        }
+
+        // And persisted, so that lookups from the head now skip the chain
+        let index =
+            ChainIndex::new(db.clone()).with_checkpoints(EpochCheckpointIndex::new(settings));
+        db.reads.store(0, Ordering::Relaxed);
+        let ts = index
+            .tipset_by_height(2000, head, ResolveNullTipset::TakeOlder)
+            .unwrap();
+        assert_eq!(ts.epoch(), 2000);
+        assert!(
+            db.reads.load(Ordering::Relaxed)
+                <= CHECKPOINT_FINALITY as u64 + 3 * CHECKPOINT_INTERVAL as u64
+        );
+    }
 }
@@ -0,0 +1,118 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Pruning of the indices kept in the settings store. Unlike the blockstore,
+//! the settings store is not garbage collected, so each index drops its
+//! entries once they fall out of the retention window behind the head.
+
+use std::sync::Arc;
+
+use super::Error;
+use crate::db::{SettingsStore, SettingsStoreExt as _};
+use crate::networks::ChainConfig;
+use crate::shim::clock::{ChainEpoch, ChainEpochDelta};
+
+/// Number of epochs behind the head kept by the settings store indices, the
+/// minimum depth of the state kept by the garbage collector. Older messages
+/// and receipts may be collected, and so are their index entries.
+pub fn index_retention_epochs(chain_config: &ChainConfig) -> ChainEpochDelta {
+    chain_config.policy.chain_finality * 2
+}
+
+/// Maximum number of epochs pruned by a single [`PruneWatermark::prune`] call,
+/// so that catching up after a long gap, e.g. a snapshot import, is spread over
+/// the following calls rather than stalling one.
+const MAX_PRUNED_EPOCHS: ChainEpochDelta = 2880;
+
+/// Lowest epoch an index may hold entries for, stored under its own key so
+/// that pruning deletes each entry once rather than scanning the store.
+pub(super) struct PruneWatermark {
+    settings: Arc<dyn SettingsStore + Sync + Send>,
+    key: &'static str,
+}
+
+impl PruneWatermark {
+    pub fn new(settings: Arc<dyn SettingsStore + Sync + Send>, key: &'static str) -> Self {
+        Self { settings, key }
+    }
+
+    /// Notes an entry written at `epoch`, lowering the watermark if needed.
+    pub fn include(&self, epoch: ChainEpoch) -> Result<(), Error> {
+        if self.get()?.is_none_or(|lowest| epoch < lowest) {
+            self.settings.write_obj(self.key, &epoch)?;
+        }
+        Ok(())
+    }
+
+    /// Calls `delete` for each epoch from the watermark up to `keep_from`,
+    /// excluded, then raises the watermark to `keep_from`. At most
+    /// [`MAX_PRUNED_EPOCHS`] epochs are pruned at once, the rest being left to
+    /// the next calls.
+    pub fn prune(
+        &self,
+        keep_from: ChainEpoch,
+        mut delete: impl FnMut(ChainEpoch) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let Some(lowest) = self.get()? else {
+            return Ok(());
+        };
+        if lowest >= keep_from {
+            return Ok(());
+        }
+        let keep_from = keep_from.min(lowest + MAX_PRUNED_EPOCHS);
+        for epoch in lowest..keep_from {
+            delete(epoch)?;
+        }
+        self.settings.write_obj(self.key, &keep_from)?;
+        Ok(())
+    }
+
+    fn get(&self) -> Result<Option<ChainEpoch>, Error> {
+        Ok(self.settings.read_obj(self.key)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MemoryDB;
+
+    #[test]
+    fn prune_watermark() {
+        let watermark = PruneWatermark::new(Arc::new(MemoryDB::default()), "/watermark");
+        let pruned = |keep_from| {
+            let mut epochs = vec![];
+            watermark
+                .prune(keep_from, |epoch| {
+                    epochs.push(epoch);
+                    Ok(())
+                })
+                .unwrap();
+            epochs
+        };
+        // Nothing indexed yet
+        assert_eq!(pruned(10), Vec::<ChainEpoch>::new());
+
+        watermark.include(5).unwrap();
+        watermark.include(7).unwrap();
+        assert_eq!(pruned(8), vec![5, 6, 7]);
+        assert_eq!(pruned(8), Vec::<ChainEpoch>::new());
+
+        // Entries written behind the watermark, e.g. by a backfill
+        watermark.include(2).unwrap();
+        assert_eq!(pruned(4), vec![2, 3]);
+        assert_eq!(pruned(10), vec![4, 5, 6, 7, 8, 9]);
+
+        // A long gap is pruned over several calls
+        let keep_from = 10 + MAX_PRUNED_EPOCHS + 5;
+        assert_eq!(
+            pruned(keep_from),
+            (10..10 + MAX_PRUNED_EPOCHS).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            pruned(keep_from),
+            (10 + MAX_PRUNED_EPOCHS..keep_from).collect::<Vec<_>>()
+        );
+        assert_eq!(pruned(keep_from), Vec::<ChainEpoch>::new());
+    }
+}
@@ -0,0 +1,245 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Inverted index of messages, mapping each message CID to the tipset that
+//! included it.
+
+use std::sync::Arc;
+
+use super::{index_retention::PruneWatermark, read_msg_cids, Error};
+use crate::blocks::{Tipset, TipsetKey};
+use crate::db::{SettingsStore, SettingsStoreExt as _};
+use crate::shim::clock::{ChainEpoch, ChainEpochDelta};
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::tuple::{Deserialize_tuple, Serialize_tuple};
+use rayon::iter::ParallelIterator;
+
+/// Prefix of the index keys in the settings store, followed by the message CID.
+const MESSAGE_TIPSET_KEY_PREFIX: &str = "/msg_tipset/";
+
+/// Prefix of the keys listing the messages indexed at an epoch, followed by
+/// the epoch, so that pruning need not scan the index.
+const MESSAGE_TIPSET_EPOCH_KEY_PREFIX: &str = "/msg_tipset_epoch/";
+
+/// Key of the lowest epoch the index may hold entries for.
+const MESSAGE_TIPSET_WATERMARK_KEY: &str = "/msg_tipset_watermark";
+
+/// Key marking the index as built for the whole stored chain.
+const MESSAGE_TIPSET_INDEX_BUILT_KEY: &str = "/msg_tipset_index/built";
+
+#[derive(Debug, Clone, PartialEq, Serialize_tuple, Deserialize_tuple)]
+struct IndexEntry {
+    tipset_key: TipsetKey,
+    epoch: ChainEpoch,
+}
+
+/// Maps each indexed message to the key of a tipset including it, for the
+/// tipsets within `retention` of the last indexed one. A message included by
+/// several tipsets (e.g. across a reorg) maps to the last one indexed, which
+/// need not be on the heaviest chain.
+pub struct MessageTipsetIndex {
+    settings: Arc<dyn SettingsStore + Sync + Send>,
+    watermark: PruneWatermark,
+    retention: ChainEpochDelta,
+}
+
+impl MessageTipsetIndex {
+    pub fn new(settings: Arc<dyn SettingsStore + Sync + Send>, retention: ChainEpochDelta) -> Self {
+        Self {
+            watermark: PruneWatermark::new(settings.clone(), MESSAGE_TIPSET_WATERMARK_KEY),
+            settings,
+            retention,
+        }
+    }
+
+    /// Number of epochs behind the last indexed tipset kept by the index.
+    pub fn retention(&self) -> ChainEpochDelta {
+        self.retention
+    }
+
+    /// Records the tipset as the one including each of the messages of its
+    /// blocks, and prunes the entries older than the retention window. Blocks
+    /// whose messages are not in the store, e.g. headers fetched ahead of
+    /// their messages, are skipped. Returns the number of indexed messages.
+    pub fn put(&self, db: &impl Blockstore, ts: &Tipset) -> Result<usize, Error> {
+        let indexed = self.write(db, ts)?;
+        if indexed > 0 {
+            self.watermark.include(ts.epoch())?;
+        }
+        self.watermark.prune(ts.epoch() - self.retention, |epoch| {
+            self.delete_epoch(epoch)
+        })?;
+        Ok(indexed)
+    }
+
+    /// Indexes the messages of the tipsets in parallel, then prunes the
+    /// entries before `keep_from`. The pruned range is only updated once all
+    /// the tipsets are written, rather than concurrently by each of them.
+    /// Returns the number of indexed messages.
+    pub fn put_all(
+        &self,
+        db: &(impl Blockstore + Sync),
+        tipsets: impl ParallelIterator<Item = Arc<Tipset>>,
+        keep_from: ChainEpoch,
+    ) -> Result<usize, Error> {
+        let (indexed, lowest) = tipsets
+            .map(|ts| {
+                let indexed = self.write(db, &ts)?;
+                Ok((indexed, (indexed > 0).then_some(ts.epoch())))
+            })
+            .try_reduce(
+                || (0, None),
+                |(a, lowest_a), (b, lowest_b)| {
+                    Ok((a + b, lowest_a.into_iter().chain(lowest_b).min()))
+                },
+            )?;
+        if let Some(lowest) = lowest {
+            self.watermark.include(lowest)?;
+        }
+        self.watermark
+            .prune(keep_from, |epoch| self.delete_epoch(epoch))?;
+        Ok(indexed)
+    }
+
+    /// Writes the entries of the messages of the tipset, without updating the
+    /// pruned range. Returns the number of indexed messages.
+    fn write(&self, db: &impl Blockstore, ts: &Tipset) -> Result<usize, Error> {
+        let mut msg_cids = vec![];
+        for header in ts.block_headers() {
+            let (bls_cids, secp_cids) = match read_msg_cids(db, &header.messages) {
+                Ok(cids) => cids,
+                Err(Error::UndefinedKey(_)) => continue,
+                Err(e) => return Err(e),
+            };
+            msg_cids.extend(bls_cids.into_iter().chain(secp_cids));
+        }
+        if !msg_cids.is_empty() {
+            let entry = fvm_ipld_encoding::to_vec(&IndexEntry {
+                tipset_key: ts.key().clone(),
+                epoch: ts.epoch(),
+            })?;
+            for msg_cid in &msg_cids {
+                self.settings
+                    .write_bin(&message_tipset_key(msg_cid), &entry)?;
+            }
+            // Tipsets at the same epoch, e.g. on a fork, share the list
+            let mut epoch_cids = self.epoch_messages(ts.epoch())?;
+            epoch_cids.extend(&msg_cids);
+            epoch_cids.sort();
+            epoch_cids.dedup();
+            self.settings.write_bin(
+                &message_epoch_key(ts.epoch()),
+                &fvm_ipld_encoding::to_vec(&epoch_cids)?,
+            )?;
+        }
+        Ok(msg_cids.len())
+    }
+
+    /// Returns the key of the tipset including the message, if indexed.
+    pub fn get(&self, msg_cid: &Cid) -> Result<Option<TipsetKey>, Error> {
+        Ok(self.entry(msg_cid)?.map(|entry| entry.tipset_key))
+    }
+
+    /// Returns `true` if the index covers the whole stored chain.
+    pub fn is_built(&self) -> Result<bool, Error> {
+        Ok(self.settings.exists(MESSAGE_TIPSET_INDEX_BUILT_KEY)?)
+    }
+
+    /// Marks the index as covering the whole stored chain.
+    pub fn set_built(&self) -> Result<(), Error> {
+        self.settings
+            .write_obj(MESSAGE_TIPSET_INDEX_BUILT_KEY, &true)?;
+        Ok(())
+    }
+
+    fn entry(&self, msg_cid: &Cid) -> Result<Option<IndexEntry>, Error> {
+        self.settings
+            .read_bin(&message_tipset_key(msg_cid))?
+            .map(|bytes| fvm_ipld_encoding::from_slice(&bytes))
+            .transpose()
+            .map_err(Error::from)
+    }
+
+    fn epoch_messages(&self, epoch: ChainEpoch) -> Result<Vec<Cid>, Error> {
+        Ok(self
+            .settings
+            .read_bin(&message_epoch_key(epoch))?
+            .map(|bytes| fvm_ipld_encoding::from_slice(&bytes))
+            .transpose()?
+            .unwrap_or_default())
+    }
+
+    /// Deletes the entries written at the epoch, except for the messages
+    /// indexed again at a later epoch since.
+    fn delete_epoch(&self, epoch: ChainEpoch) -> Result<(), Error> {
+        for msg_cid in self.epoch_messages(epoch)? {
+            if self
+                .entry(&msg_cid)?
+                .is_some_and(|entry| entry.epoch == epoch)
+            {
+                self.settings.delete(&message_tipset_key(&msg_cid))?;
+            }
+        }
+        self.settings.delete(&message_epoch_key(epoch))?;
+        Ok(())
+    }
+}
+
+fn message_tipset_key(msg_cid: &Cid) -> String {
+    format!("{MESSAGE_TIPSET_KEY_PREFIX}{msg_cid}")
+}
+
+fn message_epoch_key(epoch: ChainEpoch) -> String {
+    format!("{MESSAGE_TIPSET_EPOCH_KEY_PREFIX}{epoch}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::{chain4u, Chain4U, HeaderBuilder};
+    use crate::chain_sync::TipsetValidator;
+    use crate::db::MemoryDB;
+    use crate::shim::{address::Address, message::Message};
+    use crate::utils::db::CborStoreExt as _;
+
+    #[test]
+    fn message_index_pruning() {
+        let db = Arc::new(MemoryDB::default());
+        let message = |sequence: u64| {
+            db.put_cbor_default(&Message {
+                from: Address::new_id(1000),
+                sequence,
+                ..Default::default()
+            })
+            .unwrap()
+        };
+        let (m0, m1, m2) = (message(0), message(1), message(2));
+        let msg_root = |cids: Vec<Cid>| {
+            TipsetValidator::compute_msg_root_from_cids(&db, cids, vec![]).unwrap()
+        };
+
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            t0 @ [_b0 = HeaderBuilder::new().with_messages(msg_root(vec![m0, m1]))]
+            -> t1 @ [_b1 = HeaderBuilder::new().with_messages(msg_root(vec![m1]))]
+            -> t2 @ [_b2 = HeaderBuilder::new().with_messages(msg_root(vec![m2]))]
+            -> t3 @ [_b3 = HeaderBuilder::new().with_messages(msg_root(vec![]))]
+        };
+        let index = MessageTipsetIndex::new(db.clone(), 2);
+        assert_eq!(index.put(db.as_ref(), t0).unwrap(), 2);
+        assert_eq!(index.put(db.as_ref(), t1).unwrap(), 1);
+        assert_eq!(index.put(db.as_ref(), t2).unwrap(), 1);
+        assert_eq!(index.get(&m0).unwrap().as_ref(), Some(t0.key()));
+        assert_eq!(index.get(&m1).unwrap().as_ref(), Some(t1.key()));
+
+        // The first epoch falls out of the retention window, but the message
+        // indexed again since is kept
+        index.put(db.as_ref(), t3).unwrap();
+        assert_eq!(index.get(&m0).unwrap(), None);
+        assert_eq!(index.get(&m1).unwrap().as_ref(), Some(t1.key()));
+        assert_eq!(index.get(&m2).unwrap().as_ref(), Some(t2.key()));
+        assert!(index.epoch_messages(t0.epoch()).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,15 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use once_cell::sync::Lazy;
+use prometheus_client::metrics::counter::Counter;
+
+pub static DOUBLE_SPEND_DETECTED: Lazy<Counter> = Lazy::new(|| {
+    let metric = Counter::default();
+    crate::metrics::default_registry().register(
+        "chain_double_spend_detected",
+        "Number of sender nonces found in more than one message across forks",
+        metric.clone(),
+    );
+    metric
+});
@@ -3,8 +3,23 @@
 
 pub mod base_fee;
 mod chain_store;
+mod checkpoint_index;
 mod errors;
+mod event_index;
+mod fee_index;
+mod inclusion_proof;
 pub mod index;
+mod index_retention;
+mod message_index;
+mod metrics;
+mod state_tree_depth;
+mod state_visitor;
+mod tip_alignment;
 mod tipset_tracker;
+mod validation_markers;
 
-pub use self::{base_fee::*, chain_store::*, errors::*};
+pub use self::{
+    base_fee::*, chain_store::*, checkpoint_index::*, errors::*, event_index::*, fee_index::*,
+    inclusion_proof::*, index_retention::*, message_index::*, state_tree_depth::*,
+    state_visitor::*, tip_alignment::*, validation_markers::*,
+};
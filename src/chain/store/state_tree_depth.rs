@@ -0,0 +1,222 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Depth analysis of the HAMT and AMT structures of a state tree, to diagnose
+//! their fragmentation. Deep structures slow down the state traversals.
+
+use std::collections::VecDeque;
+
+use super::{ChainStore, Error};
+use crate::cid_collections::CidHashSet;
+use crate::ipld::Ipld;
+use crate::lotus_json::lotus_json_with_self;
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::{CborStore, DAG_CBOR};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Depths of the HAMT and AMT structures reachable from a state root, see
+/// [`ChainStore::analyse_state_tree_depth`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct StateTreeDepthReport {
+    /// Levels of the deepest HAMT, 1 for entries all held by the root node
+    pub max_hamt_depth: u32,
+    /// Levels of the deepest AMT, 1 for an AMT of height 0
+    pub max_amt_depth: u32,
+    /// Blocks reached, a block linked from several places being counted
+    /// once per link
+    pub total_nodes: u64,
+    /// Distinct blocks reached
+    pub unique_nodes: u64,
+    /// `total_nodes / unique_nodes`, above 1 when blocks are shared
+    pub sharing_ratio: f64,
+}
+lotus_json_with_self!(StateTreeDepthReport);
+
+/// Position of a block in the traversal.
+#[derive(Debug, Clone, Copy)]
+enum Position {
+    /// Linked from a block outside of any structure, possibly the root of one
+    Root,
+    /// Child of a HAMT node, at the given level
+    Hamt(u32),
+    /// Child of an AMT node, at the given level
+    Amt(u32),
+}
+
+impl<DB: Blockstore> ChainStore<DB> {
+    /// Walks the blocks reachable from `state_cid` breadth-first, recording
+    /// the depths of the HAMT and AMT structures, recognized by the shape of
+    /// their nodes. Only the `DAG-CBOR` blocks are loaded, each at most once.
+    pub fn analyse_state_tree_depth(&self, state_cid: Cid) -> Result<StateTreeDepthReport, Error> {
+        analyse_state_tree_depth(&self.db, state_cid)
+    }
+}
+
+fn analyse_state_tree_depth(
+    db: &impl Blockstore,
+    state_cid: Cid,
+) -> Result<StateTreeDepthReport, Error> {
+    let mut report = StateTreeDepthReport::default();
+    let mut seen = CidHashSet::default();
+    let mut queue = VecDeque::from([(state_cid, Position::Root)]);
+    while let Some((cid, position)) = queue.pop_front() {
+        report.total_nodes += 1;
+        if !seen.insert(cid) || cid.codec() != DAG_CBOR {
+            continue;
+        }
+        let block: Ipld = db
+            .get_cbor(&cid)?
+            .ok_or_else(|| Error::NotFound(format!("block {cid}")))?;
+        match position {
+            Position::Hamt(depth) => visit_hamt_node(&block, depth, &mut report, &mut queue),
+            Position::Amt(depth) => visit_amt_node(&block, depth, &mut report, &mut queue),
+            Position::Root => match amt_root_node(&block) {
+                Some(node) => visit_amt_node(node, 1, &mut report, &mut queue),
+                None => visit_hamt_node(&block, 1, &mut report, &mut queue),
+            },
+        }
+    }
+    report.unique_nodes = seen.len() as u64;
+    if report.unique_nodes > 0 {
+        report.sharing_ratio = report.total_nodes as f64 / report.unique_nodes as f64;
+    }
+    Ok(report)
+}
+
+/// Queues the children of a HAMT node, encoded as `[bitfield, [pointers]]`,
+/// or the links of any other block.
+fn visit_hamt_node(
+    block: &Ipld,
+    depth: u32,
+    report: &mut StateTreeDepthReport,
+    queue: &mut VecDeque<(Cid, Position)>,
+) {
+    let Ipld::List(fields) = block else {
+        return queue_links(block, queue);
+    };
+    let [Ipld::Bytes(_), Ipld::List(pointers)] = fields.as_slice() else {
+        return queue_links(block, queue);
+    };
+    report.max_hamt_depth = report.max_hamt_depth.max(depth);
+    for pointer in pointers {
+        // Pointers to a child are links, or `{"0": link}` in v0 HAMTs, the
+        // others being buckets of entries
+        let child = match pointer {
+            Ipld::Link(cid) => Some(cid),
+            Ipld::Map(map) => match map.get("0") {
+                Some(Ipld::Link(cid)) => Some(cid),
+                _ => None,
+            },
+            _ => None,
+        };
+        match child {
+            Some(cid) => queue.push_back((*cid, Position::Hamt(depth + 1))),
+            None => queue_links(pointer, queue),
+        }
+    }
+}
+
+/// Queues the children of an AMT node, encoded as `[bitmap, [links],
+/// [values]]`, or the links of any other block.
+fn visit_amt_node(
+    node: &Ipld,
+    depth: u32,
+    report: &mut StateTreeDepthReport,
+    queue: &mut VecDeque<(Cid, Position)>,
+) {
+    let Some((links, values)) = amt_node(node) else {
+        return queue_links(node, queue);
+    };
+    report.max_amt_depth = report.max_amt_depth.max(depth);
+    for link in links {
+        match link {
+            Ipld::Link(cid) => queue.push_back((*cid, Position::Amt(depth + 1))),
+            other => queue_links(other, queue),
+        }
+    }
+    for value in values {
+        queue_links(value, queue);
+    }
+}
+
+fn amt_node(node: &Ipld) -> Option<(&[Ipld], &[Ipld])> {
+    match node {
+        Ipld::List(fields) => match fields.as_slice() {
+            [Ipld::Bytes(_), Ipld::List(links), Ipld::List(values)] => Some((links, values)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Returns the node embedded in an AMT root, encoded as `[bit_width, height,
+/// count, node]`, or `[height, count, node]` for v0 AMTs.
+fn amt_root_node(block: &Ipld) -> Option<&Ipld> {
+    let Ipld::List(fields) = block else {
+        return None;
+    };
+    let node = match fields.as_slice() {
+        [Ipld::Integer(_), Ipld::Integer(_), Ipld::Integer(_), node]
+        | [Ipld::Integer(_), Ipld::Integer(_), node] => node,
+        _ => return None,
+    };
+    amt_node(node).map(|_| node)
+}
+
+fn queue_links(ipld: &Ipld, queue: &mut VecDeque<(Cid, Position)>) {
+    match ipld {
+        Ipld::Link(cid) => queue.push_back((*cid, Position::Root)),
+        Ipld::List(items) => items.iter().for_each(|item| queue_links(item, queue)),
+        Ipld::Map(map) => map.values().for_each(|value| queue_links(value, queue)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MemoryDB;
+    use crate::utils::db::CborStoreExt as _;
+    use fil_actors_shared::fvm_ipld_amt::Amtv0;
+    use fil_actors_shared::fvm_ipld_hamt::{BytesKey, Hamt};
+
+    #[test]
+    fn depth_is_bounded_by_item_count() {
+        const ITEMS: u64 = 10_000;
+        let db = MemoryDB::default();
+
+        let amt = Amtv0::new_from_iter(&db, 0..ITEMS).unwrap();
+        let mut hamt = Hamt::<_, u64>::new_with_bit_width(&db, 5);
+        for i in 0..ITEMS {
+            hamt.set(BytesKey(i.to_be_bytes().to_vec()), i).unwrap();
+        }
+        let hamt = hamt.flush().unwrap();
+        // Both structures, the AMT twice
+        let root = db.put_cbor_default(&(amt, hamt, amt)).unwrap();
+
+        let report = analyse_state_tree_depth(&db, root).unwrap();
+        // A dense AMT of bit width 3 has the fewest levels holding the items
+        assert_eq!(report.max_amt_depth, (ITEMS as f64).log(8.0).ceil() as u32);
+        // HAMT nodes split when a bucket of 3 entries overflows, a balanced
+        // HAMT of bit width 5 having the fewest levels holding the items
+        let balanced = (ITEMS as f64 / 3.0).log(32.0).ceil() as u32;
+        assert!(report.max_hamt_depth >= balanced);
+        assert!(report.max_hamt_depth <= balanced + 2);
+        assert_eq!(report.total_nodes, report.unique_nodes + 1);
+        assert!(report.sharing_ratio > 1.0);
+    }
+
+    #[test]
+    fn missing_block() {
+        let db = MemoryDB::default();
+        let missing = MemoryDB::default().put_cbor_default(&0).unwrap();
+        assert!(analyse_state_tree_depth(&db, missing).is_err());
+        let root = db.put_cbor_default(&(Cid::default(),)).unwrap();
+        // Only DAG-CBOR blocks are loaded
+        let report = analyse_state_tree_depth(&db, root).unwrap();
+        assert_eq!((report.total_nodes, report.unique_nodes), (2, 2));
+    }
+}
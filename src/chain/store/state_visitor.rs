@@ -0,0 +1,138 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Traversal of the actors of a state tree, shared by the subsystems reading
+//! the whole state.
+
+use std::sync::Arc;
+
+use super::{ChainStore, Error};
+use crate::shim::{
+    address::Address,
+    state_tree::{ActorState, StateTree},
+};
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+
+/// Directive returned by [`StateVisitor::visit_actor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisitorResult {
+    /// Proceed to the next actor
+    Continue,
+    /// The actor is of no interest to the visitor, proceed to the next one
+    Skip,
+    /// End the walk, the remaining actors are not visited
+    Stop,
+}
+
+/// Visitor of the actors of a state tree, see [`ChainStore::walk_state_tree`].
+pub trait StateVisitor {
+    fn visit_actor(&mut self, addr: &Address, state: &ActorState) -> VisitorResult;
+}
+
+impl<F> StateVisitor for F
+where
+    F: FnMut(&Address, &ActorState) -> VisitorResult,
+{
+    fn visit_actor(&mut self, addr: &Address, state: &ActorState) -> VisitorResult {
+        self(addr, state)
+    }
+}
+
+impl<DB: Blockstore> ChainStore<DB> {
+    /// Visits the actors of the state tree at `state_cid`, in the order of
+    /// the underlying HAMT, until the visitor stops the walk.
+    pub fn walk_state_tree<V: StateVisitor>(
+        &self,
+        state_cid: Cid,
+        visitor: &mut V,
+    ) -> Result<(), Error> {
+        walk_state_tree(self.db.clone(), state_cid, visitor)
+    }
+}
+
+pub(crate) fn walk_state_tree<DB: Blockstore, V: StateVisitor>(
+    db: Arc<DB>,
+    state_cid: Cid,
+    visitor: &mut V,
+) -> Result<(), Error> {
+    let state_tree = StateTree::new_from_root(db, &state_cid)?;
+    // `StateTree::for_each` only ends early on errors
+    let mut stopped = false;
+    let result = state_tree.for_each(|addr, state| match visitor.visit_actor(&addr, state) {
+        VisitorResult::Continue | VisitorResult::Skip => Ok(()),
+        VisitorResult::Stop => {
+            stopped = true;
+            anyhow::bail!("state tree walk stopped")
+        }
+    });
+    match result {
+        Err(_) if stopped => Ok(()),
+        result => Ok(result?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MemoryDB;
+    use crate::shim::{econ::TokenAmount, state_tree::StateTreeVersion};
+    use num_traits::Zero as _;
+
+    #[derive(Default)]
+    struct CountingVisitor {
+        /// Number of actors to visit before stopping
+        limit: Option<usize>,
+        visited: usize,
+        /// Actors with a non-zero balance
+        funded: usize,
+    }
+
+    impl StateVisitor for CountingVisitor {
+        fn visit_actor(&mut self, _addr: &Address, state: &ActorState) -> VisitorResult {
+            self.visited += 1;
+            if self.limit.is_some_and(|limit| self.visited >= limit) {
+                return VisitorResult::Stop;
+            }
+            if state.balance.is_zero() {
+                return VisitorResult::Skip;
+            }
+            self.funded += 1;
+            VisitorResult::Continue
+        }
+    }
+
+    #[test]
+    fn count_actors() {
+        const ACTORS: u64 = 100;
+        let db = Arc::new(MemoryDB::default());
+        let mut state_tree = StateTree::new(db.clone(), StateTreeVersion::V5).unwrap();
+        for id in 0..ACTORS {
+            let actor = ActorState::new(
+                Cid::default(),
+                Cid::default(),
+                TokenAmount::from_atto(id % 2),
+                0,
+                None,
+            );
+            state_tree.set_actor(&Address::new_id(id), actor).unwrap();
+        }
+        let state_cid = state_tree.flush().unwrap();
+
+        let mut visitor = CountingVisitor::default();
+        walk_state_tree(db.clone(), state_cid, &mut visitor).unwrap();
+        assert_eq!(visitor.visited, ACTORS as usize);
+        assert_eq!(visitor.funded, ACTORS as usize / 2);
+
+        let mut visitor = CountingVisitor {
+            limit: Some(10),
+            ..Default::default()
+        };
+        walk_state_tree(db.clone(), state_cid, &mut visitor).unwrap();
+        assert_eq!(visitor.visited, 10);
+
+        // Errors other than stopping are reported
+        let missing = Cid::default();
+        assert!(walk_state_tree(db, missing, &mut CountingVisitor::default()).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Alignment of the pending messages of the message pool with the chain head,
+//! whose nonces go stale as the head moves.
+
+use std::sync::Arc;
+
+use super::{ChainStore, Error};
+use crate::blocks::Tipset;
+use crate::cid_collections::CidHashSet;
+use crate::message::{Message as MessageTrait, SignedMessage};
+use crate::shim::{address::Address, state_tree::StateTree};
+use ahash::{HashMap, HashMapExt as _};
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+
+/// Pending messages out of line with the chain head, see
+/// [`ChainStore::message_pool_tip_alignment`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AlignmentReport {
+    /// Messages already included by the head
+    pub stale: Vec<Cid>,
+    /// Messages not on chain, with a nonce already used by the sender
+    pub nonce_too_low: Vec<Cid>,
+    /// `(sender, expected nonce, actual nonce)` for each pending message
+    /// skipping nonces of its sender
+    pub nonce_gap: Vec<(Address, u64, u64)>,
+}
+
+impl AlignmentReport {
+    pub fn is_aligned(&self) -> bool {
+        self.stale.is_empty() && self.nonce_too_low.is_empty() && self.nonce_gap.is_empty()
+    }
+}
+
+impl<DB: Blockstore> ChainStore<DB> {
+    /// Checks the `pending` messages against the state after `head`. A
+    /// message is stale if the head includes it, the messages of its ancestors
+    /// being removed from the pool as they are applied. Otherwise its nonce is
+    /// compared to the next nonce of its sender, following the on-chain
+    /// sequence and the pending messages of lower nonces. The sender is
+    /// compared as given, without resolving it to an ID address.
+    pub fn message_pool_tip_alignment(
+        &self,
+        pending: &[SignedMessage],
+        head: &Arc<Tipset>,
+    ) -> Result<AlignmentReport, Error> {
+        // The messages of the head are applied on top of its parent state
+        let mut included = CidHashSet::new();
+        let mut head_sequences: HashMap<Address, u64> = HashMap::new();
+        for msg in self.messages_for_tipset(head)? {
+            included.insert(msg.cid());
+            let next = head_sequences.entry(msg.from()).or_default();
+            *next = (*next).max(msg.sequence() + 1);
+        }
+
+        let mut report = AlignmentReport::default();
+        let mut by_sender: HashMap<Address, Vec<&SignedMessage>> = HashMap::new();
+        for msg in pending {
+            let cid = msg.cid();
+            if included.contains(&cid) {
+                report.stale.push(cid);
+            } else {
+                by_sender.entry(msg.from()).or_default().push(msg);
+            }
+        }
+
+        let state_tree = StateTree::new_from_root(self.db.clone(), head.parent_state())?;
+        for (from, mut msgs) in by_sender {
+            let on_chain = state_tree
+                .get_actor(&from)?
+                .map(|actor| actor.sequence)
+                .unwrap_or_default();
+            let mut expected = on_chain.max(head_sequences.get(&from).copied().unwrap_or_default());
+            msgs.sort_by_key(|msg| msg.sequence());
+            for msg in msgs {
+                let sequence = msg.sequence();
+                if sequence < expected {
+                    report.nonce_too_low.push(msg.cid());
+                    continue;
+                }
+                if sequence > expected {
+                    report.nonce_gap.push((from, expected, sequence));
+                }
+                expected = sequence + 1;
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::{chain4u, CachingBlockHeader, Chain4U, HeaderBuilder};
+    use crate::chain_sync::TipsetValidator;
+    use crate::db::MemoryDB;
+    use crate::networks::ChainConfig;
+    use crate::shim::{
+        crypto::Signature, econ::TokenAmount, message::Message, state_tree::ActorState,
+        state_tree::StateTreeVersion,
+    };
+    use crate::utils::db::CborStoreExt as _;
+
+    #[test]
+    fn message_pool_tip_alignment_test() {
+        let db = Arc::new(MemoryDB::default());
+        let (alice, bob, carol) = (
+            Address::new_id(1000),
+            Address::new_id(1001),
+            Address::new_id(1002),
+        );
+        let message = |from: Address, sequence: u64| {
+            SignedMessage::new_unchecked(
+                Message {
+                    from,
+                    to: Address::new_id(2000),
+                    sequence,
+                    ..Default::default()
+                },
+                Signature::new_bls(vec![]),
+            )
+        };
+
+        // Alice has sent 5 messages and Bob 2, Carol is not on chain yet
+        let mut state_tree = StateTree::new(db.clone(), StateTreeVersion::V5).unwrap();
+        for (addr, sequence) in [(alice, 5), (bob, 2)] {
+            let actor = ActorState::new(
+                Cid::default(),
+                Cid::default(),
+                TokenAmount::default(),
+                sequence,
+                None,
+            );
+            state_tree.set_actor(&addr, actor).unwrap();
+        }
+        let state_root = state_tree.flush().unwrap();
+
+        // The head includes the 6th message of Alice
+        let included = message(alice, 5);
+        let msg_root = |messages: &[&SignedMessage]| {
+            let cids = messages
+                .iter()
+                .map(|msg| db.put_cbor_default(msg.message()).unwrap())
+                .collect();
+            TipsetValidator::compute_msg_root_from_cids(&db, cids, vec![]).unwrap()
+        };
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            [genesis = HeaderBuilder::new().with_messages(msg_root(&[]))]
+            -> head @ [_b1 = HeaderBuilder::new()
+                .with_messages(msg_root(&[&included]))
+                .with_state_root(state_root)]
+        };
+        let cs = ChainStore::new(
+            db.clone(),
+            db.clone(),
+            db,
+            Arc::new(ChainConfig::default()),
+            CachingBlockHeader::new(genesis.clone()),
+        )
+        .unwrap();
+        let head = Arc::new(head.clone());
+
+        let pending = [
+            included.clone(),
+            // Valid, following the included message
+            message(alice, 6),
+            message(alice, 7),
+            // Below the sequence of Alice
+            message(alice, 4),
+            // Valid, then skipping nonces 3 and 4
+            message(bob, 2),
+            message(bob, 5),
+            message(bob, 6),
+            // Skipping nonce 0
+            message(carol, 1),
+        ];
+        let mut report = cs.message_pool_tip_alignment(&pending, &head).unwrap();
+        report.nonce_gap.sort();
+        assert_eq!(
+            report,
+            AlignmentReport {
+                stale: vec![included.cid()],
+                nonce_too_low: vec![message(alice, 4).cid()],
+                nonce_gap: vec![(bob, 3, 5), (carol, 0, 1)],
+            }
+        );
+        assert!(!report.is_aligned());
+
+        let valid = [message(alice, 6), message(bob, 2), message(carol, 0)];
+        assert!(cs
+            .message_pool_tip_alignment(&valid, &head)
+            .unwrap()
+            .is_aligned());
+    }
+}
@@ -1,7 +1,10 @@
 // Copyright 2019-2025 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use std::{collections::BTreeMap, sync::Arc};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::Arc,
+};
 
 use super::Error;
 use crate::blocks::{CachingBlockHeader, Tipset};
@@ -13,10 +16,16 @@ use nunny::vec as nonempty;
 use parking_lot::Mutex;
 use tracing::{debug, warn};
 
+/// Maximum number of blocks pruned from the tracker kept as candidates for
+/// the orphan cleanup, the oldest being forgotten first.
+const MAX_RETIRED_BLOCKS: usize = 100_000;
+
 /// Tracks blocks by their height for the purpose of forming tipsets.
 #[derive(Default)]
 pub(in crate::chain) struct TipsetTracker<DB> {
     entries: Mutex<BTreeMap<ChainEpoch, Vec<Cid>>>,
+    /// Blocks pruned past the chain finality, by increasing epoch
+    retired: Mutex<VecDeque<(ChainEpoch, Cid)>>,
     db: Arc<DB>,
     chain_config: Arc<ChainConfig>,
 }
@@ -25,6 +34,7 @@ impl<DB: Blockstore> TipsetTracker<DB> {
     pub fn new(db: Arc<DB>, chain_config: Arc<ChainConfig>) -> Self {
         Self {
             entries: Default::default(),
+            retired: Default::default(),
             db,
             chain_config,
         }
@@ -80,6 +90,42 @@ impl<DB: Blockstore> TipsetTracker<DB> {
             cut_off_epoch,
         );
         std::mem::swap(&mut finality_entries, &mut entries);
+
+        let mut retired = self.retired.lock();
+        for (epoch, cids) in finality_entries {
+            retired.extend(cids.into_iter().map(|cid| (epoch, cid)));
+        }
+        let excess = retired.len().saturating_sub(MAX_RETIRED_BLOCKS);
+        retired.drain(..excess);
+    }
+
+    /// Takes the blocks pruned from the tracker at epochs strictly before
+    /// `before`.
+    pub fn take_retired(&self, before: ChainEpoch) -> Vec<(ChainEpoch, Cid)> {
+        let mut retired = self.retired.lock();
+        let count = retired.partition_point(|(epoch, _)| *epoch < before);
+        retired.drain(..count).collect()
+    }
+
+    /// Returns blocks taken with [`Self::take_retired`] to the candidates.
+    pub fn restore_retired(&self, blocks: impl IntoIterator<Item = (ChainEpoch, Cid)>) {
+        let mut retired = self.retired.lock();
+        for block in blocks {
+            let index = retired.partition_point(|(epoch, _)| *epoch <= block.0);
+            retired.insert(index, block);
+        }
+    }
+
+    /// Returns the tracked blocks in the inclusive epoch range.
+    pub fn blocks_in_range(&self, from: ChainEpoch, to: ChainEpoch) -> Vec<Cid> {
+        let entries = self.entries.lock();
+        if from > to {
+            return vec![];
+        }
+        entries
+            .range(from..=to)
+            .flat_map(|(_, cids)| cids.iter().copied())
+            .collect()
     }
 
     /// Expands the given block header into the largest possible tipset by
@@ -115,6 +161,7 @@ impl<DB: Blockstore> TipsetTracker<DB> {
 #[cfg(test)]
 mod test {
     use crate::db::MemoryDB;
+    use crate::utils::multihash::prelude::*;
 
     use super::*;
 
@@ -136,6 +183,7 @@ mod test {
             db: Arc::new(db),
             chain_config: chain_config.clone(),
             entries: Mutex::new(entries),
+            retired: Default::default(),
         };
 
         tipset_tracker.prune_entries(head_epoch);
@@ -155,5 +203,43 @@ mod test {
                 head_epoch - chain_config.policy.chain_finality + 3,
             ]
         );
+        assert_eq!(tipset_tracker.retired.lock().len(), 0);
+    }
+
+    #[test]
+    fn pruned_blocks_are_retired() {
+        let chain_config = Arc::new(ChainConfig::default());
+        let finality = chain_config.policy.chain_finality;
+        let cid = |i: u64| {
+            Cid::new_v1(
+                fvm_ipld_encoding::DAG_CBOR,
+                MultihashCode::Blake2b256.digest(&i.to_be_bytes()),
+            )
+        };
+        let tipset_tracker = TipsetTracker {
+            db: Arc::new(MemoryDB::default()),
+            chain_config,
+            entries: Mutex::new(BTreeMap::from([
+                (10, vec![cid(0), cid(1)]),
+                (20, vec![cid(2)]),
+                (1000, vec![cid(3)]),
+            ])),
+            retired: Default::default(),
+        };
+
+        tipset_tracker.prune_entries(finality + 30);
+        assert_eq!(
+            tipset_tracker.take_retired(15),
+            vec![(10, cid(0)), (10, cid(1))]
+        );
+        tipset_tracker.restore_retired([(10, cid(1))]);
+        assert_eq!(
+            tipset_tracker.take_retired(ChainEpoch::MAX),
+            vec![(10, cid(1)), (20, cid(2))]
+        );
+        assert_eq!(
+            tipset_tracker.blocks_in_range(0, ChainEpoch::MAX),
+            vec![cid(3)]
+        );
     }
 }
@@ -0,0 +1,244 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Persisted markers of fully validated blocks, so that the tipsets validated
+//! before a restart are not executed again.
+
+use std::sync::Arc;
+
+use super::{index_retention::PruneWatermark, Error};
+use crate::blocks::{CachingBlockHeader, TipsetKey};
+use crate::cid_collections::CidHashSet;
+use crate::db::{SettingsStore, SettingsStoreExt as _};
+use crate::shim::clock::{ChainEpoch, ChainEpochDelta};
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+
+/// Prefix of the marker keys in the settings store, followed by the block CID.
+/// The value is the epoch of the block.
+const VALIDATED_BLOCK_KEY_PREFIX: &str = "/validated_block/";
+
+/// Prefix of the keys listing the blocks marked at an epoch, followed by the
+/// epoch, so that pruning need not scan the markers.
+const VALIDATED_BLOCK_EPOCH_KEY_PREFIX: &str = "/validated_block_epoch/";
+
+/// Key of the lowest epoch markers may be held for.
+const VALIDATED_BLOCK_WATERMARK_KEY: &str = "/validated_block_watermark";
+
+/// Prefix of the tipset markers written by earlier versions, followed by the
+/// tipset key CID.
+const LEGACY_VALIDATED_TIPSET_KEY_PREFIX: &str = "/validated_tipset/";
+
+/// Markers of the blocks that passed full validation, kept for the blocks
+/// within `retention` of the last marked one. The validity of a block only
+/// depends on its parents, so a tipset is validated once all its blocks are.
+pub struct ValidationMarkers {
+    settings: Arc<dyn SettingsStore + Sync + Send>,
+    watermark: PruneWatermark,
+    retention: ChainEpochDelta,
+}
+
+impl ValidationMarkers {
+    pub fn new(settings: Arc<dyn SettingsStore + Sync + Send>, retention: ChainEpochDelta) -> Self {
+        Self {
+            watermark: PruneWatermark::new(settings.clone(), VALIDATED_BLOCK_WATERMARK_KEY),
+            settings,
+            retention,
+        }
+    }
+
+    /// Returns `true` if every block of the tipset is marked.
+    pub fn contains(&self, tsk: &TipsetKey) -> Result<bool, Error> {
+        for cid in tsk.iter() {
+            if !self.settings.exists(&validated_block_key(&cid))? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Marks the blocks of the tipset at `epoch`, and prunes the markers
+    /// older than the retention window.
+    pub fn mark(&self, tsk: &TipsetKey, epoch: ChainEpoch) -> Result<(), Error> {
+        for cid in tsk.iter() {
+            self.settings
+                .write_obj(&validated_block_key(&cid), &epoch)?;
+        }
+        // Tipsets at the same epoch, e.g. on a fork, share the list
+        let mut epoch_cids = self.epoch_blocks(epoch)?;
+        epoch_cids.extend(tsk.iter());
+        epoch_cids.sort();
+        epoch_cids.dedup();
+        self.settings.write_bin(
+            &validated_block_epoch_key(epoch),
+            &fvm_ipld_encoding::to_vec(&epoch_cids)?,
+        )?;
+        self.watermark.include(epoch)?;
+        self.watermark
+            .prune(epoch - self.retention, |epoch| self.delete_epoch(epoch))
+    }
+
+    /// Removes the markers of the block and of its marked descendants, and so
+    /// of all the tipsets including any of them. The descendants are found by
+    /// walking the marked epochs above the block, whose headers are loaded
+    /// from `db`.
+    pub fn unmark_block(&self, db: &impl Blockstore, cid: &Cid) -> Result<(), Error> {
+        let epoch = match self
+            .settings
+            .read_obj::<ChainEpoch>(&validated_block_key(cid))?
+        {
+            Some(epoch) => Some(epoch),
+            None => CachingBlockHeader::load(db, *cid)?.map(|header| header.epoch),
+        };
+        self.settings.delete(&validated_block_key(cid))?;
+        let Some(epoch) = epoch else {
+            return Ok(());
+        };
+
+        let mut marked_epochs = self
+            .settings
+            .setting_keys()?
+            .into_iter()
+            .filter_map(|key| {
+                key.strip_prefix(VALIDATED_BLOCK_EPOCH_KEY_PREFIX)?
+                    .parse::<ChainEpoch>()
+                    .ok()
+            })
+            .filter(|marked| *marked > epoch)
+            .collect::<Vec<_>>();
+        marked_epochs.sort_unstable();
+
+        let mut bad = CidHashSet::default();
+        bad.insert(*cid);
+        for marked in marked_epochs {
+            for block in self.epoch_blocks(marked)? {
+                let Some(header) = CachingBlockHeader::load(db, block)? else {
+                    continue;
+                };
+                if header.parents.iter().any(|parent| bad.contains(&parent)) {
+                    self.settings.delete(&validated_block_key(&block))?;
+                    bad.insert(block);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn epoch_blocks(&self, epoch: ChainEpoch) -> Result<Vec<Cid>, Error> {
+        Ok(self
+            .settings
+            .read_bin(&validated_block_epoch_key(epoch))?
+            .map(|bytes| fvm_ipld_encoding::from_slice(&bytes))
+            .transpose()?
+            .unwrap_or_default())
+    }
+
+    fn delete_epoch(&self, epoch: ChainEpoch) -> Result<(), Error> {
+        for cid in self.epoch_blocks(epoch)? {
+            self.settings.delete(&validated_block_key(&cid))?;
+        }
+        self.settings.delete(&validated_block_epoch_key(epoch))?;
+        Ok(())
+    }
+}
+
+/// Removes all persisted validation markers, e.g. after a database migration
+/// that may have changed the data they vouch for.
+pub fn clear_validated_tipsets(settings: &(impl SettingsStore + ?Sized)) -> anyhow::Result<()> {
+    for key in settings.setting_keys()? {
+        if key.starts_with(VALIDATED_BLOCK_KEY_PREFIX)
+            || key.starts_with(VALIDATED_BLOCK_EPOCH_KEY_PREFIX)
+            || key.starts_with(LEGACY_VALIDATED_TIPSET_KEY_PREFIX)
+            || key == VALIDATED_BLOCK_WATERMARK_KEY
+        {
+            settings.delete(&key)?;
+        }
+    }
+    Ok(())
+}
+
+fn validated_block_key(cid: &Cid) -> String {
+    format!("{VALIDATED_BLOCK_KEY_PREFIX}{cid}")
+}
+
+fn validated_block_epoch_key(epoch: ChainEpoch) -> String {
+    format!("{VALIDATED_BLOCK_EPOCH_KEY_PREFIX}{epoch}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MemoryDB;
+    use crate::utils::multihash::prelude::*;
+    use fvm_ipld_encoding::DAG_CBOR;
+    use nunny::vec as nonempty;
+
+    #[test]
+    fn validation_markers_pruning() {
+        let db = Arc::new(MemoryDB::default());
+        let markers = ValidationMarkers::new(db.clone(), 10);
+        let block = |seed| Cid::new_v1(DAG_CBOR, MultihashCode::Blake2b256.digest(&[seed]));
+        let ts1 = TipsetKey::from(nonempty![block(1), block(2)]);
+        let ts2 = TipsetKey::from(nonempty![block(3)]);
+        // A fork of `ts1` sharing one of its blocks
+        let ts1_fork = TipsetKey::from(nonempty![block(1), block(4)]);
+
+        markers.mark(&ts1, 1).unwrap();
+        markers.mark(&ts2, 5).unwrap();
+        assert!(markers.contains(&ts1).unwrap());
+        assert!(!markers.contains(&ts1_fork).unwrap());
+
+        markers.unmark_block(db.as_ref(), &block(2)).unwrap();
+        assert!(!markers.contains(&ts1).unwrap());
+        assert!(markers.contains(&ts2).unwrap());
+
+        // Marking a tipset more than 10 epochs ahead prunes the markers at epoch 1
+        markers.mark(&ts1_fork, 1).unwrap();
+        assert!(markers.contains(&ts1_fork).unwrap());
+        markers
+            .mark(&TipsetKey::from(nonempty![block(5)]), 11)
+            .unwrap();
+        assert!(markers.contains(&ts1_fork).unwrap());
+        markers
+            .mark(&TipsetKey::from(nonempty![block(6)]), 12)
+            .unwrap();
+        assert!(!markers.contains(&ts1_fork).unwrap());
+        assert!(markers.contains(&ts2).unwrap());
+
+        clear_validated_tipsets(db.as_ref()).unwrap();
+        assert!(!markers.contains(&ts2).unwrap());
+        assert!(db.setting_keys().unwrap().is_empty());
+    }
+
+    #[test]
+    #[allow(unused_variables)]
+    fn unmarking_a_block_unmarks_its_descendants() {
+        use crate::blocks::{chain4u, Chain4U, HeaderBuilder};
+
+        let db = Arc::new(MemoryDB::default());
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            [genesis]
+            -> t1 @ [b1 = HeaderBuilder::new().with_epoch(1)]
+            -> t2 @ [b2 = HeaderBuilder::new().with_epoch(2)]
+            -> t3 @ [b3 = HeaderBuilder::new().with_epoch(4)]
+        };
+        let markers = ValidationMarkers::new(db.clone(), 100);
+        // A tipset at a later epoch outside of the chain
+        let unrelated = TipsetKey::from(nonempty![Cid::new_v1(
+            DAG_CBOR,
+            MultihashCode::Blake2b256.digest(&[7])
+        )]);
+        for ts in [t1, t2, t3] {
+            markers.mark(ts.key(), ts.epoch()).unwrap();
+        }
+        markers.mark(&unrelated, 5).unwrap();
+
+        markers.unmark_block(db.as_ref(), b2.cid()).unwrap();
+        assert!(markers.contains(t1.key()).unwrap());
+        assert!(!markers.contains(t2.key()).unwrap());
+        assert!(!markers.contains(t3.key()).unwrap());
+        assert!(markers.contains(&unrelated).unwrap());
+    }
+}
@@ -18,6 +18,7 @@ use crate::chain_sync::{
         TipsetProcessor, TipsetProcessorError, TipsetRangeSyncer, TipsetRangeSyncerError,
     },
     validation::{TipsetValidationError, TipsetValidator},
+    watchdog::{SyncWatchdog, WatchdogAction, WatchdogStatus},
 };
 use crate::libp2p::{
     hello::HelloRequest, NetworkEvent, NetworkMessage, PeerId, PeerManager, PubsubMessage,
@@ -44,6 +45,7 @@ use tracing::{debug, error, info, trace, warn};
 const DEFAULT_REQUEST_WINDOW: usize = 8;
 const DEFAULT_TIPSET_SAMPLE_SIZE: usize = 1;
 const DEFAULT_RECENT_STATE_ROOTS: i64 = 2000;
+const DEFAULT_WATCHDOG_LAG_THRESHOLD: i64 = 30;
 
 pub(in crate::chain_sync) type WorkerState = Arc<RwLock<SyncState>>;
 
@@ -85,6 +87,23 @@ pub struct SyncConfig {
     /// head is
     #[cfg_attr(test, arbitrary(gen(|g| u32::arbitrary(g) as _)))]
     pub tipset_sample_size: usize,
+    /// In lite mode, block headers, signatures, beacon entries and parent
+    /// weight linkage are validated but messages are not executed and state
+    /// roots are not verified.
+    #[serde(default)]
+    pub lite: bool,
+    /// Number of epochs the head may lag behind the wall clock before the
+    /// sync is considered stuck and recovered, or `0` to disable the watchdog
+    #[serde(default = "default_watchdog_lag_threshold")]
+    pub watchdog_lag_threshold: i64,
+    /// Verify the signatures of all beacon entries, including the rounds
+    /// recorded as verified before
+    #[serde(default)]
+    pub distrust_beacon_cache: bool,
+}
+
+fn default_watchdog_lag_threshold() -> i64 {
+    DEFAULT_WATCHDOG_LAG_THRESHOLD
 }
 
 impl Default for SyncConfig {
@@ -93,6 +112,9 @@ impl Default for SyncConfig {
             request_window: DEFAULT_REQUEST_WINDOW,
             recent_state_roots: DEFAULT_RECENT_STATE_ROOTS,
             tipset_sample_size: DEFAULT_TIPSET_SAMPLE_SIZE,
+            lite: false,
+            watchdog_lag_threshold: DEFAULT_WATCHDOG_LAG_THRESHOLD,
+            distrust_beacon_cache: false,
         }
     }
 }
@@ -114,6 +136,7 @@ enum NetworkHeadEvaluation {
 }
 
 /// Represents whether received messages should be added to message pool
+#[derive(Clone, Copy)]
 enum PubsubMessageProcessingStrategy {
     /// Messages should be added to the message pool
     Process,
@@ -158,6 +181,15 @@ pub struct ChainMuxer<DB, M> {
 
     /// When `stateless_mode` is true, forest connects to the P2P network but does not sync to HEAD.
     stateless_mode: bool,
+
+    /// Detects a head that stopped advancing and decides how to recover
+    watchdog: SyncWatchdog,
+
+    /// Latest status of the watchdog, shared with the RPC server
+    watchdog_status: Arc<RwLock<WatchdogStatus>>,
+
+    /// Ticks once per block delay to check the head progress
+    watchdog_interval: tokio::time::Interval,
 }
 
 impl<DB, M> ChainMuxer<DB, M>
@@ -179,6 +211,15 @@ where
     ) -> Result<Self, ChainMuxerError> {
         let network =
             SyncNetworkContext::new(network_send, peer_manager, state_manager.blockstore_owned());
+        let block_delay = state_manager.chain_config().block_delay_secs;
+        let watchdog = SyncWatchdog::new(
+            genesis.block_headers().first().timestamp,
+            block_delay,
+            state_manager.sync_config().watchdog_lag_threshold,
+        );
+        let mut watchdog_interval =
+            tokio::time::interval(std::time::Duration::from_secs(block_delay.max(1) as u64));
+        watchdog_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
 
         Ok(Self {
             state: ChainMuxerState::Idle,
@@ -192,6 +233,9 @@ where
             tipset_receiver,
             state_manager,
             stateless_mode,
+            watchdog,
+            watchdog_status: Default::default(),
+            watchdog_interval,
         })
     }
 
@@ -211,6 +255,89 @@ where
         self.worker_state.clone()
     }
 
+    /// Returns a cloned `Arc` of the sync watchdog status.
+    pub fn watchdog_status_cloned(&self) -> Arc<RwLock<WatchdogStatus>> {
+        self.watchdog_status.clone()
+    }
+
+    /// Checks that the head keeps advancing, and recovers from a stuck sync
+    /// by re-bootstrapping the peer connections first, then by restarting the
+    /// sync away from the peers stuck with the node.
+    fn check_watchdog(&mut self) {
+        let head = self.state_manager.chain_store().heaviest_tipset();
+        let action = self
+            .watchdog
+            .check(head.epoch(), chrono::Utc::now().timestamp() as u64);
+        let status = self.watchdog.status().clone();
+        metrics::WATCHDOG_EPOCHS_BEHIND.set(status.epochs_behind);
+        *self.watchdog_status.write() = status.clone();
+
+        match action {
+            Some(WatchdogAction::Rebootstrap) => {
+                let peer_manager = self.network.peer_manager();
+                let last_success = peer_manager
+                    .last_global_success()
+                    .map(|at| humantime::format_duration(at.elapsed()).to_string() + " ago")
+                    .unwrap_or_else(|| "never".into());
+                warn!(
+                    "Head is stuck at epoch {}, {} epochs behind the network. Peers: {}, last chain exchange success: {last_success}, invalid tipsets: {}. Re-bootstrapping peer connections",
+                    head.epoch(),
+                    status.epochs_behind,
+                    peer_manager.peer_count(),
+                    metrics::INVALID_TIPSET_TOTAL.get(),
+                );
+                metrics::WATCHDOG_REBOOTSTRAPS.inc();
+                if let Err(e) = self.network.network_send().send(NetworkMessage::Bootstrap) {
+                    warn!("Failed to request a re-bootstrap: {e}");
+                }
+            }
+            Some(WatchdogAction::RestartSync) => {
+                // At most this fraction of the peers is dropped per restart, so
+                // that a network-wide stall does not leave the node without peers
+                const MAX_DROPPED_PEERS_DIVISOR: usize = 4;
+                let peer_manager = self.network.peer_manager();
+                let chain_index = &self.state_manager.chain_store().chain_index;
+                // Peers that recently announced a head not ahead of ours cannot
+                // help, older announcements may predate the stall. Of those,
+                // only the ones failing more chain exchange requests than they
+                // serve are dropped.
+                let since = std::time::Instant::now()
+                    .checked_sub(std::time::Duration::from_secs(self.watchdog.stall_period()))
+                    .unwrap_or_else(std::time::Instant::now);
+                let scores = peer_manager.peer_scores();
+                let stuck = peer_manager
+                    .peer_heads_since(since)
+                    .into_iter()
+                    .filter(|(_, key)| {
+                        chain_index
+                            .load_tipset(key)
+                            .ok()
+                            .flatten()
+                            .is_some_and(|peer_head| peer_head.epoch() <= head.epoch())
+                    })
+                    .filter_map(|(peer, _)| {
+                        let score = scores.get(&peer).copied().unwrap_or_default();
+                        (score < 0.0).then_some((peer, score))
+                    })
+                    .sorted_by(|(_, a), (_, b)| a.total_cmp(b))
+                    .take(peer_manager.peer_count() / MAX_DROPPED_PEERS_DIVISOR)
+                    .collect_vec();
+                for (peer, _) in stuck {
+                    peer_manager.mark_peer_bad(peer, "stuck with the local head");
+                }
+                warn!(
+                    "Head is still stuck at epoch {}, {} epochs behind the network. Restarting the sync (restart #{})",
+                    head.epoch(),
+                    status.epochs_behind,
+                    status.restarts,
+                );
+                metrics::WATCHDOG_RESTARTS.inc();
+                self.state = ChainMuxerState::Idle;
+            }
+            None => {}
+        }
+    }
+
     async fn get_full_tipset(
         network: SyncNetworkContext<DB>,
         chain_store: Arc<ChainStore<DB>>,
@@ -333,6 +460,9 @@ where
                     .get_or_create(&metrics::values::HELLO_RESPONSE_OUTBOUND)
                     .inc();
                 let tipset_keys = TipsetKey::from(request.heaviest_tip_set.clone());
+                network
+                    .peer_manager()
+                    .update_peer_head(source, tipset_keys.clone());
                 Self::get_full_tipset(
                     network.clone(),
                     chain_store.clone(),
@@ -507,6 +637,17 @@ where
         Box::pin(future)
     }
 
+    /// Gossip messages are added to the message pool unless the node runs in
+    /// lite mode, where the sender states they are validated against are not
+    /// computed.
+    fn message_processing_strategy(&self) -> PubsubMessageProcessingStrategy {
+        if self.state_manager.sync_config().lite {
+            PubsubMessageProcessingStrategy::DoNotProcess
+        } else {
+            PubsubMessageProcessingStrategy::Process
+        }
+    }
+
     fn evaluate_network_head(&self) -> ChainMuxerFuture<NetworkHeadEvaluation, ChainMuxerError> {
         let p2p_messages = self.net_handler.clone();
         let chain_store = self.state_manager.chain_store().clone();
@@ -518,6 +659,7 @@ where
         let tipset_sample_size = self.state_manager.sync_config().tipset_sample_size;
         let block_delay = self.state_manager.chain_config().block_delay_secs;
         let stateless_mode = self.stateless_mode;
+        let message_processing_strategy = self.message_processing_strategy();
 
         let evaluator = async move {
             // If `local_epoch >= now_epoch`, return `NetworkHeadEvaluation::InSync`
@@ -556,7 +698,7 @@ where
                     bad_block_cache.clone(),
                     mem_pool.clone(),
                     genesis.clone(),
-                    PubsubMessageProcessingStrategy::Process,
+                    message_processing_strategy,
                     block_delay,
                     stateless_mode,
                 )
@@ -771,6 +913,7 @@ where
         let tipset_sender = self.tipset_sender.clone();
         let block_delay = self.state_manager.chain_config().block_delay_secs;
         let stateless_mode = self.stateless_mode;
+        let message_processing_strategy = self.message_processing_strategy();
         let stream_processor: ChainMuxerFuture<UnexpectedReturnKind, ChainMuxerError> = Box::pin(
             async move {
                 // If a tipset has been provided, pass it to the tipset processor
@@ -799,7 +942,7 @@ where
                         bad_block_cache.clone(),
                         mem_pool.clone(),
                         genesis.clone(),
-                        PubsubMessageProcessingStrategy::Process,
+                        message_processing_strategy,
                         block_delay,
                         stateless_mode,
                     )
@@ -881,6 +1024,11 @@ where
     type Output = ChainMuxerError;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        if !self.stateless_mode && self.state_manager.sync_config().watchdog_lag_threshold > 0 {
+            while self.watchdog_interval.poll_tick(cx).is_ready() {
+                self.check_watchdog();
+            }
+        }
         loop {
             match self.state {
                 ChainMuxerState::Idle => {
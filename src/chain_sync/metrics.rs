@@ -63,6 +63,15 @@ pub static TIPSET_RANGE_SYNC_FAILURE_TOTAL: Lazy<Counter> = Lazy::new(|| {
     );
     metric
 });
+pub static CHAIN_EXCHANGE_DEDUPED_REQUEST_TOTAL: Lazy<Counter> = Lazy::new(|| {
+    let metric = Counter::default();
+    crate::metrics::default_registry().register(
+        "chain_exchange_deduped_request_total",
+        "Total number of chain exchange requests served by an identical request in flight",
+        metric.clone(),
+    );
+    metric
+});
 pub static HEAD_EPOCH: Lazy<Gauge> = Lazy::new(|| {
     let metric = Gauge::default();
     crate::metrics::default_registry().register(
@@ -117,6 +126,33 @@ pub static FOLLOW_NETWORK_ERRORS: Lazy<Counter> = Lazy::new(|| {
     );
     metric
 });
+pub static WATCHDOG_EPOCHS_BEHIND: Lazy<Gauge> = Lazy::new(|| {
+    let metric = Gauge::default();
+    crate::metrics::default_registry().register(
+        "sync_watchdog_epochs_behind",
+        "Epochs between the head and the epoch expected from the wall clock",
+        metric.clone(),
+    );
+    metric
+});
+pub static WATCHDOG_REBOOTSTRAPS: Lazy<Counter> = Lazy::new(|| {
+    let metric = Counter::default();
+    crate::metrics::default_registry().register(
+        "sync_watchdog_rebootstraps",
+        "Total number of peer connection re-bootstraps triggered by a stuck head",
+        metric.clone(),
+    );
+    metric
+});
+pub static WATCHDOG_RESTARTS: Lazy<Counter> = Lazy::new(|| {
+    let metric = Counter::default();
+    crate::metrics::default_registry().register(
+        "sync_watchdog_restarts",
+        "Total number of sync restarts triggered by a stuck head",
+        metric.clone(),
+    );
+    metric
+});
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub struct Libp2pMessageKindLabel(&'static str);
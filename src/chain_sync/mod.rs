@@ -9,11 +9,13 @@ pub mod network_context;
 mod sync_state;
 mod tipset_syncer;
 mod validation;
+mod watchdog;
 
 pub use self::{
     bad_block_cache::BadBlockCache,
     chain_muxer::{ChainMuxer, SyncConfig},
     consensus::collect_errs,
-    sync_state::{SyncStage, SyncState},
+    sync_state::{SyncStage, SyncState, SyncStateSnapshot},
     validation::{TipsetValidationError, TipsetValidator},
+    watchdog::{WatchdogStage, WatchdogStatus},
 };
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use std::{
+    any::Any,
     convert::TryFrom,
     num::NonZeroU64,
     sync::{
@@ -11,6 +12,7 @@ use std::{
     time::{Duration, SystemTime},
 };
 
+use super::metrics;
 use crate::{
     blocks::{FullTipset, Tipset, TipsetKey},
     libp2p::{
@@ -27,12 +29,13 @@ use crate::{
         stats::Stats,
     },
 };
+use ahash::HashMap;
 use anyhow::Context as _;
 use fvm_ipld_blockstore::Blockstore;
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
 use std::future::Future;
-use tokio::sync::Semaphore;
+use tokio::sync::{watch, Semaphore};
 use tokio::task::JoinSet;
 use tracing::{debug, trace, warn};
 
@@ -46,6 +49,15 @@ static CHAIN_EXCHANGE_TIMEOUT_MILLIS: Lazy<ExponentialAdaptiveValueProvider<u64>
 /// network.
 const MAX_CONCURRENT_CHAIN_EXCHANGE_REQUESTS: usize = 2;
 
+/// Start tipset, length and options of a chain exchange request.
+type InFlightKey = (TipsetKey, NonZeroU64, u64);
+
+/// Result of a chain exchange request, set once it completes. The value is the
+/// `Vec<T>` returned by [`SyncNetworkContext::handle_chain_exchange_request`].
+type InFlightResult = Option<Result<Arc<dyn Any + Send + Sync>, String>>;
+
+type InFlightRequests = Mutex<HashMap<InFlightKey, watch::Receiver<InFlightResult>>>;
+
 /// Context used in chain sync to handle network requests.
 /// This contains the peer manager, P2P service interface, and [`Blockstore`]
 /// required to make network requests.
@@ -56,6 +68,9 @@ pub struct SyncNetworkContext<DB> {
     /// respective peers.
     peer_manager: Arc<PeerManager>,
     db: Arc<DB>,
+    /// Chain exchange requests being processed, shared with the callers
+    /// making identical requests in the meantime.
+    in_flight: Arc<InFlightRequests>,
 }
 
 impl<DB> Clone for SyncNetworkContext<DB> {
@@ -64,10 +79,24 @@ impl<DB> Clone for SyncNetworkContext<DB> {
             network_send: self.network_send.clone(),
             peer_manager: self.peer_manager.clone(),
             db: self.db.clone(),
+            in_flight: self.in_flight.clone(),
         }
     }
 }
 
+/// Removes a request from the in-flight requests once it completes or is
+/// canceled.
+struct InFlightGuard<'a> {
+    in_flight: &'a InFlightRequests,
+    key: InFlightKey,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.in_flight.lock().remove(&self.key);
+    }
+}
+
 /// Race tasks to completion while limiting the number of tasks that may execute concurrently.
 /// Once a task finishes without error, the rest of the tasks are canceled.
 struct RaceBatch<T> {
@@ -129,6 +158,7 @@ where
             network_send,
             peer_manager,
             db,
+            in_flight: Default::default(),
         }
     }
 
@@ -145,28 +175,122 @@ where
     /// Send a `chain_exchange` request for only block headers (ignore
     /// messages). If `peer_id` is `None`, requests will be sent to a set of
     /// shuffled peers.
+    ///
+    /// Peers are allowed to serve a partial range, in which case follow-up
+    /// requests are sent to the top peers for the remainder. The returned
+    /// chain is contiguous but may still be shorter than `count` if no peer
+    /// could serve the rest of it.
     pub async fn chain_exchange_headers(
         &self,
         peer_id: Option<PeerId>,
         tsk: &TipsetKey,
         count: NonZeroU64,
     ) -> Result<Vec<Arc<Tipset>>, String> {
-        self.handle_chain_exchange_request(
-            peer_id,
-            tsk,
-            count,
-            HEADERS,
-            |tipsets: &Vec<Arc<Tipset>>| validate_network_tipsets(tipsets, tsk),
-        )
-        .await
+        let mut tipsets = self
+            .handle_chain_exchange_request(
+                peer_id,
+                tsk,
+                count,
+                HEADERS,
+                |tipsets: &Vec<Arc<Tipset>>| validate_network_tipsets(tipsets, tsk),
+            )
+            .await?;
+        tipsets.truncate(count.get() as _);
+
+        while let Some(remaining) = count
+            .get()
+            .checked_sub(tipsets.len() as _)
+            .and_then(NonZeroU64::new)
+        {
+            let Some(last) = tipsets.last().filter(|ts| ts.epoch() > 0) else {
+                break;
+            };
+            let parents = last.parents().clone();
+            debug!(
+                "Partial chain_exchange_headers response, requesting {remaining} more tipsets from epoch {}",
+                last.epoch() - 1
+            );
+            // The peer that served the partial range is unlikely to have the
+            // rest of it, so the remainder is raced among the top peers.
+            match self
+                .handle_chain_exchange_request(
+                    None,
+                    &parents,
+                    remaining,
+                    HEADERS,
+                    |tipsets: &Vec<Arc<Tipset>>| validate_network_tipsets(tipsets, &parents),
+                )
+                .await
+            {
+                Ok(mut rest) => {
+                    rest.truncate(remaining.get() as _);
+                    tipsets.extend(rest);
+                }
+                Err(e) => {
+                    warn!(
+                        "Failed to fetch the remaining {remaining} of {count} tipsets, returning {} tipsets: {e}",
+                        tipsets.len()
+                    );
+                    break;
+                }
+            }
+        }
+        Ok(tipsets)
     }
+
     /// Send a `chain_exchange` request for only messages (ignore block
     /// headers). If `peer_id` is `None`, requests will be sent to a set of
     /// shuffled peers.
+    ///
+    /// `tipsets` are expected in chronological order. The returned messages
+    /// are in reverse chronological order, starting from the last tipset. Like
+    /// [`Self::chain_exchange_headers`], partial responses are stitched
+    /// together by requesting the remainder from the top peers, and fewer
+    /// messages than `tipsets` are returned if no peer can serve the rest.
     pub async fn chain_exchange_messages(
         &self,
         peer_id: Option<PeerId>,
         tipsets: &[Arc<Tipset>],
+    ) -> Result<Vec<CompactedMessages>, String> {
+        let mut messages = self.chain_exchange_messages_inner(peer_id, tipsets).await?;
+        messages.truncate(tipsets.len());
+
+        while let Some(remaining) = tipsets
+            .len()
+            .checked_sub(messages.len())
+            .filter(|&n| n > 0)
+            .and_then(|n| tipsets.get(..n))
+        {
+            match self.chain_exchange_messages_inner(None, remaining).await {
+                Ok(mut rest) if !rest.is_empty() => {
+                    rest.truncate(remaining.len());
+                    messages.extend(rest);
+                }
+                Ok(_) => {
+                    warn!(
+                        "Peers returned no messages for the remaining {} of {} tipsets",
+                        remaining.len(),
+                        tipsets.len()
+                    );
+                    break;
+                }
+                Err(e) => {
+                    warn!(
+                        "Failed to fetch messages for the remaining {} of {} tipsets: {e}",
+                        remaining.len(),
+                        tipsets.len()
+                    );
+                    break;
+                }
+            }
+        }
+        Ok(messages)
+    }
+
+    async fn chain_exchange_messages_inner(
+        &self,
+        peer_id: Option<PeerId>,
+        tipsets: &[Arc<Tipset>],
     ) -> Result<Vec<CompactedMessages>, String> {
         let head = tipsets
             .last()
@@ -229,9 +353,68 @@ where
         Ok(fts.remove(0))
     }
 
+    /// Sends a `chain_exchange` request, unless an identical request is
+    /// already in flight, in which case its result is shared.
+    async fn handle_chain_exchange_request<T, F>(
+        &self,
+        peer_id: Option<PeerId>,
+        tsk: &TipsetKey,
+        request_len: NonZeroU64,
+        options: u64,
+        validate: F,
+    ) -> Result<Vec<T>, String>
+    where
+        T: TryFrom<TipsetBundle, Error = String> + Clone + Send + Sync + 'static,
+        F: Fn(&Vec<T>) -> bool,
+    {
+        let key = (tsk.clone(), request_len, options);
+        let in_flight = {
+            let mut in_flight = self.in_flight.lock();
+            match in_flight.get(&key) {
+                Some(receiver) => Err(receiver.clone()),
+                None => {
+                    let (sender, receiver) = watch::channel(None);
+                    in_flight.insert(key.clone(), receiver);
+                    Ok(sender)
+                }
+            }
+        };
+        let sender = match in_flight {
+            Ok(sender) => sender,
+            Err(receiver) => {
+                if let Some(result) = wait_in_flight(receiver, &validate).await {
+                    metrics::CHAIN_EXCHANGE_DEDUPED_REQUEST_TOTAL.inc();
+                    return result;
+                }
+                // The request in flight was canceled, or its result doesn't
+                // suit this caller.
+                return self
+                    .fetch_chain_exchange(peer_id, tsk, request_len, options, validate)
+                    .await;
+            }
+        };
+
+        let guard = InFlightGuard {
+            in_flight: &self.in_flight,
+            key,
+        };
+        let result = self
+            .fetch_chain_exchange(peer_id, tsk, request_len, options, validate)
+            .await;
+        // No caller can wait for the request after this.
+        drop(guard);
+        if sender.receiver_count() > 0 {
+            let shared = result
+                .clone()
+                .map(|v| Arc::new(v) as Arc<dyn Any + Send + Sync>);
+            let _ = sender.send(Some(shared));
+        }
+        result
+    }
+
     /// Helper function to handle the peer retrieval if no peer supplied as well
     /// as the logging and updating of the peer info in the `PeerManager`.
-    async fn handle_chain_exchange_request<T, F>(
+    async fn fetch_chain_exchange<T, F>(
         &self,
         peer_id: Option<PeerId>,
         tsk: &TipsetKey,
@@ -469,6 +652,28 @@ where
     }
 }
 
+/// Waits for the result of an identical chain exchange request in flight.
+/// Returns `None` if that request is canceled or its result doesn't pass
+/// `validate`.
+async fn wait_in_flight<T, F>(
+    mut receiver: watch::Receiver<InFlightResult>,
+    validate: &F,
+) -> Option<Result<Vec<T>, String>>
+where
+    T: Clone + 'static,
+    F: Fn(&Vec<T>) -> bool,
+{
+    let result = receiver.wait_for(Option::is_some).await.ok()?.clone()?;
+    match result {
+        Ok(shared) => shared
+            .downcast_ref::<Vec<T>>()
+            .filter(|v| validate(v))
+            .cloned()
+            .map(Ok),
+        Err(e) => Some(Err(e)),
+    }
+}
+
 /// Validates network tipsets that are sorted by epoch in descending order with the below checks
 /// 1. The latest(first) tipset has the desired tipset key
 /// 2. The sorted tipsets are chained by their tipset keys
@@ -624,4 +829,288 @@ mod tests {
             t4.key()
         ));
     }
+
+    /// Spawns a mock network service that answers chain exchange requests with
+    /// `respond`, called with the index of the request. Returns a network
+    /// context that knows about a single peer, which is also returned.
+    fn mock_network<DB: Blockstore>(
+        db: Arc<DB>,
+        respond: impl Fn(usize, ChainExchangeRequest) -> ChainExchangeResponse + Send + 'static,
+    ) -> (SyncNetworkContext<DB>, PeerId) {
+        let (network_send, network_receiver) = flume::unbounded();
+        tokio::spawn(async move {
+            let mut requests = 0;
+            while let Ok(message) = network_receiver.recv_async().await {
+                if let NetworkMessage::ChainExchangeRequest {
+                    request,
+                    response_channel,
+                    ..
+                } = message
+                {
+                    response_channel
+                        .send(Ok(respond(requests, request)))
+                        .unwrap();
+                    requests += 1;
+                }
+            }
+        });
+
+        let peer_manager = Arc::new(PeerManager::default());
+        let peer_id = PeerId::random();
+        peer_manager.touch_peer(&peer_id);
+        (
+            SyncNetworkContext::new(network_send, peer_manager, db),
+            peer_id,
+        )
+    }
+
+    /// Serves at most `max_len` tipsets per request, like a peer with a pruned
+    /// store would.
+    fn capped_response<DB: Blockstore + Send + Sync + 'static>(
+        cs: &crate::chain::ChainStore<DB>,
+        mut request: ChainExchangeRequest,
+        max_len: u64,
+    ) -> ChainExchangeResponse {
+        use crate::libp2p::chain_exchange::{
+            make_chain_exchange_response, ChainExchangeResponseStatus,
+        };
+
+        request.request_len = request.request_len.min(max_len);
+        let mut response = make_chain_exchange_response(cs, &request);
+        response.status = ChainExchangeResponseStatus::PartialResponse;
+        response
+    }
+
+    /// Loads the `EXPORT_SR_40` snapshot and returns its 5 most recent
+    /// tipsets, in chronological order.
+    async fn export_sr_40_tipsets() -> (
+        Arc<crate::db::MemoryDB>,
+        crate::chain::ChainStore<crate::db::MemoryDB>,
+        Vec<Arc<Tipset>>,
+    ) {
+        use crate::blocks::{CachingBlockHeader, RawBlockHeader};
+        use crate::chain::ChainStore;
+        use crate::db::MemoryDB;
+        use crate::networks::ChainConfig;
+        use crate::shim::address::Address;
+
+        let db = Arc::new(MemoryDB::default());
+        let header = crate::utils::db::car_util::load_car(&db, crate::genesis::EXPORT_SR_40)
+            .await
+            .unwrap();
+        let cs = ChainStore::new(
+            db.clone(),
+            db.clone(),
+            db.clone(),
+            Arc::new(ChainConfig::default()),
+            CachingBlockHeader::new(RawBlockHeader {
+                miner_address: Address::new_id(0),
+                ..Default::default()
+            }),
+        )
+        .unwrap();
+        let head = cs
+            .chain_index
+            .load_required_tipset(&TipsetKey::from(header.roots))
+            .unwrap();
+        let mut tipsets = cs.chain_index.chain(head).take(5).collect::<Vec<_>>();
+        tipsets.reverse();
+        (db, cs, tipsets)
+    }
+
+    #[tokio::test]
+    #[allow(unused_variables)]
+    async fn chain_exchange_headers_stitches_partial_responses() {
+        use crate::blocks::{chain4u, CachingBlockHeader, Chain4U};
+        use crate::chain::ChainStore;
+        use crate::db::MemoryDB;
+        use crate::networks::ChainConfig;
+
+        let db = Arc::new(MemoryDB::default());
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            t0 @ [genesis_header]
+            -> t1 @ [first_header]
+            -> t2 @ [second_left, second_right]
+            -> t3 @ [third]
+            -> t4 @ [fourth]
+        };
+        let cs = ChainStore::new(
+            db.clone(),
+            db.clone(),
+            db.clone(),
+            Arc::new(ChainConfig::default()),
+            CachingBlockHeader::new(genesis_header.clone()),
+        )
+        .unwrap();
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let (network, peer_id) = mock_network(db, {
+            let requests = requests.clone();
+            move |_, request| {
+                requests.fetch_add(1, Ordering::Relaxed);
+                capped_response(&cs, request, 2)
+            }
+        });
+
+        let tipsets = network
+            .chain_exchange_headers(
+                Some(peer_id),
+                t4.key(),
+                NonZeroU64::new(5).expect("Infallible"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            tipsets.iter().map(|ts| ts.epoch()).collect::<Vec<_>>(),
+            [4, 3, 2, 1, 0]
+        );
+        assert!(validate_network_tipsets(&tipsets, t4.key()));
+        assert_eq!(requests.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    #[allow(unused_variables)]
+    async fn chain_exchange_headers_dedups_concurrent_requests() {
+        use crate::blocks::{chain4u, CachingBlockHeader, Chain4U};
+        use crate::chain::ChainStore;
+        use crate::db::MemoryDB;
+        use crate::libp2p::chain_exchange::make_chain_exchange_response;
+        use crate::networks::ChainConfig;
+
+        let db = Arc::new(MemoryDB::default());
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            t0 @ [genesis_header]
+            -> t1 @ [first_header]
+            -> t2 @ [second_header]
+        };
+        let cs = ChainStore::new(
+            db.clone(),
+            db.clone(),
+            db.clone(),
+            Arc::new(ChainConfig::default()),
+            CachingBlockHeader::new(genesis_header.clone()),
+        )
+        .unwrap();
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let (network, peer_id) = mock_network(db, {
+            let requests = requests.clone();
+            move |_, request| {
+                requests.fetch_add(1, Ordering::Relaxed);
+                make_chain_exchange_response(&cs, &request)
+            }
+        });
+
+        let count = NonZeroU64::new(3).expect("Infallible");
+        let (first, second) = tokio::join!(
+            network.chain_exchange_headers(Some(peer_id), t2.key(), count),
+            network.chain_exchange_headers(None, t2.key(), count),
+        );
+        let (first, second) = (first.unwrap(), second.unwrap());
+        assert_eq!(
+            first.iter().map(|ts| ts.epoch()).collect::<Vec<_>>(),
+            [2, 1, 0]
+        );
+        assert_eq!(first, second);
+        assert_eq!(requests.load(Ordering::Relaxed), 1);
+        assert!(network.in_flight.lock().is_empty());
+
+        // Requests made once the first one completed are sent again
+        network
+            .chain_exchange_headers(Some(peer_id), t2.key(), count)
+            .await
+            .unwrap();
+        assert_eq!(requests.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn chain_exchange_messages_stitches_partial_responses() {
+        let (db, cs, tipsets) = export_sr_40_tipsets().await;
+        let expected = tipsets
+            .iter()
+            .rev()
+            .map(|ts| {
+                let request = ChainExchangeRequest {
+                    start: ts.key().to_cids(),
+                    request_len: 1,
+                    options: MESSAGES,
+                };
+                capped_response(&cs, request, 1).chain[0]
+                    .messages
+                    .clone()
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let (network, peer_id) = mock_network(db, {
+            let requests = requests.clone();
+            move |_, request| {
+                requests.fetch_add(1, Ordering::Relaxed);
+                capped_response(&cs, request, 2)
+            }
+        });
+
+        let messages = network
+            .chain_exchange_messages(Some(peer_id), &tipsets)
+            .await
+            .unwrap();
+        // One message bundle per tipset, starting from the most recent one.
+        assert_eq!(messages, expected);
+        assert_eq!(requests.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn chain_exchange_messages_stops_on_failed_follow_up() {
+        use crate::libp2p::chain_exchange::ChainExchangeResponseStatus;
+
+        let (db, cs, tipsets) = export_sr_40_tipsets().await;
+        let (network, peer_id) = mock_network(db, move |i, request| match i {
+            0 => capped_response(&cs, request, 2),
+            _ => ChainExchangeResponse {
+                chain: vec![],
+                status: ChainExchangeResponseStatus::BlockNotFound,
+                message: "not found".into(),
+            },
+        });
+
+        let messages = network
+            .chain_exchange_messages(Some(peer_id), &tipsets)
+            .await
+            .unwrap();
+        assert_eq!(messages.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn chain_exchange_messages_stops_on_empty_follow_up() {
+        use crate::libp2p::chain_exchange::ChainExchangeResponseStatus;
+
+        let (db, cs, tipsets) = export_sr_40_tipsets().await;
+        let requests = Arc::new(AtomicUsize::new(0));
+        let (network, peer_id) = mock_network(db, {
+            let requests = requests.clone();
+            move |i, request| {
+                requests.fetch_add(1, Ordering::Relaxed);
+                match i {
+                    0 => capped_response(&cs, request, 3),
+                    _ => ChainExchangeResponse {
+                        chain: vec![],
+                        status: ChainExchangeResponseStatus::Success,
+                        message: "Success".into(),
+                    },
+                }
+            }
+        });
+
+        let messages = network
+            .chain_exchange_messages(Some(peer_id), &tipsets)
+            .await
+            .unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(requests.load(Ordering::Relaxed), 2);
+    }
 }
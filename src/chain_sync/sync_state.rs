@@ -3,11 +3,12 @@
 
 use std::sync::Arc;
 
-use crate::blocks::Tipset;
+use crate::blocks::{Tipset, TipsetKey};
 use crate::shim::clock::ChainEpoch;
 #[cfg(test)]
 use chrono::TimeZone;
 use chrono::{DateTime, Duration, Utc};
+use libp2p::PeerId;
 
 /// Current state of the `ChainSyncer` using the `ChainExchange` protocol.
 #[derive(PartialEq, Eq, Debug, Clone, Copy, strum::Display, strum::EnumString)]
@@ -131,6 +132,136 @@ impl SyncState {
     }
 }
 
+/// Point-in-time view of the syncer, exported to investigate sync stalls, see
+/// [`crate::chain::ChainStore::export_sync_state`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyncStateSnapshot {
+    pub heaviest_tipset: TipsetKey,
+    pub epoch: ChainEpoch,
+    /// Heaviest tipsets announced by the connected peers.
+    pub peer_heads: Vec<(PeerId, TipsetKey)>,
+    /// Number of tipsets received from the network that are waiting to be
+    /// synced.
+    pub pending_blocks: usize,
+    pub sync_target: Option<TipsetKey>,
+}
+
+mod snapshot_lotus_json {
+    use super::SyncStateSnapshot;
+    use crate::{blocks::TipsetKey, lotus_json::*, shim::clock::ChainEpoch};
+    use libp2p::PeerId;
+    use serde::{Deserialize, Serialize};
+    use serde_with::{serde_as, DisplayFromStr};
+
+    #[serde_as]
+    #[derive(Serialize, Deserialize, schemars::JsonSchema)]
+    #[schemars(rename = "PeerHead")]
+    #[serde(rename_all = "PascalCase")]
+    pub struct PeerHeadLotusJson {
+        #[schemars(with = "String")]
+        #[serde_as(as = "DisplayFromStr")]
+        peer: PeerId,
+        #[schemars(with = "LotusJson<TipsetKey>")]
+        #[serde(with = "crate::lotus_json")]
+        head: TipsetKey,
+    }
+
+    #[derive(Serialize, Deserialize, schemars::JsonSchema)]
+    #[schemars(rename = "SyncStateSnapshot")]
+    #[serde(rename_all = "PascalCase")]
+    pub struct SyncStateSnapshotLotusJson {
+        #[schemars(with = "LotusJson<TipsetKey>")]
+        #[serde(with = "crate::lotus_json")]
+        heaviest_tipset: TipsetKey,
+        epoch: ChainEpoch,
+        peer_heads: Vec<PeerHeadLotusJson>,
+        pending_blocks: usize,
+        #[schemars(with = "LotusJson<Option<TipsetKey>>")]
+        #[serde(with = "crate::lotus_json")]
+        sync_target: Option<TipsetKey>,
+    }
+
+    impl HasLotusJson for SyncStateSnapshot {
+        type LotusJson = SyncStateSnapshotLotusJson;
+
+        #[cfg(test)]
+        fn snapshots() -> Vec<(serde_json::Value, Self)> {
+            use crate::blocks::Tipset;
+            use std::str::FromStr as _;
+
+            let key = Tipset::from(crate::blocks::RawBlockHeader::default())
+                .key()
+                .clone();
+            let peer =
+                PeerId::from_str("12D3KooWENMwUF9YxvQxar7uBWJtZkA6amvK4xWmKXfSiHUo2Qq7").unwrap();
+            vec![(
+                serde_json::json!({
+                    "HeaviestTipset": key.clone().into_lotus_json(),
+                    "Epoch": 10,
+                    "PeerHeads": [{
+                        "Peer": peer.to_string(),
+                        "Head": key.clone().into_lotus_json(),
+                    }],
+                    "PendingBlocks": 2,
+                    "SyncTarget": null,
+                }),
+                SyncStateSnapshot {
+                    heaviest_tipset: key.clone(),
+                    epoch: 10,
+                    peer_heads: vec![(peer, key)],
+                    pending_blocks: 2,
+                    sync_target: None,
+                },
+            )]
+        }
+
+        fn into_lotus_json(self) -> Self::LotusJson {
+            let SyncStateSnapshot {
+                heaviest_tipset,
+                epoch,
+                peer_heads,
+                pending_blocks,
+                sync_target,
+            } = self;
+            Self::LotusJson {
+                heaviest_tipset,
+                epoch,
+                peer_heads: peer_heads
+                    .into_iter()
+                    .map(|(peer, head)| PeerHeadLotusJson { peer, head })
+                    .collect(),
+                pending_blocks,
+                sync_target,
+            }
+        }
+
+        fn from_lotus_json(lotus_json: Self::LotusJson) -> Self {
+            let Self::LotusJson {
+                heaviest_tipset,
+                epoch,
+                peer_heads,
+                pending_blocks,
+                sync_target,
+            } = lotus_json;
+            Self {
+                heaviest_tipset,
+                epoch,
+                peer_heads: peer_heads
+                    .into_iter()
+                    .map(|PeerHeadLotusJson { peer, head }| (peer, head))
+                    .collect(),
+                pending_blocks,
+                sync_target,
+            }
+        }
+    }
+
+    #[test]
+    fn snapshots() {
+        assert_all_snapshots::<SyncStateSnapshot>()
+    }
+}
+
 mod lotus_json {
     use super::SyncState;
     use crate::{blocks::Tipset, chain_sync::SyncStage, lotus_json::*};
@@ -6,18 +6,24 @@ use std::{
     convert::TryFrom,
     future::Future,
     num::NonZeroU64,
+    ops::RangeInclusive,
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
+    time::Instant,
 };
 
+use crate::beacon::IGNORE_DRAND_VAR;
+use crate::journal::JournalEvent;
 use crate::networks::Height;
+use crate::shim::actors::power;
 use crate::shim::clock::ALLOWABLE_CLOCK_DRIFT;
 use crate::shim::{
     address::Address, clock::ChainEpoch, crypto::verify_bls_aggregate, econ::BLOCK_GAS_LIMIT,
     gas::price_list_by_network_version, message::Message, state_tree::StateTree,
 };
 use crate::state_manager::{is_valid_for_sending, Error as StateManagerError, StateManager};
+use crate::utils::db::BlockstoreExt as _;
 use crate::utils::io::WithProgressRaw;
 use crate::{
     blocks::{Block, CachingBlockHeader, Error as ForestBlockError, FullTipset, Tipset, TipsetKey},
@@ -29,7 +35,7 @@ use crate::{
 };
 use crate::{
     eth::is_valid_eth_tx_for_sending,
-    message::{valid_for_block_inclusion, Message as MessageTrait},
+    message::{valid_for_block_inclusion, ChainMessage, Message as MessageTrait},
 };
 use crate::{libp2p::chain_exchange::TipsetBundle, shim::crypto::SignatureType};
 use ahash::{HashMap, HashMapExt, HashSet};
@@ -791,6 +797,9 @@ async fn sync_tipset_range<DB: Blockstore + Sync + Send + 'static>(
 
     // Call only once messages persisted
     chain_store.put_delegated_message_hashes(headers.into_iter())?;
+    for ts in parent_tipsets.iter() {
+        chain_store.put_message_tipset_index(ts)?;
+    }
 
     // At this point the head is synced and it can be set in the store as the
     // heaviest
@@ -800,6 +809,7 @@ async fn sync_tipset_range<DB: Blockstore + Sync + Send + 'static>(
         current_head.epoch(),
         proposed_head.key()
     );
+    let previous_head = chain_store.heaviest_tipset();
     if let Err(why) = chain_store.put_tipset(&proposed_head) {
         error!(
             "Putting tipset range head [EPOCH = {}, KEYS = {}] in the store failed: {}",
@@ -809,6 +819,7 @@ async fn sync_tipset_range<DB: Blockstore + Sync + Send + 'static>(
         );
         return Err(why.into());
     };
+    log_reorg(&chain_store, &previous_head);
     Ok(())
 }
 
@@ -1013,6 +1024,7 @@ async fn sync_tipset<DB: Blockstore + Sync + Send + 'static>(
     // Add the tipset to the store. The tipset will be expanded with other blocks
     // with the same [epoch, parents] before updating the heaviest Tipset in
     // the store.
+    let previous_head = chain_store.heaviest_tipset();
     if let Err(why) = chain_store.put_tipset(&proposed_head) {
         error!(
             "Putting tipset [EPOCH = {}, KEYS = {:?}] in the store failed: {}",
@@ -1022,9 +1034,34 @@ async fn sync_tipset<DB: Blockstore + Sync + Send + 'static>(
         );
         return Err(why.into());
     };
+    log_reorg(&chain_store, &previous_head);
     Ok(())
 }
 
+/// Logs the depth of the reorganization, if any, caused by the heaviest
+/// tipset switching away from `previous_head`. A head extended by a child, or
+/// replaced by a sibling such as the same tipset with an extra block, is not
+/// a reorganization.
+fn log_reorg<DB: Blockstore>(chain_store: &ChainStore<DB>, previous_head: &Arc<Tipset>) {
+    let head = chain_store.heaviest_tipset();
+    if head.key() == previous_head.key()
+        || head.parents() == previous_head.key()
+        || head.parents() == previous_head.parents()
+    {
+        return;
+    }
+    match chain_store.compute_fork_length(previous_head, &head) {
+        Ok(fork) if fork.ts1_depth > 0 => info!(
+            "Chain reorganization: reverted {} tipsets and applied {} on top of the common ancestor at epoch {}",
+            fork.ts1_depth,
+            fork.ts2_depth,
+            fork.common_ancestor.epoch()
+        ),
+        Ok(_) => {}
+        Err(e) => warn!("Failed to compute the chain reorganization depth: {e}"),
+    }
+}
+
 /// Ask peers for the [`Message`]s that these [`Tipset`]s should contain.
 /// Requests covering too many tipsets may be rejected. As of 2023-07-13,
 /// requesting for 8 tipsets works fine but requesting for 64 is flaky.
@@ -1043,6 +1080,18 @@ async fn fetch_batch<DB: Blockstore>(
     while n_missing > 0 {
         #[allow(clippy::indexing_slicing)]
         match fetch_batch_inner(&batch[..n_missing], network, db).await {
+            Ok(fetched) if fetched.is_empty() => {
+                // Count a response that makes no progress as a failure, so
+                // that the retries are bounded.
+                error = Some(TipsetRangeSyncerError::NetworkMessageQueryFailed(
+                    "no messages have been fetched".into(),
+                ));
+                if n_retry_left > 0 {
+                    n_retry_left -= 1;
+                } else {
+                    break;
+                }
+            }
             Ok(mut fetched) => {
                 fetched.extend(result);
                 result = fetched;
@@ -1059,6 +1108,14 @@ async fn fetch_batch<DB: Blockstore>(
         }
     }
 
+    if n_missing > 0 && !result.is_empty() {
+        warn!(
+            "Fetched messages for {} of {} tipsets in batch",
+            result.len(),
+            batch.len()
+        );
+    }
+
     match (result.len(), error) {
         (0, Some(e)) => Err(e),
         _ => Ok(result),
@@ -1088,30 +1145,34 @@ async fn fetch_batch_inner<DB: Blockstore>(
             .map_err(TipsetRangeSyncerError::NetworkMessageQueryFailed)?;
 
         // inflate our tipsets with the messages from the wire format
-        // Note: compacted_messages.len() can be not equal to batch.len()
-        compacted_messages
+        // Note: compacted_messages.len() can be less than batch.len() if peers
+        // could only serve part of the range, the rest is retried by the caller.
+        let mut messages = vec![];
+        let full_tipsets = compacted_messages
             .into_iter()
             .zip(batch.iter().rev())
             .rev()
-            .map(|(messages, tipset)| {
+            .map(|(compacted, tipset)| {
                 let bundle = TipsetBundle {
                     blocks: tipset.block_headers().iter().cloned().collect_vec(),
-                    messages: Some(messages),
+                    messages: Some(compacted),
                 };
 
                 let full_tipset = FullTipset::try_from(&bundle)
                     .map_err(TipsetRangeSyncerError::GeneratingTipsetFromTipsetBundle)?;
 
-                // Persist the messages in the store
                 if let Some(m) = bundle.messages {
-                    crate::chain::persist_objects(db, m.bls_msgs.iter())?;
-                    crate::chain::persist_objects(db, m.secp_msgs.iter())?;
-                } else {
-                    warn!("ChainExchange request for messages returned null messages");
+                    messages.extend(m.bls_msgs.into_iter().map(ChainMessage::Unsigned));
+                    messages.extend(m.secp_msgs.into_iter().map(ChainMessage::Signed));
                 }
                 Ok(full_tipset)
             })
-            .collect()
+            .collect::<Result<Vec<_>, TipsetRangeSyncerError>>()?;
+
+        // Persist the messages of the whole response in a single batch
+        db.bulk_put(&messages, DB::default_code())
+            .map_err(ChainStoreError::from)?;
+        Ok(full_tipsets)
     } else {
         Ok(vec![])
     }
@@ -1183,8 +1244,31 @@ async fn validate_tipset<DB: Blockstore + Send + Sync + 'static>(
         return Ok(());
     }
 
+    // Tipsets validated before a restart are not executed again as long as
+    // the state they computed is still present
+    let lite = state_manager.sync_config().lite;
+    if !lite
+        && chainstore.is_tipset_validated(full_tipset.key())
+        && chainstore
+            .blockstore()
+            .has(full_tipset.parent_state())
+            .unwrap_or_default()
+    {
+        trace!(
+            "Skipping execution of validated tipset {}",
+            full_tipset.key()
+        );
+        validate_tipset_linkage(chainstore, &full_tipset)?;
+        for block in full_tipset.blocks() {
+            chainstore.add_to_tipset_tracker(block.header());
+        }
+        return Ok(());
+    }
+
     let epoch = full_tipset.epoch();
     let full_tipset_key = full_tipset.key().clone();
+    let parent_state = *full_tipset.parent_state();
+    let start = Instant::now();
 
     let mut validations = FuturesUnordered::new();
     let blocks = full_tipset.into_blocks();
@@ -1212,6 +1296,12 @@ async fn validate_tipset<DB: Blockstore + Send + Sync + 'static>(
                     epoch,
                     why
                 );
+                crate::journal::record(JournalEvent::TipsetRejected {
+                    key: full_tipset_key.clone(),
+                    epoch,
+                    block: cid,
+                    reason: why.to_string(),
+                });
                 // Only do bad block accounting if the function was called with
                 // `is_strict` = true
                 if let InvalidBlockStrategy::Strict = invalid_block_strategy {
@@ -1227,6 +1317,34 @@ async fn validate_tipset<DB: Blockstore + Send + Sync + 'static>(
             }
         }
     }
+    if !lite {
+        chainstore.mark_tipset_as_validated(&full_tipset_key, epoch)?;
+    }
+    crate::journal::record(JournalEvent::TipsetApplied {
+        key: full_tipset_key,
+        epoch,
+        state_root: parent_state,
+        duration_ms: start.elapsed().as_millis() as u64,
+    });
+    Ok(())
+}
+
+/// Checks that the parent tipset is known and that the tipset extends it.
+fn validate_tipset_linkage<DB: Blockstore>(
+    chainstore: &ChainStore<DB>,
+    full_tipset: &FullTipset,
+) -> Result<(), TipsetRangeSyncerError> {
+    let parent = chainstore
+        .chain_index
+        .load_required_tipset(full_tipset.parents())
+        .map_err(TipsetRangeSyncerError::TipsetParentNotFound)?;
+    if parent.epoch() >= full_tipset.epoch() || parent.weight() >= full_tipset.weight() {
+        return Err(TipsetRangeSyncerError::Validation(format!(
+            "Tipset {} does not extend its parent {}",
+            full_tipset.key(),
+            parent.key()
+        )));
+    }
     Ok(())
 }
 
@@ -1292,6 +1410,14 @@ async fn validate_block<DB: Blockstore + Sync + Send + 'static>(
             )
         })?;
 
+    if state_manager.sync_config().lite {
+        validate_block_lite(&state_manager, &block, &base_tipset)
+            .await
+            .map_err(|e| (*block_cid, e))?;
+        chain_store.mark_block_as_validated(block_cid);
+        return Ok(block);
+    }
+
     // Retrieve lookback tipset for validation
     let lookback_state = ChainStore::get_lookback_tipset_for_round(
         state_manager.chain_store().chain_index.clone(),
@@ -1428,6 +1554,128 @@ async fn validate_block<DB: Blockstore + Sync + Send + 'static>(
     Ok(block)
 }
 
+/// Validates a block in lite mode, relative to the parent tipset.
+///
+/// This includes:
+/// * the base fee
+/// * beacon entries
+/// * parent weight linkage
+/// * the block signature, against the worker key from the lookback state, or
+///   from the most recent available state if the lookback state was never
+///   computed
+///
+/// NB: Messages are not executed, so the state root, the receipt root and the
+/// election proofs are not verified.
+async fn validate_block_lite<DB: Blockstore + Sync + Send + 'static>(
+    state_manager: &Arc<StateManager<DB>>,
+    block: &Arc<Block>,
+    base_tipset: &Arc<Tipset>,
+) -> Result<(), TipsetRangeSyncerError> {
+    let header = block.header();
+
+    let smoke_height = state_manager.chain_config().epoch(Height::Smoke);
+    let base_fee =
+        crate::chain::compute_base_fee(state_manager.blockstore(), base_tipset, smoke_height)
+            .map_err(|e| {
+                TipsetRangeSyncerError::Validation(format!("Could not compute base fee: {e}"))
+            })?;
+    if base_fee != header.parent_base_fee {
+        return Err(TipsetRangeSyncerError::Validation(format!(
+            "base fee doesn't match: {} (header), {base_fee} (computed)",
+            header.parent_base_fee
+        )));
+    }
+
+    if std::env::var(IGNORE_DRAND_VAR) != Ok("1".to_owned()) {
+        let prev_beacon = state_manager
+            .chain_store()
+            .chain_index
+            .latest_beacon_entry(base_tipset.clone())?;
+        header
+            .validate_block_drand(
+                state_manager.get_network_version(header.epoch),
+                state_manager.beacon_schedule(),
+                base_tipset.epoch(),
+                &prev_beacon,
+                (!state_manager.sync_config().distrust_beacon_cache)
+                    .then(|| state_manager.chain_store().settings())
+                    .as_deref(),
+            )
+            .map_err(|e| TipsetRangeSyncerError::Validation(e.to_string()))?;
+    }
+
+    let has_state = |state_root: &Cid| {
+        state_manager
+            .blockstore()
+            .has(state_root)
+            .unwrap_or_default()
+    };
+    let (_, lookback_state) = ChainStore::get_lookback_tipset_for_round(
+        state_manager.chain_store().chain_index.clone(),
+        state_manager.chain_config().clone(),
+        base_tipset.clone(),
+        header.epoch,
+    )?;
+    let state_root = if has_state(&lookback_state) {
+        lookback_state
+    } else {
+        state_manager.lite_state_anchor(base_tipset)?
+    };
+    let work_addr = state_manager.get_miner_work_addr(state_root, &header.miner_address)?;
+
+    // The weight is computed from the network power in the parent state when
+    // available, and in the most recent state otherwise
+    let (power_state_root, log2_power_tolerance) = if has_state(base_tipset.parent_state()) {
+        (*base_tipset.parent_state(), 0)
+    } else {
+        (state_manager.lite_state_anchor(base_tipset)?, 1)
+    };
+    let power_state: power::State = state_manager
+        .get_state_tree(&power_state_root)
+        .and_then(|state_tree| state_tree.get_actor_state())
+        .map_err(|e| TipsetRangeSyncerError::Calculation(format!("Error loading power: {e}")))?;
+    let log2_power =
+        fil_cns::log2_power(&power_state.into_total_quality_adj_power()).map_err(|e| {
+            TipsetRangeSyncerError::Calculation(format!("Error calculating weight: {e}"))
+        })?;
+    validate_header_lite(
+        header,
+        base_tipset,
+        &work_addr,
+        log2_power.saturating_sub(log2_power_tolerance)..=log2_power + log2_power_tolerance,
+    )
+}
+
+/// Header checks against the miner worker key and the network power: the
+/// block signature and the parent weight. The network power only enters the
+/// weight through its integer base-2 logarithm, which is given as a range
+/// when the parent state is not available in lite mode and the power is read
+/// from an older state: the weight is then checked to match one of the
+/// logarithms, which allows for the power to cross a power of two since.
+fn validate_header_lite(
+    header: &CachingBlockHeader,
+    base_tipset: &Tipset,
+    work_addr: &Address,
+    log2_powers: RangeInclusive<u64>,
+) -> Result<(), TipsetRangeSyncerError> {
+    header.verify_signature_against(work_addr)?;
+    let mut calc_weights = vec![];
+    for log2_power in log2_powers {
+        let calc_weight = fil_cns::weight_for_log2_power(base_tipset, log2_power).map_err(|e| {
+            TipsetRangeSyncerError::Calculation(format!("Error calculating weight: {e}"))
+        })?;
+        if calc_weight == header.weight {
+            return Ok(());
+        }
+        calc_weights.push(calc_weight.to_string());
+    }
+    Err(TipsetRangeSyncerError::Validation(format!(
+        "Parent weight doesn't match: {} (header), {} (computed)",
+        header.weight,
+        calc_weights.join(" or ")
+    )))
+}
+
 /// Validate messages in a full block, relative to the parent tipset.
 ///
 /// This includes:
@@ -1639,6 +1887,7 @@ mod test {
     use crate::blocks::RawBlockHeader;
     use crate::blocks::VRFProof;
     use crate::blocks::{CachingBlockHeader, ElectionProof, Ticket, Tipset};
+    use crate::key_management::{KeyStore, KeyStoreConfig, Wallet};
     use crate::shim::address::Address;
     use cid::Cid;
     use num_bigint::BigInt;
@@ -1691,4 +1940,137 @@ mod test {
         assert_eq!(ts, ts3);
         assert_eq!(ts.weight(), &BigInt::from(10));
     }
+
+    #[tokio::test]
+    #[allow(unused_variables)]
+    async fn test_validated_tipset_is_not_executed() {
+        use crate::blocks::{chain4u, Chain4U, HeaderBuilder};
+        use crate::chain_sync::SyncConfig;
+        use crate::db::MemoryDB;
+        use crate::networks::ChainConfig;
+        use crate::utils::db::CborStoreExt as _;
+
+        let db = Arc::new(MemoryDB::default());
+        let state_root = db.put_cbor_default(&"state").unwrap();
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            t0 @ [genesis]
+            -> t1 @ [b1 = HeaderBuilder::new().with_weight(10.into()).with_state_root(state_root)]
+        };
+        let chain_config = Arc::new(ChainConfig::default());
+        let new_state_manager = || {
+            let cs = ChainStore::new(
+                db.clone(),
+                db.clone(),
+                db.clone(),
+                chain_config.clone(),
+                CachingBlockHeader::new(genesis.clone()),
+            )
+            .unwrap();
+            Arc::new(
+                StateManager::new(
+                    Arc::new(cs),
+                    chain_config.clone(),
+                    Arc::new(SyncConfig::default()),
+                )
+                .unwrap(),
+            )
+        };
+        let genesis_tipset = Tipset::from(CachingBlockHeader::new(genesis.clone()));
+        let full_tipset = FullTipset::new([Block {
+            header: CachingBlockHeader::new(b1.clone()),
+            bls_messages: vec![],
+            secp_messages: vec![],
+        }])
+        .unwrap();
+        let validate = |state_manager: Arc<StateManager<MemoryDB>>| {
+            let full_tipset = full_tipset.clone();
+            let genesis_tipset = genesis_tipset.clone();
+            async move {
+                validate_tipset(
+                    state_manager.clone(),
+                    state_manager.chain_store(),
+                    &BadBlockCache::default(),
+                    full_tipset,
+                    &genesis_tipset,
+                    InvalidBlockStrategy::Forgiving,
+                )
+                .await
+            }
+        };
+
+        // The block is unsigned and carries no election proof, so full
+        // validation rejects it
+        let state_manager = new_state_manager();
+        assert!(validate(state_manager.clone()).await.is_err());
+        state_manager
+            .chain_store()
+            .mark_tipset_as_validated(full_tipset.key(), full_tipset.epoch())
+            .unwrap();
+        drop(state_manager);
+
+        // After a restart, the marked tipset is accepted without executing it
+        let state_manager = new_state_manager();
+        validate(state_manager.clone()).await.unwrap();
+
+        // Unless its header linkage is broken
+        let unlinked = FullTipset::new([Block {
+            header: CachingBlockHeader::new(RawBlockHeader {
+                weight: 0.into(),
+                ..b1.clone()
+            }),
+            bls_messages: vec![],
+            secp_messages: vec![],
+        }])
+        .unwrap();
+        state_manager
+            .chain_store()
+            .mark_tipset_as_validated(unlinked.key(), unlinked.epoch())
+            .unwrap();
+        assert!(matches!(
+            validate_tipset_linkage(state_manager.chain_store(), &unlinked),
+            Err(TipsetRangeSyncerError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_header_lite() {
+        let mut wallet = Wallet::new(KeyStore::new(KeyStoreConfig::Memory).unwrap());
+        let worker = wallet.generate_addr(SignatureType::Bls).unwrap();
+        let other = wallet.generate_addr(SignatureType::Bls).unwrap();
+        let parent = Tipset::from(mock_block(1000, 10, 1));
+        let sign = |wallet: &mut Wallet, signer: &Address, weight: BigInt| {
+            let mut header = RawBlockHeader {
+                parents: parent.key().clone(),
+                weight,
+                ..mock_block(1000, 0, 2).into_raw()
+            };
+            header.signature = Some(wallet.sign(signer, &header.signing_bytes()).unwrap());
+            CachingBlockHeader::new(header)
+        };
+        let weight = |log2_power| fil_cns::weight_for_log2_power(&parent, log2_power).unwrap();
+
+        let valid = sign(&mut wallet, &worker, weight(40));
+        validate_header_lite(&valid, &parent, &worker, 40..=40).unwrap();
+        // Power read from an older state, off by one power of two
+        validate_header_lite(&valid, &parent, &worker, 39..=41).unwrap();
+
+        let bad_signature = sign(&mut wallet, &other, weight(40));
+        assert!(matches!(
+            validate_header_lite(&bad_signature, &parent, &worker, 40..=40),
+            Err(TipsetRangeSyncerError::BlockError(
+                ForestBlockError::InvalidSignature(_)
+            ))
+        ));
+
+        // Greater than the parent weight, but not derived from the power
+        for bad_weight in [weight(40) + 1, weight(42)] {
+            let bad_weight = sign(&mut wallet, &worker, bad_weight);
+            assert!(matches!(
+                validate_header_lite(&bad_weight, &parent, &worker, 39..=41),
+                Err(TipsetRangeSyncerError::Validation(_))
+            ));
+        }
+    }
 }
@@ -130,7 +130,16 @@ impl TipsetValidator<'_> {
             .iter()
             .map(Cid::from_cbor_blake2b256)
             .collect::<Result<Vec<Cid>, fvm_ipld_encoding::Error>>()?;
+        Self::compute_msg_root_from_cids(blockstore, bls_cids, secp_cids)
+    }
 
+    /// Computes the message root from the CIDs of the messages, as referenced
+    /// e.g. by a [`GossipBlock`](crate::blocks::GossipBlock).
+    pub fn compute_msg_root_from_cids<DB: Blockstore>(
+        blockstore: &DB,
+        bls_cids: Vec<Cid>,
+        secp_cids: Vec<Cid>,
+    ) -> Result<Cid, TipsetValidationError> {
         // Generate Amt and batch set message values
         let bls_message_root = Amt::new_from_iter(blockstore, bls_cids)?;
         let secp_message_root = Amt::new_from_iter(blockstore, secp_cids)?;
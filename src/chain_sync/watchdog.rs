@@ -0,0 +1,274 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Detects a node that stopped following the chain without failing, i.e. whose
+//! head no longer advances while lagging behind the epoch expected from the
+//! wall clock, and escalates recovery actions until it follows again.
+
+use crate::lotus_json::HasLotusJson;
+use crate::networks::calculate_expected_epoch;
+use crate::shim::clock::{ChainEpoch, ChainEpochDelta};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Recovery step requested by the [`SyncWatchdog`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchdogAction {
+    /// Log diagnostics and reconnect to the bootstrap peers and the DHT.
+    Rebootstrap,
+    /// Restart the sync from the current head, away from the peers stuck with
+    /// it.
+    RestartSync,
+}
+
+/// Escalation stage of the [`SyncWatchdog`].
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, strum::Display,
+)]
+pub enum WatchdogStage {
+    /// The head is within the lag threshold.
+    #[default]
+    Healthy,
+    /// The head lags but still advances, e.g. while catching up.
+    Lagging,
+    /// The head stalled and the peer connections were re-bootstrapped.
+    Rebootstrapped,
+    /// The head kept stalling and the sync was restarted.
+    Restarted,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct WatchdogStatus {
+    pub stage: WatchdogStage,
+    /// Epochs between the head and the epoch expected from the wall clock
+    pub epochs_behind: ChainEpochDelta,
+    /// Number of sync restarts since the node started
+    pub restarts: u64,
+}
+
+impl HasLotusJson for WatchdogStatus {
+    type LotusJson = Self;
+
+    #[cfg(test)]
+    fn snapshots() -> Vec<(serde_json::Value, Self)> {
+        vec![(
+            serde_json::json!({"Stage": "Restarted", "EpochsBehind": 42, "Restarts": 2}),
+            WatchdogStatus {
+                stage: WatchdogStage::Restarted,
+                epochs_behind: 42,
+                restarts: 2,
+            },
+        )]
+    }
+
+    fn into_lotus_json(self) -> Self::LotusJson {
+        self
+    }
+
+    fn from_lotus_json(lotus_json: Self::LotusJson) -> Self {
+        lotus_json
+    }
+}
+
+/// State machine deciding when to recover from a stuck sync. It is driven by
+/// [`SyncWatchdog::check`] with the current head epoch and time, so that it
+/// does not depend on the system clock.
+pub struct SyncWatchdog {
+    genesis_timestamp: u64,
+    block_delay_secs: u32,
+    lag_threshold: ChainEpochDelta,
+    /// Highest head epoch seen, and the time it was first seen
+    last_advance: Option<(ChainEpoch, u64)>,
+    /// Time of the last recovery action
+    last_action: Option<u64>,
+    status: WatchdogStatus,
+}
+
+impl SyncWatchdog {
+    /// The head is considered stuck once it lags more than `lag_threshold`
+    /// epochs and has not advanced for as long as it takes to produce them.
+    pub fn new(
+        genesis_timestamp: u64,
+        block_delay_secs: u32,
+        lag_threshold: ChainEpochDelta,
+    ) -> Self {
+        Self {
+            genesis_timestamp,
+            block_delay_secs,
+            lag_threshold,
+            last_advance: None,
+            last_action: None,
+            status: WatchdogStatus::default(),
+        }
+    }
+
+    pub fn status(&self) -> &WatchdogStatus {
+        &self.status
+    }
+
+    /// Time the head may lag without advancing before each recovery action.
+    pub fn stall_period(&self) -> u64 {
+        self.lag_threshold as u64 * self.block_delay_secs as u64
+    }
+
+    /// Updates the status with the head epoch at `now`, a Unix timestamp, and
+    /// returns the recovery action to take, if any. Actions escalate from
+    /// re-bootstrapping to restarting the sync, and are spaced by the stall
+    /// period so that each one has time to take effect.
+    pub fn check(&mut self, head_epoch: ChainEpoch, now: u64) -> Option<WatchdogAction> {
+        let expected_epoch =
+            calculate_expected_epoch(now, self.genesis_timestamp, self.block_delay_secs) as i64;
+        self.status.epochs_behind = (expected_epoch - head_epoch).max(0);
+
+        let advanced = self
+            .last_advance
+            .is_none_or(|(last_epoch, _)| head_epoch > last_epoch);
+        if advanced {
+            self.last_advance = Some((head_epoch, now));
+            self.last_action = None;
+        }
+        if self.status.epochs_behind <= self.lag_threshold {
+            self.status.stage = WatchdogStage::Healthy;
+            return None;
+        }
+
+        let stall_period = self.stall_period();
+        let (_, advanced_at) = self.last_advance.unwrap_or((head_epoch, now));
+        let idle_since = self.last_action.unwrap_or(advanced_at);
+        if advanced || now.saturating_sub(idle_since) < stall_period {
+            if self.status.stage == WatchdogStage::Healthy || advanced {
+                self.status.stage = WatchdogStage::Lagging;
+            }
+            return None;
+        }
+
+        self.last_action = Some(now);
+        match self.status.stage {
+            WatchdogStage::Healthy | WatchdogStage::Lagging => {
+                self.status.stage = WatchdogStage::Rebootstrapped;
+                Some(WatchdogAction::Rebootstrap)
+            }
+            WatchdogStage::Rebootstrapped | WatchdogStage::Restarted => {
+                self.status.stage = WatchdogStage::Restarted;
+                self.status.restarts += 1;
+                Some(WatchdogAction::RestartSync)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GENESIS_TIMESTAMP: u64 = 1_000_000;
+    const BLOCK_DELAY_SECS: u32 = 30;
+    const LAG_THRESHOLD: ChainEpochDelta = 10;
+    const STALL_PERIOD: u64 = LAG_THRESHOLD as u64 * BLOCK_DELAY_SECS as u64;
+
+    /// Timestamp at which `epoch` is expected.
+    fn time_of(epoch: ChainEpoch) -> u64 {
+        GENESIS_TIMESTAMP + epoch as u64 * BLOCK_DELAY_SECS as u64
+    }
+
+    fn watchdog() -> SyncWatchdog {
+        SyncWatchdog::new(GENESIS_TIMESTAMP, BLOCK_DELAY_SECS, LAG_THRESHOLD)
+    }
+
+    #[test]
+    fn following_head_is_healthy() {
+        let mut watchdog = watchdog();
+        for epoch in 100..200 {
+            assert_eq!(watchdog.check(epoch, time_of(epoch) + 5), None);
+            assert_eq!(watchdog.status().stage, WatchdogStage::Healthy);
+            assert_eq!(watchdog.status().epochs_behind, 0);
+        }
+        // Null rounds within the threshold are fine
+        assert_eq!(watchdog.check(199, time_of(199 + LAG_THRESHOLD)), None);
+        assert_eq!(watchdog.status().epochs_behind, LAG_THRESHOLD);
+        assert_eq!(watchdog.status().stage, WatchdogStage::Healthy);
+    }
+
+    #[test]
+    fn catching_up_is_lagging() {
+        let mut watchdog = watchdog();
+        let now = time_of(1000);
+        // The head advances by a few epochs between checks
+        for (i, epoch) in (100..1000).step_by(50).enumerate() {
+            let now = now + i as u64 * STALL_PERIOD * 2;
+            assert_eq!(watchdog.check(epoch, now), None);
+            assert_eq!(watchdog.status().stage, WatchdogStage::Lagging);
+        }
+        assert_eq!(watchdog.status().restarts, 0);
+    }
+
+    #[test]
+    fn stuck_head_escalates() {
+        let mut watchdog = watchdog();
+        let head = 100;
+        assert_eq!(watchdog.check(head, time_of(head)), None);
+        assert_eq!(watchdog.check(head, time_of(head + LAG_THRESHOLD)), None);
+        assert_eq!(watchdog.status().stage, WatchdogStage::Healthy);
+
+        // Lagging more than the threshold without advancing for as long
+        let stalled = time_of(head + LAG_THRESHOLD + 1);
+        assert_eq!(
+            watchdog.check(head, stalled),
+            Some(WatchdogAction::Rebootstrap)
+        );
+        assert_eq!(watchdog.status().stage, WatchdogStage::Rebootstrapped);
+        assert_eq!(watchdog.status().epochs_behind, LAG_THRESHOLD + 1);
+
+        // The next action waits for another stall period
+        assert_eq!(watchdog.check(head, stalled + STALL_PERIOD - 1), None);
+        assert_eq!(watchdog.status().stage, WatchdogStage::Rebootstrapped);
+        assert_eq!(
+            watchdog.check(head, stalled + STALL_PERIOD),
+            Some(WatchdogAction::RestartSync)
+        );
+        assert_eq!(watchdog.status().stage, WatchdogStage::Restarted);
+        assert_eq!(watchdog.status().restarts, 1);
+
+        // Restarts repeat while the head stays stuck
+        assert_eq!(
+            watchdog.check(head, stalled + 2 * STALL_PERIOD),
+            Some(WatchdogAction::RestartSync)
+        );
+        assert_eq!(watchdog.status().restarts, 2);
+    }
+
+    #[test]
+    fn advancing_head_resets_escalation() {
+        let mut watchdog = watchdog();
+        let head = 100;
+        watchdog.check(head, time_of(head));
+        let stalled = time_of(head + LAG_THRESHOLD + 5);
+        assert_eq!(
+            watchdog.check(head, stalled),
+            Some(WatchdogAction::Rebootstrap)
+        );
+
+        // The head moves again after the re-bootstrap, but is still behind
+        assert_eq!(watchdog.check(head + 1, stalled + 1), None);
+        assert_eq!(watchdog.status().stage, WatchdogStage::Lagging);
+
+        // Stalling again starts over with a re-bootstrap
+        assert_eq!(
+            watchdog.check(head + 1, stalled + 1 + STALL_PERIOD),
+            Some(WatchdogAction::Rebootstrap)
+        );
+
+        // Catching up to the network makes the node healthy again
+        let now = stalled + 2 + STALL_PERIOD;
+        let expected = (now - GENESIS_TIMESTAMP) as i64 / BLOCK_DELAY_SECS as i64;
+        assert_eq!(watchdog.check(expected, now), None);
+        assert_eq!(watchdog.status().stage, WatchdogStage::Healthy);
+        assert_eq!(watchdog.status().restarts, 0);
+    }
+
+    #[test]
+    fn snapshots() {
+        crate::lotus_json::assert_all_snapshots::<WatchdogStatus>();
+    }
+}
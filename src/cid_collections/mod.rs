@@ -2,10 +2,12 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 pub mod hash_map;
 pub mod hash_set;
+mod sharded_hash_set;
 mod small_cid_vec;
 pub use hash_map::CidHashMap;
 pub use hash_set::CidHashSet;
 use imp::{CidV1DagCborBlake2b256, Uncompactable};
+pub use sharded_hash_set::ShardedCidHashSet;
 pub use small_cid_vec::SmallCidNonEmptyVec;
 
 /// The core primitive for saving space in this module.
@@ -0,0 +1,100 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::*;
+use cid::Cid;
+use parking_lot::Mutex;
+
+/// A [`CidHashSet`] split into shards, each behind its own lock, so that the
+/// workers of a parallel graph walk rarely contend on insertions.
+#[derive(Debug)]
+pub struct ShardedCidHashSet {
+    shards: Box<[Mutex<CidHashSet>]>,
+}
+
+impl ShardedCidHashSet {
+    /// One shard per value of the last byte of the digests.
+    const SHARDS: usize = 256;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a value to the set.
+    ///
+    /// Returns whether the value was newly inserted.
+    pub fn insert(&self, cid: Cid) -> bool {
+        self.shard(&cid).lock().insert(cid)
+    }
+
+    /// Returns `true` if the set contains a `Cid`.
+    pub fn contains(&self, cid: &Cid) -> bool {
+        self.shard(cid).lock().contains(cid)
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| shard.lock().len()).sum()
+    }
+
+    /// Returns `true` if the set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(|shard| shard.lock().is_empty())
+    }
+
+    /// Moves the elements out, leaving the set empty.
+    pub fn take(&self) -> CidHashSet {
+        let mut set = CidHashSet::new();
+        for shard in self.shards.iter() {
+            set.extend(std::mem::take(&mut *shard.lock()));
+        }
+        set
+    }
+
+    fn shard(&self, cid: &Cid) -> &Mutex<CidHashSet> {
+        // The digests are uniformly distributed
+        let byte = cid.hash().digest().last().copied().unwrap_or_default();
+        &self.shards[usize::from(byte) % Self::SHARDS]
+    }
+}
+
+impl Default for ShardedCidHashSet {
+    fn default() -> Self {
+        Self {
+            shards: (0..Self::SHARDS).map(|_| Mutex::default()).collect(),
+        }
+    }
+}
+
+impl From<CidHashSet> for ShardedCidHashSet {
+    fn from(set: CidHashSet) -> Self {
+        let this = Self::new();
+        for cid in set {
+            this.insert(cid);
+        }
+        this
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    #[quickcheck]
+    fn sharded_cid_hash_set(cids: Vec<Cid>) {
+        let expected = CidHashSet::from_iter(cids.iter().copied());
+        let sharded = ShardedCidHashSet::new();
+        for cid in &cids {
+            let is_new = !sharded.contains(cid);
+            assert_eq!(sharded.insert(*cid), is_new);
+        }
+        assert_eq!(sharded.len(), expected.len());
+        assert_eq!(sharded.is_empty(), expected.is_empty());
+        assert!(cids.iter().all(|cid| sharded.contains(cid)));
+
+        assert_eq!(sharded.take(), expected);
+        assert!(sharded.is_empty());
+        assert_eq!(ShardedCidHashSet::from(expected.clone()).take(), expected);
+    }
+}
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use super::*;
+use crate::utils::cache::EstimateSize;
 use cid::Cid;
 use nunny::Vec as NonEmpty;
 use serde::{Deserialize, Serialize};
@@ -45,6 +46,12 @@ impl SmallCidNonEmptyVec {
     }
 }
 
+impl EstimateSize for SmallCidNonEmptyVec {
+    fn estimated_size(&self) -> usize {
+        std::mem::size_of::<Self>() + self.len() * std::mem::size_of::<SmallCid>()
+    }
+}
+
 impl<'a> IntoIterator for &'a SmallCidNonEmptyVec {
     type Item = Cid;
 
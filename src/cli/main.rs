@@ -49,6 +49,7 @@ where
                 Subcommand::Shutdown(cmd) => cmd.run(client).await,
                 Subcommand::Healthcheck(cmd) => cmd.run(client).await,
                 Subcommand::F3(cmd) => cmd.run(client).await,
+                Subcommand::Debug(cmd) => cmd.run(client).await,
             }
         })
 }
@@ -1,14 +1,30 @@
 // Copyright 2019-2025 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use crate::blocks::{Tipset, TipsetKey};
+use crate::blocks::{CachingBlockHeader, Tipset, TipsetKey};
+use crate::chain::align_block_receipts;
+use crate::interpreter::detect_consensus_fault;
 use crate::lotus_json::HasLotusJson;
 use crate::message::ChainMessage;
-use crate::rpc::{self, prelude::*};
-use anyhow::{bail, ensure};
+use crate::networks::{ChainConfig, NetworkChain};
+use crate::rpc::chain::ApiReceipt;
+use crate::rpc::state::ChainNetworkParams;
+use crate::rpc::{self, prelude::*, registry};
+use crate::shim::actors::miner;
+use crate::shim::{
+    address::{Address, StrictAddress},
+    message::Message,
+};
+use ahash::HashMap;
+use anyhow::{bail, ensure, Context as _};
 use cid::Cid;
 use clap::Subcommand;
+use fil_actor_miner_state::v16::ReportConsensusFaultParams;
+use fvm_ipld_encoding::RawBytes;
+use human_repr::HumanCount as _;
+use itertools::Itertools as _;
 use nunny::Vec as NonEmpty;
+use serde::Serialize;
 
 use super::{print_pretty_lotus_json, print_rpc_res_cids};
 
@@ -23,6 +39,15 @@ pub enum ChainCommands {
     /// Prints out the genesis tipset
     Genesis,
 
+    /// Decodes the block specified by the given CID: its header, its messages
+    /// and, once the tipset containing it has been executed, their receipts
+    InspectBlock {
+        cid: Cid,
+        /// Print the block and its messages as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
     /// Prints out the canonical head of the chain
     Head {
         /// Print the first `n` tipsets from the head (inclusive).
@@ -38,6 +63,14 @@ pub enum ChainCommands {
         cid: Cid,
     },
 
+    /// Prints out the parameters of the network: genesis, block timing,
+    /// network upgrades and consensus policy
+    Params {
+        /// Print the parameters as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
     /// Reads and prints out IPLD nodes referenced by the specified CID from
     /// chain block store and returns raw bytes
     ReadObj {
@@ -45,6 +78,25 @@ pub enum ChainCommands {
         cid: Cid,
     },
 
+    /// Reports a consensus fault of a miner proven by the given blocks, with a
+    /// `ReportConsensusFault` message sent to the miner actor
+    ReportFault {
+        /// Block of the miner, not of a higher epoch than the second one
+        cid1: Cid,
+        cid2: Cid,
+        /// Sibling of the first block that the second one was mined on, for a
+        /// parent grinding fault
+        #[arg(long)]
+        extra: Option<Cid>,
+        /// The miner that mined the blocks
+        #[arg(long)]
+        miner: StrictAddress,
+        /// Account to send the report from, the default wallet address if
+        /// omitted
+        #[arg(long)]
+        from: Option<StrictAddress>,
+    },
+
     /// Manually set the head to the given tipset. This invalidates blocks
     /// between the desired head and the new head
     SetHead {
@@ -68,6 +120,15 @@ impl ChainCommands {
                 print_pretty_lotus_json(ChainGetBlock::call(&client, (cid,)).await?)
             }
             Self::Genesis => print_pretty_lotus_json(ChainGetGenesis::call(&client, ()).await?),
+            Self::InspectBlock { cid, json } => {
+                let block = inspect_block(&client, cid).await?;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&block)?);
+                } else {
+                    print_inspected_block(&block);
+                }
+                Ok(())
+            }
             Self::Head { tipsets } => print_chain_head(&client, tipsets).await,
             Self::Message { cid } => {
                 let bytes = ChainReadObj::call(&client, (cid,)).await?;
@@ -83,11 +144,37 @@ impl ChainCommands {
                     }
                 }
             }
+            Self::Params { json } => {
+                let params = ForestStateNetworkParams::call(&client, ()).await?;
+                if json {
+                    print_pretty_lotus_json(params)
+                } else {
+                    print_network_params(&params);
+                    Ok(())
+                }
+            }
             Self::ReadObj { cid } => {
                 let bytes = ChainReadObj::call(&client, (cid,)).await?;
                 println!("{}", hex::encode(bytes));
                 Ok(())
             }
+            Self::ReportFault {
+                cid1,
+                cid2,
+                extra,
+                miner,
+                from,
+            } => {
+                let cid = report_consensus_fault(
+                    &client,
+                    (cid1, cid2, extra),
+                    miner.into(),
+                    from.map(Into::into),
+                )
+                .await?;
+                println!("{cid}");
+                Ok(())
+            }
             Self::SetHead {
                 cids,
                 epoch: Some(epoch),
@@ -118,6 +205,261 @@ impl ChainCommands {
     }
 }
 
+/// A block with its messages, as printed by `forest-cli chain inspect-block`.
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct InspectedBlock {
+    #[serde(with = "crate::lotus_json")]
+    cid: Cid,
+    #[serde(with = "crate::lotus_json")]
+    header: CachingBlockHeader,
+    /// Whether the tipset containing the block has been executed, i.e. whether
+    /// receipts are available.
+    executed: bool,
+    messages: Vec<InspectedMessage>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct InspectedMessage {
+    #[serde(with = "crate::lotus_json")]
+    cid: Cid,
+    signed: bool,
+    #[serde(with = "crate::lotus_json")]
+    message: Message,
+    /// Resolved from the code of the receiving actor, if it is known.
+    method_name: Option<&'static str>,
+    /// Missing for messages that were not executed, e.g. because another
+    /// block of the tipset included them first with a different nonce.
+    receipt: Option<ApiReceipt>,
+}
+
+async fn inspect_block(client: &rpc::Client, cid: Cid) -> anyhow::Result<InspectedBlock> {
+    let header = ChainGetBlock::call(client, (cid,)).await?;
+    let block_messages = ChainGetBlockMessages::call(client, (cid,)).await?;
+    let executed = parent_receipts(client, cid, &header).await?;
+    let receipts = match &executed {
+        Some((tipset_messages, receipts)) => {
+            align_block_receipts(&block_messages.cids, tipset_messages, receipts)
+        }
+        None => vec![None; block_messages.cids.len()],
+    };
+
+    let messages = block_messages
+        .bls_msg
+        .into_iter()
+        .map(|message| (false, message))
+        .chain(
+            block_messages
+                .secp_msg
+                .into_iter()
+                .map(|message| (true, message.message)),
+        );
+    // Actor codes are looked up in the parent state, which the messages of the
+    // block are applied on top of.
+    let mut codes: HashMap<Address, Option<Cid>> = HashMap::default();
+    let mut inspected = Vec::with_capacity(block_messages.cids.len());
+    for ((&cid, (signed, message)), receipt) in
+        block_messages.cids.iter().zip(messages).zip(receipts)
+    {
+        if !codes.contains_key(&message.to) {
+            let actor = StateGetActor::call(client, (message.to, (&header.parents).into()))
+                .await
+                .ok()
+                .flatten();
+            codes.insert(message.to, actor.map(|actor| actor.code));
+        }
+        let method_name = codes[&message.to]
+            .and_then(|code| registry::method_name(&code, message.method_num).ok());
+        inspected.push(InspectedMessage {
+            cid,
+            signed,
+            message,
+            method_name,
+            receipt: receipt.cloned(),
+        });
+    }
+
+    Ok(InspectedBlock {
+        cid,
+        header,
+        executed: executed.is_some(),
+        messages: inspected,
+    })
+}
+
+/// Returns the messages executed in the tipset containing the block and their
+/// receipts, or [`None`] if no child of that tipset is on the canonical chain
+/// yet.
+async fn parent_receipts(
+    client: &rpc::Client,
+    cid: Cid,
+    header: &CachingBlockHeader,
+) -> anyhow::Result<Option<(Vec<Cid>, Vec<ApiReceipt>)>> {
+    let head = ChainHead::call(client, ()).await?;
+    if head.epoch() <= header.epoch {
+        return Ok(None);
+    }
+    let child =
+        ChainGetTipSetAfterHeight::call(client, (header.epoch + 1, head.key().into())).await?;
+    if !child.parents().contains(cid) {
+        return Ok(None);
+    }
+    let child_block = *child.block_headers().first().cid();
+    let messages = ChainGetParentMessages::call(client, (child_block,)).await?;
+    let receipts = ChainGetParentReceipts::call(client, (child_block,)).await?;
+    Ok(Some((
+        messages.into_iter().map(|message| message.cid).collect(),
+        receipts,
+    )))
+}
+
+fn print_network_params(params: &ChainNetworkParams) {
+    let policy = &params.policy;
+    println!("Network:                    {}", params.network_name);
+    println!("Genesis:                    {}", params.genesis_cid);
+    println!("Genesis timestamp:          {}", params.genesis_timestamp);
+    println!(
+        "Genesis network version:    {}",
+        u32::from(params.genesis_network_version.0)
+    );
+    println!("Block delay:                {}s", params.block_delay_secs);
+    println!(
+        "Propagation delay:          {}s",
+        params.propagation_delay_secs
+    );
+    println!(
+        "Chain finality:             {} epochs",
+        policy.chain_finality
+    );
+    println!(
+        "Consensus miner min power:  {}",
+        policy.consensus_miner_min_power
+    );
+    println!(
+        "Supported sector sizes:     {}",
+        policy
+            .supported_sector_sizes
+            .iter()
+            .map(|size| size.human_count_bytes().to_string())
+            .join(", ")
+    );
+    println!();
+    println!("Upgrades ({}):", params.upgrades.len());
+    for upgrade in &params.upgrades {
+        println!(
+            "  {:<16} epoch {:<10} nv{}",
+            upgrade.height.to_string(),
+            upgrade.epoch,
+            u32::from(upgrade.network_version.0)
+        );
+    }
+}
+
+fn print_inspected_block(block: &InspectedBlock) {
+    let header = &block.header;
+    println!("Block:         {}", block.cid);
+    println!("Miner:         {}", header.miner_address);
+    println!("Epoch:         {}", header.epoch);
+    println!("Weight:        {}", header.weight);
+    println!("Parent state:  {}", header.state_root);
+    for entry in &header.beacon_entries {
+        println!(
+            "Beacon entry:  round {} ({})",
+            entry.round(),
+            hex::encode(entry.signature())
+        );
+    }
+    if let Some(ticket) = &header.ticket {
+        println!("Ticket:        {}", hex::encode(ticket.vrfproof.as_bytes()));
+    }
+    if let Some(election_proof) = &header.election_proof {
+        println!("Win count:     {}", election_proof.win_count);
+    }
+    println!();
+    println!("Messages ({}):", block.messages.len());
+    for inspected in &block.messages {
+        let message = &inspected.message;
+        let method = match inspected.method_name {
+            Some(name) => format!("{name} ({})", message.method_num),
+            None => message.method_num.to_string(),
+        };
+        println!(
+            "  {} {} {} -> {} method {method}",
+            inspected.cid,
+            if inspected.signed { "secp" } else { "bls" },
+            message.from,
+            message.to,
+        );
+        match &inspected.receipt {
+            Some(receipt) => println!(
+                "    exit code {}, gas used {}",
+                receipt.exit_code, receipt.gas_used
+            ),
+            None if block.executed => println!("    not executed"),
+            None => {}
+        }
+    }
+    if !block.executed {
+        println!("Receipts are not available until the tipset has been executed");
+    }
+}
+
+/// Pushes a `ReportConsensusFault` message if the blocks prove a consensus
+/// fault of `miner`, returning its CID.
+async fn report_consensus_fault(
+    client: &rpc::Client,
+    (cid1, cid2, extra): (Cid, Cid, Option<Cid>),
+    miner: Address,
+    from: Option<Address>,
+) -> anyhow::Result<Cid> {
+    // The raw headers, as signed by the miner
+    let read_header = |cid| async move {
+        let bytes = ChainReadObj::call(client, (cid,)).await?;
+        let header = fvm_ipld_encoding::from_slice::<CachingBlockHeader>(&bytes)?;
+        anyhow::Ok((bytes, header))
+    };
+    let (bytes1, h1) = read_header(cid1).await?;
+    let (bytes2, h2) = read_header(cid2).await?;
+    let bytes_extra = match extra {
+        Some(cid) => read_header(cid).await?.0,
+        None => vec![],
+    };
+    ensure!(
+        h1.miner_address == miner,
+        "block {cid1} was mined by {}, not {miner}",
+        h1.miner_address
+    );
+    let network = StateNetworkName::call(client, ()).await?;
+    let chain_config = ChainConfig::from_chain(&network.parse::<NetworkChain>()?);
+    let Some(fault_type) = detect_consensus_fault(&chain_config, &h1, &h2, &bytes_extra)? else {
+        bail!("no consensus fault of {miner} in blocks {cid1} and {cid2}");
+    };
+    println!(
+        "Detected {fault_type:?} fault of {miner} at epoch {}",
+        h2.epoch
+    );
+
+    let from = match from {
+        Some(from) => from,
+        None => WalletDefaultAddress::call(client, ())
+            .await?
+            .context("No default wallet address selected. Please set a default address.")?,
+    };
+    let message = Message {
+        from,
+        to: miner,
+        method_num: miner::Method::ReportConsensusFault as u64,
+        params: RawBytes::serialize(ReportConsensusFaultParams {
+            header1: bytes1,
+            header2: bytes2,
+            header_extra: bytes_extra,
+        })?,
+        ..Default::default()
+    };
+    Ok(MpoolPushMessage::call(client, (message, None)).await?.cid())
+}
+
 /// If `epoch_or_offset` is negative, get the tipset that many blocks before the
 /// current head. Else treat `epoch_or_offset` as an epoch, and get that tipset.
 async fn tipset_by_epoch_or_offset(
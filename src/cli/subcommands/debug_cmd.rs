@@ -0,0 +1,58 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::cli::subcommands::print_pretty_lotus_json;
+use crate::lotus_json::HasLotusJson as _;
+use crate::rpc::{self, prelude::*};
+use cid::Cid;
+use clap::Subcommand;
+
+#[derive(Debug, Subcommand)]
+pub enum DebugCommands {
+    /// Export the current sync state as JSON: the heaviest tipset, the heads
+    /// announced by peers, the pending tipsets and the sync target
+    SyncState {
+        /// Write the sync state to this file instead of standard output
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Report the depths of the HAMT and AMT structures of a state tree and
+    /// how many of its blocks are shared, to diagnose their fragmentation
+    StateTreeDepth {
+        /// CID of the state root
+        state_cid: Cid,
+    },
+}
+
+impl DebugCommands {
+    pub async fn run(self, client: rpc::Client) -> anyhow::Result<()> {
+        match self {
+            Self::SyncState { output } => {
+                let snapshot = SyncStateExport::call(&client, ()).await?;
+                match output {
+                    Some(path) => {
+                        std::fs::write(&path, snapshot.into_lotus_json_string_pretty()?)?;
+                        println!("Exported sync state to {}", path.display());
+                        Ok(())
+                    }
+                    None => print_pretty_lotus_json(snapshot),
+                }
+            }
+            Self::StateTreeDepth { state_cid } => {
+                // Walks the whole state tree
+                let report = client
+                    .call(ForestStateTreeDepth::request((state_cid,))?.with_timeout(Duration::MAX))
+                    .await?;
+                println!("Max HAMT depth: {}", report.max_hamt_depth);
+                println!("Max AMT depth:  {}", report.max_amt_depth);
+                println!("Total nodes:    {}", report.total_nodes);
+                println!("Unique nodes:   {}", report.unique_nodes);
+                println!("Sharing ratio:  {:.2}", report.sharing_ratio);
+                Ok(())
+            }
+        }
+    }
+}
@@ -4,42 +4,76 @@
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use crate::blocks::Tipset;
+use crate::chain_sync::SyncStage;
 use crate::cli::humantoken::TokenAmountPretty;
-use crate::rpc::{self, prelude::*};
+use crate::networks::{ChainConfig, NetworkChain};
+use crate::rpc::{self, prelude::*, types::ApiTipsetKey};
 use crate::shim::address::Address;
-use crate::shim::clock::{ChainEpoch, BLOCKS_PER_EPOCH, EPOCH_DURATION_SECONDS};
+use crate::shim::clock::{ChainEpoch, BLOCKS_PER_EPOCH};
 use crate::shim::econ::TokenAmount;
+use crate::shim::version::NetworkVersion;
 use chrono::{DateTime, Utc};
 use clap::Subcommand;
+use colored::Colorize as _;
 use humantime::format_duration;
+use serde::Serialize;
+
+#[derive(Debug, clap::Args)]
+pub struct InfoCommand {
+    #[command(subcommand)]
+    command: Option<InfoSubcommand>,
+    /// Print the node status as JSON
+    #[arg(long, global = true)]
+    json: bool,
+}
 
 #[derive(Debug, Subcommand)]
-pub enum InfoCommand {
+enum InfoSubcommand {
+    /// Same as `forest-cli info`
     Show,
+    /// Print the usage of the node caches
+    Stats {
+        /// Also print the number of keys and the size on disk of each database column. This
+        /// scans the whole database.
+        #[arg(long)]
+        db: bool,
+    },
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
 pub struct NodeStatusInfo {
     /// How far behind the node is with respect to syncing to head in seconds
     pub lag: i64,
+    /// How far behind the node is with respect to syncing to head in epochs
+    pub lag_epochs: i64,
     /// Chain health is the percentage denoting how close we are to having
     /// an average of 5 blocks per tipset in the last couple of
     /// hours. The number of blocks per tipset is non-deterministic
     /// but averaging at 5 is considered healthy.
-    pub health: f64,
+    pub health: Option<f64>,
     /// epoch the node is currently at
     pub epoch: ChainEpoch,
     /// Base fee is the set price per unit of gas (measured in attoFIL/gas unit) to be burned (sent to an unrecoverable address) for every message execution
+    #[serde(with = "crate::lotus_json")]
     pub base_fee: TokenAmount,
     pub sync_status: SyncStatus,
+    #[serde(with = "crate::lotus_json")]
+    pub sync_stage: Option<SyncStage>,
     /// Start time of the node
-    pub start_time: DateTime<Utc>,
-    pub network: String,
+    #[serde(with = "crate::lotus_json")]
+    pub start_time: Option<DateTime<Utc>>,
+    pub network: Option<String>,
+    pub network_version: Option<NetworkVersion>,
+    /// Number of connected peers
+    pub peers: Option<usize>,
+    #[serde(with = "crate::lotus_json")]
     pub default_wallet_address: Option<Address>,
+    #[serde(with = "crate::lotus_json")]
     pub default_wallet_address_balance: Option<TokenAmount>,
 }
 
-#[derive(Debug, strum::Display, PartialEq)]
+#[derive(Debug, strum::Display, PartialEq, Serialize)]
 pub enum SyncStatus {
     Ok,
     Slow,
@@ -47,26 +81,36 @@ pub enum SyncStatus {
     Fast,
 }
 
+impl SyncStatus {
+    fn colored(&self) -> colored::ColoredString {
+        let status = format!("{self}!");
+        match self {
+            SyncStatus::Ok => status.green(),
+            SyncStatus::Slow => status.yellow(),
+            SyncStatus::Behind => status.red(),
+            SyncStatus::Fast => status.cyan(),
+        }
+    }
+}
+
+/// Placeholder for the values whose RPC call failed.
+const NOT_AVAILABLE: &str = "n/a";
+
 impl NodeStatusInfo {
-    pub fn new(
-        cur_duration: Duration,
-        blocks_per_tipset_last_finality: f64,
-        head: &Tipset,
-        start_time: DateTime<Utc>,
-        network: String,
-        default_wallet_address: Option<Address>,
-        default_wallet_address_balance: Option<TokenAmount>,
-    ) -> NodeStatusInfo {
+    /// Computes the sync status from the head, leaving the fields that come
+    /// from other RPC calls empty.
+    pub fn new(cur_duration: Duration, block_delay_secs: u32, head: &Tipset) -> NodeStatusInfo {
         let ts = head.min_timestamp() as i64;
         let cur_duration_secs = cur_duration.as_secs() as i64;
         let lag = cur_duration_secs - ts;
+        let block_delay = i64::from(block_delay_secs.max(1));
 
         let sync_status = if lag < 0 {
             SyncStatus::Fast
-        } else if lag < EPOCH_DURATION_SECONDS * 3 / 2 {
+        } else if lag < block_delay * 3 / 2 {
             // within 1.5 epochs
             SyncStatus::Ok
-        } else if lag < EPOCH_DURATION_SECONDS * 5 {
+        } else if lag < block_delay * 5 {
             // within 5 epochs
             SyncStatus::Slow
         } else {
@@ -75,35 +119,52 @@ impl NodeStatusInfo {
 
         let base_fee = head.min_ticket_block().parent_base_fee.clone();
 
-        // blocks_per_tipset_last_finality = no of blocks till head / chain finality
-        let health = 100. * blocks_per_tipset_last_finality / BLOCKS_PER_EPOCH as f64;
-
         Self {
             lag,
-            health,
+            lag_epochs: lag / block_delay,
+            health: None,
             epoch: head.epoch(),
             base_fee,
             sync_status,
-            start_time,
-            network,
-            default_wallet_address,
-            default_wallet_address_balance,
+            sync_stage: None,
+            start_time: None,
+            network: None,
+            network_version: None,
+            peers: None,
+            default_wallet_address: None,
+            default_wallet_address_balance: None,
         }
     }
 
+    /// Sets the chain health from the average number of blocks per tipset.
+    pub fn with_health(mut self, blocks_per_tipset_last_finality: f64) -> Self {
+        // blocks_per_tipset_last_finality = no of blocks till head / chain finality
+        self.health = Some(100. * blocks_per_tipset_last_finality / BLOCKS_PER_EPOCH as f64);
+        self
+    }
+
     fn format(&self, now: DateTime<Utc>) -> String {
-        let network = format!("Network: {}", self.network);
-
-        let uptime = {
-            let uptime = (now - self.start_time)
-                .to_std()
-                .expect("failed converting to std duration");
-            let uptime = Duration::from_secs(uptime.as_secs());
-            let fmt_uptime = format_duration(uptime);
-            format!(
-                "Uptime: {fmt_uptime} (Started at: {})",
-                self.start_time.with_timezone(&chrono::offset::Local)
-            )
+        let network = format!(
+            "Network: {} (version: {})",
+            self.network.as_deref().unwrap_or(NOT_AVAILABLE),
+            self.network_version
+                .map(|version| version.to_string())
+                .unwrap_or_else(|| NOT_AVAILABLE.into())
+        );
+
+        let uptime = match self.start_time {
+            Some(start_time) => {
+                let uptime = (now - start_time)
+                    .to_std()
+                    .expect("failed converting to std duration");
+                let uptime = Duration::from_secs(uptime.as_secs());
+                let fmt_uptime = format_duration(uptime);
+                format!(
+                    "Uptime: {fmt_uptime} (Started at: {})",
+                    start_time.with_timezone(&chrono::offset::Local)
+                )
+            }
+            None => format!("Uptime: {NOT_AVAILABLE}"),
         };
 
         let chain = {
@@ -116,25 +177,47 @@ impl NodeStatusInfo {
             };
 
             format!(
-                "Chain: [sync: {}! ({})] [basefee: {base_fee_fmt}] [epoch: {}]",
-                self.sync_status, behind, self.epoch
+                "Chain: [sync: {} ({})] [basefee: {base_fee_fmt}] [epoch: {}]",
+                self.sync_status.colored(),
+                behind,
+                self.epoch
             )
         };
 
-        let chain_health = format!("Chain health: {:.2}%\n\n", self.health);
+        let sync = format!(
+            "Sync lag: {} epochs [stage: {}]",
+            self.lag_epochs,
+            self.sync_stage
+                .map(|stage| stage.to_string())
+                .unwrap_or_else(|| NOT_AVAILABLE.into())
+        );
+
+        let peers = format!(
+            "Peers: {}",
+            self.peers
+                .map(|peers| peers.to_string())
+                .unwrap_or_else(|| NOT_AVAILABLE.into())
+        );
+
+        let chain_health = format!(
+            "Chain health: {}\n\n",
+            self.health
+                .map(|health| format!("{health:.2}%"))
+                .unwrap_or_else(|| NOT_AVAILABLE.into())
+        );
 
         let wallet_info = {
             let wallet_address = self
                 .default_wallet_address
                 .as_ref()
                 .map(|it| it.to_string())
-                .unwrap_or("address not set".to_string());
+                .unwrap_or(NOT_AVAILABLE.to_string());
 
             let wallet_balance = self
                 .default_wallet_address_balance
                 .as_ref()
                 .map(|balance| format!("{:.4}", balance.pretty()))
-                .unwrap_or("could not find balance".to_string());
+                .unwrap_or(NOT_AVAILABLE.to_string());
 
             format!(
                 "Default wallet address: {} [{}]",
@@ -142,47 +225,142 @@ impl NodeStatusInfo {
             )
         };
 
-        [network, uptime, chain, chain_health, wallet_info].join("\n")
+        [
+            network,
+            uptime,
+            chain,
+            sync,
+            peers,
+            chain_health,
+            wallet_info,
+        ]
+        .join("\n")
+    }
+}
+
+/// Returns the value of a successful RPC call, or prints a warning and returns
+/// `None` if it failed.
+fn or_warn<T>(what: &str, result: Result<T, impl std::fmt::Display>) -> Option<T> {
+    match result {
+        Ok(value) => Some(value),
+        Err(e) => {
+            eprintln!("{} failed to fetch {what}: {e}", "Warning:".yellow());
+            None
+        }
     }
 }
 
 impl InfoCommand {
     pub async fn run(self, client: rpc::Client) -> anyhow::Result<()> {
-        let (node_status, head, network, start_time, default_wallet_address) = tokio::try_join!(
-            NodeStatus::call(&client, ()),
+        let json = self.json;
+        if let Some(InfoSubcommand::Stats { db }) = self.command {
+            return print_stats(&client, db, json).await;
+        }
+        let (
+            head,
+            node_status,
+            sync_state,
+            peers,
+            network,
+            network_version,
+            start_time,
+            default_wallet_address,
+        ) = tokio::join!(
             ChainHead::call(&client, ()),
+            NodeStatus::call(&client, ()),
+            SyncState::call(&client, ()),
+            NetPeers::call(&client, ()),
             StateNetworkName::call(&client, ()),
+            StateNetworkVersion::call(&client, (ApiTipsetKey(None),)),
             StartTime::call(&client, ()),
             WalletDefaultAddress::call(&client, ()),
-        )?;
+        );
+        // The dashboard is meaningless without the head.
+        let head = head?;
+
+        let network = or_warn("network name", network);
+        let block_delay_secs = match &network {
+            Some(network) => {
+                ChainConfig::from_chain(&network.parse::<NetworkChain>()?).block_delay_secs
+            }
+            None => ChainConfig::mainnet().block_delay_secs,
+        };
+        let default_wallet_address =
+            or_warn("default wallet address", default_wallet_address).flatten();
+        let default_wallet_address_balance = match default_wallet_address {
+            Some(address) => or_warn(
+                "default wallet balance",
+                WalletBalance::call(&client, (address,)).await,
+            ),
+            None => None,
+        };
 
         let cur_duration: Duration = SystemTime::now().duration_since(UNIX_EPOCH)?;
-        let blocks_per_tipset_last_finality =
-            node_status.chain_status.blocks_per_tipset_last_finality;
-
-        let default_wallet_address_balance = if let Some(def_addr) = default_wallet_address {
-            let balance = WalletBalance::call(&client, (def_addr,)).await?;
-            Some(balance)
+        let mut node_status_info = NodeStatusInfo::new(cur_duration, block_delay_secs, &head);
+        if let Some(node_status) = or_warn("node status", node_status) {
+            node_status_info = node_status_info
+                .with_health(node_status.chain_status.blocks_per_tipset_last_finality);
+        }
+        node_status_info.sync_stage = or_warn("sync state", sync_state)
+            .map(|sync_state| sync_state.active_syncs.first().stage());
+        node_status_info.peers = or_warn("peers", peers).map(|peers| peers.len());
+        node_status_info.network = network;
+        node_status_info.network_version = or_warn("network version", network_version);
+        node_status_info.start_time = or_warn("start time", start_time);
+        node_status_info.default_wallet_address = default_wallet_address;
+        node_status_info.default_wallet_address_balance = default_wallet_address_balance;
+
+        if json {
+            println!("{}", serde_json::to_string_pretty(&node_status_info)?);
         } else {
-            None
-        };
-
-        let node_status_info = NodeStatusInfo::new(
-            cur_duration,
-            blocks_per_tipset_last_finality,
-            &head,
-            start_time,
-            network,
-            default_wallet_address,
-            default_wallet_address_balance,
-        );
-
-        println!("{}", node_status_info.format(Utc::now()));
+            println!("{}", node_status_info.format(Utc::now()));
+        }
 
         Ok(())
     }
 }
 
+async fn print_stats(client: &rpc::Client, db: bool, json: bool) -> anyhow::Result<()> {
+    let caches = CacheStats::call(client, ()).await?;
+    let columns = if db {
+        Some(
+            client
+                .call(DbColumnStats::request(())?.with_timeout(Duration::MAX))
+                .await?,
+        )
+    } else {
+        None
+    };
+
+    if json {
+        let stats = serde_json::json!({ "caches": caches, "db_columns": columns });
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(());
+    }
+    println!("Caches:");
+    for cache in caches {
+        println!(
+            "  {}: {} entries, {} of {}",
+            cache.kind,
+            cache.entries,
+            human_bytes::human_bytes(cache.size_bytes as f64),
+            human_bytes::human_bytes(cache.budget_bytes as f64),
+        );
+    }
+    if let Some(columns) = columns {
+        println!("Database columns:");
+        for column in columns {
+            println!(
+                "  {}: {} keys, {}",
+                column.name,
+                column.num_keys,
+                human_bytes::human_bytes(column.disk_size_bytes as f64),
+            );
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use crate::blocks::RawBlockHeader;
@@ -194,6 +372,8 @@ mod tests {
     use std::{str::FromStr, sync::Arc, time::Duration};
 
     use super::{NodeStatusInfo, SyncStatus};
+    use crate::chain_sync::SyncStage;
+    use crate::shim::version::NetworkVersion;
 
     fn mock_tipset_at(seconds_since_unix_epoch: u64) -> Arc<Tipset> {
         let mock_header = CachingBlockHeader::new(RawBlockHeader {
@@ -209,27 +389,32 @@ mod tests {
     fn mock_node_status() -> NodeStatusInfo {
         NodeStatusInfo {
             lag: 0,
-            health: 90.,
+            lag_epochs: 0,
+            health: Some(90.),
             epoch: i64::MAX,
             base_fee: TokenAmount::from_whole(1),
             sync_status: SyncStatus::Ok,
-            start_time: DateTime::<chrono::Utc>::MIN_UTC,
-            network: "calibnet".to_string(),
+            sync_stage: Some(SyncStage::Complete),
+            start_time: Some(DateTime::<chrono::Utc>::MIN_UTC),
+            network: Some("calibnet".to_string()),
+            network_version: Some(NetworkVersion::V25),
+            peers: Some(12),
             default_wallet_address: None,
             default_wallet_address_balance: None,
         }
     }
 
     fn node_status(duration: Duration, tipset: &Tipset) -> NodeStatusInfo {
-        NodeStatusInfo::new(
-            duration,
-            20.,
-            tipset,
-            DateTime::<chrono::Utc>::MIN_UTC,
-            "calibnet".to_string(),
-            None,
-            None,
-        )
+        let mut status =
+            NodeStatusInfo::new(duration, EPOCH_DURATION_SECONDS as u32, tipset).with_health(20.);
+        status.start_time = Some(DateTime::<chrono::Utc>::MIN_UTC);
+        status.network = Some("calibnet".to_string());
+        status
+    }
+
+    fn format(status: &NodeStatusInfo) -> String {
+        colored::control::set_override(false);
+        status.format(DateTime::<chrono::Utc>::MIN_UTC)
     }
 
     #[quickcheck]
@@ -273,18 +458,14 @@ mod tests {
         let tipset = mock_tipset_at(duration.as_secs() - 10);
         let status = node_status(duration, tipset.as_ref());
 
-        assert!(status
-            .format(DateTime::<chrono::Utc>::MIN_UTC)
-            .contains("10s behind"));
+        assert!(format(&status).contains("10s behind"));
     }
 
     #[test]
     fn test_lag_uptime_ahead() {
         let mut status = mock_node_status();
         status.lag = -360;
-        assert!(status
-            .format(DateTime::<chrono::Utc>::MIN_UTC)
-            .contains("6m ahead"));
+        assert!(format(&status).contains("6m ahead"));
     }
 
     #[test]
@@ -294,17 +475,74 @@ mod tests {
         let status = node_status(duration, tipset.as_ref());
         let expected_status_fmt =
             "[sync: Slow! (59s behind)] [basefee: 0 FIL] [epoch: 0]".to_string();
-        assert!(status
-            .format(DateTime::<chrono::Utc>::MIN_UTC)
-            .contains(&expected_status_fmt));
+        assert!(format(&status).contains(&expected_status_fmt));
 
         let tipset = mock_tipset_at(duration.as_secs() - 30000);
         let status = node_status(duration, tipset.as_ref());
 
         let expected_status_fmt =
             "[sync: Behind! (8h 20m behind)] [basefee: 0 FIL] [epoch: 0]".to_string();
-        assert!(status
-            .format(DateTime::<chrono::Utc>::MIN_UTC)
-            .contains(&expected_status_fmt));
+        assert!(format(&status).contains(&expected_status_fmt));
+    }
+
+    #[test]
+    fn test_lag_epochs() {
+        let duration = Duration::from_secs(100_000);
+        let tipset = mock_tipset_at(duration.as_secs() - 95);
+        let status = node_status(duration, tipset.as_ref());
+        assert_eq!(status.lag_epochs, 3);
+        assert!(format(&status).contains("Sync lag: 3 epochs [stage: n/a]"));
+    }
+
+    #[test]
+    fn test_failed_rpcs_are_not_available() {
+        let duration = Duration::from_secs(60);
+        let tipset = mock_tipset_at(duration.as_secs());
+        let status = NodeStatusInfo::new(duration, EPOCH_DURATION_SECONDS as u32, &tipset);
+        let formatted = format(&status);
+        for expected in [
+            "Network: n/a (version: n/a)",
+            "Uptime: n/a",
+            "Peers: n/a",
+            "Chain health: n/a",
+            "Default wallet address: n/a [n/a]",
+        ] {
+            assert!(
+                formatted.contains(expected),
+                "missing {expected:?} in\n{formatted}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_dashboard() {
+        let mut status = mock_node_status();
+        status.default_wallet_address = Some(Address::new_id(1234));
+        status.default_wallet_address_balance = Some(TokenAmount::from_whole(2));
+        let formatted = format(&status);
+        for expected in [
+            "Network: calibnet (version: 25)",
+            "[sync: Ok! (0s behind)]",
+            "Sync lag: 0 epochs [stage: complete]",
+            "Peers: 12",
+            "Chain health: 90.00%",
+            "Default wallet address: f01234 [2 FIL]",
+        ] {
+            assert!(
+                formatted.contains(expected),
+                "missing {expected:?} in\n{formatted}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_json() {
+        let json = serde_json::to_value(mock_node_status()).unwrap();
+        assert_eq!(json["Network"], "calibnet");
+        assert_eq!(json["NetworkVersion"], 25);
+        assert_eq!(json["Peers"], 12);
+        assert_eq!(json["BaseFee"], "1000000000000000000");
+        assert_eq!(json["SyncStatus"], "Ok");
+        assert!(json["DefaultWalletAddress"].is_null());
     }
 }
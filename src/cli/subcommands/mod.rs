@@ -9,6 +9,7 @@
 mod auth_cmd;
 mod chain_cmd;
 mod config_cmd;
+mod debug_cmd;
 mod f3_cmd;
 mod healthcheck_cmd;
 mod info_cmd;
@@ -31,9 +32,10 @@ use tracing::error;
 
 pub(super) use self::{
     auth_cmd::AuthCommands, chain_cmd::ChainCommands, config_cmd::ConfigCommands,
-    f3_cmd::F3Commands, healthcheck_cmd::HealthcheckCommand, mpool_cmd::MpoolCommands,
-    net_cmd::NetCommands, send_cmd::SendCommand, shutdown_cmd::ShutdownCommand,
-    snapshot_cmd::SnapshotCommands, state_cmd::StateCommands, sync_cmd::SyncCommands,
+    debug_cmd::DebugCommands, f3_cmd::F3Commands, healthcheck_cmd::HealthcheckCommand,
+    mpool_cmd::MpoolCommands, net_cmd::NetCommands, send_cmd::SendCommand,
+    shutdown_cmd::ShutdownCommand, snapshot_cmd::SnapshotCommands, state_cmd::StateCommands,
+    sync_cmd::SyncCommands,
 };
 use crate::cli::subcommands::info_cmd::InfoCommand;
 
@@ -88,7 +90,6 @@ pub enum Subcommand {
     Send(SendCommand),
 
     /// Print node info
-    #[command(subcommand)]
     Info(InfoCommand),
 
     /// `[REMOVED]` Attach to daemon via a JavaScript console
@@ -104,6 +105,10 @@ pub enum Subcommand {
     /// Manages Filecoin Fast Finality (F3) interactions
     #[command(subcommand)]
     F3(F3Commands),
+
+    /// Export node internals to investigate issues
+    #[command(subcommand)]
+    Debug(DebugCommands),
 }
 
 /// Format a vector to a prettified string
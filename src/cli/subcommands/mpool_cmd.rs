@@ -1,19 +1,19 @@
 // Copyright 2019-2025 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use std::path::PathBuf;
 use std::str::FromStr as _;
+use std::time::Duration;
 
-use crate::blocks::Tipset;
-use crate::lotus_json::{HasLotusJson as _, NotNullVec};
+use crate::lotus_json::{HasLotusJson as _, LotusJson, NotNullVec};
 use crate::message::SignedMessage;
+use crate::message_pool::{self, LocalMessage, MessageImport, MessageImportStatus, SenderStat};
 use crate::rpc::{self, prelude::*, types::ApiTipsetKey};
-use crate::shim::address::StrictAddress;
-use crate::shim::message::Message;
-use crate::shim::{address::Address, econ::TokenAmount};
+use crate::shim::address::{Address, StrictAddress};
 
-use ahash::{HashMap, HashSet};
+use ahash::HashSet;
 use clap::Subcommand;
-use num::BigInt;
+use itertools::Itertools as _;
 
 #[derive(Debug, Subcommand)]
 pub enum MpoolCommands {
@@ -41,6 +41,27 @@ pub enum MpoolCommands {
         #[arg(long)]
         local: bool,
     },
+    /// List messages published through this node and their journal status
+    Locals,
+    /// Export the pending messages to a file, as a JSON array of signed
+    /// messages
+    Export {
+        /// Output file
+        file: PathBuf,
+        /// Export messages from addresses in local wallet only
+        #[arg(long)]
+        local: bool,
+    },
+    /// Re-add the messages of a file written by `forest-cli mpool export`,
+    /// skipping those already included on chain
+    Import {
+        /// Input file
+        file: PathBuf,
+        /// Import the messages as local ones, journaling and republishing them
+        /// like those pushed to this node
+        #[arg(long)]
+        local: bool,
+    },
 }
 
 fn to_addr(value: &Option<String>) -> anyhow::Result<Option<StrictAddress>> {
@@ -76,120 +97,27 @@ fn filter_messages(
     Ok(filtered)
 }
 
-async fn get_actor_sequence(
-    message: &Message,
-    tipset: &Tipset,
-    client: &rpc::Client,
-) -> Option<u64> {
-    let address = message.from;
-    let get_actor_result = StateGetActor::call(client, (address, tipset.key().into())).await;
-    let actor_state = match get_actor_result {
-        Ok(maybe_actor) => {
-            if let Some(state) = maybe_actor {
-                state
-            } else {
-                println!("{}, actor state not found", address);
-                return None;
-            }
-        }
-        Err(err) => {
-            println!("{}, err: {}", address, err);
-            return None;
-        }
-    };
-
-    Some(actor_state.sequence)
-}
-
-type StatBucket = HashMap<u64, Message>;
-
-#[derive(Debug, Default, Eq, PartialEq)]
-struct MpStat {
-    address: String,
-    past: u64,
-    current: u64,
-    future: u64,
-    below_current: u64,
-    below_past: u64,
-    gas_limit: BigInt,
-}
-
-fn compute_stats(
-    messages: &[Message],
-    actor_sequences: HashMap<Address, u64>,
-    curr_base_fee: TokenAmount,
-    min_base_fee: TokenAmount,
-) -> Vec<MpStat> {
-    let mut buckets = HashMap::<Address, StatBucket>::default();
-    for msg in messages {
-        buckets
-            .entry(msg.from)
-            .or_insert(StatBucket::default())
-            .insert(msg.sequence, msg.to_owned());
-    }
-
-    let mut stats: Vec<MpStat> = Vec::with_capacity(buckets.len());
-
-    for (address, bucket) in buckets {
-        let actor_sequence = *actor_sequences.get(&address).expect("get must succeed");
-
-        let mut curr_sequence = actor_sequence;
-        while bucket.contains_key(&curr_sequence) {
-            curr_sequence += 1;
-        }
-
-        let mut stat = MpStat {
-            address: address.to_string(),
-            ..Default::default()
-        };
-
-        for (_, msg) in bucket {
-            if msg.sequence < actor_sequence {
-                stat.past += 1;
-            } else if msg.sequence > curr_sequence {
-                stat.future += 1;
-            } else {
-                stat.current += 1;
-            }
-
-            if msg.gas_fee_cap < curr_base_fee {
-                stat.below_current += 1;
-            }
-            if msg.gas_fee_cap < min_base_fee {
-                stat.below_past += 1;
-            }
-
-            stat.gas_limit += msg.gas_limit;
-        }
-
-        stats.push(stat);
-    }
-
-    stats.sort_by(|m1, m2| m1.address.cmp(&m2.address));
-    stats
-}
-
-fn print_stats(stats: &[MpStat], basefee_lookback: u32) {
-    let mut total = MpStat::default();
+fn print_stats(stat: &message_pool::MpoolStat, basefee_lookback: u32) {
+    let mut total = SenderStat::default();
 
-    for stat in stats {
-        total.past += stat.past;
-        total.current += stat.current;
-        total.future += stat.future;
-        total.below_current += stat.below_current;
-        total.below_past += stat.below_past;
-        total.gas_limit += &stat.gas_limit;
+    for sender in &stat.senders {
+        total.past += sender.past;
+        total.current += sender.current;
+        total.future += sender.future;
+        total.below_base_fee += sender.below_base_fee;
+        total.below_min_base_fee += sender.below_min_base_fee;
+        total.gas_limit += sender.gas_limit;
 
         println!(
             "{}: Nonce past: {}, cur: {}, future: {}; FeeCap cur: {}, min-{}: {}, gasLimit: {}",
-            stat.address,
-            stat.past,
-            stat.current,
-            stat.future,
-            stat.below_current,
+            sender.address,
+            sender.past,
+            sender.current,
+            sender.future,
+            sender.below_base_fee,
             basefee_lookback,
-            stat.below_past,
-            stat.gas_limit
+            sender.below_min_base_fee,
+            sender.gas_limit
         );
     }
 
@@ -199,11 +127,90 @@ fn print_stats(stats: &[MpStat], basefee_lookback: u32) {
         total.past,
         total.current,
         total.future,
-        total.below_current,
+        total.below_base_fee,
         basefee_lookback,
-        total.below_past,
+        total.below_min_base_fee,
         total.gas_limit
     );
+
+    println!("-----");
+    println!(
+        "age: {}",
+        stat.ages
+            .iter()
+            .map(|bucket| format!(">={}s: {}", bucket.min_age_secs, bucket.count))
+            .join(", ")
+    );
+    println!("top senders:");
+    for address in &stat.top_senders {
+        if let Some(sender) = stat
+            .senders
+            .iter()
+            .find(|sender| &sender.address == address)
+        {
+            println!(
+                "{}: pending: {}, nonce gap: {}, gasLimit: {}",
+                sender.address,
+                sender.pending(),
+                sender.nonce_gap,
+                sender.gas_limit
+            );
+        }
+    }
+}
+
+fn format_local_message(entry: &LocalMessage) -> String {
+    use crate::message::Message;
+
+    let published_at = chrono::DateTime::from_timestamp(entry.published_at as i64, 0)
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| entry.published_at.to_string());
+    format!(
+        "{}: from: {}, nonce: {}, published: {}, status: {:?}",
+        entry.message.cid(),
+        entry.message.from(),
+        entry.message.sequence(),
+        published_at,
+        entry.status
+    )
+}
+
+/// Parses a JSON array of signed messages, the entries that are not valid
+/// signed messages being returned as errors rather than failing the whole
+/// array.
+fn parse_exported_messages(json: &str) -> anyhow::Result<Vec<anyhow::Result<SignedMessage>>> {
+    let entries: Vec<serde_json::Value> = serde_json::from_str(json)?;
+    Ok(entries
+        .into_iter()
+        .map(|entry| Ok(serde_json::from_value::<LotusJson<SignedMessage>>(entry)?.into_inner()))
+        .collect())
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct ImportSummary {
+    added: usize,
+    already_pending: usize,
+    already_included: usize,
+    rejected: usize,
+    invalid: usize,
+}
+
+impl ImportSummary {
+    fn new(imports: &[MessageImport], invalid: usize) -> Self {
+        let mut summary = Self {
+            invalid,
+            ..Default::default()
+        };
+        for import in imports {
+            match import.status {
+                MessageImportStatus::Added => summary.added += 1,
+                MessageImportStatus::AlreadyPending => summary.already_pending += 1,
+                MessageImportStatus::AlreadyIncluded => summary.already_included += 1,
+                MessageImportStatus::Rejected => summary.rejected += 1,
+            }
+        }
+        summary
+    }
 }
 
 impl MpoolCommands {
@@ -241,12 +248,24 @@ impl MpoolCommands {
                 basefee_lookback,
                 local,
             } => {
-                let tipset = ChainHead::call(&client, ()).await?;
-                let curr_base_fee = tipset.block_headers().first().parent_base_fee.to_owned();
+                let senders = if local {
+                    Some(WalletList::call(&client, ()).await?)
+                } else {
+                    None
+                };
+                let stat = MpoolStat::call(&client, (basefee_lookback, senders)).await?;
 
-                let atto_str = ChainGetMinBaseFee::call(&client, (basefee_lookback,)).await?;
-                let min_base_fee = TokenAmount::from_atto(atto_str.parse::<BigInt>()?);
+                print_stats(&stat, basefee_lookback);
 
+                Ok(())
+            }
+            Self::Locals => {
+                for entry in MpoolLocals::call(&client, ()).await? {
+                    println!("{}", format_local_message(&entry));
+                }
+                Ok(())
+            }
+            Self::Export { file, local } => {
                 let NotNullVec(messages) =
                     MpoolPending::call(&client, (ApiTipsetKey(None),)).await?;
 
@@ -257,22 +276,45 @@ impl MpoolCommands {
                     None
                 };
 
-                let messages: Vec<Message> = filter_messages(messages, local_addrs, &None, &None)?
-                    .into_iter()
-                    .map(|it| it.message)
-                    .collect();
-
-                let mut actor_sequences: HashMap<Address, u64> = HashMap::default();
-                for msg in messages.iter() {
-                    if let Some(sequence) = get_actor_sequence(msg, &tipset, &client).await {
-                        actor_sequences.insert(msg.from, sequence);
+                let messages = filter_messages(messages, local_addrs, &None, &None)?;
+                let count = messages.len();
+                std::fs::write(&file, messages.into_lotus_json_string_pretty()?)?;
+                println!("Exported {count} messages to {}", file.display());
+                Ok(())
+            }
+            Self::Import { file, local } => {
+                let entries = parse_exported_messages(&std::fs::read_to_string(&file)?)?;
+                let mut messages = vec![];
+                let mut invalid = 0;
+                for (i, entry) in entries.into_iter().enumerate() {
+                    match entry {
+                        Ok(message) => messages.push(message),
+                        Err(e) => {
+                            println!("entry {i}: invalid: {e}");
+                            invalid += 1;
+                        }
                     }
                 }
 
-                let stats = compute_stats(&messages, actor_sequences, curr_base_fee, min_base_fee);
-
-                print_stats(&stats, basefee_lookback);
+                let imports = client
+                    .call(MpoolImport::request((messages, local))?.with_timeout(Duration::MAX))
+                    .await?;
+                for import in &imports {
+                    match &import.error {
+                        Some(e) => println!("{}: {:?}: {e}", import.cid, import.status),
+                        None => println!("{}: {:?}", import.cid, import.status),
+                    }
+                }
 
+                let summary = ImportSummary::new(&imports, invalid);
+                println!(
+                    "added: {}, already pending: {}, already included: {}, rejected: {}, invalid: {}",
+                    summary.added,
+                    summary.already_pending,
+                    summary.already_included,
+                    summary.rejected,
+                    summary.invalid
+                );
                 Ok(())
             }
         }
@@ -286,6 +328,7 @@ mod tests {
     use crate::message::{Message, SignedMessage};
     use crate::message_pool::tests::create_smsg;
     use crate::shim::crypto::SignatureType;
+    use cid::Cid;
     use std::borrow::BorrowMut;
 
     #[test]
@@ -424,77 +467,70 @@ mod tests {
     }
 
     #[test]
-    fn compute_statistics() {
-        use crate::shim::message::Message;
-        use fvm_ipld_encoding::RawBytes;
-
-        let addr0 = Address::from_str("t3urxivigpzih5f6ih3oq3lr2jlunw3m5oehbe5efts4ub5wy2oi4fbo5cw7333a4rrffo5535tjdq24wkc2aa").unwrap();
-        let addr1 = Address::from_str("t410fot3vkzzorqg4alowvghvxx4mhofhtazixbm6z2i").unwrap();
-        let messages = [
-            Message {
-                version: 0,
-                from: addr0,
-                to: Address::default(),
-                sequence: 1210,
-                value: TokenAmount::default(),
-                method_num: 5,
-                params: RawBytes::new(vec![]),
-                gas_limit: 25201703,
-                gas_fee_cap: TokenAmount::from_atto(101774),
-                gas_premium: TokenAmount::from_atto(100720),
-            },
-            Message {
-                version: 0,
-                from: addr1,
-                to: Address::default(),
-                sequence: 190,
-                value: TokenAmount::default(),
-                method_num: 5,
-                params: RawBytes::new(vec![]),
-                gas_limit: 21148671,
-                gas_fee_cap: TokenAmount::from_atto(101774),
-                gas_premium: TokenAmount::from_atto(100720),
-            },
-            Message {
-                version: 0,
-                from: addr1,
-                to: Address::default(),
-                sequence: 191,
-                value: TokenAmount::default(),
-                method_num: 5,
-                params: RawBytes::new(vec![]),
-                gas_limit: 112795625,
-                gas_fee_cap: TokenAmount::from_atto(101774),
-                gas_premium: TokenAmount::from_atto(100720),
-            },
-        ];
-        let actor_sequences = HashMap::from_iter([(addr0, 1210), (addr1, 195)]);
-        let curr_base_fee = TokenAmount::from_atto(100);
-        let min_base_fee = TokenAmount::from_atto(100);
-
-        let stats = compute_stats(&messages, actor_sequences, curr_base_fee, min_base_fee);
-
-        let expected = vec![
-            MpStat {
-                address: addr0.to_string(),
-                past: 0,
-                current: 1,
-                future: 0,
-                below_current: 0,
-                below_past: 0,
-                gas_limit: 25201703.into(),
-            },
-            MpStat {
-                address: addr1.to_string(),
-                past: 2,
-                current: 0,
-                future: 0,
-                below_current: 0,
-                below_past: 0,
-                gas_limit: 133944296.into(),
-            },
-        ];
+    fn exported_messages_round_trip() {
+        let mut wallet = Wallet::new(KeyStore::new(KeyStoreConfig::Memory).unwrap());
+        let sender = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let target = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let messages = (0..4)
+            .map(|i| create_smsg(&target, &sender, &mut wallet, i, 1000000, 1))
+            .collect::<Vec<_>>();
+
+        let json = messages.clone().into_lotus_json_string_pretty().unwrap();
+        let parsed = parse_exported_messages(&json)
+            .unwrap()
+            .into_iter()
+            .collect::<anyhow::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(parsed, messages);
+    }
+
+    #[test]
+    fn exported_messages_partially_invalid() {
+        let mut wallet = Wallet::new(KeyStore::new(KeyStoreConfig::Memory).unwrap());
+        let sender = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let target = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let message = create_smsg(&target, &sender, &mut wallet, 0, 1000000, 1);
+        let json = serde_json::json!([
+            message.clone().into_lotus_json_value().unwrap(),
+            {"Message": "not a message"},
+            42,
+        ]);
+
+        let parsed = parse_exported_messages(&json.to_string()).unwrap();
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parsed[0].as_ref().unwrap(), &message);
+        assert!(parsed[1].is_err());
+        assert!(parsed[2].is_err());
+        // The file itself must be an array
+        assert!(parse_exported_messages("{}").is_err());
+    }
 
-        assert_eq!(stats, expected);
+    #[test]
+    fn import_summary() {
+        let import = |status, error: Option<&str>| MessageImport {
+            cid: Cid::default(),
+            status,
+            error: error.map(String::from),
+        };
+        let imports = [
+            import(MessageImportStatus::Added, None),
+            import(MessageImportStatus::Added, None),
+            import(MessageImportStatus::AlreadyIncluded, None),
+            import(MessageImportStatus::AlreadyPending, None),
+            import(
+                MessageImportStatus::Rejected,
+                Some("Not enough funds to execute transaction"),
+            ),
+        ];
+        assert_eq!(
+            ImportSummary::new(&imports, 2),
+            ImportSummary {
+                added: 2,
+                already_pending: 1,
+                already_included: 1,
+                rejected: 1,
+                invalid: 2,
+            }
+        );
     }
 }
@@ -21,6 +21,9 @@ pub enum NetCommands {
         /// Print agent name
         #[arg(short, long)]
         agent: bool,
+        /// List the peers remembered across restarts instead of the connected ones
+        #[arg(long, conflicts_with = "agent")]
+        persisted: bool,
     },
     /// Connects to a peer by its peer ID and multi-addresses
     Connect {
@@ -33,7 +36,16 @@ pub enum NetCommands {
         id: String,
     },
     /// Print information about reachability from the internet
-    Reachability,
+    Reachability {
+        /// Also print the listen addresses, the addresses observed by peers and the connection directions
+        #[arg(short, long)]
+        verbose: bool,
+    },
+    /// Prints the latest `libp2p` ping round-trip time to a connected peer
+    Ping {
+        /// Peer ID to ping
+        id: String,
+    },
 }
 
 impl NetCommands {
@@ -60,7 +72,24 @@ impl NetCommands {
                 println!("num established: {}", info.num_established);
                 Ok(())
             }
-            Self::Peers { agent } => {
+            Self::Peers {
+                persisted: true, ..
+            } => {
+                let peers = NetPersistedPeers::call(&client, ()).await?;
+                for peer in peers {
+                    let last_seen = chrono::DateTime::from_timestamp(peer.last_seen as i64, 0)
+                        .map(|t| t.to_rfc3339())
+                        .unwrap_or_else(|| peer.last_seen.to_string());
+                    println!(
+                        "{}, score: {:.1}, last seen: {last_seen}, [{}]",
+                        peer.peer_id,
+                        peer.score,
+                        peer.addrs.iter().join(", ")
+                    );
+                }
+                Ok(())
+            }
+            Self::Peers { agent, .. } => {
                 let addrs = NetPeers::call(&client, ()).await?;
                 let peer_to_agents: HashMap<String, String> = if agent {
                     let agents = futures::future::join_all(
@@ -157,7 +186,7 @@ impl NetCommands {
                 println!("disconnect {id}: success");
                 Ok(())
             }
-            Self::Reachability => {
+            Self::Reachability { verbose: false } => {
                 let nat_status = NetAutoNatStatus::call(&client, ()).await?;
                 println!("AutoNAT status:  {}", nat_status.reachability_as_str());
                 if let Some(public_addrs) = nat_status.public_addrs {
@@ -169,6 +198,43 @@ impl NetCommands {
                 }
                 Ok(())
             }
+            Self::Reachability { verbose: true } => {
+                let reachability = NetReachability::call(&client, ()).await?;
+                println!(
+                    "AutoNAT status:  {}",
+                    reachability.nat_status.reachability_as_str()
+                );
+                println!("Listen addresses:");
+                for addr in &reachability.listen_addrs {
+                    println!("  {addr}");
+                }
+                println!("External addresses:");
+                for addr in &reachability.external_addrs {
+                    println!("  {addr}");
+                }
+                println!("Observed addresses:");
+                for observed in &reachability.observed_addrs {
+                    println!(
+                        "  {} (reported by {} peers)",
+                        observed.addr, observed.num_peers
+                    );
+                }
+                println!(
+                    "Connections: {} inbound, {} outbound",
+                    reachability.num_inbound, reachability.num_outbound
+                );
+                if reachability.num_inbound == 0 && reachability.num_outbound > 0 {
+                    println!(
+                        "No inbound connections, the node might not be reachable from the internet"
+                    );
+                }
+                Ok(())
+            }
+            Self::Ping { id } => {
+                let rtt = NetPing::call(&client, (id.clone(),)).await?;
+                println!("{id}: {rtt:?}");
+                Ok(())
+            }
         }
     }
 }
@@ -4,8 +4,10 @@
 use super::*;
 use crate::chain_sync::SyncConfig;
 use crate::cli_shared::snapshot::{self, TrustedVendor};
+use crate::db::car::indexed::CARV2_FILE_EXTENSION;
 use crate::rpc::types::ApiTipsetKey;
 use crate::rpc::{self, chain::ChainExportParams, prelude::*};
+use crate::shim::clock::ChainEpoch;
 use anyhow::Context as _;
 use chrono::DateTime;
 use clap::Subcommand;
@@ -34,6 +36,19 @@ pub enum SnapshotCommands {
         /// How many state-roots to include. Lower limit is 900 for `calibnet` and `mainnet`.
         #[arg(short, long)]
         depth: Option<crate::chain::ChainEpochDelta>,
+        /// Also include the full state trees of the tipsets in the inclusive epoch range, e.g.
+        /// `1000:2000`.
+        #[arg(long, value_name = "FROM:TO", value_parser = parse_epoch_range)]
+        include_state: Option<(ChainEpoch, ChainEpoch)>,
+        /// CAR format version of the snapshot. Version 2 writes an uncompressed, indexed `CARv2`
+        /// file that can be imported without copying.
+        #[arg(long, default_value_t = 1)]
+        car_version: u64,
+        /// Walk the graph with all the CPUs, in an order that differs from an export to the next.
+        /// By default, the graph is walked by a single task so that exporting the same tipset
+        /// twice writes the same file.
+        #[arg(long)]
+        parallel: bool,
     },
 }
 
@@ -46,6 +61,9 @@ impl SnapshotCommands {
                 dry_run,
                 tipset,
                 depth,
+                include_state,
+                car_version,
+                parallel,
             } => {
                 let chain_head = ChainHead::call(&client, ()).await?;
 
@@ -59,16 +77,25 @@ impl SnapshotCommands {
                     ChainGetTipSetByHeight::call(&client, (epoch, Default::default())).await?;
 
                 let output_path = match output_path.is_dir() {
-                    true => output_path.join(snapshot::filename(
-                        TrustedVendor::Forest,
-                        chain_name,
-                        DateTime::from_timestamp(tipset.min_ticket_block().timestamp as i64, 0)
-                            .unwrap_or_default()
-                            .naive_utc()
-                            .date(),
-                        epoch,
-                        true,
-                    )),
+                    true => {
+                        let filename = snapshot::filename(
+                            TrustedVendor::Forest,
+                            chain_name,
+                            DateTime::from_timestamp(tipset.min_ticket_block().timestamp as i64, 0)
+                                .unwrap_or_default()
+                                .naive_utc()
+                                .date(),
+                            epoch,
+                            car_version == 1,
+                        );
+                        output_path.join(match car_version {
+                            2 => format!(
+                                "{}{CARV2_FILE_EXTENSION}",
+                                filename.trim_end_matches(".car.zst")
+                            ),
+                            _ => filename,
+                        })
+                    }
                     false => output_path.clone(),
                 };
 
@@ -82,6 +109,9 @@ impl SnapshotCommands {
                     tipset_keys: ApiTipsetKey(Some(chain_head.key().clone())),
                     skip_checksum,
                     dry_run,
+                    include_state,
+                    car_version,
+                    parallel,
                 };
 
                 let handle = tokio::spawn({
@@ -131,6 +161,15 @@ impl SnapshotCommands {
     }
 }
 
+fn parse_epoch_range(s: &str) -> anyhow::Result<(ChainEpoch, ChainEpoch)> {
+    let (from, to) = s
+        .split_once(':')
+        .context("expected an epoch range in the `<from>:<to>` format")?;
+    let (from, to) = (from.parse()?, to.parse()?);
+    anyhow::ensure!(from <= to, "the start of the range is after its end");
+    Ok((from, to))
+}
+
 /// Prints hex-encoded representation of SHA-256 checksum and saves it to a file
 /// with the same name but with a `.sha256sum` extension.
 async fn save_checksum(source: &Path, encoded_hash: String) -> anyhow::Result<()> {
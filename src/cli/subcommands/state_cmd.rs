@@ -4,12 +4,18 @@
 use std::path::PathBuf;
 use std::time::Duration;
 
-use crate::rpc::state::StateCompute;
-use crate::rpc::{self, prelude::*};
+use crate::cli::humantoken::TokenAmountPretty as _;
+use crate::rpc::state::{ForestStateCompute, ForestStateMessageGasCost, ProvingWindow};
+use crate::rpc::{self, prelude::*, types::ApiTipsetKey};
+use crate::shim::address::{Address, StrictAddress};
 use crate::shim::clock::ChainEpoch;
 use crate::shim::econ::TokenAmount;
+use crate::utils::bitfield::bitfield_of;
+use anyhow::Context as _;
+use base64::{prelude::BASE64_STANDARD, Engine as _};
+use chrono::DateTime;
 use cid::Cid;
-use clap::Subcommand;
+use clap::{ArgGroup, Subcommand};
 use serde_tuple::{self, Deserialize_tuple, Serialize_tuple};
 
 #[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug)]
@@ -36,6 +42,64 @@ pub enum StateCommands {
         #[arg(long)]
         epoch: ChainEpoch,
     },
+    /// Show the upcoming proving windows of each deadline of a miner
+    ProvingSchedule {
+        /// Miner address
+        miner: StrictAddress,
+        /// Print the schedule as an iCalendar file
+        #[arg(long)]
+        ics: bool,
+    },
+    /// Estimate the daily fault fee and the termination penalty of sectors of
+    /// a miner, at the heaviest tipset
+    SectorPenalties {
+        /// Miner address
+        miner: StrictAddress,
+        /// Sector numbers
+        #[arg(required = true)]
+        sectors: Vec<u64>,
+    },
+    /// Export the state of an actor, and the blocks it links to, to a `.car`
+    /// file written by the node
+    ExportActor {
+        /// Actor address
+        actor: StrictAddress,
+        /// Epoch of the tipset to read the state at, the heaviest tipset if
+        /// omitted
+        #[arg(long)]
+        tipset: Option<ChainEpoch>,
+        /// Levels of links followed from the state, all of them if omitted
+        #[arg(long)]
+        depth: Option<u32>,
+        /// The `.car` file path to save the state to
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Show where the FIL spent on gas by an executed message went
+    MsgCost {
+        /// Message CID
+        message_cid: Cid,
+    },
+    /// Draw randomness from the ticket chain or the beacon, as seen by the VM at the heaviest
+    /// tipset
+    #[command(group(ArgGroup::new("source").required(true).args(["beacon", "tickets"])))]
+    Randomness {
+        /// Draw randomness from the beacon
+        #[arg(long)]
+        beacon: bool,
+        /// Draw randomness from the ticket chain
+        #[arg(long)]
+        tickets: bool,
+        /// Domain separation tag, e.g. `2` for `ElectionProofProduction`
+        #[arg(long)]
+        personalization: i64,
+        /// Epoch to draw randomness for
+        #[arg(long)]
+        epoch: ChainEpoch,
+        /// Base64-encoded entropy
+        #[arg(long, default_value = "")]
+        entropy: String,
+    },
 }
 
 impl StateCommands {
@@ -51,11 +115,170 @@ impl StateCommands {
             }
             StateCommands::Compute { epoch } => {
                 let ret = client
-                    .call(StateCompute::request((epoch,))?.with_timeout(Duration::MAX))
+                    .call(ForestStateCompute::request((epoch,))?.with_timeout(Duration::MAX))
                     .await?;
                 println!("{ret}");
             }
+            Self::ProvingSchedule { miner, ics } => {
+                let miner = miner.into();
+                let mut windows =
+                    MinerProvingSchedule::call(&client, (miner, ApiTipsetKey(None))).await?;
+                windows.sort_by_key(|window| window.open);
+                if ics {
+                    print!("{}", proving_schedule_ics(&miner, &windows));
+                } else {
+                    for window in windows {
+                        println!(
+                            "Deadline {:>2}: epochs {}..{} ({} - {}), partitions due: {}, PoSt submitted: {}",
+                            window.deadline,
+                            window.open,
+                            window.close,
+                            format_time(window.open_time, "%Y-%m-%d %H:%M:%S UTC"),
+                            format_time(window.close_time, "%Y-%m-%d %H:%M:%S UTC"),
+                            window.partitions_due.len(),
+                            window.post_submitted,
+                        );
+                    }
+                }
+            }
+            Self::SectorPenalties { miner, sectors } => {
+                let penalties = StateMinerSectorPenalties::call(
+                    &client,
+                    (miner.into(), bitfield_of(sectors), ApiTipsetKey(None)),
+                )
+                .await?;
+                for sector in &penalties.sectors {
+                    println!(
+                        "Sector {}: daily fault fee: {}, termination penalty: {}",
+                        sector.sector_number,
+                        sector.daily_fault_fee.pretty(),
+                        sector.termination_penalty.pretty()
+                    );
+                }
+                println!(
+                    "Total: daily fault fee: {}, termination penalty: {}",
+                    penalties.total_daily_fault_fee.pretty(),
+                    penalties.total_termination_penalty.pretty()
+                );
+            }
+            Self::ExportActor {
+                actor,
+                tipset,
+                depth,
+                output,
+            } => {
+                let tsk = match tipset {
+                    Some(epoch) => {
+                        let ts = ChainGetTipSetByHeight::call(&client, (epoch, ApiTipsetKey(None)))
+                            .await?;
+                        ApiTipsetKey(Some(ts.key().clone()))
+                    }
+                    None => ApiTipsetKey(None),
+                };
+                let actor = StateGetActor::call(&client, (actor.into(), tsk))
+                    .await?
+                    .context("actor not found")?;
+                // The node writes the file, relative paths being resolved here
+                let output = std::path::absolute(output)?;
+                let blocks = client
+                    .call(
+                        ForestExportSubgraph::request((actor.state, depth, None, output.clone()))?
+                            .with_timeout(Duration::MAX),
+                    )
+                    .await?;
+                println!(
+                    "Exported {blocks} blocks of state {} to {}",
+                    actor.state,
+                    output.display()
+                );
+            }
+            Self::MsgCost { message_cid } => {
+                let cost =
+                    ForestStateMessageGasCost::call(&client, (message_cid, ApiTipsetKey(None)))
+                        .await?;
+                println!("Gas used:             {}", cost.gas_used.atto());
+                println!("Base fee burn:        {}", cost.base_fee_burn.pretty());
+                println!(
+                    "Over-estimation burn: {}",
+                    cost.over_estimation_burn.pretty()
+                );
+                println!("Miner tip:            {}", cost.miner_tip.pretty());
+                println!("Miner penalty:        {}", cost.miner_penalty.pretty());
+                println!("Refund:               {}", cost.refund.pretty());
+                println!("Total cost:           {}", cost.total_cost.pretty());
+            }
+            Self::Randomness {
+                beacon,
+                tickets: _,
+                personalization,
+                epoch,
+                entropy,
+            } => {
+                let entropy = BASE64_STANDARD
+                    .decode(entropy)
+                    .context("entropy is not valid base64")?;
+                let params = (personalization, epoch, entropy, ApiTipsetKey(None));
+                let randomness = if beacon {
+                    StateGetRandomnessFromBeacon::call(&client, params).await?
+                } else {
+                    StateGetRandomnessFromTickets::call(&client, params).await?
+                };
+                println!("{}", BASE64_STANDARD.encode(randomness));
+            }
         }
         Ok(())
     }
 }
+
+fn format_time(timestamp: u64, fmt: &str) -> String {
+    DateTime::from_timestamp(timestamp as i64, 0)
+        .unwrap_or_default()
+        .format(fmt)
+        .to_string()
+}
+
+/// Renders proving windows as an iCalendar (RFC 5545) feed with one event per window.
+fn proving_schedule_ics(miner: &Address, windows: &[ProvingWindow]) -> String {
+    const ICS_TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+    let now = format_time(chrono::Utc::now().timestamp() as u64, ICS_TIME_FORMAT);
+    let mut ics = String::new();
+    // iCalendar lines are terminated by CRLF
+    let mut line = |s: String| {
+        ics.push_str(&s);
+        ics.push_str("\r\n");
+    };
+    line("BEGIN:VCALENDAR".into());
+    line("VERSION:2.0".into());
+    line("PRODID:-//ChainSafe//Forest//EN".into());
+    line(format!("X-WR-CALNAME:{miner} proving schedule"));
+    for window in windows {
+        line("BEGIN:VEVENT".into());
+        line(format!(
+            "UID:{miner}-{}-{}@forest",
+            window.deadline, window.open
+        ));
+        line(format!("DTSTAMP:{now}"));
+        line(format!(
+            "DTSTART:{}",
+            format_time(window.open_time, ICS_TIME_FORMAT)
+        ));
+        line(format!(
+            "DTEND:{}",
+            format_time(window.close_time, ICS_TIME_FORMAT)
+        ));
+        line(format!(
+            "SUMMARY:{miner} deadline {} proving window",
+            window.deadline
+        ));
+        line(format!(
+            "DESCRIPTION:Epochs {}..{}\\nPartitions due: {}\\nPoSt submitted: {}",
+            window.open,
+            window.close,
+            window.partitions_due.len(),
+            window.post_submitted,
+        ));
+        line("END:VEVENT".into());
+    }
+    line("END:VCALENDAR".into());
+    ics
+}
@@ -40,6 +40,8 @@ impl Default for BufferSize {
 pub struct Client {
     pub data_dir: PathBuf,
     pub genesis_file: Option<PathBuf>,
+    /// Definition of a custom network, see [`crate::networks::CustomNetwork`]
+    pub chain_config: Option<PathBuf>,
     pub enable_rpc: bool,
     pub enable_metrics_endpoint: bool,
     pub enable_health_check: bool,
@@ -66,6 +68,19 @@ pub struct Client {
     pub load_actors: bool,
     /// `TTL` to set for Ethereum `Hash` to `Cid` entries or `None` to never reclaim them.
     pub eth_mapping_ttl: Option<u32>,
+    /// Interval, in seconds, at which the message pool rebroadcasts the pending
+    /// messages that have been waiting for at least as long, or `None` to
+    /// disable.
+    pub mpool_rebroadcast_interval: Option<u64>,
+    /// Tracing filter directives, e.g. `info,forest::chain_sync=debug`,
+    /// replacing the default filter and `RUST_LOG`. Reloadable.
+    pub log_filter: Option<String>,
+    /// Interval, in seconds, between the steps of the database garbage
+    /// collection. Reloadable.
+    pub gc_interval: u64,
+    /// Maximum number of RPC calls per second, across all connections, or
+    /// `None` for no limit. Reloadable.
+    pub rpc_rate_limit: Option<u32>,
 }
 
 impl Default for Client {
@@ -74,6 +89,7 @@ impl Default for Client {
         Self {
             data_dir: dir.data_dir().to_path_buf(),
             genesis_file: None,
+            chain_config: None,
             enable_rpc: true,
             enable_metrics_endpoint: true,
             enable_health_check: true,
@@ -93,6 +109,10 @@ impl Default for Client {
             ),
             load_actors: true,
             eth_mapping_ttl: None,
+            mpool_rebroadcast_interval: None,
+            log_filter: None,
+            gc_interval: 60 * 60 * 10,
+            rpc_rate_limit: None,
         }
     }
 }
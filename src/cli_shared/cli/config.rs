@@ -1,9 +1,13 @@
 // Copyright 2019-2025 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use crate::chain_sync::SyncConfig;
 use crate::db::db_engine::DbConfig;
+use crate::journal::JournalConfig;
 use crate::libp2p::Libp2pConfig;
-use crate::{chain_sync::SyncConfig, networks::NetworkChain};
+use crate::networks::{ChainConfig, CustomNetwork, NetworkChain};
+use crate::state_manager::shadow_execution::DiagnosticsConfig;
+use crate::utils::cache::CacheConfig;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -46,6 +50,9 @@ pub struct Config {
     pub network: Libp2pConfig,
     pub sync: SyncConfig,
     pub daemon: DaemonConfig,
+    pub cache: CacheConfig,
+    pub journal: JournalConfig,
+    pub diagnostics: DiagnosticsConfig,
 }
 
 impl Config {
@@ -56,6 +63,15 @@ impl Config {
     pub fn chain(&self) -> &NetworkChain {
         &self.chain
     }
+
+    /// Returns the configuration of the chain, loaded from the definition of
+    /// the custom network if one is set.
+    pub fn chain_config(&self) -> anyhow::Result<ChainConfig> {
+        match &self.client.chain_config {
+            Some(path) => Ok(CustomNetwork::load(path)?.chain_config),
+            None => Ok(ChainConfig::from_chain(&self.chain)),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -9,7 +9,7 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use crate::networks::NetworkChain;
+use crate::networks::{CustomNetwork, NetworkChain, CUSTOM_NETWORK_CHAIN};
 use crate::utils::misc::LoggingColor;
 use crate::{cli_shared::read_config, daemon::db_util::ImportMode};
 use ahash::HashSet;
@@ -36,7 +36,7 @@ OPTIONS:
 ";
 
 /// CLI options
-#[derive(Default, Debug, Parser)]
+#[derive(Default, Debug, Clone, Parser)]
 pub struct CliOpts {
     /// A TOML file containing relevant configurations
     #[arg(long)]
@@ -84,12 +84,17 @@ pub struct CliOpts {
     /// Import a snapshot from a local CAR file or URL
     #[arg(long)]
     pub import_snapshot: Option<String>,
-    /// Snapshot import mode. Available modes are `auto`, `copy`, `move`, `symlink` and `hardlink`.
+    /// Snapshot import mode. Available modes are `auto`, `copy`, `move`, `symlink`, `hardlink` and
+    /// `no-copy`.
     #[arg(long, default_value = "auto")]
     pub import_mode: ImportMode,
     /// Halt with exit code 0 after successfully importing a snapshot
     #[arg(long)]
     pub halt_after_import: bool,
+    /// Skip building the chain indexes after importing a snapshot. They can be
+    /// built later with `forest-tool index rebuild`
+    #[arg(long)]
+    pub skip_indexing: bool,
     /// Skips loading CAR file and uses header to index chain. Assumes a
     /// pre-loaded database
     #[arg(long)]
@@ -104,12 +109,21 @@ pub struct CliOpts {
     /// Amount of Peers we want to be connected to (default is 75)
     #[arg(long)]
     pub target_peer_count: Option<u32>,
+    /// Rebroadcast pending messages that have been in the message pool for
+    /// this many seconds, checking at the same interval (default: disabled)
+    #[arg(long)]
+    pub rebroadcast_interval: Option<u64>,
     /// Encrypt the key-store (default: true)
     #[arg(long)]
     pub encrypt_keystore: Option<bool>,
-    /// Choose network chain to sync to
+    /// Choose network chain to sync to, `custom` for the network defined by
+    /// `--chain-config`
     #[arg(long)]
     pub chain: Option<NetworkChain>,
+    /// A TOML file defining a custom network: genesis, upgrade heights,
+    /// policy, bootstrap peers and `drand` schedule
+    #[arg(long)]
+    pub chain_config: Option<PathBuf>,
     /// Daemonize Forest process
     #[arg(long)]
     pub detach: bool,
@@ -145,9 +159,21 @@ pub struct CliOpts {
     /// Disable the automatic database garbage collection.
     #[arg(long)]
     pub no_gc: bool,
+    /// Remove the block headers of abandoned forks after each garbage collection run.
+    #[arg(long, conflicts_with = "no_gc")]
+    pub cleanup_orphans: bool,
     /// In stateless mode, forest connects to the P2P network but does not sync to HEAD.
     #[arg(long)]
     pub stateless: bool,
+    /// In lite mode, forest tracks the chain head by validating block headers and beacon
+    /// entries only. Messages are not executed and RPC methods that require state are
+    /// unavailable.
+    #[arg(long)]
+    pub lite: bool,
+    /// Verify the signatures of all beacon entries instead of skipping the rounds verified
+    /// before.
+    #[arg(long)]
+    pub distrust_beacon_cache: bool,
     /// Check your command-line options and configuration file if one is used
     #[arg(long)]
     pub dry_run: bool,
@@ -160,6 +186,35 @@ impl CliOpts {
     pub fn to_config(&self) -> Result<(Config, Option<ConfigPath>), anyhow::Error> {
         let (path, mut cfg) = read_config(self.config.as_ref(), self.chain.clone())?;
 
+        let is_custom_chain = self
+            .chain
+            .as_ref()
+            .is_some_and(|chain| chain == &NetworkChain::Devnet(CUSTOM_NETWORK_CHAIN.into()));
+        // The definition of a custom network is given by `--chain-config` or
+        // by `client.chain_config` in the configuration file, and sets the
+        // chain and its genesis either way.
+        match self
+            .chain_config
+            .clone()
+            .or_else(|| cfg.client.chain_config.clone())
+        {
+            Some(chain_config) => {
+                anyhow::ensure!(
+                    self.chain.is_none() || is_custom_chain,
+                    "--chain-config and client.chain_config require --chain {}",
+                    CUSTOM_NETWORK_CHAIN
+                );
+                let network = CustomNetwork::load(&chain_config)?;
+                cfg.chain = network.chain_config.network.clone();
+                cfg.client.genesis_file = Some(network.genesis);
+                cfg.client.chain_config = Some(chain_config);
+            }
+            None => anyhow::ensure!(
+                !is_custom_chain,
+                "--chain {} requires --chain-config",
+                CUSTOM_NETWORK_CHAIN
+            ),
+        }
         if let Some(genesis_file) = &self.genesis {
             cfg.client.genesis_file = Some(genesis_file.to_owned());
         }
@@ -219,6 +274,15 @@ impl CliOpts {
         if let Some(tipset_sample_size) = self.tipset_sample_size {
             cfg.sync.tipset_sample_size = tipset_sample_size.into();
         }
+        if self.lite {
+            cfg.sync.lite = true;
+        }
+        if self.distrust_beacon_cache {
+            cfg.sync.distrust_beacon_cache = true;
+        }
+        if let Some(rebroadcast_interval) = self.rebroadcast_interval {
+            cfg.client.mpool_rebroadcast_interval = Some(rebroadcast_interval);
+        }
         if let Some(encrypt_keystore) = self.encrypt_keystore {
             cfg.client.encrypt_keystore = encrypt_keystore;
         }
@@ -400,4 +464,69 @@ mod tests {
         };
         assert!(options.to_config().is_ok());
     }
+
+    #[test]
+    fn custom_chain_requires_chain_config() {
+        let options = CliOpts {
+            chain: Some(NetworkChain::Devnet(CUSTOM_NETWORK_CHAIN.into())),
+            ..Default::default()
+        };
+        assert!(options.to_config().is_err());
+
+        let options = CliOpts {
+            chain: Some(NetworkChain::Calibnet),
+            chain_config: Some("localnet.toml".into()),
+            ..Default::default()
+        };
+        assert!(options.to_config().is_err());
+    }
+
+    #[test]
+    fn chain_config_from_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let definition = dir.path().join("localnet.toml");
+        std::fs::write(
+            &definition,
+            r#"
+            name = "localnet"
+            genesis = "genesis.car"
+            genesis_network_version = 21
+            "#,
+        )
+        .unwrap();
+        let config = dir.path().join("config.toml");
+        std::fs::write(
+            &config,
+            format!(
+                "[client]\nchain_config = {:?}\n",
+                definition.display().to_string()
+            ),
+        )
+        .unwrap();
+
+        // The definition in the configuration file sets the chain and genesis
+        // like the flag does
+        let options = CliOpts {
+            config: Some(config.clone()),
+            ..Default::default()
+        };
+        let (cfg, _) = options.to_config().unwrap();
+        assert_eq!(cfg.chain, NetworkChain::Devnet("localnet".into()));
+        assert_eq!(
+            cfg.client.genesis_file,
+            Some(dir.path().join("genesis.car"))
+        );
+        assert_eq!(
+            cfg.chain_config().unwrap().network,
+            NetworkChain::Devnet("localnet".into())
+        );
+
+        // It conflicts with another chain given on the command line
+        let options = CliOpts {
+            config: Some(config),
+            chain: Some(NetworkChain::Calibnet),
+            ..Default::default()
+        };
+        assert!(options.to_config().is_err());
+    }
 }
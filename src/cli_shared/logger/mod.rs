@@ -4,13 +4,20 @@
 use std::pin::Pin;
 
 use futures::Future;
-use tracing_subscriber::{prelude::*, EnvFilter, Registry};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use tracing_subscriber::{prelude::*, reload, EnvFilter, Registry};
 
 use crate::cli_shared::cli::CliOpts;
 use crate::utils::misc::LoggingColor;
 
 type BackgroundTask = Pin<Box<dyn Future<Output = ()> + Send>>;
 
+/// Handles to the filters of the console and file loggers, to replace them at
+/// runtime with [`set_log_filter`].
+static LOG_FILTERS: Lazy<Mutex<Vec<reload::Handle<EnvFilter, Registry>>>> =
+    Lazy::new(Default::default);
+
 #[derive(Default)]
 pub struct Guards {
     #[cfg(feature = "tracing-chrome")]
@@ -26,7 +33,7 @@ pub fn setup_logger(opts: &CliOpts) -> (Vec<BackgroundTask>, Guards) {
         vec![Box::new(
             tracing_subscriber::fmt::Layer::new()
                 .with_ansi(opts.color.coloring_enabled())
-                .with_filter(get_env_filter(default_env_filter())),
+                .with_filter(reloadable(get_env_filter(default_env_filter()))),
         )];
 
     // file logger
@@ -36,7 +43,7 @@ pub fn setup_logger(opts: &CliOpts) -> (Vec<BackgroundTask>, Guards) {
             tracing_subscriber::fmt::Layer::new()
                 .with_ansi(false)
                 .with_writer(file_appender)
-                .with_filter(get_env_filter(default_env_filter())),
+                .with_filter(reloadable(get_env_filter(default_env_filter()))),
         ));
     }
 
@@ -120,6 +127,28 @@ pub fn setup_minimal_logger() {
         .init();
 }
 
+/// Replaces the filter of the loggers set up by [`setup_logger`] with the
+/// given directives, or restores the startup filter if `None`.
+pub fn set_log_filter(directives: Option<&str>) -> anyhow::Result<()> {
+    let filter = || match directives {
+        Some(directives) => EnvFilter::try_new(directives),
+        None => Ok(get_env_filter(default_env_filter())),
+    };
+    // Validate the directives before replacing any filter
+    filter()?;
+    for handle in LOG_FILTERS.lock().iter() {
+        handle.reload(filter()?)?;
+    }
+    Ok(())
+}
+
+/// Wraps the filter so that it can be replaced by [`set_log_filter`].
+fn reloadable(filter: EnvFilter) -> reload::Layer<EnvFilter, Registry> {
+    let (filter, handle) = reload::Layer::new(filter);
+    LOG_FILTERS.lock().push(handle);
+    filter
+}
+
 /// Returns an [`EnvFilter`] according to the `RUST_LOG` environment variable, or a default
 /// - see [`default_env_filter`] and [`default_tool_filter`]
 ///
@@ -171,3 +200,29 @@ fn default_tool_filter() -> EnvFilter {
 fn test_default_env_filter() {
     let _did_not_panic = default_env_filter();
 }
+
+#[test]
+fn test_set_log_filter() {
+    use tracing::Level;
+
+    let subscriber = Registry::default().with(
+        tracing_subscriber::fmt::Layer::new()
+            .with_writer(std::io::sink)
+            .with_filter(reloadable(EnvFilter::new("info"))),
+    );
+    tracing::subscriber::with_default(subscriber, || {
+        assert!(tracing::enabled!(Level::INFO));
+        assert!(!tracing::enabled!(Level::DEBUG));
+
+        set_log_filter(Some("debug")).unwrap();
+        assert!(tracing::enabled!(Level::DEBUG));
+
+        set_log_filter(Some("warn")).unwrap();
+        assert!(!tracing::enabled!(Level::INFO));
+
+        // Invalid directives leave the filter unchanged
+        assert!(set_log_filter(Some("forest=verbose")).is_err());
+        assert!(tracing::enabled!(Level::WARN));
+        assert!(!tracing::enabled!(Level::INFO));
+    });
+}
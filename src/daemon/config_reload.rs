@@ -0,0 +1,232 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Runtime reload of the daemon configuration, on `SIGHUP` or through the
+//! `Forest.ReloadConfig` RPC method. Only a subset of the settings is applied
+//! at runtime, the subsystems consuming them watching for changes. The other
+//! changed settings are reported as requiring a restart.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::cli_shared::{
+    cli::{CliOpts, Config},
+    logger,
+};
+use crate::lotus_json::lotus_json_with_self;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{watch, Mutex};
+use tracing::{info, warn};
+
+const LOG_FILTER: &str = "client.log_filter";
+const GC_INTERVAL: &str = "client.gc_interval";
+const RPC_RATE_LIMIT: &str = "client.rpc_rate_limit";
+const TARGET_PEER_COUNT: &str = "network.target_peer_count";
+
+/// Changed settings found by a reload, named by their path in the
+/// configuration file, e.g. `network.target_peer_count`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct ConfigReloadReport {
+    /// Settings applied at runtime
+    pub applied: Vec<String>,
+    /// Settings that only take effect after a restart
+    pub requires_restart: Vec<String>,
+}
+lotus_json_with_self!(ConfigReloadReport);
+
+pub struct ConfigReloader {
+    opts: CliOpts,
+    /// Configuration in effect, serializing the reloads
+    config: Mutex<Config>,
+    gc_interval: watch::Sender<Duration>,
+    rpc_rate_limit: watch::Sender<Option<u32>>,
+    target_peer_count: watch::Sender<u32>,
+}
+
+impl ConfigReloader {
+    /// Creates a reloader of the configuration that the daemon was started
+    /// with, re-reading it from the same file with the same command-line
+    /// overrides.
+    pub fn new(opts: CliOpts, config: Config) -> Self {
+        Self {
+            opts,
+            gc_interval: watch::Sender::new(Duration::from_secs(config.client.gc_interval)),
+            rpc_rate_limit: watch::Sender::new(config.client.rpc_rate_limit),
+            target_peer_count: watch::Sender::new(config.network.target_peer_count),
+            config: Mutex::new(config),
+        }
+    }
+
+    pub fn gc_interval(&self) -> watch::Receiver<Duration> {
+        self.gc_interval.subscribe()
+    }
+
+    pub fn rpc_rate_limit(&self) -> watch::Receiver<Option<u32>> {
+        self.rpc_rate_limit.subscribe()
+    }
+
+    pub fn target_peer_count(&self) -> watch::Receiver<u32> {
+        self.target_peer_count.subscribe()
+    }
+
+    /// Re-reads the configuration and applies the reloadable settings that
+    /// changed. Nothing is applied if the new configuration is invalid.
+    pub async fn reload(&self) -> anyhow::Result<ConfigReloadReport> {
+        let (new, _) = self.opts.to_config()?;
+        let mut config = self.config.lock().await;
+        let changed = changed_settings(&config, &new)?;
+
+        let mut report = ConfigReloadReport::default();
+        // Applied first, as the only setting that can be rejected
+        if changed.iter().any(|setting| setting == LOG_FILTER) {
+            logger::set_log_filter(new.client.log_filter.as_deref())?;
+            config.client.log_filter.clone_from(&new.client.log_filter);
+        }
+        for setting in changed {
+            match setting.as_str() {
+                LOG_FILTER => {}
+                GC_INTERVAL => {
+                    self.gc_interval
+                        .send_replace(Duration::from_secs(new.client.gc_interval));
+                    config.client.gc_interval = new.client.gc_interval;
+                }
+                RPC_RATE_LIMIT => {
+                    self.rpc_rate_limit.send_replace(new.client.rpc_rate_limit);
+                    config.client.rpc_rate_limit = new.client.rpc_rate_limit;
+                }
+                TARGET_PEER_COUNT => {
+                    self.target_peer_count
+                        .send_replace(new.network.target_peer_count);
+                    config.network.target_peer_count = new.network.target_peer_count;
+                }
+                _ => {
+                    report.requires_restart.push(setting);
+                    continue;
+                }
+            }
+            report.applied.push(setting);
+        }
+
+        info!(
+            "Reloaded configuration, applied: [{}]",
+            report.applied.join(", ")
+        );
+        if !report.requires_restart.is_empty() {
+            warn!(
+                "Configuration changes requiring a restart: [{}]",
+                report.requires_restart.join(", ")
+            );
+        }
+        Ok(report)
+    }
+}
+
+/// Returns the paths of the settings that differ between the configurations,
+/// in order.
+fn changed_settings(old: &Config, new: &Config) -> anyhow::Result<Vec<String>> {
+    let (old, new) = (flatten(old)?, flatten(new)?);
+    let mut changed: Vec<_> = old
+        .iter()
+        .filter(|(path, value)| new.get(*path) != Some(value))
+        .map(|(path, _)| path.clone())
+        .chain(new.keys().filter(|path| !old.contains_key(*path)).cloned())
+        .collect();
+    changed.sort();
+    Ok(changed)
+}
+
+/// Returns the settings of the configuration by path.
+fn flatten(config: &Config) -> anyhow::Result<BTreeMap<String, toml::Value>> {
+    fn walk(prefix: &str, value: toml::Value, settings: &mut BTreeMap<String, toml::Value>) {
+        match value {
+            toml::Value::Table(table) => {
+                for (key, value) in table {
+                    let path = match prefix {
+                        "" => key,
+                        _ => format!("{prefix}.{key}"),
+                    };
+                    walk(&path, value, settings);
+                }
+            }
+            value => {
+                settings.insert(prefix.to_owned(), value);
+            }
+        }
+    }
+    let mut settings = BTreeMap::new();
+    walk("", toml::Value::try_from(config)?, &mut settings);
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write as _;
+
+    #[test]
+    fn changed_settings_by_path() {
+        let old = Config::default();
+        let mut new = old.clone();
+        assert!(changed_settings(&old, &new).unwrap().is_empty());
+
+        new.network.target_peer_count += 1;
+        new.client.rpc_rate_limit = Some(100);
+        new.client.enable_rpc = !old.client.enable_rpc;
+        assert_eq!(
+            changed_settings(&old, &new).unwrap(),
+            [
+                "client.enable_rpc",
+                "client.rpc_rate_limit",
+                "network.target_peer_count"
+            ]
+        );
+        // Unset settings are reported too
+        assert_eq!(
+            changed_settings(&new, &old).unwrap(),
+            [
+                "client.enable_rpc",
+                "client.rpc_rate_limit",
+                "network.target_peer_count"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn reload() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        let opts = CliOpts {
+            config: Some(file.path().to_owned()),
+            ..Default::default()
+        };
+        let (config, _) = opts.to_config().unwrap();
+        let reloader = ConfigReloader::new(opts, config);
+        let (mut gc_interval, mut target_peer_count) =
+            (reloader.gc_interval(), reloader.target_peer_count());
+
+        assert_eq!(reloader.reload().await.unwrap(), Default::default());
+        assert!(!target_peer_count.has_changed().unwrap());
+
+        writeln!(
+            file,
+            "[client]\ngc_interval = 60\nrpc_address = \"127.0.0.1:1235\"\n[network]\ntarget_peer_count = 10"
+        )
+        .unwrap();
+        assert_eq!(
+            reloader.reload().await.unwrap(),
+            ConfigReloadReport {
+                applied: vec![GC_INTERVAL.into(), TARGET_PEER_COUNT.into()],
+                requires_restart: vec!["client.rpc_address".into()],
+            }
+        );
+        assert_eq!(*gc_interval.borrow_and_update(), Duration::from_secs(60));
+        assert_eq!(*target_peer_count.borrow_and_update(), 10);
+
+        // Settings requiring a restart are reported until then
+        let report = reloader.reload().await.unwrap();
+        assert!(report.applied.is_empty());
+        assert_eq!(report.requires_restart, ["client.rpc_address"]);
+        assert!(!target_peer_count.has_changed().unwrap());
+    }
+}
@@ -2,20 +2,29 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use crate::blocks::Tipset;
+use crate::chain::index::ResolveNullTipset;
+use crate::chain::ChainStore;
 use crate::cli_shared::snapshot;
 use crate::db::car::forest::FOREST_CAR_FILE_EXTENSION;
-use crate::db::car::{ForestCar, ManyCar};
+use crate::db::car::indexed::CARV2_FILE_EXTENSION;
+use crate::db::car::{AnyCar, ForestCar, IndexedCar, ManyCar};
+use crate::db::setting_keys::EVENT_BACKFILL_PROGRESS_KEY;
+use crate::db::{SettingsExt as _, SettingsStoreExt as _};
 use crate::networks::Height;
+use crate::shim::{clock::ChainEpoch, executor::StampedEvent};
 use crate::state_manager::StateManager;
 use crate::utils::db::car_stream::CarStream;
-use crate::utils::io::EitherMmapOrRandomAccessFile;
-use anyhow::{bail, Context};
-use futures::TryStreamExt;
+use crate::utils::io::{EitherMmapOrRandomAccessFile, WithProgressRaw};
+use anyhow::{bail, ensure, Context};
+use futures::{future::try_join_all, Future, TryStreamExt};
+use fvm_ipld_blockstore::Blockstore;
 use serde::{Deserialize, Serialize};
 use std::ffi::OsStr;
 use std::fs;
 use std::{
+    ops::RangeInclusive,
     path::{Path, PathBuf},
+    sync::Arc,
     time,
 };
 use tokio::io::AsyncWriteExt;
@@ -42,7 +51,9 @@ pub fn load_all_forest_cars<T>(store: &ManyCar<T>, forest_car_db_dir: &Path) ->
         .filter_map(|entry| {
             if let Ok(entry) = entry {
                 if let Some(filename) = entry.file_name().to_str() {
-                    if filename.ends_with(FOREST_CAR_FILE_EXTENSION) {
+                    if filename.ends_with(FOREST_CAR_FILE_EXTENSION)
+                        || filename.ends_with(CARV2_FILE_EXTENSION)
+                    {
                         return Some(entry.into_path());
                     }
                 }
@@ -50,9 +61,9 @@ pub fn load_all_forest_cars<T>(store: &ManyCar<T>, forest_car_db_dir: &Path) ->
             None
         })
     {
-        let car = ForestCar::try_from(file.as_path())
+        let car = AnyCar::try_from(file.as_path())
             .with_context(|| format!("Error loading car DB at {}", file.display()))?;
-        store.read_only(car.into())?;
+        store.read_only(car)?;
         debug!("Loaded car DB at {}", file.display());
     }
 
@@ -85,6 +96,10 @@ pub enum ImportMode {
     Symlink,
     /// Creates a symbolic link to the snapshot in the database directory.
     Hardlink,
+    /// Creates a symbolic link to an indexed `CARv2` (or `.forest.car.zst`) snapshot in the
+    /// database directory, and reads the blocks from it in place without trans-coding.
+    #[strum(serialize = "no-copy")]
+    NoCopy,
 }
 
 /// This function validates and stores the CAR binary from `from_path`(either local path or URL) into the `{DB_ROOT}/car_db/`
@@ -103,6 +118,7 @@ pub async fn import_chain_as_forest_car(
         chrono::Utc::now().timestamp_millis()
     ));
 
+    let mut car_db_path = forest_car_db_path.clone();
     let move_or_copy = |mode: ImportMode| {
         let forest_car_db_path = forest_car_db_path.clone();
         async move {
@@ -182,16 +198,35 @@ pub async fn import_chain_as_forest_car(
                 bail!("Snapshot file must be a valid forest.car.zst file");
             }
         }
+        ImportMode::NoCopy => {
+            let from_path = std::path::absolute(from_path)?;
+            let reader = EitherMmapOrRandomAccessFile::open(&from_path)?;
+            if IndexedCar::is_valid(&reader) {
+                car_db_path = forest_car_db_dir.join(format!(
+                    "{}{CARV2_FILE_EXTENSION}",
+                    chrono::Utc::now().timestamp_millis()
+                ));
+            } else if !ForestCar::is_valid(&reader) {
+                bail!("Snapshot file must be a valid CARv2 or forest.car.zst file");
+            }
+            tracing::info!(
+                "Symlinking {} to {}",
+                from_path.display(),
+                car_db_path.display()
+            );
+            std::os::unix::fs::symlink(from_path, &car_db_path)
+                .context("Error creating symlink")?;
+        }
     };
 
-    let ts = ForestCar::try_from(forest_car_db_path.as_path())?.heaviest_tipset()?;
+    let ts = AnyCar::try_from(car_db_path.as_path())?.heaviest_tipset()?;
     info!(
         "Imported snapshot in: {}s, heaviest tipset epoch: {}",
         stopwatch.elapsed().as_secs(),
         ts.epoch()
     );
 
-    Ok((forest_car_db_path, ts))
+    Ok((car_db_path, ts))
 }
 
 pub async fn download_to(url: &Url, destination: &Path) -> anyhow::Result<()> {
@@ -266,6 +301,7 @@ where
     DB: fvm_ipld_blockstore::Blockstore,
 {
     let mut delegated_messages = vec![];
+    let message_index_from = head.epoch() - chain_store.message_tipset_index().retention();
 
     let hygge = state_manager.chain_config().epoch(Height::Hygge);
     tracing::info!(
@@ -297,10 +333,312 @@ where
     Ok(())
 }
 
+/// Builds the indexes of an imported chain in a single walk from `head` down
+/// to genesis, so that they need not be built lazily after startup:
+/// - the epoch checkpoints used to look up old tipsets, see
+///   [`crate::chain::EpochCheckpointIndex`].
+/// - the Ethereum mappings, see [`populate_eth_mappings`].
+/// - the tipset including each recent message, see
+///   [`crate::chain::MessageTipsetIndex`].
+pub fn index_imported_chain<DB>(chain_store: &ChainStore<DB>, head: &Tipset) -> anyhow::Result<()>
+where
+    DB: fvm_ipld_blockstore::Blockstore,
+{
+    let stopwatch = time::Instant::now();
+    let hygge = chain_store.chain_config.epoch(Height::Hygge);
+    let mut checkpoints = chain_store.checkpoint_index().writer();
+    let mut delegated_messages = vec![];
+
+    #[allow(deprecated)] // Tracking issue: https://github.com/ChainSafe/forest/issues/3157
+    let wp = WithProgressRaw::new("Indexing", head.epoch().unsigned_abs());
+    for ts in head.clone().chain(chain_store.blockstore()) {
+        wp.set((head.epoch() - ts.epoch()).unsigned_abs());
+        checkpoints.push(&ts)?;
+        if ts.epoch() >= message_index_from {
+            chain_store.put_message_tipset_index(&ts)?;
+        }
+        if ts.epoch() >= hygge {
+            delegated_messages
+                .append(&mut chain_store.headers_delegated_messages(ts.block_headers().iter())?);
+            chain_store.put_tipset_key(ts.key())?;
+        }
+    }
+    drop(wp);
+    checkpoints.flush()?;
+    chain_store.process_signed_messages(&delegated_messages)?;
+    chain_store.settings().set_eth_mapping_up_to_date()?;
+    chain_store.message_tipset_index().set_built()?;
+
+    info!(
+        "Indexed chain from epoch {} in {}s",
+        head.epoch(),
+        stopwatch.elapsed().as_secs()
+    );
+    Ok(())
+}
+
+/// Progress of an event index backfill, recorded after each batch of tipsets.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct EventBackfillProgress {
+    from: ChainEpoch,
+    to: ChainEpoch,
+    /// Lowest epoch not backfilled yet
+    next: ChainEpoch,
+}
+
+/// Counts of tipsets processed by [`backfill_event_index`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EventBackfillStats {
+    /// Tipsets whose events were computed and indexed
+    pub indexed: usize,
+    /// Tipsets whose events were already indexed
+    pub skipped: usize,
+}
+
+/// Indexes the events of the tipsets of the `head` chain within `range`,
+/// computing them with `compute_events`, at most `concurrency` tipsets at a
+/// time. Tipsets already indexed are skipped, and an interrupted backfill of the
+/// same range resumes where it stopped. Backfilled tipsets are kept in the
+/// index regardless of its retention window. Fails upfront if the state needed to
+/// compute the events of any tipset of the range is missing, e.g. pruned.
+pub async fn backfill_event_index<DB, F, Fut>(
+    chain_store: &ChainStore<DB>,
+    head: Arc<Tipset>,
+    range: RangeInclusive<ChainEpoch>,
+    concurrency: usize,
+    compute_events: F,
+) -> anyhow::Result<EventBackfillStats>
+where
+    DB: Blockstore,
+    F: Fn(Arc<Tipset>) -> Fut,
+    Fut: Future<Output = anyhow::Result<Vec<Vec<StampedEvent>>>>,
+{
+    let (from, to) = (*range.start(), *range.end());
+    ensure!(
+        0 <= from && from <= to && to <= head.epoch(),
+        "invalid epoch range {from}..={to}, the head is at epoch {}",
+        head.epoch()
+    );
+    let stopwatch = time::Instant::now();
+    let settings = chain_store.settings();
+    let start = match settings.read_obj::<EventBackfillProgress>(EVENT_BACKFILL_PROGRESS_KEY)? {
+        Some(progress) if progress.from == from && progress.to == to => {
+            info!("Resuming events backfill from epoch {}", progress.next);
+            progress.next
+        }
+        _ => from,
+    };
+
+    let top = chain_store
+        .chain_index
+        .tipset_by_height(to, head, ResolveNullTipset::TakeOlder)?;
+    let mut tipsets = chain_store
+        .chain_index
+        .chain(top)
+        .take_while(|ts| ts.epoch() >= start)
+        .collect::<Vec<_>>();
+    tipsets.reverse();
+    if let Some(lowest) = tipsets.first() {
+        if lowest.epoch() > start
+            && lowest.epoch() > 0
+            && chain_store
+                .chain_index
+                .load_tipset(lowest.parents())?
+                .is_none()
+        {
+            bail!(
+                "tipsets below epoch {} are missing, the range {from}..={to} may have been pruned",
+                lowest.epoch()
+            );
+        }
+    }
+    for ts in &tipsets {
+        if !chain_store.blockstore().has(ts.parent_state())? {
+            bail!(
+                "the parent state {} of the tipset at epoch {} is missing, the range {from}..={to} may have been pruned",
+                ts.parent_state(),
+                ts.epoch()
+            );
+        }
+    }
+
+    let mut stats = EventBackfillStats::default();
+    #[allow(deprecated)] // Tracking issue: https://github.com/ChainSafe/forest/issues/3157
+    let wp = WithProgressRaw::new("Backfilling events", tipsets.len() as u64);
+    let event_index = chain_store.event_index();
+    for batch in tipsets.chunks(concurrency.max(1)) {
+        let mut pending = vec![];
+        for ts in batch {
+            if event_index.contains(ts)? {
+                stats.skipped += 1;
+            } else {
+                pending.push(ts.clone());
+            }
+        }
+        let events = try_join_all(pending.iter().cloned().map(&compute_events)).await?;
+        for (ts, events) in pending.iter().zip(events) {
+            event_index.put_backfilled(ts, &events)?;
+            stats.indexed += 1;
+        }
+        if let Some(last) = batch.last() {
+            settings.write_obj(
+                EVENT_BACKFILL_PROGRESS_KEY,
+                &EventBackfillProgress {
+                    from,
+                    to,
+                    next: last.epoch() + 1,
+                },
+            )?;
+        }
+        wp.set((stats.indexed + stats.skipped) as u64);
+    }
+    drop(wp);
+
+    info!(
+        "Backfilled the events of epochs {start}..={to} in {}s: {} tipsets indexed, {} already indexed",
+        stopwatch.elapsed().as_secs(),
+        stats.indexed,
+        stats.skipped
+    );
+    Ok(stats)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
+    #[tokio::test]
+    #[allow(unused_variables)]
+    async fn backfill_event_index_test() {
+        use crate::blocks::{chain4u, CachingBlockHeader, Chain4U, HeaderBuilder};
+        use crate::db::MemoryDB;
+        use crate::networks::ChainConfig;
+        use crate::utils::db::CborStoreExt as _;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let db = Arc::new(MemoryDB::default());
+        let state = db.put_cbor_default(&"state").unwrap();
+        let pruned = cid::Cid::default();
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            t0 @ [genesis = HeaderBuilder::new().with_state_root(state)]
+            -> t1 @ [b1 = HeaderBuilder::new().with_epoch(1).with_state_root(pruned)]
+            -> t2 @ [b2 = HeaderBuilder::new().with_epoch(2).with_state_root(state)]
+            -> t3 @ [b3 = HeaderBuilder::new().with_epoch(3).with_state_root(state)]
+            -> t5 @ [b5 = HeaderBuilder::new().with_epoch(5).with_state_root(state)]
+            -> t6 @ [b6 = HeaderBuilder::new().with_epoch(6).with_state_root(state)]
+        };
+        let cs = ChainStore::new(
+            db.clone(),
+            db.clone(),
+            db,
+            Arc::new(ChainConfig::default()),
+            CachingBlockHeader::new(genesis.clone()),
+        )
+        .unwrap();
+        let head = Arc::new(t6.clone());
+        // Synthetic events, one message emitting an event from the epoch ID
+        let computed = AtomicUsize::new(0);
+        let compute_events = |ts: Arc<Tipset>| {
+            computed.fetch_add(1, Ordering::Relaxed);
+            async move { Ok(vec![vec![StampedEvent::new(ts.epoch() as u64, vec![])]]) }
+        };
+
+        let stats = backfill_event_index(&cs, head.clone(), 2..=6, 2, compute_events)
+            .await
+            .unwrap();
+        assert_eq!(
+            stats,
+            EventBackfillStats {
+                indexed: 4,
+                skipped: 0
+            }
+        );
+        for ts in [&t2, &t3, &t5, &t6] {
+            assert_eq!(
+                cs.event_index().get(ts).unwrap(),
+                Some(vec![vec![StampedEvent::new(ts.epoch() as u64, vec![])]])
+            );
+        }
+        assert_eq!(computed.load(Ordering::Relaxed), 4);
+
+        // Indexed tipsets are not computed again
+        let stats = backfill_event_index(&cs, head.clone(), 3..=6, 2, compute_events)
+            .await
+            .unwrap();
+        assert_eq!(
+            stats,
+            EventBackfillStats {
+                indexed: 0,
+                skipped: 3
+            }
+        );
+        assert_eq!(computed.load(Ordering::Relaxed), 4);
+
+        // The state of epoch 1 is missing
+        backfill_event_index(&cs, head, 1..=6, 2, compute_events)
+            .await
+            .unwrap_err();
+        assert_eq!(computed.load(Ordering::Relaxed), 4);
+        assert!(!cs.event_index().contains(&t1).unwrap());
+    }
+
+    #[tokio::test]
+    #[allow(unused_variables)]
+    async fn backfill_event_index_resumes() {
+        use crate::blocks::{chain4u, CachingBlockHeader, Chain4U, HeaderBuilder};
+        use crate::db::MemoryDB;
+        use crate::networks::ChainConfig;
+        use crate::utils::db::CborStoreExt as _;
+
+        let db = Arc::new(MemoryDB::default());
+        let state = db.put_cbor_default(&"state").unwrap();
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            t0 @ [genesis = HeaderBuilder::new().with_state_root(state)]
+            -> t1 @ [b1 = HeaderBuilder::new().with_epoch(1).with_state_root(state)]
+            -> t2 @ [b2 = HeaderBuilder::new().with_epoch(2).with_state_root(state)]
+            -> t3 @ [b3 = HeaderBuilder::new().with_epoch(3).with_state_root(state)]
+        };
+        let cs = ChainStore::new(
+            db.clone(),
+            db.clone(),
+            db,
+            Arc::new(ChainConfig::default()),
+            CachingBlockHeader::new(genesis.clone()),
+        )
+        .unwrap();
+        let head = Arc::new(t3.clone());
+
+        // Fails on epoch 2, after the first batch is indexed
+        backfill_event_index(&cs, head.clone(), 1..=3, 1, |ts: Arc<Tipset>| async move {
+            ensure!(ts.epoch() != 2, "interrupted");
+            Ok(vec![])
+        })
+        .await
+        .unwrap_err();
+        assert!(cs.event_index().contains(&t1).unwrap());
+
+        let stats = backfill_event_index(&cs, head, 1..=3, 1, |ts: Arc<Tipset>| async move {
+            assert_ne!(ts.epoch(), 1);
+            Ok(vec![])
+        })
+        .await
+        .unwrap();
+        // Epoch 1 is not even visited
+        assert_eq!(
+            stats,
+            EventBackfillStats {
+                indexed: 2,
+                skipped: 0
+            }
+        );
+        assert!(cs.event_index().contains(&t3).unwrap());
+    }
+
     #[tokio::test]
     async fn import_snapshot_from_file_valid() {
         for import_mode in [ImportMode::Auto, ImportMode::Copy, ImportMode::Move] {
@@ -310,7 +648,11 @@ mod test {
         }
 
         // Linking is not supported for raw CAR files.
-        for import_mode in [ImportMode::Symlink, ImportMode::Hardlink] {
+        for import_mode in [
+            ImportMode::Symlink,
+            ImportMode::Hardlink,
+            ImportMode::NoCopy,
+        ] {
             import_snapshot_from_file("test-snapshots/chain4.car", import_mode)
                 .await
                 .unwrap_err();
@@ -341,6 +683,7 @@ mod test {
             ImportMode::Move,
             ImportMode::Symlink,
             ImportMode::Hardlink,
+            ImportMode::NoCopy,
         ] {
             import_snapshot_from_file("test-snapshots/chain4.forest.car.zst", import_mode)
                 .await
@@ -348,6 +691,58 @@ mod test {
         }
     }
 
+    #[tokio::test]
+    async fn import_snapshot_from_carv2_valid() {
+        let carv2 = tempfile::Builder::new().tempfile().unwrap();
+        let stream = CarStream::new(tokio::io::BufReader::new(
+            tokio::fs::File::open("test-snapshots/chain4.car")
+                .await
+                .unwrap(),
+        ))
+        .await
+        .unwrap();
+        let roots = stream.header.roots.clone();
+        crate::db::car::indexed::write_carv2(
+            tokio::fs::File::create(carv2.path()).await.unwrap(),
+            roots,
+            stream.map_err(anyhow::Error::from),
+        )
+        .await
+        .unwrap();
+        let carv2_path = carv2.path().to_str().unwrap();
+
+        for import_mode in [
+            ImportMode::Auto,
+            ImportMode::Copy,
+            ImportMode::Move,
+            ImportMode::NoCopy,
+        ] {
+            import_snapshot_from_file(carv2_path, import_mode)
+                .await
+                .unwrap();
+        }
+
+        // Only `.forest.car.zst` files can be linked
+        for import_mode in [ImportMode::Symlink, ImportMode::Hardlink] {
+            import_snapshot_from_file(carv2_path, import_mode)
+                .await
+                .unwrap_err();
+        }
+
+        // The linked CARv2 file is read in place
+        let temp_db_dir = tempfile::Builder::new().tempdir().unwrap();
+        let (path, ts) =
+            import_chain_as_forest_car(carv2.path(), temp_db_dir.path(), ImportMode::NoCopy)
+                .await
+                .unwrap();
+        assert!(path.to_str().unwrap().ends_with(CARV2_FILE_EXTENSION));
+        let store = ManyCar::new(crate::db::MemoryDB::default());
+        load_all_forest_cars(&store, temp_db_dir.path()).unwrap();
+        assert_eq!(store.heaviest_tipset().unwrap(), ts);
+        let block = ts.block_headers().first();
+        assert!(store.has(block.cid()).unwrap());
+    }
+
     #[tokio::test]
     async fn import_snapshot_from_file_invalid() {
         for import_mode in &[
@@ -356,6 +751,7 @@ mod test {
             ImportMode::Move,
             ImportMode::Symlink,
             ImportMode::Hardlink,
+            ImportMode::NoCopy,
         ] {
             import_snapshot_from_file("Cargo.toml", *import_mode)
                 .await
@@ -371,6 +767,7 @@ mod test {
             ImportMode::Move,
             ImportMode::Symlink,
             ImportMode::Hardlink,
+            ImportMode::NoCopy,
         ] {
             import_snapshot_from_file("dummy.car", *import_mode)
                 .await
@@ -386,6 +783,7 @@ mod test {
             ImportMode::Move,
             ImportMode::Symlink,
             ImportMode::Hardlink,
+            ImportMode::NoCopy,
         ] {
             import_snapshot_from_file("https://forest.chainsafe.io/dummy.car", *import_mode)
                 .await
@@ -406,7 +804,7 @@ mod test {
         let (path, ts) =
             import_chain_as_forest_car(file_path, temp_db_dir.path(), import_mode).await?;
         match import_mode {
-            ImportMode::Symlink => {
+            ImportMode::Symlink | ImportMode::NoCopy => {
                 assert_eq!(
                     std::path::absolute(path.read_link()?)?,
                     std::path::absolute(file_path)?
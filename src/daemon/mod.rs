@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 pub mod bundle;
+pub mod config_reload;
 pub mod db_util;
 pub mod main;
 
@@ -15,8 +16,9 @@ use crate::cli_shared::{
     cli::{CliOpts, Config},
 };
 
+use crate::daemon::config_reload::ConfigReloader;
 use crate::daemon::db_util::{
-    import_chain_as_forest_car, load_all_forest_cars, populate_eth_mappings,
+    import_chain_as_forest_car, index_imported_chain, load_all_forest_cars, populate_eth_mappings,
 };
 use crate::db::car::ManyCar;
 use crate::db::db_engine::{db_root, open_db};
@@ -25,7 +27,7 @@ use crate::genesis::{get_network_name_from_genesis, read_genesis_header};
 use crate::key_management::{
     KeyStore, KeyStoreConfig, ENCRYPTED_KEYSTORE_NAME, FOREST_KEYSTORE_PHRASE_ENV,
 };
-use crate::libp2p::{Libp2pConfig, Libp2pService, PeerManager};
+use crate::libp2p::{Libp2pConfig, Libp2pService, NetRPCMethods, NetworkMessage, PeerManager};
 use crate::message_pool::{MessagePool, MpoolConfig, MpoolRpcProvider};
 use crate::networks::{self, ChainConfig};
 use crate::rpc::eth::filter::EthEventHandler;
@@ -59,7 +61,7 @@ use tokio::{
         ctrl_c,
         unix::{signal, SignalKind},
     },
-    sync::{mpsc, RwLock},
+    sync::{mpsc, watch, RwLock},
     task::JoinSet,
 };
 use tracing::{debug, info, warn};
@@ -114,13 +116,19 @@ fn maybe_increase_fd_limit() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// How long the services get to persist their state on shutdown.
+const SERVICES_STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
 // Start the daemon and abort if we're interrupted by ctrl-c, SIGTERM, or `forest-cli shutdown`.
 pub async fn start_interruptable(opts: CliOpts, config: Config) -> anyhow::Result<()> {
     let mut terminate = signal(SignalKind::terminate())?;
     let (shutdown_send, mut shutdown_recv) = mpsc::channel(1);
+    let (stop_services, _) = watch::channel(false);
 
+    let daemon = start(opts, config, shutdown_send, &stop_services);
+    tokio::pin!(daemon);
     let result = tokio::select! {
-        ret = start(opts, config, shutdown_send) => ret,
+        ret = &mut daemon => ret,
         _ = ctrl_c() => {
             info!("Keyboard interrupt.");
             Ok(())
@@ -134,29 +142,41 @@ pub async fn start_interruptable(opts: CliOpts, config: Config) -> anyhow::Resul
             Ok(())
         },
     };
+    // Let the services listening for the signal wind down before their tasks
+    // are aborted along with the daemon.
+    stop_services.send_replace(true);
+    if tokio::time::timeout(SERVICES_STOP_TIMEOUT, stop_services.closed())
+        .await
+        .is_err()
+    {
+        warn!("Services did not stop within {SERVICES_STOP_TIMEOUT:?}");
+    }
+    drop(daemon);
     crate::utils::io::terminal_cleanup();
     result
 }
 
-// Garbage collection interval, currently set at 10 hours.
-const GC_INTERVAL: Duration = Duration::from_secs(60 * 60 * 10);
-
 /// Starts daemon process
 pub(super) async fn start(
     opts: CliOpts,
     config: Config,
     shutdown_send: mpsc::Sender<()>,
+    stop_services: &watch::Sender<bool>,
 ) -> anyhow::Result<()> {
-    let chain_config = Arc::new(ChainConfig::from_chain(config.chain()));
+    let chain_config = Arc::new(config.chain_config()?);
     if chain_config.is_testnet() {
         CurrentNetwork::set_global(Network::Testnet);
     }
+    config.cache.clone().set_global();
 
     info!(
         "Starting Forest daemon, version {}",
         FOREST_VERSION_STRING.as_str()
     );
     maybe_increase_fd_limit()?;
+    if let Some(log_filter) = &config.client.log_filter {
+        crate::cli_shared::logger::set_log_filter(Some(log_filter))?;
+    }
 
     let start_time = chrono::Utc::now();
     let path: PathBuf = config.client.data_dir.join("libp2p");
@@ -194,6 +214,21 @@ pub(super) async fn start(
 
     let mut services = JoinSet::new();
 
+    let config_reloader = Arc::new(ConfigReloader::new(opts.clone(), config.clone()));
+    {
+        let config_reloader = config_reloader.clone();
+        let mut hangup = signal(SignalKind::hangup())?;
+        services.spawn(async move {
+            while hangup.recv().await.is_some() {
+                info!("Received SIGHUP, reloading the configuration");
+                if let Err(e) = config_reloader.reload().await {
+                    warn!("Failed to reload the configuration: {e:#}");
+                }
+            }
+            Ok(())
+        });
+    }
+
     if opts.track_peak_rss {
         let mem_stats_tracker = MemStatsTracker::default();
         services.spawn(async move {
@@ -202,6 +237,13 @@ pub(super) async fn start(
         });
     }
 
+    if let Some(journal_writer) = crate::journal::init(
+        &config.journal,
+        &chain_data_path.join(crate::journal::JOURNAL_DIR_NAME),
+    )? {
+        services.spawn_blocking(journal_writer);
+    }
+
     // Read Genesis file
     // * When snapshot command implemented, this genesis does not need to be
     //   initialized
@@ -254,17 +296,29 @@ pub(super) async fn start(
                 config.sync.recent_state_roots,
             );
 
-            let get_heaviest_tipset = Box::new(move || chain_store.heaviest_tipset());
+            let get_heaviest_tipset = {
+                let chain_store = chain_store.clone();
+                Box::new(move || chain_store.heaviest_tipset())
+            };
 
-            MarkAndSweep::new(
+            let gc = MarkAndSweep::new(
                 db_writer,
                 get_heaviest_tipset,
                 depth,
                 Duration::from_secs(chain_config.block_delay_secs as u64),
-            )
+            );
+            if opts.cleanup_orphans {
+                let finality = chain_config.policy.chain_finality;
+                gc.with_orphan_cleanup(Box::new(move || {
+                    Ok(chain_store.orphan_block_cleanup(finality)?)
+                }))
+            } else {
+                gc
+            }
         };
 
-        services.spawn(async move { db_garbage_collector.gc_loop(GC_INTERVAL).await });
+        let gc_interval = config_reloader.gc_interval();
+        services.spawn(async move { db_garbage_collector.gc_loop(gc_interval).await });
     }
 
     if let Some(ttl) = config.client.eth_mapping_ttl {
@@ -289,9 +343,18 @@ pub(super) async fn start(
         Arc::clone(&chain_store),
         Arc::clone(&chain_config),
         Arc::new(config.sync.clone()),
-    )?;
+    )?
+    .with_shadow_execution(&config.diagnostics, shutdown_send.clone());
+    if config.diagnostics.shadow_execution {
+        warn!("Shadow execution is enabled, every tipset is executed twice");
+    }
 
     let state_manager = Arc::new(sm);
+    if config.sync.lite {
+        info!(
+            "Running in lite mode: messages are not executed and state RPC methods are unavailable"
+        );
+    }
 
     let network_name = get_network_name_from_genesis(&genesis_header, &state_manager)?;
 
@@ -324,7 +387,7 @@ pub(super) async fn start(
     services.spawn(peer_manager.clone().peer_operation_event_loop_task());
     let genesis_cid = *genesis_header.cid();
     // Libp2p service setup
-    let p2p_service = Libp2pService::new(
+    let mut p2p_service = Libp2pService::new(
         config.network.clone(),
         Arc::clone(&chain_store),
         peer_manager.clone(),
@@ -337,6 +400,23 @@ pub(super) async fn start(
     let network_rx = p2p_service.network_receiver();
     let network_send = p2p_service.network_sender();
 
+    {
+        let mut target_peer_count = config_reloader.target_peer_count();
+        let network_send = network_send.clone();
+        services.spawn(async move {
+            while target_peer_count.changed().await.is_ok() {
+                let count = *target_peer_count.borrow_and_update();
+                network_send
+                    .send_async(NetworkMessage::JSONRPCRequest {
+                        method: NetRPCMethods::SetTargetPeerCount(count),
+                    })
+                    .await
+                    .map_err(|_| anyhow::anyhow!("the network service is not running"))?;
+            }
+            Ok(())
+        });
+    }
+
     // Initialize mpool
     let provider = MpoolRpcProvider::new(publisher.clone(), Arc::clone(&state_manager));
     let mpool = MessagePool::new(
@@ -349,6 +429,49 @@ pub(super) async fn start(
     )?;
 
     let mpool = Arc::new(mpool);
+    // The message pool only tracks the chain head in lite mode, as the sender
+    // states it validates messages against are not computed
+    if !state_manager.sync_config().lite {
+        p2p_service.set_message_validator(mpool.clone());
+    }
+
+    if let Some(rebroadcast_interval) = config
+        .client
+        .mpool_rebroadcast_interval
+        .filter(|interval| *interval > 0 && !state_manager.sync_config().lite)
+    {
+        let mpool = mpool.clone();
+        services.spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(rebroadcast_interval));
+            loop {
+                interval.tick().await;
+                let rebroadcast = mpool.rebroadcast_stuck_messages(rebroadcast_interval);
+                if rebroadcast > 0 {
+                    debug!("Rebroadcast {rebroadcast} stuck pending messages");
+                }
+            }
+        });
+    }
+
+    if !state_manager.sync_config().distrust_beacon_cache {
+        let settings = state_manager.chain_store().settings();
+        services.spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(60 * 60));
+            loop {
+                interval.tick().await;
+                let settings = settings.clone();
+                match tokio::task::spawn_blocking(move || {
+                    crate::beacon::prune_verified_beacon_rounds(settings.as_ref())
+                })
+                .await?
+                {
+                    Ok(pruned) if pruned > 0 => debug!("Pruned {pruned} verified beacon rounds"),
+                    Ok(_) => {}
+                    Err(e) => warn!("failed to prune verified beacon rounds: {e}"),
+                }
+            }
+        });
+    }
 
     // Initialize ChainMuxer
     let chain_muxer = ChainMuxer::new(
@@ -364,6 +487,7 @@ pub(super) async fn start(
     )?;
     let bad_blocks = chain_muxer.bad_blocks_cloned();
     let sync_state = chain_muxer.sync_state_cloned();
+    let sync_watchdog = chain_muxer.watchdog_status_cloned();
     let sync_network_context = chain_muxer.sync_network_context();
     services.spawn(async { Err(anyhow::anyhow!("{}", chain_muxer.await)) });
 
@@ -392,6 +516,7 @@ pub(super) async fn start(
         let keystore_rpc = Arc::clone(&keystore);
         let rpc_state_manager = Arc::clone(&state_manager);
         let rpc_address = config.client.rpc_address;
+        let db_statistics = db.writer().clone();
 
         info!("JSON-RPC endpoint will listen at {rpc_address}");
 
@@ -403,11 +528,14 @@ pub(super) async fn start(
                     mpool,
                     bad_blocks,
                     sync_state,
+                    sync_watchdog,
                     eth_event_handler: Arc::new(EthEventHandler::new()),
                     sync_network_context,
                     network_name,
                     start_time,
                     shutdown: shutdown_send,
+                    config_reloader: Some(config_reloader),
+                    db_statistics: Some(db_statistics),
                     tipset_send: tipset_sender,
                 },
                 rpc_address,
@@ -488,6 +616,9 @@ pub(super) async fn start(
             state_manager
                 .chain_store()
                 .set_heaviest_tipset(Arc::new(ts.clone()))?;
+            if !opts.skip_indexing {
+                index_imported_chain(state_manager.chain_store(), &ts)?;
+            }
         }
     }
 
@@ -517,7 +648,7 @@ pub(super) async fn start(
     }
 
     // Populate task
-    if !opts.stateless && !chain_config.is_devnet() {
+    if !opts.stateless && !state_manager.sync_config().lite && !chain_config.is_devnet() {
         let state_manager = Arc::clone(&state_manager);
         services.spawn(async move {
             if let Err(err) = init_ethereum_mapping(state_manager, &config) {
@@ -527,9 +658,28 @@ pub(super) async fn start(
         });
     }
 
-    if !opts.stateless {
+    if !opts.stateless
+        && !state_manager
+            .chain_store()
+            .message_tipset_index()
+            .is_built()?
+    {
+        let chain_store = state_manager.chain_store().clone();
+        services.spawn_blocking(move || {
+            info!("Building the message index");
+            match chain_store.rebuild_message_tipset_index(rayon::current_num_threads()) {
+                Ok(indexed) => info!("Indexed {indexed} messages"),
+                Err(e) => warn!("Building the message index failed: {e}"),
+            }
+            Ok(())
+        });
+    }
+
+    // Proofs are not verified in lite mode
+    if !opts.stateless && !state_manager.sync_config().lite {
         ensure_params_downloaded().await?;
     }
+    p2p_service.set_shutdown_signal(stop_services.subscribe());
     services.spawn(p2p_service.run());
 
     // blocking until any of the services returns an error,
@@ -1,12 +1,13 @@
 // Copyright 2019-2025 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-//! There are three different CAR formats: `.car`, `.car.zst` and
-//! `.forest.car.zst`. [`AnyCar`] identifies the format by inspecting the CAR
-//! header and the first key-value block, and picks the appropriate block store
-//! (either [`super::ForestCar`] or [`super::PlainCar`]).
+//! There are four different CAR formats: `.car`, `.car.zst`,
+//! `.forest.car.zst` and indexed CARv2. [`AnyCar`] identifies the format by
+//! inspecting the CAR header and the first key-value block, and picks the
+//! appropriate block store (either [`super::ForestCar`], [`super::IndexedCar`]
+//! or [`super::PlainCar`]).
 //!
-//! CARv2 is not supported yet.
+//! Compressed CARv2 files are not supported.
 
 use super::{CacheKey, RandomAccessFileReader, ZstdFrameCache};
 use crate::blocks::Tipset;
@@ -23,18 +24,23 @@ use std::sync::Arc;
 pub enum AnyCar<ReaderT> {
     Plain(super::PlainCar<ReaderT>),
     Forest(super::ForestCar<ReaderT>),
+    Indexed(super::IndexedCar<ReaderT>),
     Memory(super::PlainCar<Vec<u8>>),
 }
 
 impl<ReaderT: RandomAccessFileReader> AnyCar<ReaderT> {
-    /// Open an archive. May be formatted as `.car`, `.car.zst` or
-    /// `.forest.car.zst`. This call may block for an indeterminate amount of
-    /// time while data is decoded and indexed.
+    /// Open an archive. May be formatted as `.car`, `.car.zst`,
+    /// `.forest.car.zst` or CARv2. This call may block for an indeterminate
+    /// amount of time while data is decoded and indexed.
     pub fn new(reader: ReaderT) -> Result<Self> {
         if super::ForestCar::is_valid(&reader) {
             return Ok(AnyCar::Forest(super::ForestCar::new(reader)?));
         }
 
+        if super::IndexedCar::is_valid(&reader) {
+            return Ok(AnyCar::Indexed(super::IndexedCar::new(reader)?));
+        }
+
         // Maybe use a tempfile for this in the future.
         if let Ok(decompressed) = zstd::stream::decode_all(positioned_io::Cursor::new(&reader)) {
             if let Ok(mem_car) = super::PlainCar::new(decompressed) {
@@ -47,7 +53,7 @@ impl<ReaderT: RandomAccessFileReader> AnyCar<ReaderT> {
         }
         Err(Error::new(
             ErrorKind::InvalidData,
-            "input not recognized as any kind of CAR data (.car, .car.zst, .forest.car)",
+            "input not recognized as any kind of CAR data (.car, .car.zst, .forest.car, CARv2)",
         ))
     }
 
@@ -56,16 +62,18 @@ impl<ReaderT: RandomAccessFileReader> AnyCar<ReaderT> {
     pub fn heaviest_tipset(&self) -> anyhow::Result<Tipset> {
         match self {
             AnyCar::Forest(forest) => forest.heaviest_tipset(),
+            AnyCar::Indexed(indexed) => indexed.heaviest_tipset(),
             AnyCar::Plain(plain) => plain.heaviest_tipset(),
             AnyCar::Memory(mem) => mem.heaviest_tipset(),
         }
     }
 
-    /// Return the identified CAR format variant. There are three variants:
-    /// `CARv1`, `CARv1.zst` and `ForestCARv1.zst`.
+    /// Return the identified CAR format variant. There are four variants:
+    /// `CARv1`, `CARv1.zst`, `ForestCARv1.zst` and `CARv2`.
     pub fn variant(&self) -> &'static str {
         match self {
             AnyCar::Forest(_) => "ForestCARv1.zst",
+            AnyCar::Indexed(_) => "CARv2",
             AnyCar::Plain(_) => "CARv1",
             AnyCar::Memory(_) => "CARv1.zst",
         }
@@ -75,6 +83,7 @@ impl<ReaderT: RandomAccessFileReader> AnyCar<ReaderT> {
     pub fn into_dyn(self) -> AnyCar<Box<dyn super::RandomAccessFileReader>> {
         match self {
             AnyCar::Forest(f) => AnyCar::Forest(f.into_dyn()),
+            AnyCar::Indexed(i) => AnyCar::Indexed(i.into_dyn()),
             AnyCar::Plain(p) => AnyCar::Plain(p.into_dyn()),
             AnyCar::Memory(m) => AnyCar::Memory(m),
         }
@@ -84,6 +93,7 @@ impl<ReaderT: RandomAccessFileReader> AnyCar<ReaderT> {
     pub fn with_cache(self, cache: Arc<Mutex<ZstdFrameCache>>, key: CacheKey) -> Self {
         match self {
             AnyCar::Forest(f) => AnyCar::Forest(f.with_cache(cache, key)),
+            AnyCar::Indexed(i) => AnyCar::Indexed(i),
             AnyCar::Plain(p) => AnyCar::Plain(p),
             AnyCar::Memory(m) => AnyCar::Memory(m),
         }
@@ -111,6 +121,7 @@ where
     fn get(&self, k: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
         match self {
             AnyCar::Forest(forest) => forest.get(k),
+            AnyCar::Indexed(indexed) => indexed.get(k),
             AnyCar::Plain(plain) => plain.get(k),
             AnyCar::Memory(mem) => mem.get(k),
         }
@@ -119,6 +130,7 @@ where
     fn put_keyed(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
         match self {
             AnyCar::Forest(forest) => forest.put_keyed(k, block),
+            AnyCar::Indexed(indexed) => indexed.put_keyed(k, block),
             AnyCar::Plain(plain) => plain.put_keyed(k, block),
             AnyCar::Memory(mem) => mem.put_keyed(k, block),
         }
@@ -132,6 +144,7 @@ where
     fn put_keyed_persistent(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
         match self {
             AnyCar::Forest(forest) => forest.put_keyed_persistent(k, block),
+            AnyCar::Indexed(indexed) => indexed.put_keyed(k, block),
             AnyCar::Plain(plain) => plain.put_keyed_persistent(k, block),
             AnyCar::Memory(mem) => mem.put_keyed_persistent(k, block),
         }
@@ -144,6 +157,12 @@ impl<ReaderT> From<super::ForestCar<ReaderT>> for AnyCar<ReaderT> {
     }
 }
 
+impl<ReaderT> From<super::IndexedCar<ReaderT>> for AnyCar<ReaderT> {
+    fn from(car: super::IndexedCar<ReaderT>) -> Self {
+        Self::Indexed(car)
+    }
+}
+
 impl<ReaderT> From<super::PlainCar<ReaderT>> for AnyCar<ReaderT> {
     fn from(car: super::PlainCar<ReaderT>) -> Self {
         Self::Plain(car)
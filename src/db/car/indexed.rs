@@ -0,0 +1,490 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! # CARv2 layout
+//!
+//! A [CARv2 file](https://ipld.io/specs/transport/car/carv2/) wraps a CARv1 _data payload_
+//! (see [`super::plain`]) between a fixed size header and an index of the blocks in the payload.
+//!
+//! ```text
+//! ├──────┬──────┬──────────────────┬─────┤
+//! │pragma│header│CARv1 data payload│index│
+//! └──────┴──────┴──────────────────┴─────┘
+//! ```
+//!
+//! - The _pragma_ is a CARv1 header frame with `version: 2` and no roots, which CARv1 readers
+//!   reject.
+//! - The _header_ holds 16 bytes of characteristics, followed by the offset and size of the data
+//!   payload and the offset of the index, as little-endian `u64`s.
+//! - The _index_ is prefixed with the multicodec of its format. The only format supported here is
+//!   [`MultihashIndexSorted`](https://ipld.io/specs/transport/car/carv2/#format-0x0401-multihashindexsorted),
+//!   the default of `go-car`. For each multihash code, it lists the digests of the blocks with
+//!   the offset of their varint frame in the data payload, sorted by digest and bucketed by
+//!   digest width.
+//!
+//! ```text
+//! ├──────┬──────┬───────────┬──────────┬──────┬──────┬────────────┬───────┬──────────┬─────
+//! │varint│i32:  │u64:       │i32:      │u32:  │i64:  │width-sized │u32:   │          │
+//! │codec │#codes│hash code 1│#widths   │width │length│records     │width  │records   │ ...
+//! └──────┴──────┴───────────┴──────────┴──────┼──────┴────────────┴───────┴──────────┴─────
+//!                                             │digest│u64: offset│
+//! ```
+//!
+//! [`IndexedCar`] reads the index instead of scanning the data payload, so that opening a CARv2
+//! file is cheap, and serves the file as a read-only [`Blockstore`]. Only the bucket headers are
+//! kept in memory, the records are binary searched in the file.
+
+use super::plain::get_roots_from_v1_header;
+use crate::blocks::{Tipset, TipsetKey};
+use crate::utils::db::car_stream::{CarBlock, CarHeader};
+use anyhow::Context as _;
+use byteorder::{LittleEndian, ReadBytesExt as _};
+use cid::Cid;
+use futures::{Stream, TryStreamExt as _};
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::to_vec;
+use integer_encoding::{VarInt as _, VarIntReader as _};
+use nunny::Vec as NonEmpty;
+use positioned_io::{ReadAt, Size};
+use std::collections::BTreeMap;
+use std::io::{
+    self, BufReader,
+    ErrorKind::{InvalidData, Unsupported},
+    Seek as _,
+};
+use tokio::io::{AsyncSeek, AsyncSeekExt as _, AsyncWrite, AsyncWriteExt as _};
+use tracing::debug;
+
+pub const CARV2_FILE_EXTENSION: &str = ".v2.car";
+
+/// CARv1 header frame of `{version: 2}`.
+pub const CARV2_PRAGMA: [u8; 11] = [
+    0x0a, 0xa1, 0x67, 0x76, 0x65, 0x72, 0x73, 0x69, 0x6f, 0x6e, 0x02,
+];
+
+/// Multicodec of the `MultihashIndexSorted` index format.
+const MULTIHASH_INDEX_SORTED: u64 = 0x0401;
+
+/// Size of a digest offset in an index record.
+const OFFSET_SIZE: usize = std::mem::size_of::<u64>();
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CarV2Header {
+    pub characteristics: [u8; 16],
+    pub data_offset: u64,
+    pub data_size: u64,
+    pub index_offset: u64,
+}
+
+impl CarV2Header {
+    pub const SIZE: usize = 40;
+
+    pub fn to_le_bytes(self) -> [u8; Self::SIZE] {
+        let mut bytes = [0; Self::SIZE];
+        bytes[..16].copy_from_slice(&self.characteristics);
+        bytes[16..24].copy_from_slice(&self.data_offset.to_le_bytes());
+        bytes[24..32].copy_from_slice(&self.data_size.to_le_bytes());
+        bytes[32..].copy_from_slice(&self.index_offset.to_le_bytes());
+        bytes
+    }
+
+    pub fn from_le_bytes(bytes: [u8; Self::SIZE]) -> Self {
+        let u64_at = |offset: usize| {
+            u64::from_le_bytes(bytes[offset..offset + 8].try_into().expect("8 bytes"))
+        };
+        Self {
+            characteristics: bytes[..16].try_into().expect("16 bytes"),
+            data_offset: u64_at(16),
+            data_size: u64_at(24),
+            index_offset: u64_at(32),
+        }
+    }
+}
+
+/// Location in the file of the records of the digests of a given multihash
+/// code and width, sorted by digest, as laid out in the index.
+struct IndexBucket {
+    code: u64,
+    /// Size of a record, i.e. of a digest and its offset
+    width: usize,
+    /// Offset of the first record in the file
+    records_offset: u64,
+    len: u64,
+}
+
+impl IndexBucket {
+    /// Reads the digest and the block offset of the `i`-th record.
+    fn record(&self, reader: &impl ReadAt, i: u64) -> io::Result<(Vec<u8>, u64)> {
+        let mut record = vec![0; self.width];
+        reader.read_exact_at(self.records_offset + i * self.width as u64, &mut record)?;
+        let offset = record.split_off(self.width - OFFSET_SIZE);
+        Ok((
+            record,
+            u64::from_le_bytes(offset.try_into().expect("8 bytes")),
+        ))
+    }
+
+    /// Offsets of the blocks with this digest.
+    fn offsets(&self, reader: &impl ReadAt, digest: &[u8]) -> io::Result<Vec<u64>> {
+        let (mut low, mut high) = (0, self.len);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.record(reader, mid)?.0.as_slice() < digest {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        let mut offsets = vec![];
+        for i in low..self.len {
+            let (record_digest, offset) = self.record(reader, i)?;
+            if record_digest != digest {
+                break;
+            }
+            offsets.push(offset);
+        }
+        Ok(offsets)
+    }
+}
+
+/// **Note that all operations on this store are blocking**.
+///
+/// A read-only [`Blockstore`] backed by an uncompressed, indexed [CARv2
+/// file](https://ipld.io/specs/transport/car/carv2/). Blocks are located with the index embedded
+/// in the file, whose bucket headers are read on creation.
+///
+/// See [module documentation](mod@self) for more.
+pub struct IndexedCar<ReaderT> {
+    reader: ReaderT,
+    header: CarV2Header,
+    roots: NonEmpty<Cid>,
+    index: Vec<IndexBucket>,
+}
+
+impl<ReaderT: super::RandomAccessFileReader> IndexedCar<ReaderT> {
+    /// To be correct, `reader` must read immutable data, see [`super::PlainCar::new`].
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub fn new(reader: ReaderT) -> io::Result<Self> {
+        let mut pragma_and_header = [0; CARV2_PRAGMA.len() + CarV2Header::SIZE];
+        reader.read_exact_at(0, &mut pragma_and_header)?;
+        let (pragma, header) = pragma_and_header.split_at(CARV2_PRAGMA.len());
+        if pragma != CARV2_PRAGMA {
+            return Err(io::Error::new(InvalidData, "not a CARv2 file"));
+        }
+        let header = CarV2Header::from_le_bytes(header.try_into().expect("header size"));
+        if header.index_offset == 0 {
+            return Err(io::Error::new(
+                Unsupported,
+                "CARv2 files without an index are not supported",
+            ));
+        }
+
+        let roots =
+            get_roots_from_v1_header(positioned_io::Cursor::new_pos(&reader, header.data_offset))?;
+        let index = read_index(&reader, header.index_offset)?;
+        debug!(
+            num_blocks = index.iter().map(|it| it.len).sum::<u64>(),
+            "loaded CARv2 index"
+        );
+        Ok(Self {
+            reader,
+            header,
+            roots,
+            index,
+        })
+    }
+
+    /// Returns `true` if `reader` starts with the CARv2 pragma.
+    pub fn is_valid(reader: &ReaderT) -> bool {
+        let mut pragma = [0; CARV2_PRAGMA.len()];
+        reader.read_exact_at(0, &mut pragma).is_ok() && pragma == CARV2_PRAGMA
+    }
+
+    pub fn roots(&self) -> &NonEmpty<Cid> {
+        &self.roots
+    }
+
+    pub fn heaviest_tipset(&self) -> anyhow::Result<Tipset> {
+        Tipset::load_required(self, &TipsetKey::from(self.roots().clone()))
+    }
+
+    pub fn into_dyn(self) -> IndexedCar<Box<dyn super::RandomAccessFileReader>> {
+        IndexedCar {
+            reader: Box::new(self.reader),
+            header: self.header,
+            roots: self.roots,
+            index: self.index,
+        }
+    }
+}
+
+impl<ReaderT: ReadAt> IndexedCar<ReaderT> {
+    /// Reads the varint frame at `offset` in the data payload.
+    fn read_block_at(&self, offset: u64) -> io::Result<CarBlock> {
+        let mut reader = BufReader::with_capacity(
+            128,
+            positioned_io::Cursor::new_pos(&self.reader, self.header.data_offset + offset),
+        );
+        let body_length: u64 = reader.read_varint()?;
+        let body_offset = reader.stream_position()?;
+        let cid = Cid::read_bytes(&mut reader).map_err(|e| io::Error::new(InvalidData, e))?;
+        let data_offset = reader.stream_position()?;
+        let data_length = (body_offset + body_length)
+            .checked_sub(data_offset)
+            .ok_or_else(|| io::Error::new(InvalidData, "invalid CARv2 block frame"))?;
+        let mut data = vec![0; usize::try_from(data_length).map_err(io::Error::other)?];
+        self.reader
+            .read_exact_at(self.header.data_offset + data_offset, &mut data)?;
+        Ok(CarBlock { cid, data })
+    }
+}
+
+impl<ReaderT> Blockstore for IndexedCar<ReaderT>
+where
+    ReaderT: ReadAt,
+{
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn get(&self, k: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+        let digest = k.hash().digest();
+        // Blocks with different codecs may share a multihash
+        for bucket in self
+            .index
+            .iter()
+            .filter(|it| it.code == k.hash().code() && it.width == digest.len() + OFFSET_SIZE)
+        {
+            for offset in bucket.offsets(&self.reader, digest)? {
+                let block = self.read_block_at(offset)?;
+                if block.cid == *k {
+                    return Ok(Some(block.data));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn put_keyed(&self, k: &Cid, _block: &[u8]) -> anyhow::Result<()> {
+        anyhow::bail!("cannot write block {k}: CARv2 files are read-only")
+    }
+}
+
+/// Reads the bucket headers of the index at `index_offset`, checking that
+/// their records lie within the file.
+fn read_index(file: &(impl ReadAt + Size), index_offset: u64) -> io::Result<Vec<IndexBucket>> {
+    let file_size = file
+        .size()?
+        .ok_or_else(|| io::Error::new(Unsupported, "unknown CARv2 file size"))?;
+    let mut reader = BufReader::new(positioned_io::Cursor::new_pos(file, index_offset));
+    let codec: u64 = reader.read_varint()?;
+    if codec != MULTIHASH_INDEX_SORTED {
+        return Err(io::Error::new(
+            Unsupported,
+            format!("unsupported CARv2 index format {codec:#x}"),
+        ));
+    }
+    let invalid = |what| io::Error::new(InvalidData, format!("invalid CARv2 index {what}"));
+    let mut buckets = vec![];
+    let num_codes = reader.read_i32::<LittleEndian>()?;
+    for _ in 0..num_codes {
+        let code = reader.read_u64::<LittleEndian>()?;
+        let num_widths = reader.read_i32::<LittleEndian>()?;
+        for _ in 0..num_widths {
+            let width = usize::try_from(reader.read_u32::<LittleEndian>()?)
+                .map_err(|_| invalid("width"))?;
+            let length =
+                u64::try_from(reader.read_i64::<LittleEndian>()?).map_err(|_| invalid("length"))?;
+            let records_offset = reader.stream_position()?;
+            if width <= OFFSET_SIZE
+                || length % width as u64 != 0
+                || records_offset
+                    .checked_add(length)
+                    .is_none_or(|end| end > file_size)
+            {
+                return Err(invalid("bucket"));
+            }
+            reader.seek_relative(length as i64)?;
+            buckets.push(IndexBucket {
+                code,
+                width,
+                records_offset,
+                len: length / width as u64,
+            });
+        }
+    }
+    Ok(buckets)
+}
+
+/// Encodes a `MultihashIndexSorted` index of the blocks at the given offsets
+/// in the data payload.
+fn encode_index(offsets: impl IntoIterator<Item = (Cid, u64)>) -> Vec<u8> {
+    let mut buckets: BTreeMap<u64, BTreeMap<usize, Vec<(Vec<u8>, u64)>>> = BTreeMap::new();
+    for (cid, offset) in offsets {
+        let digest = cid.hash().digest();
+        buckets
+            .entry(cid.hash().code())
+            .or_default()
+            .entry(digest.len() + OFFSET_SIZE)
+            .or_default()
+            .push((digest.to_vec(), offset));
+    }
+
+    let mut index = MULTIHASH_INDEX_SORTED.encode_var_vec();
+    index.extend((buckets.len() as i32).to_le_bytes());
+    for (code, widths) in buckets {
+        index.extend(code.to_le_bytes());
+        index.extend((widths.len() as i32).to_le_bytes());
+        for (width, mut records) in widths {
+            records.sort();
+            index.extend((width as u32).to_le_bytes());
+            index.extend(((records.len() * width) as i64).to_le_bytes());
+            for (digest, offset) in records {
+                index.extend(digest);
+                index.extend(offset.to_le_bytes());
+            }
+        }
+    }
+    index
+}
+
+/// Writes the blocks as an uncompressed CARv2 file, indexed with a
+/// `MultihashIndexSorted` index. The header is written last, so the writer
+/// must be seekable.
+pub async fn write_carv2(
+    mut writer: impl AsyncWrite + AsyncSeek + Unpin,
+    roots: NonEmpty<Cid>,
+    blocks: impl Stream<Item = anyhow::Result<CarBlock>>,
+) -> anyhow::Result<()> {
+    let data_offset = (CARV2_PRAGMA.len() + CarV2Header::SIZE) as u64;
+    writer.write_all(&CARV2_PRAGMA).await?;
+    // Placeholder until the payload and index sizes are known
+    writer.write_all(&[0; CarV2Header::SIZE]).await?;
+
+    let car_header = to_vec(&CarHeader { roots, version: 1 })?;
+    let mut frame = car_header.len().encode_var_vec();
+    frame.extend(car_header);
+    writer.write_all(&frame).await?;
+    let mut data_size = frame.len() as u64;
+
+    let mut offsets = vec![];
+    futures::pin_mut!(blocks);
+    while let Some(block) = blocks.try_next().await? {
+        frame.clear();
+        block.write(&mut frame)?;
+        writer.write_all(&frame).await?;
+        offsets.push((block.cid, data_size));
+        data_size += frame.len() as u64;
+    }
+
+    writer.write_all(&encode_index(offsets)).await?;
+    let header = CarV2Header {
+        characteristics: [0; 16],
+        data_offset,
+        data_size,
+        index_offset: data_offset + data_size,
+    };
+    writer
+        .seek(io::SeekFrom::Start(CARV2_PRAGMA.len() as u64))
+        .await?;
+    writer.write_all(&header.to_le_bytes()).await?;
+    writer.flush().await.context("failed to flush")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::car::{AnyCar, PlainCar};
+    use crate::utils::db::car_stream::CarStream;
+    use quickcheck_macros::quickcheck;
+
+    fn chain4_car() -> &'static [u8] {
+        include_bytes!("../../../test-snapshots/chain4.car")
+    }
+
+    async fn carv2_of(car: &'static [u8]) -> Vec<u8> {
+        let stream = CarStream::new(car).await.unwrap();
+        let roots = stream.header.roots.clone();
+        let mut carv2 = std::io::Cursor::new(vec![]);
+        write_carv2(&mut carv2, roots, stream.map_err(anyhow::Error::from))
+            .await
+            .unwrap();
+        carv2.into_inner()
+    }
+
+    #[tokio::test]
+    async fn carv1_carv2_roundtrip() {
+        let carv1 = PlainCar::new(chain4_car()).unwrap();
+        let carv2 = carv2_of(chain4_car()).await;
+
+        let indexed = IndexedCar::new(carv2.clone()).unwrap();
+        assert_eq!(indexed.roots(), carv1.roots());
+        assert_eq!(
+            indexed.heaviest_tipset().unwrap(),
+            carv1.heaviest_tipset().unwrap()
+        );
+        for cid in carv1.cids() {
+            assert_eq!(indexed.get(&cid).unwrap(), carv1.get(&cid).unwrap());
+        }
+        assert!(indexed.put_keyed(&carv1.cids()[0], &[]).is_err());
+
+        // Streaming the CARv2 file yields its CARv1 data payload
+        let stream = CarStream::new(carv2.as_slice()).await.unwrap();
+        assert_eq!(&stream.header.roots, carv1.roots());
+        let blocks: Vec<CarBlock> = stream.try_collect().await.unwrap();
+        assert_eq!(blocks.len(), carv1.cids().len());
+        for block in blocks {
+            assert_eq!(carv1.get(&block.cid).unwrap(), Some(block.data));
+        }
+
+        let any = AnyCar::new(carv2).unwrap();
+        assert_eq!(any.variant(), "CARv2");
+    }
+
+    #[test]
+    fn header_roundtrip() {
+        let header = CarV2Header {
+            characteristics: [1; 16],
+            data_offset: 51,
+            data_size: 1 << 40,
+            index_offset: (1 << 40) + 51,
+        };
+        assert_eq!(CarV2Header::from_le_bytes(header.to_le_bytes()), header);
+    }
+
+    #[quickcheck]
+    fn index_roundtrip(blocks: Vec<CarBlock>, offsets: Vec<u64>) {
+        let entries: Vec<(Cid, u64)> = blocks
+            .iter()
+            .map(|it| it.cid)
+            .zip(offsets.into_iter().chain(std::iter::repeat(0)))
+            .collect();
+        let file = encode_index(entries.clone());
+        let index = read_index(&file, 0).unwrap();
+        for (cid, offset) in entries {
+            let digest = cid.hash().digest();
+            assert!(index
+                .iter()
+                .filter(|it| it.code == cid.hash().code())
+                .any(|it| it.offsets(&file, digest).unwrap().contains(&offset)));
+        }
+    }
+
+    #[tokio::test]
+    async fn index_beyond_the_file_is_rejected() {
+        let mut carv2 = carv2_of(chain4_car()).await;
+        let header = CarV2Header::from_le_bytes(
+            carv2[CARV2_PRAGMA.len()..][..CarV2Header::SIZE]
+                .try_into()
+                .unwrap(),
+        );
+        // The length of the first bucket follows the codec, the number of
+        // codes, the first code, the number of widths and the width
+        let length_offset =
+            header.index_offset as usize + MULTIHASH_INDEX_SORTED.required_space() + 4 + 8 + 4 + 4;
+        let width = u32::from_le_bytes(carv2[length_offset - 4..length_offset].try_into().unwrap());
+        carv2[length_offset..length_offset + 8]
+            .copy_from_slice(&(i64::from(width) << 40).to_le_bytes());
+
+        let err = IndexedCar::new(carv2).err().unwrap();
+        assert_eq!(err.kind(), InvalidData);
+    }
+}
@@ -9,7 +9,7 @@
 //! A single z-frame cache is shared between all read-only stores.
 
 use super::{AnyCar, ZstdFrameCache};
-use crate::db::{EthMappingsStore, MemoryDB, PersistentStore, SettingsStore};
+use crate::db::{EthMappingsStore, GarbageCollectable, MemoryDB, PersistentStore, SettingsStore};
 use crate::libp2p_bitswap::BitswapStoreReadWrite;
 use crate::rpc::eth::types::EthHash;
 use crate::shim::clock::ChainEpoch;
@@ -176,6 +176,18 @@ impl<WriterT: PersistentStore> PersistentStore for ManyCar<WriterT> {
     }
 }
 
+/// Only the writable store is garbage collected, the CAR-backed stores are
+/// read-only.
+impl<T, WriterT: GarbageCollectable<T>> GarbageCollectable<T> for ManyCar<WriterT> {
+    fn get_keys(&self) -> anyhow::Result<T> {
+        self.writer.get_keys()
+    }
+
+    fn remove_keys(&self, keys: T) -> anyhow::Result<u32> {
+        self.writer.remove_keys(keys)
+    }
+}
+
 impl<WriterT: BitswapStoreRead + Blockstore> BitswapStoreRead for ManyCar<WriterT> {
     fn contains(&self, cid: &Cid) -> anyhow::Result<bool> {
         Blockstore::has(self, cid)
@@ -210,6 +222,10 @@ impl<WriterT: SettingsStore> SettingsStore for ManyCar<WriterT> {
     fn setting_keys(&self) -> anyhow::Result<Vec<String>> {
         SettingsStore::setting_keys(self.writer())
     }
+
+    fn delete(&self, key: &str) -> anyhow::Result<()> {
+        SettingsStore::delete(self.writer(), key)
+    }
 }
 
 impl<WriterT: EthMappingsStore> EthMappingsStore for ManyCar<WriterT> {
@@ -2,11 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 mod any;
 pub mod forest;
+pub mod indexed;
 mod many;
 pub mod plain;
 
 pub use any::AnyCar;
 pub use forest::ForestCar;
+pub use indexed::IndexedCar;
 pub use many::ManyCar;
 pub use plain::PlainCar;
 
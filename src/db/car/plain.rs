@@ -57,7 +57,6 @@
 //! - Use safe arithmetic for all operations - a malicious frame shouldn't cause a crash.
 //! - Theoretically, file-backed blockstores should be clonable (or even [`Sync`]) with very low
 //!   overhead, so that multiple threads could perform operations concurrently.
-//! - CARv2 support
 //! - A wrapper that abstracts over car formats for reading.
 
 use crate::cid_collections::{hash_map::Entry as CidHashMapEntry, CidHashMap};
@@ -292,7 +291,7 @@ fn handle_write_cache(
     }
 }
 
-fn get_roots_from_v1_header(reader: impl Read) -> io::Result<NonEmpty<Cid>> {
+pub(super) fn get_roots_from_v1_header(reader: impl Read) -> io::Result<NonEmpty<Cid>> {
     match read_header(reader)? {
         CarHeader { roots, version: 1 } => Ok(roots),
         other_version => Err(io::Error::new(
@@ -33,6 +33,8 @@
 //! 3. Traverse reachable blocks starting at the current heaviest tipset and remove those from the
 //!    marked set, leaving only unreachable entries that are older than `chain finality`.
 //! 4. Sweep, removing all the remaining marked entries from the database.
+//! 5. Optionally, remove the block headers of abandoned forks as soon as they are older than
+//!    `chain finality`, see [`crate::chain::ChainStore::orphan_block_cleanup`].
 //!
 //! ## Correctness
 //! This algorithm considers all the blocks that are visited during the `snapshot export` task
@@ -70,7 +72,7 @@
 //! is the number of edges.
 
 use crate::blocks::Tipset;
-use crate::chain::ChainEpochDelta;
+use crate::chain::{ChainEpochDelta, OrphanCleanupStats};
 
 use crate::cid_collections::CidHashSet;
 use crate::db::{GarbageCollectable, SettingsStore};
@@ -81,6 +83,7 @@ use fvm_ipld_blockstore::Blockstore;
 use std::mem;
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::watch;
 use tokio::time;
 use tracing::{error, info};
 
@@ -99,6 +102,7 @@ pub struct MarkAndSweep<DB> {
     epoch_marked: ChainEpoch,
     depth: ChainEpochDelta,
     block_time: Duration,
+    cleanup_orphans: Option<Box<dyn Fn() -> anyhow::Result<OrphanCleanupStats> + Send>>,
 }
 
 impl<DB: Blockstore + SettingsStore + GarbageCollectable<CidHashSet> + Sync + Send + 'static>
@@ -125,8 +129,19 @@ impl<DB: Blockstore + SettingsStore + GarbageCollectable<CidHashSet> + Sync + Se
             marked: CidHashSet::new(),
             epoch_marked: 0,
             block_time,
+            cleanup_orphans: None,
         }
     }
+
+    /// Runs `cleanup_orphans` after each sweep, removing the blocks of abandoned forks that left
+    /// the reorg window since the last mark, without waiting for the next sweep.
+    pub fn with_orphan_cleanup(
+        mut self,
+        cleanup_orphans: Box<dyn Fn() -> anyhow::Result<OrphanCleanupStats> + Send>,
+    ) -> Self {
+        self.cleanup_orphans = Some(cleanup_orphans);
+        self
+    }
     // Populate the initial set with all the available database keys.
     fn populate(&mut self) -> anyhow::Result<()> {
         self.marked = self.db.get_keys()?;
@@ -156,12 +171,14 @@ impl<DB: Blockstore + SettingsStore + GarbageCollectable<CidHashSet> + Sync + Se
     ///
     /// # Arguments
     ///
-    /// * `interval` - GC Interval to avoid constantly consuming node's resources.
+    /// * `interval` - GC Interval to avoid constantly consuming node's resources. Changes are
+    ///   picked up at the next step of the workflow.
     ///
     /// NOTE: This currently does not take into account the fact that we might be starting the node
     /// using CAR-backed storage with a snapshot, for implementation simplicity.
-    pub async fn gc_loop(&mut self, interval: Duration) -> anyhow::Result<()> {
+    pub async fn gc_loop(&mut self, interval: watch::Receiver<Duration>) -> anyhow::Result<()> {
         loop {
+            let interval = *interval.borrow();
             if let Err(err) = self.gc_workflow(interval).await {
                 error!("GC run error: {}", err)
             }
@@ -227,6 +244,14 @@ impl<DB: Blockstore + SettingsStore + GarbageCollectable<CidHashSet> + Sync + Se
         let deleted = self.sweep()?;
         info!("GC finished sweep: {} deleted records", deleted);
 
+        if let Some(cleanup_orphans) = &self.cleanup_orphans {
+            let stats = cleanup_orphans()?;
+            info!(
+                "GC removed {} orphan blocks ({} bytes) in {:?}",
+                stats.blocks_removed, stats.bytes_freed, stats.time_elapsed
+            );
+        }
+
         self.update_last_gc_run(current_epoch)?;
 
         anyhow::Ok(())
@@ -95,6 +95,11 @@ impl SettingsStore for MemoryDB {
     fn setting_keys(&self) -> anyhow::Result<Vec<String>> {
         Ok(self.settings_db.read().keys().cloned().collect_vec())
     }
+
+    fn delete(&self, key: &str) -> anyhow::Result<()> {
+        self.settings_db.write().remove(key);
+        Ok(())
+    }
 }
 
 impl EthMappingsStore for MemoryDB {
@@ -6,8 +6,10 @@ use std::path::PathBuf;
 use tracing::info;
 
 use crate::{
+    chain::clear_validated_tipsets,
     cli_shared::chain_path,
     db::{
+        db_engine::open_db,
         db_mode::{get_latest_versioned_database, DbMode},
         migration::migration_map::create_migration_chain,
     },
@@ -78,6 +80,13 @@ impl DbMigration {
             migration.migrate(&self.chain_data_path(), &self.config)?;
         }
 
+        // Validated tipsets are checked again after a migration
+        let db = open_db(
+            self.chain_data_path().join(target_db_version.to_string()),
+            self.config.db_config().clone(),
+        )?;
+        clear_validated_tipsets(&db)?;
+
         info!(
             "Migration to version {} complete",
             target_db_version.to_string()
@@ -14,7 +14,6 @@ use crate::db::migration::migration_map::temporary_db_name;
 use crate::db::migration::v0_19_0::paritydb_0_18_0::{DbColumn, ParityDb};
 use crate::db::CAR_DB_DIR_NAME;
 use crate::genesis::read_genesis_header;
-use crate::networks::ChainConfig;
 use crate::state_manager::StateManager;
 use crate::utils::multihash::prelude::*;
 use crate::{db, Config};
@@ -152,6 +151,7 @@ async fn create_state_manager_and_populate(config: Config, db_name: String) -> a
     let db_root_dir = chain_data_path.join(db_name);
     let db = ParityDbCurrent::wrap(
         paritydb_0_19_0::ParityDb::open(db_root_dir.clone())?.db,
+        db_root_dir.clone(),
         false,
         true,
     );
@@ -160,7 +160,7 @@ async fn create_state_manager_and_populate(config: Config, db_name: String) -> a
     let forest_car_db_dir = db_root_dir.join(CAR_DB_DIR_NAME);
     load_all_forest_cars(&db, &forest_car_db_dir)?;
 
-    let chain_config = Arc::new(ChainConfig::from_chain(config.chain()));
+    let chain_config = Arc::new(config.chain_config()?);
 
     let genesis_header = read_genesis_header(
         config.client.genesis_file.as_deref(),
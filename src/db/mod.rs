@@ -3,6 +3,7 @@
 
 pub mod car;
 mod memory;
+mod overlay;
 pub mod parity_db;
 pub mod parity_db_config;
 
@@ -10,10 +11,12 @@ mod gc;
 pub mod ttl;
 pub use gc::MarkAndSweep;
 pub use memory::MemoryDB;
+pub use overlay::OverlayDB;
 use setting_keys::ETH_MAPPING_UP_TO_DATE_KEY;
 mod db_mode;
 pub mod migration;
 
+use crate::lotus_json::lotus_json;
 use crate::rpc::eth::types::EthHash;
 use anyhow::Context as _;
 use cid::Cid;
@@ -29,8 +32,20 @@ pub mod setting_keys {
     pub const HEAD_KEY: &str = "head";
     /// Key used to store the memory pool configuration in the settings store.
     pub const MPOOL_CONFIG_KEY: &str = "/mpool/config";
+    /// Prefix of the keys storing the journal of locally published messages in the settings
+    /// store, followed by the message CID.
+    pub const MPOOL_LOCAL_MESSAGE_KEY_PREFIX: &str = "/mpool/local_message/";
     /// Key used to store the state of the Ethereum mapping. This is expected to be a [`bool`].
     pub const ETH_MAPPING_UP_TO_DATE_KEY: &str = "eth_mapping_up_to_date";
+    /// Prefix of the keys marking verified beacon rounds, followed by the round. The value is the
+    /// digest of the verified signature.
+    pub const VERIFIED_BEACON_ROUND_KEY_PREFIX: &str = "/verified_beacon_round/";
+    /// Key used to store the highest beacon round held under [`VERIFIED_BEACON_ROUND_KEY_PREFIX`].
+    pub const VERIFIED_BEACON_ROUND_HIGHEST_KEY: &str = "/verified_beacon_round_highest";
+    /// Key used to store the recently useful peers, dialed again on startup.
+    pub const PEER_STORE_KEY: &str = "/libp2p/peers";
+    /// Key used to store the progress of the last event index backfill, to resume it.
+    pub const EVENT_BACKFILL_PROGRESS_KEY: &str = "/event_backfill/progress";
 }
 
 /// Interface used to store and retrieve settings from the database.
@@ -49,6 +64,9 @@ pub trait SettingsStore {
 
     /// Returns all setting keys.
     fn setting_keys(&self) -> anyhow::Result<Vec<String>>;
+
+    /// Removes the key from the store. Removing a missing key is a no-op.
+    fn delete(&self, key: &str) -> anyhow::Result<()>;
 }
 
 impl<T: SettingsStore> SettingsStore for Arc<T> {
@@ -67,6 +85,10 @@ impl<T: SettingsStore> SettingsStore for Arc<T> {
     fn setting_keys(&self) -> anyhow::Result<Vec<String>> {
         SettingsStore::setting_keys(self.as_ref())
     }
+
+    fn delete(&self, key: &str) -> anyhow::Result<()> {
+        SettingsStore::delete(self.as_ref(), key)
+    }
 }
 
 /// Extension trait for the [`SettingsStore`] trait. It is implemented for all types that implement
@@ -180,17 +202,51 @@ impl<T: ?Sized + EthMappingsStore> EthMappingsStoreExt for T {
     }
 }
 
+lotus_json! {
+    /// Number of keys and size on disk of a database column.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct ColumnStats {
+        pub name: String,
+        pub num_keys: u64,
+        pub disk_size_bytes: u64,
+    }
+    snapshots {
+        vec![(
+            serde_json::json!({
+                "Name": "GraphDagCborBlake2b256",
+                "NumKeys": 1000,
+                "DiskSizeBytes": 65536,
+            }),
+            ColumnStats {
+                name: "GraphDagCborBlake2b256".into(),
+                num_keys: 1000,
+                disk_size_bytes: 65536,
+            },
+        )]
+    }
+}
+
 /// Traits for collecting DB stats
 pub trait DBStatistics {
     fn get_statistics(&self) -> Option<String> {
         None
     }
+
+    /// Returns the statistics of each column. Counting the keys scans the
+    /// whole database.
+    fn column_stats(&self) -> anyhow::Result<Vec<ColumnStats>> {
+        anyhow::bail!("column statistics are not supported by this database")
+    }
 }
 
 impl<DB: DBStatistics> DBStatistics for std::sync::Arc<DB> {
     fn get_statistics(&self) -> Option<String> {
         self.as_ref().get_statistics()
     }
+
+    fn column_stats(&self) -> anyhow::Result<Vec<ColumnStats>> {
+        self.as_ref().column_stats()
+    }
 }
 
 /// A trait to facilitate mark-and-sweep garbage collection.
@@ -265,4 +321,9 @@ mod tests {
     mod mem_test;
     mod parity_test;
     pub mod subtests;
+
+    #[test]
+    fn column_stats_snapshots() {
+        crate::lotus_json::assert_all_snapshots::<super::ColumnStats>();
+    }
 }
@@ -0,0 +1,82 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::{MemoryDB, PersistentStore};
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use std::sync::Arc;
+
+/// A [`Blockstore`] that reads through to a base store but keeps all writes in
+/// memory, so that they are discarded once the overlay is dropped. Useful for
+/// speculative execution, e.g. simulating messages.
+#[derive(Debug)]
+pub struct OverlayDB<DB> {
+    base: Arc<DB>,
+    overlay: MemoryDB,
+}
+
+impl<DB> OverlayDB<DB> {
+    pub fn new(base: Arc<DB>) -> Self {
+        Self {
+            base,
+            overlay: MemoryDB::default(),
+        }
+    }
+}
+
+impl<DB: Blockstore> Blockstore for OverlayDB<DB> {
+    fn get(&self, k: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+        match self.overlay.get(k)? {
+            Some(block) => Ok(Some(block)),
+            None => self.base.get(k),
+        }
+    }
+
+    fn put_keyed(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
+        self.overlay.put_keyed(k, block)
+    }
+
+    fn has(&self, k: &Cid) -> anyhow::Result<bool> {
+        Ok(self.overlay.has(k)? || self.base.has(k)?)
+    }
+}
+
+impl<DB: Blockstore> PersistentStore for OverlayDB<DB> {
+    fn put_keyed_persistent(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
+        self.overlay.put_keyed(k, block)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::multihash::prelude::*;
+    use fvm_ipld_encoding::DAG_CBOR;
+
+    #[test]
+    fn writes_do_not_reach_base() {
+        let base = Arc::new(MemoryDB::default());
+        let base_key = Cid::new_v1(DAG_CBOR, MultihashCode::Blake2b256.digest(b"base"));
+        base.put_keyed(&base_key, b"base").unwrap();
+
+        let overlay = OverlayDB::new(base.clone());
+        let key = Cid::new_v1(DAG_CBOR, MultihashCode::Blake2b256.digest(b"overlay"));
+        overlay.put_keyed(&key, b"overlay").unwrap();
+
+        assert_eq!(overlay.get(&base_key).unwrap(), Some(b"base".to_vec()));
+        assert_eq!(overlay.get(&key).unwrap(), Some(b"overlay".to_vec()));
+        assert!(overlay.has(&base_key).unwrap());
+        assert!(!base.has(&key).unwrap());
+    }
+
+    #[test]
+    fn persistent_writes_do_not_reach_base() {
+        let base = Arc::new(MemoryDB::default());
+        let overlay = OverlayDB::new(base.clone());
+        let key = Cid::new_v1(DAG_CBOR, MultihashCode::Blake2b256.digest(b"bundle"));
+        overlay.put_keyed_persistent(&key, b"bundle").unwrap();
+
+        assert!(overlay.has(&key).unwrap());
+        assert!(!base.has(&key).unwrap());
+    }
+}
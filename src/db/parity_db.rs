@@ -3,7 +3,7 @@
 
 use super::{EthMappingsStore, PersistentStore, SettingsStore};
 use crate::cid_collections::CidHashSet;
-use crate::db::{parity_db_config::ParityDbConfig, DBStatistics, GarbageCollectable};
+use crate::db::{parity_db_config::ParityDbConfig, ColumnStats, DBStatistics, GarbageCollectable};
 use crate::libp2p_bitswap::{BitswapStoreRead, BitswapStoreReadWrite};
 use crate::rpc::eth::types::EthHash;
 use crate::utils::multihash::prelude::*;
@@ -12,7 +12,7 @@ use cid::Cid;
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::DAG_CBOR;
 use parity_db::{CompressionType, Db, Operation, Options};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use strum::{Display, EnumIter, FromRepr, IntoEnumIterator};
 use tracing::warn;
 
@@ -81,6 +81,7 @@ impl DbColumn {
 
 pub struct ParityDb {
     pub db: parity_db::Db,
+    path: PathBuf,
     statistics_enabled: bool,
     // This is needed to maintain backwards-compatibility for pre-persistent-column migrations.
     disable_persistent_fallback: bool,
@@ -103,14 +104,21 @@ impl ParityDb {
         let opts = Self::to_options(path.into(), config);
         Ok(Self {
             db: Db::open_or_create(&opts)?,
+            path: opts.path,
             statistics_enabled: opts.stats,
             disable_persistent_fallback: false,
         })
     }
 
-    pub fn wrap(db: parity_db::Db, stats: bool, disable_persistent: bool) -> Self {
+    pub fn wrap(
+        db: parity_db::Db,
+        path: impl Into<PathBuf>,
+        stats: bool,
+        disable_persistent: bool,
+    ) -> Self {
         Self {
             db,
+            path: path.into(),
             statistics_enabled: stats,
             disable_persistent_fallback: disable_persistent,
         }
@@ -146,6 +154,54 @@ impl ParityDb {
             .commit(tx)
             .map_err(|e| anyhow!("error writing to column {column}: {e}"))
     }
+
+    fn count_keys(&self, column: DbColumn) -> anyhow::Result<u64> {
+        let mut count = 0;
+        match column {
+            // Columns with a B-tree index are iterated by key.
+            DbColumn::GraphFull | DbColumn::Settings => {
+                let mut iter = self.db.iter(column as u8)?;
+                while iter.next()?.is_some() {
+                    count += 1;
+                }
+            }
+            DbColumn::GraphDagCborBlake2b256
+            | DbColumn::EthMappings
+            | DbColumn::PersistentGraph => {
+                self.db.iter_column_while(column as u8, |_| {
+                    count += 1;
+                    true
+                })?;
+            }
+        }
+        Ok(count)
+    }
+}
+
+/// Returns the size on disk of the index and value table files of a column,
+/// named e.g. `index_00_16` and `table_00_1f`.
+fn column_disk_size(db_directory: &Path, column: DbColumn) -> anyhow::Result<u64> {
+    let index_prefix = format!("index_{:02}_", column as u8);
+    let table_prefix = format!("table_{:02}_", column as u8);
+    let mut size = 0;
+    for entry in std::fs::read_dir(db_directory)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with(&index_prefix) || name.starts_with(&table_prefix) {
+            size += entry.metadata()?.len();
+        }
+    }
+    Ok(size)
+}
+
+/// Returns the name and size on disk of each column of the database at
+/// `db_directory`. Unlike [`DBStatistics::column_stats`], this does not read
+/// the database.
+pub fn column_disk_sizes(db_directory: &Path) -> anyhow::Result<Vec<(String, u64)>> {
+    DbColumn::iter()
+        .map(|column| Ok((column.to_string(), column_disk_size(db_directory, column)?)))
+        .collect()
 }
 
 impl SettingsStore for ParityDb {
@@ -172,6 +228,13 @@ impl SettingsStore for ParityDb {
         }
         Ok(keys)
     }
+
+    fn delete(&self, key: &str) -> anyhow::Result<()> {
+        let tx = [(DbColumn::Settings as u8, key.as_bytes(), None)];
+        self.db
+            .commit(tx)
+            .map_err(|e| anyhow!("error deleting from column {}: {e}", DbColumn::Settings))
+    }
 }
 
 impl EthMappingsStore for ParityDb {
@@ -307,6 +370,18 @@ impl DBStatistics for ParityDb {
             }
         }
     }
+
+    fn column_stats(&self) -> anyhow::Result<Vec<ColumnStats>> {
+        DbColumn::iter()
+            .map(|column| {
+                Ok(ColumnStats {
+                    name: column.to_string(),
+                    num_keys: self.count_keys(column)?,
+                    disk_size_bytes: column_disk_size(&self.path, column)?,
+                })
+            })
+            .collect()
+    }
 }
 
 type Op = (u8, Operation<Vec<u8>, Vec<u8>>);
@@ -576,4 +651,47 @@ mod test {
             );
         }
     }
+
+    #[test]
+    fn column_stats_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("paritydb");
+        let config = ParityDbConfig::default();
+        let num_keys = |db: &ParityDb, column: DbColumn| {
+            db.column_stats()
+                .unwrap()
+                .into_iter()
+                .find(|stats| stats.name == column.to_string())
+                .unwrap()
+                .num_keys
+        };
+
+        let db = ParityDb::open(&path, &config).unwrap();
+        let before = [
+            num_keys(&db, DbColumn::GraphDagCborBlake2b256),
+            num_keys(&db, DbColumn::GraphFull),
+        ];
+        for i in 0..10_u8 {
+            let data = [i; 32];
+            db.put_keyed(
+                &Cid::new_v1(DAG_CBOR, MultihashCode::Blake2b256.digest(&data)),
+                &data,
+            )
+            .unwrap();
+            db.put_keyed(
+                &Cid::new_v1(IPLD_RAW, MultihashCode::Blake2b256.digest(&data)),
+                &data,
+            )
+            .unwrap();
+        }
+        // Iterating columns does not guarantee the visibility of the latest
+        // commits, reopening the database flushes them.
+        drop(db);
+        let db = ParityDb::open(&path, &config).unwrap();
+        assert_eq!(
+            num_keys(&db, DbColumn::GraphDagCborBlake2b256),
+            before[0] + 10
+        );
+        assert_eq!(num_keys(&db, DbColumn::GraphFull), before[1] + 10);
+    }
 }
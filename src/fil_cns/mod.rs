@@ -5,10 +5,12 @@ use std::{fmt::Debug, sync::Arc};
 use crate::beacon::BeaconSchedule;
 use crate::blocks::{Block, Tipset};
 use crate::chain::{Error as ChainStoreError, Weight};
+use crate::shim::fvm_shared_latest::sector::RegisteredPoStProof;
 use crate::state_manager::{Error as StateManagerError, StateManager};
 use anyhow::anyhow;
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::Error as ForestEncodingError;
+use num::BigInt;
 use nunny::Vec as NonEmpty;
 use thiserror::Error;
 
@@ -26,6 +28,8 @@ pub enum FilecoinConsensusError {
     BlockHeightNotGreaterThanParentHeight { current: i64, parent: i64 },
     #[error("Block had the wrong timestamp: {0} != {1}")]
     UnequalBlockTimestamps(u64, u64),
+    #[error("Block timestamp {timestamp} does not match its epoch, expected {expected}")]
+    BlockTimestampNotAlignedWithGenesis { timestamp: u64, expected: u64 },
     #[error("Tipset without ticket to verify")]
     TipsetWithoutTicket,
     #[error("Block is not claiming to be a winner")]
@@ -50,6 +54,11 @@ pub enum FilecoinConsensusError {
     BeaconValidation(String),
     #[error("Failed to verify winning PoSt: {0}")]
     WinningPoStValidation(String),
+    #[error("Winning PoSt proof type {actual:?} does not match the miner window PoSt proof type {window:?}")]
+    WinningPoStProofTypeMismatch {
+        actual: RegisteredPoStProof,
+        window: RegisteredPoStProof,
+    },
     #[error("Chain store error: {0}")]
     ChainStore(#[from] ChainStoreError),
     #[error("StateManager error: {0}")]
@@ -95,3 +104,15 @@ where
 {
     weight::weight(&Arc::new(db), ts).map_err(|s| anyhow!(s))
 }
+
+/// Returns the integer base-2 logarithm of the total network power `tpow`,
+/// see [`weight_for_log2_power`].
+pub fn log2_power(tpow: &BigInt) -> Result<u64, anyhow::Error> {
+    weight::log2_power(tpow).map_err(|s| anyhow!(s))
+}
+
+/// Returns the weight of `ts` as [`weight`] does, from the logarithm of the
+/// total network power rather than from the power actor state.
+pub fn weight_for_log2_power(ts: &Tipset, log2_power: u64) -> Result<Weight, anyhow::Error> {
+    weight::weight_for_log2_power(ts, log2_power).map_err(|s| anyhow!(s))
+}
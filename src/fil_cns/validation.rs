@@ -14,14 +14,15 @@ use crate::shim::actors::PowerActorStateLoad as _;
 use crate::shim::crypto::{
     cid_to_replica_commitment_v1, verify_bls_sig, TICKET_RANDOMNESS_LOOKBACK,
 };
-use crate::shim::sector::RegisteredSealProof;
+use crate::shim::fvm_shared_latest::sector::RegisteredPoStProof as RegisteredPoStProofV4;
+use crate::shim::sector::{RegisteredPoStProof, RegisteredSealProof};
 use crate::shim::{
     address::Address,
     randomness::Randomness,
     sector::{PoStProof, SectorInfo},
     version::NetworkVersion,
 };
-use crate::state_manager::StateManager;
+use crate::state_manager::{MinerConsensusStatus, StateManager};
 use crate::utils::encoding::prover_id_from_u64;
 use cid::Cid;
 use fil_actors_shared::filecoin_proofs_api::{post, PublicReplicaInfo, SectorId};
@@ -66,6 +67,7 @@ pub(in crate::fil_cns) async fn validate_block<DB: Blockstore + Sync + Send + 's
     block_timestamp_checks(
         header,
         base_tipset.as_ref(),
+        chain_store.genesis_block_header().timestamp,
         state_manager.chain_config().as_ref(),
     )
     .map_err(to_errs)?;
@@ -135,10 +137,18 @@ pub(in crate::fil_cns) async fn validate_block<DB: Blockstore + Sync + Send + 's
             let parent_epoch = base_tipset.epoch();
             let prev_beacon = Arc::clone(&prev_beacon);
             let nv = state_manager.get_network_version(header.epoch);
+            let verified_rounds = (!state_manager.sync_config().distrust_beacon_cache)
+                .then(|| state_manager.chain_store().settings());
             async move {
                 block
                     .header()
-                    .validate_block_drand(nv, beacon_schedule.as_ref(), parent_epoch, &prev_beacon)
+                    .validate_block_drand(
+                        nv,
+                        beacon_schedule.as_ref(),
+                        parent_epoch,
+                        &prev_beacon,
+                        verified_rounds.as_deref(),
+                    )
                     .map_err(|e| FilecoinConsensusError::BeaconValidation(e.to_string()))
             }
         }));
@@ -192,10 +202,11 @@ fn block_sanity_checks(header: &CachingBlockHeader) -> Result<(), FilecoinConsen
 }
 
 /// Check the timestamp corresponds exactly to the number of epochs since the
-/// parents.
+/// genesis and since the parents.
 fn block_timestamp_checks(
     header: &CachingBlockHeader,
     base_tipset: &Tipset,
+    genesis_timestamp: u64,
     chain_config: &ChainConfig,
 ) -> Result<(), FilecoinConsensusError> {
     if header.epoch <= base_tipset.epoch() {
@@ -208,6 +219,15 @@ fn block_timestamp_checks(
     }
     // Timestamp checks
     let block_delay = chain_config.block_delay_secs;
+    let expected = genesis_timestamp + block_delay as u64 * header.epoch as u64;
+    if header.timestamp != expected {
+        return Err(
+            FilecoinConsensusError::BlockTimestampNotAlignedWithGenesis {
+                timestamp: header.timestamp,
+                expected,
+            },
+        );
+    }
     let nulls = header.epoch - (base_tipset.epoch() + 1);
     let target_timestamp = base_tipset.min_timestamp() + block_delay as u64 * (nulls + 1) as u64;
     if target_timestamp != header.timestamp {
@@ -282,7 +302,9 @@ fn validate_winner_election<DB: Blockstore + Sync + Send + 'static>(
 
     verify_election_post_vrf(work_addr, &vrf_base, election_proof.vrfproof.as_bytes())?;
 
-    if state_manager.is_miner_slashed(&header.miner_address, base_tipset.parent_state())? {
+    if state_manager.miner_consensus_status(&header.miner_address, base_tipset)?
+        == MinerConsensusStatus::NoClaim
+    {
         return Err(FilecoinConsensusError::InvalidOrSlashedMiner);
     }
 
@@ -365,6 +387,14 @@ fn verify_winning_post_proof<DB: Blockstore>(
         .get_or_create(&metrics::values::VERIFY_WINNING_POST_PROOF);
     let _timer = metric.start_timer();
 
+    let miner_info = state_manager
+        .get_miner_info_at(&header.miner_address, *lookback_state)
+        .map_err(|e| FilecoinConsensusError::WinningPoStValidation(e.to_string()))?;
+    winning_post_proof_type_checks(
+        header,
+        *RegisteredPoStProof::from(miner_info.window_post_proof_type),
+    )?;
+
     let miner_addr_buf = to_vec(&header.miner_address)?;
     let rand_base = header
         .beacon_entries
@@ -405,6 +435,38 @@ fn verify_winning_post_proof<DB: Blockstore>(
     .map_err(|e| FilecoinConsensusError::WinningPoStValidation(e.to_string()))
 }
 
+/// Checks that the winning PoSt proofs of the header are of the winning
+/// variant of the miner's window PoSt proof type, i.e. for the same sector size.
+fn winning_post_proof_type_checks(
+    header: &CachingBlockHeader,
+    window_post_proof: RegisteredPoStProofV4,
+) -> Result<(), FilecoinConsensusError> {
+    use RegisteredPoStProofV4::*;
+    let expected = match window_post_proof {
+        StackedDRGWindow2KiBV1 | StackedDRGWindow2KiBV1P1 => StackedDRGWinning2KiBV1,
+        StackedDRGWindow8MiBV1 | StackedDRGWindow8MiBV1P1 => StackedDRGWinning8MiBV1,
+        StackedDRGWindow512MiBV1 | StackedDRGWindow512MiBV1P1 => StackedDRGWinning512MiBV1,
+        StackedDRGWindow32GiBV1 | StackedDRGWindow32GiBV1P1 => StackedDRGWinning32GiBV1,
+        StackedDRGWindow64GiBV1 | StackedDRGWindow64GiBV1P1 => StackedDRGWinning64GiBV1,
+        _ => {
+            return Err(FilecoinConsensusError::WinningPoStValidation(format!(
+                "unsupported window PoSt proof type {window_post_proof:?}"
+            )))
+        }
+    };
+    match header
+        .winning_post_proof
+        .iter()
+        .find(|proof| proof.post_proof != expected)
+    {
+        Some(proof) => Err(FilecoinConsensusError::WinningPoStProofTypeMismatch {
+            actual: proof.post_proof,
+            window: window_post_proof,
+        }),
+        None => Ok(()),
+    }
+}
+
 fn to_fil_public_replica_infos(
     src: &[SectorInfo],
     typ: ProofType,
@@ -471,3 +533,86 @@ fn verify_winning_post(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::RawBlockHeader;
+
+    const GENESIS_TIMESTAMP: u64 = 1_000_000;
+
+    fn header(epoch: i64, timestamp: u64) -> CachingBlockHeader {
+        CachingBlockHeader::new(RawBlockHeader {
+            epoch,
+            timestamp,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn block_timestamps_are_aligned_with_genesis() {
+        let chain_config = ChainConfig::default();
+        let delay = chain_config.block_delay_secs as u64;
+        let aligned = |epoch: i64| GENESIS_TIMESTAMP + delay * epoch as u64;
+        let base_tipset = Tipset::from(header(1, aligned(1)));
+        let check = |header: &CachingBlockHeader| {
+            block_timestamp_checks(header, &base_tipset, GENESIS_TIMESTAMP, &chain_config)
+        };
+
+        check(&header(2, aligned(2))).unwrap();
+        // After null rounds
+        check(&header(4, aligned(4))).unwrap();
+        for skewed in [aligned(2) - 1, aligned(2) + 1, aligned(3)] {
+            assert!(matches!(
+                check(&header(2, skewed)),
+                Err(FilecoinConsensusError::BlockTimestampNotAlignedWithGenesis {
+                    timestamp,
+                    expected,
+                }) if timestamp == skewed && expected == aligned(2)
+            ));
+        }
+    }
+
+    #[test]
+    fn winning_post_proof_type_matches_window_post_proof_type() {
+        use RegisteredPoStProofV4::*;
+        let with_proofs = |proofs: &[RegisteredPoStProofV4]| {
+            CachingBlockHeader::new(RawBlockHeader {
+                winning_post_proof: proofs
+                    .iter()
+                    .map(|proof| PoStProof::new((*proof).into(), vec![]))
+                    .collect(),
+                ..Default::default()
+            })
+        };
+
+        winning_post_proof_type_checks(
+            &with_proofs(&[StackedDRGWinning32GiBV1]),
+            StackedDRGWindow32GiBV1P1,
+        )
+        .unwrap();
+        winning_post_proof_type_checks(
+            &with_proofs(&[StackedDRGWinning2KiBV1]),
+            StackedDRGWindow2KiBV1,
+        )
+        .unwrap();
+        assert!(matches!(
+            winning_post_proof_type_checks(
+                &with_proofs(&[StackedDRGWinning32GiBV1, StackedDRGWinning64GiBV1]),
+                StackedDRGWindow32GiBV1P1,
+            ),
+            Err(FilecoinConsensusError::WinningPoStProofTypeMismatch {
+                actual: StackedDRGWinning64GiBV1,
+                window: StackedDRGWindow32GiBV1P1,
+            })
+        ));
+        // A window PoSt proof type in place of a winning one
+        assert!(matches!(
+            winning_post_proof_type_checks(
+                &with_proofs(&[StackedDRGWindow32GiBV1P1]),
+                StackedDRGWindow32GiBV1P1,
+            ),
+            Err(FilecoinConsensusError::WinningPoStProofTypeMismatch { .. })
+        ));
+    }
+}
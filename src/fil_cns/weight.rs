@@ -27,16 +27,29 @@ where
     let state_tree = StateTree::new_from_tipset(Arc::clone(db), ts).map_err(|e| e.to_string())?;
     let state: power::State = state_tree.get_actor_state().map_err(|e| e.to_string())?;
 
-    let tpow = state.into_total_quality_adj_power();
-    let log2_p = if tpow > BigInt::zero() {
-        BigInt::from(tpow.bits() - 1)
+    weight_for_log2_power(ts, log2_power(&state.into_total_quality_adj_power())?)
+}
+
+/// Returns the integer base-2 logarithm of the total network power, the only
+/// way the power enters the weight.
+pub(in crate::fil_cns) fn log2_power(tpow: &BigInt) -> Result<u64, String> {
+    if tpow > &BigInt::zero() {
+        Ok(tpow.bits() - 1)
     } else {
-        return Err(
+        Err(
             "All power in the net is gone. You network might be disconnected, or the net is dead!"
                 .to_owned(),
-        );
-    };
+        )
+    }
+}
 
+/// Returns the weight of provided [Tipset] for a total network power of
+/// integer base-2 logarithm `log2_p`.
+pub(in crate::fil_cns) fn weight_for_log2_power(
+    ts: &Tipset,
+    log2_p: u64,
+) -> Result<BigInt, String> {
+    let log2_p = BigInt::from(log2_p);
     let mut total_j = 0;
     for b in ts.block_headers() {
         total_j += b
@@ -0,0 +1,222 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use crate::blocks::CachingBlockHeader;
+use crate::networks::{ChainConfig, Height, NetworkChain};
+use crate::utils::encoding::from_slice_with_fallback;
+use anyhow::{bail, Context as _};
+use fvm_shared4::consensus::ConsensusFaultType;
+
+/// Returns the consensus fault proven by the block headers, if any, with the
+/// conditions checked by the `verify_consensus_fault` externs when the miner
+/// actor handles a `ReportConsensusFault` message, which call it for every
+/// FVM version. `h1` must not be of a higher epoch than `h2`, and `extra`,
+/// if not empty, is the encoded witness of a parent grinding fault: a
+/// sibling of `h1` that `h2` was mined on, omitting `h1`.
+///
+/// The signatures of the headers are not verified, the externs checking them
+/// against the worker of the miner once a fault is found.
+pub fn detect_consensus_fault(
+    chain_config: &ChainConfig,
+    h1: &CachingBlockHeader,
+    h2: &CachingBlockHeader,
+    extra: &[u8],
+) -> anyhow::Result<Option<ConsensusFaultType>> {
+    // Note that block syntax is not validated. Any validly signed block will be
+    // accepted pursuant to the below conditions. Whether or not it could
+    // ever have been accepted in a chain is not checked/does not matter here.
+    // for that reason when checking block parent relationships, rather than
+    // instantiating a Tipset to do so (which runs a syntactic check), we do
+    // it directly on the CIDs.
+
+    if h1.cid() == h2.cid() {
+        bail!("no consensus fault: submitted blocks are the same");
+    }
+
+    // This is a workaround for the broken calibnet chain. See:
+    // https://github.com/filecoin-project/lotus/pull/11399
+    // Basically we relax the consensus fault checks around the WatermelonFix epoch.
+    if chain_config.network == NetworkChain::Calibnet {
+        let watermelonfix_height = chain_config
+            .height_infos
+            .get(&Height::WatermelonFix)
+            .context("Missing WatermelonFix for calibnet")?
+            .epoch;
+
+        let finality = chain_config.policy.chain_finality;
+
+        let is_near_upgrade = |epoch| {
+            epoch > watermelonfix_height - finality && epoch < watermelonfix_height + finality
+        };
+
+        if is_near_upgrade(h1.epoch) || is_near_upgrade(h2.epoch) {
+            return Ok(None);
+        }
+    }
+
+    // (1) check conditions necessary to any consensus fault
+
+    if h1.miner_address != h2.miner_address {
+        bail!(
+            "no consensus fault: blocks not mined by same miner: {:?}, {:?}",
+            h1.miner_address,
+            h2.miner_address
+        );
+    };
+    // block a must be earlier or equal to block b, epoch wise (ie at least as early
+    // in the chain).
+    if h2.epoch < h1.epoch {
+        bail!(
+            "first block must not be of higher height than second: {:?}, {:?}",
+            h1.epoch,
+            h2.epoch
+        );
+    };
+
+    let mut fault_type = None;
+
+    // (2) check for the consensus faults themselves
+
+    // (a) double-fork mining fault
+    if h1.epoch == h2.epoch {
+        fault_type = Some(ConsensusFaultType::DoubleForkMining);
+    };
+
+    // (b) time-offset mining fault
+    // strictly speaking no need to compare heights based on double fork mining
+    // check above, but at same height this would be a different fault.
+    if h1.parents == h2.parents && h1.epoch != h2.epoch {
+        fault_type = Some(ConsensusFaultType::TimeOffsetMining);
+    };
+
+    // (c) parent-grinding fault
+    // Here extra is the "witness", a third block that shows the connection between
+    // A and B as A's sibling and B's parent.
+    // Specifically, since A is of lower height, it must be that B was mined
+    // omitting A from its tipset
+    if !extra.is_empty() {
+        let h3 = from_slice_with_fallback::<CachingBlockHeader>(extra)?;
+        if h1.parents == h3.parents
+            && h1.epoch == h3.epoch
+            && h2.parents.contains(*h3.cid())
+            && !h2.parents.contains(*h1.cid())
+        {
+            fault_type = Some(ConsensusFaultType::ParentGrinding);
+        }
+    };
+
+    Ok(fault_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::{RawBlockHeader, TipsetKey};
+    use crate::shim::address::Address;
+    use crate::shim::clock::ChainEpoch;
+    use cid::Cid;
+
+    const MINER: Address = Address::new_id(1000);
+
+    fn detect(
+        h1: &CachingBlockHeader,
+        h2: &CachingBlockHeader,
+        extra: Option<&CachingBlockHeader>,
+    ) -> anyhow::Result<Option<ConsensusFaultType>> {
+        let extra = extra
+            .map(|h| fvm_ipld_encoding::to_vec(h).unwrap())
+            .unwrap_or_default();
+        detect_consensus_fault(&ChainConfig::default(), h1, h2, &extra)
+    }
+
+    fn header(epoch: ChainEpoch, parents: &[Cid], timestamp: u64) -> CachingBlockHeader {
+        CachingBlockHeader::new(RawBlockHeader {
+            miner_address: MINER,
+            epoch,
+            parents: TipsetKey::from(nunny::Vec::new(parents.to_vec()).unwrap()),
+            timestamp,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn double_fork_mining() {
+        let parents = [Cid::default()];
+        let (h1, h2) = (header(10, &parents, 1), header(10, &parents, 2));
+        assert_eq!(
+            detect(&h1, &h2, None).unwrap(),
+            Some(ConsensusFaultType::DoubleForkMining)
+        );
+    }
+
+    #[test]
+    fn time_offset_mining() {
+        let parents = [Cid::default()];
+        let (h1, h2) = (header(10, &parents, 1), header(11, &parents, 1));
+        assert_eq!(
+            detect(&h1, &h2, None).unwrap(),
+            Some(ConsensusFaultType::TimeOffsetMining)
+        );
+        // The first header must not be the later one
+        detect(&h2, &h1, None).unwrap_err();
+    }
+
+    #[test]
+    fn parent_grinding() {
+        let parents = [Cid::default()];
+        let h1 = header(10, &parents, 1);
+        // A sibling of `h1` by another miner
+        let sibling = CachingBlockHeader::new(RawBlockHeader {
+            miner_address: Address::new_id(1001),
+            ..header(10, &parents, 1).into_raw()
+        });
+        let h2 = header(11, &[*sibling.cid()], 2);
+        assert_eq!(
+            detect(&h1, &h2, Some(&sibling)).unwrap(),
+            Some(ConsensusFaultType::ParentGrinding)
+        );
+        // Not a fault without the witness, nor if `h2` includes `h1`
+        assert_eq!(detect(&h1, &h2, None).unwrap(), None);
+        let h2 = header(11, &[*sibling.cid(), *h1.cid()], 2);
+        assert_eq!(detect(&h1, &h2, Some(&sibling)).unwrap(), None);
+    }
+
+    #[test]
+    fn no_fault() {
+        let h1 = header(10, &[Cid::default()], 1);
+        // A block on top of `h1`
+        let h2 = header(11, &[*h1.cid()], 2);
+        assert_eq!(detect(&h1, &h2, None).unwrap(), None);
+        // The same block
+        detect(&h1, &h1, None).unwrap_err();
+        // Blocks of different miners
+        let other = CachingBlockHeader::new(RawBlockHeader {
+            miner_address: Address::new_id(1001),
+            ..header(10, &[Cid::default()], 2).into_raw()
+        });
+        detect(&h1, &other, None).unwrap_err();
+    }
+
+    #[test]
+    fn calibnet_watermelon_fix_exemption() {
+        let chain_config = ChainConfig::calibnet();
+        let watermelon_fix = chain_config
+            .height_infos
+            .get(&Height::WatermelonFix)
+            .unwrap()
+            .epoch;
+        let parents = [Cid::default()];
+        let (h1, h2) = (
+            header(watermelon_fix, &parents, 1),
+            header(watermelon_fix, &parents, 2),
+        );
+        assert_eq!(
+            detect_consensus_fault(&chain_config, &h1, &h2, &[]).unwrap(),
+            None
+        );
+        assert_eq!(
+            detect(&h1, &h2, None).unwrap(),
+            Some(ConsensusFaultType::DoubleForkMining)
+        );
+    }
+}
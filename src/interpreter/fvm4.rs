@@ -10,8 +10,8 @@ use crate::chain::{
     ChainStore,
 };
 use crate::interpreter::errors::Error;
-use crate::interpreter::resolve_to_key_addr;
-use crate::networks::{ChainConfig, Height, NetworkChain};
+use crate::interpreter::{detect_consensus_fault, resolve_to_key_addr};
+use crate::networks::ChainConfig;
 use crate::shim::actors::miner;
 use crate::shim::actors::MinerActorStateLoad as _;
 use crate::shim::{
@@ -29,10 +29,7 @@ use fvm_ipld_blockstore::{
     tracking::{BSStats, TrackingBlockstore},
     Blockstore,
 };
-use fvm_shared4::{
-    clock::ChainEpoch,
-    consensus::{ConsensusFault, ConsensusFaultType},
-};
+use fvm_shared4::{clock::ChainEpoch, consensus::ConsensusFault};
 
 pub struct ForestExterns<DB> {
     rand: Box<dyn Rand>,
@@ -162,13 +159,6 @@ impl<DB: Blockstore + Send + Sync + 'static> Consensus for ForestExterns<DB> {
     ) -> anyhow::Result<(Option<ConsensusFault>, i64)> {
         let mut total_gas: i64 = 0;
 
-        // Note that block syntax is not validated. Any validly signed block will be
-        // accepted pursuant to the below conditions. Whether or not it could
-        // ever have been accepted in a chain is not checked/does not matter here.
-        // for that reason when checking block parent relationships, rather than
-        // instantiating a Tipset to do so (which runs a syntactic check), we do
-        // it directly on the CIDs.
-
         // (0) cheap preliminary checks
 
         // are blocks the same?
@@ -183,82 +173,7 @@ impl<DB: Blockstore + Send + Sync + 'static> Consensus for ForestExterns<DB> {
         let bh_1 = from_slice_with_fallback::<CachingBlockHeader>(h1)?;
         let bh_2 = from_slice_with_fallback::<CachingBlockHeader>(h2)?;
 
-        if bh_1.cid() == bh_2.cid() {
-            bail!("no consensus fault: submitted blocks are the same");
-        }
-
-        // This is a workaround for the broken calibnet chain. See:
-        // https://github.com/filecoin-project/lotus/pull/11399
-        // Basically we relax the consensus fault checks around the WatermelonFix epoch.
-        if self.chain_config.network == NetworkChain::Calibnet {
-            let watermelonfix_height = self
-                .chain_config
-                .height_infos
-                .get(&Height::WatermelonFix)
-                .context("Missing WatermelonFix for calibnet")?
-                .epoch;
-
-            let finality = self.chain_config.policy.chain_finality;
-
-            let is_near_upgrade = |epoch| {
-                epoch > watermelonfix_height - finality && epoch < watermelonfix_height + finality
-            };
-
-            if is_near_upgrade(bh_1.epoch) || is_near_upgrade(bh_2.epoch) {
-                return Ok((None, total_gas));
-            }
-        }
-
-        // (1) check conditions necessary to any consensus fault
-
-        if bh_1.miner_address != bh_2.miner_address {
-            bail!(
-                "no consensus fault: blocks not mined by same miner: {:?}, {:?}",
-                bh_1.miner_address,
-                bh_2.miner_address
-            );
-        };
-        // block a must be earlier or equal to block b, epoch wise (ie at least as early
-        // in the chain).
-        if bh_2.epoch < bh_1.epoch {
-            bail!(
-                "first block must not be of higher height than second: {:?}, {:?}",
-                bh_1.epoch,
-                bh_2.epoch
-            );
-        };
-
-        let mut fault_type: Option<ConsensusFaultType> = None;
-
-        // (2) check for the consensus faults themselves
-
-        // (a) double-fork mining fault
-        if bh_1.epoch == bh_2.epoch {
-            fault_type = Some(ConsensusFaultType::DoubleForkMining);
-        };
-
-        // (b) time-offset mining fault
-        // strictly speaking no need to compare heights based on double fork mining
-        // check above, but at same height this would be a different fault.
-        if bh_1.parents == bh_2.parents && bh_1.epoch != bh_2.epoch {
-            fault_type = Some(ConsensusFaultType::TimeOffsetMining);
-        };
-
-        // (c) parent-grinding fault
-        // Here extra is the "witness", a third block that shows the connection between
-        // A and B as A's sibling and B's parent.
-        // Specifically, since A is of lower height, it must be that B was mined
-        // omitting A from its tipset
-        if !extra.is_empty() {
-            let bh_3 = from_slice_with_fallback::<CachingBlockHeader>(extra)?;
-            if bh_1.parents == bh_3.parents
-                && bh_1.epoch == bh_3.epoch
-                && bh_2.parents.contains(*bh_3.cid())
-                && !bh_2.parents.contains(*bh_1.cid())
-            {
-                fault_type = Some(ConsensusFaultType::ParentGrinding);
-            }
-        };
+        let fault_type = detect_consensus_fault(&self.chain_config, &bh_1, &bh_2, extra)?;
 
         match fault_type {
             None => {
@@ -1,6 +1,7 @@
 // Copyright 2019-2025 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+mod consensus_fault;
 mod errors;
 mod fvm2;
 pub mod fvm3;
@@ -15,6 +16,7 @@ use crate::shim::{
 };
 use fvm_ipld_blockstore::Blockstore;
 
+pub use self::consensus_fault::detect_consensus_fault;
 pub use self::vm::*;
 
 /// returns the public key type of address (`BLS`/`SECP256K1`) of an account
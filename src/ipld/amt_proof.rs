@@ -0,0 +1,203 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Inclusion proofs over the legacy (v0) AMT, used for the message lists of
+//! the blocks. A proof is the encoded blocks on the path from the root of the
+//! AMT to the leaf holding a value, enough to check that value against the
+//! root CID alone.
+
+use crate::ipld::Ipld;
+use crate::lotus_json::lotus_json;
+use crate::utils::multihash::prelude::*;
+use anyhow::{bail, ensure, Context as _};
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::DAG_CBOR;
+
+/// Number of children of a node, the legacy AMT having a bit width of 3.
+const WIDTH: u64 = 8;
+
+/// Height above which `WIDTH.pow(height + 1)` overflows.
+const MAX_HEIGHT: u32 = 20;
+
+lotus_json! {
+    /// Proof that a legacy AMT holds a value at `index`, see [`amt_v0_proof`].
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct AmtProof {
+        pub index: u64,
+        /// The encoded root block, followed by the encoded nodes down to the
+        /// leaf holding the value
+        pub blocks: Vec<Vec<u8>>,
+    }
+    snapshots {
+        vec![(
+            serde_json::json!({"Index": 1, "Blocks": ["gQ=="]}),
+            AmtProof {
+                index: 1,
+                blocks: vec![vec![0x81]],
+            },
+        )]
+    }
+}
+
+impl AmtProof {
+    /// Checks the proof against the `root` of the AMT, returning the value at
+    /// the proven index.
+    pub fn verify(&self, root: &Cid) -> anyhow::Result<Ipld> {
+        let mut blocks = self.blocks.iter();
+        let value = walk(root, self.index, |cid| {
+            let block = blocks.next().context("proof is missing AMT nodes")?;
+            ensure!(
+                &block_cid(block) == cid,
+                "AMT node does not match its link {cid}"
+            );
+            Ok(block.clone())
+        })?;
+        ensure!(blocks.next().is_none(), "proof has unused AMT nodes");
+        value.with_context(|| format!("no value at index {}", self.index))
+    }
+}
+
+/// Returns the proof that the legacy AMT at `root` holds a value at `index`,
+/// or `None` if it does not.
+pub fn amt_v0_proof(
+    db: &impl Blockstore,
+    root: &Cid,
+    index: u64,
+) -> anyhow::Result<Option<AmtProof>> {
+    let mut blocks = vec![];
+    let value = walk(root, index, |cid| {
+        let block = db
+            .get(cid)?
+            .with_context(|| format!("AMT node {cid} not found"))?;
+        blocks.push(block.clone());
+        Ok(block)
+    })?;
+    Ok(value.map(|_| AmtProof { index, blocks }))
+}
+
+/// `DAG-CBOR` CID of an encoded block, hashed with BLAKE2b-256.
+pub(crate) fn block_cid(block: &[u8]) -> Cid {
+    Cid::new_v1(DAG_CBOR, MultihashCode::Blake2b256.digest(block))
+}
+
+/// Descends from the root of the legacy AMT to the value at `index`,
+/// `load`ing the encoded blocks on the way.
+fn walk(
+    root: &Cid,
+    index: u64,
+    mut load: impl FnMut(&Cid) -> anyhow::Result<Vec<u8>>,
+) -> anyhow::Result<Option<Ipld>> {
+    // The root is `[height, count, node]`
+    let Ipld::List(fields) = fvm_ipld_encoding::from_slice(&load(root)?)? else {
+        bail!("invalid AMT root {root}");
+    };
+    let [Ipld::Integer(height), Ipld::Integer(_), node] = fields.as_slice() else {
+        bail!("invalid AMT root {root}");
+    };
+    let height = u32::try_from(*height).context("invalid AMT height")?;
+    ensure!(height <= MAX_HEIGHT, "AMT height {height} is too large");
+    if index >= WIDTH.pow(height + 1) {
+        return Ok(None);
+    }
+
+    let mut node = node.clone();
+    let mut level = height;
+    loop {
+        // A node is `[bitmap, links, values]`, with links above the leaves
+        // and values in them, for the set bits of the bitmap only
+        let Ipld::List(fields) = &node else {
+            bail!("invalid AMT node");
+        };
+        let [Ipld::Bytes(bitmap), Ipld::List(links), Ipld::List(values)] = fields.as_slice() else {
+            bail!("invalid AMT node");
+        };
+        let is_set = |slot: u64| bitmap.first().is_some_and(|byte| byte & (1 << slot) != 0);
+        let slot = (index / WIDTH.pow(level)) % WIDTH;
+        if !is_set(slot) {
+            return Ok(None);
+        }
+        let position = (0..slot).filter(|&slot| is_set(slot)).count();
+        if level == 0 {
+            return values
+                .get(position)
+                .cloned()
+                .map(Some)
+                .context("invalid AMT leaf");
+        }
+        let Some(Ipld::Link(child)) = links.get(position) else {
+            bail!("invalid AMT node");
+        };
+        node = fvm_ipld_encoding::from_slice(&load(child)?)?;
+        level -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MemoryDB;
+    use crate::lotus_json::assert_all_snapshots;
+    use fil_actors_shared::fvm_ipld_amt::Amtv0;
+
+    #[test]
+    fn snapshots() {
+        assert_all_snapshots::<AmtProof>();
+    }
+
+    fn amt_of(db: &MemoryDB, values: &[Cid]) -> Cid {
+        Amtv0::new_from_iter(db, values.iter().copied()).unwrap()
+    }
+
+    #[test]
+    fn amt_v0_proof_round_trip() {
+        let db = MemoryDB::default();
+        let values: Vec<_> = (0..100u32).map(|i| block_cid(&i.to_be_bytes())).collect();
+        let root = amt_of(&db, &values);
+
+        for index in [0, 7, 8, 63, 64, 99] {
+            let proof = amt_v0_proof(&db, &root, index).unwrap().unwrap();
+            // A root and 2 levels of nodes for 100 values
+            assert_eq!(proof.blocks.len(), 3);
+            assert_eq!(
+                proof.verify(&root).unwrap(),
+                Ipld::Link(values[index as usize])
+            );
+        }
+        assert_eq!(amt_v0_proof(&db, &root, 100).unwrap(), None);
+        assert_eq!(amt_v0_proof(&db, &root, u64::MAX).unwrap(), None);
+    }
+
+    #[test]
+    fn amt_v0_proof_tampering() {
+        let db = MemoryDB::default();
+        let values: Vec<_> = (0..100u32).map(|i| block_cid(&i.to_be_bytes())).collect();
+        let root = amt_of(&db, &values);
+        let proof = amt_v0_proof(&db, &root, 42).unwrap().unwrap();
+
+        // Tampering with any node breaks the chain of CIDs
+        for i in 0..proof.blocks.len() {
+            let mut tampered = proof.clone();
+            let last = tampered.blocks[i].len() - 1;
+            tampered.blocks[i][last] ^= 1;
+            assert!(tampered.verify(&root).is_err());
+        }
+
+        // A proof for an index in another subtree or another root does not
+        // verify
+        let moved = AmtProof {
+            index: 99,
+            ..proof.clone()
+        };
+        assert!(moved.verify(&root).is_err());
+        assert!(proof.verify(&amt_of(&db, &values[1..])).is_err());
+
+        // Missing or extra nodes
+        let mut truncated = proof.clone();
+        truncated.blocks.pop();
+        assert!(truncated.verify(&root).is_err());
+        let mut extended = proof.clone();
+        extended.blocks.push(vec![0x80]);
+        assert!(extended.verify(&root).is_err());
+    }
+}
@@ -1,6 +1,8 @@
 // Copyright 2019-2025 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+pub mod amt_proof;
+pub mod reachability;
 pub mod selector;
 pub mod util;
 
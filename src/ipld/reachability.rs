@@ -0,0 +1,477 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Reachability analysis of the chain graph, used to check that a snapshot
+//! contains every block a node needs to sync from it.
+//!
+//! The graph is walked the same way as [`stream_chain`](super::stream_chain):
+//! all block headers back to genesis, and the messages and state trees of the
+//! most recent epochs. Unlike the stream, the walk does not stop at the first
+//! broken link, and keeps its memory usage bounded:
+//! - the depth-first frontier only holds the path to the current block, along
+//!   with the links left to visit at each level;
+//! - visited blocks are tracked in a fixed-size bloom filter.
+
+use super::util::should_save_block_to_snapshot;
+use crate::blocks::{Tipset, TipsetKey};
+use crate::shim::clock::ChainEpoch;
+use crate::utils::db::car_stream::CarBlock;
+use crate::utils::encoding::extract_cids;
+use anyhow::Context as _;
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use itertools::Itertools as _;
+use std::fmt;
+use std::hash::BuildHasher as _;
+
+/// Number of bits set in the visited filter for each block
+const VISITED_FILTER_HASHES: u64 = 7;
+
+/// Where a broken link was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, strum::Display)]
+pub enum LinkSource {
+    /// Block header of the chain, or the genesis dummy parent
+    Header,
+    /// Messages included in a block
+    Messages,
+    /// Parent state tree of a tipset
+    StateTree,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum LinkProblem {
+    Missing,
+    /// The block data does not match the multihash of its CID
+    Corrupt,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenLink {
+    pub cid: Cid,
+    pub problem: LinkProblem,
+    pub source: LinkSource,
+    /// Epoch of the tipset the link was reached from
+    pub epoch: ChainEpoch,
+    /// Blocks on the way from the tipset to the broken link, the last one
+    /// references it. Empty for links of the tipset itself.
+    pub path: Vec<Cid>,
+}
+
+#[derive(Debug, Default)]
+pub struct ReachabilityReport {
+    pub headers_checked: u64,
+    pub blocks_checked: u64,
+    pub broken_links: Vec<BrokenLink>,
+    /// Estimated probability that a block was skipped because the visited
+    /// filter wrongly reported it as seen
+    pub false_positive_rate: f64,
+}
+
+impl ReachabilityReport {
+    pub fn is_complete(&self) -> bool {
+        self.broken_links.is_empty()
+    }
+}
+
+impl fmt::Display for ReachabilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Headers checked:     {}", self.headers_checked)?;
+        writeln!(f, "Blocks checked:      {}", self.blocks_checked)?;
+        writeln!(
+            f,
+            "False positive rate: {:.6}%",
+            self.false_positive_rate * 100.
+        )?;
+        write!(f, "Broken links:        {}", self.broken_links.len())?;
+        for (source, links) in &self
+            .broken_links
+            .iter()
+            .sorted_by_key(|link| link.source)
+            .chunk_by(|link| link.source)
+        {
+            write!(f, "\n{source}:")?;
+            for link in links {
+                write!(
+                    f,
+                    "\n  {} ({}) at epoch {}",
+                    link.cid, link.problem, link.epoch
+                )?;
+                if !link.path.is_empty() {
+                    write!(f, ", via {}", link.path.iter().join(" -> "))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Walks the chain from `head` to genesis, checking that every reachable
+/// block is present in `db` and matches its CID. Messages and state trees are
+/// only walked for tipsets within `depth` epochs of `head`, and genesis.
+///
+/// `visited_filter_bytes` is the memory used to track visited blocks.
+/// `on_tipset` is called before each tipset is walked, e.g. to report
+/// progress.
+pub fn check_reachability(
+    db: &impl Blockstore,
+    head: &TipsetKey,
+    depth: ChainEpoch,
+    visited_filter_bytes: usize,
+    mut on_tipset: impl FnMut(&Tipset),
+) -> anyhow::Result<ReachabilityReport> {
+    let mut walker = Walker {
+        db,
+        visited: VisitedFilter::new(visited_filter_bytes),
+        report: ReachabilityReport::default(),
+    };
+
+    let mut key = head.clone();
+    let mut child: Option<ChainEpoch> = None;
+    let mut stateroot_limit = None;
+    loop {
+        let mut headers_ok = true;
+        for cid in key.iter() {
+            walker.report.headers_checked += 1;
+            if let Err(problem) = walker.check_block(&cid)? {
+                headers_ok = false;
+                walker.broken(cid, problem, LinkSource::Header, child, &[]);
+            }
+        }
+        // The chain can't be followed past a broken header
+        if !headers_ok {
+            break;
+        }
+
+        let tipset = Tipset::load_required(db, &key)?;
+        on_tipset(&tipset);
+        let epoch = tipset.epoch();
+        let stateroot_limit = *stateroot_limit.get_or_insert(epoch - depth);
+        if epoch > stateroot_limit || epoch == 0 {
+            for header in tipset.block_headers() {
+                walker.walk_dag(header.messages, LinkSource::Messages, epoch)?;
+            }
+            walker.walk_dag(*tipset.parent_state(), LinkSource::StateTree, epoch)?;
+        }
+
+        if epoch == 0 {
+            // The genesis block has a dummy parent that is part of snapshots
+            for cid in tipset.parents().iter() {
+                walker.report.headers_checked += 1;
+                if let Err(problem) = walker.check_block(&cid)? {
+                    walker.broken(cid, problem, LinkSource::Header, Some(epoch), &[]);
+                }
+            }
+            break;
+        }
+        child = Some(epoch);
+        key = tipset.parents().clone();
+    }
+
+    walker.report.false_positive_rate = walker.visited.false_positive_rate();
+    Ok(walker.report)
+}
+
+struct Walker<'a, DB> {
+    db: &'a DB,
+    visited: VisitedFilter,
+    report: ReachabilityReport,
+}
+
+impl<DB: Blockstore> Walker<'_, DB> {
+    /// Returns the block data, or what is wrong with it.
+    fn check_block(&self, cid: &Cid) -> anyhow::Result<Result<Vec<u8>, LinkProblem>> {
+        let Some(data) = self.db.get(cid)? else {
+            return Ok(Err(LinkProblem::Missing));
+        };
+        let block = CarBlock { cid: *cid, data };
+        if block.valid() {
+            Ok(Ok(block.data))
+        } else {
+            Ok(Err(LinkProblem::Corrupt))
+        }
+    }
+
+    /// Records a broken link. Header links are attributed to the epoch of the
+    /// child tipset, when there is one.
+    fn broken(
+        &mut self,
+        cid: Cid,
+        problem: LinkProblem,
+        source: LinkSource,
+        epoch: Option<ChainEpoch>,
+        path: &[Cid],
+    ) {
+        self.report.broken_links.push(BrokenLink {
+            cid,
+            problem,
+            source,
+            epoch: epoch.unwrap_or_default(),
+            path: path.to_vec(),
+        });
+    }
+
+    /// Depth-first walk of the DAG under `root`, skipping visited blocks.
+    fn walk_dag(&mut self, root: Cid, source: LinkSource, epoch: ChainEpoch) -> anyhow::Result<()> {
+        // Blocks on the path to the current one, with the links left to visit
+        let mut path: Vec<Cid> = vec![];
+        let mut frontier: Vec<std::vec::IntoIter<Cid>> = vec![];
+        let mut next = Some(root);
+        loop {
+            if let Some(cid) = next.take() {
+                if should_save_block_to_snapshot(cid) && self.visited.insert(&cid) {
+                    self.report.blocks_checked += 1;
+                    match self.check_block(&cid)? {
+                        Ok(data) if cid.codec() == fvm_ipld_encoding::DAG_CBOR => {
+                            let links = extract_cids(&data)
+                                .with_context(|| format!("failed to decode block {cid}"))?;
+                            path.push(cid);
+                            frontier.push(links.into_iter());
+                        }
+                        Ok(_) => {}
+                        Err(problem) => self.broken(cid, problem, source, Some(epoch), &path),
+                    }
+                }
+            }
+            match frontier.last_mut() {
+                Some(links) => {
+                    next = links.next();
+                    if next.is_none() {
+                        frontier.pop();
+                        path.pop();
+                    }
+                }
+                None => return Ok(()),
+            }
+        }
+    }
+}
+
+/// Bloom filter of visited blocks. A false positive skips a block that has
+/// not been visited, and the subgraph only reachable through it.
+struct VisitedFilter {
+    bits: Vec<u64>,
+    hasher: ahash::RandomState,
+    len: u64,
+}
+
+impl VisitedFilter {
+    fn new(size_bytes: usize) -> Self {
+        Self {
+            bits: vec![0; (size_bytes / 8).max(1)],
+            // Fixed seeds, so that runs are reproducible
+            hasher: ahash::RandomState::with_seeds(1, 2, 3, 4),
+            len: 0,
+        }
+    }
+
+    fn num_bits(&self) -> u64 {
+        self.bits.len() as u64 * 64
+    }
+
+    /// Returns `true` if the block had not been inserted before.
+    fn insert(&mut self, cid: &Cid) -> bool {
+        let hash = self.hasher.hash_one(cid);
+        // Double hashing, see <https://doi.org/10.1002/rsa.20208>
+        let (h1, h2) = (hash & 0xffff_ffff, (hash >> 32) | 1);
+        let num_bits = self.num_bits();
+        let mut inserted = false;
+        for i in 0..VISITED_FILTER_HASHES {
+            let bit = h1.wrapping_add(i.wrapping_mul(h2)) % num_bits;
+            if let Some(word) = self.bits.get_mut((bit / 64) as usize) {
+                let mask = 1 << (bit % 64);
+                if *word & mask == 0 {
+                    *word |= mask;
+                    inserted = true;
+                }
+            }
+        }
+        if inserted {
+            self.len += 1;
+        }
+        inserted
+    }
+
+    fn false_positive_rate(&self) -> f64 {
+        let k = VISITED_FILTER_HASHES as f64;
+        let fill = 1. - (-k * self.len as f64 / self.num_bits() as f64).exp();
+        fill.powf(k)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::{CachingBlockHeader, RawBlockHeader};
+    use crate::db::car::{forest, ForestCar};
+    use crate::db::MemoryDB;
+    use crate::utils::multihash::prelude::*;
+    use futures::StreamExt as _;
+    use fvm_ipld_encoding::DAG_CBOR;
+    use nunny::vec as nonempty;
+    use serde::Serialize;
+    use tokio::io::AsyncWriteExt as _;
+
+    fn block(value: &impl Serialize) -> CarBlock {
+        let data = fvm_ipld_encoding::to_vec(value).unwrap();
+        CarBlock {
+            cid: Cid::new_v1(DAG_CBOR, MultihashCode::Blake2b256.digest(&data)),
+            data,
+        }
+    }
+
+    /// Chain of a genesis and a child tipset, each with a two-level state tree
+    /// and a message block.
+    struct TestChain {
+        blocks: Vec<CarBlock>,
+        head: TipsetKey,
+        state_leaf: Cid,
+        genesis: Cid,
+    }
+
+    impl TestChain {
+        fn new() -> Self {
+            let dummy_parent = block(&"genesis parent");
+            let state_leaf = block(&"state leaf");
+            let genesis_state = block(&vec![state_leaf.cid]);
+            let genesis_messages = block(&"genesis messages");
+            let genesis = CachingBlockHeader::new(RawBlockHeader {
+                parents: TipsetKey::from(nonempty![dummy_parent.cid]),
+                state_root: genesis_state.cid,
+                messages: genesis_messages.cid,
+                ..Default::default()
+            });
+            let state = block(&vec![state_leaf.cid, genesis_state.cid]);
+            let messages = block(&"messages");
+            let header = CachingBlockHeader::new(RawBlockHeader {
+                parents: TipsetKey::from(nonempty![*genesis.cid()]),
+                epoch: 1,
+                state_root: state.cid,
+                messages: messages.cid,
+                ..Default::default()
+            });
+            Self {
+                head: TipsetKey::from(nonempty![*header.cid()]),
+                state_leaf: state_leaf.cid,
+                genesis: *genesis.cid(),
+                blocks: vec![
+                    block(&header),
+                    state,
+                    messages,
+                    block(&genesis),
+                    genesis_state,
+                    state_leaf,
+                    genesis_messages,
+                    dummy_parent,
+                ],
+            }
+        }
+
+        fn db_without(&self, excluded: &[Cid]) -> MemoryDB {
+            let db = MemoryDB::default();
+            for block in &self.blocks {
+                if !excluded.contains(&block.cid) {
+                    db.put_keyed(&block.cid, &block.data).unwrap();
+                }
+            }
+            db
+        }
+    }
+
+    fn check(db: &MemoryDB, head: &TipsetKey) -> ReachabilityReport {
+        check_reachability(db, head, 10, 1024, |_| {}).unwrap()
+    }
+
+    #[test]
+    fn test_complete_chain() {
+        let chain = TestChain::new();
+        let report = check(&chain.db_without(&[]), &chain.head);
+        assert!(report.is_complete(), "{report}");
+        assert_eq!(report.headers_checked, 3);
+        // Messages and state of both tipsets, with the shared leaf visited once
+        assert_eq!(report.blocks_checked, 5);
+    }
+
+    #[test]
+    fn test_missing_state_block() {
+        let chain = TestChain::new();
+        let report = check(&chain.db_without(&[chain.state_leaf]), &chain.head);
+        assert_eq!(report.broken_links.len(), 1);
+        let link = &report.broken_links[0];
+        assert_eq!(link.cid, chain.state_leaf);
+        assert_eq!(link.problem, LinkProblem::Missing);
+        assert_eq!(link.source, LinkSource::StateTree);
+        assert_eq!(link.epoch, 1);
+        assert_eq!(link.path.len(), 1);
+    }
+
+    #[test]
+    fn test_missing_header() {
+        let chain = TestChain::new();
+        let report = check(&chain.db_without(&[chain.genesis]), &chain.head);
+        assert_eq!(
+            report.broken_links,
+            vec![BrokenLink {
+                cid: chain.genesis,
+                problem: LinkProblem::Missing,
+                source: LinkSource::Header,
+                epoch: 1,
+                path: vec![],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_corrupt_block() {
+        let chain = TestChain::new();
+        let db = chain.db_without(&[]);
+        db.put_keyed(&chain.state_leaf, b"corrupt").unwrap();
+        let report = check(&db, &chain.head);
+        assert_eq!(report.broken_links.len(), 1);
+        assert_eq!(report.broken_links[0].problem, LinkProblem::Corrupt);
+    }
+
+    #[tokio::test]
+    async fn test_truncated_car() {
+        let chain = TestChain::new();
+        // Cut the archive short, as an interrupted download would
+        let blocks = chain.blocks[..chain.blocks.len() - 3].to_vec();
+        let path = tempfile::Builder::new()
+            .tempfile()
+            .unwrap()
+            .into_temp_path();
+        let mut writer = tokio::fs::File::create(&path).await.unwrap();
+        let frames =
+            forest::Encoder::compress_stream_default(futures::stream::iter(blocks).map(anyhow::Ok));
+        forest::Encoder::write(&mut writer, chain.head.to_cids(), frames)
+            .await
+            .unwrap();
+        writer.shutdown().await.unwrap();
+
+        let car = ForestCar::try_from(path.as_ref()).unwrap();
+        let report = check_reachability(&car, &chain.head, 10, 1024, |_| {}).unwrap();
+        assert_eq!(
+            report
+                .broken_links
+                .iter()
+                .map(|link| (link.source, link.problem, link.epoch))
+                .collect_vec(),
+            vec![
+                (LinkSource::StateTree, LinkProblem::Missing, 1),
+                (LinkSource::Messages, LinkProblem::Missing, 0),
+                (LinkSource::Header, LinkProblem::Missing, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_visited_filter() {
+        let mut filter = VisitedFilter::new(1024);
+        let cids = (0..100u64).map(|i| block(&i).cid).collect_vec();
+        for cid in &cids {
+            filter.insert(cid);
+        }
+        assert!(cids.iter().all(|cid| !filter.insert(cid)));
+        assert!(filter.false_positive_rate() < 0.01);
+    }
+}
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use crate::blocks::Tipset;
-use crate::cid_collections::CidHashSet;
+use crate::cid_collections::{CidHashSet, ShardedCidHashSet};
 use crate::ipld::Ipld;
 use crate::shim::clock::ChainEpoch;
 use crate::utils::db::car_stream::CarBlock;
@@ -11,21 +11,22 @@ use crate::utils::multihash::prelude::*;
 use anyhow::Context as _;
 use cid::Cid;
 use flume::TryRecvError;
-use futures::Stream;
+use futures::task::AtomicWaker;
+use futures::{Stream, StreamExt as _};
 use fvm_ipld_blockstore::Blockstore;
-use parking_lot::Mutex;
 use pin_project_lite::pin_project;
 use std::borrow::Borrow;
-use std::ops::DerefMut;
+use std::ops::RangeInclusive;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::task::{Context, Poll};
-use std::{collections::VecDeque, mem, sync::Arc};
+use std::{collections::VecDeque, sync::Arc};
 use tokio::task;
 use tokio::task::{JoinHandle, JoinSet};
 
 const BLOCK_CHANNEL_LIMIT: usize = 2048;
 
-fn should_save_block_to_snapshot(cid: Cid) -> bool {
+pub(crate) fn should_save_block_to_snapshot(cid: Cid) -> bool {
     // Don't include identity CIDs.
     // We only include raw and dagcbor, for now.
     // Raw for "code" CIDs.
@@ -114,6 +115,7 @@ pin_project! {
         dfs: VecDeque<Task>, // Depth-first work queue.
         seen: CidHashSet,
         stateroot_limit: ChainEpoch,
+        state_range: Option<RangeInclusive<ChainEpoch>>,
         fail_on_dead_links: bool,
     }
 }
@@ -123,6 +125,15 @@ impl<DB, T> ChainStream<DB, T> {
         ChainStream { seen, ..self }
     }
 
+    /// Also visits the state trees of the tipsets in `state_range`, regardless of the
+    /// `stateroot_limit`.
+    pub fn with_state_range(self, state_range: RangeInclusive<ChainEpoch>) -> Self {
+        ChainStream {
+            state_range: Some(state_range),
+            ..self
+        }
+    }
+
     #[allow(dead_code)]
     pub fn into_seen(self) -> CidHashSet {
         self.seen
@@ -151,6 +162,7 @@ pub fn stream_chain<DB: Blockstore, T: Borrow<Tipset>, ITER: Iterator<Item = T>
         dfs: VecDeque::new(),
         seen: CidHashSet::default(),
         stateroot_limit,
+        state_range: None,
         fail_on_dead_links: true,
     }
 }
@@ -168,6 +180,7 @@ pub fn stream_graph<DB: Blockstore, T: Borrow<Tipset>, ITER: Iterator<Item = T>
         dfs: VecDeque::new(),
         seen: CidHashSet::default(),
         stateroot_limit,
+        state_range: None,
         fail_on_dead_links: false,
     }
 }
@@ -189,6 +202,7 @@ impl<DB: Blockstore, T: Borrow<Tipset>, ITER: Iterator<Item = T> + Unpin> Stream
         };
 
         let stateroot_limit = *this.stateroot_limit;
+        let state_range = this.state_range.clone();
         loop {
             while let Some(task) = this.dfs.front_mut() {
                 match task {
@@ -257,9 +271,14 @@ impl<DB: Blockstore, T: Borrow<Tipset>, ITER: Iterator<Item = T> + Unpin> Stream
                             ));
                         }
 
-                        // Visit the block if it's within required depth. And a special case for `0`
-                        // epoch to match Lotus' implementation.
-                        if block.epoch == 0 || block.epoch > stateroot_limit {
+                        // Visit the block if it's within required depth or the requested state
+                        // range. And a special case for `0` epoch to match Lotus' implementation.
+                        if block.epoch == 0
+                            || block.epoch > stateroot_limit
+                            || state_range
+                                .as_ref()
+                                .is_some_and(|range| range.contains(&block.epoch))
+                        {
                             // NOTE: In the original `walk_snapshot` implementation we walk the dag
                             // immediately. Which is what we do here as well, but using a queue.
                             this.dfs.push_back(Iterate(
@@ -282,11 +301,18 @@ pin_project! {
     pub struct UnorderedChainStream<DB, T> {
         tipset_iter: T,
         db: Arc<DB>,
-        seen: Arc<Mutex<CidHashSet>>,
+        seen: Arc<ShardedCidHashSet>,
         worker_handle: JoinHandle<anyhow::Result<()>>,
         block_receiver: flume::Receiver<anyhow::Result<CarBlock>>,
+        // Registers the waker of the stream on the block channel.
+        block_stream: flume::r#async::RecvStream<'static, anyhow::Result<CarBlock>>,
         extract_sender: flume::Sender<Cid>,
+        // CIDs sent to the workers whose subgraphs have not been walked yet.
+        pending: Arc<AtomicUsize>,
+        // Woken once no CIDs are pending, when no more blocks may be sent.
+        walked: Arc<AtomicWaker>,
         stateroot_limit: ChainEpoch,
+        state_range: Option<RangeInclusive<ChainEpoch>>,
         queue: Vec<Cid>,
         fail_on_dead_links: bool,
     }
@@ -299,18 +325,34 @@ pin_project! {
 }
 
 impl<DB, T> UnorderedChainStream<DB, T> {
+    /// Skips the given CIDs, and the subgraphs they link to. Must be called
+    /// before the stream is polled.
+    pub fn with_seen(self, seen: CidHashSet) -> Self {
+        for cid in seen {
+            self.seen.insert(cid);
+        }
+        self
+    }
+
+    /// Also visits the state trees of the tipsets in `state_range`, regardless of the
+    /// `stateroot_limit`.
+    pub fn with_state_range(mut self, state_range: RangeInclusive<ChainEpoch>) -> Self {
+        self.state_range = Some(state_range);
+        self
+    }
+
     pub fn into_seen(self) -> CidHashSet {
-        let mut set = CidHashSet::new();
-        let mut guard = self.seen.lock();
-        let data = guard.deref_mut();
-        mem::swap(data, &mut set);
-        set
+        self.seen.take()
     }
 }
 
 /// Stream all blocks that are reachable before the `stateroot_limit` epoch in an unordered fashion.
 /// After this limit, only block headers are streamed. Any dead links are reported as errors.
 ///
+/// The set of visited blocks is exact, as a false positive would leave a block out of the
+/// snapshot, and grows with the number of walked blocks. `forest-tool benchmark export` reports
+/// the extra memory used by a walk.
+///
 /// # Arguments
 ///
 /// * `db` - A database that implements [`Blockstore`] interface.
@@ -318,7 +360,6 @@ impl<DB, T> UnorderedChainStream<DB, T> {
 /// * `stateroot_limit` - An epoch that signifies how far back we need to inspect tipsets, in-depth.
 ///   This has to be pre-calculated using this formula: `$cur_epoch - $depth`, where `$depth` is the
 ///   number of `[`Tipset`]` that needs inspection.
-#[allow(dead_code)]
 pub fn unordered_stream_chain<
     DB: Blockstore + Sync + Send + 'static,
     T: Borrow<Tipset>,
@@ -328,29 +369,7 @@ pub fn unordered_stream_chain<
     tipset_iter: ITER,
     stateroot_limit: ChainEpoch,
 ) -> UnorderedChainStream<DB, ITER> {
-    let (sender, receiver) = flume::bounded(BLOCK_CHANNEL_LIMIT);
-    let (extract_sender, extract_receiver) = flume::unbounded();
-    let fail_on_dead_links = true;
-    let seen = Arc::new(Mutex::new(CidHashSet::default()));
-    let handle = UnorderedChainStream::<DB, ITER>::start_workers(
-        db.clone(),
-        sender.clone(),
-        extract_receiver,
-        seen.clone(),
-        fail_on_dead_links,
-    );
-
-    UnorderedChainStream {
-        seen,
-        db,
-        worker_handle: handle,
-        block_receiver: receiver,
-        queue: Vec::new(),
-        extract_sender,
-        tipset_iter,
-        stateroot_limit,
-        fail_on_dead_links,
-    }
+    UnorderedChainStream::new(db, tipset_iter, stateroot_limit, true)
 }
 
 // Stream available graph in unordered search. All reachable nodes are touched and dead-links
@@ -364,42 +383,71 @@ pub fn unordered_stream_graph<
     tipset_iter: ITER,
     stateroot_limit: ChainEpoch,
 ) -> UnorderedChainStream<DB, ITER> {
-    let (sender, receiver) = flume::bounded(2048);
-    let (extract_sender, extract_receiver) = flume::unbounded();
-    let fail_on_dead_links = false;
-    let seen = Arc::new(Mutex::new(CidHashSet::default()));
-    let handle = UnorderedChainStream::<DB, ITER>::start_workers(
-        db.clone(),
-        sender.clone(),
-        extract_receiver,
-        seen.clone(),
-        fail_on_dead_links,
-    );
-
-    UnorderedChainStream {
-        seen,
-        db,
-        worker_handle: handle,
-        block_receiver: receiver,
-        queue: Vec::new(),
-        tipset_iter,
-        extract_sender,
-        stateroot_limit,
-        fail_on_dead_links,
-    }
+    UnorderedChainStream::new(db, tipset_iter, stateroot_limit, false)
 }
 
+/// Walks of a worker are shared with the idle workers once they queue more
+/// CIDs than this.
+const WORKER_SHARING_THRESHOLD: usize = 1024;
+
 impl<
         DB: Blockstore + Send + Sync + 'static,
         T: Borrow<Tipset>,
         ITER: Iterator<Item = T> + Unpin,
     > UnorderedChainStream<DB, ITER>
 {
+    fn new(
+        db: Arc<DB>,
+        tipset_iter: ITER,
+        stateroot_limit: ChainEpoch,
+        fail_on_dead_links: bool,
+    ) -> Self {
+        let (block_sender, block_receiver) = flume::bounded(BLOCK_CHANNEL_LIMIT);
+        let (extract_sender, extract_receiver) = flume::unbounded();
+        let seen = Arc::new(ShardedCidHashSet::new());
+        let pending = Arc::new(AtomicUsize::new(0));
+        let walked = Arc::new(AtomicWaker::new());
+        let worker_handle = Self::start_workers(
+            db.clone(),
+            block_sender,
+            extract_sender.clone(),
+            extract_receiver,
+            seen.clone(),
+            pending.clone(),
+            walked.clone(),
+            fail_on_dead_links,
+        );
+
+        UnorderedChainStream {
+            tipset_iter,
+            db,
+            seen,
+            worker_handle,
+            block_stream: block_receiver.clone().into_stream(),
+            block_receiver,
+            extract_sender,
+            pending,
+            walked,
+            stateroot_limit,
+            state_range: None,
+            queue: Vec::new(),
+            fail_on_dead_links,
+        }
+    }
+
+    /// Starts one worker per CPU. Each worker walks the subgraph of a CID
+    /// taken from the shared extract queue depth-first, and hands half of
+    /// its own queue back to the shared one when it grows large while other
+    /// workers are idle, so that a single large state tree is walked by all
+    /// the workers.
     fn start_workers(
         db: Arc<DB>,
         block_sender: flume::Sender<anyhow::Result<CarBlock>>,
+        extract_sender: flume::Sender<Cid>,
         extract_receiver: flume::Receiver<Cid>,
-        seen: Arc<Mutex<CidHashSet>>,
+        seen: Arc<ShardedCidHashSet>,
+        pending: Arc<AtomicUsize>,
+        walked: Arc<AtomicWaker>,
         fail_on_dead_links: bool,
     ) -> JoinHandle<anyhow::Result<()>> {
         task::spawn(async move {
@@ -407,34 +455,57 @@ impl<
 
             for _ in 0..num_cpus::get() {
                 let seen = seen.clone();
+                let pending = pending.clone();
+                let walked = walked.clone();
+                let extract_sender = extract_sender.clone();
                 let extract_receiver = extract_receiver.clone();
                 let db = db.clone();
                 let block_sender = block_sender.clone();
                 handles.spawn(async move {
-                    'main: while let Ok(cid) = extract_receiver.recv_async().await {
-                        let mut cid_vec = vec![cid];
-                        while let Some(cid) = cid_vec.pop() {
-                            if should_save_block_to_snapshot(cid) && seen.lock().insert(cid) {
-                                if let Some(data) = db.get(&cid)? {
-                                    if cid.codec() == fvm_ipld_encoding::DAG_CBOR {
-                                        let mut new_values = extract_cids(&data)?;
-                                        cid_vec.append(&mut new_values);
+                    let walk = async {
+                        while let Ok(cid) = extract_receiver.recv_async().await {
+                            let mut cid_vec = vec![cid];
+                            while let Some(cid) = cid_vec.pop() {
+                                if should_save_block_to_snapshot(cid) && seen.insert(cid) {
+                                    if let Some(data) = db.get(&cid)? {
+                                        if cid.codec() == fvm_ipld_encoding::DAG_CBOR {
+                                            let mut new_values = extract_cids(&data)?;
+                                            cid_vec.append(&mut new_values);
+                                        }
+                                        // Stop if the receiving end quit.
+                                        if block_sender
+                                            .send_async(Ok(CarBlock { cid, data }))
+                                            .await
+                                            .is_err()
+                                        {
+                                            return Ok(());
+                                        }
+                                    } else if fail_on_dead_links {
+                                        anyhow::bail!("missing key: {}", cid);
                                     }
-                                    // Break out of the loop if the receiving end quit.
-                                    if block_sender.send(Ok(CarBlock { cid, data })).is_err() {
-                                        break 'main;
+                                }
+                                // Share the bottom of the queue, the largest subgraphs.
+                                if cid_vec.len() > WORKER_SHARING_THRESHOLD
+                                    && extract_sender.is_empty()
+                                {
+                                    let shared = cid_vec.len() / 2;
+                                    pending.fetch_add(shared, Ordering::AcqRel);
+                                    for cid in cid_vec.drain(..shared) {
+                                        extract_sender.send(cid)?;
                                     }
-                                } else if fail_on_dead_links {
-                                    // If the receiving end has already quit - just ignore it and
-                                    // break out of the loop.
-                                    let _ = block_sender
-                                        .send(Err(anyhow::anyhow!("missing key: {}", cid)));
-                                    break 'main;
                                 }
                             }
+                            // The blocks of the subgraph have all been sent.
+                            if pending.fetch_sub(1, Ordering::AcqRel) == 1 {
+                                walked.wake();
+                            }
                         }
+                        anyhow::Ok(())
+                    };
+                    if let Err(e) = walk.await {
+                        // If the receiving end has already quit - just ignore it.
+                        let _ = block_sender.send_async(Err(e)).await;
                     }
-                    anyhow::Ok(())
                 });
             }
 
@@ -456,7 +527,7 @@ impl<DB: Blockstore + Send + Sync + 'static, T: Iterator<Item = Tipset> + Unpin>
 {
     type Item = anyhow::Result<CarBlock>;
 
-    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let this = self.project();
         let receive_block = || {
             if let Ok(item) = this.block_receiver.try_recv() {
@@ -464,6 +535,10 @@ impl<DB: Blockstore + Send + Sync + 'static, T: Iterator<Item = Tipset> + Unpin>
             }
             None
         };
+        let extract = |cid: Cid| {
+            this.pending.fetch_add(1, Ordering::AcqRel);
+            this.extract_sender.send(cid)
+        };
         loop {
             while let Some(cid) = this.queue.pop() {
                 if let Some(data) = this.db.get(&cid)? {
@@ -483,7 +558,7 @@ impl<DB: Blockstore + Send + Sync + 'static, T: Iterator<Item = Tipset> + Unpin>
             // has been reached yield a block without walking the graph it represents.
             if let Some(tipset) = this.tipset_iter.next() {
                 for block in tipset.into_block_headers().into_iter() {
-                    if this.seen.lock().insert(*block.cid()) {
+                    if this.seen.insert(*block.cid()) {
                         // Make sure we always yield a block, directly to the stream to avoid extra
                         // work.
                         this.queue.push(*block.cid());
@@ -500,7 +575,7 @@ impl<DB: Blockstore + Send + Sync + 'static, T: Iterator<Item = Tipset> + Unpin>
                             && should_save_block_to_snapshot(block.messages)
                         {
                             if this.db.has(&block.messages)? {
-                                this.extract_sender.send(block.messages)?;
+                                extract(block.messages)?;
                                 // This will simply return an error once we reach that item in
                                 // the queue.
                             } else if *this.fail_on_dead_links {
@@ -508,17 +583,22 @@ impl<DB: Blockstore + Send + Sync + 'static, T: Iterator<Item = Tipset> + Unpin>
                             } else {
                                 // Make sure we update seen here as we don't send the block for
                                 // inspection.
-                                this.seen.lock().insert(block.messages);
+                                this.seen.insert(block.messages);
                             }
                         }
 
-                        // Visit the block if it's within required depth. And a special case for `0`
-                        // epoch to match Lotus' implementation.
-                        if (block.epoch == 0 || block.epoch > stateroot_limit)
+                        // Visit the block if it's within required depth or the requested state
+                        // range. And a special case for `0` epoch to match Lotus' implementation.
+                        if (block.epoch == 0
+                            || block.epoch > stateroot_limit
+                            || this
+                                .state_range
+                                .as_ref()
+                                .is_some_and(|range| range.contains(&block.epoch)))
                             && should_save_block_to_snapshot(block.state_root)
                         {
                             if this.db.has(&block.state_root)? {
-                                this.extract_sender.send(block.state_root)?;
+                                extract(block.state_root)?;
                                 // This will simply return an error once we reach that item in
                                 // the queue.
                             } else if *this.fail_on_dead_links {
@@ -526,29 +606,34 @@ impl<DB: Blockstore + Send + Sync + 'static, T: Iterator<Item = Tipset> + Unpin>
                             } else {
                                 // Make sure we update seen here as we don't send the block for
                                 // inspection.
-                                this.seen.lock().insert(block.state_root);
+                                this.seen.insert(block.state_root);
                             }
                         }
                     }
                 }
             } else {
-                match this.block_receiver.try_recv() {
-                    Ok(item) => return Poll::Ready(Some(item)),
-                    Err(err) => {
-                        if this.extract_sender.is_empty() {
-                            this.worker_handle.abort();
-                            return Poll::Ready(None);
-                            // This should never happen, because both `extract_sender` and
-                            // `block_receiver` are held by worker_handle and their counterparts -
-                            // by the main process. So those are either both functional or both
-                            // closed.
-                        } else if err == TryRecvError::Disconnected {
-                            panic!(
-                                "block_receiver can only be closed after extract_sender is empty"
-                            )
-                        }
-                    }
+                // Either a block or the end of the walk wakes the stream up.
+                this.walked.register(cx.waker());
+                if let Poll::Ready(item) = this.block_stream.poll_next_unpin(cx) {
+                    return Poll::Ready(Some(item.unwrap_or_else(|| {
+                        Err(anyhow::anyhow!("graph walk workers stopped unexpectedly"))
+                    })));
                 }
+                // The workers send all the blocks of a subgraph before it is no longer
+                // pending, so an empty channel afterwards means the walk is over.
+                if this.pending.load(Ordering::Acquire) != 0 {
+                    return Poll::Pending;
+                }
+                return match this.block_receiver.try_recv() {
+                    Ok(item) => Poll::Ready(Some(item)),
+                    Err(TryRecvError::Empty) => {
+                        this.worker_handle.abort();
+                        Poll::Ready(None)
+                    }
+                    Err(TryRecvError::Disconnected) => Poll::Ready(Some(Err(anyhow::anyhow!(
+                        "graph walk workers stopped unexpectedly"
+                    )))),
+                };
             }
         }
     }
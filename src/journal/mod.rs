@@ -0,0 +1,317 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Persistent journal of the decisions of the node, for debugging consensus
+//! issues after the fact, enabled with `journal.enabled`. Events are sent
+//! through a bounded channel to a background writer appending them as JSON
+//! lines to rotating files, so that recording an event never waits on the
+//! disk. Events are dropped while the writer lags behind, and the journal is
+//! disabled if the writer fails.
+
+mod reader;
+mod writer;
+
+pub use reader::{read_entries, JournalFilter};
+pub use writer::JournalWriter;
+
+use crate::blocks::TipsetKey;
+use crate::shim::{address::Address, clock::ChainEpoch, executor::Receipt};
+use ahash::HashSet;
+use chrono::{DateTime, SecondsFormat, Utc};
+use cid::Cid;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::path::Path;
+use std::time::Duration;
+use tracing::{trace, warn};
+
+/// Name of the journal directory in the chain data directory.
+pub const JOURNAL_DIR_NAME: &str = "journal";
+
+/// Capacity of the channel to the writer.
+const JOURNAL_CHANNEL_CAPACITY: usize = 8192;
+
+static JOURNAL: Lazy<RwLock<Option<Journal>>> = Lazy::new(Default::default);
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    strum::Display,
+    strum::EnumString,
+    strum::EnumIter,
+    clap::ValueEnum,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+#[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
+pub enum JournalEventType {
+    TipsetApplied,
+    TipsetRejected,
+    HeadChange,
+    MpoolAdd,
+    MpoolRemove,
+    PeerBan,
+    ShadowExecutionDivergence,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+    tag = "Type",
+    rename_all = "kebab-case",
+    rename_all_fields = "PascalCase"
+)]
+pub enum JournalEvent {
+    /// The messages of the tipset were executed and its blocks validated
+    TipsetApplied {
+        #[serde(with = "crate::lotus_json")]
+        key: TipsetKey,
+        epoch: ChainEpoch,
+        /// The parent state root validated by the tipset
+        #[serde(with = "crate::lotus_json")]
+        state_root: Cid,
+        duration_ms: u64,
+    },
+    /// A block of the tipset failed validation
+    TipsetRejected {
+        #[serde(with = "crate::lotus_json")]
+        key: TipsetKey,
+        epoch: ChainEpoch,
+        #[serde(with = "crate::lotus_json")]
+        block: Cid,
+        reason: String,
+    },
+    HeadChange {
+        #[serde(with = "crate::lotus_json")]
+        key: TipsetKey,
+        epoch: ChainEpoch,
+    },
+    MpoolAdd {
+        #[serde(with = "crate::lotus_json")]
+        message: Cid,
+        #[serde(with = "crate::lotus_json")]
+        from: Address,
+        sequence: u64,
+    },
+    MpoolRemove {
+        #[serde(with = "crate::lotus_json")]
+        message: Cid,
+        #[serde(with = "crate::lotus_json")]
+        from: Address,
+        sequence: u64,
+        /// Whether the message was removed because it was included on chain
+        applied: bool,
+    },
+    PeerBan {
+        peer: String,
+        reason: String,
+        /// Ban duration, unbounded if absent
+        duration_secs: Option<u64>,
+    },
+    /// The primary and reference executions of the tipset disagree, see
+    /// [`crate::state_manager::shadow_execution`]
+    ShadowExecutionDivergence {
+        #[serde(with = "crate::lotus_json")]
+        key: TipsetKey,
+        epoch: ChainEpoch,
+        #[serde(with = "crate::lotus_json")]
+        primary_state_root: Cid,
+        #[serde(with = "crate::lotus_json")]
+        reference_state_root: Cid,
+        #[serde(with = "crate::lotus_json")]
+        primary_receipt_root: Cid,
+        #[serde(with = "crate::lotus_json")]
+        reference_receipt_root: Cid,
+        /// The first message with different receipts, if any
+        #[serde(with = "crate::lotus_json")]
+        message: Option<Cid>,
+        #[serde(with = "crate::lotus_json")]
+        primary_receipt: Option<Receipt>,
+        #[serde(with = "crate::lotus_json")]
+        reference_receipt: Option<Receipt>,
+        /// Changed lines of the execution traces of the message
+        trace_diff: Vec<String>,
+    },
+}
+
+impl JournalEvent {
+    pub fn event_type(&self) -> JournalEventType {
+        match self {
+            Self::TipsetApplied { .. } => JournalEventType::TipsetApplied,
+            Self::TipsetRejected { .. } => JournalEventType::TipsetRejected,
+            Self::HeadChange { .. } => JournalEventType::HeadChange,
+            Self::MpoolAdd { .. } => JournalEventType::MpoolAdd,
+            Self::MpoolRemove { .. } => JournalEventType::MpoolRemove,
+            Self::PeerBan { .. } => JournalEventType::PeerBan,
+            Self::ShadowExecutionDivergence { .. } => JournalEventType::ShadowExecutionDivergence,
+        }
+    }
+}
+
+/// A line of the journal.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct JournalEntry {
+    #[serde(
+        serialize_with = "serialize_time",
+        deserialize_with = "deserialize_time"
+    )]
+    pub time: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: JournalEvent,
+}
+
+fn serialize_time<S: Serializer>(time: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+    time.to_rfc3339_opts(SecondsFormat::Millis, true)
+        .serialize(serializer)
+}
+
+fn deserialize_time<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+    let time = String::deserialize(deserializer)?;
+    DateTime::parse_from_rfc3339(&time)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(serde::de::Error::custom)
+}
+
+/// Settings of the journal, in the `[journal]` section of the configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
+#[serde(default)]
+pub struct JournalConfig {
+    /// Whether to record the events, disabled by default
+    pub enabled: bool,
+    /// Types of the events to record
+    pub event_types: Vec<JournalEventType>,
+    /// Size in bytes after which the journal file is rotated
+    pub max_file_size: u64,
+    /// Number of rotated journal files to keep
+    pub max_files: usize,
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            event_types: strum::IntoEnumIterator::iter().collect(),
+            max_file_size: 100 * 1024 * 1024,
+            max_files: 10,
+        }
+    }
+}
+
+/// Message to the journal writer.
+pub enum JournalMessage {
+    Entry(JournalEntry),
+    /// Acknowledges once the entries received before are written
+    Flush(flume::Sender<()>),
+}
+
+/// Handle to the journal writer.
+#[derive(Clone)]
+struct Journal {
+    sender: flume::Sender<JournalMessage>,
+    event_types: HashSet<JournalEventType>,
+}
+
+/// Starts recording the events to the journal in `dir`, replacing any previous
+/// journal. Returns the writer loop to run on a blocking thread, or `None` if
+/// the journal is disabled.
+pub fn init(
+    config: &JournalConfig,
+    dir: &Path,
+) -> anyhow::Result<Option<impl FnOnce() -> anyhow::Result<()>>> {
+    if !config.enabled {
+        *JOURNAL.write() = None;
+        return Ok(None);
+    }
+    let writer = JournalWriter::open(dir, config.max_file_size, config.max_files)?;
+    let (sender, receiver) = flume::bounded(JOURNAL_CHANNEL_CAPACITY);
+    *JOURNAL.write() = Some(Journal {
+        sender,
+        event_types: config.event_types.iter().copied().collect(),
+    });
+    Ok(Some(move || {
+        // Failing to write the journal, e.g. on a full disk, must not stop
+        // the node
+        if let Err(e) = writer.run(receiver) {
+            warn!("Failed to write the journal, disabling it: {e:#}");
+            *JOURNAL.write() = None;
+        }
+        Ok(())
+    }))
+}
+
+/// Records the event if the journal is enabled for its type, without blocking.
+pub fn record(event: JournalEvent) {
+    if let Some(journal) = JOURNAL.read().as_ref() {
+        if journal.event_types.contains(&event.event_type()) {
+            let entry = JournalEntry {
+                time: Utc::now(),
+                event,
+            };
+            if journal
+                .sender
+                .try_send(JournalMessage::Entry(entry))
+                .is_err()
+            {
+                trace!("journal writer is lagging, event dropped");
+            }
+        }
+    }
+}
+
+/// Waits for at most `timeout` until the events recorded so far are written,
+/// e.g. before aborting the process.
+pub fn flush(timeout: Duration) {
+    let Some(sender) = JOURNAL
+        .read()
+        .as_ref()
+        .map(|journal| journal.sender.clone())
+    else {
+        return;
+    };
+    let (tx, rx) = flume::bounded(1);
+    if sender
+        .send_timeout(JournalMessage::Flush(tx), timeout)
+        .is_ok()
+    {
+        let _ = rx.recv_timeout(timeout);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_json_roundtrip() {
+        let entry = JournalEntry {
+            time: DateTime::parse_from_rfc3339("2024-05-06T07:08:09.123Z")
+                .unwrap()
+                .with_timezone(&Utc),
+            event: JournalEvent::PeerBan {
+                peer: "12D3KooWGUmEM8M2SwRKeJmKGrzs3BSWfEMLV9ecr8YCVd7Y6U7N".into(),
+                reason: "bad block".into(),
+                duration_secs: Some(3600),
+            },
+        };
+        let json = serde_json::to_value(&entry).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "Time": "2024-05-06T07:08:09.123Z",
+                "Type": "peer-ban",
+                "Peer": "12D3KooWGUmEM8M2SwRKeJmKGrzs3BSWfEMLV9ecr8YCVd7Y6U7N",
+                "Reason": "bad block",
+                "DurationSecs": 3600,
+            })
+        );
+        assert_eq!(serde_json::from_value::<JournalEntry>(json).unwrap(), entry);
+    }
+}
@@ -0,0 +1,134 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::{writer::journal_files, JournalEntry, JournalEventType};
+use chrono::{DateTime, Utc};
+use std::fs::File;
+use std::io::{BufRead as _, BufReader};
+use std::path::Path;
+use tracing::warn;
+
+#[derive(Debug, Clone, Default)]
+pub struct JournalFilter {
+    /// Types of the events to keep, all if empty
+    pub event_types: Vec<JournalEventType>,
+    /// Keep the events recorded at or after this time
+    pub since: Option<DateTime<Utc>>,
+}
+
+impl JournalFilter {
+    pub fn matches(&self, entry: &JournalEntry) -> bool {
+        (self.event_types.is_empty() || self.event_types.contains(&entry.event.event_type()))
+            && self.since.is_none_or(|since| entry.time >= since)
+    }
+}
+
+/// Reads the entries of the journal in `dir` matching the filter, oldest
+/// first. Malformed lines, e.g. truncated by a crash, are skipped.
+pub fn read_entries(
+    dir: &Path,
+    filter: JournalFilter,
+) -> anyhow::Result<impl Iterator<Item = anyhow::Result<JournalEntry>>> {
+    let files = journal_files(dir)?;
+    Ok(files
+        .into_iter()
+        .flat_map(|path| {
+            let lines = File::open(&path).map(|file| BufReader::new(file).lines());
+            let (lines, error) = match lines {
+                Ok(lines) => (Some(lines), None),
+                // The file may have been removed by the rotation since listed
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => (None, None),
+                Err(e) => (None, Some(Err(e.into()))),
+            };
+            error
+                .into_iter()
+                .chain(lines.into_iter().flatten().filter_map(move |line| {
+                    let line = match line {
+                        Ok(line) => line,
+                        Err(e) => return Some(Err(e.into())),
+                    };
+                    match serde_json::from_str(&line) {
+                        Ok(entry) => Some(Ok(entry)),
+                        Err(e) => {
+                            warn!("skipping malformed line in {}: {e}", path.display());
+                            None
+                        }
+                    }
+                }))
+        })
+        .filter(move |entry| match entry {
+            Ok(entry) => filter.matches(entry),
+            Err(_) => true,
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::TipsetKey;
+    use crate::journal::{JournalEvent, JournalWriter};
+    use chrono::TimeDelta;
+    use cid::Cid;
+    use std::io::Write as _;
+
+    #[test]
+    fn read_filtered_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let start = DateTime::parse_from_rfc3339("2024-05-06T07:08:09.123Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let entries: Vec<_> = (0..6)
+            .map(|i| JournalEntry {
+                time: start + TimeDelta::minutes(i),
+                event: if i % 2 == 0 {
+                    JournalEvent::HeadChange {
+                        key: TipsetKey::from(nunny::vec![Cid::default()]),
+                        epoch: i,
+                    }
+                } else {
+                    JournalEvent::PeerBan {
+                        peer: format!("peer{i}"),
+                        reason: "test".into(),
+                        duration_secs: None,
+                    }
+                },
+            })
+            .collect();
+        let mut writer = JournalWriter::open(dir.path(), 512, 10).unwrap();
+        for entry in &entries {
+            writer.write(entry).unwrap();
+        }
+        writer.flush().unwrap();
+        drop(writer);
+        // A truncated line left by a crash
+        File::options()
+            .append(true)
+            .open(dir.path().join("journal.ndjson"))
+            .unwrap()
+            .write_all(b"{\"Time\":")
+            .unwrap();
+
+        let read = |filter| {
+            read_entries(dir.path(), filter)
+                .unwrap()
+                .collect::<anyhow::Result<Vec<_>>>()
+                .unwrap()
+        };
+        // Entries are kept in order across the rotated files
+        assert_eq!(read(JournalFilter::default()), entries);
+        assert_eq!(
+            read(JournalFilter {
+                event_types: vec![JournalEventType::PeerBan],
+                since: None,
+            }),
+            [entries[1].clone(), entries[3].clone(), entries[5].clone()]
+        );
+        assert_eq!(
+            read(JournalFilter {
+                event_types: vec![JournalEventType::HeadChange],
+                since: Some(entries[2].time),
+            }),
+            [entries[2].clone(), entries[4].clone()]
+        );
+    }
+}
@@ -0,0 +1,208 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::{JournalEntry, JournalMessage};
+use anyhow::Context as _;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write as _};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Name of the journal file being written.
+const CURRENT_FILE_NAME: &str = "journal.ndjson";
+
+/// Appends journal entries as JSON lines to `journal.ndjson`, which is renamed
+/// to `journal.<index>.ndjson` once it exceeds the maximum size.
+pub struct JournalWriter {
+    dir: PathBuf,
+    file: BufWriter<File>,
+    size: u64,
+    max_file_size: u64,
+    max_files: usize,
+}
+
+impl JournalWriter {
+    pub fn open(dir: &Path, max_file_size: u64, max_files: usize) -> anyhow::Result<Self> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create journal directory {}", dir.display()))?;
+        let (file, size) = open_current(dir)?;
+        Ok(Self {
+            dir: dir.to_owned(),
+            file,
+            size,
+            max_file_size,
+            max_files,
+        })
+    }
+
+    pub fn write(&mut self, entry: &JournalEntry) -> anyhow::Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        self.size += line.len() as u64;
+        if self.size >= self.max_file_size {
+            self.rotate()?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> anyhow::Result<()> {
+        Ok(self.file.flush()?)
+    }
+
+    /// Writes the received entries until all the senders are dropped.
+    pub fn run(mut self, receiver: flume::Receiver<JournalMessage>) -> anyhow::Result<()> {
+        while let Ok(message) = receiver.recv() {
+            let mut flushed = vec![];
+            for message in std::iter::once(message).chain(receiver.try_iter()) {
+                match message {
+                    JournalMessage::Entry(entry) => self.write(&entry)?,
+                    JournalMessage::Flush(tx) => flushed.push(tx),
+                }
+            }
+            self.flush()?;
+            for tx in flushed {
+                let _ = tx.send(());
+            }
+        }
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> anyhow::Result<()> {
+        self.flush()?;
+        let rotated = rotated_files(&self.dir)?;
+        let index = rotated
+            .last()
+            .map(|(index, _)| index + 1)
+            .unwrap_or_default();
+        fs::rename(
+            self.dir.join(CURRENT_FILE_NAME),
+            self.dir.join(rotated_file_name(index)),
+        )?;
+        (self.file, self.size) = open_current(&self.dir)?;
+
+        let rotated = rotated_files(&self.dir)?;
+        let excess = rotated.len().saturating_sub(self.max_files);
+        for (_, path) in rotated.into_iter().take(excess) {
+            if let Err(e) = fs::remove_file(&path) {
+                warn!("failed to remove journal file {}: {e}", path.display());
+            }
+        }
+        Ok(())
+    }
+}
+
+fn open_current(dir: &Path) -> anyhow::Result<(BufWriter<File>, u64)> {
+    let path = dir.join(CURRENT_FILE_NAME);
+    let file = File::options()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("failed to open journal file {}", path.display()))?;
+    let size = file.metadata()?.len();
+    Ok((BufWriter::new(file), size))
+}
+
+fn rotated_file_name(index: u64) -> String {
+    format!("journal.{index:06}.ndjson")
+}
+
+/// Returns the rotated journal files in `dir`, oldest first.
+fn rotated_files(dir: &Path) -> anyhow::Result<Vec<(u64, PathBuf)>> {
+    let mut files = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let index = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix("journal."))
+            .and_then(|name| name.strip_suffix(".ndjson"))
+            .and_then(|index| index.parse().ok());
+        if let Some(index) = index {
+            files.push((index, path));
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Returns all the journal files in `dir`, oldest first.
+pub(super) fn journal_files(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files: Vec<_> = rotated_files(dir)?
+        .into_iter()
+        .map(|(_, path)| path)
+        .collect();
+    let current = dir.join(CURRENT_FILE_NAME);
+    if current.is_file() {
+        files.push(current);
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::TipsetKey;
+    use crate::journal::JournalEvent;
+    use chrono::Utc;
+    use cid::Cid;
+
+    fn entry(epoch: i64) -> JournalEntry {
+        JournalEntry {
+            time: Utc::now(),
+            event: JournalEvent::HeadChange {
+                key: TipsetKey::from(nunny::vec![Cid::default()]),
+                epoch,
+            },
+        }
+    }
+
+    #[test]
+    fn rotation_and_retention() {
+        let dir = tempfile::tempdir().unwrap();
+        let line_len = serde_json::to_vec(&entry(0)).unwrap().len() as u64 + 1;
+        // Rotate after every 2 entries, keeping 2 rotated files
+        let mut writer = JournalWriter::open(dir.path(), 2 * line_len, 2).unwrap();
+        for epoch in 0..7 {
+            writer.write(&entry(epoch)).unwrap();
+        }
+        writer.flush().unwrap();
+
+        let files = journal_files(dir.path()).unwrap();
+        let names: Vec<_> = files
+            .iter()
+            .map(|path| path.file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "journal.000001.ndjson",
+                "journal.000002.ndjson",
+                "journal.ndjson"
+            ]
+        );
+        let epochs: Vec<_> = files
+            .iter()
+            .flat_map(|path| {
+                fs::read_to_string(path)
+                    .unwrap()
+                    .lines()
+                    .map(|line| match serde_json::from_str(line).unwrap() {
+                        JournalEntry {
+                            event: JournalEvent::HeadChange { epoch, .. },
+                            ..
+                        } => epoch,
+                        _ => unreachable!(),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        assert_eq!(epochs, [2, 3, 4, 5, 6]);
+
+        // Reopening appends to the current file and keeps the rotation index
+        let mut writer = JournalWriter::open(dir.path(), 2 * line_len, 2).unwrap();
+        writer.write(&entry(7)).unwrap();
+        assert!(dir.path().join("journal.000003.ndjson").is_file());
+        assert!(!dir.path().join("journal.000001.ndjson").exists());
+    }
+}
@@ -21,6 +21,9 @@ pub enum Error {
     IO(#[from] io::Error),
     #[error("{0}")]
     Other(String),
+    /// Keystore could not be decrypted with the given passphrase
+    #[error("Wrong passphrase or corrupted keystore")]
+    WrongPassphrase,
     #[error("Could not convert from KeyInfo to Key")]
     KeyInfoConversion,
 }
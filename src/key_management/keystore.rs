@@ -13,10 +13,12 @@ use crate::{
     utils::{encoding::from_slice_with_fallback, io::create_new_sensitive_file},
 };
 use ahash::{HashMap, HashMapExt};
+use anyhow::Context as _;
 use argon2::{
     password_hash::SaltString, Argon2, ParamsBuilder, PasswordHasher, RECOMMENDED_SALT_LEN,
 };
 use base64::{prelude::BASE64_STANDARD, Engine};
+use chacha20poly1305::XChaCha20Poly1305;
 use crypto_secretbox::{
     aead::{generic_array::GenericArray, Aead},
     KeyInit, SecretBox, XSalsa20Poly1305,
@@ -24,7 +26,7 @@ use crypto_secretbox::{
 use rand::{rngs::OsRng, RngCore};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use tracing::{error, warn};
+use tracing::{error, info, warn};
 
 use super::errors::Error;
 
@@ -36,7 +38,18 @@ pub const ENCRYPTED_KEYSTORE_NAME: &str = "keystore";
 /// Environmental variable which holds the `KeyStore` encryption phrase.
 pub const FOREST_KEYSTORE_PHRASE_ENV: &str = "FOREST_KEYSTORE_PHRASE";
 
-type SaltByteArray = [u8; RECOMMENDED_SALT_LEN];
+/// Prefix of versioned encrypted keystore files.
+const KEYSTORE_MAGIC: &[u8] = b"FORESTKS";
+
+/// Current format version of encrypted keystore files.
+pub const KEYSTORE_FORMAT_VERSION: u32 = 2;
+
+/// Upper bounds of the key derivation parameters read from keystore files, so
+/// that a crafted file cannot make unlocking exhaust memory or CPU. These are
+/// well above the parameters Forest generates.
+const MAX_KDF_M_COST: u32 = 1024 * 1024; // 1 GiB
+const MAX_KDF_T_COST: u32 = 16;
+const MAX_KDF_P_COST: u32 = 16;
 
 /// `KeyInfo` structure, this contains the type of key (stored as a string) and
 /// the private key. Note how the private key is stored as a byte vector
@@ -96,14 +109,39 @@ struct PersistentKeyStore {
 
 /// Encrypted `KeyStore`
 /// `Argon2id` hash key derivation
-/// `XSalsa20Poly1305` authenticated encryption
+/// `XChaCha20Poly1305` authenticated encryption
 /// CBOR encoding
 #[derive(Clone, PartialEq, Debug, Eq)]
 struct EncryptedKeyStore {
-    salt: SaltByteArray,
+    kdf: KdfParams,
     encryption_key: Vec<u8>,
 }
 
+/// Tunable `Argon2id` key derivation parameters, stored in the keystore header.
+#[derive(Clone, PartialEq, Debug, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    /// Memory size in KiB
+    pub m_cost: u32,
+    /// Number of iterations
+    pub t_cost: u32,
+    /// Degree of parallelism
+    pub p_cost: u32,
+    #[serde(with = "fvm_ipld_encoding::strict_bytes")]
+    pub salt: Vec<u8>,
+}
+
+/// Version 2 keystore file, CBOR encoded after [`KEYSTORE_MAGIC`]. Version 1
+/// files have no header and consist of the salt followed by the
+/// `XSalsa20Poly1305` encrypted keys.
+#[derive(Serialize, Deserialize)]
+struct EncryptedKeyStoreFile {
+    version: u32,
+    kdf: KdfParams,
+    /// Encrypted keys with the nonce appended
+    #[serde(with = "fvm_ipld_encoding::strict_bytes")]
+    data: Vec<u8>,
+}
+
 #[derive(Debug, Error)]
 pub enum EncryptedKeyStoreError {
     /// An error occurred while encrypting keys
@@ -181,84 +219,47 @@ impl KeyStore {
                     File::create(file_path.clone())?;
                 }
 
-                match File::open(&file_path) {
-                    Ok(file) => {
-                        let mut reader = BufReader::new(file);
-                        let mut buf = vec![];
-                        let read_bytes = reader.read_to_end(&mut buf)?;
-
-                        if read_bytes == 0 {
-                            // New encrypted keystore if file exists but is zero bytes (i.e., touch)
-                            warn!(
-                                "Keystore does not exist, initializing new keystore at {:?}",
-                                file_path
-                            );
-
-                            let (salt, encryption_key) =
-                                EncryptedKeyStore::derive_key(&passphrase, None).map_err(
-                                    |error| {
-                                        error!("Failed to create key from passphrase");
-                                        Error::Other(error.to_string())
-                                    },
-                                )?;
-                            Ok(Self {
-                                key_info: HashMap::new(),
-                                persistence: Some(PersistentKeyStore { file_path }),
-                                encryption: Some(EncryptedKeyStore {
-                                    salt,
-                                    encryption_key,
-                                }),
-                            })
-                        } else {
-                            // Existing encrypted keystore
-                            // Split off data from prepended salt
-                            let data = buf.split_off(RECOMMENDED_SALT_LEN);
-                            let mut prev_salt = [0; RECOMMENDED_SALT_LEN];
-                            prev_salt.copy_from_slice(&buf);
-                            let (salt, encryption_key) =
-                                EncryptedKeyStore::derive_key(&passphrase, Some(prev_salt))
-                                    .map_err(|error| {
-                                        error!("Failed to create key from passphrase");
-                                        Error::Other(error.to_string())
-                                    })?;
-
-                            let decrypted_data = EncryptedKeyStore::decrypt(&encryption_key, &data)
-                                .map_err(|error| Error::Other(error.to_string()))?;
-
-                            let key_info = from_slice_with_fallback(&decrypted_data)
-                                .inspect_err(|error| {
-                                    error!(%error, "Failed to deserialize keyfile, initializing new");
-                                })
-                                .unwrap_or_default();
-
-                            Ok(Self {
-                                key_info,
-                                persistence: Some(PersistentKeyStore { file_path }),
-                                encryption: Some(EncryptedKeyStore {
-                                    salt,
-                                    encryption_key,
-                                }),
-                            })
-                        }
-                    }
-                    Err(_) => {
-                        warn!("Encrypted keystore does not exist, initializing new keystore");
-
-                        let (salt, encryption_key) =
-                            EncryptedKeyStore::derive_key(&passphrase, None).map_err(|error| {
-                                error!("Failed to create key from passphrase");
-                                Error::Other(error.to_string())
-                            })?;
-
-                        Ok(Self {
-                            key_info: HashMap::new(),
-                            persistence: Some(PersistentKeyStore { file_path }),
-                            encryption: Some(EncryptedKeyStore {
-                                salt,
-                                encryption_key,
-                            }),
-                        })
+                let mut buf = vec![];
+                BufReader::new(File::open(&file_path)?).read_to_end(&mut buf)?;
+
+                if buf.is_empty() {
+                    // New encrypted keystore if file exists but is zero bytes (i.e., touch)
+                    warn!(
+                        "Keystore does not exist, initializing new keystore at {:?}",
+                        file_path
+                    );
+                    Ok(Self {
+                        key_info: HashMap::new(),
+                        persistence: Some(PersistentKeyStore { file_path }),
+                        encryption: Some(
+                            EncryptedKeyStore::new(&passphrase)
+                                .map_err(|error| Error::Other(error.to_string()))?,
+                        ),
+                    })
+                } else {
+                    // Existing encrypted keystore
+                    let (encryption, decrypted_data, version) =
+                        EncryptedKeyStore::unlock(&passphrase, &buf)?;
+
+                    // An undecodable keystore is left in place rather than
+                    // overwritten, e.g. by the upgrade below
+                    let key_info = from_slice_with_fallback(&decrypted_data)
+                        .map_err(|e| Error::Other(format!("failed to deserialize keyfile: {e}")))?;
+
+                    let keystore = Self {
+                        key_info,
+                        persistence: Some(PersistentKeyStore { file_path }),
+                        encryption: Some(encryption),
+                    };
+                    if version < KEYSTORE_FORMAT_VERSION {
+                        info!(
+                            "Upgrading keystore from format version {version} to {KEYSTORE_FORMAT_VERSION}"
+                        );
+                        keystore
+                            .flush()
+                            .map_err(|error| Error::Other(error.to_string()))?;
                     }
+                    Ok(keystore)
                 }
             }
         }
@@ -267,10 +268,6 @@ impl KeyStore {
     pub fn flush(&self) -> anyhow::Result<()> {
         match &self.persistence {
             Some(persistent_keystore) => {
-                let file = create_new_sensitive_file(&persistent_keystore.file_path)?;
-
-                let mut writer = BufWriter::new(file);
-
                 match &self.encryption {
                     Some(encrypted_keystore) => {
                         // Flush For EncryptedKeyStore
@@ -278,15 +275,15 @@ impl KeyStore {
                             Error::Other(format!("failed to serialize and write key info: {e}"))
                         })?;
 
-                        let encrypted_data =
-                            EncryptedKeyStore::encrypt(&encrypted_keystore.encryption_key, &data)?;
-                        let mut salt_vec = encrypted_keystore.salt.to_vec();
-                        salt_vec.extend(encrypted_data);
-                        writer.write_all(&salt_vec)?;
+                        let file_data = encrypted_keystore.seal(&data)?;
+                        write_atomically(&persistent_keystore.file_path, &file_data)?;
 
                         Ok(())
                     }
                     None => {
+                        let file = create_new_sensitive_file(&persistent_keystore.file_path)?;
+                        let writer = BufWriter::new(file);
+
                         let mut key_info: HashMap<String, PersistentKeyInfo> = HashMap::new();
                         for (key, value) in self.key_info.iter() {
                             key_info.insert(
@@ -338,6 +335,45 @@ impl KeyStore {
         Ok(())
     }
 
+    /// Key derivation parameters of an encrypted `KeyStore`
+    pub fn kdf_params(&self) -> Option<&KdfParams> {
+        self.encryption.as_ref().map(|encryption| &encryption.kdf)
+    }
+
+    /// Format version of the file of an encrypted `KeyStore`, as read from its
+    /// header. `None` if the keystore is not encrypted or not written yet.
+    pub fn file_format_version(&self) -> anyhow::Result<Option<u32>> {
+        let (Some(persistence), Some(_)) = (&self.persistence, &self.encryption) else {
+            return Ok(None);
+        };
+        let file_data = std::fs::read(&persistence.file_path)?;
+        if file_data.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(match file_data.strip_prefix(KEYSTORE_MAGIC) {
+            Some(header) => {
+                serde_ipld_dagcbor::from_slice::<EncryptedKeyStoreFile>(header)
+                    .context("failed to parse keystore header")?
+                    .version
+            }
+            None => 1,
+        }))
+    }
+
+    /// Re-encrypt the `KeyStore` under a new passphrase with freshly generated
+    /// key derivation parameters. The keystore file is replaced atomically.
+    pub fn rotate_passphrase(&mut self, new_passphrase: &str) -> anyhow::Result<()> {
+        anyhow::ensure!(self.encryption.is_some(), "keystore is not encrypted");
+        let previous = self
+            .encryption
+            .replace(EncryptedKeyStore::new(new_passphrase)?);
+        if let Err(e) = self.flush() {
+            self.encryption = previous;
+            return Err(e);
+        }
+        Ok(())
+    }
+
     /// Remove the key and corresponding `KeyInfo` from the `KeyStore`
     pub fn remove(&mut self, key: &str) -> anyhow::Result<KeyInfo> {
         let key_out = self.key_info.remove(key).ok_or(Error::KeyInfo)?;
@@ -350,30 +386,121 @@ impl KeyStore {
     }
 }
 
-impl EncryptedKeyStore {
-    fn derive_key(
-        passphrase: &str,
-        prev_salt: Option<SaltByteArray>,
-    ) -> anyhow::Result<(SaltByteArray, Vec<u8>)> {
-        let salt = match prev_salt {
-            Some(prev_salt) => prev_salt,
-            None => {
-                let mut salt = [0; RECOMMENDED_SALT_LEN];
-                OsRng.fill_bytes(&mut salt);
-                salt
-            }
-        };
+impl KdfParams {
+    /// `Argon2id` parameters with a freshly generated salt.
+    fn generate() -> Self {
+        let mut salt = vec![0; RECOMMENDED_SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Self::with_salt(salt)
+    }
 
-        let mut param_builder = ParamsBuilder::new();
+    /// Rejects parameters beyond the `MAX_KDF_*` bounds.
+    fn check_bounds(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.m_cost <= MAX_KDF_M_COST,
+            "keystore memory cost {} KiB exceeds {MAX_KDF_M_COST} KiB",
+            self.m_cost
+        );
+        anyhow::ensure!(
+            self.t_cost <= MAX_KDF_T_COST,
+            "keystore time cost {} exceeds {MAX_KDF_T_COST}",
+            self.t_cost
+        );
+        anyhow::ensure!(
+            self.p_cost <= MAX_KDF_P_COST,
+            "keystore parallelism {} exceeds {MAX_KDF_P_COST}",
+            self.p_cost
+        );
+        Ok(())
+    }
+
+    /// Version 1 keystores only store the salt, the other parameters are
+    /// hard-coded to the `libsodium` interactive limits.
+    fn with_salt(salt: Vec<u8>) -> Self {
         // #define crypto_pwhash_argon2id_MEMLIMIT_INTERACTIVE 67108864U
         // see <https://github.com/jedisct1/libsodium/blob/089f850608737f9d969157092988cb274fe7f8d4/src/libsodium/include/sodium/crypto_pwhash_argon2id.h#L70>
         const CRYPTO_PWHASH_ARGON2ID_MEMLIMIT_INTERACTIVE: u32 = 67108864;
         // #define crypto_pwhash_argon2id_OPSLIMIT_INTERACTIVE 2U
         // see <https://github.com/jedisct1/libsodium/blob/089f850608737f9d969157092988cb274fe7f8d4/src/libsodium/include/sodium/crypto_pwhash_argon2id.h#L66>
         const CRYPTO_PWHASH_ARGON2ID_OPSLIMIT_INTERACTIVE: u32 = 2;
+        Self {
+            m_cost: CRYPTO_PWHASH_ARGON2ID_MEMLIMIT_INTERACTIVE / 1024,
+            t_cost: CRYPTO_PWHASH_ARGON2ID_OPSLIMIT_INTERACTIVE,
+            p_cost: argon2::Params::DEFAULT_P_COST,
+            salt,
+        }
+    }
+}
+
+impl EncryptedKeyStore {
+    fn new(passphrase: &str) -> anyhow::Result<Self> {
+        Self::with_kdf(passphrase, KdfParams::generate())
+    }
+
+    fn with_kdf(passphrase: &str, kdf: KdfParams) -> anyhow::Result<Self> {
+        let encryption_key = Self::derive_key(passphrase, &kdf)?;
+        Ok(Self {
+            kdf,
+            encryption_key,
+        })
+    }
+
+    /// Decrypts the contents of a keystore file, returning the format version
+    /// they were stored in. Version 1 contents are re-keyed under fresh
+    /// parameters, so that the next flush upgrades the file.
+    fn unlock(passphrase: &str, file_data: &[u8]) -> Result<(Self, Vec<u8>, u32), Error> {
+        match file_data.strip_prefix(KEYSTORE_MAGIC) {
+            Some(header) => {
+                let file: EncryptedKeyStoreFile = serde_ipld_dagcbor::from_slice(header)
+                    .map_err(|e| Error::Other(format!("failed to parse keystore header: {e}")))?;
+                if file.version != KEYSTORE_FORMAT_VERSION {
+                    return Err(Error::Other(format!(
+                        "unsupported keystore format version {}",
+                        file.version
+                    )));
+                }
+                file.kdf
+                    .check_bounds()
+                    .map_err(|e| Error::Other(e.to_string()))?;
+                let keystore = Self::with_kdf(passphrase, file.kdf)
+                    .map_err(|e| Error::Other(e.to_string()))?;
+                let data = Self::decrypt(&keystore.encryption_key, &file.data)
+                    .map_err(|_| Error::WrongPassphrase)?;
+                Ok((keystore, data, file.version))
+            }
+            None => {
+                let (salt, data) = file_data
+                    .split_at_checked(RECOMMENDED_SALT_LEN)
+                    .ok_or_else(|| Error::Other("keystore file is truncated".into()))?;
+                let encryption_key =
+                    Self::derive_key(passphrase, &KdfParams::with_salt(salt.to_vec()))
+                        .map_err(|e| Error::Other(e.to_string()))?;
+                let data = Self::decrypt_with::<XSalsa20Poly1305>(&encryption_key, data)
+                    .map_err(|_| Error::WrongPassphrase)?;
+                let keystore = Self::new(passphrase).map_err(|e| Error::Other(e.to_string()))?;
+                Ok((keystore, data, 1))
+            }
+        }
+    }
+
+    /// Encrypts `data` into the contents of a keystore file.
+    fn seal(&self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let file = EncryptedKeyStoreFile {
+            version: KEYSTORE_FORMAT_VERSION,
+            kdf: self.kdf.clone(),
+            data: Self::encrypt(&self.encryption_key, data)?,
+        };
+        let mut file_data = KEYSTORE_MAGIC.to_vec();
+        file_data.extend(serde_ipld_dagcbor::to_vec(&file)?);
+        Ok(file_data)
+    }
+
+    fn derive_key(passphrase: &str, kdf: &KdfParams) -> anyhow::Result<Vec<u8>> {
+        let mut param_builder = ParamsBuilder::new();
         param_builder
-            .m_cost(CRYPTO_PWHASH_ARGON2ID_MEMLIMIT_INTERACTIVE / 1024)
-            .t_cost(CRYPTO_PWHASH_ARGON2ID_OPSLIMIT_INTERACTIVE);
+            .m_cost(kdf.m_cost)
+            .t_cost(kdf.t_cost)
+            .p_cost(kdf.p_cost);
         // https://docs.rs/sodiumoxide/latest/sodiumoxide/crypto/secretbox/xsalsa20poly1305/constant.KEYBYTES.html
         // KEYBYTES = 0x20
         // param_builder.output_len(32)?;
@@ -382,12 +509,12 @@ impl EncryptedKeyStore {
             argon2::Version::V0x13,
             param_builder.build().map_err(map_err_to_anyhow)?,
         );
-        let salt_string = SaltString::encode_b64(&salt).map_err(map_err_to_anyhow)?;
+        let salt_string = SaltString::encode_b64(&kdf.salt).map_err(map_err_to_anyhow)?;
         let pw_hash = hasher
             .hash_password(passphrase.as_bytes(), &salt_string)
             .map_err(map_err_to_anyhow)?;
         if let Some(hash) = pw_hash.hash {
-            Ok((salt, hash.as_bytes().to_vec()))
+            Ok(hash.as_bytes().to_vec())
         } else {
             anyhow::bail!(EncryptedKeyStoreError::EncryptionError)
         }
@@ -398,20 +525,26 @@ impl EncryptedKeyStore {
         OsRng.fill_bytes(&mut nonce);
         let nonce = GenericArray::from_slice(&nonce);
         let key = GenericArray::from_slice(encryption_key);
-        let cipher = XSalsa20Poly1305::new(key);
+        let cipher = XChaCha20Poly1305::new(key);
         let mut ciphertext = cipher.encrypt(nonce, msg).map_err(map_err_to_anyhow)?;
         ciphertext.extend(nonce.iter());
         Ok(ciphertext)
     }
 
-    #[allow(clippy::indexing_slicing)]
     fn decrypt(encryption_key: &[u8], msg: &[u8]) -> anyhow::Result<Vec<u8>> {
+        Self::decrypt_with::<XChaCha20Poly1305>(encryption_key, msg)
+    }
+
+    /// Both ciphers use a 24-byte nonce, which is appended to the ciphertext.
+    fn decrypt_with<C: KeyInit + Aead>(
+        encryption_key: &[u8],
+        msg: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
         anyhow::ensure!(msg.len() > NONCE_SIZE);
-        let cyphertext_len = msg.len() - NONCE_SIZE;
-        let ciphertext = &msg[..cyphertext_len];
-        let nonce = GenericArray::from_slice(&msg[cyphertext_len..]);
+        let (ciphertext, nonce) = msg.split_at(msg.len() - NONCE_SIZE);
+        let nonce = GenericArray::from_slice(nonce);
         let key = GenericArray::from_slice(encryption_key);
-        let cipher = XSalsa20Poly1305::new(key);
+        let cipher = C::new(key);
         let plaintext = cipher
             .decrypt(nonce, ciphertext)
             .map_err(map_err_to_anyhow)?;
@@ -419,6 +552,27 @@ impl EncryptedKeyStore {
     }
 }
 
+/// Writes `data` to a temporary file next to `path` and syncs it to disk,
+/// returning the temporary file path.
+fn write_temp_file(path: &Path, data: &[u8]) -> std::io::Result<PathBuf> {
+    let tmp_path = path.with_extension("tmp");
+    let mut file = create_new_sensitive_file(&tmp_path)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    Ok(tmp_path)
+}
+
+/// Replaces the file at `path` with `data` such that an interruption leaves
+/// either the old or the new contents in place, never a partial write.
+fn write_atomically(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let tmp_path = write_temp_file(path, data)?;
+    std::fs::rename(&tmp_path, path)?;
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        File::open(parent)?.sync_all()?;
+    }
+    Ok(())
+}
+
 fn map_err_to_anyhow<T: Display>(e: T) -> anyhow::Error {
     anyhow::Error::msg(e.to_string())
 }
@@ -432,22 +586,38 @@ mod test {
 
     const PASSPHRASE: &str = "foobarbaz";
 
+    fn encrypted_keystore(location: &Path, passphrase: &str) -> Result<KeyStore, Error> {
+        KeyStore::new(KeyStoreConfig::Encrypted(
+            location.to_path_buf(),
+            passphrase.to_string(),
+        ))
+    }
+
+    fn keystore_with_key(location: &Path) -> KeyStore {
+        let mut ks = encrypted_keystore(location, PASSPHRASE).unwrap();
+        let key = wallet::generate_key(SignatureType::Bls).unwrap();
+        ks.put(&format!("wallet-{}", key.address), key.key_info)
+            .unwrap();
+        ks
+    }
+
     #[test]
     fn test_generate_key() {
-        let (salt, encryption_key) = EncryptedKeyStore::derive_key(PASSPHRASE, None).unwrap();
-        let (second_salt, second_key) =
-            EncryptedKeyStore::derive_key(PASSPHRASE, Some(salt)).unwrap();
+        let kdf = KdfParams::generate();
+        let encryption_key = EncryptedKeyStore::derive_key(PASSPHRASE, &kdf).unwrap();
+        let second_key = EncryptedKeyStore::derive_key(PASSPHRASE, &kdf).unwrap();
 
         assert_eq!(
             encryption_key, second_key,
             "Derived key must be deterministic"
         );
-        assert_eq!(salt, second_salt, "Salts must match");
+        assert_ne!(kdf.salt, KdfParams::generate().salt, "Salts must be random");
     }
 
     #[test]
     fn test_encrypt_message() {
-        let (_, private_key) = EncryptedKeyStore::derive_key(PASSPHRASE, None).unwrap();
+        let private_key =
+            EncryptedKeyStore::derive_key(PASSPHRASE, &KdfParams::generate()).unwrap();
         let message = "foo is coming";
         let ciphertext = EncryptedKeyStore::encrypt(&private_key, message.as_bytes()).unwrap();
         let second_pass = EncryptedKeyStore::encrypt(&private_key, message.as_bytes()).unwrap();
@@ -459,7 +629,8 @@ mod test {
 
     #[test]
     fn test_decrypt_message() {
-        let (_, private_key) = EncryptedKeyStore::derive_key(PASSPHRASE, None).unwrap();
+        let private_key =
+            EncryptedKeyStore::derive_key(PASSPHRASE, &KdfParams::generate()).unwrap();
         let message = "foo is coming";
         let ciphertext = EncryptedKeyStore::encrypt(&private_key, message.as_bytes()).unwrap();
         let plaintext = EncryptedKeyStore::decrypt(&private_key, &ciphertext).unwrap();
@@ -468,10 +639,168 @@ mod test {
 
     #[test]
     fn test_read_old_encrypted_keystore() {
-        let dir: PathBuf = "src/key_management/tests/keystore_encrypted_old".into();
-        assert!(dir.exists());
-        let ks = KeyStore::new(KeyStoreConfig::Encrypted(dir, PASSPHRASE.to_string())).unwrap();
+        // Opening a version 1 keystore upgrades it in place, so work on a copy.
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join(ENCRYPTED_KEYSTORE_NAME);
+        std::fs::copy(
+            "src/key_management/tests/keystore_encrypted_old/keystore",
+            &file_path,
+        )
+        .unwrap();
+        assert!(!std::fs::read(&file_path)
+            .unwrap()
+            .starts_with(KEYSTORE_MAGIC));
+
+        let ks = encrypted_keystore(dir.path(), PASSPHRASE).unwrap();
         assert!(ks.persistence.is_some());
+        assert!(std::fs::read(&file_path)
+            .unwrap()
+            .starts_with(KEYSTORE_MAGIC));
+
+        let ks_read = encrypted_keystore(dir.path(), PASSPHRASE).unwrap();
+        assert_eq!(ks.key_info, ks_read.key_info);
+        assert_eq!(ks.kdf_params(), ks_read.kdf_params());
+    }
+
+    #[test]
+    fn test_undecodable_old_keystore() {
+        // A version 1 file holding data that are not a key map
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join(ENCRYPTED_KEYSTORE_NAME);
+        let salt = vec![1; RECOMMENDED_SALT_LEN];
+        let encryption_key =
+            EncryptedKeyStore::derive_key(PASSPHRASE, &KdfParams::with_salt(salt.clone())).unwrap();
+        let nonce = [2; NONCE_SIZE];
+        let mut old = salt;
+        old.extend(
+            XSalsa20Poly1305::new(GenericArray::from_slice(&encryption_key))
+                .encrypt(GenericArray::from_slice(&nonce), &[0xff][..])
+                .unwrap(),
+        );
+        old.extend(nonce);
+        std::fs::write(&file_path, &old).unwrap();
+
+        assert!(encrypted_keystore(dir.path(), PASSPHRASE).is_err());
+        assert_eq!(
+            std::fs::read(&file_path).unwrap(),
+            old,
+            "An undecodable keystore must not be overwritten"
+        );
+    }
+
+    #[test]
+    fn test_wrong_passphrase() {
+        let dir = tempfile::tempdir().unwrap();
+        keystore_with_key(dir.path());
+        assert!(matches!(
+            encrypted_keystore(dir.path(), "wrong"),
+            Err(Error::WrongPassphrase)
+        ));
+
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join(ENCRYPTED_KEYSTORE_NAME);
+        std::fs::copy(
+            "src/key_management/tests/keystore_encrypted_old/keystore",
+            &file_path,
+        )
+        .unwrap();
+        let old = std::fs::read(&file_path).unwrap();
+        assert!(matches!(
+            encrypted_keystore(dir.path(), "wrong"),
+            Err(Error::WrongPassphrase)
+        ));
+        assert_eq!(
+            std::fs::read(&file_path).unwrap(),
+            old,
+            "A failed unlock must not upgrade the keystore"
+        );
+    }
+
+    #[test]
+    fn test_kdf_params_bounds() {
+        let dir = tempfile::tempdir().unwrap();
+        let ks = keystore_with_key(dir.path());
+        assert_eq!(
+            ks.file_format_version().unwrap(),
+            Some(KEYSTORE_FORMAT_VERSION)
+        );
+        assert_eq!(
+            KeyStore::new(KeyStoreConfig::Memory)
+                .unwrap()
+                .file_format_version()
+                .unwrap(),
+            None
+        );
+
+        // A crafted header is rejected before any key derivation
+        let file_path = dir.path().join(ENCRYPTED_KEYSTORE_NAME);
+        for kdf in [
+            KdfParams {
+                m_cost: u32::MAX,
+                ..KdfParams::generate()
+            },
+            KdfParams {
+                t_cost: u32::MAX,
+                ..KdfParams::generate()
+            },
+            KdfParams {
+                p_cost: MAX_KDF_P_COST + 1,
+                ..KdfParams::generate()
+            },
+        ] {
+            let file = EncryptedKeyStoreFile {
+                version: KEYSTORE_FORMAT_VERSION,
+                kdf,
+                data: vec![],
+            };
+            let mut file_data = KEYSTORE_MAGIC.to_vec();
+            file_data.extend(serde_ipld_dagcbor::to_vec(&file).unwrap());
+            std::fs::write(&file_path, file_data).unwrap();
+            let err = encrypted_keystore(dir.path(), PASSPHRASE).unwrap_err();
+            assert!(err.to_string().contains("exceeds"), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_rotate_passphrase() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut ks = keystore_with_key(dir.path());
+        let kdf = ks.kdf_params().cloned();
+        ks.rotate_passphrase("new passphrase").unwrap();
+        assert_ne!(ks.kdf_params().cloned(), kdf);
+
+        assert!(matches!(
+            encrypted_keystore(dir.path(), PASSPHRASE),
+            Err(Error::WrongPassphrase)
+        ));
+        let ks_read = encrypted_keystore(dir.path(), "new passphrase").unwrap();
+        assert_eq!(ks, ks_read);
+
+        let mut ks = KeyStore::new(KeyStoreConfig::Memory).unwrap();
+        assert!(ks.rotate_passphrase("new passphrase").is_err());
+    }
+
+    #[test]
+    fn test_interrupted_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let ks = keystore_with_key(dir.path());
+        let file_path = dir.path().join(ENCRYPTED_KEYSTORE_NAME);
+
+        // Simulate a crash after writing the rotated keystore but before
+        // renaming it over the old one.
+        let rotated = EncryptedKeyStore::new("new passphrase").unwrap();
+        let data = serde_ipld_dagcbor::to_vec(&ks.key_info).unwrap();
+        let tmp_path = write_temp_file(&file_path, &rotated.seal(&data).unwrap()).unwrap();
+        assert!(tmp_path.exists());
+
+        let mut ks_read = encrypted_keystore(dir.path(), PASSPHRASE).unwrap();
+        assert_eq!(ks, ks_read);
+
+        // A retried rotation replaces the leftover temporary file.
+        ks_read.rotate_passphrase("new passphrase").unwrap();
+        assert!(!tmp_path.exists());
+        let ks_rotated = encrypted_keystore(dir.path(), "new passphrase").unwrap();
+        assert_eq!(ks.key_info, ks_rotated.key_info);
     }
 
     #[test]
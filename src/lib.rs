@@ -55,6 +55,7 @@ mod genesis;
 mod health;
 mod interpreter;
 mod ipld;
+mod journal;
 mod key_management;
 mod libp2p;
 mod libp2p_bitswap;
@@ -102,7 +103,13 @@ pub mod doctest_private {
 #[cfg(feature = "benchmark-private")]
 #[doc(hidden)]
 pub mod benchmark_private {
+    pub use crate::blocks::{CachingBlockHeader, RawBlockHeader, Tipset};
+    pub use crate::chain::{read_msg_cids, ChainStore};
+    pub use crate::chain_sync::TipsetValidator;
     pub use crate::db::car::forest;
+    pub use crate::db::MemoryDB;
+    pub use crate::networks::ChainConfig;
+    pub use crate::shim::address::Address;
     pub use crate::utils::cid;
 }
 
@@ -125,7 +132,8 @@ pub use cli::main::main as forest_main;
 pub use cli_shared::cli::{Client, Config};
 pub use daemon::main::main as forestd_main;
 pub use key_management::{
-    KeyStore, KeyStoreConfig, ENCRYPTED_KEYSTORE_NAME, FOREST_KEYSTORE_PHRASE_ENV, KEYSTORE_NAME,
+    KeyStore, KeyStoreConfig, ENCRYPTED_KEYSTORE_NAME, FOREST_KEYSTORE_PHRASE_ENV,
+    KEYSTORE_FORMAT_VERSION, KEYSTORE_NAME,
 };
 pub use tool::main::main as forest_tool_main;
 pub use wallet::main::main as forest_wallet_main;
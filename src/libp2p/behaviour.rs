@@ -33,6 +33,8 @@ use libp2p::{
 use once_cell::sync::Lazy;
 use tracing::info;
 
+const MAX_ESTABLISHED_PER_PEER: u32 = 4;
+
 /// Libp2p behavior for the Forest node. This handles all sub protocols needed
 /// for a Filecoin node.
 #[derive(NetworkBehaviour)]
@@ -71,7 +73,6 @@ impl ForestBehaviour {
         network_name: &str,
         peer_manager: Arc<PeerManager>,
     ) -> anyhow::Result<Self> {
-        const MAX_ESTABLISHED_PER_PEER: u32 = 4;
         static MAX_CONCURRENT_REQUEST_RESPONSE_STREAMS_PER_PEER: Lazy<usize> = Lazy::new(|| {
             std::env::var("FOREST_MAX_CONCURRENT_REQUEST_RESPONSE_STREAMS_PER_PEER")
                 .ok()
@@ -88,6 +89,8 @@ impl ForestBehaviour {
         let mut gs_config_builder = gossipsub::ConfigBuilder::default();
         gs_config_builder.max_transmit_size(1 << 20);
         gs_config_builder.validation_mode(ValidationMode::Strict);
+        // Messages are only propagated once reported as accepted, see `gossip_validation`
+        gs_config_builder.validate_messages();
         gs_config_builder.message_id_fn(|msg: &gossipsub::Message| {
             let s = blake2b_256(&msg.data);
             MessageId::from(s)
@@ -127,30 +130,8 @@ impl ForestBehaviour {
             .target_peer_count(config.target_peer_count as u64)
             .finish()?;
 
-        let connection_limits = connection_limits::Behaviour::new(
-            connection_limits::ConnectionLimits::default()
-                .with_max_pending_incoming(Some(
-                    config
-                        .target_peer_count
-                        .saturating_mul(MAX_ESTABLISHED_PER_PEER),
-                ))
-                .with_max_pending_outgoing(Some(
-                    config
-                        .target_peer_count
-                        .saturating_mul(MAX_ESTABLISHED_PER_PEER),
-                ))
-                .with_max_established_incoming(Some(
-                    config
-                        .target_peer_count
-                        .saturating_mul(MAX_ESTABLISHED_PER_PEER),
-                ))
-                .with_max_established_outgoing(Some(
-                    config
-                        .target_peer_count
-                        .saturating_mul(MAX_ESTABLISHED_PER_PEER),
-                ))
-                .with_max_established_per_peer(Some(MAX_ESTABLISHED_PER_PEER)),
-        );
+        let connection_limits =
+            connection_limits::Behaviour::new(connection_limits_for(config.target_peer_count));
 
         info!("libp2p Forest version: {}", FOREST_VERSION_STRING.as_str());
         Ok(ForestBehaviour {
@@ -177,6 +158,13 @@ impl ForestBehaviour {
         self.discovery.bootstrap()
     }
 
+    /// Sets the target peer count, and the connection limits derived from it.
+    pub fn set_target_peer_count(&mut self, target_peer_count: u32) {
+        self.discovery
+            .set_target_peer_count(target_peer_count as u64);
+        *self.connection_limits.limits_mut() = connection_limits_for(target_peer_count);
+    }
+
     /// Publish data over the gossip network.
     pub fn publish(
         &mut self,
@@ -186,6 +174,19 @@ impl ForestBehaviour {
         self.gossipsub.publish(topic, data)
     }
 
+    /// Reports the outcome of the application-level validation of a gossip
+    /// message. Accepted messages are forwarded to other peers, rejected ones
+    /// lower the score of the propagation source.
+    pub fn report_message_validation_result(
+        &mut self,
+        msg_id: &MessageId,
+        propagation_source: &PeerId,
+        acceptance: MessageAcceptance,
+    ) -> bool {
+        self.gossipsub
+            .report_message_validation_result(msg_id, propagation_source, acceptance)
+    }
+
     /// Subscribe to a gossip topic.
     pub fn subscribe(&mut self, topic: &Topic) -> Result<bool, SubscriptionError> {
         self.gossipsub.subscribe(topic)
@@ -205,3 +206,14 @@ impl ForestBehaviour {
         self.discovery.peer_info(peer_id)
     }
 }
+
+/// Returns the connection limits for the target peer count.
+fn connection_limits_for(target_peer_count: u32) -> connection_limits::ConnectionLimits {
+    let max_connections = target_peer_count.saturating_mul(MAX_ESTABLISHED_PER_PEER);
+    connection_limits::ConnectionLimits::default()
+        .with_max_pending_incoming(Some(max_connections))
+        .with_max_pending_outgoing(Some(max_connections))
+        .with_max_established_incoming(Some(max_connections))
+        .with_max_established_outgoing(Some(max_connections))
+        .with_max_established_per_peer(Some(MAX_ESTABLISHED_PER_PEER))
+}
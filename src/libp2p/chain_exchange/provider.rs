@@ -7,12 +7,17 @@ use ahash::{HashMap, HashMapExt};
 use cid::Cid;
 use fvm_ipld_blockstore::Blockstore;
 use itertools::Itertools;
+use tracing::debug;
 
 use super::{
     ChainExchangeRequest, ChainExchangeResponse, ChainExchangeResponseStatus, CompactedMessages,
     TipsetBundle,
 };
 
+/// Upper bound on the number of bundles pre-allocated for a response, so that a
+/// request with a huge `request_len` cannot force a large allocation.
+const MAX_RESPONSE_PREALLOC: u64 = 1024;
+
 /// Builds chain exchange response out of chain data.
 pub fn make_chain_exchange_response<DB>(
     cs: &ChainStore<DB>,
@@ -44,32 +49,46 @@ where
             }
         };
 
-        let chain: Vec<_> = cs
-            .chain_index
-            .chain(root)
-            .take(request.request_len as _)
-            .map(|tipset| {
-                let mut tipset_bundle: TipsetBundle = TipsetBundle::default();
-                if request.include_messages() {
-                    tipset_bundle.messages = Some(compact_messages(cs.blockstore(), &tipset)?);
-                }
-
-                if request.include_blocks() {
-                    tipset_bundle.blocks = tipset.block_headers().iter().cloned().collect_vec();
+        // Serve as many contiguous tipsets as we have, walking down from the
+        // requested head. The walk stops at the first tipset whose parents or
+        // messages are not available locally (e.g. after pruning), in which
+        // case a partial response is returned instead of failing the request.
+        let mut chain = Vec::with_capacity(request.request_len.min(MAX_RESPONSE_PREALLOC) as _);
+        let mut unavailable = None;
+        for tipset in cs.chain_index.chain(root).take(request.request_len as _) {
+            match make_tipset_bundle(cs.blockstore(), request, &tipset) {
+                Ok(bundle) => chain.push(bundle),
+                // The head itself cannot be served, so the whole request fails.
+                Err(e) if chain.is_empty() => return Err(e),
+                Err(e) => {
+                    debug!(
+                        "Truncating chain exchange response at epoch {}: {e}",
+                        tipset.epoch()
+                    );
+                    unavailable = Some(tipset.epoch());
+                    break;
                 }
+            }
+        }
 
-                anyhow::Ok(tipset_bundle)
-            })
-            .try_collect()?;
+        let (status, message) = if (chain.len() as u64) < request.request_len {
+            let message = match unavailable {
+                Some(epoch) => format!("Tipset at epoch {epoch} is not available"),
+                None => format!(
+                    "Only {} of {} tipsets are available",
+                    chain.len(),
+                    request.request_len
+                ),
+            };
+            (ChainExchangeResponseStatus::PartialResponse, message)
+        } else {
+            (ChainExchangeResponseStatus::Success, "Success".into())
+        };
 
         anyhow::Ok(ChainExchangeResponse {
-            status: if request.request_len > chain.len() as u64 {
-                ChainExchangeResponseStatus::PartialResponse
-            } else {
-                ChainExchangeResponseStatus::Success
-            },
+            status,
             chain,
-            message: "Success".into(),
+            message,
         })
     };
 
@@ -83,6 +102,28 @@ where
     }
 }
 
+/// Builds the bundle for a single tipset, including only the parts selected by
+/// the request options.
+fn make_tipset_bundle<DB>(
+    db: &DB,
+    request: &ChainExchangeRequest,
+    tipset: &Tipset,
+) -> anyhow::Result<TipsetBundle>
+where
+    DB: Blockstore,
+{
+    let mut tipset_bundle = TipsetBundle::default();
+    if request.include_messages() {
+        tipset_bundle.messages = Some(compact_messages(db, tipset)?);
+    }
+
+    if request.include_blocks() {
+        tipset_bundle.blocks = tipset.block_headers().iter().cloned().collect_vec();
+    }
+
+    Ok(tipset_bundle)
+}
+
 // Builds CompactedMessages for given Tipset.
 fn compact_messages<DB>(db: &DB, tipset: &Tipset) -> Result<CompactedMessages, ChainError>
 where
@@ -150,7 +191,7 @@ mod tests {
         *,
     };
     use crate::blocks::{CachingBlockHeader, RawBlockHeader};
-    use crate::db::MemoryDB;
+    use crate::db::{GarbageCollectable as _, MemoryDB};
     use crate::genesis::EXPORT_SR_40;
     use crate::networks::ChainConfig;
     use crate::shim::address::Address;
@@ -165,6 +206,116 @@ mod tests {
         (header.roots, db)
     }
 
+    fn chain_store(db: Arc<MemoryDB>) -> ChainStore<MemoryDB> {
+        let gen_block = CachingBlockHeader::new(RawBlockHeader {
+            miner_address: Address::new_id(0),
+            ..Default::default()
+        });
+        ChainStore::new(
+            db.clone(),
+            db.clone(),
+            db,
+            Arc::new(ChainConfig::default()),
+            gen_block,
+        )
+        .unwrap()
+    }
+
+    /// Returns the `n`-th tipset (0 being the head) of the chain starting at `start`.
+    fn nth_tipset(cs: &ChainStore<MemoryDB>, start: &NonEmpty<Cid>, n: usize) -> Tipset {
+        let head = cs
+            .chain_index
+            .load_required_tipset(&TipsetKey::from(start.clone()))
+            .unwrap();
+        cs.chain_index.chain(head).nth(n).unwrap().as_ref().clone()
+    }
+
+    #[tokio::test]
+    async fn partial_response_on_missing_headers() {
+        let (cids, db) = populate_db().await;
+        let cs = chain_store(db.clone());
+        // Drop the headers of the 4th tipset, the chain can only be walked down
+        // to the 3rd one.
+        let pruned = nth_tipset(&cs, &cids, 3);
+        db.remove_keys(pruned.cids().into_iter().collect()).unwrap();
+
+        let response = make_chain_exchange_response(
+            &chain_store(db),
+            &ChainExchangeRequest {
+                start: cids,
+                request_len: 5,
+                options: HEADERS | MESSAGES,
+            },
+        );
+        assert_eq!(
+            response.status,
+            ChainExchangeResponseStatus::PartialResponse
+        );
+        assert_eq!(response.chain.len(), 3);
+        assert!(response.chain.iter().all(|b| b.messages.is_some()));
+    }
+
+    #[tokio::test]
+    async fn partial_response_on_missing_messages() {
+        let (cids, db) = populate_db().await;
+        let cs = chain_store(db.clone());
+        let pruned = nth_tipset(&cs, &cids, 2);
+        db.remove_keys(pruned.block_headers().iter().map(|h| h.messages).collect())
+            .unwrap();
+
+        let cs = chain_store(db);
+        let response = make_chain_exchange_response(
+            &cs,
+            &ChainExchangeRequest {
+                start: cids.clone(),
+                request_len: 5,
+                options: HEADERS | MESSAGES,
+            },
+        );
+        assert_eq!(
+            response.status,
+            ChainExchangeResponseStatus::PartialResponse
+        );
+        assert_eq!(response.chain.len(), 2);
+        assert_eq!(
+            response.message,
+            format!("Tipset at epoch {} is not available", pruned.epoch())
+        );
+
+        // Headers are still available, so a headers-only request is served in full.
+        let response = make_chain_exchange_response(
+            &cs,
+            &ChainExchangeRequest {
+                start: cids,
+                request_len: 5,
+                options: HEADERS,
+            },
+        );
+        assert_eq!(response.status, ChainExchangeResponseStatus::Success);
+        assert_eq!(response.chain.len(), 5);
+        assert!(response.chain.iter().all(|b| b.messages.is_none()));
+    }
+
+    #[tokio::test]
+    async fn missing_head_messages_is_an_error() {
+        let (cids, db) = populate_db().await;
+        let cs = chain_store(db.clone());
+        let head = nth_tipset(&cs, &cids, 0);
+        db.remove_keys(head.block_headers().iter().map(|h| h.messages).collect())
+            .unwrap();
+
+        let response = make_chain_exchange_response(
+            &chain_store(db),
+            &ChainExchangeRequest {
+                start: cids,
+                request_len: 5,
+                options: MESSAGES,
+            },
+        );
+        assert_eq!(response.status, ChainExchangeResponseStatus::InternalError);
+        assert!(response.chain.is_empty());
+    }
+
     #[tokio::test]
     async fn compact_messages_test() {
         let (cids, db) = populate_db().await;
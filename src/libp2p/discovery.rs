@@ -29,6 +29,7 @@ use libp2p::{
 use tokio::time::Interval;
 use tracing::{debug, info, trace, warn};
 
+use super::reachability::ReachabilityTracker;
 use crate::utils::version::FOREST_VERSION_STRING;
 
 #[derive(NetworkBehaviour)]
@@ -195,6 +196,7 @@ impl<'a> DiscoveryConfig<'a> {
             target_peer_count,
             custom_seed_peers: user_defined,
             pending_dial_opts: VecDeque::new(),
+            reachability: Default::default(),
         })
     }
 }
@@ -242,6 +244,8 @@ pub struct DiscoveryBehaviour {
     custom_seed_peers: Vec<(PeerId, Multiaddr)>,
     /// Options to configure dials to known peers.
     pending_dial_opts: VecDeque<DialOpts>,
+    /// Connection directions, observed addresses and ping round-trip times.
+    pub(crate) reachability: ReachabilityTracker,
 }
 
 #[derive(Default)]
@@ -286,6 +290,11 @@ impl DiscoveryBehaviour {
         }
     }
 
+    /// Sets the number of connected peers to pause discovery on.
+    pub fn set_target_peer_count(&mut self, target_peer_count: u64) {
+        self.target_peer_count = target_peer_count;
+    }
+
     /// Gets the NAT status.
     pub fn nat_status(&self) -> autonat::NatStatus {
         self.discovery.autonat.nat_status()
@@ -366,6 +375,8 @@ impl NetworkBehaviour for DiscoveryBehaviour {
     fn on_swarm_event(&mut self, event: FromSwarm) {
         match &event {
             FromSwarm::ConnectionEstablished(e) => {
+                self.reachability
+                    .on_connection_established(e.endpoint.is_dialer());
                 if e.other_established == 0 {
                     self.n_node_connected += 1;
                     self.peers.insert(e.peer_id);
@@ -374,6 +385,11 @@ impl NetworkBehaviour for DiscoveryBehaviour {
                 }
             }
             FromSwarm::ConnectionClosed(e) => {
+                self.reachability.on_connection_closed(
+                    &e.peer_id,
+                    e.endpoint.is_dialer(),
+                    e.remaining_established,
+                );
                 if e.remaining_established == 0 {
                     self.n_node_connected -= 1;
                     self.peers.remove(&e.peer_id);
@@ -445,6 +461,8 @@ impl NetworkBehaviour for DiscoveryBehaviour {
                             if let identify::Event::Received { peer_id, info, .. } = ev {
                                 self.peer_info.entry(*peer_id).or_default().identify_info =
                                     Some(info.clone());
+                                self.reachability
+                                    .on_observed_addr(*peer_id, info.observed_addr.clone());
                                 if let Some(kademlia) = self.discovery.kademlia.as_mut() {
                                     for address in &info.listen_addrs {
                                         kademlia.add_address(peer_id, address.clone());
@@ -0,0 +1,535 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Application-level validation of blocks and messages received over
+//! `GossipSub`. They are only propagated to other peers once accepted here;
+//! rejected ones lower the score of the peer that forwarded them.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::blocks::{GossipBlock, BLOCK_MESSAGE_LIMIT};
+use crate::chain::messages_from_cids;
+use crate::chain_sync::TipsetValidator;
+use crate::db::MemoryDB;
+use crate::interpreter::resolve_to_key_addr;
+use crate::libp2p_bitswap::{request_manager::BitswapRequestManager, BitswapStoreReadWrite};
+use crate::message::{Message as _, SignedMessage};
+use crate::shim::actors::miner;
+use crate::shim::address::{Address, Payload};
+use crate::shim::crypto::verify_bls_aggregate;
+use crate::shim::econ::BLOCK_GAS_LIMIT;
+use crate::shim::message::Message;
+use crate::shim::state_tree::StateTree;
+use crate::state_manager::StateManager;
+use crate::utils::encoding::from_slice_with_fallback;
+use bls_signatures::{PublicKey as BlsPublicKey, Serialize as _};
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use itertools::Itertools as _;
+use libp2p::gossipsub::MessageAcceptance;
+
+use super::metrics;
+
+/// Time budget for fetching the messages referenced by a gossip block.
+pub const GOSSIP_BLOCK_VALIDATION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Maximum size of an encoded gossip block, in bytes.
+pub const MAX_GOSSIP_BLOCK_SIZE: usize = 1 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display, strum::IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum BlockRejectReason {
+    TooLarge,
+    Undecodable,
+    TooManyMessages,
+    MessageRootMismatch,
+    MissingSignature,
+    InvalidSignature,
+    InvalidMessage,
+    GasLimitExceeded,
+    MissingBlsAggregate,
+    InvalidBlsAggregate,
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum BlockValidation {
+    Accept(GossipBlock),
+    /// The block could not be validated, e.g. because its messages could not
+    /// be fetched in time. It is neither propagated nor penalized.
+    Ignore(String),
+    Reject(BlockRejectReason),
+}
+
+impl BlockValidation {
+    pub fn acceptance(&self) -> MessageAcceptance {
+        match self {
+            Self::Accept(_) => MessageAcceptance::Accept,
+            Self::Ignore(_) => MessageAcceptance::Ignore,
+            Self::Reject(_) => MessageAcceptance::Reject,
+        }
+    }
+
+    pub fn record_metrics(&self) {
+        match self {
+            Self::Accept(_) => {}
+            Self::Ignore(_) => metrics::GOSSIP_BLOCK_IGNORED_TOTAL.inc(),
+            Self::Reject(reason) => {
+                metrics::GOSSIP_BLOCK_REJECTED_TOTAL
+                    .get_or_create(&crate::metrics::KindLabel::new(reason.into()))
+                    .inc();
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display, strum::IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum MessageRejectReason {
+    Undecodable,
+    InvalidMessage,
+    UnknownSender,
+    SequenceTooLow,
+    InsufficientBalance,
+    TooManyPending,
+}
+
+/// Reasons for not propagating a message without validating it. They don't
+/// lower the score of the forwarding peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display, strum::IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum MessageIgnoreReason {
+    /// The validation queue is full
+    QueueFull,
+}
+
+#[derive(Debug)]
+pub enum MessageValidation {
+    Accept(SignedMessage),
+    /// The message is neither propagated nor penalized.
+    Ignore(MessageIgnoreReason),
+    Reject(MessageRejectReason),
+}
+
+impl MessageValidation {
+    pub fn acceptance(&self) -> MessageAcceptance {
+        match self {
+            Self::Accept(_) => MessageAcceptance::Accept,
+            Self::Ignore(_) => MessageAcceptance::Ignore,
+            Self::Reject(_) => MessageAcceptance::Reject,
+        }
+    }
+
+    pub fn record_metrics(&self) {
+        match self {
+            Self::Accept(_) => {}
+            Self::Ignore(reason) => {
+                metrics::GOSSIP_MESSAGE_IGNORED_TOTAL
+                    .get_or_create(&crate::metrics::KindLabel::new(reason.into()))
+                    .inc();
+            }
+            Self::Reject(reason) => {
+                metrics::GOSSIP_MESSAGE_REJECTED_TOTAL
+                    .get_or_create(&crate::metrics::KindLabel::new(reason.into()))
+                    .inc();
+            }
+        }
+    }
+}
+
+/// Stateful validation of the messages received over `GossipSub`, against the
+/// state of their sender at the current head and the messages it already has
+/// pending. Implemented by the message pool.
+pub trait GossipMessageValidator: Send + Sync {
+    fn validate_gossip_message(&self, msg: SignedMessage) -> MessageValidation;
+}
+
+/// Decodes a gossip message and, if a validator is given, checks it against
+/// the chain state. Only the encoding is checked otherwise.
+pub fn validate_gossip_message(
+    validator: Option<&dyn GossipMessageValidator>,
+    data: &[u8],
+) -> MessageValidation {
+    let Ok(msg) = from_slice_with_fallback::<SignedMessage>(data) else {
+        return MessageValidation::Reject(MessageRejectReason::Undecodable);
+    };
+    match validator {
+        Some(validator) => validator.validate_gossip_message(msg),
+        None => MessageValidation::Accept(msg),
+    }
+}
+
+/// Validates an encoded gossip block. The header and its signature are
+/// checked first, and only then are the messages it references fetched from
+/// the network if they are missing locally. The miner worker and BLS sender
+/// keys are resolved in the block parent state, or in `fallback_state` if the
+/// former is not available.
+pub async fn validate_gossip_block<DB>(
+    db: Arc<DB>,
+    bitswap: Arc<BitswapRequestManager>,
+    fallback_state: Cid,
+    data: Vec<u8>,
+) -> BlockValidation
+where
+    DB: Blockstore + BitswapStoreReadWrite + Send + Sync + 'static,
+{
+    let block = match check_block_syntax(&data) {
+        Ok(block) => block,
+        Err(reason) => return BlockValidation::Reject(reason),
+    };
+
+    let state_root = match db.has(&block.header.state_root) {
+        Ok(true) => block.header.state_root,
+        _ => fallback_state,
+    };
+    let block = match tokio::task::spawn_blocking({
+        let db = db.clone();
+        move || check_block_header(&db, block, state_root)
+    })
+    .await
+    {
+        Ok(BlockValidation::Accept(block)) => block,
+        Ok(validation) => return validation,
+        Err(e) => return BlockValidation::Ignore(e.to_string()),
+    };
+
+    // The messages may be missing from the peers we are connected to rather
+    // than from the forwarding peer, so the block is not penalized for them.
+    let fetched = tokio::time::timeout(
+        GOSSIP_BLOCK_VALIDATION_TIMEOUT,
+        fetch_messages(&db, &bitswap, &block),
+    )
+    .await;
+    if !matches!(fetched, Ok(true)) {
+        return BlockValidation::Ignore(format!(
+            "messages of block {} could not be fetched",
+            block.header.cid()
+        ));
+    }
+
+    tokio::task::spawn_blocking(move || check_block_messages(&db, block, state_root))
+        .await
+        .unwrap_or_else(|e| BlockValidation::Ignore(e.to_string()))
+}
+
+/// Checks that only need the encoded block: size, message count and message
+/// root.
+fn check_block_syntax(data: &[u8]) -> Result<GossipBlock, BlockRejectReason> {
+    if data.len() > MAX_GOSSIP_BLOCK_SIZE {
+        return Err(BlockRejectReason::TooLarge);
+    }
+    let block = from_slice_with_fallback::<GossipBlock>(data)
+        .map_err(|_| BlockRejectReason::Undecodable)?;
+    if block.bls_messages.len() + block.secpk_messages.len() > BLOCK_MESSAGE_LIMIT {
+        return Err(BlockRejectReason::TooManyMessages);
+    }
+    // The AMT nodes are only needed to compute the root, don't persist them yet.
+    let msg_root = TipsetValidator::compute_msg_root_from_cids(
+        &MemoryDB::default(),
+        block.bls_messages.clone(),
+        block.secpk_messages.clone(),
+    )
+    .map_err(|_| BlockRejectReason::MessageRootMismatch)?;
+    if msg_root != block.header.messages {
+        return Err(BlockRejectReason::MessageRootMismatch);
+    }
+    Ok(block)
+}
+
+/// Checks that need the header only, and the miner state for its signature:
+/// signature presence, timestamp and signature against the miner worker key.
+/// The block is handed back on acceptance.
+fn check_block_header<DB>(db: &Arc<DB>, block: GossipBlock, state_root: Cid) -> BlockValidation
+where
+    DB: Blockstore + Send + Sync + 'static,
+{
+    if block.header.signature.is_none() {
+        return BlockValidation::Reject(BlockRejectReason::MissingSignature);
+    }
+    // Our clock may be the one drifting
+    if !block.header.is_within_clock_drift() {
+        return BlockValidation::Ignore(format!(
+            "block {} is from the future, at timestamp {}",
+            block.header.cid(),
+            block.header.timestamp
+        ));
+    }
+    let worker = match miner_worker_key(db, &block.header.miner_address, state_root) {
+        Ok(worker) => worker,
+        Err(e) => return BlockValidation::Ignore(format!("cannot resolve miner worker key: {e}")),
+    };
+    if block.header.verify_signature_against(&worker).is_err() {
+        return BlockValidation::Reject(BlockRejectReason::InvalidSignature);
+    }
+    BlockValidation::Accept(block)
+}
+
+/// Requests the messages missing from the store over bitswap. Returns `false`
+/// if any of them could not be fetched.
+async fn fetch_messages<DB>(
+    db: &Arc<DB>,
+    bitswap: &Arc<BitswapRequestManager>,
+    block: &GossipBlock,
+) -> bool
+where
+    DB: Blockstore + BitswapStoreReadWrite,
+{
+    let mut responses = vec![];
+    for cid in block.bls_messages.iter().chain(&block.secpk_messages) {
+        if !Blockstore::has(db.as_ref(), cid).unwrap_or_default() {
+            let (tx, rx) = flume::bounded(1);
+            bitswap.clone().get_block(
+                db.clone(),
+                *cid,
+                GOSSIP_BLOCK_VALIDATION_TIMEOUT,
+                Some(tx),
+                None,
+            );
+            responses.push(rx);
+        }
+    }
+    for rx in responses {
+        if !rx.recv_async().await.unwrap_or_default() {
+            return false;
+        }
+    }
+    true
+}
+
+/// Checks that need the block messages: gas limit and BLS aggregate signature.
+fn check_block_messages<DB>(db: &Arc<DB>, block: GossipBlock, state_root: Cid) -> BlockValidation
+where
+    DB: Blockstore + Send + Sync + 'static,
+{
+    let Ok(bls_msgs) = messages_from_cids::<_, Message>(db, &block.bls_messages) else {
+        return BlockValidation::Reject(BlockRejectReason::InvalidMessage);
+    };
+    let Ok(secp_msgs) = messages_from_cids::<_, SignedMessage>(db, &block.secpk_messages) else {
+        return BlockValidation::Reject(BlockRejectReason::InvalidMessage);
+    };
+
+    let gas_limit = bls_msgs
+        .iter()
+        .map(|m| m.gas_limit)
+        .chain(secp_msgs.iter().map(|m| m.gas_limit()))
+        .fold(0u64, u64::saturating_add);
+    if gas_limit > BLOCK_GAS_LIMIT {
+        return BlockValidation::Reject(BlockRejectReason::GasLimitExceeded);
+    }
+
+    let Some(sig) = &block.header.bls_aggregate else {
+        return BlockValidation::Reject(BlockRejectReason::MissingBlsAggregate);
+    };
+    let pub_keys = match bls_msgs
+        .iter()
+        .map(|m| bls_public_key(db, &m.from, state_root))
+        .try_collect::<_, Vec<_>, _>()
+    {
+        Ok(pub_keys) => pub_keys,
+        Err(e) => return BlockValidation::Ignore(format!("cannot resolve BLS sender key: {e}")),
+    };
+    let cids = block
+        .bls_messages
+        .iter()
+        .map(|cid| cid.to_bytes())
+        .collect_vec();
+    if !verify_bls_aggregate(
+        &cids.iter().map(Vec::as_slice).collect_vec(),
+        &pub_keys,
+        sig,
+    ) {
+        return BlockValidation::Reject(BlockRejectReason::InvalidBlsAggregate);
+    }
+
+    BlockValidation::Accept(block)
+}
+
+fn miner_worker_key<DB>(db: &Arc<DB>, miner: &Address, state_root: Cid) -> anyhow::Result<Address>
+where
+    DB: Blockstore + Send + Sync + 'static,
+{
+    let state = StateTree::new_from_root(db.clone(), &state_root)?;
+    let miner_state: miner::State = state.get_actor_state_from_address(miner)?;
+    let info = miner_state.info(db)?;
+    resolve_to_key_addr(&state, db, &info.worker().into())
+}
+
+fn bls_public_key<DB>(db: &Arc<DB>, addr: &Address, state_root: Cid) -> anyhow::Result<BlsPublicKey>
+where
+    DB: Blockstore + Send + Sync + 'static,
+{
+    // Key addresses don't need the state to be available
+    if let Payload::BLS(key) = addr.into_payload() {
+        return Ok(BlsPublicKey::from_bytes(&key)?);
+    }
+    Ok(StateManager::get_bls_public_key(db, addr, state_root)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::{CachingBlockHeader, RawBlockHeader};
+    use crate::key_management::{KeyStore, KeyStoreConfig, Wallet};
+    use crate::shim::crypto::{Signature, SignatureType};
+    use crate::utils::db::CborStoreExt as _;
+
+    fn aggregate(sigs: &[Signature]) -> Signature {
+        let sigs = sigs
+            .iter()
+            .map(|sig| bls_signatures::Signature::from_bytes(sig.bytes()).unwrap())
+            .collect_vec();
+        Signature::new_bls(bls_signatures::aggregate(&sigs).unwrap().as_bytes())
+    }
+
+    /// Returns a block with two BLS messages stored in `db`, and the signatures
+    /// of the messages.
+    fn block(db: &MemoryDB) -> (GossipBlock, Vec<Signature>) {
+        let mut wallet = Wallet::new(KeyStore::new(KeyStoreConfig::Memory).unwrap());
+        let from = wallet.generate_addr(SignatureType::Bls).unwrap();
+        let (cids, sigs): (Vec<_>, Vec<_>) = (0..2)
+            .map(|sequence| {
+                let msg = Message {
+                    from,
+                    to: Address::new_id(1000),
+                    sequence,
+                    gas_limit: 1_000_000,
+                    ..Default::default()
+                };
+                let cid = db.put_cbor_default(&msg).unwrap();
+                (cid, wallet.sign(&from, &cid.to_bytes()).unwrap())
+            })
+            .unzip();
+        let messages =
+            TipsetValidator::compute_msg_root_from_cids(&MemoryDB::default(), cids.clone(), vec![])
+                .unwrap();
+        let header = CachingBlockHeader::new(RawBlockHeader {
+            messages,
+            bls_aggregate: Some(aggregate(&sigs)),
+            ..Default::default()
+        });
+        let block = GossipBlock {
+            header,
+            bls_messages: cids,
+            secpk_messages: vec![],
+        };
+        (block, sigs)
+    }
+
+    fn validate(db: &Arc<MemoryDB>, block: &GossipBlock) -> BlockValidation {
+        let data = fvm_ipld_encoding::to_vec(block).unwrap();
+        match check_block_syntax(&data) {
+            Ok(block) => check_block_messages(db, block, Cid::default()),
+            Err(reason) => BlockValidation::Reject(reason),
+        }
+    }
+
+    fn assert_rejected(validation: BlockValidation, expected: BlockRejectReason) {
+        assert_eq!(validation.acceptance(), MessageAcceptance::Reject);
+        assert!(
+            matches!(validation, BlockValidation::Reject(reason) if reason == expected),
+            "expected {expected}, got {validation:?}"
+        );
+    }
+
+    #[test]
+    fn test_valid_block_is_accepted() {
+        let db = Arc::new(MemoryDB::default());
+        let (block, _) = block(&db);
+        let validation = validate(&db, &block);
+        assert_eq!(validation.acceptance(), MessageAcceptance::Accept);
+        assert!(matches!(validation, BlockValidation::Accept(accepted) if accepted == block));
+    }
+
+    #[test]
+    fn test_block_header_is_checked() {
+        let db = Arc::new(MemoryDB::default());
+        let (block, _) = block(&db);
+        match check_block_header(&db, block.clone(), Cid::default()) {
+            BlockValidation::Reject(reason) => {
+                assert_eq!(reason, BlockRejectReason::MissingSignature)
+            }
+            validation => panic!("expected a rejection, got {validation:?}"),
+        }
+
+        let mut future = block.clone().header.into_raw();
+        future.signature = Some(Signature::new_bls(vec![0; 96]));
+        future.timestamp = chrono::Utc::now().timestamp() as u64 + 3600;
+        let future = GossipBlock {
+            header: CachingBlockHeader::new(future),
+            ..block
+        };
+        // Ignored before the miner state is needed
+        let validation = check_block_header(&db, future, Cid::default());
+        assert_eq!(validation.acceptance(), MessageAcceptance::Ignore);
+    }
+
+    #[test]
+    fn test_undecodable_block_is_rejected() {
+        assert_eq!(
+            check_block_syntax(b"not a block").unwrap_err(),
+            BlockRejectReason::Undecodable
+        );
+        assert_eq!(
+            check_block_syntax(&vec![0; MAX_GOSSIP_BLOCK_SIZE + 1]).unwrap_err(),
+            BlockRejectReason::TooLarge
+        );
+    }
+
+    #[test]
+    fn test_undecodable_message_is_rejected() {
+        let validation = validate_gossip_message(None, b"not a message");
+        assert_eq!(validation.acceptance(), MessageAcceptance::Reject);
+        assert!(matches!(
+            validation,
+            MessageValidation::Reject(MessageRejectReason::Undecodable)
+        ));
+    }
+
+    #[test]
+    fn test_message_root_mismatch_is_rejected() {
+        let db = Arc::new(MemoryDB::default());
+        let (mut block, _) = block(&db);
+        block.bls_messages.pop();
+        assert_rejected(
+            validate(&db, &block),
+            BlockRejectReason::MessageRootMismatch,
+        );
+    }
+
+    #[test]
+    fn test_too_many_messages_is_rejected() {
+        let db = Arc::new(MemoryDB::default());
+        let (mut block, _) = block(&db);
+        block.secpk_messages = vec![Cid::default(); BLOCK_MESSAGE_LIMIT];
+        assert_rejected(validate(&db, &block), BlockRejectReason::TooManyMessages);
+    }
+
+    #[test]
+    fn test_invalid_bls_aggregate_is_rejected() {
+        let db = Arc::new(MemoryDB::default());
+        let (block, sigs) = block(&db);
+
+        let mut missing = block.clone().header.into_raw();
+        missing.bls_aggregate = None;
+        let missing = GossipBlock {
+            header: CachingBlockHeader::new(missing),
+            ..block.clone()
+        };
+        assert_rejected(
+            validate(&db, &missing),
+            BlockRejectReason::MissingBlsAggregate,
+        );
+
+        let mut partial = block.clone().header.into_raw();
+        partial.bls_aggregate = Some(aggregate(&sigs[..1]));
+        let partial = GossipBlock {
+            header: CachingBlockHeader::new(partial),
+            ..block
+        };
+        assert_rejected(
+            validate(&db, &partial),
+            BlockRejectReason::InvalidBlsAggregate,
+        );
+    }
+}
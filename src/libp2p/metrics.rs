@@ -2,7 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use once_cell::sync::Lazy;
-use prometheus_client::metrics::{counter::Counter, gauge::Gauge};
+use prometheus_client::metrics::{counter::Counter, family::Family, gauge::Gauge};
+
+use crate::metrics::KindLabel;
 
 pub static PEER_FAILURE_TOTAL: Lazy<Counter> = Lazy::new(|| {
     let metric = Counter::default();
@@ -33,3 +35,43 @@ pub static BAD_PEERS: Lazy<Gauge> = Lazy::new(|| {
     );
     metric
 });
+
+pub static GOSSIP_BLOCK_REJECTED_TOTAL: Lazy<Family<KindLabel, Counter>> = Lazy::new(|| {
+    let metric = Family::default();
+    crate::metrics::default_registry().register(
+        "gossip_block_rejected_total",
+        "Total number of gossip blocks rejected before propagation, by reason",
+        metric.clone(),
+    );
+    metric
+});
+
+pub static GOSSIP_BLOCK_IGNORED_TOTAL: Lazy<Counter> = Lazy::new(|| {
+    let metric = Counter::default();
+    crate::metrics::default_registry().register(
+        "gossip_block_ignored_total",
+        "Total number of gossip blocks that could not be validated in time",
+        metric.clone(),
+    );
+    metric
+});
+
+pub static GOSSIP_MESSAGE_REJECTED_TOTAL: Lazy<Family<KindLabel, Counter>> = Lazy::new(|| {
+    let metric = Family::default();
+    crate::metrics::default_registry().register(
+        "gossip_message_rejected_total",
+        "Total number of gossip messages rejected before propagation, by reason",
+        metric.clone(),
+    );
+    metric
+});
+
+pub static GOSSIP_MESSAGE_IGNORED_TOTAL: Lazy<Family<KindLabel, Counter>> = Lazy::new(|| {
+    let metric = Family::default();
+    crate::metrics::default_registry().register(
+        "gossip_message_ignored_total",
+        "Total number of gossip messages not propagated without validation, by reason",
+        metric.clone(),
+    );
+    metric
+});
@@ -6,11 +6,14 @@ pub mod chain_exchange;
 mod config;
 pub mod discovery;
 mod gossip_params;
+mod gossip_validation;
 pub mod hello;
 pub mod keypair;
 pub mod metrics;
 mod peer_manager;
+mod peer_store;
 pub mod ping;
+mod reachability;
 pub mod rpc;
 mod service;
 
@@ -22,7 +25,15 @@ pub use libp2p::{
 };
 
 pub(in crate::libp2p) use self::behaviour::*;
-pub use self::{config::*, peer_manager::*, service::*};
+pub use self::{
+    config::*,
+    gossip_validation::{
+        GossipMessageValidator, MessageIgnoreReason, MessageRejectReason, MessageValidation,
+    },
+    peer_manager::*,
+    peer_store::{load_persisted_peers, persist_peers, PeerRecord},
+    service::*,
+};
 #[cfg(test)]
 mod tests {
     mod decode_test;
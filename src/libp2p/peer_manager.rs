@@ -12,6 +12,8 @@ use parking_lot::RwLock;
 use rand::seq::SliceRandom;
 use tracing::{debug, trace, warn};
 
+use crate::blocks::TipsetKey;
+use crate::journal::JournalEvent;
 use crate::libp2p::*;
 
 /// New peer multiplier slightly less than 1 to incentivize choosing new peers.
@@ -25,6 +27,10 @@ const LOCAL_INV_ALPHA: u32 = 5;
 /// Global duration multiplier, affects duration delta change.
 const GLOBAL_INV_ALPHA: u32 = 20;
 
+/// Score of a relayed gossip message relative to a successful chain exchange
+/// request.
+const GOSSIP_SCORE_WEIGHT: f64 = 0.1;
+
 #[derive(Debug, Default)]
 /// Contains info about the peer's head [Tipset], as well as the request stats.
 struct PeerInfo {
@@ -45,6 +51,11 @@ struct PeerSets {
     /// Set of peers to ignore for being incompatible/ failing to accept
     /// connections.
     bad_peers: HashSet<PeerId>,
+    /// Heaviest tipsets announced by peers in their hello requests, or blocks
+    /// they relayed since, and when they were announced.
+    heads: HashMap<PeerId, (TipsetKey, Instant)>,
+    /// Number of valid gossip messages and blocks relayed by peers.
+    gossip: HashMap<PeerId, u32>,
 }
 
 /// Thread safe peer manager which handles peer management for the
@@ -54,6 +65,8 @@ pub struct PeerManager {
     peers: RwLock<PeerSets>,
     /// Average response time from peers.
     avg_global_time: RwLock<Duration>,
+    /// Time of the last successful request.
+    last_global_success: RwLock<Option<Instant>>,
     /// Peer operation sender
     peer_ops_tx: Sender<PeerOperation>,
     /// Peer operation receiver
@@ -70,6 +83,7 @@ impl Default for PeerManager {
         PeerManager {
             peers: Default::default(),
             avg_global_time: Default::default(),
+            last_global_success: Default::default(),
             peer_ops_tx,
             peer_ops_rx,
             peer_ban_list: Default::default(),
@@ -138,6 +152,7 @@ impl PeerManager {
     /// peer manager.
     pub fn log_global_success(&self, dur: Duration) {
         debug!("logging global success");
+        *self.last_global_success.write() = Some(Instant::now());
         let mut avg_global = self.avg_global_time.write();
         if *avg_global == Duration::default() {
             *avg_global = dur;
@@ -150,6 +165,11 @@ impl PeerManager {
         }
     }
 
+    /// Returns the time of the last successful request to any peer.
+    pub fn last_global_success(&self) -> Option<Instant> {
+        *self.last_global_success.read()
+    }
+
     /// Logs a success for the given peer, and updates the average request
     /// duration.
     pub fn log_success(&self, peer: &PeerId, dur: Duration) {
@@ -198,6 +218,55 @@ impl PeerManager {
         }
     }
 
+    /// Records the heaviest tipset announced by a peer.
+    pub fn update_peer_head(&self, peer_id: PeerId, head: TipsetKey) {
+        self.peers
+            .write()
+            .heads
+            .insert(peer_id, (head, Instant::now()));
+    }
+
+    /// Logs a valid gossip message or block relayed by the given peer.
+    pub fn log_gossip(&self, peer: &PeerId) {
+        *self.peers.write().gossip.entry(*peer).or_default() += 1;
+    }
+
+    /// Usefulness of the connected peers, used to pick the peers to remember
+    /// across restarts. Chain exchange outcomes weigh more than gossip.
+    pub fn peer_scores(&self) -> HashMap<PeerId, f64> {
+        let peers = self.peers.read();
+        let mut scores = HashMap::default();
+        for (peer, info) in peers.full_peers.iter() {
+            *scores.entry(*peer).or_default() +=
+                f64::from(info.successes) - f64::from(info.failures);
+        }
+        for (peer, count) in peers.gossip.iter() {
+            *scores.entry(*peer).or_default() += GOSSIP_SCORE_WEIGHT * f64::from(*count);
+        }
+        scores
+    }
+
+    /// Returns the last heaviest tipset announced by each connected peer.
+    pub fn peer_heads(&self) -> Vec<(PeerId, TipsetKey)> {
+        self.peers
+            .read()
+            .heads
+            .iter()
+            .map(|(peer, (head, _))| (*peer, head.clone()))
+            .collect()
+    }
+
+    /// Returns the heads announced by the connected peers since `since`.
+    pub fn peer_heads_since(&self, since: Instant) -> Vec<(PeerId, TipsetKey)> {
+        self.peers
+            .read()
+            .heads
+            .iter()
+            .filter(|(_, (_, announced))| *announced >= since)
+            .map(|(peer, (head, _))| (*peer, head.clone()))
+            .collect()
+    }
+
     /// Remove peer from managed set, does not mark as bad
     pub fn remove_peer(&self, peer_id: &PeerId) {
         let mut peers = self.peers.write();
@@ -223,12 +292,18 @@ impl PeerManager {
         let mut locked = self.peer_ban_list.write().await;
         locked.insert(peer, duration.and_then(|d| Instant::now().checked_add(d)));
         let user_agent = get_user_agent(&peer);
+        let reason = reason.into();
+        crate::journal::record(JournalEvent::PeerBan {
+            peer: peer.to_string(),
+            reason: reason.clone(),
+            duration_secs: duration.map(|d| d.as_secs()),
+        });
         if let Err(e) = self
             .peer_ops_tx
             .send_async(PeerOperation::Ban {
                 peer,
                 user_agent,
-                reason: reason.into(),
+                reason,
             })
             .await
         {
@@ -304,6 +379,8 @@ impl PeerManager {
 }
 
 fn remove_peer(peers: &mut PeerSets, peer_id: &PeerId) {
+    peers.heads.remove(peer_id);
+    peers.gossip.remove(peer_id);
     if peers.full_peers.remove(peer_id).is_some() {
         metrics::FULL_PEERS.set(peers.full_peers.len() as _);
     }
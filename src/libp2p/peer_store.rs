@@ -0,0 +1,269 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Persistent store of recently useful peers. Peers that served chain exchange
+//! requests or relayed valid gossip are remembered across restarts, and the
+//! best of them are dialed on startup alongside the bootstrap list.
+
+use std::time::Duration;
+
+use crate::db::{setting_keys::PEER_STORE_KEY, SettingsStore, SettingsStoreExt as _};
+use crate::lotus_json::lotus_json_with_self;
+use ahash::{HashMap, HashMapExt as _};
+use itertools::Itertools as _;
+use libp2p::{
+    swarm::dial_opts::{DialOpts, PeerCondition},
+    Multiaddr, PeerId, Swarm,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DisplayFromStr};
+use tracing::debug;
+
+use super::ForestBehaviour;
+
+/// Maximum number of persisted peers, the lowest scored ones are dropped first.
+const MAX_PERSISTED_PEERS: usize = 200;
+/// Persisted peers that have not been useful for this long are dropped.
+const PERSISTED_PEER_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+/// Number of persisted peers dialed on startup.
+pub(in crate::libp2p) const PERSISTED_PEERS_TO_DIAL: usize = 32;
+/// Interval between two snapshots of the useful peers.
+pub(in crate::libp2p) const PEER_STORE_PERSIST_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// A peer remembered across restarts.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct PeerRecord {
+    #[schemars(with = "String")]
+    #[serde_as(as = "DisplayFromStr")]
+    pub peer_id: PeerId,
+    #[schemars(with = "Vec<String>")]
+    pub addrs: Vec<Multiaddr>,
+    /// Usefulness of the peer, see [`crate::libp2p::PeerManager::peer_scores`].
+    pub score: f64,
+    /// Unix timestamp (seconds) of the last snapshot the peer was useful in.
+    pub last_seen: u64,
+}
+
+lotus_json_with_self!(PeerRecord);
+
+/// On-disk format of the peer store. New versions are added as variants so
+/// that older entries can still be read, or at least recognized and dropped.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "Version")]
+enum PersistedPeers {
+    #[serde(rename = "1", rename_all = "PascalCase")]
+    V1 { peers: Vec<PeerRecord> },
+}
+
+impl PersistedPeers {
+    fn into_peers(self) -> Vec<PeerRecord> {
+        match self {
+            Self::V1 { peers } => peers,
+        }
+    }
+}
+
+fn is_expired(record: &PeerRecord, now: u64) -> bool {
+    record.last_seen + PERSISTED_PEER_TTL.as_secs() < now
+}
+
+/// Reads the persisted peers that are not expired at `now`, best scored first.
+pub fn load_persisted_peers(
+    store: &(impl SettingsStore + ?Sized),
+    now: u64,
+) -> anyhow::Result<Vec<PeerRecord>> {
+    let peers = store
+        .read_obj::<PersistedPeers>(PEER_STORE_KEY)?
+        .map(PersistedPeers::into_peers)
+        .unwrap_or_default();
+    Ok(merge_peers(peers, vec![], now))
+}
+
+/// Merges the currently useful peers into the persisted ones and writes the
+/// result back.
+pub fn persist_peers(
+    store: &(impl SettingsStore + ?Sized),
+    current: Vec<PeerRecord>,
+    now: u64,
+) -> anyhow::Result<()> {
+    // An unreadable store, e.g. written by a newer version, is overwritten.
+    let persisted = load_persisted_peers(store, now).unwrap_or_default();
+    let peers = merge_peers(persisted, current, now);
+    debug!("Persisting {} peers", peers.len());
+    store.write_obj(PEER_STORE_KEY, &PersistedPeers::V1 { peers })
+}
+
+/// Overrides the `persisted` records with the `current` ones, drops the
+/// expired records and keeps the [`MAX_PERSISTED_PEERS`] best scored ones.
+fn merge_peers(persisted: Vec<PeerRecord>, current: Vec<PeerRecord>, now: u64) -> Vec<PeerRecord> {
+    let mut merged = HashMap::new();
+    for record in persisted.into_iter().chain(current) {
+        merged.insert(record.peer_id, record);
+    }
+    merged
+        .into_values()
+        .filter(|record| !is_expired(record, now) && !record.addrs.is_empty())
+        .sorted_by(|a, b| b.score.total_cmp(&a.score))
+        .take(MAX_PERSISTED_PEERS)
+        .collect()
+}
+
+/// Dials peers, abstracted over the swarm for testing.
+pub(in crate::libp2p) trait Dialer {
+    fn dial_peer(&mut self, peer_id: PeerId, addrs: Vec<Multiaddr>) -> anyhow::Result<()>;
+}
+
+impl Dialer for Swarm<ForestBehaviour> {
+    fn dial_peer(&mut self, peer_id: PeerId, addrs: Vec<Multiaddr>) -> anyhow::Result<()> {
+        Ok(self.dial(
+            DialOpts::peer_id(peer_id)
+                .condition(PeerCondition::Disconnected)
+                .addresses(addrs)
+                .build(),
+        )?)
+    }
+}
+
+/// Dials the `limit` best scored of the persisted `peers`, returning the
+/// number of dials that were started.
+pub(in crate::libp2p) fn dial_persisted_peers(
+    dialer: &mut impl Dialer,
+    peers: &[PeerRecord],
+    limit: usize,
+) -> usize {
+    peers
+        .iter()
+        .sorted_by(|a, b| b.score.total_cmp(&a.score))
+        .take(limit)
+        .filter(|record| {
+            dialer
+                .dial_peer(record.peer_id, record.addrs.clone())
+                .inspect_err(|e| debug!("Failed to dial persisted peer {}: {e}", record.peer_id))
+                .is_ok()
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MemoryDB;
+    use std::str::FromStr as _;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn record(score: f64, last_seen: u64) -> PeerRecord {
+        PeerRecord {
+            peer_id: PeerId::random(),
+            addrs: vec!["/ip4/1.2.3.4/tcp/1234".parse().unwrap()],
+            score,
+            last_seen,
+        }
+    }
+
+    #[test]
+    fn persisted_format() {
+        let peer_id =
+            PeerId::from_str("12D3KooWENMwUF9YxvQxar7uBWJtZkA6amvK4xWmKXfSiHUo2Qq7").unwrap();
+        let peers = PersistedPeers::V1 {
+            peers: vec![PeerRecord {
+                peer_id,
+                addrs: vec!["/ip4/1.2.3.4/tcp/1234".parse().unwrap()],
+                score: 2.5,
+                last_seen: NOW,
+            }],
+        };
+        let json = serde_json::json!({
+            "Version": "1",
+            "Peers": [{
+                "PeerId": "12D3KooWENMwUF9YxvQxar7uBWJtZkA6amvK4xWmKXfSiHUo2Qq7",
+                "Addrs": ["/ip4/1.2.3.4/tcp/1234"],
+                "Score": 2.5,
+                "LastSeen": NOW,
+            }],
+        });
+        assert_eq!(serde_json::to_value(&peers).unwrap(), json);
+        assert_eq!(
+            serde_json::from_value::<PersistedPeers>(json).unwrap(),
+            peers
+        );
+        assert!(serde_json::from_value::<PersistedPeers>(serde_json::json!({
+            "Version": "2",
+            "Peers": [],
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn persist_and_load() {
+        let store = MemoryDB::default();
+        assert_eq!(load_persisted_peers(&store, NOW).unwrap(), vec![]);
+
+        let low = record(1.0, NOW);
+        let high = record(3.0, NOW);
+        let stale = record(10.0, NOW - PERSISTED_PEER_TTL.as_secs() - 1);
+        persist_peers(&store, vec![low.clone(), stale, high.clone()], NOW).unwrap();
+        assert_eq!(
+            load_persisted_peers(&store, NOW).unwrap(),
+            vec![high.clone(), low.clone()]
+        );
+
+        // Fresh records replace the persisted ones, and the others are kept.
+        let updated = PeerRecord {
+            score: 5.0,
+            last_seen: NOW + 60,
+            ..low.clone()
+        };
+        persist_peers(&store, vec![updated.clone()], NOW + 60).unwrap();
+        assert_eq!(
+            load_persisted_peers(&store, NOW + 60).unwrap(),
+            vec![updated, high]
+        );
+    }
+
+    #[test]
+    fn persisted_peers_are_capped() {
+        let store = MemoryDB::default();
+        let records = (0..MAX_PERSISTED_PEERS + 10)
+            .map(|i| record(i as f64, NOW))
+            .collect_vec();
+        persist_peers(&store, records, NOW).unwrap();
+        let loaded = load_persisted_peers(&store, NOW).unwrap();
+        assert_eq!(loaded.len(), MAX_PERSISTED_PEERS);
+        assert_eq!(loaded.last().unwrap().score, 10.0);
+    }
+
+    #[derive(Default)]
+    struct MockDialer {
+        dialed: Vec<PeerId>,
+        unreachable: Option<PeerId>,
+    }
+
+    impl Dialer for MockDialer {
+        fn dial_peer(&mut self, peer_id: PeerId, _: Vec<Multiaddr>) -> anyhow::Result<()> {
+            if self.unreachable == Some(peer_id) {
+                anyhow::bail!("unreachable");
+            }
+            self.dialed.push(peer_id);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn dialing_prefers_higher_scores() {
+        let peers = [record(1.0, NOW), record(4.0, NOW), record(2.0, NOW)];
+        let mut dialer = MockDialer::default();
+        assert_eq!(dial_persisted_peers(&mut dialer, &peers, 2), 2);
+        assert_eq!(dialer.dialed, vec![peers[1].peer_id, peers[2].peer_id]);
+
+        let mut dialer = MockDialer {
+            unreachable: Some(peers[1].peer_id),
+            ..Default::default()
+        };
+        assert_eq!(dial_persisted_peers(&mut dialer, &peers, 3), 2);
+        assert_eq!(dialer.dialed, vec![peers[2].peer_id, peers[0].peer_id]);
+    }
+}
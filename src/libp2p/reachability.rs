@@ -0,0 +1,121 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use ahash::{HashMap, HashSet};
+use libp2p::{Multiaddr, PeerId};
+
+/// Aggregates what the swarm learns about the reachability of this node:
+/// the direction of the established connections, the addresses remote peers
+/// observe us on (from `identify`).
+#[derive(Debug, Default)]
+pub struct ReachabilityTracker {
+    num_inbound: usize,
+    num_outbound: usize,
+    /// Our addresses as observed by remote peers, with the peers reporting them.
+    observed_addrs: HashMap<Multiaddr, HashSet<PeerId>>,
+}
+
+impl ReachabilityTracker {
+    pub fn on_connection_established(&mut self, is_dialer: bool) {
+        if is_dialer {
+            self.num_outbound += 1;
+        } else {
+            self.num_inbound += 1;
+        }
+    }
+
+    pub fn on_connection_closed(
+        &mut self,
+        peer_id: &PeerId,
+        is_dialer: bool,
+        remaining_established: usize,
+    ) {
+        if is_dialer {
+            self.num_outbound = self.num_outbound.saturating_sub(1);
+        } else {
+            self.num_inbound = self.num_inbound.saturating_sub(1);
+        }
+        if remaining_established == 0 {
+            self.observed_addrs.retain(|_, peers| {
+                peers.remove(peer_id);
+                !peers.is_empty()
+            });
+        }
+    }
+
+    /// Records the address `peer_id` reports to observe us on, replacing
+    /// the one it reported previously.
+    pub fn on_observed_addr(&mut self, peer_id: PeerId, addr: Multiaddr) {
+        self.observed_addrs.retain(|_, peers| {
+            peers.remove(&peer_id);
+            !peers.is_empty()
+        });
+        self.observed_addrs.entry(addr).or_default().insert(peer_id);
+    }
+
+    /// Number of established inbound connections.
+    pub fn num_inbound(&self) -> usize {
+        self.num_inbound
+    }
+
+    /// Number of established outbound connections.
+    pub fn num_outbound(&self) -> usize {
+        self.num_outbound
+    }
+
+    /// Observed addresses with the number of peers reporting them, the most
+    /// reported first.
+    pub fn observed_addrs(&self) -> Vec<(Multiaddr, usize)> {
+        let mut addrs: Vec<_> = self
+            .observed_addrs
+            .iter()
+            .map(|(addr, peers)| (addr.clone(), peers.len()))
+            .collect();
+        addrs.sort_by(|(a_addr, a_count), (b_addr, b_count)| {
+            b_count.cmp(a_count).then_with(|| a_addr.cmp(b_addr))
+        });
+        addrs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reachability_tracker_aggregation() {
+        let mut tracker = ReachabilityTracker::default();
+        let (a, b, c) = (PeerId::random(), PeerId::random(), PeerId::random());
+        let public: Multiaddr = "/ip4/1.2.3.4/tcp/1234".parse().unwrap();
+        let nated: Multiaddr = "/ip4/1.2.3.4/tcp/4321".parse().unwrap();
+
+        // `a` dials us twice, we dial `b` and `c`
+        tracker.on_connection_established(false);
+        tracker.on_connection_established(false);
+        tracker.on_connection_established(true);
+        tracker.on_connection_established(true);
+        assert_eq!((tracker.num_inbound(), tracker.num_outbound()), (2, 2));
+
+        tracker.on_observed_addr(a, nated.clone());
+        tracker.on_observed_addr(a, public.clone());
+        tracker.on_observed_addr(b, public.clone());
+        tracker.on_observed_addr(c, nated.clone());
+        assert_eq!(
+            tracker.observed_addrs(),
+            vec![(public.clone(), 2), (nated.clone(), 1)]
+        );
+
+        // Closing one of the connections to `a` keeps its observations
+        tracker.on_connection_closed(&a, false, 1);
+        assert_eq!((tracker.num_inbound(), tracker.num_outbound()), (1, 2));
+        assert_eq!(
+            tracker.observed_addrs(),
+            vec![(public.clone(), 2), (nated.clone(), 1)]
+        );
+
+        tracker.on_connection_closed(&a, false, 0);
+        tracker.on_connection_closed(&c, true, 0);
+        assert_eq!((tracker.num_inbound(), tracker.num_outbound()), (0, 1));
+        assert_eq!(tracker.observed_addrs(), vec![(public, 1)]);
+    }
+}
@@ -6,27 +6,29 @@ use std::{
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
+use crate::chain::ChainStore;
 use crate::message::SignedMessage;
-use crate::{blocks::GossipBlock, rpc::net::NetInfoResult};
-use crate::{chain::ChainStore, utils::encoding::from_slice_with_fallback};
 use crate::{
-    libp2p_bitswap::{
-        request_manager::BitswapRequestManager, BitswapStoreRead, BitswapStoreReadWrite,
-    },
+    blocks::{GossipBlock, TipsetKey},
+    rpc::net::{NetInfoResult, NetReachabilityResult, ObservedAddr},
+};
+use crate::{
+    libp2p_bitswap::{request_manager::BitswapRequestManager, BitswapStoreReadWrite},
     utils::flume::FlumeSenderExt as _,
 };
 use ahash::{HashMap, HashSet};
 use anyhow::Context as _;
 use cid::Cid;
 use flume::Sender;
-use futures::{select, stream::StreamExt as _};
+use futures::{select, stream::StreamExt as _, FutureExt as _};
 use fvm_ipld_blockstore::Blockstore;
 pub use libp2p::gossipsub::{IdentTopic, Topic};
 use libp2p::{
     autonat::NatStatus,
     connection_limits::Exceeded,
     core::Multiaddr,
-    gossipsub, identify,
+    gossipsub::{self, MessageAcceptance, MessageId},
+    identify,
     identity::Keypair,
     metrics::{Metrics, Recorder},
     multiaddr::Protocol,
@@ -34,12 +36,21 @@ use libp2p::{
     swarm::{DialError, SwarmEvent},
     tcp, yamux, PeerId, Swarm, SwarmBuilder,
 };
+use tokio::sync::{watch, Semaphore};
 use tokio_stream::wrappers::IntervalStream;
 use tracing::{debug, error, info, trace, warn};
 
 use super::{
     chain_exchange::{make_chain_exchange_response, ChainExchangeRequest, ChainExchangeResponse},
     discovery::{DerivedDiscoveryBehaviourEvent, PeerInfo},
+    gossip_validation::{
+        validate_gossip_block, validate_gossip_message, BlockValidation, GossipMessageValidator,
+        MessageIgnoreReason, MessageValidation,
+    },
+    peer_store::{
+        dial_persisted_peers, load_persisted_peers, persist_peers, PeerRecord,
+        PEER_STORE_PERSIST_INTERVAL, PERSISTED_PEERS_TO_DIAL,
+    },
     ForestBehaviour, ForestBehaviourEvent, Libp2pConfig,
 };
 use crate::libp2p::{
@@ -85,6 +96,14 @@ const PUBSUB_TOPICS: [&str; 2] = [PUBSUB_BLOCK_STR, PUBSUB_MSG_STR];
 
 pub const BITSWAP_TIMEOUT: Duration = Duration::from_secs(30);
 
+/// Maximum number of gossip blocks validated at once. Blocks arriving while as
+/// many are being validated are ignored.
+const MAX_CONCURRENT_BLOCK_VALIDATIONS: usize = 64;
+
+/// Maximum number of gossip messages waiting for validation. Messages arriving
+/// while the queue is full are ignored.
+const MESSAGE_VALIDATION_QUEUE_SIZE: usize = 1024;
+
 /// Events emitted by this Service.
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
@@ -141,6 +160,8 @@ pub enum NetworkMessage {
     JSONRPCRequest {
         method: NetRPCMethods,
     },
+    /// Re-dials the bootstrap peers and restarts the Kademlia bootstrap
+    Bootstrap,
 }
 
 /// Network RPC API methods used to gather data from libp2p node.
@@ -157,6 +178,8 @@ pub enum NetRPCMethods {
     Disconnect(flume::Sender<()>, PeerId),
     AgentVersion(flume::Sender<Option<String>>, PeerId),
     AutoNATStatus(flume::Sender<NatStatus>),
+    Reachability(flume::Sender<NetReachabilityResult>),
+    SetTargetPeerCount(u32),
 }
 
 /// The `Libp2pService` listens to events from the libp2p swarm.
@@ -171,6 +194,8 @@ pub struct Libp2pService<DB> {
     network_sender_out: Sender<NetworkEvent>,
     network_name: String,
     genesis_cid: Cid,
+    message_validator: Option<Arc<dyn GossipMessageValidator>>,
+    shutdown: Option<watch::Receiver<bool>>,
 }
 
 impl<DB> Libp2pService<DB>
@@ -268,14 +293,38 @@ where
             network_sender_out,
             network_name: network_name.into(),
             genesis_cid,
+            message_validator: None,
+            shutdown: None,
         })
     }
 
+    /// Sets the validator checking gossip messages against the chain state
+    /// before they are propagated. Without one, only their encoding is
+    /// checked.
+    pub fn set_message_validator(&mut self, validator: Arc<dyn GossipMessageValidator>) {
+        self.message_validator = Some(validator);
+    }
+
+    /// Sets the signal on which the service persists its peer store and
+    /// stops, before the daemon aborts its tasks.
+    pub fn set_shutdown_signal(&mut self, shutdown: watch::Receiver<bool>) {
+        self.shutdown = Some(shutdown);
+    }
+
     /// Starts the libp2p service networking stack. This Future resolves when
     /// shutdown occurs.
     pub async fn run(mut self) -> anyhow::Result<()> {
         info!("Running libp2p service");
 
+        // Dial the peers that were useful before the last shutdown
+        match load_persisted_peers(self.cs.settings().as_ref(), unix_now()) {
+            Ok(peers) => {
+                let dialed = dial_persisted_peers(&mut self.swarm, &peers, PERSISTED_PEERS_TO_DIAL);
+                info!("Dialing {dialed} persisted peers");
+            }
+            Err(e) => warn!("Failed to load persisted peers: {e}"),
+        }
+
         // Bootstrap with Kademlia
         if let Err(e) = self.swarm.behaviour_mut().bootstrap() {
             warn!("Failed to bootstrap with Kademlia: {e}");
@@ -290,8 +339,26 @@ where
         let pubsub_msg_str = format!("{}/{}", PUBSUB_MSG_STR, self.network_name);
 
         let (cx_response_tx, cx_response_rx) = flume::unbounded();
+        let (block_validation_tx, block_validation_rx) = flume::unbounded();
+        let block_validation_permits = Arc::new(Semaphore::new(MAX_CONCURRENT_BLOCK_VALIDATIONS));
+        let (message_validation_tx, message_validation_rx) = flume::unbounded();
+        let (message_validation_request_tx, message_validation_request_rx) =
+            flume::bounded::<(MessageId, PeerId, Vec<u8>)>(MESSAGE_VALIDATION_QUEUE_SIZE);
+
+        // Gossip messages are checked against the chain state by a single
+        // worker, off the event loop, and only propagated and processed once
+        // accepted. The worker stops when the service does.
+        let message_validator = self.message_validator.clone();
+        tokio::task::spawn_blocking(move || {
+            for (message_id, source, data) in message_validation_request_rx.iter() {
+                let validation = validate_gossip_message(message_validator.as_deref(), &data);
+                message_validation_tx.send_or_warn((message_id, source, validation));
+            }
+        });
 
         let mut cx_response_rx_stream = cx_response_rx.stream().fuse();
+        let mut block_validation_rx_stream = block_validation_rx.stream().fuse();
+        let mut message_validation_rx_stream = message_validation_rx.stream().fuse();
         let mut bitswap_outbound_request_stream =
             bitswap_request_manager.outbound_request_stream().fuse();
         let mut peer_ops_rx_stream = self.peer_manager.peer_ops_rx().stream().fuse();
@@ -305,6 +372,13 @@ where
                 BOOTSTRAP_PEER_DIALER_INTERVAL,
             ))
             .fuse();
+        let mut peer_store_interval_stream = IntervalStream::new(tokio::time::interval_at(
+            tokio::time::Instant::now() + PEER_STORE_PERSIST_INTERVAL,
+            PEER_STORE_PERSIST_INTERVAL,
+        ))
+        .fuse();
+        let shutdown = wait_for_shutdown(self.shutdown.take()).fuse();
+        futures::pin_mut!(shutdown);
         loop {
             select! {
                 swarm_event = swarm_stream.next() => match swarm_event {
@@ -320,6 +394,9 @@ where
                             &self.genesis_cid,
                             &self.network_sender_out,
                             cx_response_tx.clone(),
+                            &block_validation_tx,
+                            &block_validation_permits,
+                            &message_validation_request_tx,
                             &pubsub_block_str,
                             &pubsub_msg_str,).await;
                     },
@@ -335,7 +412,8 @@ where
                             bitswap_request_manager.clone(),
                             message,
                             &self.network_sender_out,
-                            &self.peer_manager).await;
+                            &self.peer_manager,
+                            &self.bootstrap_peers).await;
                     }
                     None => { break; }
                 },
@@ -351,6 +429,30 @@ where
                         }
                     }
                 },
+                block_validation_opt = block_validation_rx_stream.next() => {
+                    if let Some((message_id, source, validation)) = block_validation_opt {
+                        handle_block_validation(
+                            swarm_stream.get_mut(),
+                            message_id,
+                            source,
+                            validation,
+                            &self.network_sender_out,
+                            &self.peer_manager,
+                        ).await;
+                    }
+                },
+                message_validation_opt = message_validation_rx_stream.next() => {
+                    if let Some((message_id, source, validation)) = message_validation_opt {
+                        handle_message_validation(
+                            swarm_stream.get_mut(),
+                            message_id,
+                            source,
+                            validation,
+                            &self.network_sender_out,
+                            &self.peer_manager,
+                        ).await;
+                    }
+                },
                 bitswap_outbound_request_opt = bitswap_outbound_request_stream.next() => {
                     if let Some((peer, request)) = bitswap_outbound_request_opt {
                         let bitswap = &mut swarm_stream.get_mut().behaviour_mut().bitswap;
@@ -365,6 +467,14 @@ where
                 _ = bootstrap_peer_dialer_interval_stream.next() => {
                     dial_to_bootstrap_peers_if_needed(swarm_stream.get_mut(), &self.bootstrap_peers);
                 }
+                _ = peer_store_interval_stream.next() => {
+                    persist_peer_store(swarm_stream.get_mut(), &self.peer_manager, &self.cs);
+                }
+                _ = shutdown => {
+                    info!("Persisting the peer store before shutdown");
+                    persist_peer_store(swarm_stream.get_mut(), &self.peer_manager, &self.cs);
+                    break;
+                }
             };
         }
         Ok(())
@@ -381,6 +491,51 @@ where
     }
 }
 
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time before unix epoch")
+        .as_secs()
+}
+
+/// Resolves once the shutdown signal is raised or its sender is dropped, and
+/// never without a signal.
+async fn wait_for_shutdown(shutdown: Option<watch::Receiver<bool>>) {
+    match shutdown {
+        Some(mut shutdown) => {
+            let _ = shutdown.wait_for(|stop| *stop).await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Persists the addresses and scores of the useful connected peers.
+fn persist_peer_store<DB>(
+    swarm: &mut Swarm<ForestBehaviour>,
+    peer_manager: &PeerManager,
+    cs: &ChainStore<DB>,
+) {
+    let now = unix_now();
+    let mut addresses = swarm.behaviour().peer_addresses();
+    let current = peer_manager
+        .peer_scores()
+        .into_iter()
+        .filter(|(_, score)| *score > 0.0)
+        .filter_map(|(peer_id, score)| {
+            let addrs = addresses.remove(&peer_id)?.into_iter().collect();
+            Some(PeerRecord {
+                peer_id,
+                addrs,
+                score,
+                last_seen: now,
+            })
+        })
+        .collect();
+    if let Err(e) = persist_peers(cs.settings().as_ref(), current, now) {
+        warn!("Failed to persist peers: {e}");
+    }
+}
+
 fn dial_to_bootstrap_peers_if_needed(
     swarm: &mut Swarm<ForestBehaviour>,
     bootstrap_peers: &HashMap<PeerId, Multiaddr>,
@@ -428,6 +583,7 @@ async fn handle_network_message(
     message: NetworkMessage,
     network_sender_out: &Sender<NetworkEvent>,
     peer_manager: &Arc<PeerManager>,
+    bootstrap_peers: &HashMap<PeerId, Multiaddr>,
 ) {
     match message {
         NetworkMessage::PubsubMessage { topic, message } => {
@@ -475,6 +631,12 @@ async fn handle_network_message(
                 None,
             );
         }
+        NetworkMessage::Bootstrap => {
+            dial_to_bootstrap_peers_if_needed(swarm, bootstrap_peers);
+            if let Err(e) = swarm.behaviour_mut().bootstrap() {
+                warn!("Failed to bootstrap with Kademlia: {e}");
+            }
+        }
         NetworkMessage::JSONRPCRequest { method } => {
             match method {
                 NetRPCMethods::AddrsListen(response_channel) => {
@@ -555,6 +717,35 @@ async fn handle_network_message(
                     let nat_status = swarm.behaviour().discovery.nat_status();
                     response_channel.send_or_warn(nat_status);
                 }
+                NetRPCMethods::Reachability(response_channel) => {
+                    let discovery = &swarm.behaviour().discovery;
+                    let reachability = NetReachabilityResult {
+                        listen_addrs: swarm.listeners().map(|addr| addr.to_string()).collect(),
+                        external_addrs: swarm
+                            .external_addresses()
+                            .map(|addr| addr.to_string())
+                            .collect(),
+                        observed_addrs: discovery
+                            .reachability
+                            .observed_addrs()
+                            .into_iter()
+                            .map(|(addr, num_peers)| ObservedAddr {
+                                addr: addr.to_string(),
+                                num_peers,
+                            })
+                            .collect(),
+                        nat_status: discovery.nat_status().into(),
+                        num_inbound: discovery.reachability.num_inbound(),
+                        num_outbound: discovery.reachability.num_outbound(),
+                    };
+                    response_channel.send_or_warn(reachability);
+                }
+                NetRPCMethods::SetTargetPeerCount(target_peer_count) => {
+                    info!("Setting the target peer count to {target_peer_count}");
+                    swarm
+                        .behaviour_mut()
+                        .set_target_peer_count(target_peer_count);
+                }
             }
         }
     }
@@ -606,57 +797,158 @@ async fn handle_discovery_event(
     }
 }
 
-async fn handle_gossip_event(
+#[allow(clippy::too_many_arguments)]
+async fn handle_gossip_event<DB>(
+    swarm: &mut Swarm<ForestBehaviour>,
     e: gossipsub::Event,
+    cs: &Arc<ChainStore<DB>>,
+    peer_manager: &PeerManager,
+    bitswap_request_manager: &Arc<BitswapRequestManager>,
+    block_validation_tx: &Sender<(MessageId, PeerId, BlockValidation)>,
+    block_validation_permits: &Arc<Semaphore>,
+    message_validation_request_tx: &Sender<(MessageId, PeerId, Vec<u8>)>,
     network_sender_out: &Sender<NetworkEvent>,
     pubsub_block_str: &str,
     pubsub_msg_str: &str,
-) {
+) where
+    DB: Blockstore + BitswapStoreReadWrite + Sync + Send + 'static,
+{
     if let gossipsub::Event::Message {
         propagation_source: source,
         message,
-        message_id: _,
+        message_id,
     } = e
     {
         let topic = message.topic.as_str();
         let message = message.data;
         trace!("Got a Gossip Message from {:?}", source);
         if topic == pubsub_block_str {
-            match from_slice_with_fallback::<GossipBlock>(&message) {
-                Ok(b) => {
-                    emit_event(
-                        network_sender_out,
-                        NetworkEvent::PubsubMessage {
-                            message: PubsubMessage::Block(b),
-                        },
-                    )
-                    .await;
-                }
-                Err(e) => {
-                    warn!("Gossip Block from peer {source:?} could not be deserialized: {e}",);
-                }
-            }
+            // Blocks are validated asynchronously, and only propagated and
+            // processed once accepted.
+            let Ok(permit) = block_validation_permits.clone().try_acquire_owned() else {
+                let validation =
+                    BlockValidation::Ignore("too many blocks being validated".to_owned());
+                validation.record_metrics();
+                swarm.behaviour_mut().report_message_validation_result(
+                    &message_id,
+                    &source,
+                    validation.acceptance(),
+                );
+                debug!(
+                    "Gossip Block from peer {source:?} ignored: too many blocks being validated"
+                );
+                return;
+            };
+            let db = cs.blockstore().clone();
+            let fallback_state = *cs.heaviest_tipset().parent_state();
+            let bitswap_request_manager = bitswap_request_manager.clone();
+            let block_validation_tx = block_validation_tx.clone();
+            tokio::task::spawn(async move {
+                let validation =
+                    validate_gossip_block(db, bitswap_request_manager, fallback_state, message)
+                        .await;
+                drop(permit);
+                block_validation_tx.send_or_warn((message_id, source, validation));
+            });
         } else if topic == pubsub_msg_str {
-            match from_slice_with_fallback::<SignedMessage>(&message) {
-                Ok(m) => {
-                    emit_event(
-                        network_sender_out,
-                        NetworkEvent::PubsubMessage {
-                            message: PubsubMessage::Message(m),
-                        },
-                    )
-                    .await;
+            match message_validation_request_tx.try_send((message_id, source, message)) {
+                Ok(()) => {}
+                Err(flume::TrySendError::Full((message_id, source, _))) => {
+                    let reason = MessageIgnoreReason::QueueFull;
+                    let validation = MessageValidation::Ignore(reason);
+                    validation.record_metrics();
+                    swarm.behaviour_mut().report_message_validation_result(
+                        &message_id,
+                        &source,
+                        validation.acceptance(),
+                    );
+                    debug!("Gossip Message from peer {source:?} ignored: {reason}");
                 }
-                Err(e) => {
-                    warn!("Gossip Message from peer {source:?} could not be deserialized: {e}");
+                Err(flume::TrySendError::Disconnected(_)) => {
+                    warn!("Gossip message validation worker is gone");
                 }
             }
         } else {
+            swarm.behaviour_mut().report_message_validation_result(
+                &message_id,
+                &source,
+                MessageAcceptance::Ignore,
+            );
             warn!("Getting gossip messages from unknown topic: {topic}");
         }
     }
 }
 
+async fn handle_block_validation(
+    swarm: &mut Swarm<ForestBehaviour>,
+    message_id: MessageId,
+    source: PeerId,
+    validation: BlockValidation,
+    network_sender_out: &Sender<NetworkEvent>,
+    peer_manager: &PeerManager,
+) {
+    validation.record_metrics();
+    swarm.behaviour_mut().report_message_validation_result(
+        &message_id,
+        &source,
+        validation.acceptance(),
+    );
+    match validation {
+        BlockValidation::Accept(b) => {
+            peer_manager.log_gossip(&source);
+            // The relayed block is at least as recent as the head it announced
+            peer_manager.update_peer_head(source, TipsetKey::from(nunny::vec![*b.header.cid()]));
+            emit_event(
+                network_sender_out,
+                NetworkEvent::PubsubMessage {
+                    message: PubsubMessage::Block(b),
+                },
+            )
+            .await;
+        }
+        BlockValidation::Ignore(reason) => {
+            debug!("Gossip Block from peer {source:?} ignored: {reason}");
+        }
+        BlockValidation::Reject(reason) => {
+            warn!("Gossip Block from peer {source:?} rejected: {reason}");
+        }
+    }
+}
+
+async fn handle_message_validation(
+    swarm: &mut Swarm<ForestBehaviour>,
+    message_id: MessageId,
+    source: PeerId,
+    validation: MessageValidation,
+    network_sender_out: &Sender<NetworkEvent>,
+    peer_manager: &PeerManager,
+) {
+    validation.record_metrics();
+    swarm.behaviour_mut().report_message_validation_result(
+        &message_id,
+        &source,
+        validation.acceptance(),
+    );
+    match validation {
+        MessageValidation::Accept(m) => {
+            peer_manager.log_gossip(&source);
+            emit_event(
+                network_sender_out,
+                NetworkEvent::PubsubMessage {
+                    message: PubsubMessage::Message(m),
+                },
+            )
+            .await;
+        }
+        MessageValidation::Ignore(reason) => {
+            debug!("Gossip Message from peer {source:?} ignored: {reason}");
+        }
+        MessageValidation::Reject(reason) => {
+            debug!("Gossip Message from peer {source:?} rejected: {reason}");
+        }
+    }
+}
+
 async fn handle_hello_event(
     peer_info_map: &HashMap<PeerId, PeerInfo>,
     hello: &mut HelloBehaviour,
@@ -865,10 +1157,13 @@ async fn handle_forest_behaviour_event<DB>(
         request_response::ResponseChannel<ChainExchangeResponse>,
         ChainExchangeResponse,
     )>,
+    block_validation_tx: &Sender<(MessageId, PeerId, BlockValidation)>,
+    block_validation_permits: &Arc<Semaphore>,
+    message_validation_request_tx: &Sender<(MessageId, PeerId, Vec<u8>)>,
     pubsub_block_str: &str,
     pubsub_msg_str: &str,
 ) where
-    DB: Blockstore + BitswapStoreRead + Sync + Send + 'static,
+    DB: Blockstore + BitswapStoreReadWrite + Sync + Send + 'static,
 {
     match event {
         ForestBehaviourEvent::Discovery(discovery_out) => {
@@ -881,7 +1176,20 @@ async fn handle_forest_behaviour_event<DB>(
             .await
         }
         ForestBehaviourEvent::Gossipsub(e) => {
-            handle_gossip_event(e, network_sender_out, pubsub_block_str, pubsub_msg_str).await
+            handle_gossip_event(
+                swarm,
+                e,
+                db,
+                peer_manager,
+                bitswap_request_manager,
+                block_validation_tx,
+                block_validation_permits,
+                message_validation_request_tx,
+                network_sender_out,
+                pubsub_block_str,
+                pubsub_msg_str,
+            )
+            .await
         }
         ForestBehaviourEvent::Hello(rr_event) => {
             let behaviour_mut = swarm.behaviour_mut();
@@ -0,0 +1,61 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::*;
+use crate::shim::actors::market::DealLabel;
+
+/// Lotus renders labels as plain strings, see
+/// [`DealLabel::into_lotus_string`], and parses any string as a string label.
+/// Byte labels that are not valid UTF-8 have no string form, and are rendered
+/// as IPLD bytes, `{"/": {"bytes": "<base64>"}}`, so that they round-trip.
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+#[schemars(rename = "DealLabel")]
+pub enum DealLabelLotusJson {
+    String(String),
+    Bytes {
+        #[serde(rename = "/")]
+        bytes: DealLabelBytesLotusJson,
+    },
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DealLabelBytesLotusJson {
+    #[schemars(with = "LotusJson<Vec<u8>>")]
+    #[serde(with = "crate::lotus_json")]
+    bytes: Vec<u8>,
+}
+
+impl HasLotusJson for DealLabel {
+    type LotusJson = DealLabelLotusJson;
+
+    #[cfg(test)]
+    fn snapshots() -> Vec<(serde_json::Value, Self)> {
+        vec![
+            (json!("hello"), DealLabel::String("hello".into())),
+            (json!(""), DealLabel::String(String::new())),
+            (
+                json!({"/": {"bytes": "/wM="}}),
+                DealLabel::Bytes(vec![0xff, 3]),
+            ),
+        ]
+    }
+
+    fn into_lotus_json(self) -> Self::LotusJson {
+        match self.into_lotus_string() {
+            Ok(s) => DealLabelLotusJson::String(s),
+            Err(bytes) => DealLabelLotusJson::Bytes {
+                bytes: DealLabelBytesLotusJson { bytes },
+            },
+        }
+    }
+
+    fn from_lotus_json(lotus_json: Self::LotusJson) -> Self {
+        match lotus_json {
+            DealLabelLotusJson::String(s) => DealLabel::String(s),
+            DealLabelLotusJson::Bytes {
+                bytes: DealLabelBytesLotusJson { bytes },
+            } => DealLabel::Bytes(bytes),
+        }
+    }
+}
@@ -101,6 +101,9 @@
 //!   should use [`Into`] and [`LotusJson::into_inner`] calls
 //! - Use destructuring to ensure exhaustiveness
 //!
+//! For simple compound structs, [`lotus_json!`] generates all of the above from the struct
+//! definition.
+//!
 //! ### Optional fields
 //! It's not clear if optional fields should be serialized as `null` or not.
 //! See e.g `LotusJson<Receipt>`.
@@ -119,7 +122,6 @@
 //!
 //! # Future work
 //! - use [`proptest`](https://docs.rs/proptest/) to test the parser pipeline
-//! - use a derive macro for simple compound structs
 
 use crate::shim::actors::miner::DeadlineInfo;
 use derive_more::From;
@@ -222,6 +224,8 @@ decl_and_test!(
 mod allocation;
 mod beneficiary_term; // fil_actor_miner_state::v12::BeneficiaryTerm: !quickcheck::Arbitrary
 mod bit_field; //  fil_actors_shared::fvm_ipld_bitfield::BitField: !quickcheck::Arbitrary
+mod btree_map;
+mod deal_label; // byte labels are rendered as strings, like Lotus does
 mod hash_map;
 mod ipld; // NaN != NaN
 mod miner_info; // fil_actor_miner_state::v12::MinerInfo: !quickcheck::Arbitrary
@@ -230,10 +234,12 @@ mod nonempty; // can't make snapshots of generic type
 mod opt; // can't make snapshots of generic type
 mod pending_beneficiary_change; // fil_actor_miner_state::v12::PendingBeneficiaryChange: !quickcheck::Arbitrary
 mod power_claim; // actors::power::Claim: !quickcheck::Arbitrary
+mod power_pair; // fil_actor_miner_state::v12::PowerPair: !quickcheck::Arbitrary
 mod raw_bytes; // fvm_ipld_encoding::RawBytes: !quickcheck::Arbitrary
 mod receipt; // shim type roundtrip is wrong - see module
 mod vec; // can't make snapshots of generic type
 mod verifreg_claim;
+mod worker_key_change; // fil_actor_miner_state::v12::WorkerKeyChange: !quickcheck::Arbitrary
 
 pub use vec::*;
 
@@ -501,6 +507,97 @@ macro_rules! lotus_json_with_self {
 }
 pub(crate) use lotus_json_with_self;
 
+/// Declares a compound struct along with its [`HasLotusJson`] implementation.
+///
+/// The JSON type is a generated struct of the same name, with every field wrapped
+/// in [`LotusJson`] and renamed to `PascalCase`. This gives e.g [`Cid`](::cid::Cid),
+/// [`TokenAmount`](crate::shim::econ::TokenAmount), `BitField` and
+/// [`Address`](crate::shim::address::Address) fields their lotus representation.
+///
+/// Field doc comments are copied to the JSON struct. Field `#[serde(...)]` attributes
+/// MUST follow the doc comments, and only apply to the JSON struct.
+///
+/// The trailing `snapshots` block is the body of [`HasLotusJson::snapshots`], and is only
+/// compiled for tests. As for out-of-module implementations, you MUST call
+/// [`assert_all_snapshots`] and, if the struct is [`quickcheck::Arbitrary`],
+/// [`assert_unchanged_via_json`] in the defining module.
+///
+/// ```rust,ignore
+/// lotus_json! {
+///     #[derive(Debug, Clone, PartialEq)]
+///     pub struct SectorLocation {
+///         pub deadline: u64,
+///         pub partition: u64,
+///     }
+///     snapshots {
+///         vec![(
+///             serde_json::json!({"Deadline": 1, "Partition": 2}),
+///             SectorLocation { deadline: 1, partition: 2 },
+///         )]
+///     }
+/// }
+/// ```
+macro_rules! lotus_json {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $(
+                $(#[doc = $doc:expr])*
+                $(#[serde($($serde:tt)*)])*
+                $field_vis:vis $field:ident: $ty:ty
+            ),* $(,)?
+        }
+        snapshots $snapshots:block
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $(
+                $(#[doc = $doc])*
+                $field_vis $field: $ty,
+            )*
+        }
+
+        ::paste::paste! {
+            mod [<$name:snake _lotus_json>] {
+                #[allow(unused_imports)]
+                use super::*;
+
+                #[derive(::serde::Serialize, ::serde::Deserialize, ::schemars::JsonSchema)]
+                #[serde(rename_all = "PascalCase")]
+                pub struct $name {
+                    $(
+                        $(#[doc = $doc])*
+                        $(#[serde($($serde)*)])*
+                        pub(super) $field: $crate::lotus_json::LotusJson<$ty>,
+                    )*
+                }
+            }
+
+            impl $crate::lotus_json::HasLotusJson for $name {
+                type LotusJson = [<$name:snake _lotus_json>]::$name;
+
+                #[cfg(test)]
+                fn snapshots() -> Vec<(serde_json::Value, Self)> $snapshots
+
+                fn into_lotus_json(self) -> Self::LotusJson {
+                    let Self { $($field),* } = self;
+                    Self::LotusJson {
+                        $($field: $crate::lotus_json::LotusJson($field)),*
+                    }
+                }
+
+                fn from_lotus_json(lotus_json: Self::LotusJson) -> Self {
+                    let Self::LotusJson { $($field),* } = lotus_json;
+                    Self {
+                        $($field: $field.into_inner()),*
+                    }
+                }
+            }
+        }
+    };
+}
+pub(crate) use lotus_json;
+
 lotus_json_with_self!(
     u32,
     u64,
@@ -0,0 +1,56 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::*;
+
+use fil_actor_miner_state::v12::PowerPair;
+use num::BigInt;
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+#[schemars(rename = "PowerPair")]
+pub struct PowerPairLotusJson {
+    #[schemars(with = "LotusJson<BigInt>")]
+    #[serde(with = "crate::lotus_json")]
+    pub raw: BigInt,
+    #[schemars(with = "LotusJson<BigInt>")]
+    #[serde(rename = "QA", with = "crate::lotus_json")]
+    pub qa: BigInt,
+}
+
+impl HasLotusJson for PowerPair {
+    type LotusJson = PowerPairLotusJson;
+
+    #[cfg(test)]
+    fn snapshots() -> Vec<(serde_json::Value, Self)> {
+        vec![(
+            json!({
+                "Raw": "34359738368",
+                "QA": "343597383680",
+            }),
+            Self {
+                raw: BigInt::from(34359738368_u64),
+                qa: BigInt::from(343597383680_u64),
+            },
+        )]
+    }
+
+    fn into_lotus_json(self) -> Self::LotusJson {
+        PowerPairLotusJson {
+            raw: self.raw,
+            qa: self.qa,
+        }
+    }
+
+    fn from_lotus_json(lotus_json: Self::LotusJson) -> Self {
+        Self {
+            raw: lotus_json.raw,
+            qa: lotus_json.qa,
+        }
+    }
+}
+
+#[test]
+fn snapshots() {
+    assert_all_snapshots::<PowerPair>();
+}
@@ -0,0 +1,54 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::*;
+
+use crate::shim::{address::Address, clock::ChainEpoch};
+use fil_actor_miner_state::v12::WorkerKeyChange;
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+#[schemars(rename = "WorkerKeyChange")]
+pub struct WorkerKeyChangeLotusJson {
+    #[schemars(with = "LotusJson<Address>")]
+    #[serde(with = "crate::lotus_json")]
+    pub new_worker: Address,
+    pub effective_at: ChainEpoch,
+}
+
+impl HasLotusJson for WorkerKeyChange {
+    type LotusJson = WorkerKeyChangeLotusJson;
+
+    #[cfg(test)]
+    fn snapshots() -> Vec<(serde_json::Value, Self)> {
+        vec![(
+            json!({
+                "NewWorker": "f01002",
+                "EffectiveAt": 2000,
+            }),
+            Self {
+                new_worker: fvm_shared4::address::Address::new_id(1002),
+                effective_at: 2000,
+            },
+        )]
+    }
+
+    fn into_lotus_json(self) -> Self::LotusJson {
+        WorkerKeyChangeLotusJson {
+            new_worker: self.new_worker.into(),
+            effective_at: self.effective_at,
+        }
+    }
+
+    fn from_lotus_json(lotus_json: Self::LotusJson) -> Self {
+        Self {
+            new_worker: lotus_json.new_worker.into(),
+            effective_at: lotus_json.effective_at,
+        }
+    }
+}
+
+#[test]
+fn snapshots() {
+    assert_all_snapshots::<WorkerKeyChange>();
+}
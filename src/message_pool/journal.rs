@@ -0,0 +1,206 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Persistent journal of messages published through this node. Entries survive
+//! restarts so that pending local messages can be re-added to the pool and
+//! rebroadcast until they land on chain.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::db::{
+    setting_keys::MPOOL_LOCAL_MESSAGE_KEY_PREFIX, SettingsStore, SettingsStoreExt as _,
+};
+use crate::lotus_json::{lotus_json_with_self, LotusJson};
+use crate::message::{Message as _, SignedMessage};
+use ahash::{HashMap, HashMapExt as _};
+use cid::Cid;
+use itertools::Itertools as _;
+use parking_lot::{Mutex, RwLock as SyncRwLock};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Minimum time between two broadcasts of the same pending local message.
+pub const LOCAL_MESSAGE_REBROADCAST_THRESHOLD: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum LocalMessageStatus {
+    /// Waiting for inclusion, rebroadcast periodically
+    Pending,
+    /// The sender sequence was used by a different message
+    Replaced,
+}
+
+/// A message published through this node, as recorded in the journal.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct LocalMessage {
+    #[serde(with = "crate::lotus_json")]
+    #[schemars(with = "LotusJson<SignedMessage>")]
+    pub message: SignedMessage,
+    /// Unix timestamp (seconds) of the first publication
+    pub published_at: u64,
+    pub status: LocalMessageStatus,
+}
+
+lotus_json_with_self!(LocalMessage);
+
+/// Journal of local messages, persisted to the settings store on every change.
+/// Each entry is stored under its own key, so that a change only writes the
+/// entry it affects.
+pub struct LocalMessageJournal {
+    store: Arc<dyn SettingsStore + Send + Sync>,
+    entries: SyncRwLock<HashMap<Cid, LocalMessage>>,
+    /// Last broadcast of each entry since startup. Entries loaded from the
+    /// store are missing here and therefore due for rebroadcast immediately.
+    last_broadcast: Mutex<HashMap<Cid, Instant>>,
+}
+
+impl LocalMessageJournal {
+    /// Loads the journal from the store. Replaced entries have been reported
+    /// during the previous run and are dropped.
+    pub fn load(store: Arc<dyn SettingsStore + Send + Sync>) -> anyhow::Result<Self> {
+        let mut entries = HashMap::new();
+        for key in store.setting_keys()? {
+            if !key.starts_with(MPOOL_LOCAL_MESSAGE_KEY_PREFIX) {
+                continue;
+            }
+            match store.read_obj::<LocalMessage>(&key)? {
+                Some(entry) if entry.status == LocalMessageStatus::Pending => {
+                    entries.insert(entry.message.cid(), entry);
+                }
+                _ => store.delete(&key)?,
+            }
+        }
+        Ok(Self {
+            store,
+            entries: SyncRwLock::new(entries),
+            last_broadcast: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Records a freshly published message.
+    pub fn record(&self, message: SignedMessage) -> anyhow::Result<()> {
+        let cid = message.cid();
+        self.last_broadcast.lock().insert(cid, Instant::now());
+        let mut entries = self.entries.write();
+        if entries.contains_key(&cid) {
+            return Ok(());
+        }
+        let entry = LocalMessage {
+            message,
+            published_at: chrono::Utc::now().timestamp() as u64,
+            status: LocalMessageStatus::Pending,
+        };
+        self.store.write_obj(&local_message_key(&cid), &entry)?;
+        entries.insert(cid, entry);
+        Ok(())
+    }
+
+    /// Returns all journal entries, ordered by sender and sequence.
+    pub fn entries(&self) -> Vec<LocalMessage> {
+        self.entries
+            .read()
+            .values()
+            .cloned()
+            .sorted_by_key(|entry| (entry.message.from(), entry.message.sequence()))
+            .collect()
+    }
+
+    /// Returns the messages still waiting for inclusion.
+    pub fn pending(&self) -> Vec<SignedMessage> {
+        self.entries
+            .read()
+            .values()
+            .filter(|entry| entry.status == LocalMessageStatus::Pending)
+            .map(|entry| entry.message.clone())
+            .collect()
+    }
+
+    /// Returns `true` if the pending message has not been broadcast for at
+    /// least `threshold`.
+    pub fn is_due_for_rebroadcast(&self, cid: &Cid, threshold: Duration) -> bool {
+        self.last_broadcast
+            .lock()
+            .get(cid)
+            .is_none_or(|last| last.elapsed() >= threshold)
+    }
+
+    pub fn mark_broadcast(&self, cid: Cid) {
+        self.last_broadcast.lock().insert(cid, Instant::now());
+    }
+
+    /// Removes an entry whose receipt has been found.
+    pub fn clear(&self, cid: &Cid) -> anyhow::Result<()> {
+        self.entries.write().remove(cid);
+        self.last_broadcast.lock().remove(cid);
+        self.store.delete(&local_message_key(cid))
+    }
+
+    /// Marks an entry whose sequence has been used by a different message.
+    pub fn mark_replaced(&self, cid: &Cid) -> anyhow::Result<()> {
+        self.last_broadcast.lock().remove(cid);
+        if let Some(entry) = self.entries.write().get_mut(cid) {
+            entry.status = LocalMessageStatus::Replaced;
+            self.store.write_obj(&local_message_key(cid), &*entry)?;
+        }
+        Ok(())
+    }
+}
+
+fn local_message_key(cid: &Cid) -> String {
+    format!("{MPOOL_LOCAL_MESSAGE_KEY_PREFIX}{cid}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MemoryDB;
+    use crate::key_management::{KeyStore, KeyStoreConfig, Wallet};
+    use crate::message_pool::tests::create_smsg;
+    use crate::shim::crypto::SignatureType;
+
+    #[test]
+    fn test_journal_persistence() {
+        let mut wallet = Wallet::new(KeyStore::new(KeyStoreConfig::Memory).unwrap());
+        let sender = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let target = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let store = Arc::new(MemoryDB::default());
+
+        let journal = LocalMessageJournal::load(store.clone()).unwrap();
+        let included = create_smsg(&target, &sender, &mut wallet, 0, 1000000, 1);
+        let replaced = create_smsg(&target, &sender, &mut wallet, 1, 1000000, 1);
+        let pending = create_smsg(&target, &sender, &mut wallet, 2, 1000000, 1);
+        for msg in [&included, &replaced, &pending] {
+            journal.record(msg.clone()).unwrap();
+        }
+        journal.clear(&included.cid()).unwrap();
+        journal.mark_replaced(&replaced.cid()).unwrap();
+        assert!(
+            !journal.is_due_for_rebroadcast(&pending.cid(), LOCAL_MESSAGE_REBROADCAST_THRESHOLD)
+        );
+        assert_eq!(
+            journal
+                .entries()
+                .iter()
+                .map(|entry| (entry.message.sequence(), entry.status))
+                .collect_vec(),
+            vec![
+                (1, LocalMessageStatus::Replaced),
+                (2, LocalMessageStatus::Pending)
+            ]
+        );
+
+        let reloaded = LocalMessageJournal::load(store.clone()).unwrap();
+        assert_eq!(reloaded.pending(), vec![pending.clone()]);
+        assert_eq!(reloaded.entries().len(), 1);
+        // The replaced entry is dropped from the store as well
+        assert_eq!(
+            store.setting_keys().unwrap(),
+            vec![local_message_key(&pending.cid())]
+        );
+        assert!(
+            reloaded.is_due_for_rebroadcast(&pending.cid(), LOCAL_MESSAGE_REBROADCAST_THRESHOLD)
+        );
+    }
+}
@@ -3,15 +3,18 @@
 mod block_prob;
 mod config;
 mod errors;
+mod journal;
 mod msg_chain;
 mod msgpool;
 
 pub use self::{
     config::*,
     errors::*,
+    journal::*,
     msgpool::{
-        msg_pool::MessagePool,
+        msg_pool::{MessageImport, MessageImportStatus, MessagePool, PendingMessage},
         provider::{MpoolRpcProvider, Provider},
+        stat::{AgeBucket, MpoolStat, SenderStat},
         *,
     },
 };
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use once_cell::sync::Lazy;
-use prometheus_client::metrics::gauge::Gauge;
+use prometheus_client::metrics::{counter::Counter, gauge::Gauge};
 
 pub static MPOOL_MESSAGE_TOTAL: Lazy<Gauge> = Lazy::new(|| {
     let metric = Gauge::default();
@@ -13,3 +13,66 @@ pub static MPOOL_MESSAGE_TOTAL: Lazy<Gauge> = Lazy::new(|| {
     );
     metric
 });
+pub static MPOOL_REBROADCAST_TOTAL: Lazy<Counter> = Lazy::new(|| {
+    let metric = Counter::default();
+    crate::metrics::default_registry().register(
+        "mpool_rebroadcast",
+        "Total number of stuck pending messages rebroadcast",
+        metric.clone(),
+    );
+    metric
+});
+pub static MPOOL_STALE_MESSAGES: Lazy<Gauge> = Lazy::new(|| {
+    let metric = Gauge::default();
+    crate::metrics::default_registry().register(
+        "mpool_stale_messages",
+        "Number of pending messages already included on chain, as of the last head change",
+        metric.clone(),
+    );
+    metric
+});
+pub static MPOOL_NONCE_TOO_LOW_MESSAGES: Lazy<Gauge> = Lazy::new(|| {
+    let metric = Gauge::default();
+    crate::metrics::default_registry().register(
+        "mpool_nonce_too_low_messages",
+        "Number of pending messages with an already used nonce, as of the last head change",
+        metric.clone(),
+    );
+    metric
+});
+pub static MPOOL_NONCE_GAPS: Lazy<Gauge> = Lazy::new(|| {
+    let metric = Gauge::default();
+    crate::metrics::default_registry().register(
+        "mpool_nonce_gaps",
+        "Number of gaps in the nonces of the pending messages, as of the last head change",
+        metric.clone(),
+    );
+    metric
+});
+pub static MPOOL_SENDERS: Lazy<Gauge> = Lazy::new(|| {
+    let metric = Gauge::default();
+    crate::metrics::default_registry().register(
+        "mpool_senders",
+        "Number of senders with pending messages, as of the last head change",
+        metric.clone(),
+    );
+    metric
+});
+pub static MPOOL_MAX_SENDER_PENDING: Lazy<Gauge> = Lazy::new(|| {
+    let metric = Gauge::default();
+    crate::metrics::default_registry().register(
+        "mpool_max_sender_pending",
+        "Number of pending messages of the sender with the most, as of the last head change",
+        metric.clone(),
+    );
+    metric
+});
+pub static MPOOL_BELOW_BASE_FEE_MESSAGES: Lazy<Gauge> = Lazy::new(|| {
+    let metric = Gauge::default();
+    crate::metrics::default_registry().register(
+        "mpool_below_base_fee_messages",
+        "Number of pending messages with a fee cap below the base fee of the head, as of the last head change",
+        metric.clone(),
+    );
+    metric
+});
@@ -5,11 +5,12 @@ pub(in crate::message_pool) mod metrics;
 pub(in crate::message_pool) mod msg_pool;
 pub(in crate::message_pool) mod provider;
 pub mod selection;
+pub(in crate::message_pool) mod stat;
 #[cfg(test)]
 pub mod test_provider;
 pub(in crate::message_pool) mod utils;
 
-use std::{borrow::BorrowMut, cmp::Ordering, sync::Arc};
+use std::{borrow::BorrowMut, cmp::Ordering, sync::Arc, time::Duration};
 
 use crate::blocks::Tipset;
 use crate::libp2p::{NetworkMessage, Topic, PUBSUB_MSG_STR};
@@ -21,11 +22,12 @@ use cid::Cid;
 use fvm_ipld_encoding::to_vec;
 use lru::LruCache;
 use parking_lot::{Mutex, RwLock as SyncRwLock};
-use tracing::error;
+use tracing::{debug, error, warn};
 use utils::{get_base_fee_lower_bound, recover_sig};
 
 use super::errors::Error;
 use crate::message_pool::{
+    journal::LocalMessageJournal,
     msg_chain::{create_message_chains, Chains},
     msg_pool::{add_helper, remove, MsgSet},
     provider::Provider,
@@ -107,6 +109,43 @@ where
     Ok(())
 }
 
+/// Resolves the journal entries of local messages whose sequence has been used
+/// on chain and rebroadcasts the pending ones that have not been broadcast for
+/// at least `threshold`.
+async fn rebroadcast_local_messages<T>(
+    api: &T,
+    network_sender: &flume::Sender<NetworkMessage>,
+    network_name: &str,
+    cur_tipset: &Mutex<Arc<Tipset>>,
+    journal: &LocalMessageJournal,
+    threshold: Duration,
+) -> Result<(), Error>
+where
+    T: Provider,
+{
+    let ts = cur_tipset.lock().clone();
+    for msg in journal.pending() {
+        let cid = msg.cid();
+        if get_state_sequence(api, &msg.from(), &ts)? > msg.sequence() {
+            if api.has_receipt(cid).await {
+                journal.clear(&cid)?;
+            } else {
+                journal.mark_replaced(&cid)?;
+            }
+        } else if journal.is_due_for_rebroadcast(&cid, threshold) {
+            network_sender
+                .send_async(NetworkMessage::PubsubMessage {
+                    topic: Topic::new(format!("{PUBSUB_MSG_STR}/{network_name}")),
+                    message: to_vec(&msg)?,
+                })
+                .await
+                .map_err(|_| Error::Other("Network receiver dropped".to_string()))?;
+            journal.mark_broadcast(cid);
+        }
+    }
+    Ok(())
+}
+
 /// Select messages from the mempool to be included in the next block that
 /// builds on a given base tipset. The messages should be eligible for inclusion
 /// based on their sequences and the overall number of them should observe block
@@ -277,9 +316,51 @@ where
             }
         }
     }
+    stat::record_sender_metrics(
+        pending.read().values(),
+        &cur_tipset.lock().block_headers().first().parent_base_fee,
+    );
     Ok(())
 }
 
+/// Records the number of pending messages out of line with the head in the
+/// metrics. The messages are left in the pool, a failed check is logged only.
+/// The check reads the state of every sender, so it runs periodically rather
+/// than on each head change.
+pub(in crate::message_pool) fn check_tip_alignment<T>(
+    api: &T,
+    pending: &SyncRwLock<HashMap<Address, MsgSet>>,
+    head: &Arc<Tipset>,
+) where
+    T: Provider,
+{
+    let msgs: Vec<SignedMessage> = pending
+        .read()
+        .values()
+        .flat_map(|mset| mset.msgs.values().cloned())
+        .collect();
+    match api.tip_alignment(&msgs, head) {
+        Ok(report) => {
+            metrics::MPOOL_STALE_MESSAGES.set(report.stale.len() as i64);
+            metrics::MPOOL_NONCE_TOO_LOW_MESSAGES.set(report.nonce_too_low.len() as i64);
+            metrics::MPOOL_NONCE_GAPS.set(report.nonce_gap.len() as i64);
+            if !report.is_aligned() {
+                debug!(
+                    "Message pool out of line with head {}: {} stale, {} with a nonce too low, {} nonce gaps",
+                    head.key(),
+                    report.stale.len(),
+                    report.nonce_too_low.len(),
+                    report.nonce_gap.len()
+                );
+            }
+        }
+        Err(e) => warn!(
+            "Failed to check the message pool against head {}: {e}",
+            head.key()
+        ),
+    }
+}
+
 /// This is a helper function for `head_change`. This method will remove a
 /// sequence for a from address from the messages selected by priority hash-map.
 /// It also removes the 'from' address and sequence from the `MessagePool`.
@@ -322,6 +403,8 @@ pub mod tests {
 
     use crate::blocks::Tipset;
     use crate::key_management::{KeyStore, KeyStoreConfig, Wallet};
+    use crate::libp2p::{MessageRejectReason, MessageValidation};
+    use crate::lotus_json::{assert_all_snapshots, assert_unchanged_via_json};
     use crate::message::SignedMessage;
     use crate::networks::ChainConfig;
     use crate::shim::{
@@ -331,13 +414,17 @@ pub mod tests {
         message::{Message, Message_v3},
     };
     use num_traits::Zero;
+    use quickcheck_macros::quickcheck;
     use test_provider::*;
     use tokio::task::JoinSet;
 
     use super::*;
     use crate::message_pool::{
+        journal::{LocalMessageStatus, LOCAL_MESSAGE_REBROADCAST_THRESHOLD},
         msg_chain::{create_message_chains, Chains},
-        msg_pool::MessagePool,
+        msg_pool::{
+            next_sequence, MessageImport, MessageImportStatus, MessagePool, PendingMessage,
+        },
     };
 
     #[tokio::test]
@@ -376,6 +463,352 @@ pub mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_pending_sequence() {
+        let keystore = KeyStore::new(KeyStoreConfig::Memory).unwrap();
+        let mut wallet = Wallet::new(keystore);
+        let sender = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let target = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let tma = TestApi::default();
+        tma.set_state_sequence(&sender, 0);
+
+        let (tx, _rx) = flume::bounded(50);
+        let mut services = JoinSet::new();
+        let mpool = MessagePool::new(
+            tma,
+            "mptest".to_string(),
+            tx,
+            Default::default(),
+            Arc::default(),
+            &mut services,
+        )
+        .unwrap();
+        // Empty pool
+        assert_eq!(mpool.get_nonce(&sender).unwrap(), 0);
+        assert_eq!(mpool.next_sequence(&sender, 0), 0);
+        assert_eq!(mpool.next_sequence(&sender, 5), 5);
+
+        for i in 0..3 {
+            let msg = create_smsg(&target, &sender, wallet.borrow_mut(), i, 1000000, 1);
+            mpool.add(msg).unwrap();
+        }
+        assert_eq!(mpool.next_sequence(&sender, 0), 3);
+        assert_eq!(mpool.next_sequence(&target, 0), 0);
+        // Messages already applied on chain
+        assert_eq!(mpool.next_sequence(&sender, 7), 7);
+
+        // A gap in the pending sequences is not filled
+        let msg = create_smsg(&target, &sender, wallet.borrow_mut(), 5, 1000000, 1);
+        mpool.add(msg).unwrap();
+        assert_eq!(mpool.next_sequence(&sender, 0), 6);
+        assert_eq!(mpool.get_nonce(&sender).unwrap(), 6);
+    }
+
+    #[test]
+    fn test_next_sequence() {
+        assert_eq!(next_sequence(0, []), 0);
+        assert_eq!(next_sequence(4, []), 4);
+        assert_eq!(next_sequence(0, [0, 1, 2]), 3);
+        assert_eq!(next_sequence(0, [2, 0, 7]), 8);
+        assert_eq!(next_sequence(10, [2, 3]), 10);
+        assert_eq!(next_sequence(3, [3]), 4);
+    }
+
+    #[tokio::test]
+    async fn test_pending_prior_messages() {
+        use crate::message::ChainMessage;
+        use crate::state_manager::{pending_prior_messages, MAX_PRIOR_MESSAGES};
+
+        let keystore = KeyStore::new(KeyStoreConfig::Memory).unwrap();
+        let mut wallet = Wallet::new(keystore);
+        let sender = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let target = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let tma = TestApi::default();
+        tma.set_state_sequence(&sender, 0);
+
+        let (tx, _rx) = flume::bounded(50);
+        let mut services = JoinSet::new();
+        let mpool = MessagePool::new(
+            tma,
+            "mptest".to_string(),
+            tx,
+            Default::default(),
+            Arc::default(),
+            &mut services,
+        )
+        .unwrap();
+        let msgs = (0..2)
+            .map(|i| create_smsg(&target, &sender, wallet.borrow_mut(), i, 1000000, 1))
+            .collect::<Vec<_>>();
+        for msg in msgs.iter().rev() {
+            mpool.add(msg.clone()).unwrap();
+        }
+
+        let priors = pending_prior_messages(&mpool, &sender, MAX_PRIOR_MESSAGES);
+        assert_eq!(
+            priors.iter().map(ChainMessage::cid).collect::<Vec<_>>(),
+            msgs.iter().map(SignedMessage::cid).collect::<Vec<_>>()
+        );
+        // The estimated message follows the pending ones
+        let last_pending = priors.last().unwrap().sequence();
+        assert_eq!(mpool.next_sequence(&sender, 0), last_pending + 1);
+
+        // The number of applied priors is capped
+        let priors = pending_prior_messages(&mpool, &sender, 1);
+        assert_eq!(
+            priors.iter().map(ChainMessage::cid).collect::<Vec<_>>(),
+            vec![msgs[0].cid()]
+        );
+        assert!(pending_prior_messages(&mpool, &target, MAX_PRIOR_MESSAGES).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rebroadcast_stuck_messages() {
+        let keystore = KeyStore::new(KeyStoreConfig::Memory).unwrap();
+        let mut wallet = Wallet::new(keystore);
+        let sender = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let target = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let tma = TestApi::default();
+        tma.set_state_sequence(&sender, 0);
+
+        let (tx, rx) = flume::bounded(50);
+        let mut services = JoinSet::new();
+        let mpool = MessagePool::new(
+            tma,
+            "mptest".to_string(),
+            tx,
+            Default::default(),
+            Arc::default(),
+            &mut services,
+        )
+        .unwrap();
+        services.abort_all();
+        let msg = create_smsg(&target, &sender, wallet.borrow_mut(), 0, 1000000, 1);
+        mpool.add(msg.clone()).unwrap();
+        assert_eq!(mpool.rebroadcast_stuck_messages(60), 0);
+
+        // Pretend the message was added two minutes ago.
+        let two_minutes_ago = std::time::Instant::now() - Duration::from_secs(120);
+        mpool
+            .pending
+            .write()
+            .get_mut(&sender)
+            .unwrap()
+            .added_at
+            .insert(0, two_minutes_ago);
+        assert_eq!(mpool.rebroadcast_stuck_messages(60), 1);
+
+        let broadcast: Vec<_> = rx.drain().collect();
+        assert_eq!(broadcast.len(), 1);
+        assert!(matches!(
+            &broadcast[0],
+            NetworkMessage::PubsubMessage { message, .. } if *message == to_vec(&msg).unwrap()
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_pending_for_address() {
+        let keystore = KeyStore::new(KeyStoreConfig::Memory).unwrap();
+        let mut wallet = Wallet::new(keystore);
+        let sender = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let target = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let tma = TestApi::default();
+        tma.set_state_sequence(&sender, 0);
+
+        let (tx, _rx) = flume::bounded(50);
+        let mut services = JoinSet::new();
+        let mpool = MessagePool::new(
+            tma,
+            "mptest".to_string(),
+            tx,
+            Default::default(),
+            Arc::default(),
+            &mut services,
+        )
+        .unwrap();
+        assert!(mpool.get_pending_for_address(&sender).is_empty());
+        assert!(mpool
+            .get_pending_gaps_for_address(&sender)
+            .unwrap()
+            .is_empty());
+
+        for i in [5, 1, 3] {
+            let msg = create_smsg(&target, &sender, wallet.borrow_mut(), i, 1000000, 1);
+            mpool.add(msg).unwrap();
+        }
+        // Sequence 0 is missing before the first pending message
+        let pending = mpool.get_pending_gaps_for_address(&sender).unwrap();
+        assert_eq!(
+            pending
+                .iter()
+                .map(|p| (p.message.sequence(), p.missing_before))
+                .collect::<Vec<_>>(),
+            vec![(1, 1), (3, 1), (5, 1)]
+        );
+        assert_eq!(
+            PendingMessage::annotate_gaps(1, mpool.get_pending_for_address(&sender))
+                .iter()
+                .map(|p| p.missing_before)
+                .collect::<Vec<_>>(),
+            vec![0, 1, 1]
+        );
+    }
+
+    fn gossip_test_mpool(
+        tma: TestApi,
+        services: &mut JoinSet<anyhow::Result<()>>,
+    ) -> MessagePool<TestApi> {
+        let (tx, _rx) = flume::bounded(50);
+        MessagePool::new(
+            tma,
+            "mptest".to_string(),
+            tx,
+            Default::default(),
+            Arc::default(),
+            services,
+        )
+        .unwrap()
+    }
+
+    fn assert_gossip_rejected(validation: MessageValidation, expected: MessageRejectReason) {
+        assert!(
+            matches!(validation, MessageValidation::Reject(reason) if reason == expected),
+            "expected {expected}, got {validation:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_gossip_message_sequence_too_low() {
+        let mut wallet = Wallet::new(KeyStore::new(KeyStoreConfig::Memory).unwrap());
+        let sender = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let target = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let tma = TestApi::default();
+        tma.set_state_sequence(&sender, 2);
+        let mut services = JoinSet::new();
+        let mpool = gossip_test_mpool(tma, &mut services);
+
+        let msg = create_smsg(&target, &sender, &mut wallet, 1, 1000000, 1);
+        assert_gossip_rejected(
+            mpool.validate_gossip_message(msg),
+            MessageRejectReason::SequenceTooLow,
+        );
+        let msg = create_smsg(&target, &sender, &mut wallet, 2, 1000000, 1);
+        assert!(matches!(
+            mpool.validate_gossip_message(msg),
+            MessageValidation::Accept(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_gossip_message_insufficient_balance() {
+        let mut wallet = Wallet::new(KeyStore::new(KeyStoreConfig::Memory).unwrap());
+        let sender = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let target = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let msgs = (0..3)
+            .map(|i| create_smsg(&target, &sender, &mut wallet, i, 1000000, 1))
+            .collect::<Vec<_>>();
+        let tma = TestApi::default();
+        tma.set_state_sequence(&sender, 0);
+        // Enough for two of the messages only
+        tma.set_state_balance_raw(&sender, msgs[0].required_funds() * 2u64);
+        let mut services = JoinSet::new();
+        let mpool = gossip_test_mpool(tma, &mut services);
+
+        mpool.add(msgs[0].clone()).unwrap();
+        mpool.add(msgs[1].clone()).unwrap();
+        assert_gossip_rejected(
+            mpool.validate_gossip_message(msgs[2].clone()),
+            MessageRejectReason::InsufficientBalance,
+        );
+        // Replacing a pending message doesn't add to its spend
+        let replacement = create_smsg(&target, &sender, &mut wallet, 1, 1000000, 2);
+        assert!(matches!(
+            mpool.validate_gossip_message(replacement),
+            MessageValidation::Accept(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_gossip_message_too_many_pending() {
+        let mut wallet = Wallet::new(KeyStore::new(KeyStoreConfig::Memory).unwrap());
+        let sender = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let target = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let tma = TestApi::default();
+        tma.set_state_sequence(&sender, 0);
+        let mut services = JoinSet::new();
+        let mpool = gossip_test_mpool(tma, &mut services);
+
+        let max_pending = mpool.api.max_untrusted_actor_pending_messages();
+        for i in 0..max_pending {
+            let msg = create_smsg(&target, &sender, &mut wallet, i, 1000000, 1);
+            mpool.add(msg).unwrap();
+        }
+        let msg = create_smsg(&target, &sender, &mut wallet, max_pending, 1000000, 1);
+        assert_gossip_rejected(
+            mpool.validate_gossip_message(msg),
+            MessageRejectReason::TooManyPending,
+        );
+        let replacement = create_smsg(&target, &sender, &mut wallet, max_pending - 1, 1000000, 2);
+        assert!(matches!(
+            mpool.validate_gossip_message(replacement),
+            MessageValidation::Accept(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_gossip_sender_state_evicted_on_head_change() {
+        let mut wallet = Wallet::new(KeyStore::new(KeyStoreConfig::Memory).unwrap());
+        let sender = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let target = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let tma = TestApi::default();
+        tma.set_state_sequence(&sender, 0);
+        let mut services = JoinSet::new();
+        let mpool = gossip_test_mpool(tma, &mut services);
+
+        let msg = create_smsg(&target, &sender, &mut wallet, 0, 1000000, 1);
+        assert!(matches!(
+            mpool.validate_gossip_message(msg.clone()),
+            MessageValidation::Accept(_)
+        ));
+        // The sender state is cached until the head changes
+        mpool.api.set_state_sequence(&sender, 1);
+        assert!(matches!(
+            mpool.validate_gossip_message(msg.clone()),
+            MessageValidation::Accept(_)
+        ));
+        assert_eq!(mpool.sender_states.lock().len(), 1);
+
+        mpool
+            .api
+            .set_heaviest_tipset(Arc::new(Tipset::from(mock_block(1, 2))));
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !mpool.sender_states.lock().is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_gossip_rejected(
+            mpool.validate_gossip_message(msg),
+            MessageRejectReason::SequenceTooLow,
+        );
+    }
+
+    #[test]
+    fn test_pending_message_snapshots() {
+        assert_all_snapshots::<PendingMessage>();
+    }
+
+    #[test]
+    fn test_message_import_snapshots() {
+        assert_all_snapshots::<MessageImport>();
+    }
+
+    #[quickcheck]
+    fn pending_message_roundtrip(val: PendingMessage) {
+        assert_unchanged_via_json(val)
+    }
+
     pub fn create_smsg(
         to: &Address,
         from: &Address,
@@ -643,6 +1076,186 @@ pub mod tests {
         assert_eq!(cur_ts.as_ref(), &tipset);
     }
 
+    #[tokio::test]
+    async fn test_local_messages_rebroadcast_after_restart() {
+        let keystore = KeyStore::new(KeyStoreConfig::Memory).unwrap();
+        let mut wallet = Wallet::new(keystore);
+        let sender = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let target = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let smsg_vec: Vec<_> = (0..3)
+            .map(|i| create_smsg(&target, &sender, wallet.borrow_mut(), i, 1000000, 1))
+            .collect();
+
+        let tma = TestApi::default();
+        tma.set_state_sequence(&sender, 0);
+        let settings = tma.settings.clone();
+        let (tx, _rx) = flume::bounded(50);
+        let mut services = JoinSet::new();
+        let mpool = MessagePool::new(
+            tma,
+            "mptest".to_string(),
+            tx,
+            Default::default(),
+            Arc::default(),
+            &mut services,
+        )
+        .unwrap();
+        for msg in &smsg_vec {
+            mpool.push(msg.clone()).await.unwrap();
+        }
+        drop(mpool);
+        services.abort_all();
+
+        // While the node was down, the first message landed on chain and the
+        // second sequence was used by a different message.
+        let tma = TestApi {
+            settings,
+            ..TestApi::default()
+        };
+        tma.set_state_sequence(&sender, 2);
+        tma.set_block_messages(&mock_block(1, 1), vec![smsg_vec[0].clone()]);
+        let (tx, rx) = flume::bounded(50);
+        let mut services = JoinSet::new();
+        let mpool = MessagePool::new(
+            tma,
+            "mptest".to_string(),
+            tx,
+            Default::default(),
+            Arc::default(),
+            &mut services,
+        )
+        .unwrap();
+        services.abort_all();
+        assert_eq!(mpool.pending_for(&sender), Some(vec![smsg_vec[2].clone()]));
+
+        rebroadcast_local_messages(
+            mpool.api.as_ref(),
+            &mpool.network_sender,
+            &mpool.network_name,
+            mpool.cur_tipset.as_ref(),
+            mpool.local_journal.as_ref(),
+            LOCAL_MESSAGE_REBROADCAST_THRESHOLD,
+        )
+        .await
+        .unwrap();
+
+        let broadcast: Vec<_> = rx.drain().collect();
+        assert_eq!(broadcast.len(), 1);
+        assert!(matches!(
+            &broadcast[0],
+            NetworkMessage::PubsubMessage { message, .. } if *message == to_vec(&smsg_vec[2]).unwrap()
+        ));
+        assert_eq!(
+            mpool
+                .local_messages()
+                .into_iter()
+                .map(|entry| (entry.message, entry.status))
+                .collect::<Vec<_>>(),
+            vec![
+                (smsg_vec[1].clone(), LocalMessageStatus::Replaced),
+                (smsg_vec[2].clone(), LocalMessageStatus::Pending),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_import_exported_messages() {
+        let mut wallet = Wallet::new(KeyStore::new(KeyStoreConfig::Memory).unwrap());
+        let sender = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let target = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let tma = TestApi::default();
+        tma.set_state_sequence(&sender, 0);
+        let mut services = JoinSet::new();
+        let old = gossip_test_mpool(tma, &mut services);
+        for i in 0..4 {
+            let msg = create_smsg(&target, &sender, &mut wallet, i, 1000000, 1);
+            old.add(msg).unwrap();
+        }
+        let (mut exported, _) = old.pending().unwrap();
+        exported.sort_by_key(|msg| msg.sequence());
+
+        // Half of the messages were mined while migrating
+        let tma = TestApi::default();
+        tma.set_state_sequence(&sender, 2);
+        let (tx, rx) = flume::bounded(50);
+        let new = MessagePool::new(
+            tma,
+            "mptest".to_string(),
+            tx,
+            Default::default(),
+            Arc::default(),
+            &mut services,
+        )
+        .unwrap();
+        let mut tampered = exported[3].clone();
+        tampered.message.sequence = 4;
+        let mut imports = vec![];
+        for msg in exported.iter().chain([&tampered]) {
+            imports.push(new.import(msg.clone(), true).await);
+        }
+        assert_eq!(
+            imports
+                .iter()
+                .map(|import| import.status)
+                .collect::<Vec<_>>(),
+            vec![
+                MessageImportStatus::AlreadyIncluded,
+                MessageImportStatus::AlreadyIncluded,
+                MessageImportStatus::Added,
+                MessageImportStatus::Added,
+                MessageImportStatus::Rejected,
+            ]
+        );
+        assert!(imports[4].error.is_some());
+        assert_eq!(new.pending_for(&sender), Some(exported[2..].to_vec()));
+        // Imported messages are handled like pushed ones
+        assert_eq!(rx.drain().count(), 2);
+        assert_eq!(
+            new.local_messages()
+                .into_iter()
+                .map(|entry| entry.message)
+                .collect::<Vec<_>>(),
+            exported[2..].to_vec()
+        );
+
+        // Importing again is harmless
+        assert_eq!(
+            new.import(exported[3].clone(), true).await.status,
+            MessageImportStatus::AlreadyPending
+        );
+        assert_eq!(new.pending_for(&sender), Some(exported[2..].to_vec()));
+        assert!(rx.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_import_non_local_messages() {
+        let mut wallet = Wallet::new(KeyStore::new(KeyStoreConfig::Memory).unwrap());
+        let sender = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let target = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        let tma = TestApi::default();
+        tma.set_state_sequence(&sender, 0);
+        let (tx, rx) = flume::bounded(50);
+        let mut services = JoinSet::new();
+        let mpool = MessagePool::new(
+            tma,
+            "mptest".to_string(),
+            tx,
+            Default::default(),
+            Arc::default(),
+            &mut services,
+        )
+        .unwrap();
+        let msg = create_smsg(&target, &sender, &mut wallet, 0, 1000000, 1);
+        assert_eq!(
+            mpool.import(msg.clone(), false).await.status,
+            MessageImportStatus::Added
+        );
+        assert_eq!(mpool.pending_for(&sender), Some(vec![msg]));
+        // Neither journaled nor published
+        assert!(mpool.local_messages().is_empty());
+        assert!(rx.is_empty());
+    }
+
     #[tokio::test]
     async fn test_msg_chains() {
         let keystore = KeyStore::new(KeyStoreConfig::Memory).unwrap();
@@ -6,14 +6,23 @@
 // inclusion in the chain. Messages are added either directly for locally
 // published messages or through pubsub propagation.
 
-use std::{num::NonZeroUsize, sync::Arc, time::Duration};
+use std::{
+    num::NonZeroUsize,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use crate::blocks::{CachingBlockHeader, Tipset};
+use crate::blocks::{CachingBlockHeader, Tipset, TipsetKey};
 use crate::chain::{HeadChange, MINIMUM_BASE_FEE};
 #[cfg(test)]
 use crate::db::SettingsStore;
 use crate::eth::is_valid_eth_tx_for_sending;
-use crate::libp2p::{NetworkMessage, Topic, PUBSUB_MSG_STR};
+use crate::journal::JournalEvent;
+use crate::libp2p::{
+    GossipMessageValidator, MessageRejectReason, MessageValidation, NetworkMessage, Topic,
+    PUBSUB_MSG_STR,
+};
+use crate::lotus_json::{lotus_json, lotus_json_with_self};
 use crate::message::{valid_for_block_inclusion, ChainMessage, Message, SignedMessage};
 use crate::networks::{ChainConfig, NEWEST_NETWORK_VERSION};
 use crate::shim::{
@@ -22,7 +31,7 @@ use crate::shim::{
     econ::TokenAmount,
     gas::{price_list_by_network_version, Gas},
 };
-use crate::state_manager::is_valid_for_sending;
+use crate::state_manager::{is_valid_for_sending, PendingMessageSource};
 use ahash::{HashMap, HashMapExt, HashSet, HashSetExt};
 use anyhow::Context as _;
 use cid::Cid;
@@ -31,17 +40,26 @@ use fvm_ipld_encoding::to_vec;
 use itertools::Itertools;
 use lru::LruCache;
 use nonzero_ext::nonzero;
+use num_traits::Zero as _;
 use parking_lot::{Mutex, RwLock as SyncRwLock};
-use tokio::{sync::broadcast::error::RecvError, task::JoinSet, time::interval};
-use tracing::warn;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::broadcast::error::RecvError,
+    task::JoinSet,
+    time::{interval, MissedTickBehavior},
+};
+use tracing::{debug, warn};
 
 use crate::message_pool::{
     config::MpoolConfig,
     errors::Error,
-    head_change, metrics,
+    head_change,
+    journal::{LocalMessage, LocalMessageJournal, LOCAL_MESSAGE_REBROADCAST_THRESHOLD},
+    metrics,
     msgpool::{
-        recover_sig, republish_pending_messages, BASE_FEE_LOWER_BOUND_FACTOR_CONSERVATIVE,
-        RBF_DENOM, RBF_NUM,
+        check_tip_alignment, rebroadcast_local_messages, recover_sig, republish_pending_messages,
+        BASE_FEE_LOWER_BOUND_FACTOR_CONSERVATIVE, RBF_DENOM, RBF_NUM,
     },
     provider::Provider,
     utils::get_base_fee_lower_bound,
@@ -50,6 +68,7 @@ use crate::message_pool::{
 // LruCache sizes have been taken from the lotus implementation
 const BLS_SIG_CACHE_SIZE: NonZeroUsize = nonzero!(40000usize);
 const SIG_VAL_CACHE_SIZE: NonZeroUsize = nonzero!(32000usize);
+const SENDER_STATE_CACHE_SIZE: NonZeroUsize = nonzero!(1024usize);
 
 pub const MAX_ACTOR_PENDING_MESSAGES: u64 = 1000;
 pub const MAX_UNTRUSTED_ACTOR_PENDING_MESSAGES: u64 = 10;
@@ -59,15 +78,85 @@ pub const MAX_UNTRUSTED_ACTOR_PENDING_MESSAGES: u64 = 10;
 #[derive(Clone, Default, Debug)]
 pub struct MsgSet {
     pub(in crate::message_pool) msgs: HashMap<u64, SignedMessage>,
+    /// When each message was added to the set, keyed by sequence
+    pub(in crate::message_pool) added_at: HashMap<u64, Instant>,
     next_sequence: u64,
 }
 
+lotus_json! {
+    /// A pending message, annotated with the number of sequences missing from the
+    /// pool between it and the previous pending message of the same sender, or
+    /// the on-chain sequence of the sender for the first one.
+    #[derive(Debug, Clone, PartialEq)]
+    #[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
+    pub struct PendingMessage {
+        pub message: SignedMessage,
+        pub missing_before: u64,
+    }
+    snapshots {
+        vec![(
+            serde_json::json!({
+                "Message": {
+                    "Message": {
+                        "From": "f00",
+                        "GasFeeCap": "0",
+                        "GasLimit": 0,
+                        "GasPremium": "0",
+                        "Method": 0,
+                        "Nonce": 0,
+                        "Params": null,
+                        "To": "f00",
+                        "Value": "0",
+                        "Version": 0,
+                    },
+                    "Signature": {"Type": 2, "Data": "aGVsbG8gd29ybGQh"},
+                    "CID": {
+                        "/": "bafy2bzaced3xdk2uf6azekyxgcttujvy3fzyeqmibtpjf2fxcpfdx2zcx4s3g"
+                    },
+                },
+                "MissingBefore": 2,
+            }),
+            PendingMessage {
+                message: SignedMessage {
+                    message: crate::shim::message::Message::default(),
+                    signature: Signature {
+                        sig_type: SignatureType::Bls,
+                        bytes: Vec::from_iter(*b"hello world!"),
+                    },
+                },
+                missing_before: 2,
+            },
+        )]
+    }
+}
+
+impl PendingMessage {
+    /// Annotates messages of a single sender, sorted by sequence, given the
+    /// on-chain sequence of the sender.
+    pub fn annotate_gaps(state_sequence: u64, messages: Vec<SignedMessage>) -> Vec<Self> {
+        let mut expected = state_sequence;
+        messages
+            .into_iter()
+            .map(|message| {
+                let sequence = message.sequence();
+                let missing_before = sequence.saturating_sub(expected);
+                expected = sequence + 1;
+                Self {
+                    message,
+                    missing_before,
+                }
+            })
+            .collect()
+    }
+}
+
 impl MsgSet {
     /// Generate a new `MsgSet` with an empty hash-map and setting the sequence
     /// specifically.
     pub fn new(sequence: u64) -> Self {
         MsgSet {
             msgs: HashMap::new(),
+            added_at: HashMap::new(),
             next_sequence: sequence,
         }
     }
@@ -127,16 +216,24 @@ impl MsgSet {
                 trusted,
             ));
         }
+        self.added_at.insert(m.sequence(), Instant::now());
+        let event = JournalEvent::MpoolAdd {
+            message: m.cid(),
+            from: m.from(),
+            sequence: m.sequence(),
+        };
         if self.msgs.insert(m.sequence(), m).is_none() {
             metrics::MPOOL_MESSAGE_TOTAL.inc();
         }
+        crate::journal::record(event);
         Ok(())
     }
 
     /// Removes message with the given sequence. If applied, update the set's
     /// next sequence.
     pub fn rm(&mut self, sequence: u64, applied: bool) {
-        if self.msgs.remove(&sequence).is_none() {
+        self.added_at.remove(&sequence);
+        let Some(removed) = self.msgs.remove(&sequence) else {
             if applied && sequence >= self.next_sequence {
                 self.next_sequence = sequence + 1;
                 while self.msgs.contains_key(&self.next_sequence) {
@@ -144,8 +241,14 @@ impl MsgSet {
                 }
             }
             return;
-        }
+        };
         metrics::MPOOL_MESSAGE_TOTAL.dec();
+        crate::journal::record(JournalEvent::MpoolRemove {
+            message: removed.cid(),
+            from: removed.from(),
+            sequence,
+            applied,
+        });
 
         // adjust next sequence
         if applied {
@@ -162,6 +265,91 @@ impl MsgSet {
             self.next_sequence = sequence;
         }
     }
+
+    /// Returns the messages that were added to the set at least `min_age` ago.
+    fn stuck(&self, min_age: Duration) -> impl Iterator<Item = &SignedMessage> {
+        self.msgs.iter().filter_map(move |(sequence, msg)| {
+            self.added_at
+                .get(sequence)
+                .is_some_and(|added_at| added_at.elapsed() >= min_age)
+                .then_some(msg)
+        })
+    }
+}
+
+/// Returns the sequence following both the `state_sequence` of an actor and the
+/// highest of its `pending_sequences`. Gaps in the pending sequences are not
+/// filled, as the messages after them would be replaced.
+pub fn next_sequence(state_sequence: u64, pending_sequences: impl IntoIterator<Item = u64>) -> u64 {
+    pending_sequences
+        .into_iter()
+        .map(|sequence| sequence + 1)
+        .fold(state_sequence, u64::max)
+}
+
+/// State of a message sender at a given head, used to validate the messages
+/// received over gossip.
+#[derive(Clone, Debug)]
+pub struct SenderState {
+    /// The head the state was resolved at
+    tipset: TipsetKey,
+    sequence: u64,
+    balance: TokenAmount,
+}
+
+/// Outcome of a message re-added with [`MessagePool::import`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum MessageImportStatus {
+    /// Added to the pending messages
+    Added,
+    /// Already waiting in the pool
+    AlreadyPending,
+    /// The sender sequence of the message was used on chain, by the message
+    /// or one replacing it
+    AlreadyIncluded,
+    /// Failed the validation of the pool
+    Rejected,
+}
+
+lotus_json_with_self!(MessageImportStatus);
+
+lotus_json! {
+    /// Report of a message re-added with [`MessagePool::import`].
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct MessageImport {
+        pub cid: Cid,
+        pub status: MessageImportStatus,
+        /// Reason of the rejection
+        pub error: Option<String>,
+    }
+    snapshots {
+        vec![
+            (
+                serde_json::json!({
+                    "Cid": {"/": "baeaaaaa"},
+                    "Status": "Added",
+                    "Error": null,
+                }),
+                MessageImport {
+                    cid: Cid::default(),
+                    status: MessageImportStatus::Added,
+                    error: None,
+                },
+            ),
+            (
+                serde_json::json!({
+                    "Cid": {"/": "baeaaaaa"},
+                    "Status": "Rejected",
+                    "Error": "sequence too low",
+                }),
+                MessageImport {
+                    cid: Cid::default(),
+                    status: MessageImportStatus::Rejected,
+                    error: Some("sequence too low".into()),
+                },
+            ),
+        ]
+    }
 }
 
 /// This contains all necessary information needed for the message pool.
@@ -183,12 +371,17 @@ pub struct MessagePool<T> {
     pub bls_sig_cache: Arc<Mutex<LruCache<Cid, Signature>>>,
     /// A cache for BLS signature keyed by Cid
     pub sig_val_cache: Arc<Mutex<LruCache<Cid, ()>>>,
+    /// A cache of the state of gossip message senders at the current head,
+    /// cleared on head changes
+    pub sender_states: Arc<Mutex<LruCache<Address, SenderState>>>,
     /// A set of republished messages identified by their Cid
     pub republished: Arc<SyncRwLock<HashSet<Cid>>>,
     /// Acts as a signal to republish messages from the republished set of
     /// messages
     pub repub_trigger: flume::Sender<()>,
     local_msgs: Arc<SyncRwLock<HashSet<SignedMessage>>>,
+    /// Persistent journal of messages published through this node
+    pub(in crate::message_pool) local_journal: Arc<LocalMessageJournal>,
     /// Configurable parameters of the message pool
     pub config: MpoolConfig,
     /// Chain configuration
@@ -210,10 +403,19 @@ where
     /// checks on the validity of a message.
     pub async fn push(&self, msg: SignedMessage) -> Result<Cid, Error> {
         self.check_message(&msg)?;
-        let cid = msg.cid();
         let cur_ts = self.cur_tipset.lock().clone();
-        let publish = self.add_tipset(msg.clone(), &cur_ts, true)?;
+        self.push_tipset(msg, &cur_ts).await
+    }
+
+    /// Adds a checked message to the pool as a local one, recording it in the
+    /// journal and publishing it if it may be included soon.
+    async fn push_tipset(&self, msg: SignedMessage, cur_ts: &Tipset) -> Result<Cid, Error> {
+        let cid = msg.cid();
+        let publish = self.add_tipset(msg.clone(), cur_ts, true)?;
         let msg_ser = to_vec(&msg)?;
+        if let Err(e) = self.local_journal.record(msg.clone()) {
+            warn!("Failed to record local message {cid} in the journal: {e}");
+        }
         self.add_local(msg)?;
         if publish {
             self.network_sender
@@ -252,6 +454,112 @@ where
         Ok(())
     }
 
+    /// Re-adds a message exported from the pool of another node, skipping it if
+    /// its sender sequence was already used on chain. The message is otherwise
+    /// validated and added like those received from the network, or, if
+    /// `local`, like those pushed to this node: recorded in the journal and
+    /// published.
+    pub async fn import(&self, msg: SignedMessage, local: bool) -> MessageImport {
+        let cid = msg.cid();
+        let (status, error) = match self.import_inner(msg, local).await {
+            Ok(status) => (status, None),
+            Err(e) => (MessageImportStatus::Rejected, Some(e.to_string())),
+        };
+        MessageImport { cid, status, error }
+    }
+
+    async fn import_inner(
+        &self,
+        msg: SignedMessage,
+        local: bool,
+    ) -> Result<MessageImportStatus, Error> {
+        self.check_message(&msg)?;
+        let cur_ts = self.cur_tipset.lock().clone();
+        if msg.sequence() < self.get_state_sequence(&msg.from(), &cur_ts)? {
+            return Ok(MessageImportStatus::AlreadyIncluded);
+        }
+        let pending = self
+            .pending
+            .read()
+            .get(&msg.from())
+            .and_then(|mset| mset.msgs.get(&msg.sequence()))
+            .is_some_and(|pending| pending.cid() == msg.cid());
+        if pending {
+            return Ok(MessageImportStatus::AlreadyPending);
+        }
+        if local {
+            self.push_tipset(msg, &cur_ts).await?;
+        } else {
+            self.add_tipset(msg, &cur_ts, false)?;
+        }
+        Ok(MessageImportStatus::Added)
+    }
+
+    /// Checks a message received over gossip against the state of its sender
+    /// at the current head and the messages it already has pending, before
+    /// the message is propagated. Invalid messages are rejected, lowering the
+    /// score of the peer that forwarded them.
+    pub fn validate_gossip_message(&self, msg: SignedMessage) -> MessageValidation {
+        if self.check_message(&msg).is_err() {
+            return MessageValidation::Reject(MessageRejectReason::InvalidMessage);
+        }
+        let cur_ts = self.cur_tipset.lock().clone();
+        let state = match self.get_sender_state(&msg.from(), &cur_ts) {
+            Ok(state) => state,
+            Err(e) => {
+                debug!("Cannot resolve gossip message sender {}: {e}", msg.from());
+                return MessageValidation::Reject(MessageRejectReason::UnknownSender);
+            }
+        };
+        if msg.sequence() < state.sequence {
+            return MessageValidation::Reject(MessageRejectReason::SequenceTooLow);
+        }
+
+        // A pending message with the same sequence would be replaced
+        let (pending_count, pending_spend) = self
+            .pending
+            .read()
+            .get(&msg.from())
+            .map(|mset| {
+                mset.msgs
+                    .values()
+                    .filter(|m| m.sequence() >= state.sequence && m.sequence() != msg.sequence())
+                    .fold((0, TokenAmount::zero()), |(count, spend), m| {
+                        (count + 1, spend + max_spend(m))
+                    })
+            })
+            .unwrap_or_default();
+        if pending_count >= self.api.max_untrusted_actor_pending_messages() {
+            return MessageValidation::Reject(MessageRejectReason::TooManyPending);
+        }
+        if pending_spend + max_spend(&msg) > state.balance {
+            return MessageValidation::Reject(MessageRejectReason::InsufficientBalance);
+        }
+        MessageValidation::Accept(msg)
+    }
+
+    /// Returns the state of `addr` at `cur_ts`, from the cache if it was
+    /// resolved at the same head.
+    fn get_sender_state(&self, addr: &Address, cur_ts: &Tipset) -> Result<SenderState, Error> {
+        let cached = self
+            .sender_states
+            .lock()
+            .get(addr)
+            .filter(|state| &state.tipset == cur_ts.key())
+            .cloned();
+        if let Some(state) = cached {
+            return Ok(state);
+        }
+        let actor = self.api.get_actor_after(addr, cur_ts)?;
+        let state = SenderState {
+            tipset: cur_ts.key().clone(),
+            sequence: actor.sequence,
+            balance: TokenAmount::from(&actor.balance),
+        };
+        self.sender_states.lock().put(*addr, state.clone());
+        Ok(state)
+    }
+
     /// Verify the message signature. first check if it has already been
     /// verified and put into cache. If it has not, then manually verify it
     /// then put it into cache for future use.
@@ -346,6 +654,26 @@ where
         }
     }
 
+    /// Returns the next sequence `addr` can use given its `state_sequence` on
+    /// chain, skipping the sequences of its messages waiting in the pool.
+    pub fn next_sequence(&self, addr: &Address, state_sequence: u64) -> u64 {
+        let pending = self.pending.read();
+        let pending_sequences = pending
+            .get(addr)
+            .into_iter()
+            .flat_map(|mset| mset.msgs.keys().copied());
+        next_sequence(state_sequence, pending_sequences)
+    }
+
+    /// Returns the next sequence `addr` can use, from its sequence in the
+    /// state of the current head, cached along with the sender balance, and
+    /// its messages waiting in the pool.
+    pub fn get_nonce(&self, addr: &Address) -> Result<u64, Error> {
+        let cur_ts = self.cur_tipset.lock().clone();
+        let state = self.get_sender_state(addr, &cur_ts)?;
+        Ok(self.next_sequence(addr, state.sequence))
+    }
+
     /// Get the state of the sequence for a given address in `cur_ts`.
     fn get_state_sequence(&self, addr: &Address, cur_ts: &Tipset) -> Result<u64, Error> {
         let actor = self.api.get_actor_after(addr, cur_ts)?;
@@ -397,6 +725,61 @@ where
         )
     }
 
+    /// Returns the pending messages from `addr`, sorted by sequence.
+    pub fn get_pending_for_address(&self, addr: &Address) -> Vec<SignedMessage> {
+        self.pending_for(addr).unwrap_or_default()
+    }
+
+    /// Returns the pending messages from `addr`, sorted by sequence and
+    /// annotated with the gaps from the sequence of `addr` at the current
+    /// tipset.
+    pub fn get_pending_gaps_for_address(
+        &self,
+        addr: &Address,
+    ) -> Result<Vec<PendingMessage>, Error> {
+        let Some(pending) = self.pending_for(addr) else {
+            return Ok(vec![]);
+        };
+        let cur_ts = self.cur_tipset.lock().clone();
+        let sequence = self.get_state_sequence(addr, &cur_ts)?;
+        Ok(PendingMessage::annotate_gaps(sequence, pending))
+    }
+
+    /// Re-publishes the pending messages that were added to the pool at least
+    /// `min_age_secs` seconds ago, in case their first broadcast was missed by
+    /// most peers. Pending messages are kept signed, so they are published
+    /// as-is. Returns the number of messages rebroadcast.
+    pub fn rebroadcast_stuck_messages(&self, min_age_secs: u64) -> usize {
+        let min_age = Duration::from_secs(min_age_secs);
+        let stuck = self
+            .pending
+            .read()
+            .values()
+            .flat_map(|mset| mset.stuck(min_age).cloned())
+            .collect_vec();
+        let topic = Topic::new(format!("{PUBSUB_MSG_STR}/{}", self.network_name));
+        let mut rebroadcast = 0;
+        for msg in stuck {
+            let message = match to_vec(&msg) {
+                Ok(message) => message,
+                Err(e) => {
+                    warn!("Failed to encode message {}: {e}", msg.cid());
+                    continue;
+                }
+            };
+            if let Err(e) = self.network_sender.try_send(NetworkMessage::PubsubMessage {
+                topic: topic.clone(),
+                message,
+            }) {
+                warn!("Failed to rebroadcast stuck messages: {e}");
+                break;
+            }
+            rebroadcast += 1;
+        }
+        metrics::MPOOL_REBROADCAST_TOTAL.inc_by(rebroadcast as u64);
+        rebroadcast
+    }
+
     /// Return Vector of signed messages given a block header for self.
     pub fn messages_for_blocks<'a>(
         &self,
@@ -416,8 +799,17 @@ where
         Ok(msg_vec)
     }
 
-    /// Loads local messages to the message pool to be applied.
+    /// Returns the journal entries of messages published through this node.
+    pub fn local_messages(&self) -> Vec<LocalMessage> {
+        self.local_journal.entries()
+    }
+
+    /// Loads local messages, including the pending ones from the journal, to
+    /// the message pool to be applied.
     pub fn load_local(&mut self) -> Result<(), Error> {
+        for msg in self.local_journal.pending() {
+            self.add_local(msg)?;
+        }
         let mut local_msgs = self.local_msgs.write();
         for k in local_msgs.iter().cloned().collect::<Vec<SignedMessage>>() {
             self.add(k.clone()).unwrap_or_else(|err| {
@@ -449,6 +841,24 @@ where
     }
 }
 
+impl<T> GossipMessageValidator for MessagePool<T>
+where
+    T: Provider + Send + Sync,
+{
+    fn validate_gossip_message(&self, msg: SignedMessage) -> MessageValidation {
+        MessagePool::validate_gossip_message(self, msg)
+    }
+}
+
+impl<T> PendingMessageSource for MessagePool<T>
+where
+    T: Provider,
+{
+    fn pending_messages(&self, from: &Address) -> Vec<SignedMessage> {
+        self.get_pending_for_address(from)
+    }
+}
+
 impl<T> MessagePool<T>
 where
     T: Provider + Send + Sync + 'static,
@@ -470,7 +880,9 @@ where
         let tipset = Arc::new(Mutex::new(api.get_heaviest_tipset()));
         let bls_sig_cache = Arc::new(Mutex::new(LruCache::new(BLS_SIG_CACHE_SIZE)));
         let sig_val_cache = Arc::new(Mutex::new(LruCache::new(SIG_VAL_CACHE_SIZE)));
+        let sender_states = Arc::new(Mutex::new(LruCache::new(SENDER_STATE_CACHE_SIZE)));
         let local_msgs = Arc::new(SyncRwLock::new(HashSet::new()));
+        let local_journal = Arc::new(LocalMessageJournal::load(api.settings_store())?);
         let republished = Arc::new(SyncRwLock::new(HashSet::new()));
         let block_delay = chain_config.block_delay_secs;
 
@@ -483,7 +895,9 @@ where
             network_name,
             bls_sig_cache,
             sig_val_cache,
+            sender_states,
             local_msgs,
+            local_journal,
             republished,
             config,
             network_sender,
@@ -499,6 +913,7 @@ where
         let bls_sig_cache = mp.bls_sig_cache.clone();
         let pending = mp.pending.clone();
         let republished = mp.republished.clone();
+        let sender_states = mp.sender_states.clone();
 
         let cur_tipset = mp.cur_tipset.clone();
         let repub_trigger = Arc::new(mp.repub_trigger.clone());
@@ -527,6 +942,7 @@ where
                         )
                         .await
                         .context("Error changing head")?;
+                        sender_states.lock().clear();
                     }
                     Err(RecvError::Lagged(e)) => {
                         warn!("Head change subscriber lagged: skipping {} events", e);
@@ -538,6 +954,60 @@ where
             }
         });
 
+        let api = mp.api.clone();
+        let pending = mp.pending.clone();
+        let cur_tipset = mp.cur_tipset.clone();
+        // Checks the pending messages against each new head, at most once per
+        // epoch and off the head change path
+        services.spawn(async move {
+            let mut interval = interval(Duration::from_secs(block_delay as u64));
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            let mut checked: Option<TipsetKey> = None;
+            loop {
+                interval.tick().await;
+                let head = cur_tipset.lock().clone();
+                if checked.as_ref() == Some(head.key()) {
+                    continue;
+                }
+                checked = Some(head.key().clone());
+                let (api, pending) = (api.clone(), pending.clone());
+                if let Err(e) = tokio::task::spawn_blocking(move || {
+                    check_tip_alignment(api.as_ref(), pending.as_ref(), &head)
+                })
+                .await
+                {
+                    warn!("Failed to check the message pool against the head: {e}");
+                }
+            }
+        });
+
+        let api = mp.api.clone();
+        let cur_tipset = mp.cur_tipset.clone();
+        let local_journal = mp.local_journal.clone();
+        let network_sender = mp.network_sender.clone();
+        let network_name = mp.network_name.clone();
+        let rebroadcast_interval = (10 * block_delay + chain_config.propagation_delay_secs) as u64;
+        // Resolves journaled local messages and rebroadcasts the pending ones,
+        // starting right away with the ones loaded from the journal
+        services.spawn(async move {
+            let mut interval = interval(Duration::from_secs(rebroadcast_interval));
+            loop {
+                interval.tick().await;
+                if let Err(e) = rebroadcast_local_messages(
+                    api.as_ref(),
+                    &network_sender,
+                    &network_name,
+                    cur_tipset.as_ref(),
+                    local_journal.as_ref(),
+                    LOCAL_MESSAGE_REBROADCAST_THRESHOLD,
+                )
+                .await
+                {
+                    warn!("Failed to rebroadcast local messages: {e}");
+                }
+            }
+        });
+
         let api = mp.api.clone();
         let pending = mp.pending.clone();
         let cur_tipset = mp.cur_tipset.clone();
@@ -619,6 +1089,12 @@ where
     Ok(())
 }
 
+/// Returns the most a message can take from the balance of its sender: its
+/// value and its maximum gas fee.
+fn max_spend(m: &SignedMessage) -> TokenAmount {
+    m.required_funds() + m.value()
+}
+
 fn verify_msg_before_add(
     m: &SignedMessage,
     cur_ts: &Tipset,
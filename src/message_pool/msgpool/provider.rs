@@ -4,20 +4,17 @@
 use std::sync::Arc;
 
 use crate::blocks::{CachingBlockHeader, Tipset, TipsetKey};
-use crate::chain::HeadChange;
+use crate::chain::{AlignmentReport, HeadChange};
+use crate::db::SettingsStore;
 use crate::message::{ChainMessage, SignedMessage};
 use crate::message_pool::msg_pool::{
     MAX_ACTOR_PENDING_MESSAGES, MAX_UNTRUSTED_ACTOR_PENDING_MESSAGES,
 };
 use crate::networks::Height;
-use crate::shim::{
-    address::Address,
-    econ::TokenAmount,
-    message::Message,
-    state_tree::{ActorState, StateTree},
-};
+use crate::shim::{address::Address, econ::TokenAmount, message::Message, state_tree::ActorState};
 use crate::state_manager::StateManager;
 use crate::utils::db::CborStoreExt;
+use anyhow::Context as _;
 use async_trait::async_trait;
 use cid::Cid;
 use fvm_ipld_blockstore::Blockstore;
@@ -50,6 +47,20 @@ pub trait Provider {
     fn load_tipset(&self, tsk: &TipsetKey) -> Result<Arc<Tipset>, Error>;
     /// Computes the base fee
     fn chain_compute_base_fee(&self, ts: &Tipset) -> Result<TokenAmount, Error>;
+    /// Store used to persist the journal of local messages
+    fn settings_store(&self) -> Arc<dyn SettingsStore + Send + Sync>;
+    /// Returns `true` if a receipt for the message with the given CID is found
+    /// within the last chain finality of the heaviest chain. Pending local
+    /// messages are checked on every rebroadcast round, well within that window.
+    /// Search failures are logged and reported as `false`.
+    async fn has_receipt(&self, msg_cid: Cid) -> bool;
+    /// Checks the pending messages against the given head, see
+    /// [`crate::chain::ChainStore::message_pool_tip_alignment`]
+    fn tip_alignment(
+        &self,
+        pending: &[SignedMessage],
+        head: &Arc<Tipset>,
+    ) -> Result<AlignmentReport, Error>;
     // Get max number of messages per actor in the pool
     fn max_actor_pending_messages(&self) -> u64 {
         MAX_ACTOR_PENDING_MESSAGES
@@ -99,9 +110,10 @@ where
     }
 
     fn get_actor_after(&self, addr: &Address, ts: &Tipset) -> Result<ActorState, Error> {
-        let state = StateTree::new_from_root(self.sm.blockstore_owned(), ts.parent_state())
-            .map_err(|e| Error::Other(e.to_string()))?;
-        Ok(state.get_required_actor(addr)?)
+        Ok(self
+            .sm
+            .get_actor_at(addr, ts)?
+            .with_context(|| format!("Actor not found: addr={addr}"))?)
     }
 
     fn messages_for_block(
@@ -125,4 +137,39 @@ where
             .map_err(|err| err.into())
             .map(Into::into)
     }
+
+    fn settings_store(&self) -> Arc<dyn SettingsStore + Send + Sync> {
+        self.sm.chain_store().settings()
+    }
+
+    async fn has_receipt(&self, msg_cid: Cid) -> bool {
+        let look_back_limit = self.sm.chain_config().policy.chain_finality;
+        match self
+            .sm
+            .search_for_message(None, msg_cid, Some(look_back_limit), Some(false))
+            .await
+        {
+            Ok(found) => found.is_some(),
+            // The search also errors out if the sequence was used by a message
+            // with a different CID, which means there is no receipt for this
+            // one either, so store failures can't be told apart here.
+            Err(e) => {
+                tracing::warn!(
+                    "failed to search the receipt of message {msg_cid}, assuming it was replaced: {e}"
+                );
+                false
+            }
+        }
+    }
+
+    fn tip_alignment(
+        &self,
+        pending: &[SignedMessage],
+        head: &Arc<Tipset>,
+    ) -> Result<AlignmentReport, Error> {
+        Ok(self
+            .sm
+            .chain_store()
+            .message_pool_tip_alignment(pending, head)?)
+    }
 }
@@ -0,0 +1,355 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Introspection of the composition of the message pool, see
+//! [`MessagePool::stat`].
+
+use std::time::Instant;
+
+use crate::lotus_json::lotus_json;
+use crate::message::Message as MessageTrait;
+use crate::shim::{address::Address, econ::TokenAmount};
+use ahash::{HashMap, HashSet};
+use itertools::Itertools as _;
+
+use crate::message_pool::{
+    errors::Error,
+    metrics,
+    msg_pool::{MessagePool, MsgSet},
+    provider::Provider,
+};
+
+/// Lower bounds of the age buckets of [`MpoolStat::ages`], in seconds.
+const AGE_BUCKETS_SECS: [u64; 6] = [0, 60, 5 * 60, 30 * 60, 60 * 60, 6 * 60 * 60];
+
+/// Number of senders listed in [`MpoolStat::top_senders`].
+const TOP_SENDERS: usize = 10;
+
+lotus_json! {
+    /// Pending messages of a sender, categorized against its sequence on chain
+    /// as by `lotus mpool stat`.
+    #[derive(Debug, Clone, Default, PartialEq)]
+    pub struct SenderStat {
+        pub address: Address,
+        /// Sequence of the sender at the head of the pool
+        pub state_sequence: u64,
+        /// Messages with a sequence already used on chain
+        pub past: u64,
+        /// Messages executable in a row from the sequence on chain
+        pub current: u64,
+        /// Messages waiting for the missing sequences before them
+        pub future: u64,
+        /// Sequences missing from the pool between the sequence on chain and
+        /// the last pending message
+        pub nonce_gap: u64,
+        /// Messages with a fee cap below the base fee of the head
+        pub below_base_fee: u64,
+        /// Messages with a fee cap below the lowest base fee of the lookback
+        pub below_min_base_fee: u64,
+        /// Sum of the gas limits of the messages
+        pub gas_limit: u64,
+    }
+    snapshots {
+        vec![(
+            serde_json::json!({
+                "Address": "f01000",
+                "StateSequence": 5,
+                "Past": 1,
+                "Current": 2,
+                "Future": 1,
+                "NonceGap": 1,
+                "BelowBaseFee": 1,
+                "BelowMinBaseFee": 0,
+                "GasLimit": 400,
+            }),
+            SenderStat {
+                address: Address::new_id(1000),
+                state_sequence: 5,
+                past: 1,
+                current: 2,
+                future: 1,
+                nonce_gap: 1,
+                below_base_fee: 1,
+                below_min_base_fee: 0,
+                gas_limit: 400,
+            },
+        )]
+    }
+}
+
+impl SenderStat {
+    /// Number of pending messages of the sender.
+    pub fn pending(&self) -> u64 {
+        self.past + self.current + self.future
+    }
+}
+
+lotus_json! {
+    /// Number of pending messages added to the pool at least `min_age_secs`
+    /// ago, and more recently than the bound of the next bucket.
+    #[derive(Debug, Clone, Default, PartialEq)]
+    pub struct AgeBucket {
+        pub min_age_secs: u64,
+        pub count: u64,
+    }
+    snapshots {
+        vec![(
+            serde_json::json!({"MinAgeSecs": 60, "Count": 3}),
+            AgeBucket {
+                min_age_secs: 60,
+                count: 3,
+            },
+        )]
+    }
+}
+
+lotus_json! {
+    /// Composition of the message pool, see [`MessagePool::stat`].
+    #[derive(Debug, Clone, Default, PartialEq)]
+    pub struct MpoolStat {
+        /// Sorted by address
+        pub senders: Vec<SenderStat>,
+        /// By increasing age
+        pub ages: Vec<AgeBucket>,
+        /// Senders with the most pending messages, first the one with the most
+        pub top_senders: Vec<Address>,
+    }
+    snapshots {
+        vec![(
+            serde_json::json!({
+                "Senders": [],
+                "Ages": [{"MinAgeSecs": 0, "Count": 0}],
+                "TopSenders": ["f01000"],
+            }),
+            MpoolStat {
+                senders: vec![],
+                ages: vec![AgeBucket::default()],
+                top_senders: vec![Address::new_id(1000)],
+            },
+        )]
+    }
+}
+
+impl MpoolStat {
+    /// Categorizes the messages of each `(sender, state sequence, messages)`
+    /// entry, the age of the messages being taken at `now`.
+    pub(in crate::message_pool) fn compute<'a>(
+        pending: impl IntoIterator<Item = (Address, u64, &'a MsgSet)>,
+        base_fee: &TokenAmount,
+        min_base_fee: &TokenAmount,
+        now: Instant,
+    ) -> Self {
+        let mut ages = AGE_BUCKETS_SECS.map(|min_age_secs| AgeBucket {
+            min_age_secs,
+            count: 0,
+        });
+        let mut senders = vec![];
+        for (address, state_sequence, mset) in pending {
+            let mut current_end = state_sequence;
+            while mset.msgs.contains_key(&current_end) {
+                current_end += 1;
+            }
+            let mut stat = SenderStat {
+                address,
+                state_sequence,
+                ..Default::default()
+            };
+            for (sequence, msg) in mset.msgs.iter() {
+                match *sequence {
+                    sequence if sequence < state_sequence => stat.past += 1,
+                    sequence if sequence > current_end => stat.future += 1,
+                    _ => stat.current += 1,
+                }
+                let fee_cap = &msg.message().gas_fee_cap;
+                if fee_cap < base_fee {
+                    stat.below_base_fee += 1;
+                }
+                if fee_cap < min_base_fee {
+                    stat.below_min_base_fee += 1;
+                }
+                stat.gas_limit += msg.gas_limit();
+
+                let age = mset
+                    .added_at
+                    .get(sequence)
+                    .map(|added_at| now.saturating_duration_since(*added_at).as_secs())
+                    .unwrap_or_default();
+                if let Some(bucket) = ages.iter_mut().rev().find(|b| b.min_age_secs <= age) {
+                    bucket.count += 1;
+                }
+            }
+            if let Some(last) = mset
+                .msgs
+                .keys()
+                .max()
+                .filter(|&&last| last >= state_sequence)
+            {
+                stat.nonce_gap = last + 1 - state_sequence - (stat.current + stat.future);
+            }
+            senders.push(stat);
+        }
+        senders.sort_by_cached_key(|stat| stat.address.to_string());
+
+        let top_senders = senders
+            .iter()
+            .sorted_by_key(|stat| std::cmp::Reverse(stat.pending()))
+            .take(TOP_SENDERS)
+            .map(|stat| stat.address)
+            .collect();
+        Self {
+            senders,
+            ages: ages.into(),
+            top_senders,
+        }
+    }
+}
+
+impl<T> MessagePool<T>
+where
+    T: Provider,
+{
+    /// Returns the composition of the pool at its current head, for the
+    /// messages of `senders` only if given. Messages with a fee cap below
+    /// `min_base_fee` are counted apart from the ones below the base fee of
+    /// the head.
+    pub fn stat(
+        &self,
+        min_base_fee: &TokenAmount,
+        senders: Option<&HashSet<Address>>,
+    ) -> Result<MpoolStat, Error> {
+        let head = self.cur_tipset.lock().clone();
+        let pending: HashMap<Address, MsgSet> = self
+            .pending
+            .read()
+            .iter()
+            .filter(|(addr, mset)| {
+                !mset.msgs.is_empty() && senders.is_none_or(|senders| senders.contains(addr))
+            })
+            .map(|(addr, mset)| (*addr, mset.clone()))
+            .collect();
+        let mut entries = Vec::with_capacity(pending.len());
+        for (addr, mset) in pending.iter() {
+            let state_sequence = self.api.get_actor_after(addr, &head)?.sequence;
+            entries.push((*addr, state_sequence, mset));
+        }
+        Ok(MpoolStat::compute(
+            entries,
+            &head.block_headers().first().parent_base_fee,
+            min_base_fee,
+            Instant::now(),
+        ))
+    }
+}
+
+/// Records the number of senders with pending messages, the pending count of
+/// the sender with the most, and the number of messages that cannot be
+/// executed at the base fee of the head.
+pub(in crate::message_pool) fn record_sender_metrics<'a>(
+    pending: impl IntoIterator<Item = &'a MsgSet>,
+    base_fee: &TokenAmount,
+) {
+    let (mut senders, mut max_pending, mut below_base_fee) = (0, 0, 0);
+    for mset in pending {
+        if mset.msgs.is_empty() {
+            continue;
+        }
+        senders += 1;
+        max_pending = max_pending.max(mset.msgs.len());
+        below_base_fee += mset
+            .msgs
+            .values()
+            .filter(|msg| &msg.message().gas_fee_cap < base_fee)
+            .count();
+    }
+    metrics::MPOOL_SENDERS.set(senders as i64);
+    metrics::MPOOL_MAX_SENDER_PENDING.set(max_pending as i64);
+    metrics::MPOOL_BELOW_BASE_FEE_MESSAGES.set(below_base_fee as i64);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lotus_json::assert_all_snapshots;
+    use crate::message::SignedMessage;
+    use crate::shim::{crypto::Signature, message::Message};
+    use std::time::Duration;
+
+    #[test]
+    fn snapshots() {
+        assert_all_snapshots::<SenderStat>();
+        assert_all_snapshots::<AgeBucket>();
+        assert_all_snapshots::<MpoolStat>();
+    }
+
+    #[test]
+    fn mpool_stat_test() {
+        let (alice, bob) = (Address::new_id(1000), Address::new_id(1001));
+        let now = Instant::now() + Duration::from_secs(24 * 60 * 60);
+        // (sequence, fee cap, age in seconds)
+        let msg_set = |from: Address, msgs: &[(u64, u64, u64)]| {
+            let mut mset = MsgSet::new(0);
+            for &(sequence, fee_cap, age) in msgs {
+                let msg = Message {
+                    from,
+                    sequence,
+                    gas_limit: 100,
+                    gas_fee_cap: TokenAmount::from_atto(fee_cap),
+                    ..Default::default()
+                };
+                mset.msgs.insert(
+                    sequence,
+                    SignedMessage::new_unchecked(msg, Signature::new_bls(vec![])),
+                );
+                mset.added_at
+                    .insert(sequence, now - Duration::from_secs(age));
+            }
+            mset
+        };
+        // Alice is at sequence 10, with a replaced message, then a gap of 2
+        let alice_msgs = msg_set(
+            alice,
+            &[(9, 200, 10), (10, 200, 10), (11, 50, 100), (14, 200, 4000)],
+        );
+        // Bob is at sequence 0, with messages below the lowest base fee
+        let bob_msgs = msg_set(bob, &[(0, 5, 30), (1, 5, 30000)]);
+
+        let stat = MpoolStat::compute(
+            [(bob, 0, &bob_msgs), (alice, 10, &alice_msgs)],
+            &TokenAmount::from_atto(100),
+            &TokenAmount::from_atto(10),
+            now,
+        );
+        assert_eq!(
+            stat.senders,
+            vec![
+                SenderStat {
+                    address: alice,
+                    state_sequence: 10,
+                    past: 1,
+                    current: 2,
+                    future: 1,
+                    nonce_gap: 2,
+                    below_base_fee: 1,
+                    below_min_base_fee: 0,
+                    gas_limit: 400,
+                },
+                SenderStat {
+                    address: bob,
+                    state_sequence: 0,
+                    past: 0,
+                    current: 2,
+                    future: 0,
+                    nonce_gap: 0,
+                    below_base_fee: 2,
+                    below_min_base_fee: 2,
+                    gas_limit: 200,
+                },
+            ]
+        );
+        assert_eq!(
+            stat.ages.iter().map(|bucket| bucket.count).collect_vec(),
+            vec![3, 1, 0, 0, 1, 1]
+        );
+        assert_eq!(stat.top_senders, vec![alice, bob]);
+    }
+}
@@ -8,8 +8,9 @@ use std::{convert::TryFrom, sync::Arc};
 use crate::blocks::RawBlockHeader;
 use crate::blocks::VRFProof;
 use crate::blocks::{CachingBlockHeader, ElectionProof, Ticket, Tipset, TipsetKey};
-use crate::chain::HeadChange;
+use crate::chain::{AlignmentReport, HeadChange};
 use crate::cid_collections::CidHashMap;
+use crate::db::{MemoryDB, SettingsStore};
 use crate::message::{ChainMessage, Message as MessageTrait, SignedMessage};
 use crate::shim::{address::Address, econ::TokenAmount, message::Message, state_tree::ActorState};
 use ahash::HashMap;
@@ -27,6 +28,7 @@ use tokio::sync::broadcast::{Receiver as Subscriber, Sender as Publisher};
 pub struct TestApi {
     pub inner: Mutex<TestApiInner>,
     pub publisher: Publisher<HeadChange>,
+    pub settings: Arc<MemoryDB>,
 }
 
 #[derive(Default)]
@@ -48,6 +50,7 @@ impl Default for TestApi {
                 ..TestApiInner::default()
             }),
             publisher,
+            settings: Arc::default(),
         }
     }
 }
@@ -62,6 +65,7 @@ impl TestApi {
                 ..TestApiInner::default()
             }),
             publisher,
+            settings: Arc::default(),
         }
     }
 
@@ -205,6 +209,28 @@ impl Provider for TestApi {
     fn max_actor_pending_messages(&self) -> u64 {
         self.inner.lock().max_actor_pending_messages
     }
+
+    fn settings_store(&self) -> Arc<dyn SettingsStore + Send + Sync> {
+        self.settings.clone()
+    }
+
+    async fn has_receipt(&self, msg_cid: Cid) -> bool {
+        self.inner
+            .lock()
+            .bmsgs
+            .values()
+            .flatten()
+            .any(|m| m.cid() == msg_cid)
+    }
+
+    fn tip_alignment(
+        &self,
+        _pending: &[SignedMessage],
+        _head: &Arc<Tipset>,
+    ) -> Result<AlignmentReport, Error> {
+        // There is no state tree to check the nonces against
+        Ok(AlignmentReport::default())
+    }
 }
 
 pub fn create_header(weight: u64) -> CachingBlockHeader {
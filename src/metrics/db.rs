@@ -3,12 +3,17 @@
 
 use prometheus_client::{
     collector::Collector,
-    encoding::{DescriptorEncoder, EncodeMetric},
-    metrics::gauge::Gauge,
+    encoding::{DescriptorEncoder, EncodeLabelSet, EncodeMetric},
+    metrics::{family::Family, gauge::Gauge, TypedMetric as _},
 };
 use std::path::PathBuf;
 use tracing::error;
 
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct ColumnLabel {
+    column: String,
+}
+
 #[derive(Debug)]
 pub struct DBCollector {
     db_directory: PathBuf,
@@ -46,6 +51,24 @@ impl Collector for DBCollector {
             self.db_size.metric_type(),
         )?;
         self.db_size.encode(metric_encoder)?;
+
+        let column_size = Family::<ColumnLabel, Gauge>::default();
+        match crate::db::parity_db::column_disk_sizes(&self.db_directory) {
+            Ok(sizes) => {
+                for (column, size) in sizes {
+                    column_size
+                        .get_or_create(&ColumnLabel { column })
+                        .set(size as _);
+                }
+            }
+            Err(e) => error!("Calculating DB column sizes for metrics failed: {e}"),
+        }
+        column_size.encode(encoder.encode_descriptor(
+            "forest_db_column_size",
+            "Size of the files of each Forest database column in bytes",
+            None,
+            column_size.metric_type(),
+        )?)?;
         Ok(())
     }
 }
@@ -87,6 +87,9 @@ where
     DEFAULT_REGISTRY
         .write()
         .register_collector(Box::new(crate::metrics::db::DBCollector::new(db_directory)));
+    DEFAULT_REGISTRY
+        .write()
+        .register_collector(Box::new(crate::utils::cache::CacheCollector));
 
     // Create an configure HTTP server
     let app = Router::new()
@@ -146,6 +149,10 @@ impl KindLabel {
     pub const fn new(kind: &'static str) -> Self {
         Self { kind }
     }
+
+    pub const fn kind(&self) -> &'static str {
+        self.kind
+    }
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
@@ -166,6 +173,12 @@ pub mod values {
     pub const TIPSET: KindLabel = KindLabel::new("tipset");
     /// tipset cache in state manager
     pub const STATE_MANAGER_TIPSET: KindLabel = KindLabel::new("sm_tipset");
+    /// tipset events cache in state manager
+    pub const STATE_MANAGER_EVENTS: KindLabel = KindLabel::new("sm_events");
+    /// actors at the head state in state manager
+    pub const STATE_MANAGER_ACTOR: KindLabel = KindLabel::new("sm_actor");
+    /// tipset weight cache in chain store
+    pub const TIPSET_WEIGHT: KindLabel = KindLabel::new("tipset_weight");
 }
 
 pub fn default_histogram() -> Histogram {
@@ -0,0 +1,399 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Custom networks, defined by a TOML file loaded at runtime with
+//! `--chain custom --chain-config <file>`, for private devnets and test
+//! clusters. The definition builds on the devnet configuration and its actor
+//! bundles:
+//!
+//! ```toml
+//! name = "localnet"
+//! # Relative to the directory of the definition
+//! genesis = "genesis.car"
+//! genesis_network_version = 21
+//! block_delay_secs = 4
+//! bootstrap_peers = ["/ip4/10.0.0.1/tcp/1347/p2p/12D3KooW..."]
+//!
+//! [upgrades]
+//! Dragon = 10
+//! Waffle = 20
+//!
+//! [policy]
+//! minimum_consensus_power = 2048
+//! valid_pre_commit_proof_types = ["StackedDRG2KiBV1P1"]
+//! valid_post_proof_types = ["StackedDRGWindow2KiBV1P1"]
+//!
+//! [[drand]]
+//! height = 0
+//! network = "quicknet"
+//! ```
+//!
+//! Upgrades left out of `[upgrades]` are active from genesis if the genesis
+//! network version includes them, happen with a listed upgrade of the same
+//! network version, and are never scheduled otherwise.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr as _;
+
+use ahash::HashMap;
+use anyhow::{bail, ensure, Context as _};
+use itertools::Itertools as _;
+use libp2p::Multiaddr;
+use serde::Deserialize;
+use strum::IntoEnumIterator as _;
+
+use super::{
+    ChainConfig, DrandSchedulePoint, Height, HeightInfo, NetworkChain, RegisteredPoStProofV3,
+    RegisteredSealProofV3,
+};
+use crate::shim::clock::ChainEpoch;
+use crate::shim::version::NetworkVersion;
+
+/// Value of `--chain` selecting the network defined by `--chain-config`.
+pub const CUSTOM_NETWORK_CHAIN: &str = "custom";
+
+/// Epoch of the upgrades that never happen.
+const UNSCHEDULED_EPOCH: ChainEpoch = 9999999999;
+
+const SEAL_PROOFS: [RegisteredSealProofV3; 10] = [
+    RegisteredSealProofV3::StackedDRG2KiBV1P1,
+    RegisteredSealProofV3::StackedDRG8MiBV1P1,
+    RegisteredSealProofV3::StackedDRG512MiBV1P1,
+    RegisteredSealProofV3::StackedDRG32GiBV1P1,
+    RegisteredSealProofV3::StackedDRG64GiBV1P1,
+    RegisteredSealProofV3::StackedDRG2KiBV1P1_Feat_SyntheticPoRep,
+    RegisteredSealProofV3::StackedDRG8MiBV1P1_Feat_SyntheticPoRep,
+    RegisteredSealProofV3::StackedDRG512MiBV1P1_Feat_SyntheticPoRep,
+    RegisteredSealProofV3::StackedDRG32GiBV1P1_Feat_SyntheticPoRep,
+    RegisteredSealProofV3::StackedDRG64GiBV1P1_Feat_SyntheticPoRep,
+];
+
+const POST_PROOFS: [RegisteredPoStProofV3; 5] = [
+    RegisteredPoStProofV3::StackedDRGWindow2KiBV1P1,
+    RegisteredPoStProofV3::StackedDRGWindow8MiBV1P1,
+    RegisteredPoStProofV3::StackedDRGWindow512MiBV1P1,
+    RegisteredPoStProofV3::StackedDRGWindow32GiBV1P1,
+    RegisteredPoStProofV3::StackedDRGWindow64GiBV1P1,
+];
+
+/// The definition file, as written.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct NetworkFile {
+    name: String,
+    genesis: PathBuf,
+    genesis_network_version: u32,
+    block_delay_secs: Option<u32>,
+    propagation_delay_secs: Option<u32>,
+    eth_chain_id: Option<u64>,
+    #[serde(default)]
+    bootstrap_peers: Vec<String>,
+    #[serde(default)]
+    upgrades: BTreeMap<String, ChainEpoch>,
+    #[serde(default)]
+    policy: PolicyOverrides,
+    drand: Option<Vec<DrandSchedulePoint>>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct PolicyOverrides {
+    minimum_consensus_power: Option<u64>,
+    valid_pre_commit_proof_types: Option<Vec<String>>,
+    valid_post_proof_types: Option<Vec<String>>,
+}
+
+/// A network defined at runtime, see the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct CustomNetwork {
+    pub chain_config: ChainConfig,
+    /// The genesis CAR file
+    pub genesis: PathBuf,
+}
+
+impl CustomNetwork {
+    /// Loads and validates the definition at `path`.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read network definition {}", path.display()))?;
+        let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+        Self::parse(&content, base_dir)
+            .with_context(|| format!("invalid network definition {}", path.display()))
+    }
+
+    /// Parses and validates a definition, resolving the genesis file relative
+    /// to `base_dir`.
+    pub fn parse(content: &str, base_dir: &Path) -> anyhow::Result<Self> {
+        let file: NetworkFile = toml::from_str(content)?;
+
+        ensure!(!file.name.is_empty(), "network name must not be empty");
+        ensure!(
+            ![CUSTOM_NETWORK_CHAIN, "devnet"].contains(&file.name.as_str())
+                && matches!(NetworkChain::from_str(&file.name)?, NetworkChain::Devnet(_)),
+            "network name `{}` is reserved",
+            file.name
+        );
+
+        let mut chain_config = ChainConfig {
+            network: NetworkChain::Devnet(file.name.clone()),
+            genesis_cid: None,
+            ..ChainConfig::devnet()
+        };
+        chain_config.genesis_network = NetworkVersion::from(file.genesis_network_version);
+        chain_config.height_infos =
+            schedule_upgrades(&chain_config, &file.upgrades, chain_config.genesis_network)?;
+
+        if let Some(block_delay_secs) = file.block_delay_secs {
+            ensure!(block_delay_secs > 0, "block_delay_secs must be positive");
+            chain_config.block_delay_secs = block_delay_secs;
+        }
+        if let Some(propagation_delay_secs) = file.propagation_delay_secs {
+            chain_config.propagation_delay_secs = propagation_delay_secs;
+        }
+        if let Some(eth_chain_id) = file.eth_chain_id {
+            chain_config.eth_chain_id = eth_chain_id;
+        }
+        chain_config.bootstrap_peers = file
+            .bootstrap_peers
+            .iter()
+            .map(|peer| {
+                Multiaddr::from_str(peer).with_context(|| format!("invalid bootstrap peer {peer}"))
+            })
+            .try_collect()?;
+
+        let policy = &mut chain_config.policy;
+        if let Some(power) = file.policy.minimum_consensus_power {
+            policy.minimum_consensus_power = power.into();
+        }
+        if let Some(names) = &file.policy.valid_pre_commit_proof_types {
+            policy.valid_pre_commit_proof_type = Default::default();
+            for name in names {
+                policy
+                    .valid_pre_commit_proof_type
+                    .insert(proof_by_name(&SEAL_PROOFS, name)?);
+            }
+        }
+        if let Some(names) = &file.policy.valid_post_proof_types {
+            policy.valid_post_proof_type = Default::default();
+            for name in names {
+                policy
+                    .valid_post_proof_type
+                    .insert(proof_by_name(&POST_PROOFS, name)?);
+            }
+        }
+
+        if let Some(schedule) = &file.drand {
+            ensure!(
+                schedule.first().is_some_and(|point| point.height == 0),
+                "drand schedule must start at height 0"
+            );
+            ensure!(
+                schedule
+                    .iter()
+                    .tuple_windows()
+                    .all(|(a, b)| a.height < b.height),
+                "drand schedule heights must be increasing"
+            );
+        }
+        chain_config.drand_schedule = file.drand;
+
+        Ok(Self {
+            chain_config,
+            genesis: base_dir.join(file.genesis),
+        })
+    }
+}
+
+/// Returns the height infos of the devnet with the epochs of `upgrades`,
+/// scheduling the upgrades left out after the network version of `genesis`.
+fn schedule_upgrades(
+    devnet: &ChainConfig,
+    upgrades: &BTreeMap<String, ChainEpoch>,
+    genesis: NetworkVersion,
+) -> anyhow::Result<HashMap<Height, HeightInfo>> {
+    // Heights with a sentinel epoch are markers, not upgrades to schedule
+    let supported = Height::iter()
+        .filter(|height| {
+            devnet
+                .height_infos
+                .get(height)
+                .is_some_and(|info| info.epoch != ChainEpoch::MIN)
+        })
+        .collect_vec();
+    let max_version = supported
+        .iter()
+        .map(|&height| NetworkVersion::from(height))
+        .max()
+        .context("no supported upgrades")?;
+    ensure!(
+        genesis <= max_version,
+        "genesis network version {} is above the latest supported {}",
+        u32::from(genesis.0),
+        u32::from(max_version.0)
+    );
+
+    let mut listed = vec![];
+    for (name, &epoch) in upgrades {
+        let height = supported
+            .iter()
+            .find(|height| height.to_string().eq_ignore_ascii_case(name))
+            .with_context(|| {
+                format!(
+                    "unknown upgrade `{name}`, expected one of: {}",
+                    supported.iter().join(", ")
+                )
+            })?;
+        if NetworkVersion::from(*height) <= genesis {
+            ensure!(
+                epoch < 0,
+                "upgrade {height} is included by the genesis network version, its epoch must be negative"
+            );
+        } else {
+            ensure!(
+                epoch >= 0,
+                "upgrade {height} is after the genesis network version, its epoch must not be negative"
+            );
+        }
+        listed.push((*height, epoch));
+    }
+
+    let mut height_infos = devnet.height_infos.clone();
+    for height in supported.iter() {
+        let version = NetworkVersion::from(*height);
+        let epoch = listed
+            .iter()
+            .find(|(listed, _)| listed == height)
+            .or_else(|| {
+                listed
+                    .iter()
+                    .find(|(listed, _)| NetworkVersion::from(*listed) == version)
+            })
+            .map(|(_, epoch)| *epoch)
+            .unwrap_or_else(|| {
+                if version > genesis {
+                    UNSCHEDULED_EPOCH
+                } else {
+                    devnet.epoch(*height).min(-1)
+                }
+            });
+        if let Some(info) = height_infos.get_mut(height) {
+            info.epoch = epoch;
+        }
+    }
+
+    // Upgrades happen in the order of their network versions, the ones
+    // active from genesis in any order
+    for (a, b) in supported.iter().tuple_windows() {
+        let (epoch_a, epoch_b) = (height_infos[a].epoch.max(-1), height_infos[b].epoch.max(-1));
+        if epoch_a > epoch_b {
+            bail!(
+                "upgrade {b} at epoch {epoch_b} is scheduled before upgrade {a} at epoch {epoch_a}"
+            );
+        }
+    }
+    Ok(height_infos)
+}
+
+fn proof_by_name<P: std::fmt::Debug + Copy>(proofs: &[P], name: &str) -> anyhow::Result<P> {
+    proofs
+        .iter()
+        .find(|proof| format!("{proof:?}") == name)
+        .copied()
+        .with_context(|| {
+            format!(
+                "unknown proof type `{name}`, expected one of: {}",
+                proofs.iter().map(|proof| format!("{proof:?}")).join(", ")
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beacon::DrandNetwork;
+
+    const SAMPLE: &str = r#"
+        name = "localnet"
+        genesis = "genesis.car"
+        genesis_network_version = 21
+        block_delay_secs = 2
+        bootstrap_peers = ["/ip4/127.0.0.1/tcp/1347"]
+
+        [upgrades]
+        Dragon = 10
+        Waffle = 20
+        TukTuk = 30
+
+        [policy]
+        minimum_consensus_power = 1024
+        valid_pre_commit_proof_types = ["StackedDRG2KiBV1P1"]
+        valid_post_proof_types = ["StackedDRGWindow2KiBV1P1"]
+
+        [[drand]]
+        height = 0
+        network = "quicknet"
+    "#;
+
+    #[test]
+    fn custom_network_test() {
+        let network = CustomNetwork::parse(SAMPLE, Path::new("/networks")).unwrap();
+        assert_eq!(network.genesis, Path::new("/networks/genesis.car"));
+
+        let config = &network.chain_config;
+        assert_eq!(config.network, NetworkChain::Devnet("localnet".into()));
+        assert_eq!(config.block_delay_secs, 2);
+        assert_eq!(config.bootstrap_peers.len(), 1);
+        assert_eq!(config.policy.minimum_consensus_power, 1024.into());
+        assert_eq!(
+            config
+                .policy
+                .valid_pre_commit_proof_type
+                .clone()
+                .into_inner()
+                .len(),
+            1
+        );
+        assert_eq!(
+            config.drand_schedule,
+            Some(vec![DrandSchedulePoint {
+                height: 0,
+                network: DrandNetwork::Quicknet
+            }])
+        );
+
+        for (epoch, version) in [
+            (0, NetworkVersion::V21),
+            (10, NetworkVersion::V21),
+            (11, NetworkVersion::V22),
+            (25, NetworkVersion::V23),
+            (31, NetworkVersion::V24),
+            (1_000_000, NetworkVersion::V24),
+        ] {
+            assert_eq!(config.network_version(epoch), version, "epoch {epoch}");
+        }
+    }
+
+    #[test]
+    fn custom_network_errors() {
+        let with_upgrades = |upgrades: &str| {
+            SAMPLE.replace(
+                "Dragon = 10\n        Waffle = 20\n        TukTuk = 30",
+                upgrades,
+            )
+        };
+        let error = |content: &str| {
+            CustomNetwork::parse(content, Path::new("."))
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert!(error(&with_upgrades("Breakfast = 10")).contains("unknown upgrade `Breakfast`"));
+        assert!(error(&with_upgrades("Dragon = 20\n Waffle = 10")).contains("scheduled before"));
+        assert!(error(&with_upgrades("Watermelon = 10")).contains("must be negative"));
+        assert!(error(&SAMPLE.replace("localnet", "calibnet")).contains("reserved"));
+        assert!(error(&SAMPLE.replace("StackedDRG2KiB", "StackedDRG3KiB"))
+            .contains("unknown proof type `StackedDRG3KiBV1P1`"));
+        assert!(error(&SAMPLE.replace("height = 0", "height = 5")).contains("start at height 0"));
+    }
+}
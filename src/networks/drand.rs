@@ -66,6 +66,15 @@ pub(super) static DRAND_INCENTINET: Lazy<DrandConfig<'static>> = Lazy::new(|| {
     }
 });
 
+/// Returns the configuration of a `drand` network.
+pub(super) fn config(network: DrandNetwork) -> &'static Lazy<DrandConfig<'static>> {
+    match network {
+        DrandNetwork::Mainnet => &DRAND_MAINNET,
+        DrandNetwork::Quicknet => &DRAND_QUICKNET,
+        DrandNetwork::Incentinet => &DRAND_INCENTINET,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use url::Url;
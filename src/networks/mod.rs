@@ -13,7 +13,7 @@ use serde::{Deserialize, Serialize};
 use strum_macros::Display;
 use tracing::warn;
 
-use crate::beacon::{BeaconPoint, BeaconSchedule, DrandBeacon, DrandConfig};
+use crate::beacon::{BeaconPoint, BeaconSchedule, DrandBeacon, DrandConfig, DrandNetwork};
 use crate::db::SettingsStore;
 use crate::eth::EthChainId;
 use crate::shim::clock::{ChainEpoch, EPOCHS_IN_DAY, EPOCH_DURATION_SECONDS};
@@ -28,6 +28,7 @@ pub use actors_bundle::{
     ACTOR_BUNDLES_METADATA,
 };
 
+mod custom;
 mod drand;
 
 pub mod butterflynet;
@@ -37,6 +38,8 @@ pub mod mainnet;
 
 pub mod metrics;
 
+pub use custom::{CustomNetwork, CUSTOM_NETWORK_CHAIN};
+
 /// Newest network version for all networks
 pub const NEWEST_NETWORK_VERSION: NetworkVersion = NetworkVersion::V17;
 
@@ -110,7 +113,20 @@ impl NetworkChain {
 }
 
 /// Defines the meaningful heights of the protocol.
-#[derive(Debug, Display, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[derive(
+    Debug,
+    Display,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    strum::EnumString, // impl std::str::FromStr
+    strum::EnumIter,
+)]
+#[strum(ascii_case_insensitive)]
 #[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
 pub enum Height {
     Breeze,
@@ -199,6 +215,14 @@ pub struct HeightInfo {
     pub bundle: Option<Cid>,
 }
 
+/// A `drand` network used from `height` on, see [`ChainConfig::drand_schedule`].
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+#[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
+pub struct DrandSchedulePoint {
+    pub height: ChainEpoch,
+    pub network: DrandNetwork,
+}
+
 #[derive(Clone)]
 struct DrandPoint<'a> {
     pub height: ChainEpoch,
@@ -238,6 +262,8 @@ pub struct ChainConfig {
     // This will likely be deprecated once F3 is fully bootstrapped to avoid single point network dependencies.
     #[cfg_attr(test, arbitrary(gen(|_| Some(libp2p::PeerId::random()))))]
     pub f3_manifest_server: Option<libp2p::PeerId>,
+    // Replaces the built-in `drand` schedule of the network, for custom networks.
+    pub drand_schedule: Option<Vec<DrandSchedulePoint>>,
 }
 
 impl ChainConfig {
@@ -268,6 +294,7 @@ impl ChainConfig {
                     .parse()
                     .expect("Invalid PeerId"),
             ),
+            drand_schedule: None,
         }
     }
 
@@ -304,6 +331,7 @@ impl ChainConfig {
                     .parse()
                     .expect("Invalid PeerId"),
             ),
+            drand_schedule: None,
         }
     }
 
@@ -327,6 +355,7 @@ impl ChainConfig {
             f3_bootstrap_epoch: -1,
             f3_initial_power_table: Default::default(),
             f3_manifest_server: None,
+            drand_schedule: None,
         }
     }
 
@@ -360,6 +389,7 @@ impl ChainConfig {
                     .parse()
                     .expect("Invalid PeerId"),
             ),
+            drand_schedule: None,
         }
     }
 
@@ -389,11 +419,21 @@ impl ChainConfig {
     }
 
     pub fn get_beacon_schedule(&self, genesis_ts: u64) -> BeaconSchedule {
-        let ds_iter = match self.network {
-            NetworkChain::Mainnet => mainnet::DRAND_SCHEDULE.iter(),
-            NetworkChain::Calibnet => calibnet::DRAND_SCHEDULE.iter(),
-            NetworkChain::Butterflynet => butterflynet::DRAND_SCHEDULE.iter(),
-            NetworkChain::Devnet(_) => devnet::DRAND_SCHEDULE.iter(),
+        let custom_schedule = self.drand_schedule.as_ref().map(|schedule| {
+            schedule
+                .iter()
+                .map(|point| DrandPoint {
+                    height: point.height,
+                    config: drand::config(point.network),
+                })
+                .collect_vec()
+        });
+        let ds_iter = match (&custom_schedule, &self.network) {
+            (Some(schedule), _) => schedule.iter(),
+            (None, NetworkChain::Mainnet) => mainnet::DRAND_SCHEDULE.iter(),
+            (None, NetworkChain::Calibnet) => calibnet::DRAND_SCHEDULE.iter(),
+            (None, NetworkChain::Butterflynet) => butterflynet::DRAND_SCHEDULE.iter(),
+            (None, NetworkChain::Devnet(_)) => devnet::DRAND_SCHEDULE.iter(),
         };
 
         BeaconSchedule(
@@ -3,7 +3,7 @@
 
 use crate::auth::{verify_token, JWT_IDENTIFIER};
 use crate::key_management::KeyStore;
-use crate::rpc::{chain, Permission, RpcMethod as _, CANCEL_METHOD_NAME};
+use crate::rpc::{chain, misc, Permission, RpcMethod as _, CANCEL_METHOD_NAME};
 use ahash::{HashMap, HashMapExt as _};
 use futures::future::BoxFuture;
 use futures::FutureExt;
@@ -35,6 +35,7 @@ static METHOD_NAME2REQUIRED_PERMISSION: Lazy<HashMap<&str, Permission>> = Lazy::
     super::for_each_rpc_method!(insert);
 
     access.insert(chain::CHAIN_NOTIFY, Permission::Read);
+    access.insert(misc::SUBSCRIBE_ACTOR_EVENTS, Permission::Read);
     access.insert(CANCEL_METHOD_NAME, Permission::Read);
 
     access
@@ -162,6 +162,12 @@ impl PendingSubscriptionSink {
         }
     }
 
+    /// Rejects the subscription call with an error.
+    pub fn reject(self, error: ServerError) {
+        let error: ErrorObjectOwned = error.into();
+        let _ = self.subscribe.send(MethodResponse::error(self.id, error));
+    }
+
     /// Returns the channel identifier
     pub fn channel_id(&self) -> ChannelId {
         self.channel_id
@@ -344,12 +350,21 @@ impl RpcModule {
         callback: F,
     ) -> Result<&mut MethodCallback, RegisterMethodError>
     where
-        F: (Fn(Params) -> tokio::sync::broadcast::Receiver<R>) + Send + Sync + 'static,
+        F: (Fn(Params) -> Result<tokio::sync::broadcast::Receiver<R>, ServerError>)
+            + Send
+            + Sync
+            + 'static,
         R: serde::Serialize + Clone + Send + 'static,
     {
         self.register_channel_raw(subscribe_method_name, {
             move |params, pending| {
-                let mut receiver = callback(params);
+                let mut receiver = match callback(params) {
+                    Ok(receiver) => receiver,
+                    Err(e) => {
+                        pending.reject(e);
+                        return;
+                    }
+                };
                 tokio::spawn(async move {
                     let sink = pending.accept().await.unwrap();
                     tracing::debug!("Channel created: chann_id={}", sink.channel_id);
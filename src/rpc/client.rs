@@ -8,14 +8,16 @@
 //!   - endpoint paths (`v0`, `v1`).
 //!   - communication protocols (`ws`, `http`).
 //! - Support per-request timeouts.
+//! - Support typed subscriptions to channels, e.g. `Filecoin.ChainNotify`.
 
 use std::env;
 use std::fmt::{self, Debug};
 use std::time::Duration;
 
 use anyhow::bail;
+use futures::{Stream, StreamExt as _};
 use http::{header, HeaderMap, HeaderValue};
-use jsonrpsee::core::client::ClientT as _;
+use jsonrpsee::core::client::{ClientT as _, SubscriptionClientT as _};
 use jsonrpsee::core::params::{ArrayParams, ObjectParams};
 use jsonrpsee::core::ClientError;
 use once_cell::sync::Lazy;
@@ -23,6 +25,8 @@ use serde::de::DeserializeOwned;
 use tracing::{debug, Instrument, Level};
 use url::Url;
 
+use super::chain::{ApiHeadChange, CHAIN_NOTIFY};
+use super::channel::{ChannelId, NOTIF_METHOD_NAME};
 use super::{ApiPath, ApiPaths, Request, MAX_REQUEST_BODY_SIZE, MAX_RESPONSE_BODY_SIZE};
 
 /// A JSON-RPC client that can dispatch either a [`crate::rpc::Request`] to a single URL.
@@ -129,6 +133,61 @@ impl Client {
         };
         work.instrument(span.or_current()).await
     }
+
+    /// Opens a channel with the subscription method `method_name` and returns
+    /// the stream of values pushed to it. Each subscription uses its own
+    /// websocket connection to the `v1` endpoint, closed when the stream is
+    /// dropped.
+    pub async fn subscribe<T: crate::lotus_json::HasLotusJson>(
+        &self,
+        method_name: &str,
+        params: ArrayParams,
+    ) -> Result<impl Stream<Item = Result<T, ClientError>>, ClientError> {
+        let mut url = self
+            .base_url
+            .join("rpc/v1")
+            .map_err(|it| ClientError::Custom(format!("creating url for endpoint failed: {it}")))?;
+        let scheme = match url.scheme() {
+            "http" => "ws",
+            "https" => "wss",
+            other => other,
+        }
+        .to_owned();
+        if url.set_scheme(&scheme).is_err() {
+            return Err(ClientError::Custom(format!(
+                "Unsupported URL scheme: {scheme}"
+            )));
+        }
+        let client = UrlClient::new(url, self.token.clone()).await?;
+        // Listen to the channel values before opening it, not to miss the first ones
+        let values = client
+            .subscribe_to_method::<(ChannelId, serde_json::Value)>(NOTIF_METHOD_NAME)
+            .await?;
+        let channel_id: ChannelId = client.request(method_name, params).await?;
+        debug!(method = %method_name, %channel_id, "opened channel");
+        Ok(values.filter_map(move |value| {
+            // The connection must live as long as the stream
+            let _client = &client;
+            std::future::ready(match value {
+                Ok((id, value)) if id == channel_id => Some(
+                    serde_json::from_value::<T::LotusJson>(value)
+                        .map(T::from_lotus_json)
+                        .map_err(ClientError::ParseError),
+                ),
+                Ok(_) => None,
+                Err(e) => Some(Err(ClientError::ParseError(e))),
+            })
+        }))
+    }
+
+    /// Subscribes to the head changes of the node, starting with its current
+    /// head.
+    pub async fn chain_notify(
+        &self,
+    ) -> Result<impl Stream<Item = Result<Vec<ApiHeadChange>, ClientError>>, ClientError> {
+        self.subscribe(CHAIN_NOTIFY, ArrayParams::new()).await
+    }
+
     async fn get_or_init_client(&self, version: ApiPaths) -> Result<&UrlClient, ClientError> {
         let path = ApiPaths::max(&version);
         match path {
@@ -23,6 +23,9 @@ pub(crate) mod implementation_defined_errors {
     /// node. Note that it's not the same as not found, as we are explicitly not supporting it,
     /// e.g., because it's deprecated or Lotus is doing the same.
     pub(crate) const UNSUPPORTED_METHOD: i32 = -32001;
+    /// This error indicates that the method requires state, which is not computed when the
+    /// node runs in lite mode.
+    pub(crate) const UNAVAILABLE_IN_LITE_MODE: i32 = -32002;
 }
 
 impl ServerError {
@@ -61,6 +64,14 @@ impl ServerError {
             Some("This method is not supported by the current version of the Forest node".into()),
         )
     }
+
+    pub fn unavailable_in_lite_mode() -> Self {
+        Self::new(
+            implementation_defined_errors::UNAVAILABLE_IN_LITE_MODE,
+            "not available in lite mode",
+            Some("This method requires state, which is not computed in lite mode".into()),
+        )
+    }
 }
 
 impl Display for ServerError {
@@ -108,6 +119,14 @@ macro_rules! from2internal {
     };
 }
 
+/// Registry errors are caused by the parameters of the request, i.e. an unknown
+/// actor or method, or parameters that cannot be converted.
+impl From<crate::rpc::registry::RegistryError> for ServerError {
+    fn from(it: crate::rpc::registry::RegistryError) -> Self {
+        Self::invalid_params(it, None)
+    }
+}
+
 // TODO(forest): https://github.com/ChainSafe/forest/issues/3965
 //               Just mapping everything to an internal error is not appropriate
 from2internal! {
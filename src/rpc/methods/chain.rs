@@ -17,12 +17,15 @@ use crate::lotus_json::{lotus_json_with_self, HasLotusJson, LotusJson};
 use crate::message::{ChainMessage, SignedMessage};
 use crate::rpc::types::ApiTipsetKey;
 use crate::rpc::{ApiPaths, Ctx, Permission, RpcMethod, ServerError};
+use crate::shim::address::Address;
 use crate::shim::clock::ChainEpoch;
+use crate::shim::econ::TokenAmount;
 use crate::shim::error::ExitCode;
 use crate::shim::executor::Receipt;
 use crate::shim::message::Message;
 use crate::utils::db::CborStoreExt as _;
-use crate::utils::io::VoidAsyncWriter;
+use crate::utils::io::{AsyncWriterWithChecksum, Checksum as _, VoidAsyncWriter};
+use crate::utils::misc::env::env_or_default;
 use anyhow::{Context as _, Result};
 use cid::Cid;
 use fvm_ipld_blockstore::Blockstore;
@@ -36,7 +39,12 @@ use once_cell::sync::Lazy;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use sha2::Sha256;
-use std::{any::Any, collections::VecDeque, path::PathBuf, sync::Arc};
+use std::{
+    any::Any,
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 use tokio::sync::{
     broadcast::{self, Receiver as Subscriber},
     Mutex,
@@ -171,6 +179,27 @@ impl RpcMethod<1> for ChainGetMessagesInTipset {
     }
 }
 
+/// Returns the messages of each block of a tipset. Messages included by
+/// several blocks are only returned for the first of them.
+pub enum ChainGetTipsetBlockMessages {}
+impl RpcMethod<1> for ChainGetTipsetBlockMessages {
+    const NAME: &'static str = "Filecoin.ChainGetTipsetBlockMessages";
+    const PARAM_NAMES: [&'static str; 1] = ["tsk"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (ApiTipsetKey,);
+    type Ok = Vec<TipsetBlockMessages>;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore>,
+        (ApiTipsetKey(tsk),): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let tipset = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        Ok(load_tipset_block_messages(ctx.store(), &tipset)?)
+    }
+}
+
 pub enum ChainExport {}
 impl RpcMethod<1> for ChainExport {
     const NAME: &'static str = "Filecoin.ChainExport";
@@ -192,6 +221,9 @@ impl RpcMethod<1> for ChainExport {
             tipset_keys: ApiTipsetKey(tsk),
             skip_checksum,
             dry_run,
+            include_state,
+            car_version,
+            parallel,
         } = params;
 
         static LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
@@ -209,19 +241,57 @@ impl RpcMethod<1> for ChainExport {
             .into());
         }
 
+        if !matches!(car_version, 1 | 2) {
+            return Err(anyhow::anyhow!("unsupported CAR version {car_version}").into());
+        }
+
+        let include_state = match include_state {
+            Some((from, to)) if from > to => {
+                return Err(anyhow::anyhow!("invalid state range {from}:{to}").into())
+            }
+            Some((from, to)) => Some(from..=to),
+            None => None,
+        };
+
         let head = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
         let start_ts =
             ctx.chain_index()
                 .tipset_by_height(epoch, head, ResolveNullTipset::TakeOlder)?;
 
+        if car_version == 2 && !dry_run {
+            let file = tokio::fs::File::create(&output_path).await?;
+            crate::chain::export_v2(
+                ctx.store_owned(),
+                &start_ts,
+                recent_roots,
+                include_state,
+                file,
+                CidHashSet::default(),
+                !parallel,
+            )
+            .await?;
+            if skip_checksum {
+                return Ok(None);
+            }
+            // The CARv2 header is written last, so hash the complete file
+            let mut hasher = AsyncWriterWithChecksum::<Sha256, _>::new(
+                tokio::io::BufWriter::new(VoidAsyncWriter),
+                true,
+            );
+            tokio::io::copy(&mut tokio::fs::File::open(&output_path).await?, &mut hasher).await?;
+            return Ok(hasher.finalize()?.map(|hash| hash.encode_hex()));
+        }
+
         match if dry_run {
             crate::chain::export::<Sha256>(
                 ctx.store_owned(),
                 &start_ts,
                 recent_roots,
+                include_state,
                 VoidAsyncWriter,
                 CidHashSet::default(),
                 skip_checksum,
+                !parallel,
             )
             .await
         } else {
@@ -230,9 +300,11 @@ impl RpcMethod<1> for ChainExport {
                 ctx.store_owned(),
                 &start_ts,
                 recent_roots,
+                include_state,
                 file,
                 CidHashSet::default(),
                 skip_checksum,
+                !parallel,
             )
             .await
         } {
@@ -242,6 +314,69 @@ impl RpcMethod<1> for ChainExport {
     }
 }
 
+/// Blocks exported at most by [`ForestExportSubgraph`], overridden by the
+/// `FOREST_EXPORT_SUBGRAPH_MAX_BLOCKS` environment variable.
+const DEFAULT_EXPORT_SUBGRAPH_MAX_BLOCKS: u64 = 1_000_000;
+
+pub enum ForestExportSubgraph {}
+impl RpcMethod<4> for ForestExportSubgraph {
+    const NAME: &'static str = "Forest.ExportSubgraph";
+    const PARAM_NAMES: [&'static str; 4] =
+        ["root", "depth_limit", "links_per_block", "output_path"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Admin;
+
+    type Params = (Cid, Option<u32>, Option<usize>, PathBuf);
+    type Ok = u64;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (root, depth_limit, links_per_block, output_path): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let limits = crate::chain::SubgraphLimits {
+            depth_limit,
+            links_per_block,
+            max_blocks: env_or_default(
+                "FOREST_EXPORT_SUBGRAPH_MAX_BLOCKS",
+                DEFAULT_EXPORT_SUBGRAPH_MAX_BLOCKS,
+            ),
+        };
+        // Written next to the output, which is only replaced once complete
+        let output_dir = match output_path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let temp_path = tempfile::NamedTempFile::new_in(output_dir)?.into_temp_path();
+        let file = tokio::fs::File::create(&temp_path).await?;
+        let blocks = crate::chain::export_subgraph(ctx.store(), root, limits, file).await?;
+        temp_path.persist(&output_path).map_err(|e| e.error)?;
+        Ok(blocks)
+    }
+}
+
+/// Returns the proof that a block of a tipset includes a message, from the
+/// `Messages` root of its header down to the message CID.
+pub enum ForestMessageInclusionProof {}
+impl RpcMethod<2> for ForestMessageInclusionProof {
+    const NAME: &'static str = "Forest.MessageInclusionProof";
+    const PARAM_NAMES: [&'static str; 2] = ["tsk", "msg_cid"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (ApiTipsetKey, Cid);
+    type Ok = crate::chain::MessageInclusionProof;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore>,
+        (ApiTipsetKey(tsk), msg_cid): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let tipset = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        Ok(ctx
+            .chain_store()
+            .message_inclusion_proof(&tipset, &msg_cid)?)
+    }
+}
+
 pub enum ChainReadObj {}
 impl RpcMethod<1> for ChainReadObj {
     const NAME: &'static str = "Filecoin.ChainReadObj";
@@ -503,6 +638,24 @@ impl RpcMethod<0> for ChainGetGenesis {
     }
 }
 
+/// Returns the details of the genesis state. Unlike
+/// [`ChainGetGenesis`], which returns the Lotus-compatible genesis tipset.
+pub enum ForestChainGetGenesisInfo {}
+impl RpcMethod<0> for ForestChainGetGenesisInfo {
+    const NAME: &'static str = "Forest.ChainGetGenesisInfo";
+    const PARAM_NAMES: [&'static str; 0] = [];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = ();
+    type Ok = ApiGenesisInfo;
+
+    async fn handle(ctx: Ctx<impl Blockstore>, (): Self::Params) -> Result<Self::Ok, ServerError> {
+        let info = ctx.state_manager.get_genesis_info()?;
+        Ok(ApiGenesisInfo::from(info.as_ref()))
+    }
+}
+
 pub enum ChainHead {}
 impl RpcMethod<0> for ChainHead {
     const NAME: &'static str = "Filecoin.ChainHead";
@@ -603,18 +756,59 @@ impl RpcMethod<1> for ChainGetMinBaseFee {
         ctx: Ctx<impl Blockstore>,
         (lookback,): Self::Params,
     ) -> Result<Self::Ok, ServerError> {
-        let mut current = ctx.chain_store().heaviest_tipset();
-        let mut min_base_fee = current.block_headers().first().parent_base_fee.clone();
+        Ok(min_base_fee(&ctx, lookback)?.atto().to_string())
+    }
+}
 
-        for _ in 0..lookback {
-            let parents = &current.block_headers().first().parents;
-            current = ctx.chain_index().load_required_tipset(parents)?;
+/// Returns the lowest base fee of the heaviest tipset and its `lookback`
+/// ancestors.
+pub(super) fn min_base_fee(
+    ctx: &Ctx<impl Blockstore>,
+    lookback: u32,
+) -> Result<TokenAmount, ServerError> {
+    let mut current = ctx.chain_store().heaviest_tipset();
+    let mut min_base_fee = current.block_headers().first().parent_base_fee.clone();
 
-            min_base_fee =
-                min_base_fee.min(current.block_headers().first().parent_base_fee.to_owned());
-        }
+    for _ in 0..lookback {
+        let parents = &current.block_headers().first().parents;
+        current = ctx.chain_index().load_required_tipset(parents)?;
 
-        Ok(min_base_fee.atto().to_string())
+        min_base_fee = min_base_fee.min(current.block_headers().first().parent_base_fee.to_owned());
+    }
+
+    Ok(min_base_fee)
+}
+
+/// Maximum number of epochs covered by a single `Filecoin.ChainBaseFeeHistory` request.
+const MAX_BASE_FEE_HISTORY_RANGE: ChainEpoch = 20160;
+
+pub enum ChainBaseFeeHistory {}
+impl RpcMethod<2> for ChainBaseFeeHistory {
+    const NAME: &'static str = "Filecoin.ChainBaseFeeHistory";
+    const PARAM_NAMES: [&'static str; 2] = ["from", "to"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (ChainEpoch, ChainEpoch);
+    type Ok = Vec<(ChainEpoch, TokenAmount)>;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore>,
+        (from, to): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        if from > to {
+            return Err(anyhow::anyhow!("invalid range: {from} > {to}").into());
+        }
+        if to
+            .checked_sub(from)
+            .is_none_or(|range| range >= MAX_BASE_FEE_HISTORY_RANGE)
+        {
+            return Err(anyhow::anyhow!(
+                "range too large: at most {MAX_BASE_FEE_HISTORY_RANGE} epochs per request"
+            )
+            .into());
+        }
+        Ok(ctx.chain_store().get_fee_range(from, to)?)
     }
 }
 
@@ -632,9 +826,8 @@ impl RpcMethod<1> for ChainTipSetWeight {
         ctx: Ctx<impl Blockstore>,
         (ApiTipsetKey(tsk),): Self::Params,
     ) -> Result<Self::Ok, ServerError> {
-        let tsk = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
-        let weight = crate::fil_cns::weight(ctx.store(), &tsk)?;
-        Ok(weight)
+        let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        Ok(ctx.chain_store().compute_chain_weight_at(&ts, ts.epoch())?)
     }
 }
 
@@ -715,6 +908,44 @@ fn load_api_messages_from_tipset(
     Ok(messages)
 }
 
+fn load_tipset_block_messages(
+    store: &impl Blockstore,
+    tipset: &Tipset,
+) -> anyhow::Result<Vec<TipsetBlockMessages>> {
+    let mut seen = CidHashSet::default();
+    tipset
+        .block_headers()
+        .iter()
+        .map(|block| {
+            let (bls_cids, secp_cids) = crate::chain::read_msg_cids(store, &block.messages)?;
+            let bls_cids: Vec<_> = bls_cids
+                .into_iter()
+                .filter(|cid| seen.insert(*cid))
+                .collect();
+            let secp_cids: Vec<_> = secp_cids
+                .into_iter()
+                .filter(|cid| seen.insert(*cid))
+                .collect();
+            let (bls_msg, secp_msg) =
+                crate::chain::block_messages_from_cids(store, &bls_cids, &secp_cids)?;
+            Ok(TipsetBlockMessages {
+                block_cid: *block.cid(),
+                miner: block.miner_address,
+                win_count: block
+                    .election_proof
+                    .as_ref()
+                    .map(|e| e.win_count)
+                    .unwrap_or_default(),
+                messages: BlockMessages {
+                    bls_msg,
+                    secp_msg,
+                    cids: bls_cids.into_iter().chain(secp_cids).collect(),
+                },
+            })
+        })
+        .collect()
+}
+
 #[derive(Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct BlockMessages {
     #[serde(rename = "BlsMessages", with = "crate::lotus_json")]
@@ -729,6 +960,22 @@ pub struct BlockMessages {
 }
 lotus_json_with_self!(BlockMessages);
 
+/// The messages a block of a tipset contributes to it.
+#[derive(Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct TipsetBlockMessages {
+    #[serde(with = "crate::lotus_json")]
+    #[schemars(with = "LotusJson<Cid>")]
+    pub block_cid: Cid,
+    #[serde(with = "crate::lotus_json")]
+    #[schemars(with = "LotusJson<Address>")]
+    pub miner: Address,
+    pub win_count: i64,
+    #[serde(flatten)]
+    pub messages: BlockMessages,
+}
+lotus_json_with_self!(TipsetBlockMessages);
+
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, JsonSchema)]
 #[serde(rename_all = "PascalCase")]
 pub struct ApiReceipt {
@@ -770,9 +1017,26 @@ pub struct ChainExportParams {
     pub tipset_keys: ApiTipsetKey,
     pub skip_checksum: bool,
     pub dry_run: bool,
+    /// Inclusive epoch range whose state trees are exported in addition to
+    /// the `recent_roots` most recent ones.
+    #[serde(default)]
+    pub include_state: Option<(ChainEpoch, ChainEpoch)>,
+    /// Version of the CAR format: `1` for a `.forest.car.zst` file, `2` for
+    /// an uncompressed, indexed CARv2 file.
+    #[serde(default = "default_car_version")]
+    pub car_version: u64,
+    /// Walks the graph with all the CPUs, in an order that differs from an
+    /// export to the next. By default, the graph is walked by a single task
+    /// so that exporting the same tipset twice writes the same file.
+    #[serde(default)]
+    pub parallel: bool,
 }
 lotus_json_with_self!(ChainExportParams);
 
+fn default_car_version() -> u64 {
+    1
+}
+
 #[derive(PartialEq, Debug, Serialize, Deserialize, Clone, JsonSchema)]
 #[serde(rename_all = "PascalCase")]
 pub struct ApiHeadChange {
@@ -874,7 +1138,7 @@ mod tests {
     use PathChange::{Apply, Revert};
 
     use crate::{
-        blocks::{chain4u, Chain4U, RawBlockHeader},
+        blocks::{chain4u, Chain4U, HeaderBuilder, RawBlockHeader},
         db::{car::PlainCar, MemoryDB},
         networks::{self, ChainConfig},
     };
@@ -987,6 +1251,60 @@ mod tests {
         let _ = (a, c1);
     }
 
+    #[test]
+    fn tipset_block_messages_dedup() {
+        use crate::chain_sync::TipsetValidator;
+
+        let db = MemoryDB::default();
+        let message = |sequence| {
+            let message = Message {
+                from: Address::new_id(1000),
+                to: Address::new_id(1001),
+                sequence,
+                ..Default::default()
+            };
+            db.put_cbor_default(&message).unwrap()
+        };
+        let (m0, m1, m2) = (message(0), message(1), message(2));
+        let root_a =
+            TipsetValidator::compute_msg_root_from_cids(&db, vec![m0, m1], vec![]).unwrap();
+        let root_b =
+            TipsetValidator::compute_msg_root_from_cids(&db, vec![m1, m2], vec![]).unwrap();
+        let c4u = Chain4U::with_blockstore(&db);
+        chain4u! {
+            in c4u;
+            [_genesis] -> t1 @ [
+                a = HeaderBuilder::new()
+                    .with_miner_address(Address::new_id(1))
+                    .with_messages(root_a),
+                b = HeaderBuilder::new()
+                    .with_miner_address(Address::new_id(2))
+                    .with_messages(root_b)
+            ]
+        };
+
+        let blocks = load_tipset_block_messages(&db, t1).unwrap();
+        assert_eq!(
+            blocks
+                .iter()
+                .map(|block| (block.block_cid, block.miner))
+                .collect::<Vec<_>>(),
+            t1.block_headers()
+                .iter()
+                .map(|header| (*header.cid(), header.miner_address))
+                .collect::<Vec<_>>()
+        );
+        // `m1` is included by both blocks, but only returned for the first
+        let [first, second] = blocks.as_slice() else {
+            panic!("expected two blocks");
+        };
+        assert!(first.messages.cids.contains(&m1));
+        assert!(!second.messages.cids.contains(&m1));
+        assert_eq!(first.messages.cids.len() + second.messages.cids.len(), 3);
+        assert_eq!(second.messages.bls_msg.len(), 1);
+        let _ = (a, b);
+    }
+
     impl ChainStore<Chain4U<PlainCar<&'static [u8]>>> {
         fn _load(genesis_car: &'static [u8], genesis_cid: Cid) -> Self {
             let db = Arc::new(Chain4U::with_blockstore(
@@ -2,7 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use super::*;
+use crate::lotus_json::lotus_json;
 use crate::rpc::types::EventEntry;
+use crate::shim::state_tree::ActorState;
+use crate::state_manager::GenesisStateInfo;
 
 #[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Eq, PartialEq, Default)]
 #[serde(rename_all = "PascalCase")]
@@ -20,3 +23,75 @@ pub struct Event {
     pub entries: Vec<EventEntry>,
 }
 lotus_json_with_self!(Event);
+
+lotus_json! {
+    /// Details of the genesis state, see
+    /// [`StateManager::get_genesis_info`](crate::state_manager::StateManager::get_genesis_info).
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct ApiGenesisInfo {
+        pub timestamp: u64,
+        pub initial_supply: TokenAmount,
+        pub genesis_miners: Vec<Address>,
+        /// Genesis actors by ID address
+        pub genesis_actors: ahash::HashMap<String, ActorState>,
+    }
+    snapshots {
+        vec![(
+            serde_json::json!({
+                "Timestamp": 1598306400,
+                "InitialSupply": "2",
+                "GenesisMiners": ["f01000"],
+                "GenesisActors": {
+                    "f01000": {
+                        "Balance": "2",
+                        "Code": {"/": "baeaaaaa"},
+                        "Head": {"/": "baeaaaaa"},
+                        "Nonce": 0,
+                    },
+                },
+            }),
+            ApiGenesisInfo {
+                timestamp: 1598306400,
+                initial_supply: TokenAmount::from_atto(2),
+                genesis_miners: vec![Address::new_id(1000)],
+                genesis_actors: [(
+                    "f01000".to_string(),
+                    ActorState::new(
+                        Cid::default(),
+                        Cid::default(),
+                        TokenAmount::from_atto(2),
+                        0,
+                        None,
+                    ),
+                )]
+                .into_iter()
+                .collect(),
+            },
+        )]
+    }
+}
+
+impl From<&GenesisStateInfo> for ApiGenesisInfo {
+    fn from(info: &GenesisStateInfo) -> Self {
+        Self {
+            timestamp: info.timestamp,
+            initial_supply: info.initial_supply.clone(),
+            genesis_miners: info.genesis_miners.clone(),
+            genesis_actors: info
+                .genesis_actors
+                .iter()
+                .map(|(address, actor)| (address.to_string(), actor.clone()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshots() {
+        assert_all_snapshots::<ApiGenesisInfo>();
+    }
+}
@@ -1,9 +1,11 @@
 // Copyright 2019-2025 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use crate::daemon::config_reload::ConfigReloadReport;
 use crate::lotus_json::lotus_json_with_self;
 use crate::rpc::error::ServerError;
 use crate::rpc::{ApiPaths, Ctx, Permission, RpcMethod};
+use anyhow::Context as _;
 use fvm_ipld_blockstore::Blockstore;
 use once_cell::sync::Lazy;
 use schemars::JsonSchema;
@@ -66,6 +68,28 @@ impl RpcMethod<0> for Shutdown {
     }
 }
 
+/// Re-reads the configuration of the node and applies the settings that can
+/// change at runtime. The other changed settings are reported as requiring a
+/// restart.
+pub enum ReloadConfig {}
+impl RpcMethod<0> for ReloadConfig {
+    const NAME: &'static str = "Forest.ReloadConfig";
+    const PARAM_NAMES: [&'static str; 0] = [];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Admin;
+
+    type Params = ();
+    type Ok = ConfigReloadReport;
+
+    async fn handle(ctx: Ctx<impl Any>, (): Self::Params) -> Result<Self::Ok, ServerError> {
+        let config_reloader = ctx
+            .config_reloader
+            .as_ref()
+            .context("configuration reload is not supported by this node")?;
+        Ok(config_reloader.reload().await?)
+    }
+}
+
 pub enum StartTime {}
 impl RpcMethod<0> for StartTime {
     const NAME: &'static str = "Filecoin.StartTime";
@@ -21,7 +21,7 @@ use crate::eth::{
     EthLegacyHomesteadTxArgs,
 };
 use crate::interpreter::VMTrace;
-use crate::lotus_json::{lotus_json_with_self, HasLotusJson};
+use crate::lotus_json::{lotus_json_with_self, HasLotusJson, LotusJson};
 use crate::message::{ChainMessage, Message as _, SignedMessage};
 use crate::rpc::error::ServerError;
 use crate::rpc::eth::types::EthBlockTrace;
@@ -44,6 +44,7 @@ use crate::shim::gas::GasOutputs;
 use crate::shim::message::Message;
 use crate::shim::trace::{CallReturn, ExecutionEvent};
 use crate::shim::{clock::ChainEpoch, state_tree::StateTree};
+use crate::state_manager::PriorMessages;
 use crate::utils::db::BlockstoreExt as _;
 use crate::utils::encoding::from_slice_with_fallback;
 use crate::utils::multihash::prelude::*;
@@ -1518,14 +1519,13 @@ impl EthEstimateGas {
     where
         DB: Blockstore + Send + Sync + 'static,
     {
-        let (_invoc_res, apply_ret, prior_messages, ts) =
-            gas::GasEstimateGasLimit::estimate_call_with_gas(
-                data,
-                msg.clone(),
-                tsk,
-                VMTrace::Traced,
-            )
-            .await?;
+        let (_invoc_res, apply_ret, ts) = gas::GasEstimateGasLimit::estimate_call_with_gas(
+            data,
+            msg.clone(),
+            tsk,
+            VMTrace::Traced,
+        )
+        .await?;
         if apply_ret.msg_receipt().exit_code().is_success() {
             return Ok(msg.gas_limit());
         }
@@ -1541,7 +1541,7 @@ impl EthEstimateGas {
                 })
             )
         }) {
-            let ret = Self::gas_search(data, &msg, &prior_messages, ts).await?;
+            let ret = Self::gas_search(data, &msg, ts).await?;
             Ok(((ret as f64) * data.mpool.config.gas_limit_overestimation) as u64)
         } else {
             anyhow::bail!(
@@ -1556,17 +1556,16 @@ impl EthEstimateGas {
     /// message with. It first finds a high gas limit that allows the message to execute
     /// by doubling the previous gas limit until it succeeds then does a binary
     /// search till it gets within a range of 1%
-    async fn gas_search<DB>(
-        data: &Ctx<DB>,
-        msg: &Message,
-        prior_messages: &[ChainMessage],
-        ts: Arc<Tipset>,
-    ) -> anyhow::Result<u64>
+    async fn gas_search<DB>(data: &Ctx<DB>, msg: &Message, ts: Arc<Tipset>) -> anyhow::Result<u64>
     where
         DB: Blockstore + Send + Sync + 'static,
     {
         let mut high = msg.gas_limit;
         let mut low = msg.gas_limit;
+        let prior_messages = data
+            .state_manager
+            .pending_prior_messages_of(data.mpool.as_ref(), &msg.from, &ts)
+            .await;
 
         async fn can_succeed<DB>(
             data: &Ctx<DB>,
@@ -1583,7 +1582,7 @@ impl EthEstimateGas {
                 .state_manager
                 .call_with_gas(
                     &mut msg.into(),
-                    prior_messages,
+                    PriorMessages::Given(prior_messages),
                     Some(ts),
                     VMTrace::NotTraced,
                 )
@@ -1592,7 +1591,7 @@ impl EthEstimateGas {
         }
 
         while high <= BLOCK_GAS_LIMIT {
-            if can_succeed(data, msg.clone(), prior_messages, ts.clone(), high).await? {
+            if can_succeed(data, msg.clone(), &prior_messages, ts.clone(), high).await? {
                 break;
             }
             low = high;
@@ -1602,7 +1601,7 @@ impl EthEstimateGas {
         let mut check_threshold = high / 100;
         while (high - low) > check_threshold {
             let median = (high + low) / 2;
-            if can_succeed(data, msg.clone(), prior_messages, ts.clone(), high).await? {
+            if can_succeed(data, msg.clone(), &prior_messages, ts.clone(), high).await? {
                 high = median;
             } else {
                 low = median;
@@ -1908,7 +1907,7 @@ impl RpcMethod<2> for EthGetTransactionCount {
 
             Ok(EthUint64(evm_state.nonce()))
         } else {
-            Ok(EthUint64(ctx.mpool.get_sequence(&addr)?))
+            Ok(EthUint64(ctx.mpool.get_nonce(&addr)?))
         }
     }
 }
@@ -2415,6 +2414,19 @@ pub struct CollectedEvent {
     msg_cid: Cid,
 }
 
+impl From<CollectedEvent> for crate::rpc::misc::ActorEvent {
+    fn from(event: CollectedEvent) -> Self {
+        Self {
+            entries: event.entries,
+            emitter: LotusJson(event.emitter_addr),
+            reverted: event.reverted,
+            height: event.height,
+            tipset_key: LotusJson(event.tipset_key),
+            msg_cid: LotusJson(event.msg_cid),
+        }
+    }
+}
+
 fn match_key(key: &str) -> Option<usize> {
     match key.get(0..2) {
         Some("t1") => Some(0),
@@ -15,6 +15,7 @@ use crate::shim::{
     econ::{TokenAmount, BLOCK_GAS_LIMIT},
     message::Message,
 };
+use crate::state_manager::PriorMessages;
 use anyhow::{Context, Result};
 use fvm_ipld_blockstore::Blockstore;
 use num::BigInt;
@@ -187,7 +188,7 @@ impl GasEstimateGasLimit {
         mut msg: Message,
         ApiTipsetKey(tsk): &ApiTipsetKey,
         trace_config: VMTrace,
-    ) -> anyhow::Result<(InvocResult, ApplyRet, Vec<ChainMessage>, Arc<Tipset>)>
+    ) -> anyhow::Result<(InvocResult, ApplyRet, Arc<Tipset>)>
     where
         DB: Blockstore + Send + Sync + 'static,
     {
@@ -201,11 +202,6 @@ impl GasEstimateGasLimit {
             .resolve_to_key_addr(&msg.from, &curr_ts)
             .await?;
 
-        let pending = data.mpool.pending_for(&from_a);
-        let prior_messages: Vec<ChainMessage> = pending
-            .map(|s| s.into_iter().map(ChainMessage::Signed).collect::<Vec<_>>())
-            .unwrap_or_default();
-
         let ts = data.mpool.cur_tipset.lock().clone();
         // Pretend that the message is signed. This has an influence on the gas
         // cost. We obviously can't generate a valid signature. Instead, we just
@@ -228,12 +224,12 @@ impl GasEstimateGasLimit {
             .state_manager
             .call_with_gas(
                 &mut chain_msg,
-                &prior_messages,
+                PriorMessages::Pending(data.mpool.as_ref()),
                 Some(ts.clone()),
                 trace_config,
             )
             .await?;
-        Ok((invoc_res, apply_ret, prior_messages, ts))
+        Ok((invoc_res, apply_ret, ts))
     }
 
     pub async fn estimate_gas_limit<DB>(
@@ -285,7 +285,7 @@ impl RpcMethod<3> for MinerGetBaseInfo {
 
         Ok(ctx
             .state_manager
-            .miner_get_base_info(ctx.beacon(), ts, address, epoch)
+            .miner_get_base_info(ts, address, epoch)
             .await?)
     }
 }
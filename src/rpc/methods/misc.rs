@@ -1,17 +1,24 @@
 // Copyright 2019-2025 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use std::{any::Any, collections::BTreeMap};
+use std::{any::Any, collections::BTreeMap, sync::Arc};
 
 use crate::{
-    blocks::TipsetKey,
+    blocks::{Tipset, TipsetKey},
+    chain::{index::ChainIndex, HeadChange},
     lotus_json::{lotus_json_with_self, LotusJson},
-    rpc::{types::EventEntry, ApiPaths, Ctx, Permission, RpcMethod, ServerError},
+    rpc::{types::EventEntry, ApiPaths, Ctx, EthEventHandler, Permission, RpcMethod, ServerError},
     shim::{address::Address, clock::ChainEpoch},
+    utils::misc::env::env_or_default,
 };
+use anyhow::{anyhow, ensure};
 use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use itertools::Itertools as _;
+use jsonrpsee::types::Params;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError, Receiver as Subscriber, Sender};
 
 pub enum GetActorEventsRaw {}
 impl RpcMethod<1> for GetActorEventsRaw {
@@ -41,13 +48,13 @@ pub struct ActorEventFilter {
     pub tipset_key: Option<LotusJson<TipsetKey>>,
 }
 
-#[derive(Clone, JsonSchema, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, JsonSchema, Serialize, Deserialize)]
 pub struct ActorEventBlock {
     pub codec: u64,
     pub value: LotusJson<Vec<u8>>,
 }
 
-#[derive(Clone, JsonSchema, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, JsonSchema, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ActorEvent {
     pub entries: Vec<EventEntry>,
@@ -62,3 +69,379 @@ lotus_json_with_self! {
     ActorEvent,
     ActorEventFilter
 }
+
+pub const SUBSCRIBE_ACTOR_EVENTS: &str = "Forest.SubscribeActorEvents";
+
+/// Filter of a [`SUBSCRIBE_ACTOR_EVENTS`] subscription.
+#[derive(Clone, Debug, Default, JsonSchema, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActorEventSubscription {
+    /// Emitters to match, or all emitters if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub addresses: Vec<LotusJson<Address>>,
+    /// Accepted values of event entries, by entry key. An event matches if,
+    /// for each key, one of its entries has the key and one of the values.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub topics: BTreeMap<String, Vec<ActorEventBlock>>,
+    /// Epoch of the first tipset to deliver events from, defaulting to the
+    /// current head.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_epoch: Option<ChainEpoch>,
+    /// Number of epochs that must be built on top of a tipset before its
+    /// events are delivered.
+    #[serde(default)]
+    pub confidence: ChainEpoch,
+}
+
+impl ActorEventSubscription {
+    fn validate(
+        &self,
+        head_epoch: ChainEpoch,
+        finality: ChainEpoch,
+        max_filter_values: usize,
+        max_height_range: ChainEpoch,
+    ) -> anyhow::Result<()> {
+        ensure!(
+            (0..=finality).contains(&self.confidence),
+            "confidence must be between 0 and {finality}"
+        );
+        let filter_values =
+            self.addresses.len() + self.topics.values().map(Vec::len).sum::<usize>();
+        ensure!(
+            filter_values <= max_filter_values,
+            "filter has {filter_values} addresses and topic values, the maximum is {max_filter_values}"
+        );
+        if let Some(from_epoch) = self.from_epoch {
+            ensure!(
+                from_epoch <= head_epoch,
+                "from epoch {from_epoch} is after the head epoch {head_epoch}"
+            );
+            ensure!(
+                head_epoch - from_epoch <= max_height_range,
+                "from epoch is too far in the past (maximum: {max_height_range})"
+            );
+        }
+        Ok(())
+    }
+
+    fn matches(&self, event: &ActorEvent) -> bool {
+        let match_addr = self.addresses.is_empty()
+            || self
+                .addresses
+                .iter()
+                .any(|LotusJson(addr)| *addr == event.emitter.0);
+        let match_topics = self.topics.iter().all(|(key, values)| {
+            event.entries.iter().any(|entry| {
+                &entry.key == key
+                    && values
+                        .iter()
+                        .any(|v| v.codec == entry.codec && v.value.0 == entry.value.0)
+            })
+        });
+        match_addr && match_topics
+    }
+}
+
+/// Subscribes to the actor events matching the filter given as the only
+/// parameter. Events are delivered once their tipset has enough confidence,
+/// and delivered events whose tipset is reverted before finality are sent
+/// again with `reverted` set.
+///
+/// The subscription is closed if the client falls behind by more than
+/// `FOREST_MAX_SUBSCRIPTION_QUEUE_DEPTH` notifications.
+pub(crate) fn subscribe_actor_events<DB: Blockstore + Send + Sync + 'static>(
+    params: Params<'_>,
+    ctx: &Ctx<DB>,
+) -> Result<Subscriber<Vec<ActorEvent>>, ServerError> {
+    let (filter,): (ActorEventSubscription,) = params.parse()?;
+    let max_filter_values = env_or_default("FOREST_MAX_SUBSCRIPTION_FILTER_VALUES", 256);
+    let max_queue_depth = env_or_default("FOREST_MAX_SUBSCRIPTION_QUEUE_DEPTH", 256);
+    let max_height_range = env_or_default("FOREST_MAX_FILTER_HEIGHT_RANGE", 2880);
+
+    let head = ctx.chain_store().heaviest_tipset();
+    let finality = ctx.chain_config().policy.chain_finality;
+    filter
+        .validate(head.epoch(), finality, max_filter_values, max_height_range)
+        .map_err(|e| ServerError::invalid_params(e, None))?;
+
+    let (sender, receiver) = broadcast::channel(max_queue_depth);
+    let mut head_changes = ctx.chain_store().publisher().subscribe();
+    let mut delivered = DeliveredEvents::new(
+        filter.from_epoch.unwrap_or(head.epoch()),
+        filter.confidence,
+        finality,
+    );
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        let mut head = head;
+        loop {
+            if let Err(e) = notify_actor_events(
+                &ctx,
+                &filter,
+                &mut delivered,
+                head,
+                &sender,
+                max_queue_depth,
+            )
+            .await
+            {
+                tracing::debug!("Closing actor events subscription: {e:#}");
+                break;
+            }
+            head = match head_changes.recv().await {
+                Ok(HeadChange::Apply(ts)) => ts,
+                // Skipped heads need no replay, each head is checked against
+                // all the delivered tipsets.
+                Err(RecvError::Lagged(_)) => ctx.chain_store().heaviest_tipset(),
+                Err(RecvError::Closed) => break,
+            };
+        }
+    });
+    Ok(receiver)
+}
+
+async fn notify_actor_events<DB: Blockstore + Send + Sync + 'static>(
+    ctx: &Ctx<DB>,
+    filter: &ActorEventSubscription,
+    delivered: &mut DeliveredEvents,
+    head: Arc<Tipset>,
+    sender: &Sender<Vec<ActorEvent>>,
+    max_queue_depth: usize,
+) -> anyhow::Result<()> {
+    ensure!(sender.receiver_count() > 0, "subscription closed");
+    let (reverted, ready) = delivered.apply_head(head, &ctx.chain_store().chain_index);
+    send_actor_events(sender, reverted, max_queue_depth)?;
+    for tipset in ready {
+        let mut collected = vec![];
+        EthEventHandler::collect_events(ctx, &tipset, None, &mut collected).await?;
+        let events = collected
+            .into_iter()
+            .map(ActorEvent::from)
+            .filter(|event| filter.matches(event))
+            .collect_vec();
+        delivered.record(&tipset, events.clone());
+        send_actor_events(sender, events, max_queue_depth)?;
+    }
+    Ok(())
+}
+
+fn send_actor_events(
+    sender: &Sender<Vec<ActorEvent>>,
+    events: Vec<ActorEvent>,
+    max_queue_depth: usize,
+) -> anyhow::Result<()> {
+    if events.is_empty() {
+        return Ok(());
+    }
+    // The channel would otherwise silently drop the oldest notification
+    ensure!(
+        sender.len() < max_queue_depth,
+        "queue depth limit of {max_queue_depth} notifications reached"
+    );
+    sender
+        .send(events)
+        .map_err(|_| anyhow!("subscription closed"))?;
+    Ok(())
+}
+
+/// Tracks the tipsets whose events were delivered to a subscription, until
+/// they are final, to revert their events if they leave the canonical chain.
+struct DeliveredEvents {
+    confidence: ChainEpoch,
+    finality: ChainEpoch,
+    /// Epoch of the first tipset whose events are yet to be delivered.
+    next_epoch: ChainEpoch,
+    /// Delivered tipsets and their matching events, by epoch.
+    delivered: BTreeMap<ChainEpoch, (TipsetKey, Vec<ActorEvent>)>,
+}
+
+impl DeliveredEvents {
+    fn new(from_epoch: ChainEpoch, confidence: ChainEpoch, finality: ChainEpoch) -> Self {
+        Self {
+            confidence,
+            finality,
+            next_epoch: from_epoch,
+            delivered: BTreeMap::new(),
+        }
+    }
+
+    /// Compares the chain of the new head with the delivered tipsets. Returns
+    /// the events of the tipsets that are no longer canonical, newest first
+    /// and flagged as reverted, and the canonical tipsets whose events are
+    /// ready to be delivered, oldest first.
+    fn apply_head<DB: Blockstore>(
+        &mut self,
+        head: Arc<Tipset>,
+        chain_index: &ChainIndex<DB>,
+    ) -> (Vec<ActorEvent>, Vec<Arc<Tipset>>) {
+        let head_epoch = head.epoch();
+        let lowest_epoch = self
+            .delivered
+            .keys()
+            .next()
+            .map_or(self.next_epoch, |&epoch| epoch.min(self.next_epoch));
+        let canonical: BTreeMap<_, _> = chain_index
+            .chain(head)
+            .take_while(|ts| ts.epoch() >= lowest_epoch)
+            .map(|ts| (ts.epoch(), ts))
+            .collect();
+
+        let mut reverted = vec![];
+        let reverted_epochs = self
+            .delivered
+            .iter()
+            .rev()
+            .filter(|(epoch, (key, _))| canonical.get(epoch).map(|ts| ts.key()) != Some(key))
+            .map(|(&epoch, _)| epoch)
+            .collect_vec();
+        for epoch in reverted_epochs {
+            if let Some((_, events)) = self.delivered.remove(&epoch) {
+                reverted.extend(events.into_iter().map(|event| ActorEvent {
+                    reverted: true,
+                    ..event
+                }));
+                self.next_epoch = self.next_epoch.min(epoch);
+            }
+        }
+
+        let confident_epoch = head_epoch - self.confidence;
+        let ready = canonical
+            .range(self.next_epoch..=confident_epoch)
+            .map(|(_, ts)| ts.clone())
+            .collect_vec();
+        self.next_epoch = self.next_epoch.max(confident_epoch + 1);
+        self.delivered = self.delivered.split_off(&(head_epoch - self.finality));
+        (reverted, ready)
+    }
+
+    /// Records the events delivered from the tipset.
+    fn record(&mut self, tipset: &Tipset, events: Vec<ActorEvent>) {
+        self.delivered
+            .insert(tipset.epoch(), (tipset.key().clone(), events));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::{chain4u, Chain4U};
+    use crate::db::MemoryDB;
+
+    fn event(tipset: &Tipset, emitter: u64) -> ActorEvent {
+        ActorEvent {
+            entries: vec![],
+            emitter: LotusJson(Address::new_id(emitter)),
+            reverted: false,
+            height: tipset.epoch(),
+            tipset_key: LotusJson(tipset.key().clone()),
+            msg_cid: LotusJson(Cid::default()),
+        }
+    }
+
+    fn keys(tipsets: &[Arc<Tipset>]) -> Vec<TipsetKey> {
+        tipsets.iter().map(|ts| ts.key().clone()).collect()
+    }
+
+    #[test]
+    fn revert_delivered_events_on_reorg() {
+        let db = Arc::new(MemoryDB::default());
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            [genesis] -> t1 @ [a1] -> t2 @ [a2] -> t3 @ [a3]
+        };
+        chain4u! {
+            from [a1] in c4u;
+            u2 @ [b2] -> u3 @ [b3] -> u4 @ [b4]
+        };
+        let chain_index = ChainIndex::new(db);
+        let mut delivered = DeliveredEvents::new(1, 1, 900);
+
+        let (reverted, ready) = delivered.apply_head(Arc::new(t3.clone()), &chain_index);
+        assert!(reverted.is_empty());
+        assert_eq!(keys(&ready), vec![t1.key().clone(), t2.key().clone()]);
+        for tipset in &ready {
+            delivered.record(tipset, vec![event(tipset, 1000)]);
+        }
+
+        // A heavier fork from `t1` replaces `t2`, whose event was delivered
+        let (reverted, ready) = delivered.apply_head(Arc::new(u4.clone()), &chain_index);
+        assert_eq!(
+            reverted,
+            vec![ActorEvent {
+                reverted: true,
+                ..event(t2, 1000)
+            }]
+        );
+        assert_eq!(keys(&ready), vec![u2.key().clone(), u3.key().clone()]);
+        for tipset in &ready {
+            delivered.record(tipset, vec![]);
+        }
+
+        // Nothing new until the head advances
+        let (reverted, ready) = delivered.apply_head(Arc::new(u4.clone()), &chain_index);
+        assert!(reverted.is_empty());
+        assert!(ready.is_empty());
+    }
+
+    #[test]
+    fn events_wait_for_confidence() {
+        let db = Arc::new(MemoryDB::default());
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            [genesis] -> t1 @ [a1] -> t2 @ [a2] -> t3 @ [a3]
+        };
+        let chain_index = ChainIndex::new(db);
+        let mut delivered = DeliveredEvents::new(1, 2, 900);
+
+        let (_, ready) = delivered.apply_head(Arc::new(t2.clone()), &chain_index);
+        assert!(ready.is_empty());
+        let (_, ready) = delivered.apply_head(Arc::new(t3.clone()), &chain_index);
+        assert_eq!(keys(&ready), vec![t1.key().clone()]);
+    }
+
+    #[test]
+    fn subscription_filter() {
+        let db = Arc::new(MemoryDB::default());
+        let c4u = Chain4U::with_blockstore(db);
+        chain4u! {
+            in c4u;
+            t0 @ [genesis]
+        };
+        let mut event = event(t0, 1000);
+        event.entries.push(EventEntry {
+            flags: 0,
+            key: "t1".into(),
+            codec: 0x55,
+            value: LotusJson(vec![1, 2, 3]),
+        });
+        let block = |value: Vec<u8>| ActorEventBlock {
+            codec: 0x55,
+            value: LotusJson(value),
+        };
+
+        let mut filter = ActorEventSubscription::default();
+        assert!(filter.matches(&event));
+        filter.addresses.push(LotusJson(Address::new_id(1001)));
+        assert!(!filter.matches(&event));
+        filter.addresses.push(LotusJson(Address::new_id(1000)));
+        assert!(filter.matches(&event));
+        filter
+            .topics
+            .insert("t1".into(), vec![block(vec![0]), block(vec![1, 2, 3])]);
+        assert!(filter.matches(&event));
+        filter
+            .topics
+            .insert("t2".into(), vec![block(vec![1, 2, 3])]);
+        assert!(!filter.matches(&event));
+
+        assert!(filter.validate(10, 900, 4, 2880).is_ok());
+        assert!(filter.validate(10, 900, 3, 2880).is_err());
+        filter.confidence = 901;
+        assert!(filter.validate(10, 900, 4, 2880).is_err());
+        filter.confidence = 0;
+        filter.from_epoch = Some(11);
+        assert!(filter.validate(10, 900, 4, 2880).is_err());
+    }
+}
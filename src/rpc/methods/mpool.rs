@@ -1,9 +1,11 @@
 // Copyright 2019-2025 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use super::chain::min_base_fee;
 use super::gas::estimate_message_gas;
 use crate::lotus_json::NotNullVec;
 use crate::message::SignedMessage;
+use crate::message_pool::{LocalMessage, MessageImport, PendingMessage};
 use crate::rpc::error::ServerError;
 use crate::rpc::types::{ApiTipsetKey, MessageSendSpec};
 use crate::rpc::{ApiPaths, Ctx, Permission, RpcMethod};
@@ -30,7 +32,50 @@ impl RpcMethod<1> for MpoolGetNonce {
         ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
         (address,): Self::Params,
     ) -> Result<Self::Ok, ServerError> {
-        Ok(ctx.mpool.get_sequence(&address)?)
+        Ok(ctx.mpool.get_nonce(&address)?)
+    }
+}
+
+/// Re-add messages exported from the pool of another node, as local ones if
+/// requested, return the outcome for each of them
+pub enum MpoolImport {}
+impl RpcMethod<2> for MpoolImport {
+    const NAME: &'static str = "Forest.MpoolImport";
+    const PARAM_NAMES: [&'static str; 2] = ["msgs", "local"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Write;
+
+    type Params = (Vec<SignedMessage>, bool);
+    type Ok = Vec<MessageImport>;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (msgs, local): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let mut imports = Vec::with_capacity(msgs.len());
+        for msg in msgs {
+            imports.push(ctx.mpool.import(msg, local).await);
+        }
+        Ok(imports)
+    }
+}
+
+/// Return the journal of messages published through this node, with their status
+pub enum MpoolLocals {}
+impl RpcMethod<0> for MpoolLocals {
+    const NAME: &'static str = "Forest.MpoolLocals";
+    const PARAM_NAMES: [&'static str; 0] = [];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = ();
+    type Ok = Vec<LocalMessage>;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        Ok(ctx.mpool.local_messages())
     }
 }
 
@@ -103,6 +148,69 @@ impl RpcMethod<1> for MpoolPending {
     }
 }
 
+/// Return the pending messages of an address, sorted by nonce and annotated
+/// with the nonce gaps between them
+pub enum MpoolPendingForAddress {}
+impl RpcMethod<1> for MpoolPendingForAddress {
+    const NAME: &'static str = "Filecoin.MpoolPendingForAddress";
+    const PARAM_NAMES: [&'static str; 1] = ["address"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (Address,);
+    type Ok = Vec<PendingMessage>;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (address,): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        Ok(ctx.mpool.get_pending_gaps_for_address(&address)?)
+    }
+}
+
+/// Rebroadcast the pending messages that have been waiting in the pool for at
+/// least the given number of seconds, return how many were rebroadcast
+pub enum MpoolRebroadcast {}
+impl RpcMethod<1> for MpoolRebroadcast {
+    const NAME: &'static str = "Filecoin.MpoolRebroadcast";
+    const PARAM_NAMES: [&'static str; 1] = ["min_age_secs"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Write;
+
+    type Params = (u64,);
+    type Ok = u64;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (min_age_secs,): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        Ok(ctx.mpool.rebroadcast_stuck_messages(min_age_secs) as u64)
+    }
+}
+
+/// Return the composition of the pool, for the messages of the given senders
+/// only if any, the fee caps being compared to the lowest base fee of the
+/// lookback as well as to the current one
+pub enum MpoolStat {}
+impl RpcMethod<2> for MpoolStat {
+    const NAME: &'static str = "Forest.MpoolStat";
+    const PARAM_NAMES: [&'static str; 2] = ["basefee_lookback", "senders"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (u32, Option<Vec<Address>>);
+    type Ok = crate::message_pool::MpoolStat;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (basefee_lookback, senders): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let min_base_fee = min_base_fee(&ctx, basefee_lookback)?;
+        let senders = senders.map(HashSet::from_iter);
+        Ok(ctx.mpool.stat(&min_base_fee, senders.as_ref())?)
+    }
+}
+
 /// Return `Vec` of pending messages for inclusion in the next block
 pub enum MpoolSelect {}
 impl RpcMethod<2> for MpoolSelect {
@@ -249,7 +357,7 @@ impl RpcMethod<2> for MpoolPushMessage {
         if from.protocol() == Protocol::ID {
             umsg.from = key_addr;
         }
-        let nonce = ctx.mpool.get_sequence(&from)?;
+        let nonce = ctx.mpool.get_nonce(&from)?;
         umsg.sequence = nonce;
         let key = crate::key_management::Key::try_from(crate::key_management::try_find(
             &key_addr,
@@ -7,9 +7,14 @@ pub use types::*;
 
 use std::any::Any;
 use std::str::FromStr;
+use std::time::Duration;
 
-use crate::libp2p::{NetRPCMethods, NetworkMessage, PeerId};
+use crate::libp2p::{
+    load_persisted_peers, ping::p2p_ping, NetRPCMethods, NetworkMessage, PeerId, PeerRecord,
+    Protocol,
+};
 use crate::rpc::{ApiPaths, Ctx, Permission, RpcMethod, ServerError};
+use crate::utils::p2p::MultiaddrExt as _;
 use anyhow::{Context as _, Result};
 use cid::multibase;
 use fvm_ipld_blockstore::Blockstore;
@@ -130,6 +135,25 @@ impl RpcMethod<0> for NetInfo {
     }
 }
 
+pub enum NetPersistedPeers {}
+impl RpcMethod<0> for NetPersistedPeers {
+    const NAME: &'static str = "Forest.NetPersistedPeers";
+    const PARAM_NAMES: [&'static str; 0] = [];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = ();
+    type Ok = Vec<PeerRecord>;
+
+    async fn handle(ctx: Ctx<impl Blockstore>, (): Self::Params) -> Result<Self::Ok, ServerError> {
+        let now = chrono::Utc::now().timestamp() as u64;
+        Ok(load_persisted_peers(
+            ctx.chain_store().settings().as_ref(),
+            now,
+        )?)
+    }
+}
+
 pub enum NetConnect {}
 impl RpcMethod<1> for NetConnect {
     const NAME: &'static str = "Filecoin.NetConnect";
@@ -237,6 +261,65 @@ impl RpcMethod<0> for NetAutoNatStatus {
     }
 }
 
+pub enum NetReachability {}
+impl RpcMethod<0> for NetReachability {
+    const NAME: &'static str = "Forest.NetReachability";
+    const PARAM_NAMES: [&'static str; 0] = [];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = ();
+    type Ok = NetReachabilityResult;
+
+    async fn handle(ctx: Ctx<impl Blockstore>, (): Self::Params) -> Result<Self::Ok, ServerError> {
+        let (tx, rx) = flume::bounded(1);
+        let req = NetworkMessage::JSONRPCRequest {
+            method: NetRPCMethods::Reachability(tx),
+        };
+        ctx.network_send().send_async(req).await?;
+        Ok(rx.recv_async().await?)
+    }
+}
+
+pub enum NetPing {}
+impl RpcMethod<1> for NetPing {
+    const NAME: &'static str = "Forest.NetPing";
+    const PARAM_NAMES: [&'static str; 1] = ["peer_id"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (String,);
+    type Ok = Duration;
+
+    // Like `Filecoin.NetPing` in Lotus, this measures a fresh round-trip time
+    // by pinging the peer on the addresses it is known by.
+    async fn handle(
+        ctx: Ctx<impl Blockstore>,
+        (peer_id,): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let peer_id = PeerId::from_str(&peer_id)?;
+        let (tx, rx) = flume::bounded(1);
+        ctx.network_send()
+            .send_async(NetworkMessage::JSONRPCRequest {
+                method: NetRPCMethods::Peer(tx, peer_id),
+            })
+            .await?;
+        let addrs = rx
+            .recv_async()
+            .await?
+            .with_context(|| format!("peer {peer_id} not found"))?;
+        let mut error = anyhow::anyhow!("no known address for peer {peer_id}");
+        for addr in addrs {
+            let addr = addr.without_p2p().with(Protocol::P2p(peer_id));
+            match p2p_ping(addr.clone()).await {
+                Ok(rtt) => return Ok(rtt),
+                Err(e) => error = anyhow::anyhow!("failed to ping {addr}: {e}"),
+            }
+        }
+        Err(error.into())
+    }
+}
+
 pub enum NetVersion {}
 impl RpcMethod<0> for NetVersion {
     const NAME: &'static str = "Filecoin.NetVersion";
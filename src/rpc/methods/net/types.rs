@@ -89,3 +89,25 @@ impl From<libp2p::autonat::NatStatus> for NatStatusResult {
         }
     }
 }
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct ObservedAddr {
+    pub addr: String,
+    /// Number of connected peers reporting this address
+    pub num_peers: usize,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct NetReachabilityResult {
+    pub listen_addrs: Vec<String>,
+    /// Addresses confirmed to be reachable from the outside, e.g. by `AutoNAT` or `UPnP`
+    pub external_addrs: Vec<String>,
+    /// Addresses remote peers observe us on, as reported by `identify`
+    pub observed_addrs: Vec<ObservedAddr>,
+    pub nat_status: NatStatusResult,
+    pub num_inbound: usize,
+    pub num_outbound: usize,
+}
+lotus_json_with_self!(NetReachabilityResult);
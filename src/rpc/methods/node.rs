@@ -4,13 +4,55 @@
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use crate::{
+    chain_sync::WatchdogStatus,
+    db::ColumnStats,
     lotus_json::lotus_json_with_self,
+    networks::calculate_expected_epoch,
     rpc::{ApiPaths, Ctx, Permission, RpcMethod, ServerError},
+    utils::cache,
 };
+use anyhow::Context as _;
 use fvm_ipld_blockstore::Blockstore;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+/// Entry counts and estimated memory usage of the node caches.
+pub enum CacheStats {}
+impl RpcMethod<0> for CacheStats {
+    const NAME: &'static str = "Forest.CacheStats";
+    const PARAM_NAMES: [&'static str; 0] = [];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = ();
+    type Ok = Vec<cache::CacheStats>;
+
+    async fn handle(_: Ctx<impl Blockstore>, (): Self::Params) -> Result<Self::Ok, ServerError> {
+        Ok(cache::cache_stats())
+    }
+}
+
+/// Number of keys and size on disk of each column of the node database. The
+/// keys are counted by scanning the whole database.
+pub enum DbColumnStats {}
+impl RpcMethod<0> for DbColumnStats {
+    const NAME: &'static str = "Forest.DbColumnStats";
+    const PARAM_NAMES: [&'static str; 0] = [];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Admin;
+
+    type Params = ();
+    type Ok = Vec<ColumnStats>;
+
+    async fn handle(ctx: Ctx<impl Blockstore>, (): Self::Params) -> Result<Self::Ok, ServerError> {
+        let db_statistics = ctx
+            .db_statistics
+            .clone()
+            .context("database statistics are not supported by this node")?;
+        Ok(tokio::task::spawn_blocking(move || db_statistics.column_stats()).await??)
+    }
+}
+
 pub enum NodeStatus {}
 impl RpcMethod<0> for NodeStatus {
     const NAME: &'static str = "Filecoin.NodeStatus";
@@ -22,51 +64,84 @@ impl RpcMethod<0> for NodeStatus {
     type Ok = NodeStatusResult;
 
     async fn handle(ctx: Ctx<impl Blockstore>, (): Self::Params) -> Result<Self::Ok, ServerError> {
-        let mut node_status = NodeStatusResult::default();
-
-        let head = ctx.chain_store().heaviest_tipset();
-        let cur_duration: Duration = SystemTime::now().duration_since(UNIX_EPOCH)?;
-
-        let ts = head.min_timestamp();
-        let cur_duration_secs = cur_duration.as_secs();
-        let behind = if ts <= cur_duration_secs + 1 {
-            cur_duration_secs.saturating_sub(ts)
-        } else {
-            return Err(anyhow::anyhow!(
-                "System time should not be behind tipset timestamp, please sync the system clock."
-            )
-            .into());
-        };
-
-        let chain_finality = ctx.chain_config().policy.chain_finality;
-
-        node_status.sync_status.epoch = head.epoch() as u64;
-        node_status.sync_status.behind = behind;
-
-        if head.epoch() > chain_finality {
-            let mut block_count = 0;
-            let mut ts = head;
-
-            for _ in 0..100 {
-                block_count += ts.block_headers().len();
-                let tsk = ts.parents();
-                ts = ctx.chain_index().load_required_tipset(tsk)?;
-            }
-
-            node_status.chain_status.blocks_per_tipset_last_100 = block_count as f64 / 100.;
-
-            for _ in 100..chain_finality {
-                block_count += ts.block_headers().len();
-                let tsk = ts.parents();
-                ts = ctx.chain_index().load_required_tipset(tsk)?;
-            }
-
-            node_status.chain_status.blocks_per_tipset_last_finality =
-                block_count as f64 / chain_finality as f64;
+        node_status(&ctx)
+    }
+}
+
+/// [`NodeStatus`] extended with how far the head lags behind the network and
+/// the state of the sync watchdog.
+pub enum ForestNodeStatus {}
+impl RpcMethod<0> for ForestNodeStatus {
+    const NAME: &'static str = "Forest.NodeStatus";
+    const PARAM_NAMES: [&'static str; 0] = [];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = ();
+    type Ok = ForestNodeStatusResult;
+
+    async fn handle(ctx: Ctx<impl Blockstore>, (): Self::Params) -> Result<Self::Ok, ServerError> {
+        let node_status = node_status(&ctx)?;
+        let genesis_timestamp = ctx.chain_store().genesis_block_header().timestamp;
+        let expected_epoch = calculate_expected_epoch(
+            chrono::Utc::now().timestamp() as u64,
+            genesis_timestamp,
+            ctx.chain_config().block_delay_secs,
+        ) as i64;
+        let epochs_behind = (expected_epoch - ctx.chain_store().heaviest_tipset().epoch()).max(0);
+        Ok(ForestNodeStatusResult {
+            node_status,
+            epochs_behind,
+            watchdog: ctx.sync_watchdog.read().clone(),
+        })
+    }
+}
+
+fn node_status(ctx: &Ctx<impl Blockstore>) -> Result<NodeStatusResult, ServerError> {
+    let mut node_status = NodeStatusResult::default();
+
+    let head = ctx.chain_store().heaviest_tipset();
+    let cur_duration: Duration = SystemTime::now().duration_since(UNIX_EPOCH)?;
+
+    let ts = head.min_timestamp();
+    let cur_duration_secs = cur_duration.as_secs();
+    let behind = if ts <= cur_duration_secs + 1 {
+        cur_duration_secs.saturating_sub(ts)
+    } else {
+        return Err(anyhow::anyhow!(
+            "System time should not be behind tipset timestamp, please sync the system clock."
+        )
+        .into());
+    };
+
+    let chain_finality = ctx.chain_config().policy.chain_finality;
+
+    node_status.sync_status.epoch = head.epoch() as u64;
+    node_status.sync_status.behind = behind;
+
+    if head.epoch() > chain_finality {
+        let mut block_count = 0;
+        let mut ts = head;
+
+        for _ in 0..100 {
+            block_count += ts.block_headers().len();
+            let tsk = ts.parents();
+            ts = ctx.chain_index().load_required_tipset(tsk)?;
+        }
+
+        node_status.chain_status.blocks_per_tipset_last_100 = block_count as f64 / 100.;
+
+        for _ in 100..chain_finality {
+            block_count += ts.block_headers().len();
+            let tsk = ts.parents();
+            ts = ctx.chain_index().load_required_tipset(tsk)?;
         }
 
-        Ok(node_status)
+        node_status.chain_status.blocks_per_tipset_last_finality =
+            block_count as f64 / chain_finality as f64;
     }
+
+    Ok(node_status)
 }
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone, JsonSchema)]
@@ -97,3 +172,13 @@ pub struct NodeStatusResult {
     pub chain_status: NodeChainStatus,
 }
 lotus_json_with_self!(NodeStatusResult);
+
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct ForestNodeStatusResult {
+    pub node_status: NodeStatusResult,
+    /// Epochs between the head and the epoch expected from the wall clock
+    pub epochs_behind: i64,
+    pub watchdog: WatchdogStatus,
+}
+lotus_json_with_self!(ForestNodeStatusResult);
@@ -11,6 +11,7 @@ pub use types::*;
 
 use crate::blocks::Tipset;
 use crate::chain::index::ResolveNullTipset;
+use crate::chain::{StateTreeDepthReport, VisitorResult};
 use crate::cid_collections::CidHashSet;
 use crate::eth::EthChainId;
 use crate::interpreter::VMEvent;
@@ -25,7 +26,7 @@ use crate::shim::actors::verifreg::{Allocation, AllocationID, Claim};
 use crate::shim::actors::{
     market, miner,
     miner::{MinerInfo, MinerPower},
-    power, reward, verifreg,
+    power, reward, verifreg, Policy,
 };
 use crate::shim::actors::{
     market::ext::BalanceTableExt as _,
@@ -33,7 +34,8 @@ use crate::shim::actors::{
     power::ext::PowerStateExt as _,
 };
 use crate::shim::address::Payload;
-use crate::shim::message::Message;
+use crate::shim::externs::Rand;
+use crate::shim::message::{Message, MethodNum};
 use crate::shim::piece::PaddedPieceSize;
 use crate::shim::sector::{SectorNumber, SectorSize};
 use crate::shim::state_tree::{ActorID, StateTree};
@@ -42,7 +44,11 @@ use crate::shim::{
     state_tree::ActorState, version::NetworkVersion,
 };
 use crate::state_manager::circulating_supply::GenesisInfo;
-use crate::state_manager::{MarketBalance, StateOutput};
+use crate::state_manager::{
+    BlockProducerStats, ClaimInfo, FeeDebtProjection, MarketBalance, MinerConsensusStatus,
+    MinerControlInfo, PreCommitDepositInfo, SectorPenalties, SectorQualityAdjPower,
+    SectorRewardEstimate, StateOutput, StateOverride, UpgradeCost, VestingStats,
+};
 use crate::utils::db::{
     car_stream::{CarBlock, CarWriter},
     BlockstoreExt as _,
@@ -56,6 +62,7 @@ use anyhow::Context as _;
 use anyhow::Result;
 use cid::Cid;
 use fil_actor_miner_state::v10::{qa_power_for_weight, qa_power_max};
+use fil_actor_miner_state::v12::{PowerPair, WorkerKeyChange};
 use fil_actor_verifreg_state::v13::ClaimID;
 use fil_actors_shared::fvm_ipld_bitfield::BitField;
 use futures::StreamExt;
@@ -68,6 +75,7 @@ use num_bigint::BigInt;
 use num_traits::Euclid;
 use nunny::{vec as nonempty, Vec as NonEmpty};
 use parking_lot::Mutex;
+use std::collections::BTreeMap;
 use std::ops::Mul;
 use std::path::PathBuf;
 use std::{sync::Arc, time::Duration};
@@ -76,24 +84,77 @@ use tokio::task::JoinSet;
 const INITIAL_PLEDGE_NUM: u64 = 110;
 const INITIAL_PLEDGE_DEN: u64 = 100;
 
+/// Parses the actor states keyed by address of the `state_overrides`
+/// parameters.
+fn parse_state_overrides(
+    state_overrides: HashMap<String, ActorState>,
+) -> Result<StateOverride, ServerError> {
+    state_overrides
+        .into_iter()
+        .map(|(address, actor)| {
+            let address = address.parse::<Address>().map_err(|e| {
+                ServerError::invalid_params(format!("invalid address {address}: {e}"), None)
+            })?;
+            Ok((address, actor))
+        })
+        .collect()
+}
+
 pub enum StateCall {}
-impl RpcMethod<2> for StateCall {
+impl RpcMethod<3> for StateCall {
     const NAME: &'static str = "Filecoin.StateCall";
-    const PARAM_NAMES: [&'static str; 2] = ["message", "tsk"];
+    const PARAM_NAMES: [&'static str; 3] = ["message", "tsk", "state_overrides"];
     const API_PATHS: ApiPaths = ApiPaths::V1;
     const PERMISSION: Permission = Permission::Read;
+    const N_REQUIRED_PARAMS: usize = 2;
 
-    type Params = (Message, ApiTipsetKey);
+    type Params = (Message, ApiTipsetKey, Option<HashMap<String, ActorState>>);
     type Ok = ApiInvocResult;
 
     async fn handle(
         ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
-        (message, ApiTipsetKey(tsk)): Self::Params,
+        (message, ApiTipsetKey(tsk), state_overrides): Self::Params,
     ) -> Result<Self::Ok, ServerError> {
         let tipset = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
         // Handle expensive fork error?
         // TODO(elmattic): https://github.com/ChainSafe/forest/issues/3733
-        Ok(ctx.state_manager.call(&message, Some(tipset))?)
+        match state_overrides {
+            Some(state_overrides) => Ok(ctx.state_manager.call_with_state_override(
+                &message,
+                parse_state_overrides(state_overrides)?,
+                &tipset,
+            )?),
+            None => Ok(ctx.state_manager.call(&message, Some(tipset))?),
+        }
+    }
+}
+
+/// Applies a batch of messages on top of the state of a tipset, optionally
+/// overriding actor states first, without persisting any changes.
+pub enum StateSimulateMessageBatch {}
+impl RpcMethod<3> for StateSimulateMessageBatch {
+    const NAME: &'static str = "Filecoin.StateSimulateMessageBatch";
+    const PARAM_NAMES: [&'static str; 3] = ["messages", "state_overrides", "tsk"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (
+        Vec<Message>,
+        Option<HashMap<String, ActorState>>,
+        ApiTipsetKey,
+    );
+    type Ok = Vec<InvocResult>;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (messages, state_overrides, ApiTipsetKey(tsk)): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let tipset = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        let state_override = state_overrides.map(parse_state_overrides).transpose()?;
+        Ok(ctx
+            .state_manager
+            .simulate_message_batch(messages, state_override, tipset)
+            .await?)
     }
 }
 
@@ -481,6 +542,100 @@ impl RpcMethod<1> for StateMarketDeals {
     }
 }
 
+/// Maximum number of deals returned by a single [`StateMarketDealsPage`] call.
+const MAX_MARKET_DEALS_PAGE_SIZE: u64 = 10_000;
+
+/// Paginated variant of [`StateMarketDeals`], returning at most `limit` deals
+/// with an ID of at least `offset`.
+pub enum StateMarketDealsPage {}
+
+impl RpcMethod<3> for StateMarketDealsPage {
+    const NAME: &'static str = "Forest.StateMarketDealsPage";
+    const PARAM_NAMES: [&'static str; 3] = ["offset", "limit", "tipset_key"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (DealID, u64, ApiTipsetKey);
+    type Ok = ApiMarketDealsPage;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (offset, limit, ApiTipsetKey(tsk)): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        if !(1..=MAX_MARKET_DEALS_PAGE_SIZE).contains(&limit) {
+            return Err(anyhow::anyhow!(
+                "limit must be between 1 and {MAX_MARKET_DEALS_PAGE_SIZE}"
+            )
+            .into());
+        }
+        let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        let market_state: market::State = ctx.state_manager.get_actor_state(&ts)?;
+        Ok(market_deals_page(
+            ctx.store(),
+            &market_state,
+            offset,
+            limit,
+        )?)
+    }
+}
+
+/// Joins the deal proposals with their states in deal ID order, starting at
+/// `offset`, until `limit` deals are collected. Only the existing proposals
+/// are visited, gaps in the deal IDs are skipped.
+fn market_deals_page(
+    store: &impl Blockstore,
+    market_state: &market::State,
+    offset: DealID,
+    limit: u64,
+) -> anyhow::Result<ApiMarketDealsPage> {
+    let proposals = market_state.proposals(store)?;
+    let states = market_state.states(store)?;
+
+    let mut deals = BTreeMap::new();
+    let next_offset =
+        proposals.for_each_while_ranged(Some(offset), Some(limit), |deal_id, proposal| {
+            let state = states.get(deal_id)?.unwrap_or_else(DealState::empty);
+            deals.insert(deal_id, MarketDeal { proposal, state }.into());
+            Ok(true)
+        })?;
+    Ok(ApiMarketDealsPage { deals, next_offset })
+}
+
+/// Returns the network baseline power at each of the given epochs, looked up
+/// on the chain of the given tipset.
+pub enum StateNetworkBaselinePower {}
+
+impl RpcMethod<2> for StateNetworkBaselinePower {
+    const NAME: &'static str = "Filecoin.StateNetworkBaselinePower";
+    const PARAM_NAMES: [&'static str; 2] = ["epochs", "tipset_key"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (Vec<ChainEpoch>, ApiTipsetKey);
+    type Ok = Vec<ApiBaselinePower>;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (epochs, ApiTipsetKey(tsk)): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        if let Some(epoch) = epochs.iter().find(|&&epoch| epoch > ts.epoch()) {
+            return Err(
+                anyhow::anyhow!("epoch {epoch} is after the tipset epoch {}", ts.epoch()).into(),
+            );
+        }
+        Ok(ctx
+            .state_manager
+            .get_network_baseline_power_history(&epochs, &ts)?
+            .into_iter()
+            .map(|(epoch, baseline_power)| ApiBaselinePower {
+                epoch,
+                baseline_power,
+            })
+            .collect())
+    }
+}
+
 /// looks up the miner info of the given address.
 pub enum StateMinerInfo {}
 
@@ -502,6 +657,216 @@ impl RpcMethod<2> for StateMinerInfo {
     }
 }
 
+/// Returns the pending worker key change of the miner, if any.
+pub enum StateMinerWorkerKeyChange {}
+
+impl RpcMethod<2> for StateMinerWorkerKeyChange {
+    const NAME: &'static str = "Filecoin.StateMinerWorkerKeyChange";
+    const PARAM_NAMES: [&'static str; 2] = ["address", "tipset_key"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (Address, ApiTipsetKey);
+    type Ok = Option<WorkerKeyChange>;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (address, ApiTipsetKey(tsk)): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        Ok(ctx
+            .state_manager
+            .get_miner_worker_key_change(&address, *ts.parent_state())?)
+    }
+}
+
+/// Returns the owner, worker and control addresses of the miner in their ID
+/// and robust forms, along with the pending worker and owner changes.
+pub enum StateMinerControlAddresses {}
+
+impl RpcMethod<2> for StateMinerControlAddresses {
+    const NAME: &'static str = "Filecoin.StateMinerControlAddresses";
+    const PARAM_NAMES: [&'static str; 2] = ["address", "tipset_key"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (Address, ApiTipsetKey);
+    type Ok = MinerControlInfo;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (address, ApiTipsetKey(tsk)): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        Ok(ctx
+            .state_manager
+            .get_miner_control_address_info(&address, *ts.parent_state())?)
+    }
+}
+
+pub enum StateMinerSectorPower {}
+
+impl RpcMethod<3> for StateMinerSectorPower {
+    const NAME: &'static str = "Filecoin.StateMinerSectorPower";
+    const PARAM_NAMES: [&'static str; 3] = ["address", "sector_number", "tipset_key"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (Address, SectorNumber, ApiTipsetKey);
+    type Ok = PowerPair;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (address, sector_number, ApiTipsetKey(tsk)): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        Ok(ctx.state_manager.get_sector_power_contribution(
+            &address,
+            sector_number,
+            *ts.parent_state(),
+        )?)
+    }
+}
+
+pub enum StateMinerSectorQAPower {}
+
+impl RpcMethod<3> for StateMinerSectorQAPower {
+    const NAME: &'static str = "Filecoin.StateMinerSectorQAPower";
+    const PARAM_NAMES: [&'static str; 3] = ["address", "sector_number", "tipset_key"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (Address, SectorNumber, ApiTipsetKey);
+    type Ok = SectorQualityAdjPower;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (address, sector_number, ApiTipsetKey(tsk)): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        Ok(ctx.state_manager.get_sector_quality_adj_power(
+            &address,
+            sector_number,
+            *ts.parent_state(),
+        )?)
+    }
+}
+
+pub enum StateMinerSectorRewardEstimate {}
+
+impl RpcMethod<4> for StateMinerSectorRewardEstimate {
+    const NAME: &'static str = "Filecoin.StateMinerSectorRewardEstimate";
+    const PARAM_NAMES: [&'static str; 4] = [
+        "address",
+        "sector_number",
+        "projection_epochs",
+        "tipset_key",
+    ];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (Address, SectorNumber, ChainEpoch, ApiTipsetKey);
+    type Ok = SectorRewardEstimate;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (address, sector_number, projection_epochs, ApiTipsetKey(tsk)): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        if projection_epochs < 0 {
+            return Err(ServerError::invalid_params(
+                format!("negative projection epochs {projection_epochs}"),
+                None,
+            ));
+        }
+        let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        Ok(ctx.state_manager.compute_sector_reward_estimate(
+            &address,
+            sector_number,
+            projection_epochs,
+            *ts.parent_state(),
+        )?)
+    }
+}
+
+pub enum StateMinerSectorPenalties {}
+
+impl RpcMethod<3> for StateMinerSectorPenalties {
+    const NAME: &'static str = "Filecoin.StateMinerSectorPenalties";
+    const PARAM_NAMES: [&'static str; 3] = ["address", "sectors", "tipset_key"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (Address, BitField, ApiTipsetKey);
+    type Ok = SectorPenalties;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (address, sectors, ApiTipsetKey(tsk)): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        Ok(ctx
+            .state_manager
+            .miner_sector_penalties(&address, &sectors, &ts)?)
+    }
+}
+
+pub enum StateMinerSectorUpgradeCost {}
+
+impl RpcMethod<4> for StateMinerSectorUpgradeCost {
+    const NAME: &'static str = "Filecoin.StateMinerSectorUpgradeCost";
+    const PARAM_NAMES: [&'static str; 4] = ["address", "sector_number", "deal_ids", "tipset_key"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (Address, SectorNumber, Vec<DealID>, ApiTipsetKey);
+    type Ok = UpgradeCost;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (address, sector_number, deal_ids, ApiTipsetKey(tsk)): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        Ok(ctx
+            .state_manager
+            .get_sector_upgrade_cost(&address, sector_number, &deal_ids, &ts)?)
+    }
+}
+
+/// Maximum number of epochs covered by a single [`StateBlockProducerStats`]
+/// call, one day of blocks, as each epoch loads a reward actor state.
+const MAX_BLOCK_PRODUCER_STATS_EPOCHS: ChainEpoch = 2880;
+
+pub enum StateBlockProducerStats {}
+
+impl RpcMethod<3> for StateBlockProducerStats {
+    const NAME: &'static str = "Filecoin.StateBlockProducerStats";
+    const PARAM_NAMES: [&'static str; 3] = ["from", "to", "tipset_key"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (ChainEpoch, ChainEpoch, ApiTipsetKey);
+    type Ok = HashMap<String, BlockProducerStats>;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (from, to, ApiTipsetKey(tsk)): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        if to.saturating_sub(from) >= MAX_BLOCK_PRODUCER_STATS_EPOCHS {
+            return Err(anyhow::anyhow!(
+                "the epoch range must cover at most {MAX_BLOCK_PRODUCER_STATS_EPOCHS} epochs"
+            )
+            .into());
+        }
+        let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        Ok(ctx
+            .state_manager
+            .get_block_producer_stats(from, to, &ts)?
+            .into_iter()
+            .map(|(miner, stats)| (miner.to_string(), stats))
+            .collect())
+    }
+}
+
 pub enum StateMinerActiveSectors {}
 
 impl RpcMethod<2> for StateMinerActiveSectors {
@@ -522,16 +887,11 @@ impl RpcMethod<2> for StateMinerActiveSectors {
         let miner_state: miner::State = ctx
             .state_manager
             .get_actor_state_from_address(&ts, &address)?;
-        // Collect active sectors from each partition in each deadline.
-        let mut active_sectors = vec![];
-        miner_state.for_each_deadline(policy, ctx.store(), |_dlidx, deadline| {
-            deadline.for_each(ctx.store(), |_partidx, partition| {
-                active_sectors.push(partition.active_sectors());
-                Ok(())
-            })
-        })?;
-        let sectors =
-            miner_state.load_sectors_ext(ctx.store(), Some(&BitField::union(&active_sectors)))?;
+        let active_sectors =
+            miner_state.partition_sectors_union(policy, ctx.store(), |partition| {
+                partition.active_sectors()
+            })?;
+        let sectors = miner_state.load_sectors_ext(ctx.store(), Some(&active_sectors))?;
         Ok(sectors)
     }
 }
@@ -637,23 +997,59 @@ impl RpcMethod<2> for StateMinerSectorCount {
         (address, ApiTipsetKey(tsk)): Self::Params,
     ) -> Result<Self::Ok, ServerError> {
         let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
-        let policy = &ctx.chain_config().policy;
-        let miner_state: miner::State = ctx
+        Ok(ctx
             .state_manager
-            .get_actor_state_from_address(&ts, &address)?;
-        // Collect live, active and faulty sectors count from each partition in each deadline.
-        let mut live_count = 0;
-        let mut active_count = 0;
-        let mut faulty_count = 0;
-        miner_state.for_each_deadline(policy, ctx.store(), |_dlidx, deadline| {
-            deadline.for_each(ctx.store(), |_partidx, partition| {
-                live_count += partition.live_sectors().len();
-                active_count += partition.active_sectors().len();
-                faulty_count += partition.faulty_sectors().len();
-                Ok(())
-            })
-        })?;
-        Ok(MinerSectors::new(live_count, active_count, faulty_count))
+            .get_miner_sector_count(&address, *ts.parent_state())?)
+    }
+}
+
+/// Returns the number of sectors of a miner in the state resulting from the
+/// tipset at the given epoch
+pub enum StateMinerSectorCountAtEpoch {}
+
+impl RpcMethod<3> for StateMinerSectorCountAtEpoch {
+    const NAME: &'static str = "Filecoin.StateMinerSectorCountAtEpoch";
+    const PARAM_NAMES: [&'static str; 3] = ["address", "epoch", "tipset_key"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (Address, ChainEpoch, ApiTipsetKey);
+    type Ok = MinerSectors;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (address, epoch, ApiTipsetKey(tsk)): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        Ok(ctx
+            .state_manager
+            .get_miner_sector_count_at_epoch(&address, epoch, &ts)
+            .await?)
+    }
+}
+
+/// Returns the FIL+ claims on data committed in a sector of a miner
+pub enum StateMinerSectorClaims {}
+
+impl RpcMethod<3> for StateMinerSectorClaims {
+    const NAME: &'static str = "Filecoin.StateMinerSectorClaims";
+    const PARAM_NAMES: [&'static str; 3] = ["address", "sector_number", "tipset_key"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (Address, SectorNumber, ApiTipsetKey);
+    type Ok = Vec<ClaimInfo>;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (address, sector_number, ApiTipsetKey(tsk)): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        Ok(ctx.state_manager.get_sector_active_claims(
+            &address,
+            sector_number,
+            *ts.parent_state(),
+        )?)
     }
 }
 
@@ -766,6 +1162,197 @@ impl RpcMethod<2> for StateMinerProvingDeadline {
     }
 }
 
+/// Returns the collateral deposited for each pending pre-commit of a miner.
+pub enum StateMinerPreCommitDeposits {}
+
+impl RpcMethod<2> for StateMinerPreCommitDeposits {
+    const NAME: &'static str = "Filecoin.StateMinerPreCommitDeposits";
+    const PARAM_NAMES: [&'static str; 2] = ["address", "tipset_key"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (Address, ApiTipsetKey);
+    type Ok = Vec<PreCommitDepositInfo>;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (address, ApiTipsetKey(tsk)): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        Ok(ctx
+            .state_manager
+            .get_miner_pre_commit_deposits(&address, *ts.parent_state())?)
+    }
+}
+
+/// Returns the start of the proving period of a miner containing the epoch of
+/// the tipset.
+pub enum StateMinerProvingPeriodStart {}
+
+impl RpcMethod<2> for StateMinerProvingPeriodStart {
+    const NAME: &'static str = "Filecoin.StateMinerProvingPeriodStart";
+    const PARAM_NAMES: [&'static str; 2] = ["address", "tipset_key"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (Address, ApiTipsetKey);
+    type Ok = ChainEpoch;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (address, ApiTipsetKey(tsk)): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        Ok(ctx.state_manager.get_miner_proving_period_start(
+            &address,
+            ts.epoch(),
+            *ts.parent_state(),
+        )?)
+    }
+}
+
+/// Projects the fee debt of a miner over the given number of epochs, assuming
+/// its faulty sectors remain faulty.
+pub enum StateMinerFeeDebtProjection {}
+
+impl RpcMethod<3> for StateMinerFeeDebtProjection {
+    const NAME: &'static str = "Filecoin.StateMinerFeeDebtProjection";
+    const PARAM_NAMES: [&'static str; 3] = ["address", "future_epochs", "tipset_key"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (Address, ChainEpoch, ApiTipsetKey);
+    type Ok = FeeDebtProjection;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (address, future_epochs, ApiTipsetKey(tsk)): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        if future_epochs < 0 {
+            return Err(anyhow::anyhow!("future epochs must not be negative").into());
+        }
+        let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        Ok(ctx.state_manager.get_miner_fee_debt_projection(
+            &address,
+            *ts.parent_state(),
+            future_epochs,
+        )?)
+    }
+}
+
+/// Returns the funds locked in the vesting schedules of all the miners, in
+/// total and by vesting epoch.
+pub enum StateTokenVestingStats {}
+
+impl RpcMethod<1> for StateTokenVestingStats {
+    const NAME: &'static str = "Filecoin.StateTokenVestingStats";
+    const PARAM_NAMES: [&'static str; 1] = ["tipset_key"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (ApiTipsetKey,);
+    type Ok = VestingStats;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (ApiTipsetKey(tsk),): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        let state_manager = ctx.state_manager.clone();
+        Ok(tokio::task::spawn_blocking(move || {
+            state_manager.get_token_vesting_stats(*ts.parent_state())
+        })
+        .await??)
+    }
+}
+
+/// Returns the proving window of each deadline of a miner at its next
+/// occurrence that has not yet elapsed, with wall-clock open and close times.
+pub enum MinerProvingSchedule {}
+
+impl RpcMethod<2> for MinerProvingSchedule {
+    const NAME: &'static str = "Forest.MinerProvingSchedule";
+    const PARAM_NAMES: [&'static str; 2] = ["address", "tipset_key"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (Address, ApiTipsetKey);
+    type Ok = Vec<ProvingWindow>;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (address, ApiTipsetKey(tsk)): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        let state: miner::State = ctx
+            .state_manager
+            .get_actor_state_from_address(&ts, &address)?;
+        Ok(proving_schedule(
+            ctx.store(),
+            &state,
+            &ctx.chain_config().policy,
+            ts.epoch(),
+            ctx.chain_store().genesis_block_header().timestamp,
+            ctx.chain_config().block_delay_secs,
+        )?)
+    }
+}
+
+/// Builds the proving window of each deadline of the miner state at `epoch`,
+/// with the partitions that have live sectors as due.
+fn proving_schedule(
+    store: &impl Blockstore,
+    state: &miner::State,
+    policy: &Policy,
+    epoch: ChainEpoch,
+    genesis_timestamp: u64,
+    block_delay_secs: u32,
+) -> anyhow::Result<Vec<ProvingWindow>> {
+    let current = state.recorded_deadline_info(policy, epoch);
+    let mut res = Vec::new();
+    state.for_each_deadline(policy, store, |idx, deadline| {
+        let mut partitions_due = BitField::new();
+        deadline.for_each(store, |part_idx, partition| {
+            if !partition.live_sectors().is_empty() {
+                partitions_due.set(part_idx);
+            }
+            Ok(())
+        })?;
+        res.push(ProvingWindow::new(
+            &current.for_index(idx).next_not_elapsed(),
+            genesis_timestamp,
+            block_delay_secs,
+            partitions_due,
+            &deadline.partitions_posted(),
+        ));
+        Ok(())
+    })?;
+    Ok(res)
+}
+
+/// Returns the standing of a miner in consensus: whether it has a power claim,
+/// whether the claim meets the minimum, and whether it is excluded after a
+/// consensus fault.
+pub enum ForestMinerConsensusStatus {}
+
+impl RpcMethod<2> for ForestMinerConsensusStatus {
+    const NAME: &'static str = "Forest.MinerConsensusStatus";
+    const PARAM_NAMES: [&'static str; 2] = ["address", "tipset_key"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (Address, ApiTipsetKey);
+    type Ok = MinerConsensusStatus;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (address, ApiTipsetKey(tsk)): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        Ok(ctx.state_manager.miner_consensus_status(&address, &ts)?)
+    }
+}
+
 /// looks up the miner power of the given address.
 pub enum StateMinerFaults {}
 
@@ -1348,9 +1935,60 @@ impl RpcMethod<2> for StateFetchRoot {
     }
 }
 
-pub enum StateCompute {}
+/// Waits for a message to land on chain, and returns the first
+/// [`LandingEvent`](crate::state_manager::LandingEvent): the message landed,
+/// was reverted, or was not found in `timeout_epochs` tipsets.
+pub enum ForestStateWaitMsgLanding {}
+
+impl RpcMethod<2> for ForestStateWaitMsgLanding {
+    const NAME: &'static str = "Forest.StateWaitMsgLanding";
+    const PARAM_NAMES: [&'static str; 2] = ["message_cid", "timeout_epochs"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (Cid, u64);
+    type Ok = MessageLanding;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (message_cid, timeout_epochs): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let landing = ctx
+            .state_manager
+            .stream_message_landing(message_cid, timeout_epochs)?;
+        futures::pin_mut!(landing);
+        let event = landing
+            .next()
+            .await
+            .context("head change subscription closed")??;
+        Ok(event.into())
+    }
+}
+
+/// Breaks down the gas costs paid by the sender of an executed message.
+pub enum ForestStateMessageGasCost {}
+
+impl RpcMethod<2> for ForestStateMessageGasCost {
+    const NAME: &'static str = "Forest.StateMessageGasCost";
+    const PARAM_NAMES: [&'static str; 2] = ["message_cid", "tipset_key"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (Cid, ApiTipsetKey);
+    type Ok = MessageGasCost;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (message_cid, ApiTipsetKey(tsk)): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        Ok(ctx.state_manager.message_gas_cost(message_cid, ts).await?)
+    }
+}
 
-impl RpcMethod<1> for StateCompute {
+pub enum ForestStateCompute {}
+
+impl RpcMethod<1> for ForestStateCompute {
     const NAME: &'static str = "Forest.StateCompute";
     const PARAM_NAMES: [&'static str; 1] = ["epoch"];
     const API_PATHS: ApiPaths = ApiPaths::V1;
@@ -1382,6 +2020,33 @@ impl RpcMethod<1> for StateCompute {
     }
 }
 
+/// Applies the given messages at an epoch on top of the state computed for a
+/// tipset, without affecting the node, and returns the resulting state root
+/// together with the message traces.
+pub enum StateCompute {}
+
+impl RpcMethod<3> for StateCompute {
+    const NAME: &'static str = "Filecoin.StateCompute";
+    const PARAM_NAMES: [&'static str; 3] = ["height", "messages", "tipset_key"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (ChainEpoch, Vec<Message>, ApiTipsetKey);
+    type Ok = ComputeStateOutput;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (height, messages, ApiTipsetKey(tsk)): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let tipset = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        let (root, trace) = ctx
+            .state_manager
+            .state_compute(height, messages, tipset)
+            .await?;
+        Ok(ComputeStateOutput { root, trace })
+    }
+}
+
 // Convenience function for locking and popping a value out of a vector. If this function is
 // inlined, the mutex guard isn't dropped early enough.
 fn lock_pop<T>(mutex: &Mutex<Vec<T>>) -> Option<T> {
@@ -1407,7 +2072,7 @@ impl RpcMethod<4> for StateGetRandomnessFromTickets {
     ) -> Result<Self::Ok, ServerError> {
         let tipset = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
         let chain_rand = ctx.state_manager.chain_rand(tipset);
-        let digest = chain_rand.get_chain_randomness(rand_epoch, false)?;
+        let digest = Rand::get_chain_randomness(&chain_rand, rand_epoch)?;
         let value = crate::state_manager::chain_rand::draw_randomness_from_digest(
             &digest,
             personalization,
@@ -1435,7 +2100,7 @@ impl RpcMethod<2> for StateGetRandomnessDigestFromTickets {
     ) -> Result<Self::Ok, ServerError> {
         let tipset = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
         let chain_rand = ctx.state_manager.chain_rand(tipset);
-        let digest = chain_rand.get_chain_randomness(rand_epoch, false)?;
+        let digest = Rand::get_chain_randomness(&chain_rand, rand_epoch)?;
         Ok(digest.to_vec())
     }
 }
@@ -1459,7 +2124,7 @@ impl RpcMethod<4> for StateGetRandomnessFromBeacon {
     ) -> Result<Self::Ok, ServerError> {
         let tipset = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
         let chain_rand = ctx.state_manager.chain_rand(tipset);
-        let digest = chain_rand.get_beacon_randomness_v3(rand_epoch)?;
+        let digest = Rand::get_beacon_randomness(&chain_rand, rand_epoch)?;
         let value = crate::state_manager::chain_rand::draw_randomness_from_digest(
             &digest,
             personalization,
@@ -1487,7 +2152,7 @@ impl RpcMethod<2> for StateGetRandomnessDigestFromBeacon {
     ) -> Result<Self::Ok, ServerError> {
         let tipset = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
         let chain_rand = ctx.state_manager.chain_rand(tipset);
-        let digest = chain_rand.get_beacon_randomness_v3(rand_epoch)?;
+        let digest = Rand::get_beacon_randomness(&chain_rand, rand_epoch)?;
         Ok(digest.to_vec())
     }
 }
@@ -1524,6 +2189,56 @@ impl RpcMethod<2> for StateReadState {
     }
 }
 
+/// Decodes the CBOR parameters of a message into their JSON representation,
+/// based on the code of the receiving actor at the given tipset.
+pub enum StateDecodeParams {}
+
+impl RpcMethod<4> for StateDecodeParams {
+    const NAME: &'static str = "Filecoin.StateDecodeParams";
+    const PARAM_NAMES: [&'static str; 4] = ["address", "method", "params", "tipset_key"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (Address, MethodNum, Vec<u8>, ApiTipsetKey);
+    type Ok = serde_json::Value;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore>,
+        (address, method, params, ApiTipsetKey(tsk)): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        let actor = ctx
+            .state_manager
+            .get_required_actor(&address, *ts.parent_state())?;
+        Ok(crate::rpc::registry::decode_params(
+            &actor.code,
+            method,
+            &params,
+        )?)
+    }
+}
+
+/// Encodes the JSON parameters of a method on the actor with the given code
+/// into CBOR, the inverse of [`StateDecodeParams`].
+pub enum StateEncodeParams {}
+
+impl RpcMethod<3> for StateEncodeParams {
+    const NAME: &'static str = "Filecoin.StateEncodeParams";
+    const PARAM_NAMES: [&'static str; 3] = ["code", "method", "params"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (Cid, MethodNum, serde_json::Value);
+    type Ok = Vec<u8>;
+
+    async fn handle(
+        _: Ctx<impl Blockstore>,
+        (code, method, params): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        Ok(crate::rpc::registry::encode_params(&code, method, params)?)
+    }
+}
+
 pub enum StateCirculatingSupply {}
 
 impl RpcMethod<1> for StateCirculatingSupply {
@@ -1565,8 +2280,14 @@ impl RpcMethod<2> for StateVerifiedClientStatus {
         (address, ApiTipsetKey(tsk)): Self::Params,
     ) -> Result<Self::Ok, ServerError> {
         let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
-        let status = ctx.state_manager.verified_client_status(&address, &ts)?;
-        Ok(status)
+        // Unknown addresses are an error, as in Lotus
+        ctx.state_manager.lookup_required_id(&address, &ts)?;
+        let allowance = ctx
+            .state_manager
+            .get_verified_client_allowance(&address, *ts.parent_state())?;
+        // Exhausted datacap is removed from the table, so a zero allowance
+        // means that the client has no entry
+        Ok((!allowance.is_zero()).then(|| allowance.atto() / TokenAmount::PRECISION))
     }
 }
 
@@ -1586,12 +2307,9 @@ impl RpcMethod<1> for StateVMCirculatingSupplyInternal {
         (ApiTipsetKey(tsk),): Self::Params,
     ) -> Result<Self::Ok, ServerError> {
         let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
-        let genesis_info = GenesisInfo::from_chain_config(ctx.chain_config().clone());
-        Ok(genesis_info.get_vm_circulating_supply_detailed(
-            ts.epoch(),
-            &ctx.store_owned(),
-            ts.parent_state(),
-        )?)
+        Ok(ctx
+            .state_manager
+            .compute_circulating_supply_breakdown(*ts.parent_state(), ts.epoch())?)
     }
 }
 
@@ -1638,15 +2356,40 @@ impl RpcMethod<1> for StateListActors {
     ) -> Result<Self::Ok, ServerError> {
         let mut actors = vec![];
         let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
-        let state_tree = ctx.state_manager.get_state_tree(ts.parent_state())?;
-        state_tree.for_each(|addr, _state| {
-            actors.push(addr);
-            Ok(())
-        })?;
+        ctx.chain_store().walk_state_tree(
+            *ts.parent_state(),
+            &mut |addr: &Address, _state: &ActorState| {
+                actors.push(*addr);
+                VisitorResult::Continue
+            },
+        )?;
         Ok(actors)
     }
 }
 
+pub enum StateMarketDealProposal {}
+
+impl RpcMethod<2> for StateMarketDealProposal {
+    const NAME: &'static str = "Filecoin.StateMarketDealProposal";
+    const PARAM_NAMES: [&'static str; 2] = ["deal_id", "tipset_key"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = (DealID, ApiTipsetKey);
+    type Ok = Option<ApiDealProposal>;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (deal_id, ApiTipsetKey(tsk)): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
+        Ok(ctx
+            .state_manager
+            .get_deal_proposal(deal_id, *ts.parent_state(), ts.epoch())?
+            .map(Into::into))
+    }
+}
+
 pub enum StateMarketStorageDeal {}
 
 impl RpcMethod<2> for StateMarketStorageDeal {
@@ -2170,47 +2913,61 @@ impl RpcMethod<3> for StateSectorExpiration {
         ctx: Ctx<impl Blockstore>,
         (miner_address, sector_number, ApiTipsetKey(tsk)): Self::Params,
     ) -> Result<Self::Ok, ServerError> {
-        let store = ctx.store();
-        let policy = &ctx.chain_config().policy;
         let ts = ctx.chain_store().load_required_tipset_or_heaviest(&tsk)?;
         let state: miner::State = ctx
             .state_manager
             .get_actor_state_from_address(&ts, &miner_address)?;
-        let mut early = 0;
-        let mut on_time = 0;
-        let mut terminated = false;
-        state.for_each_deadline(policy, store, |_deadline_index, deadline| {
-            deadline.for_each(store, |_partition_index, partition| {
-                if !terminated && partition.all_sectors().get(sector_number) {
-                    if partition.terminated().get(sector_number) {
-                        terminated = true;
-                        early = 0;
-                        on_time = 0;
-                        return Ok(());
-                    }
-                    let expirations: Amt<fil_actor_miner_state::v13::ExpirationSet, _> =
-                        Amt::load(&partition.expirations_epochs(), store)?;
-                    expirations.for_each(|epoch, expiration| {
-                        if expiration.early_sectors.get(sector_number) {
-                            early = epoch as _;
-                        }
-                        if expiration.on_time_sectors.get(sector_number) {
-                            on_time = epoch as _;
-                        }
-                        Ok(())
-                    })?;
+        Ok(sector_expiration(
+            ctx.store(),
+            &state,
+            &ctx.chain_config().policy,
+            sector_number,
+        )?)
+    }
+}
+
+/// Scans the expiration queue of the partition holding the sector for the
+/// epochs at which it expires on time and, if faulty, early. Terminated
+/// sectors are not found.
+fn sector_expiration(
+    store: &impl Blockstore,
+    state: &miner::State,
+    policy: &Policy,
+    sector_number: u64,
+) -> anyhow::Result<SectorExpiration> {
+    let mut early = 0;
+    let mut on_time = 0;
+    let mut terminated = false;
+    state.for_each_deadline(policy, store, |_deadline_index, deadline| {
+        deadline.for_each(store, |_partition_index, partition| {
+            if !terminated && partition.all_sectors().get(sector_number) {
+                if partition.terminated().get(sector_number) {
+                    terminated = true;
+                    early = 0;
+                    on_time = 0;
+                    return Ok(());
                 }
+                let expirations: Amt<fil_actor_miner_state::v13::ExpirationSet, _> =
+                    Amt::load(&partition.expirations_epochs(), store)?;
+                expirations.for_each(|epoch, expiration| {
+                    if expiration.early_sectors.get(sector_number) {
+                        early = epoch as _;
+                    }
+                    if expiration.on_time_sectors.get(sector_number) {
+                        on_time = epoch as _;
+                    }
+                    Ok(())
+                })?;
+            }
 
-                Ok(())
-            })?;
             Ok(())
         })?;
-        if early == 0 && on_time == 0 {
-            Err(anyhow::anyhow!("failed to find sector {sector_number}").into())
-        } else {
-            Ok(SectorExpiration { early, on_time })
-        }
+        Ok(())
+    })?;
+    if early == 0 && on_time == 0 {
+        anyhow::bail!("failed to find sector {sector_number}")
     }
+    Ok(SectorExpiration { early, on_time })
 }
 
 pub enum StateSectorPartition {}
@@ -2801,6 +3558,58 @@ impl TryFrom<&ChainConfig> for ForkUpgradeParams {
     }
 }
 
+/// Parameters of the chain and of the network: genesis, block timing, network
+/// upgrades and consensus policy.
+pub enum ForestStateNetworkParams {}
+
+impl RpcMethod<0> for ForestStateNetworkParams {
+    const NAME: &'static str = "Forest.StateNetworkParams";
+    const PARAM_NAMES: [&'static str; 0] = [];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = ();
+    type Ok = ChainNetworkParams;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let genesis = ctx.chain_store().genesis_block_header();
+        Ok(ChainNetworkParams::new(
+            ctx.chain_config(),
+            ctx.network_name.clone(),
+            *genesis.cid(),
+            genesis.timestamp,
+        ))
+    }
+}
+
+/// Depths of the HAMT and AMT structures of a state tree, and counts of its
+/// blocks, to diagnose their fragmentation.
+pub enum ForestStateTreeDepth {}
+
+impl RpcMethod<1> for ForestStateTreeDepth {
+    const NAME: &'static str = "Forest.StateTreeDepth";
+    const PARAM_NAMES: [&'static str; 1] = ["state_cid"];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Admin;
+
+    type Params = (Cid,);
+    type Ok = StateTreeDepthReport;
+
+    async fn handle(
+        ctx: Ctx<impl Blockstore + Send + Sync + 'static>,
+        (state_cid,): Self::Params,
+    ) -> Result<Self::Ok, ServerError> {
+        let chain_store = ctx.chain_store().clone();
+        Ok(
+            tokio::task::spawn_blocking(move || chain_store.analyse_state_tree_depth(state_cid))
+                .await??,
+        )
+    }
+}
+
 pub enum StateMinerInitialPledgeForSector {}
 impl RpcMethod<4> for StateMinerInitialPledgeForSector {
     const NAME: &'static str = "Filecoin.StateMinerInitialPledgeForSector";
@@ -2891,3 +3700,311 @@ fn get_pledge_ramp_params(
         Ok((0, 0))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MemoryDB;
+    use crate::shim::actors::market::DealLabel;
+    use crate::utils::bitfield::{bitfield_of, difference};
+    use crate::utils::db::CborStoreExt as _;
+    use fil_actor_market_state::v16::{DealProposal, Label, State as MarketState};
+    use fvm_shared4::address::Address as AddressV4;
+    use fvm_shared4::econ::TokenAmount as TokenAmountV4;
+    use serde_json::json;
+
+    /// Market state with deals 0, 1, 3 and 4, where odd deals have byte labels,
+    /// deal 3 not being valid UTF-8.
+    fn market_state(store: &MemoryDB) -> market::State {
+        let mut state = MarketState::new(store).unwrap();
+        let mut proposals =
+            fil_actors_shared::v16::Array::<DealProposal, _>::new_with_bit_width(store, 5);
+        for deal_id in [0, 1, 3, 4] {
+            let label = match deal_id % 2 {
+                0 => Label::String(format!("deal {deal_id}")),
+                _ if deal_id == 3 => Label::Bytes(vec![0xff, 3]),
+                _ => Label::Bytes(format!("bytes {deal_id}").into_bytes()),
+            };
+            let proposal = DealProposal {
+                piece_cid: Cid::default(),
+                piece_size: fvm_shared4::piece::PaddedPieceSize(2048),
+                verified_deal: false,
+                client: AddressV4::new_id(1000),
+                provider: AddressV4::new_id(2000),
+                label,
+                start_epoch: 10,
+                end_epoch: 20,
+                storage_price_per_epoch: TokenAmountV4::from_atto(1),
+                provider_collateral: TokenAmountV4::from_atto(2),
+                client_collateral: TokenAmountV4::from_atto(3),
+            };
+            proposals.set(deal_id, proposal).unwrap();
+        }
+        state.proposals = proposals.flush().unwrap();
+        state.next_id = 5;
+        market::State::V16(state)
+    }
+
+    fn page(store: &MemoryDB, offset: DealID, limit: u64) -> (Vec<DealID>, Option<DealID>) {
+        let page = market_deals_page(store, &market_state(store), offset, limit).unwrap();
+        (page.deals.keys().copied().collect(), page.next_offset)
+    }
+
+    #[test]
+    fn market_deals_pagination() {
+        let store = MemoryDB::default();
+        assert_eq!(page(&store, 0, 10), (vec![0, 1, 3, 4], None));
+        // The gap at deal 2 is skipped
+        assert_eq!(page(&store, 0, 2), (vec![0, 1], Some(3)));
+        assert_eq!(page(&store, 2, 2), (vec![3, 4], None));
+        assert_eq!(page(&store, 1, 1), (vec![1], Some(3)));
+        assert_eq!(page(&store, 4, 1), (vec![4], None));
+        assert_eq!(page(&store, 5, 10), (vec![], None));
+    }
+
+    #[test]
+    fn market_deal_labels_roundtrip() {
+        let store = MemoryDB::default();
+        let page = market_deals_page(&store, &market_state(&store), 0, 10).unwrap();
+        // Labels are rendered as strings, like Lotus does, unless not valid
+        // UTF-8
+        assert_eq!(
+            page.deals[&0].proposal.label,
+            DealLabel::String("deal 0".into())
+        );
+        assert_eq!(
+            page.deals[&1].proposal.label,
+            DealLabel::String("bytes 1".into())
+        );
+        assert_eq!(
+            page.deals[&3].proposal.label,
+            DealLabel::Bytes(vec![0xff, 3])
+        );
+
+        let json = serde_json::to_value(&page).unwrap();
+        assert_eq!(json["Deals"]["0"]["Proposal"]["Label"], json!("deal 0"));
+        assert_eq!(json["Deals"]["1"]["Proposal"]["Label"], json!("bytes 1"));
+        assert_eq!(
+            json["Deals"]["3"]["Proposal"]["Label"],
+            json!({"/": {"bytes": "/wM="}})
+        );
+        assert_eq!(
+            serde_json::from_value::<ApiMarketDealsPage>(json).unwrap(),
+            page
+        );
+    }
+
+    /// Miner state with sectors 1 to 4 in partition 1 of deadline 2. Sector 1
+    /// is healthy, sector 2 is faulty, sector 3 is terminated and sector 4 is
+    /// missing from the expiration queue.
+    fn miner_state(store: &MemoryDB, policy: &Policy) -> miner::State {
+        use fil_actor_miner_state::v13::{Deadline, ExpirationSet, Partition, State as MinerState};
+        use fil_actors_shared::v13::Array;
+
+        // The faulty sector was rescheduled from its on-time expiration
+        let mut expirations = Array::<ExpirationSet, _>::new_with_bit_width(store, 4);
+        let mut faulty = ExpirationSet::empty();
+        faulty.early_sectors = bitfield_of([2]);
+        expirations.set(1500, faulty).unwrap();
+        let mut on_time = ExpirationSet::empty();
+        on_time.on_time_sectors = bitfield_of([1, 3]);
+        expirations.set(3000, on_time).unwrap();
+
+        let mut partition = Partition::new(store).unwrap();
+        partition.sectors = bitfield_of([1, 2, 3, 4]);
+        partition.faults = bitfield_of([2]);
+        partition.terminated = bitfield_of([3]);
+        partition.expirations_epochs = expirations.flush().unwrap();
+        let mut partitions = Array::<Partition, _>::new_with_bit_width(store, 3);
+        partitions.set(0, Partition::new(store).unwrap()).unwrap();
+        partitions.set(1, partition).unwrap();
+        let mut deadline = Deadline::new(store).unwrap();
+        deadline.partitions = partitions.flush().unwrap();
+
+        let mut state = MinerState::new(policy, store, Cid::default(), 0, 0).unwrap();
+        let mut deadlines = state.load_deadlines(store).unwrap();
+        deadlines.due[2] = store.put_cbor_default(&deadline).unwrap();
+        state.deadlines = store.put_cbor_default(&deadlines).unwrap();
+        miner::State::V13(state)
+    }
+
+    #[test]
+    fn sector_expiration_and_partition() {
+        let store = MemoryDB::default();
+        let policy = &ChainConfig::calibnet().policy;
+        let state = miner_state(&store, policy);
+
+        assert_eq!(
+            sector_expiration(&store, &state, policy, 1).unwrap(),
+            SectorExpiration {
+                on_time: 3000,
+                early: 0
+            }
+        );
+        assert_eq!(
+            sector_expiration(&store, &state, policy, 2).unwrap(),
+            SectorExpiration {
+                on_time: 0,
+                early: 1500
+            }
+        );
+        for sector in [3, 4, 5] {
+            assert!(sector_expiration(&store, &state, policy, sector).is_err());
+        }
+
+        for sector in [1, 2, 3, 4] {
+            assert_eq!(state.find_sector(&store, sector, policy).unwrap(), (2, 1));
+        }
+        assert!(state.find_sector(&store, 5, policy).is_err());
+    }
+
+    /// Miner state with sectors in partition 0 of deadlines 0 and 3. Sectors 2,
+    /// 11 and 12 are faulty, of which 2 and 12 are recovering, and sector 13 is
+    /// terminated.
+    fn miner_state_with_faults(store: &MemoryDB, policy: &Policy) -> miner::State {
+        use fil_actor_miner_state::v13::{Deadline, Partition, State as MinerState};
+        use fil_actors_shared::v13::Array;
+
+        let mut state = MinerState::new(policy, store, Cid::default(), 0, 0).unwrap();
+        let mut deadlines = state.load_deadlines(store).unwrap();
+        for (deadline_index, sectors, faults, recoveries, terminated) in [
+            (0, vec![1, 2, 3, 4], vec![2], vec![2], vec![]),
+            (3, vec![10, 11, 12, 13], vec![11, 12], vec![12], vec![13]),
+        ] {
+            let mut partition = Partition::new(store).unwrap();
+            partition.sectors = bitfield_of(sectors);
+            partition.faults = bitfield_of(faults);
+            partition.recoveries = bitfield_of(recoveries);
+            partition.terminated = bitfield_of(terminated);
+            let mut partitions = Array::<Partition, _>::new_with_bit_width(store, 3);
+            partitions.set(0, partition).unwrap();
+            let mut deadline = Deadline::new(store).unwrap();
+            deadline.partitions = partitions.flush().unwrap();
+            deadlines.due[deadline_index] = store.put_cbor_default(&deadline).unwrap();
+        }
+        state.deadlines = store.put_cbor_default(&deadlines).unwrap();
+        miner::State::V13(state)
+    }
+
+    #[test]
+    fn partition_sectors_across_deadlines() {
+        let store = MemoryDB::default();
+        let policy = &ChainConfig::calibnet().policy;
+        let state = miner_state_with_faults(&store, policy);
+
+        let faults = state
+            .partition_sectors_union(policy, &store, |partition| {
+                partition.faulty_sectors().clone()
+            })
+            .unwrap();
+        let recoveries = state
+            .partition_sectors_union(policy, &store, |partition| {
+                partition.recovering_sectors().clone()
+            })
+            .unwrap();
+        let live = state
+            .partition_sectors_union(policy, &store, |partition| partition.live_sectors())
+            .unwrap();
+        let active = state
+            .partition_sectors_union(policy, &store, |partition| partition.active_sectors())
+            .unwrap();
+
+        let bits = |bitfield: &BitField| bitfield.iter().collect::<Vec<_>>();
+        assert_eq!(bits(&faults), [2, 11, 12]);
+        assert_eq!(bits(&recoveries), [2, 12]);
+        assert_eq!(bits(&live), [1, 2, 3, 4, 10, 11, 12]);
+        assert_eq!(bits(&active), [1, 3, 4, 10]);
+        // Active sectors are the live ones that are not faulty, and recovering
+        // sectors are still faulty
+        assert_eq!(difference(&live, &faults), active);
+        assert!(difference(&recoveries, &faults).is_empty());
+    }
+
+    #[test]
+    fn test_proving_schedule() {
+        use fil_actor_miner_state::v13::{Deadline, Partition, State as MinerState};
+        use fil_actors_shared::v13::Array;
+
+        const GENESIS_TIMESTAMP: u64 = 1598306400;
+        const BLOCK_DELAY_SECS: u32 = 30;
+        let store = MemoryDB::default();
+        let policy = &ChainConfig::calibnet().policy;
+        let window = policy.wpost_challenge_window;
+
+        // Deadline 0 has a live partition and a fully terminated one, with a
+        // PoSt for the live one. Deadline 5 has a live partition with a
+        // posted bit that does not apply to its upcoming window.
+        let mut state = MinerState::new(policy, &store, Cid::default(), 0, 0).unwrap();
+        let mut deadlines = state.load_deadlines(&store).unwrap();
+        for (deadline_index, partition_sectors, terminated) in [
+            (0, vec![vec![1, 2], vec![3]], vec![3]),
+            (5, vec![vec![4]], vec![]),
+        ] {
+            let mut partitions = Array::<Partition, _>::new_with_bit_width(&store, 3);
+            for (partition_index, sectors) in partition_sectors.into_iter().enumerate() {
+                let mut partition = Partition::new(&store).unwrap();
+                partition.sectors = bitfield_of(sectors);
+                partition.terminated = bitfield_of(terminated.clone());
+                partitions.set(partition_index as u64, partition).unwrap();
+            }
+            let mut deadline = Deadline::new(&store).unwrap();
+            deadline.partitions = partitions.flush().unwrap();
+            deadline.partitions_posted = bitfield_of([0]);
+            deadlines.due[deadline_index] = store.put_cbor_default(&deadline).unwrap();
+        }
+        state.deadlines = store.put_cbor_default(&deadlines).unwrap();
+        let state = miner::State::V13(state);
+        let schedule = |epoch| {
+            proving_schedule(
+                &store,
+                &state,
+                policy,
+                epoch,
+                GENESIS_TIMESTAMP,
+                BLOCK_DELAY_SECS,
+            )
+            .unwrap()
+        };
+
+        let windows = schedule(10);
+        assert_eq!(windows.len() as u64, policy.wpost_period_deadlines);
+        for (index, proving_window) in windows.iter().enumerate() {
+            assert_eq!(proving_window.deadline, index as u64);
+            assert_eq!(proving_window.close - proving_window.open, window);
+        }
+        let bits = |bitfield: &BitField| bitfield.iter().collect::<Vec<_>>();
+        // The open window of deadline 0, starting at genesis
+        assert_eq!((windows[0].open, windows[0].close), (0, window));
+        assert_eq!(windows[0].open_time, GENESIS_TIMESTAMP);
+        assert_eq!(
+            windows[0].close_time,
+            GENESIS_TIMESTAMP + window as u64 * BLOCK_DELAY_SECS as u64
+        );
+        assert_eq!(bits(&windows[0].partitions_due), [0]);
+        assert!(windows[0].post_submitted);
+        // An upcoming window of the same proving period
+        assert_eq!(
+            (windows[5].open, windows[5].close),
+            (5 * window, 6 * window)
+        );
+        assert_eq!(
+            windows[5].open_time,
+            GENESIS_TIMESTAMP + 5 * window as u64 * BLOCK_DELAY_SECS as u64
+        );
+        assert_eq!(bits(&windows[5].partitions_due), [0]);
+        assert!(!windows[5].post_submitted);
+        // Deadlines without partitions have nothing due
+        assert!(windows[1].partitions_due.is_empty());
+        assert!(!windows[1].post_submitted);
+
+        // Once deadline 0 closes, its next window is in the following period
+        let windows = schedule(window);
+        assert_eq!(windows[0].open, policy.wpost_proving_period);
+        assert_eq!(
+            windows[0].open_time,
+            GENESIS_TIMESTAMP + policy.wpost_proving_period as u64 * BLOCK_DELAY_SECS as u64
+        );
+        assert!(!windows[0].post_submitted);
+        assert_eq!(windows[5].open, 5 * window);
+    }
+}
@@ -1,8 +1,10 @@
 // Copyright 2019-2025 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use crate::lotus_json::{lotus_json_with_self, LotusJson};
+use crate::lotus_json::{lotus_json, lotus_json_with_self, LotusJson};
 use crate::message::Message as _;
+use crate::networks::{ChainConfig, Height};
+use crate::shim::actors::miner::DeadlineInfo;
 use crate::shim::executor::ApplyRet;
 use crate::shim::{
     address::Address,
@@ -10,11 +12,17 @@ use crate::shim::{
     econ::TokenAmount,
     error::ExitCode,
     executor::Receipt,
+    gas::GasOutputs,
     message::Message,
     state_tree::{ActorID, ActorState},
 };
+use crate::shim::{sector::RegisteredSealProofV3, version::NetworkVersion};
+use crate::state_manager::LandingEvent;
 use cid::Cid;
+use fil_actors_shared::fvm_ipld_bitfield::BitField;
 use fvm_ipld_encoding::RawBytes;
+use itertools::Itertools as _;
+use num_bigint::BigInt;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -50,7 +58,94 @@ impl PartialEq for ApiInvocResult {
     }
 }
 
-#[derive(Default, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+/// Result of [`crate::rpc::state::StateCompute`].
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct ComputeStateOutput {
+    #[serde(with = "crate::lotus_json")]
+    #[schemars(with = "LotusJson<Cid>")]
+    pub root: Cid,
+    pub trace: Vec<ApiInvocResult>,
+}
+
+lotus_json_with_self!(ComputeStateOutput);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, strum::Display)]
+pub enum MessageLandingStatus {
+    Landed,
+    Timeout,
+    Reverted,
+}
+
+lotus_json_with_self!(MessageLandingStatus);
+
+lotus_json! {
+    /// First [`LandingEvent`] of a message.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct MessageLanding {
+        pub status: MessageLandingStatus,
+        /// Epoch of the tipset holding the receipt, if landed
+        pub height: Option<ChainEpoch>,
+        pub receipt: Option<Receipt>,
+    }
+    snapshots {
+        vec![
+            (
+                serde_json::json!({
+                    "Status": "Landed",
+                    "Height": 1000,
+                    "Receipt": {
+                        "ExitCode": 0,
+                        "Return": "aGVsbG8gd29ybGQh",
+                        "GasUsed": 100,
+                        "EventsRoot": null,
+                    },
+                }),
+                MessageLanding {
+                    status: MessageLandingStatus::Landed,
+                    height: Some(1000),
+                    receipt: Some(Receipt::V3(fvm_shared3::receipt::Receipt {
+                        exit_code: fvm_shared3::error::ExitCode::new(0),
+                        return_data: RawBytes::new(Vec::from_iter(*b"hello world!")),
+                        gas_used: 100,
+                        events_root: None,
+                    })),
+                },
+            ),
+            (
+                serde_json::json!({
+                    "Status": "Timeout",
+                    "Height": null,
+                    "Receipt": null,
+                }),
+                MessageLanding {
+                    status: MessageLandingStatus::Timeout,
+                    height: None,
+                    receipt: None,
+                },
+            ),
+        ]
+    }
+}
+
+impl From<LandingEvent> for MessageLanding {
+    fn from(event: LandingEvent) -> Self {
+        let (status, height, receipt) = match event {
+            LandingEvent::Landed { epoch, receipt } => {
+                (MessageLandingStatus::Landed, Some(epoch), Some(receipt))
+            }
+            LandingEvent::Timeout => (MessageLandingStatus::Timeout, None, None),
+            LandingEvent::Reverted => (MessageLandingStatus::Reverted, None, None),
+        };
+        Self {
+            status,
+            height,
+            receipt,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "PascalCase")]
 pub struct MessageGasCost {
     #[serde(with = "crate::lotus_json")]
@@ -94,6 +189,29 @@ impl MessageGasCost {
             total_cost: message.required_funds() - &apply_ret.refund(),
         })
     }
+
+    /// Replicates the fee arithmetic of the VM from the gas used by the
+    /// message, as recorded in its receipt, and the base fee of the tipset
+    /// that executed it.
+    pub fn from_receipt(message: &Message, receipt: &Receipt, base_fee: &TokenAmount) -> Self {
+        let outputs = GasOutputs::compute(
+            receipt.gas_used(),
+            message.gas_limit,
+            base_fee,
+            &message.gas_fee_cap,
+            &message.gas_premium,
+        );
+        Self {
+            message: Some(message.cid()),
+            gas_used: TokenAmount::from_atto(receipt.gas_used()),
+            base_fee_burn: outputs.base_fee_burn(),
+            over_estimation_burn: outputs.over_estimation_burn(),
+            miner_penalty: outputs.miner_penalty(),
+            miner_tip: outputs.miner_tip(),
+            refund: outputs.refund(),
+            total_cost: message.required_funds() - outputs.refund(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
@@ -217,18 +335,492 @@ impl InvocResult {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
-#[serde(rename_all = "PascalCase")]
-pub struct SectorExpiration {
-    pub on_time: ChainEpoch,
-    pub early: ChainEpoch,
+lotus_json! {
+    #[derive(Debug, Clone, PartialEq)]
+    #[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
+    pub struct SectorExpiration {
+        pub on_time: ChainEpoch,
+        pub early: ChainEpoch,
+    }
+    snapshots {
+        vec![(
+            serde_json::json!({
+                "OnTime": 2880,
+                "Early": 0,
+            }),
+            SectorExpiration {
+                on_time: 2880,
+                early: 0,
+            },
+        )]
+    }
+}
+
+lotus_json! {
+    #[derive(Debug, Clone, PartialEq)]
+    #[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
+    pub struct SectorLocation {
+        pub deadline: u64,
+        pub partition: u64,
+    }
+    snapshots {
+        vec![(
+            serde_json::json!({
+                "Deadline": 12,
+                "Partition": 1,
+            }),
+            SectorLocation {
+                deadline: 12,
+                partition: 1,
+            },
+        )]
+    }
+}
+
+lotus_json! {
+    /// Proving window of a single miner deadline, with its epochs converted to
+    /// wall-clock time.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct ProvingWindow {
+        pub deadline: u64,
+        pub open: ChainEpoch,
+        pub close: ChainEpoch,
+        /// Unix timestamp of the `open` epoch
+        pub open_time: u64,
+        /// Unix timestamp of the `close` epoch
+        pub close_time: u64,
+        /// Partitions with live sectors that must be proven in this window
+        pub partitions_due: BitField,
+        /// Whether a `PoSt` has been recorded for this window
+        #[serde(rename = "PoStSubmitted")]
+        pub post_submitted: bool,
+    }
+    snapshots {
+        vec![(
+            serde_json::json!({
+                "Deadline": 3,
+                "Open": 1180,
+                "Close": 1240,
+                "OpenTime": 1598341800,
+                "CloseTime": 1598343600,
+                "PartitionsDue": [1, 1],
+                "PoStSubmitted": false,
+            }),
+            ProvingWindow {
+                deadline: 3,
+                open: 1180,
+                close: 1240,
+                open_time: 1598341800,
+                close_time: 1598343600,
+                partitions_due: {
+                    let mut partitions_due = BitField::new();
+                    partitions_due.set(1);
+                    partitions_due
+                },
+                post_submitted: false,
+            },
+        )]
+    }
+}
+
+impl ProvingWindow {
+    /// `partitions_posted` only applies to the currently open window, the
+    /// actor clears it when the deadline closes.
+    pub fn new(
+        info: &DeadlineInfo,
+        genesis_timestamp: u64,
+        block_delay_secs: u32,
+        partitions_due: BitField,
+        partitions_posted: &BitField,
+    ) -> Self {
+        let epoch_to_time = |epoch: ChainEpoch| {
+            genesis_timestamp.saturating_add_signed(epoch * block_delay_secs as i64)
+        };
+        Self {
+            deadline: info.index,
+            open: info.open,
+            close: info.close,
+            open_time: epoch_to_time(info.open),
+            close_time: epoch_to_time(info.close),
+            partitions_due,
+            post_submitted: info.is_open() && !partitions_posted.is_empty(),
+        }
+    }
+}
+
+lotus_json! {
+    /// Parameters of the chain and of the network, as returned by
+    /// `Forest.StateNetworkParams`.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct ChainNetworkParams {
+        pub network_name: String,
+        pub genesis_cid: Cid,
+        /// Unix timestamp of the genesis block
+        pub genesis_timestamp: u64,
+        pub genesis_network_version: NetworkVersion,
+        pub block_delay_secs: u32,
+        pub propagation_delay_secs: u32,
+        /// Network upgrades, by epoch
+        pub upgrades: Vec<NetworkUpgrade>,
+        pub policy: NetworkPolicyParams,
+    }
+    snapshots {
+        vec![(
+            serde_json::json!({
+                "NetworkName": "calibrationnet",
+                "GenesisCid": {"/": "baeaaaaa"},
+                "GenesisTimestamp": 1667326380,
+                "GenesisNetworkVersion": 0,
+                "BlockDelaySecs": 30,
+                "PropagationDelaySecs": 10,
+                "Upgrades": [{"Height": "Breeze", "Epoch": -1, "NetworkVersion": 1}],
+                "Policy": {
+                    "SupportedSectorSizes": [34359738368_u64],
+                    "ConsensusMinerMinPower": "10995116277760",
+                    "ChainFinality": 900,
+                },
+            }),
+            ChainNetworkParams {
+                network_name: "calibrationnet".into(),
+                genesis_cid: Cid::default(),
+                genesis_timestamp: 1667326380,
+                genesis_network_version: NetworkVersion::V0,
+                block_delay_secs: 30,
+                propagation_delay_secs: 10,
+                upgrades: vec![NetworkUpgrade {
+                    height: Height::Breeze,
+                    epoch: -1,
+                    network_version: NetworkVersion::V1,
+                }],
+                policy: NetworkPolicyParams {
+                    supported_sector_sizes: vec![34359738368],
+                    consensus_miner_min_power: BigInt::from(10995116277760_u64),
+                    chain_finality: 900,
+                },
+            },
+        )]
+    }
 }
-lotus_json_with_self!(SectorExpiration);
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "PascalCase")]
-pub struct SectorLocation {
-    pub deadline: u64,
-    pub partition: u64,
+pub struct NetworkUpgrade {
+    #[schemars(with = "String")]
+    pub height: Height,
+    pub epoch: ChainEpoch,
+    pub network_version: NetworkVersion,
+}
+
+lotus_json_with_self!(NetworkUpgrade);
+
+lotus_json! {
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct NetworkPolicyParams {
+        /// Sizes of the sectors that can be pre-committed, in bytes
+        pub supported_sector_sizes: Vec<u64>,
+        pub consensus_miner_min_power: BigInt,
+        pub chain_finality: ChainEpoch,
+    }
+    snapshots {
+        vec![(
+            serde_json::json!({
+                "SupportedSectorSizes": [2048],
+                "ConsensusMinerMinPower": "2048",
+                "ChainFinality": 20,
+            }),
+            NetworkPolicyParams {
+                supported_sector_sizes: vec![2048],
+                consensus_miner_min_power: BigInt::from(2048),
+                chain_finality: 20,
+            },
+        )]
+    }
+}
+
+impl ChainNetworkParams {
+    pub fn new(
+        config: &ChainConfig,
+        network_name: String,
+        genesis_cid: Cid,
+        genesis_timestamp: u64,
+    ) -> Self {
+        let upgrades = config
+            .height_infos
+            .iter()
+            .map(|(&height, info)| NetworkUpgrade {
+                height,
+                epoch: info.epoch,
+                network_version: height.into(),
+            })
+            .sorted_by_key(|upgrade| {
+                (
+                    upgrade.epoch,
+                    upgrade.network_version,
+                    upgrade.height.to_string(),
+                )
+            })
+            .collect();
+        let policy = &config.policy;
+        let supported_sector_sizes = policy
+            .valid_pre_commit_proof_type
+            .clone()
+            .into_inner()
+            .iter()
+            .enumerate()
+            .filter(|&(_, &valid)| valid)
+            .filter_map(|(i, _)| RegisteredSealProofV3::from(i as i64).sector_size().ok())
+            .map(|size| size as u64)
+            .sorted()
+            .dedup()
+            .collect();
+        Self {
+            network_name,
+            genesis_cid,
+            genesis_timestamp,
+            genesis_network_version: config.genesis_network,
+            block_delay_secs: config.block_delay_secs,
+            propagation_delay_secs: config.propagation_delay_secs,
+            upgrades,
+            policy: NetworkPolicyParams {
+                supported_sector_sizes,
+                consensus_miner_min_power: policy.minimum_consensus_power.clone(),
+                chain_finality: policy.chain_finality,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lotus_json::{assert_all_snapshots, assert_unchanged_via_json};
+    use quickcheck_macros::quickcheck;
+
+    // Mainnet genesis timestamp and proving parameters
+    const GENESIS_TIMESTAMP: u64 = 1598306400;
+    const BLOCK_DELAY_SECS: u32 = 30;
+
+    fn deadline_info(
+        period_start: ChainEpoch,
+        index: u64,
+        current_epoch: ChainEpoch,
+    ) -> DeadlineInfo {
+        DeadlineInfo::new(period_start, index, current_epoch, 48, 2880, 60, 20, 70)
+    }
+
+    #[test]
+    fn test_chain_network_params() {
+        let mainnet = ChainConfig::mainnet();
+        let params = ChainNetworkParams::new(
+            &mainnet,
+            "testnetnet".into(),
+            mainnet.genesis_cid.as_deref().unwrap().parse().unwrap(),
+            GENESIS_TIMESTAMP,
+        );
+        let json = serde_json::to_value(&params).unwrap();
+        let keys = json
+            .as_object()
+            .unwrap()
+            .keys()
+            .sorted()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            [
+                "BlockDelaySecs",
+                "GenesisCid",
+                "GenesisNetworkVersion",
+                "GenesisTimestamp",
+                "NetworkName",
+                "Policy",
+                "PropagationDelaySecs",
+                "Upgrades",
+            ]
+        );
+        assert_eq!(
+            json["GenesisCid"]["/"],
+            "bafy2bzacecnamqgqmifpluoeldx7zzglxcljo6oja4vrmtj7432rphldpdmm2"
+        );
+        assert_eq!(json["BlockDelaySecs"], 30);
+        assert_eq!(
+            json["Upgrades"][0],
+            serde_json::json!({"Height": "Breeze", "Epoch": 41280, "NetworkVersion": 1})
+        );
+        assert!(json["Upgrades"].as_array().unwrap().contains(
+            &serde_json::json!({"Height": "Hygge", "Epoch": 2683348, "NetworkVersion": 18})
+        ));
+        assert_eq!(
+            json["Policy"],
+            serde_json::json!({
+                "SupportedSectorSizes": [34359738368u64, 68719476736u64],
+                "ConsensusMinerMinPower": "10995116277760",
+                "ChainFinality": 900,
+            })
+        );
+
+        let calibnet = ChainConfig::calibnet();
+        let params = ChainNetworkParams::new(
+            &calibnet,
+            "calibrationnet".into(),
+            calibnet.genesis_cid.as_deref().unwrap().parse().unwrap(),
+            0,
+        );
+        let json = serde_json::to_value(&params).unwrap();
+        assert_eq!(json["NetworkName"], "calibrationnet");
+        assert_eq!(json["Policy"]["ConsensusMinerMinPower"], "34359738368");
+        assert!(json["Upgrades"].as_array().unwrap().contains(
+            &serde_json::json!({"Height": "Watermelon", "Epoch": 1013134, "NetworkVersion": 21})
+        ));
+        // Every upgrade is listed, in order
+        assert_eq!(params.upgrades.len(), calibnet.height_infos.len());
+        assert!(params.upgrades.is_sorted_by_key(|upgrade| upgrade.epoch));
+    }
+
+    #[test]
+    fn test_proving_window_timestamps() {
+        let info = deadline_info(1000, 3, 1000);
+        let window = ProvingWindow::new(
+            &info,
+            GENESIS_TIMESTAMP,
+            BLOCK_DELAY_SECS,
+            BitField::new(),
+            &BitField::new(),
+        );
+        assert_eq!(window.deadline, 3);
+        assert_eq!(window.open, 1180);
+        assert_eq!(window.close, 1240);
+        assert_eq!(window.open_time, GENESIS_TIMESTAMP + 1180 * 30);
+        assert_eq!(window.close_time - window.open_time, 60 * 30);
+    }
+
+    #[test]
+    fn test_proving_window_post_submitted() {
+        let mut partitions_due = BitField::new();
+        partitions_due.set(0);
+        partitions_due.set(1);
+        let mut posted = BitField::new();
+        posted.set(0);
+
+        // Open window with a recorded proof
+        let open = deadline_info(1000, 3, 1200);
+        let window = ProvingWindow::new(
+            &open,
+            GENESIS_TIMESTAMP,
+            BLOCK_DELAY_SECS,
+            partitions_due.clone(),
+            &posted,
+        );
+        assert!(window.post_submitted);
+        assert_eq!(window.partitions_due, partitions_due);
+
+        // Open window without any proofs
+        let window = ProvingWindow::new(
+            &open,
+            GENESIS_TIMESTAMP,
+            BLOCK_DELAY_SECS,
+            partitions_due.clone(),
+            &BitField::new(),
+        );
+        assert!(!window.post_submitted);
+
+        // Posted partitions only refer to the current window
+        let upcoming = deadline_info(1000, 4, 1200);
+        let window = ProvingWindow::new(
+            &upcoming,
+            GENESIS_TIMESTAMP,
+            BLOCK_DELAY_SECS,
+            partitions_due,
+            &posted,
+        );
+        assert!(!window.post_submitted);
+    }
+
+    #[test]
+    fn snapshots() {
+        assert_all_snapshots::<MessageLanding>();
+        assert_all_snapshots::<ChainNetworkParams>();
+        assert_all_snapshots::<NetworkPolicyParams>();
+        assert_all_snapshots::<SectorExpiration>();
+        assert_all_snapshots::<SectorLocation>();
+        // `BitField` is not `quickcheck::Arbitrary`
+        assert_all_snapshots::<ProvingWindow>();
+    }
+
+    #[quickcheck]
+    fn sector_expiration_roundtrip(val: SectorExpiration) {
+        assert_unchanged_via_json(val)
+    }
+
+    #[quickcheck]
+    fn sector_location_roundtrip(val: SectorLocation) {
+        assert_unchanged_via_json(val)
+    }
+
+    #[test]
+    fn test_compute_state_output_json() {
+        let output = ComputeStateOutput {
+            root: Cid::default(),
+            trace: vec![],
+        };
+        let json = serde_json::to_value(&output).unwrap();
+        assert_eq!(json["Root"]["/"], Cid::default().to_string());
+        assert_eq!(json["Trace"], serde_json::json!([]));
+        let roundtrip: ComputeStateOutput = serde_json::from_value(json).unwrap();
+        assert!(roundtrip == output);
+    }
+
+    fn gas_cost(
+        gas_used: u64,
+        gas_limit: u64,
+        base_fee: u64,
+        gas_fee_cap: u64,
+        gas_premium: u64,
+    ) -> MessageGasCost {
+        let message = Message {
+            gas_limit,
+            gas_fee_cap: TokenAmount::from_atto(gas_fee_cap),
+            gas_premium: TokenAmount::from_atto(gas_premium),
+            ..Default::default()
+        };
+        let receipt = Receipt::V4(fvm_shared4::receipt::Receipt {
+            exit_code: fvm_shared4::error::ExitCode::OK,
+            return_data: RawBytes::default(),
+            gas_used,
+            events_root: None,
+        });
+        MessageGasCost::from_receipt(&message, &receipt, &TokenAmount::from_atto(base_fee))
+    }
+
+    #[test]
+    fn test_gas_cost_over_estimated() {
+        // 25% over the gas used, of which the 10% allowance is refunded
+        // and a share of the rest is burnt
+        let cost = gas_cost(40_000_000, 50_000_000, 100, 200, 50);
+        let atto = TokenAmount::from_atto;
+        assert_eq!(cost.gas_used, atto(40_000_000));
+        assert_eq!(cost.base_fee_burn, atto(4_000_000_000_u64));
+        assert_eq!(cost.over_estimation_burn, atto(150_000_000));
+        assert_eq!(cost.miner_penalty, atto(0));
+        assert_eq!(cost.miner_tip, atto(2_500_000_000_u64));
+        assert_eq!(cost.refund, atto(3_350_000_000_u64));
+        assert_eq!(cost.total_cost, atto(6_650_000_000_u64));
+        assert_eq!(
+            cost.total_cost,
+            cost.base_fee_burn + cost.over_estimation_burn + cost.miner_tip
+        );
+    }
+
+    #[test]
+    fn test_gas_cost_fee_cap_below_base_fee() {
+        // The sender pays up to the fee cap, the miner pays the rest of the
+        // base fee and gets no tip
+        let cost = gas_cost(1_000_000, 1_100_000, 300, 250, 10);
+        let atto = TokenAmount::from_atto;
+        assert_eq!(cost.base_fee_burn, atto(250_000_000));
+        assert_eq!(cost.over_estimation_burn, atto(0));
+        assert_eq!(cost.miner_penalty, atto(50_000_000));
+        assert_eq!(cost.miner_tip, atto(0));
+        assert_eq!(cost.refund, atto(25_000_000));
+        assert_eq!(cost.total_cost, atto(250_000_000));
+    }
 }
-lotus_json_with_self!(SectorLocation);
@@ -3,19 +3,17 @@
 
 use crate::blocks::{Block, FullTipset, GossipBlock};
 use crate::libp2p::{IdentTopic, NetworkMessage, PUBSUB_BLOCK_STR};
-use crate::lotus_json::{lotus_json_with_self, LotusJson};
+use crate::lotus_json::lotus_json;
 use crate::rpc::{ApiPaths, Ctx, Permission, RpcMethod, ServerError};
 use anyhow::{anyhow, Context as _};
 use cid::Cid;
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::to_vec;
 use nunny::{vec as nonempty, Vec as NonEmpty};
-use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 use crate::chain;
-use crate::chain_sync::{SyncStage, TipsetValidator};
+use crate::chain_sync::{SyncStage, SyncStateSnapshot, TipsetValidator};
 
 pub enum SyncCheckBad {}
 impl RpcMethod<1> for SyncCheckBad {
@@ -51,6 +49,7 @@ impl RpcMethod<1> for SyncMarkBad {
     ) -> Result<Self::Ok, ServerError> {
         ctx.bad_blocks
             .put(cid, "Marked bad manually through RPC API".to_string());
+        ctx.chain_store().unmark_tipsets_with_block(&cid)?;
         Ok(())
     }
 }
@@ -71,6 +70,27 @@ impl RpcMethod<0> for SyncState {
     }
 }
 
+/// Exports the heaviest tipset, the peer heads, the pending tipsets and the
+/// sync target, to investigate sync stalls.
+pub enum SyncStateExport {}
+impl RpcMethod<0> for SyncStateExport {
+    const NAME: &'static str = "Forest.SyncStateExport";
+    const PARAM_NAMES: [&'static str; 0] = [];
+    const API_PATHS: ApiPaths = ApiPaths::V1;
+    const PERMISSION: Permission = Permission::Read;
+
+    type Params = ();
+    type Ok = SyncStateSnapshot;
+
+    async fn handle(ctx: Ctx<impl Blockstore>, (): Self::Params) -> Result<Self::Ok, ServerError> {
+        Ok(ctx.chain_store().sync_state_snapshot(
+            ctx.sync_network_context.peer_manager(),
+            &ctx.sync_state.read(),
+            ctx.tipset_send.len(),
+        ))
+    }
+}
+
 pub enum SyncSubmitBlock {}
 impl RpcMethod<1> for SyncSubmitBlock {
     const NAME: &'static str = "Filecoin.SyncSubmitBlock";
@@ -123,17 +143,30 @@ impl RpcMethod<1> for SyncSubmitBlock {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
-#[serde(rename_all = "PascalCase")]
-pub struct RPCSyncState {
-    #[schemars(with = "LotusJson<Vec<crate::chain_sync::SyncState>>")]
-    #[serde(with = "crate::lotus_json")]
-    pub active_syncs: NonEmpty<crate::chain_sync::SyncState>,
+lotus_json! {
+    #[derive(Debug, Clone, PartialEq)]
+    #[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
+    pub struct RPCSyncState {
+        pub active_syncs: NonEmpty<crate::chain_sync::SyncState>,
+    }
+    snapshots {
+        vec![(
+            serde_json::json!({
+                "ActiveSyncs": [{
+                    "Epoch": 0,
+                    "Message": "",
+                    "Stage": "header sync",
+                }],
+            }),
+            RPCSyncState {
+                active_syncs: nonempty![crate::chain_sync::SyncState::default()],
+            },
+        )]
+    }
 }
-lotus_json_with_self!(RPCSyncState);
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use std::sync::Arc;
 
     use super::*;
@@ -144,7 +177,8 @@ mod tests {
     use crate::chain_sync::{SyncConfig, SyncStage};
     use crate::db::MemoryDB;
     use crate::key_management::{KeyStore, KeyStoreConfig};
-    use crate::libp2p::{NetworkMessage, PeerManager};
+    use crate::libp2p::{NetworkMessage, PeerId, PeerManager};
+    use crate::lotus_json::{assert_all_snapshots, assert_unchanged_via_json, LotusJson};
     use crate::message_pool::{MessagePool, MpoolRpcProvider};
     use crate::networks::ChainConfig;
     use crate::rpc::eth::filter::EthEventHandler;
@@ -152,12 +186,13 @@ mod tests {
     use crate::shim::address::Address;
     use crate::state_manager::StateManager;
     use crate::utils::encoding::from_slice_with_fallback;
+    use quickcheck_macros::quickcheck;
     use tokio::sync::mpsc;
     use tokio::{sync::RwLock, task::JoinSet};
 
     const TEST_NET_NAME: &str = "test";
 
-    fn ctx() -> (Arc<RPCState<MemoryDB>>, flume::Receiver<NetworkMessage>) {
+    pub(crate) fn ctx() -> (Arc<RPCState<MemoryDB>>, flume::Receiver<NetworkMessage>) {
         let (network_send, network_rx) = flume::bounded(5);
         let (tipset_send, _) = flume::bounded(5);
         let mut services = JoinSet::new();
@@ -225,11 +260,14 @@ mod tests {
             mpool: Arc::new(pool),
             bad_blocks: Default::default(),
             sync_state: Arc::new(parking_lot::RwLock::new(Default::default())),
+            sync_watchdog: Default::default(),
             eth_event_handler: Arc::new(EthEventHandler::new()),
             sync_network_context,
             network_name: TEST_NET_NAME.to_owned(),
             start_time,
             shutdown: mpsc::channel(1).0, // dummy for tests
+            config_reloader: None,
+            db_statistics: None,
             tipset_send,
         });
         (state, network_rx)
@@ -270,4 +308,52 @@ mod tests {
 
         assert_eq!(ret.active_syncs, nonempty![st_copy.as_ref().read().clone()]);
     }
+
+    #[tokio::test]
+    async fn export_sync_state_test() {
+        let (ctx, _) = ctx();
+        let heaviest = ctx.chain_store().heaviest_tipset();
+        let genesis = Arc::new(ctx.chain_store().genesis_tipset());
+        ctx.sync_state.write().init(genesis, heaviest.clone());
+        let peer = PeerId::random();
+        ctx.sync_network_context
+            .peer_manager()
+            .update_peer_head(peer, heaviest.key().clone());
+
+        let mut exported = vec![];
+        ctx.chain_store()
+            .export_sync_state(
+                &mut exported,
+                ctx.sync_network_context.peer_manager(),
+                &ctx.sync_state.read(),
+                ctx.tipset_send.len(),
+            )
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&exported).unwrap();
+        assert_eq!(
+            json["HeaviestTipset"],
+            serde_json::to_value(LotusJson(heaviest.key().clone())).unwrap()
+        );
+
+        let snapshot = serde_json::from_value::<LotusJson<SyncStateSnapshot>>(json)
+            .unwrap()
+            .into_inner();
+        assert_eq!(
+            snapshot,
+            SyncStateExport::handle(ctx.clone(), ()).await.unwrap()
+        );
+        assert_eq!(snapshot.epoch, heaviest.epoch());
+        assert_eq!(snapshot.peer_heads, vec![(peer, heaviest.key().clone())]);
+        assert_eq!(snapshot.sync_target.as_ref(), Some(heaviest.key()));
+    }
+
+    #[test]
+    fn snapshots() {
+        assert_all_snapshots::<RPCSyncState>();
+    }
+
+    #[quickcheck]
+    fn rpc_sync_state_roundtrip(val: RPCSyncState) {
+        assert_unchanged_via_json(val)
+    }
 }
@@ -11,7 +11,6 @@ use crate::shim::{
     crypto::{Signature, SignatureType},
     econ::TokenAmount,
     message::Message,
-    state_tree::StateTree,
 };
 use fvm_ipld_blockstore::Blockstore;
 
@@ -29,11 +28,9 @@ impl RpcMethod<1> for WalletBalance {
         ctx: Ctx<impl Blockstore>,
         (address,): Self::Params,
     ) -> Result<Self::Ok, ServerError> {
-        let heaviest_ts = ctx.chain_store().heaviest_tipset();
-        let cid = heaviest_ts.parent_state();
-
-        Ok(StateTree::new_from_root(ctx.store_owned(), cid)?
-            .get_actor(&address)?
+        Ok(ctx
+            .state_manager
+            .get_actor_cached(&address)?
             .map(|it| it.balance.clone().into())
             .unwrap_or_default())
     }
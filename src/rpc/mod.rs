@@ -6,6 +6,8 @@ mod channel;
 mod client;
 mod log_layer;
 mod metrics_layer;
+mod rate_limit_layer;
+pub(crate) mod registry;
 mod request;
 
 pub use client::Client;
@@ -42,6 +44,7 @@ macro_rules! for_each_rpc_method {
         $callback!($crate::rpc::beacon::BeaconGetEntry);
 
         // chain vertical
+        $callback!($crate::rpc::chain::ChainBaseFeeHistory);
         $callback!($crate::rpc::chain::ChainExport);
         $callback!($crate::rpc::chain::ChainGetBlock);
         $callback!($crate::rpc::chain::ChainGetBlockMessages);
@@ -56,14 +59,19 @@ macro_rules! for_each_rpc_method {
         $callback!($crate::rpc::chain::ChainGetTipSet);
         $callback!($crate::rpc::chain::ChainGetTipSetAfterHeight);
         $callback!($crate::rpc::chain::ChainGetTipSetByHeight);
+        $callback!($crate::rpc::chain::ChainGetTipsetBlockMessages);
         $callback!($crate::rpc::chain::ChainHasObj);
         $callback!($crate::rpc::chain::ChainHead);
         $callback!($crate::rpc::chain::ChainReadObj);
         $callback!($crate::rpc::chain::ChainSetHead);
         $callback!($crate::rpc::chain::ChainStatObj);
         $callback!($crate::rpc::chain::ChainTipSetWeight);
+        $callback!($crate::rpc::chain::ForestChainGetGenesisInfo);
+        $callback!($crate::rpc::chain::ForestExportSubgraph);
+        $callback!($crate::rpc::chain::ForestMessageInclusionProof);
 
         // common vertical
+        $callback!($crate::rpc::common::ReloadConfig);
         $callback!($crate::rpc::common::Session);
         $callback!($crate::rpc::common::Shutdown);
         $callback!($crate::rpc::common::StartTime);
@@ -125,11 +133,16 @@ macro_rules! for_each_rpc_method {
         $callback!($crate::rpc::mpool::MpoolBatchPush);
         $callback!($crate::rpc::mpool::MpoolBatchPushUntrusted);
         $callback!($crate::rpc::mpool::MpoolGetNonce);
+        $callback!($crate::rpc::mpool::MpoolImport);
+        $callback!($crate::rpc::mpool::MpoolLocals);
         $callback!($crate::rpc::mpool::MpoolPending);
+        $callback!($crate::rpc::mpool::MpoolPendingForAddress);
         $callback!($crate::rpc::mpool::MpoolPush);
         $callback!($crate::rpc::mpool::MpoolPushMessage);
         $callback!($crate::rpc::mpool::MpoolPushUntrusted);
+        $callback!($crate::rpc::mpool::MpoolRebroadcast);
         $callback!($crate::rpc::mpool::MpoolSelect);
+        $callback!($crate::rpc::mpool::MpoolStat);
 
         // msig vertical
         $callback!($crate::rpc::msig::MsigGetAvailableBalance);
@@ -147,20 +160,36 @@ macro_rules! for_each_rpc_method {
         $callback!($crate::rpc::net::NetInfo);
         $callback!($crate::rpc::net::NetListening);
         $callback!($crate::rpc::net::NetPeers);
+        $callback!($crate::rpc::net::NetPersistedPeers);
+        $callback!($crate::rpc::net::NetPing);
         $callback!($crate::rpc::net::NetProtectAdd);
         $callback!($crate::rpc::net::NetProtectList);
         $callback!($crate::rpc::net::NetProtectRemove);
+        $callback!($crate::rpc::net::NetReachability);
         $callback!($crate::rpc::net::NetVersion);
 
         // node vertical
+        $callback!($crate::rpc::node::CacheStats);
+        $callback!($crate::rpc::node::DbColumnStats);
+        $callback!($crate::rpc::node::ForestNodeStatus);
         $callback!($crate::rpc::node::NodeStatus);
 
         // state vertical
+        $callback!($crate::rpc::state::ForestMinerConsensusStatus);
+        $callback!($crate::rpc::state::ForestStateCompute);
+        $callback!($crate::rpc::state::ForestStateMessageGasCost);
+        $callback!($crate::rpc::state::ForestStateNetworkParams);
+        $callback!($crate::rpc::state::ForestStateTreeDepth);
+        $callback!($crate::rpc::state::ForestStateWaitMsgLanding);
+        $callback!($crate::rpc::state::MinerProvingSchedule);
         $callback!($crate::rpc::state::StateAccountKey);
+        $callback!($crate::rpc::state::StateBlockProducerStats);
         $callback!($crate::rpc::state::StateCall);
         $callback!($crate::rpc::state::StateCirculatingSupply);
         $callback!($crate::rpc::state::StateCompute);
         $callback!($crate::rpc::state::StateDealProviderCollateralBounds);
+        $callback!($crate::rpc::state::StateDecodeParams);
+        $callback!($crate::rpc::state::StateEncodeParams);
         $callback!($crate::rpc::state::StateFetchRoot);
         $callback!($crate::rpc::state::StateGetActor);
         $callback!($crate::rpc::state::StateGetAllAllocations);
@@ -184,24 +213,39 @@ macro_rules! for_each_rpc_method {
         $callback!($crate::rpc::state::StateLookupID);
         $callback!($crate::rpc::state::StateLookupRobustAddress);
         $callback!($crate::rpc::state::StateMarketBalance);
+        $callback!($crate::rpc::state::StateMarketDealProposal);
         $callback!($crate::rpc::state::StateMarketDeals);
+        $callback!($crate::rpc::state::StateMarketDealsPage);
         $callback!($crate::rpc::state::StateMarketParticipants);
         $callback!($crate::rpc::state::StateMarketStorageDeal);
         $callback!($crate::rpc::state::StateMinerActiveSectors);
         $callback!($crate::rpc::state::StateMinerAllocated);
         $callback!($crate::rpc::state::StateMinerAvailableBalance);
+        $callback!($crate::rpc::state::StateMinerControlAddresses);
         $callback!($crate::rpc::state::StateMinerDeadlines);
         $callback!($crate::rpc::state::StateMinerFaults);
+        $callback!($crate::rpc::state::StateMinerFeeDebtProjection);
         $callback!($crate::rpc::state::StateMinerInfo);
         $callback!($crate::rpc::state::StateMinerInitialPledgeCollateral);
         $callback!($crate::rpc::state::StateMinerPartitions);
         $callback!($crate::rpc::state::StateMinerPower);
         $callback!($crate::rpc::state::StateMinerPreCommitDepositForPower);
+        $callback!($crate::rpc::state::StateMinerPreCommitDeposits);
         $callback!($crate::rpc::state::StateMinerProvingDeadline);
+        $callback!($crate::rpc::state::StateMinerProvingPeriodStart);
         $callback!($crate::rpc::state::StateMinerRecoveries);
         $callback!($crate::rpc::state::StateMinerSectorAllocated);
+        $callback!($crate::rpc::state::StateMinerSectorClaims);
         $callback!($crate::rpc::state::StateMinerSectorCount);
+        $callback!($crate::rpc::state::StateMinerSectorCountAtEpoch);
+        $callback!($crate::rpc::state::StateMinerSectorPenalties);
+        $callback!($crate::rpc::state::StateMinerSectorPower);
+        $callback!($crate::rpc::state::StateMinerSectorQAPower);
+        $callback!($crate::rpc::state::StateMinerSectorRewardEstimate);
+        $callback!($crate::rpc::state::StateMinerSectorUpgradeCost);
         $callback!($crate::rpc::state::StateMinerSectors);
+        $callback!($crate::rpc::state::StateMinerWorkerKeyChange);
+        $callback!($crate::rpc::state::StateNetworkBaselinePower);
         $callback!($crate::rpc::state::StateNetworkName);
         $callback!($crate::rpc::state::StateNetworkVersion);
         $callback!($crate::rpc::state::StateReadState);
@@ -213,6 +257,8 @@ macro_rules! for_each_rpc_method {
         $callback!($crate::rpc::state::StateSectorPartition);
         $callback!($crate::rpc::state::StateSectorPreCommitInfo);
         $callback!($crate::rpc::state::StateSectorPreCommitInfoV0);
+        $callback!($crate::rpc::state::StateSimulateMessageBatch);
+        $callback!($crate::rpc::state::StateTokenVestingStats);
         $callback!($crate::rpc::state::StateVerifiedClientStatus);
         $callback!($crate::rpc::state::StateVerifiedRegistryRootKey);
         $callback!($crate::rpc::state::StateVerifierStatus);
@@ -225,6 +271,7 @@ macro_rules! for_each_rpc_method {
         $callback!($crate::rpc::sync::SyncCheckBad);
         $callback!($crate::rpc::sync::SyncMarkBad);
         $callback!($crate::rpc::sync::SyncState);
+        $callback!($crate::rpc::sync::SyncStateExport);
         $callback!($crate::rpc::sync::SyncSubmitBlock);
 
         // wallet vertical
@@ -357,6 +404,7 @@ use crate::rpc::auth_layer::AuthLayer;
 use crate::rpc::channel::RpcModule as FilRpcModule;
 pub use crate::rpc::channel::CANCEL_METHOD_NAME;
 use crate::rpc::metrics_layer::MetricsLayer;
+use crate::rpc::rate_limit_layer::{RateLimitLayer, RateLimiter};
 use crate::{chain_sync::network_context::SyncNetworkContext, key_management::KeyStore};
 
 use crate::blocks::Tipset;
@@ -370,7 +418,7 @@ use std::env;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{mpsc, watch, RwLock};
 use tower::Service;
 
 use openrpc_types::{self, ParamStructure};
@@ -396,12 +444,17 @@ pub struct RPCState<DB> {
     pub mpool: Arc<crate::message_pool::MessagePool<crate::message_pool::MpoolRpcProvider<DB>>>,
     pub bad_blocks: Arc<crate::chain_sync::BadBlockCache>,
     pub sync_state: Arc<parking_lot::RwLock<crate::chain_sync::SyncState>>,
+    pub sync_watchdog: Arc<parking_lot::RwLock<crate::chain_sync::WatchdogStatus>>,
     pub eth_event_handler: Arc<EthEventHandler>,
     pub sync_network_context: SyncNetworkContext<DB>,
     pub network_name: String,
     pub tipset_send: flume::Sender<Arc<Tipset>>,
     pub start_time: chrono::DateTime<chrono::Utc>,
     pub shutdown: mpsc::Sender<()>,
+    /// Reloads the daemon configuration, if supported by the node.
+    pub config_reloader: Option<Arc<crate::daemon::config_reload::ConfigReloader>>,
+    /// Statistics of the node database, if supported by the node.
+    pub db_statistics: Option<Arc<dyn crate::db::DBStatistics + Send + Sync>>,
 }
 
 impl<DB: Blockstore> RPCState<DB> {
@@ -434,12 +487,65 @@ impl<DB: Blockstore> RPCState<DB> {
     }
 }
 
+/// Method name prefixes of the methods that depend on the state tree and are
+/// therefore unavailable in lite mode.
+const LITE_MODE_UNAVAILABLE_PREFIXES: &[&str] = &[
+    "Filecoin.ChainGetEvents",
+    "Filecoin.ChainGetParentReceipts",
+    "Filecoin.Eth",
+    "Filecoin.Gas",
+    "Filecoin.GetActorEvents",
+    "Filecoin.Market",
+    "Filecoin.Miner",
+    "Filecoin.Mpool",
+    "Filecoin.Msig",
+    "Filecoin.State",
+    "Filecoin.WalletBalance",
+    "Forest.Miner",
+    "Forest.Mpool",
+    "Forest.State",
+];
+
+/// Methods matching [`LITE_MODE_UNAVAILABLE_PREFIXES`] that only read block
+/// headers or the chain configuration and remain available in lite mode.
+const LITE_MODE_AVAILABLE: &[&str] = &[
+    "Filecoin.EthBlockNumber",
+    "Filecoin.EthChainId",
+    "Filecoin.EthProtocolVersion",
+    "Filecoin.EthSyncing",
+    "Filecoin.StateGetBeaconEntry",
+    "Filecoin.StateGetNetworkParams",
+    "Filecoin.StateGetRandomnessDigestFromBeacon",
+    "Filecoin.StateGetRandomnessDigestFromTickets",
+    "Filecoin.StateGetRandomnessFromBeacon",
+    "Filecoin.StateGetRandomnessFromTickets",
+    "Filecoin.StateNetworkVersion",
+];
+
+/// Returns an error if the node runs in lite mode and `method_name` requires
+/// state.
+pub(crate) fn check_lite_mode<DB: Blockstore>(
+    state_manager: &crate::state_manager::StateManager<DB>,
+    method_name: &str,
+) -> Result<(), ServerError> {
+    if state_manager.sync_config().lite
+        && LITE_MODE_UNAVAILABLE_PREFIXES
+            .iter()
+            .any(|prefix| method_name.starts_with(prefix))
+        && !LITE_MODE_AVAILABLE.contains(&method_name)
+    {
+        return Err(ServerError::unavailable_in_lite_mode());
+    }
+    Ok(())
+}
+
 #[derive(Clone)]
 struct PerConnection<RpcMiddleware, HttpMiddleware> {
     methods: Methods,
     stop_handle: StopHandle,
     svc_builder: TowerServiceBuilder<RpcMiddleware, HttpMiddleware>,
     keystore: Arc<RwLock<KeyStore>>,
+    rate_limiter: Arc<RateLimiter>,
 }
 
 pub async fn start_rpc<DB>(state: RPCState<DB>, rpc_endpoint: SocketAddr) -> anyhow::Result<()>
@@ -449,13 +555,22 @@ where
     // `Arc` is needed because we will share the state between two modules
     let state = Arc::new(state);
     let keystore = state.keystore.clone();
+    let rate_limit = match &state.config_reloader {
+        Some(config_reloader) => config_reloader.rpc_rate_limit(),
+        None => watch::channel(None).1,
+    };
+    let rate_limiter = Arc::new(RateLimiter::new(rate_limit));
     let mut module = create_module(state.clone());
 
     let mut pubsub_module = FilRpcModule::default();
 
     pubsub_module.register_channel("Filecoin.ChainNotify", {
         let state_clone = state.clone();
-        move |params| chain::chain_notify(params, &state_clone)
+        move |params| Ok(chain::chain_notify(params, &state_clone))
+    })?;
+    pubsub_module.register_channel(misc::SUBSCRIBE_ACTOR_EVENTS, {
+        let state_clone = state.clone();
+        move |params| misc::subscribe_actor_events(params, &state_clone)
     })?;
     module.merge(pubsub_module)?;
 
@@ -470,6 +585,7 @@ where
             .max_response_body_size(MAX_RESPONSE_BODY_SIZE)
             .to_service_builder(),
         keystore,
+        rate_limiter,
     };
 
     let listener = tokio::net::TcpListener::bind(rpc_endpoint).await.unwrap();
@@ -497,6 +613,7 @@ where
                     stop_handle,
                     svc_builder,
                     keystore,
+                    rate_limiter,
                 } = per_conn.clone();
                 let http_middleware = tower::ServiceBuilder::new()
                     .layer(CompressionLayer::new())
@@ -508,6 +625,9 @@ where
                 // with data from the connection such as the headers in this example
                 let headers = req.headers().clone();
                 let rpc_middleware = RpcServiceBuilder::new()
+                    .layer(RateLimitLayer {
+                        limiter: rate_limiter,
+                    })
                     .layer(AuthLayer {
                         headers,
                         keystore: keystore.clone(),
@@ -670,4 +790,126 @@ mod tests {
             insta::assert_yaml_snapshot!(_spec);
         }
     }
+
+    #[test]
+    fn lite_mode_state_methods() {
+        use crate::blocks::{CachingBlockHeader, RawBlockHeader};
+        use crate::chain::ChainStore;
+        use crate::chain_sync::SyncConfig;
+        use crate::db::MemoryDB;
+        use crate::networks::ChainConfig;
+        use crate::rpc::{beacon, chain, eth, state, RpcMethod as _};
+        use crate::shim::address::Address;
+        use crate::state_manager::StateManager;
+        use std::sync::Arc;
+
+        let state_manager = |lite| {
+            let db = Arc::new(MemoryDB::default());
+            let chain_config = Arc::new(ChainConfig::default());
+            let genesis = CachingBlockHeader::new(RawBlockHeader {
+                miner_address: Address::new_id(0),
+                ..Default::default()
+            });
+            let cs = Arc::new(
+                ChainStore::new(db.clone(), db.clone(), db, chain_config.clone(), genesis).unwrap(),
+            );
+            let sync_config = Arc::new(SyncConfig {
+                lite,
+                ..Default::default()
+            });
+            StateManager::new(cs, chain_config, sync_config).unwrap()
+        };
+
+        let full = state_manager(false);
+        let lite = state_manager(true);
+        for method in [
+            chain::ChainHead::NAME,
+            chain::ChainGetBlock::NAME,
+            beacon::BeaconGetEntry::NAME,
+            state::StateGetRandomnessFromTickets::NAME,
+            state::StateGetRandomnessFromBeacon::NAME,
+        ] {
+            super::check_lite_mode(&lite, method).unwrap();
+        }
+        for method in [
+            state::StateGetActor::NAME,
+            state::StateCall::NAME,
+            eth::EthGetBalance::NAME,
+            "Filecoin.MpoolPending",
+            "Filecoin.WalletBalance",
+        ] {
+            super::check_lite_mode(&full, method).unwrap();
+            let err = super::check_lite_mode(&lite, method).unwrap_err();
+            assert_eq!(err.message(), "not available in lite mode");
+        }
+    }
+
+    #[tokio::test]
+    async fn chain_notify_subscription() {
+        use crate::blocks::{CachingBlockHeader, RawBlockHeader, Tipset};
+        use crate::chain::HeadChange;
+        use crate::rpc::chain::ApiHeadChange;
+        use futures::StreamExt as _;
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let (state, _) = crate::rpc::sync::tests::ctx();
+        let chain_store = state.chain_store().clone();
+        let current = chain_store.heaviest_tipset();
+        let rpc_endpoint = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        tokio::spawn(super::start_rpc(
+            Arc::into_inner(state).unwrap(),
+            rpc_endpoint,
+        ));
+        let client = super::Client::from_url(format!("http://{rpc_endpoint}/").parse().unwrap());
+        // Wait for the server to accept connections
+        let mut head_changes = loop {
+            match client.chain_notify().await {
+                Ok(head_changes) => break Box::pin(head_changes),
+                Err(_) => tokio::time::sleep(Duration::from_millis(100)).await,
+            }
+        };
+        async fn next(
+            head_changes: &mut (impl futures::Stream<Item = Result<Vec<ApiHeadChange>, super::ClientError>>
+                      + Unpin),
+        ) -> Vec<ApiHeadChange> {
+            tokio::time::timeout(Duration::from_secs(10), head_changes.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap()
+        }
+
+        // The channel starts with the current head
+        assert_eq!(
+            next(&mut head_changes).await,
+            vec![ApiHeadChange {
+                change: "current".into(),
+                tipset: current.as_ref().clone(),
+            }]
+        );
+        let head = Arc::new(Tipset::from(CachingBlockHeader::new(RawBlockHeader {
+            epoch: current.epoch() + 1,
+            parents: current.key().clone(),
+            ..Default::default()
+        })));
+        // The first head change published after the channel is opened is
+        // skipped
+        for _ in 0..2 {
+            chain_store
+                .publisher()
+                .send(HeadChange::Apply(head.clone()))
+                .unwrap();
+        }
+        assert_eq!(
+            next(&mut head_changes).await,
+            vec![ApiHeadChange {
+                change: "apply".into(),
+                tipset: head.as_ref().clone(),
+            }]
+        );
+    }
 }
@@ -0,0 +1,130 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Middleware layer limiting the rate of RPC calls across all connections.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use jsonrpsee::server::middleware::rpc::RpcServiceT;
+use jsonrpsee::types::ErrorObject;
+use jsonrpsee::MethodResponse;
+use parking_lot::Mutex;
+use tokio::sync::watch;
+use tower::Layer;
+
+/// Token bucket refilled at the configured number of calls per second, holding
+/// at most as many tokens. The limit can be changed at runtime through the
+/// watch channel, `None` meaning no limit.
+pub(super) struct RateLimiter {
+    limit: watch::Receiver<Option<u32>>,
+    bucket: Mutex<Bucket>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(limit: watch::Receiver<Option<u32>>) -> Self {
+        Self {
+            limit,
+            bucket: Mutex::new(Bucket {
+                tokens: f64::INFINITY,
+                updated: Instant::now(),
+            }),
+        }
+    }
+
+    /// Takes a token from the bucket, returning `false` if it is empty.
+    fn try_acquire(&self, now: Instant) -> bool {
+        let Some(limit) = *self.limit.borrow() else {
+            return true;
+        };
+        let limit = f64::from(limit);
+        let mut bucket = self.bucket.lock();
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * limit).min(limit);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(super) struct RateLimitLayer {
+    pub limiter: Arc<RateLimiter>,
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimit<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        RateLimit {
+            limiter: self.limiter.clone(),
+            service,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(super) struct RateLimit<S> {
+    limiter: Arc<RateLimiter>,
+    service: S,
+}
+
+impl<'a, S> RpcServiceT<'a> for RateLimit<S>
+where
+    S: RpcServiceT<'a> + Send + Sync + Clone + 'static,
+{
+    type Future = BoxFuture<'a, MethodResponse>;
+
+    fn call(&self, req: jsonrpsee::types::Request<'a>) -> Self::Future {
+        if self.limiter.try_acquire(Instant::now()) {
+            self.service.call(req).boxed()
+        } else {
+            let resp = MethodResponse::error(
+                req.id(),
+                ErrorObject::borrowed(
+                    http::StatusCode::TOO_MANY_REQUESTS.as_u16() as _,
+                    "Too Many Requests",
+                    None,
+                ),
+            );
+            async move { resp }.boxed()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn rate_limiter() {
+        let (limit, receiver) = watch::channel(None);
+        let limiter = RateLimiter::new(receiver);
+        let start = Instant::now();
+        assert!((0..100).all(|_| limiter.try_acquire(start)));
+
+        limit.send_replace(Some(2));
+        assert!(limiter.try_acquire(start));
+        assert!(limiter.try_acquire(start));
+        assert!(!limiter.try_acquire(start));
+        // Refilled at 2 tokens per second
+        let later = start + Duration::from_millis(500);
+        assert!(limiter.try_acquire(later));
+        assert!(!limiter.try_acquire(later));
+
+        limit.send_replace(None);
+        assert!(limiter.try_acquire(later));
+    }
+}
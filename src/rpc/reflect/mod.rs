@@ -229,6 +229,7 @@ pub trait RpcMethodExt<const ARITY: usize>: RpcMethod<ARITY> {
         );
 
         module.register_async_method(Self::NAME, move |params, ctx, _extensions| async move {
+            crate::rpc::check_lite_mode(&ctx.state_manager, Self::NAME)?;
             let params = Self::parse_params(params.as_str(), calling_convention)
                 .map_err(|e| Error::invalid_params(e, None))?;
             let ok = Self::handle(ctx, params).await?;
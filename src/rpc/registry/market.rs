@@ -0,0 +1,215 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::*;
+use crate::shim::{
+    actors::market::DealLabel,
+    address::Address,
+    clock::ChainEpoch,
+    crypto::{Signature, SignatureType},
+    econ::TokenAmount,
+};
+use fil_actor_market_state::v16::{
+    ClientDealProposal, DealProposal, Label, Method, PublishStorageDealsParams,
+    WithdrawBalanceParams,
+};
+use fvm_shared4::{
+    address::Address as AddressV4,
+    crypto::signature::{Signature as SignatureV4, SignatureType as SignatureTypeV4},
+    piece::PaddedPieceSize,
+};
+use serde::Deserialize;
+
+pub(super) fn register(methods: &mut BTreeMap<(BuiltinActor, MethodNum), ParamsCodec>) {
+    methods.insert(
+        (BuiltinActor::Market, Method::AddBalance as _),
+        ParamsCodec::new::<AddressV4, AddBalanceParamsJson>("AddBalance"),
+    );
+    methods.insert(
+        (BuiltinActor::Market, Method::WithdrawBalance as _),
+        ParamsCodec::new::<WithdrawBalanceParams, WithdrawBalanceParamsJson>("WithdrawBalance"),
+    );
+    methods.insert(
+        (BuiltinActor::Market, Method::PublishStorageDeals as _),
+        ParamsCodec::new::<PublishStorageDealsParams, PublishStorageDealsParamsJson>(
+            "PublishStorageDeals",
+        ),
+    );
+}
+
+/// `AddBalance` takes the bare address to credit.
+#[derive(Serialize, Deserialize)]
+#[serde(transparent)]
+struct AddBalanceParamsJson(#[serde(with = "crate::lotus_json")] Address);
+
+impl From<AddressV4> for AddBalanceParamsJson {
+    fn from(address: AddressV4) -> Self {
+        Self(address.into())
+    }
+}
+
+impl TryFrom<AddBalanceParamsJson> for AddressV4 {
+    type Error = anyhow::Error;
+
+    fn try_from(json: AddBalanceParamsJson) -> anyhow::Result<Self> {
+        Ok(json.0.into())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct WithdrawBalanceParamsJson {
+    #[serde(with = "crate::lotus_json")]
+    provider_or_client: Address,
+    #[serde(with = "crate::lotus_json")]
+    amount: TokenAmount,
+}
+
+impl From<WithdrawBalanceParams> for WithdrawBalanceParamsJson {
+    fn from(params: WithdrawBalanceParams) -> Self {
+        Self {
+            provider_or_client: params.provider_or_client.into(),
+            amount: params.amount.into(),
+        }
+    }
+}
+
+impl TryFrom<WithdrawBalanceParamsJson> for WithdrawBalanceParams {
+    type Error = anyhow::Error;
+
+    fn try_from(json: WithdrawBalanceParamsJson) -> anyhow::Result<Self> {
+        Ok(Self {
+            provider_or_client: json.provider_or_client.into(),
+            amount: json.amount.into(),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DealProposalJson {
+    #[serde(rename = "PieceCID", with = "crate::lotus_json")]
+    piece_cid: Cid,
+    piece_size: u64,
+    verified_deal: bool,
+    #[serde(with = "crate::lotus_json")]
+    client: Address,
+    #[serde(with = "crate::lotus_json")]
+    provider: Address,
+    #[serde(with = "crate::lotus_json")]
+    label: DealLabel,
+    start_epoch: ChainEpoch,
+    end_epoch: ChainEpoch,
+    #[serde(with = "crate::lotus_json")]
+    storage_price_per_epoch: TokenAmount,
+    #[serde(with = "crate::lotus_json")]
+    provider_collateral: TokenAmount,
+    #[serde(with = "crate::lotus_json")]
+    client_collateral: TokenAmount,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ClientDealProposalJson {
+    proposal: DealProposalJson,
+    #[serde(with = "crate::lotus_json")]
+    client_signature: Signature,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PublishStorageDealsParamsJson {
+    deals: Vec<ClientDealProposalJson>,
+}
+
+impl From<PublishStorageDealsParams> for PublishStorageDealsParamsJson {
+    fn from(params: PublishStorageDealsParams) -> Self {
+        Self {
+            deals: params
+                .deals
+                .into_iter()
+                .map(
+                    |ClientDealProposal {
+                         proposal,
+                         client_signature,
+                     }| {
+                        ClientDealProposalJson {
+                            proposal: DealProposalJson {
+                                piece_cid: proposal.piece_cid,
+                                piece_size: proposal.piece_size.0,
+                                verified_deal: proposal.verified_deal,
+                                client: proposal.client.into(),
+                                provider: proposal.provider.into(),
+                                label: match proposal.label {
+                                    Label::String(s) => DealLabel::String(s),
+                                    Label::Bytes(bytes) => DealLabel::Bytes(bytes),
+                                },
+                                start_epoch: proposal.start_epoch,
+                                end_epoch: proposal.end_epoch,
+                                storage_price_per_epoch: proposal.storage_price_per_epoch.into(),
+                                provider_collateral: proposal.provider_collateral.into(),
+                                client_collateral: proposal.client_collateral.into(),
+                            },
+                            client_signature: Signature::new(
+                                match client_signature.sig_type {
+                                    SignatureTypeV4::Secp256k1 => SignatureType::Secp256k1,
+                                    SignatureTypeV4::BLS => SignatureType::Bls,
+                                },
+                                client_signature.bytes,
+                            ),
+                        }
+                    },
+                )
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<PublishStorageDealsParamsJson> for PublishStorageDealsParams {
+    type Error = anyhow::Error;
+
+    fn try_from(json: PublishStorageDealsParamsJson) -> anyhow::Result<Self> {
+        let deals = json
+            .deals
+            .into_iter()
+            .map(
+                |ClientDealProposalJson {
+                     proposal,
+                     client_signature,
+                 }| {
+                    anyhow::Ok(ClientDealProposal {
+                        proposal: DealProposal {
+                            piece_cid: proposal.piece_cid,
+                            piece_size: PaddedPieceSize(proposal.piece_size),
+                            verified_deal: proposal.verified_deal,
+                            client: proposal.client.into(),
+                            provider: proposal.provider.into(),
+                            label: match proposal.label {
+                                DealLabel::String(s) => Label::String(s),
+                                DealLabel::Bytes(bytes) => Label::Bytes(bytes),
+                            },
+                            start_epoch: proposal.start_epoch,
+                            end_epoch: proposal.end_epoch,
+                            storage_price_per_epoch: proposal.storage_price_per_epoch.into(),
+                            provider_collateral: proposal.provider_collateral.into(),
+                            client_collateral: proposal.client_collateral.into(),
+                        },
+                        client_signature: SignatureV4 {
+                            sig_type: match client_signature.sig_type {
+                                SignatureType::Secp256k1 => SignatureTypeV4::Secp256k1,
+                                SignatureType::Bls => SignatureTypeV4::BLS,
+                                SignatureType::Delegated => {
+                                    anyhow::bail!(
+                                        "delegated signatures are not supported for deals"
+                                    )
+                                }
+                            },
+                            bytes: client_signature.bytes,
+                        },
+                    })
+                },
+            )
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self { deals })
+    }
+}
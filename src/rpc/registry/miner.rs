@@ -0,0 +1,427 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::*;
+use crate::shim::{address::Address, clock::ChainEpoch, econ::TokenAmount, sector::PoStProof};
+use fil_actor_miner_state::v16::{
+    ChangeBeneficiaryParams, ChangePeerIDParams, ChangeWorkerAddressParams,
+    CompactPartitionsParams, DeclareFaultsParams, DeclareFaultsRecoveredParams,
+    DisputeWindowedPoStParams, FaultDeclaration, Method, PoStPartition, RecoveryDeclaration,
+    SubmitWindowedPoStParams, TerminateSectorsParams, TerminationDeclaration,
+    WithdrawBalanceParams,
+};
+use fil_actors_shared::fvm_ipld_bitfield::BitField;
+use fvm_shared4::randomness::Randomness;
+use serde::Deserialize;
+
+pub(super) fn register(methods: &mut BTreeMap<(BuiltinActor, MethodNum), ParamsCodec>) {
+    methods.insert(
+        (BuiltinActor::Miner, Method::ChangeWorkerAddress as _),
+        ParamsCodec::new::<ChangeWorkerAddressParams, ChangeWorkerAddressParamsJson>(
+            "ChangeWorkerAddress",
+        ),
+    );
+    methods.insert(
+        (BuiltinActor::Miner, Method::ChangePeerID as _),
+        ParamsCodec::new::<ChangePeerIDParams, ChangePeerIDParamsJson>("ChangePeerID"),
+    );
+    methods.insert(
+        (BuiltinActor::Miner, Method::SubmitWindowedPoSt as _),
+        ParamsCodec::new::<SubmitWindowedPoStParams, SubmitWindowedPoStParamsJson>(
+            "SubmitWindowedPoSt",
+        ),
+    );
+    methods.insert(
+        (BuiltinActor::Miner, Method::TerminateSectors as _),
+        ParamsCodec::new::<TerminateSectorsParams, TerminateSectorsParamsJson>("TerminateSectors"),
+    );
+    methods.insert(
+        (BuiltinActor::Miner, Method::DeclareFaults as _),
+        ParamsCodec::new::<DeclareFaultsParams, DeclareFaultsParamsJson>("DeclareFaults"),
+    );
+    methods.insert(
+        (BuiltinActor::Miner, Method::DeclareFaultsRecovered as _),
+        ParamsCodec::new::<DeclareFaultsRecoveredParams, DeclareFaultsRecoveredParamsJson>(
+            "DeclareFaultsRecovered",
+        ),
+    );
+    methods.insert(
+        (BuiltinActor::Miner, Method::WithdrawBalance as _),
+        ParamsCodec::new::<WithdrawBalanceParams, WithdrawBalanceParamsJson>("WithdrawBalance"),
+    );
+    methods.insert(
+        (BuiltinActor::Miner, Method::CompactPartitions as _),
+        ParamsCodec::new::<CompactPartitionsParams, CompactPartitionsParamsJson>(
+            "CompactPartitions",
+        ),
+    );
+    methods.insert(
+        (BuiltinActor::Miner, Method::DisputeWindowedPoSt as _),
+        ParamsCodec::new::<DisputeWindowedPoStParams, DisputeWindowedPoStParamsJson>(
+            "DisputeWindowedPoSt",
+        ),
+    );
+    methods.insert(
+        (BuiltinActor::Miner, Method::ChangeBeneficiary as _),
+        ParamsCodec::new::<ChangeBeneficiaryParams, ChangeBeneficiaryParamsJson>(
+            "ChangeBeneficiary",
+        ),
+    );
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ChangeWorkerAddressParamsJson {
+    #[serde(with = "crate::lotus_json")]
+    new_worker: Address,
+    #[serde(with = "crate::lotus_json")]
+    new_control_addresses: Vec<Address>,
+}
+
+impl From<ChangeWorkerAddressParams> for ChangeWorkerAddressParamsJson {
+    fn from(params: ChangeWorkerAddressParams) -> Self {
+        Self {
+            new_worker: params.new_worker.into(),
+            new_control_addresses: params
+                .new_control_addresses
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<ChangeWorkerAddressParamsJson> for ChangeWorkerAddressParams {
+    type Error = anyhow::Error;
+
+    fn try_from(json: ChangeWorkerAddressParamsJson) -> anyhow::Result<Self> {
+        Ok(Self {
+            new_worker: json.new_worker.into(),
+            new_control_addresses: json
+                .new_control_addresses
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ChangePeerIDParamsJson {
+    #[serde(rename = "NewID", with = "crate::lotus_json")]
+    new_id: Vec<u8>,
+}
+
+impl From<ChangePeerIDParams> for ChangePeerIDParamsJson {
+    fn from(params: ChangePeerIDParams) -> Self {
+        Self {
+            new_id: params.new_id,
+        }
+    }
+}
+
+impl TryFrom<ChangePeerIDParamsJson> for ChangePeerIDParams {
+    type Error = anyhow::Error;
+
+    fn try_from(json: ChangePeerIDParamsJson) -> anyhow::Result<Self> {
+        Ok(Self {
+            new_id: json.new_id,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PoStPartitionJson {
+    index: u64,
+    #[serde(with = "crate::lotus_json")]
+    skipped: BitField,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SubmitWindowedPoStParamsJson {
+    deadline: u64,
+    partitions: Vec<PoStPartitionJson>,
+    #[serde(with = "crate::lotus_json")]
+    proofs: Vec<PoStProof>,
+    chain_commit_epoch: ChainEpoch,
+    #[serde(with = "crate::lotus_json")]
+    chain_commit_rand: Vec<u8>,
+}
+
+impl From<SubmitWindowedPoStParams> for SubmitWindowedPoStParamsJson {
+    fn from(params: SubmitWindowedPoStParams) -> Self {
+        let SubmitWindowedPoStParams {
+            deadline,
+            partitions,
+            proofs,
+            chain_commit_epoch,
+            chain_commit_rand,
+        } = params;
+        Self {
+            deadline,
+            partitions: partitions
+                .into_iter()
+                .map(|PoStPartition { index, skipped }| PoStPartitionJson { index, skipped })
+                .collect(),
+            proofs: proofs.into_iter().map(Into::into).collect(),
+            chain_commit_epoch,
+            chain_commit_rand: chain_commit_rand.0,
+        }
+    }
+}
+
+impl TryFrom<SubmitWindowedPoStParamsJson> for SubmitWindowedPoStParams {
+    type Error = anyhow::Error;
+
+    fn try_from(json: SubmitWindowedPoStParamsJson) -> anyhow::Result<Self> {
+        let SubmitWindowedPoStParamsJson {
+            deadline,
+            partitions,
+            proofs,
+            chain_commit_epoch,
+            chain_commit_rand,
+        } = json;
+        Ok(Self {
+            deadline,
+            partitions: partitions
+                .into_iter()
+                .map(|PoStPartitionJson { index, skipped }| PoStPartition { index, skipped })
+                .collect(),
+            proofs: proofs.into_iter().map(Into::into).collect(),
+            chain_commit_epoch,
+            chain_commit_rand: Randomness(chain_commit_rand),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct WithdrawBalanceParamsJson {
+    #[serde(with = "crate::lotus_json")]
+    amount_requested: TokenAmount,
+}
+
+impl From<WithdrawBalanceParams> for WithdrawBalanceParamsJson {
+    fn from(params: WithdrawBalanceParams) -> Self {
+        Self {
+            amount_requested: params.amount_requested.into(),
+        }
+    }
+}
+
+impl TryFrom<WithdrawBalanceParamsJson> for WithdrawBalanceParams {
+    type Error = anyhow::Error;
+
+    fn try_from(json: WithdrawBalanceParamsJson) -> anyhow::Result<Self> {
+        Ok(Self {
+            amount_requested: json.amount_requested.into(),
+        })
+    }
+}
+
+/// Partition sectors, as declared for terminations, faults and recoveries.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SectorsDeclarationJson {
+    deadline: u64,
+    partition: u64,
+    #[serde(with = "crate::lotus_json")]
+    sectors: BitField,
+}
+
+/// Implements the conversions of a declaration type with `deadline`,
+/// `partition` and `sectors` fields.
+macro_rules! sectors_declaration {
+    ($($declaration:ty),* $(,)?) => {
+        $(
+            impl From<$declaration> for SectorsDeclarationJson {
+                fn from(declaration: $declaration) -> Self {
+                    Self {
+                        deadline: declaration.deadline,
+                        partition: declaration.partition,
+                        sectors: declaration.sectors,
+                    }
+                }
+            }
+
+            impl From<SectorsDeclarationJson> for $declaration {
+                fn from(json: SectorsDeclarationJson) -> Self {
+                    Self {
+                        deadline: json.deadline,
+                        partition: json.partition,
+                        sectors: json.sectors,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+sectors_declaration!(
+    TerminationDeclaration,
+    FaultDeclaration,
+    RecoveryDeclaration
+);
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TerminateSectorsParamsJson {
+    terminations: Vec<SectorsDeclarationJson>,
+}
+
+impl From<TerminateSectorsParams> for TerminateSectorsParamsJson {
+    fn from(params: TerminateSectorsParams) -> Self {
+        Self {
+            terminations: params.terminations.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl TryFrom<TerminateSectorsParamsJson> for TerminateSectorsParams {
+    type Error = anyhow::Error;
+
+    fn try_from(json: TerminateSectorsParamsJson) -> anyhow::Result<Self> {
+        Ok(Self {
+            terminations: json.terminations.into_iter().map(Into::into).collect(),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DeclareFaultsParamsJson {
+    faults: Vec<SectorsDeclarationJson>,
+}
+
+impl From<DeclareFaultsParams> for DeclareFaultsParamsJson {
+    fn from(params: DeclareFaultsParams) -> Self {
+        Self {
+            faults: params.faults.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl TryFrom<DeclareFaultsParamsJson> for DeclareFaultsParams {
+    type Error = anyhow::Error;
+
+    fn try_from(json: DeclareFaultsParamsJson) -> anyhow::Result<Self> {
+        Ok(Self {
+            faults: json.faults.into_iter().map(Into::into).collect(),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DeclareFaultsRecoveredParamsJson {
+    recoveries: Vec<SectorsDeclarationJson>,
+}
+
+impl From<DeclareFaultsRecoveredParams> for DeclareFaultsRecoveredParamsJson {
+    fn from(params: DeclareFaultsRecoveredParams) -> Self {
+        Self {
+            recoveries: params.recoveries.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl TryFrom<DeclareFaultsRecoveredParamsJson> for DeclareFaultsRecoveredParams {
+    type Error = anyhow::Error;
+
+    fn try_from(json: DeclareFaultsRecoveredParamsJson) -> anyhow::Result<Self> {
+        Ok(Self {
+            recoveries: json.recoveries.into_iter().map(Into::into).collect(),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CompactPartitionsParamsJson {
+    deadline: u64,
+    #[serde(with = "crate::lotus_json")]
+    partitions: BitField,
+}
+
+impl From<CompactPartitionsParams> for CompactPartitionsParamsJson {
+    fn from(params: CompactPartitionsParams) -> Self {
+        Self {
+            deadline: params.deadline,
+            partitions: params.partitions,
+        }
+    }
+}
+
+impl TryFrom<CompactPartitionsParamsJson> for CompactPartitionsParams {
+    type Error = anyhow::Error;
+
+    fn try_from(json: CompactPartitionsParamsJson) -> anyhow::Result<Self> {
+        Ok(Self {
+            deadline: json.deadline,
+            partitions: json.partitions,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DisputeWindowedPoStParamsJson {
+    deadline: u64,
+    #[serde(rename = "PoStIndex")]
+    post_index: u64,
+}
+
+impl From<DisputeWindowedPoStParams> for DisputeWindowedPoStParamsJson {
+    fn from(params: DisputeWindowedPoStParams) -> Self {
+        Self {
+            deadline: params.deadline,
+            post_index: params.post_index,
+        }
+    }
+}
+
+impl TryFrom<DisputeWindowedPoStParamsJson> for DisputeWindowedPoStParams {
+    type Error = anyhow::Error;
+
+    fn try_from(json: DisputeWindowedPoStParamsJson) -> anyhow::Result<Self> {
+        Ok(Self {
+            deadline: json.deadline,
+            post_index: json.post_index,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ChangeBeneficiaryParamsJson {
+    #[serde(with = "crate::lotus_json")]
+    new_beneficiary: Address,
+    #[serde(with = "crate::lotus_json")]
+    new_quota: TokenAmount,
+    new_expiration: ChainEpoch,
+}
+
+impl From<ChangeBeneficiaryParams> for ChangeBeneficiaryParamsJson {
+    fn from(params: ChangeBeneficiaryParams) -> Self {
+        Self {
+            new_beneficiary: params.new_beneficiary.into(),
+            new_quota: params.new_quota.into(),
+            new_expiration: params.new_expiration,
+        }
+    }
+}
+
+impl TryFrom<ChangeBeneficiaryParamsJson> for ChangeBeneficiaryParams {
+    type Error = anyhow::Error;
+
+    fn try_from(json: ChangeBeneficiaryParamsJson) -> anyhow::Result<Self> {
+        Ok(Self {
+            new_beneficiary: json.new_beneficiary.into(),
+            new_quota: json.new_quota.into(),
+            new_expiration: json.new_expiration,
+        })
+    }
+}
@@ -0,0 +1,518 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Registry of builtin actor method parameter types.
+//!
+//! Message parameters are opaque CBOR on-chain. The registry maps an actor
+//! type and a method number to the Rust type of its parameters, which allows
+//! converting between the CBOR bytes and a human-readable (Lotus compatible)
+//! JSON representation.
+
+mod market;
+mod miner;
+mod multisig;
+mod power;
+
+use crate::networks::ACTOR_BUNDLES_METADATA;
+use crate::shim::machine::BuiltinActor;
+use crate::shim::message::{MethodNum, METHOD_SEND};
+use crate::utils::multihash::prelude::*;
+use ahash::HashMap;
+use cid::Cid;
+use fvm_ipld_encoding::IPLD_RAW;
+use itertools::Itertools as _;
+use once_cell::sync::Lazy;
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum RegistryError {
+    #[error("unknown actor code {0}")]
+    UnknownActor(Cid),
+    #[error("unknown method {method} for {actor} actor")]
+    UnknownMethod { actor: String, method: MethodNum },
+    /// The parameters could not be converted, e.g. malformed CBOR or JSON.
+    #[error("invalid params: {0}")]
+    InvalidParams(String),
+}
+
+type DecodeFn = fn(&[u8]) -> anyhow::Result<serde_json::Value>;
+type EncodeFn = fn(serde_json::Value) -> anyhow::Result<Vec<u8>>;
+
+/// Converts the parameters of a single method between CBOR and JSON.
+struct ParamsCodec {
+    name: &'static str,
+    decode: DecodeFn,
+    encode: EncodeFn,
+}
+
+impl ParamsCodec {
+    /// `C` is the CBOR encoded type used by the actors, `J` its JSON
+    /// representation.
+    fn new<C, J>(name: &'static str) -> Self
+    where
+        C: Serialize + DeserializeOwned + TryFrom<J, Error = anyhow::Error>,
+        J: Serialize + DeserializeOwned + From<C>,
+    {
+        Self {
+            name,
+            decode: |bytes| {
+                let params: C = fvm_ipld_encoding::from_slice(bytes)?;
+                Ok(serde_json::to_value(J::from(params))?)
+            },
+            encode: |json| {
+                let params = C::try_from(serde_json::from_value::<J>(json)?)?;
+                Ok(fvm_ipld_encoding::to_vec(&params)?)
+            },
+        }
+    }
+}
+
+/// Actor code CIDs of all bundled actor versions, plus the identity CIDs
+/// (`fil/<version>/<name>`) of the actors that predate bundles.
+static ACTOR_TYPES: Lazy<HashMap<Cid, BuiltinActor>> = Lazy::new(|| {
+    let legacy = METHODS
+        .keys()
+        .map(|(actor, _)| *actor)
+        .dedup()
+        .flat_map(|actor| {
+            (1..=7).map(move |version| {
+                let name = format!("fil/{version}/{}", actor.name());
+                let code = Cid::new_v1(IPLD_RAW, MultihashCode::Identity.digest(name.as_bytes()));
+                (code, actor)
+            })
+        });
+    ACTOR_BUNDLES_METADATA
+        .values()
+        .flat_map(|bundle| bundle.manifest.builtin_actors())
+        .map(|(actor, code)| (code, actor))
+        .chain(legacy)
+        .collect()
+});
+
+/// The parameter encoding of the registered methods did not change across
+/// actor versions, so a single type is registered for each method.
+static METHODS: Lazy<BTreeMap<(BuiltinActor, MethodNum), ParamsCodec>> = Lazy::new(|| {
+    let mut methods = BTreeMap::new();
+    market::register(&mut methods);
+    miner::register(&mut methods);
+    multisig::register(&mut methods);
+    power::register(&mut methods);
+    methods
+});
+
+fn lookup(code: &Cid, method: MethodNum) -> Result<&'static ParamsCodec, RegistryError> {
+    let actor = *ACTOR_TYPES
+        .get(code)
+        .ok_or(RegistryError::UnknownActor(*code))?;
+    METHODS
+        .get(&(actor, method))
+        .ok_or_else(|| RegistryError::UnknownMethod {
+            actor: actor.name().to_string(),
+            method,
+        })
+}
+
+/// Returns the name of `method` on the actor with the given code, e.g.
+/// `SubmitWindowedPoSt`. Method `0` is a plain value transfer on every actor.
+pub fn method_name(code: &Cid, method: MethodNum) -> Result<&'static str, RegistryError> {
+    if method == METHOD_SEND {
+        return Ok("Send");
+    }
+    Ok(lookup(code, method)?.name)
+}
+
+/// Decodes the CBOR `params` of `method` on the actor with the given code into
+/// their JSON representation.
+pub fn decode_params(
+    code: &Cid,
+    method: MethodNum,
+    params: &[u8],
+) -> Result<serde_json::Value, RegistryError> {
+    (lookup(code, method)?.decode)(params)
+        .map_err(|e| RegistryError::InvalidParams(format!("{e:#}")))
+}
+
+/// Encodes the JSON `params` of `method` on the actor with the given code into
+/// CBOR, the inverse of [`decode_params`].
+pub fn encode_params(
+    code: &Cid,
+    method: MethodNum,
+    params: serde_json::Value,
+) -> Result<Vec<u8>, RegistryError> {
+    (lookup(code, method)?.encode)(params)
+        .map_err(|e| RegistryError::InvalidParams(format!("{e:#}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::networks::NetworkChain;
+    use serde_json::json;
+
+    fn actor_code(actor: BuiltinActor, version: &str) -> Cid {
+        ACTOR_BUNDLES_METADATA
+            .get(&(NetworkChain::Mainnet, version.into()))
+            .unwrap()
+            .manifest
+            .get(actor)
+            .unwrap()
+    }
+
+    /// Code of an actor that predates bundles, e.g. `fil/2/storagemarket`.
+    fn legacy_actor_code(actor: BuiltinActor, version: u64) -> Cid {
+        let name = format!("fil/{version}/{}", actor.name());
+        Cid::new_v1(IPLD_RAW, MultihashCode::Identity.digest(name.as_bytes()))
+    }
+
+    /// Codes of `actor` in a legacy version and in the oldest and newest
+    /// bundled versions.
+    fn actor_codes(actor: BuiltinActor) -> [Cid; 4] {
+        [
+            legacy_actor_code(actor, 2),
+            legacy_actor_code(actor, 7),
+            actor_code(actor, "v9.0.3"),
+            actor_code(actor, "v15.0.0"),
+        ]
+    }
+
+    /// Asserts that `params` decode to `json` and that `json` encodes back to
+    /// the same bytes, for every actor version.
+    fn assert_codec(
+        actor: BuiltinActor,
+        method: MethodNum,
+        params: &[u8],
+        json: serde_json::Value,
+    ) {
+        for code in actor_codes(actor) {
+            assert_eq!(decode_params(&code, method, params).unwrap(), json);
+            assert_eq!(encode_params(&code, method, json.clone()).unwrap(), params);
+        }
+    }
+
+    /// Asserts that `json` survives an encoding round-trip.
+    fn assert_round_trip(actor: BuiltinActor, method: MethodNum, json: serde_json::Value) {
+        let code = actor_code(actor, "v15.0.0");
+        let params = encode_params(&code, method, json.clone()).unwrap();
+        assert_eq!(decode_params(&code, method, &params).unwrap(), json);
+    }
+
+    /// `SubmitWindowedPoSt` params in the wire format of a mainnet message,
+    /// with two partitions and a 32GiB window PoSt proof.
+    const SUBMIT_WINDOWED_POST_PARAMS: &str = "85118282004082014081820858c0748e8025d8e40bf3098f32f67528f22c6b2fc10c94b19f1e2837225f9f35c6fee4f64aa69acd53ef904881cef9a0353f478f85d9cd8bb027cf1c7bafb55d6645b63b1297b9826e3af434dcaf2367d4e52e25cd7a258217a4cefbccc949c16f1ae305f5c0b472e0411545a9800467bb6728c6d96377640ad26150a53e9ca9503ca57115da08026e1f1b833ca2a36a3657dfc8d55f044b5ae18f8e551eed4ac7f8c7e3f5442f060655537dbcae4dcd0b3d1c36689dd4b9f9943196d8932868dff91908f35820b51f08724a9e089fdb013e454bfc0a1a9675dd12877e1f31053fd0d24a44933f";
+
+    /// `PublishStorageDeals` params in the wire format of a mainnet message
+    /// from before actors v8, which only had string labels.
+    const PUBLISH_STORAGE_DEALS_PARAMS: &str = "8181828bd82a5828000181e203922020ab7c1b7a70f5de417b2dc3cb4b361c0c190dbf70d1a42d7fdb080156648b40751b0000000800000000f44200654300e807782e516d6579774e673956585764314a466148657a795173336d56464761635971666d623350713770514a4e3752714d1920401a0017490044000ee6b24040584201f364c7c4cd0f966f1a251aa6de1ca24f47b4a4525130b338c2954447fde6a5e1ba1febb54cc29cb948d95e3da1b0de22332878e8eae3fac39ac8355ffb63b85c12";
+
+    #[test]
+    fn submit_windowed_post_fixture() {
+        assert_codec(
+            BuiltinActor::Miner,
+            5,
+            &hex::decode(SUBMIT_WINDOWED_POST_PARAMS).unwrap(),
+            json!({
+                "Deadline": 17,
+                "Partitions": [
+                    { "Index": 0, "Skipped": [0] },
+                    { "Index": 1, "Skipped": [0] }
+                ],
+                "Proofs": [{
+                    "PoStProof": 8,
+                    "ProofBytes": "dI6AJdjkC/MJjzL2dSjyLGsvwQyUsZ8eKDciX581xv7k9kqmms1T75BIgc75oDU/R4+F2c2LsCfPHHuvtV1mRbY7Epe5gm469DTcryNn1OUuJc16JYIXpM77zMlJwW8a4wX1wLRy4EEVRamABGe7ZyjG2WN3ZArSYVClPpypUDylcRXaCAJuHxuDPKKjajZX38jVXwRLWuGPjlUe7UrH+Mfj9UQvBgZVU328rk3NCz0cNmid1Ln5lDGW2JMoaN/5"
+                }],
+                "ChainCommitEpoch": 2291,
+                "ChainCommitRand": "tR8IckqeCJ/bAT5FS/wKGpZ13RKHfh8xBT/Q0kpEkz8="
+            }),
+        );
+    }
+
+    #[test]
+    fn publish_storage_deals_fixture() {
+        assert_codec(
+            BuiltinActor::Market,
+            4,
+            &hex::decode(PUBLISH_STORAGE_DEALS_PARAMS).unwrap(),
+            json!({
+                "Deals": [{
+                    "Proposal": {
+                        "PieceCID": { "/": "baga6ea4seaqkw7a3pjyplxsbpmw4hs2lgyoayginx5yndjbnp7nqqakwmsfua5i" },
+                        "PieceSize": 34359738368_u64,
+                        "VerifiedDeal": false,
+                        "Client": "f0101",
+                        "Provider": "f01000",
+                        "Label": "QmeywNg9VXWd1JFaHezyQs3mVFGacYqfmb3Pq7pQJN7RqM",
+                        "StartEpoch": 8256,
+                        "EndEpoch": 1526016,
+                        "StoragePricePerEpoch": "976562",
+                        "ProviderCollateral": "0",
+                        "ClientCollateral": "0"
+                    },
+                    "ClientSignature": {
+                        "Type": 1,
+                        "Data": "82THxM0Plm8aJRqm3hyiT0e0pFJRMLM4wpVER/3mpeG6H+u1TMKcuUjZXj2hsN4iMyh46Orj+sOayDVf+2O4XBI="
+                    }
+                }]
+            }),
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let samples = [
+            (BuiltinActor::Market, 2, json!("f01000")),
+            (
+                BuiltinActor::Market,
+                3,
+                json!({ "ProviderOrClient": "f01000", "Amount": "1000" }),
+            ),
+            (
+                BuiltinActor::Market,
+                4,
+                json!({
+                    "Deals": [{
+                        "Proposal": {
+                            "PieceCID": { "/": "baga6ea4seaqkw7a3pjyplxsbpmw4hs2lgyoayginx5yndjbnp7nqqakwmsfua5i" },
+                            "PieceSize": 2048,
+                            "VerifiedDeal": true,
+                            "Client": "f0101",
+                            "Provider": "f01000",
+                            "Label": "hello world!",
+                            "StartEpoch": 100,
+                            "EndEpoch": 200,
+                            "StoragePricePerEpoch": "1",
+                            "ProviderCollateral": "2",
+                            "ClientCollateral": "3"
+                        },
+                        "ClientSignature": { "Type": 2, "Data": "aGVsbG8gd29ybGQh" }
+                    }]
+                }),
+            ),
+            (
+                BuiltinActor::Miner,
+                3,
+                json!({ "NewWorker": "f0101", "NewControlAddresses": ["f0102", "f0103"] }),
+            ),
+            (
+                BuiltinActor::Miner,
+                5,
+                json!({
+                    "Deadline": 0,
+                    "Partitions": [{ "Index": 3, "Skipped": [1, 1] }],
+                    "Proofs": [{ "PoStProof": 13, "ProofBytes": "aGVsbG8gd29ybGQh" }],
+                    "ChainCommitEpoch": 10,
+                    "ChainCommitRand": "aGVsbG8gd29ybGQh"
+                }),
+            ),
+            (
+                BuiltinActor::Miner,
+                4,
+                json!({ "NewID": "aGVsbG8gd29ybGQh" }),
+            ),
+            (
+                BuiltinActor::Miner,
+                9,
+                json!({
+                    "Terminations": [{ "Deadline": 1, "Partition": 2, "Sectors": [3, 2] }]
+                }),
+            ),
+            (
+                BuiltinActor::Miner,
+                10,
+                json!({
+                    "Faults": [{ "Deadline": 4, "Partition": 0, "Sectors": [0, 1] }]
+                }),
+            ),
+            (
+                BuiltinActor::Miner,
+                11,
+                json!({
+                    "Recoveries": [
+                        { "Deadline": 4, "Partition": 0, "Sectors": [0, 1] },
+                        { "Deadline": 5, "Partition": 1, "Sectors": [2, 3] }
+                    ]
+                }),
+            ),
+            (
+                BuiltinActor::Miner,
+                16,
+                json!({ "AmountRequested": "1000" }),
+            ),
+            (
+                BuiltinActor::Miner,
+                19,
+                json!({ "Deadline": 7, "Partitions": [0, 2] }),
+            ),
+            (
+                BuiltinActor::Miner,
+                24,
+                json!({ "Deadline": 7, "PoStIndex": 1 }),
+            ),
+            (
+                BuiltinActor::Miner,
+                30,
+                json!({ "NewBeneficiary": "f0101", "NewQuota": "1000", "NewExpiration": 5000 }),
+            ),
+            (
+                BuiltinActor::Multisig,
+                2,
+                json!({
+                    "To": "f0101",
+                    "Value": "1000",
+                    "Method": 0,
+                    "Params": null
+                }),
+            ),
+            (
+                BuiltinActor::Multisig,
+                3,
+                json!({ "ID": 3, "ProposalHash": "aGVsbG8gd29ybGQh" }),
+            ),
+            (
+                BuiltinActor::Multisig,
+                4,
+                json!({ "ID": 4, "ProposalHash": null }),
+            ),
+            (
+                BuiltinActor::Multisig,
+                5,
+                json!({ "Signer": "f0101", "Increase": true }),
+            ),
+            (
+                BuiltinActor::Multisig,
+                6,
+                json!({ "Signer": "f0101", "Decrease": false }),
+            ),
+            (
+                BuiltinActor::Multisig,
+                7,
+                json!({ "From": "f0101", "To": "f0102" }),
+            ),
+            (BuiltinActor::Multisig, 8, json!({ "NewThreshold": 2 })),
+            (
+                BuiltinActor::Power,
+                2,
+                json!({
+                    "Owner": "f0101",
+                    "Worker": "f0102",
+                    "WindowPoStProofType": 8,
+                    "Peer": "aGVsbG8gd29ybGQh",
+                    "Multiaddrs": ["aGVsbG8gd29ybGQh"]
+                }),
+            ),
+        ];
+        assert_eq!(
+            samples
+                .iter()
+                .map(|(actor, method, _)| (*actor, *method))
+                .sorted()
+                .collect::<Vec<_>>(),
+            METHODS.keys().copied().collect::<Vec<_>>(),
+            "every registered method has a sample"
+        );
+        for (actor, method, json) in samples {
+            assert_round_trip(actor, method, json);
+        }
+    }
+
+    #[test]
+    fn delegated_deal_signature() {
+        let code = actor_code(BuiltinActor::Market, "v15.0.0");
+        let mut json = decode_params(
+            &code,
+            4,
+            &hex::decode(PUBLISH_STORAGE_DEALS_PARAMS).unwrap(),
+        )
+        .unwrap();
+        json["Deals"][0]["ClientSignature"]["Type"] = json!(3);
+        assert_eq!(
+            encode_params(&code, 4, json),
+            Err(RegistryError::InvalidParams(
+                "delegated signatures are not supported for deals".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn byte_deal_labels() {
+        use fil_actor_market_state::v16::{Label, PublishStorageDealsParams};
+
+        // Byte labels are rendered as strings, and encoded back as such
+        let code = actor_code(BuiltinActor::Market, "v15.0.0");
+        let mut params: PublishStorageDealsParams =
+            fvm_ipld_encoding::from_slice(&hex::decode(PUBLISH_STORAGE_DEALS_PARAMS).unwrap())
+                .unwrap();
+        params.deals[0].proposal.label = Label::Bytes(b"hello".to_vec());
+        let json = decode_params(&code, 4, &fvm_ipld_encoding::to_vec(&params).unwrap()).unwrap();
+        assert_eq!(json["Deals"][0]["Proposal"]["Label"], json!("hello"));
+        let encoded: PublishStorageDealsParams =
+            fvm_ipld_encoding::from_slice(&encode_params(&code, 4, json).unwrap()).unwrap();
+        assert_eq!(
+            encoded.deals[0].proposal.label,
+            Label::String("hello".into())
+        );
+    }
+
+    #[test]
+    fn method_names() {
+        let code = actor_code(BuiltinActor::Miner, "v15.0.0");
+        assert_eq!(method_name(&code, 0), Ok("Send"));
+        assert_eq!(method_name(&code, 5), Ok("SubmitWindowedPoSt"));
+        assert_eq!(
+            method_name(&legacy_actor_code(BuiltinActor::Market, 2), 4),
+            Ok("PublishStorageDeals")
+        );
+        assert_eq!(
+            method_name(&code, 1234),
+            Err(RegistryError::UnknownMethod {
+                actor: "storageminer".into(),
+                method: 1234
+            })
+        );
+    }
+
+    #[test]
+    fn unknown_actor() {
+        assert_eq!(
+            decode_params(&Cid::default(), 0, &[]),
+            Err(RegistryError::UnknownActor(Cid::default()))
+        );
+    }
+
+    #[test]
+    fn unknown_method() {
+        let code = actor_code(BuiltinActor::Miner, "v15.0.0");
+        assert_eq!(
+            decode_params(&code, 1234, &[]),
+            Err(RegistryError::UnknownMethod {
+                actor: "storageminer".into(),
+                method: 1234
+            })
+        );
+    }
+
+    #[test]
+    fn garbage_params() {
+        let code = actor_code(BuiltinActor::Market, "v15.0.0");
+        assert!(matches!(
+            decode_params(&code, 4, &[0xff, 0x00]),
+            Err(RegistryError::InvalidParams(_))
+        ));
+        assert!(matches!(
+            encode_params(&code, 4, serde_json::json!({ "Deals": 1 })),
+            Err(RegistryError::InvalidParams(_))
+        ));
+    }
+
+    #[test]
+    fn errors_are_invalid_params() {
+        use crate::rpc::ServerError;
+        use jsonrpsee::types::error::ErrorCode;
+
+        let err = decode_params(&Cid::default(), 0, &[]).unwrap_err();
+        assert_eq!(
+            ServerError::from(err).known_code(),
+            ErrorCode::InvalidParams
+        );
+    }
+}
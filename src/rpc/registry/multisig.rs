@@ -0,0 +1,220 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::*;
+use crate::shim::{address::Address, econ::TokenAmount};
+use fil_actor_multisig_state::v16::{
+    AddSignerParams, ChangeNumApprovalsThresholdParams, Method, ProposeParams, RemoveSignerParams,
+    SwapSignerParams, TxnID, TxnIDParams,
+};
+use fvm_ipld_encoding::RawBytes;
+use serde::Deserialize;
+
+pub(super) fn register(methods: &mut BTreeMap<(BuiltinActor, MethodNum), ParamsCodec>) {
+    methods.insert(
+        (BuiltinActor::Multisig, Method::Propose as _),
+        ParamsCodec::new::<ProposeParams, ProposeParamsJson>("Propose"),
+    );
+    methods.insert(
+        (BuiltinActor::Multisig, Method::Approve as _),
+        ParamsCodec::new::<TxnIDParams, TxnIDParamsJson>("Approve"),
+    );
+    methods.insert(
+        (BuiltinActor::Multisig, Method::Cancel as _),
+        ParamsCodec::new::<TxnIDParams, TxnIDParamsJson>("Cancel"),
+    );
+    methods.insert(
+        (BuiltinActor::Multisig, Method::AddSigner as _),
+        ParamsCodec::new::<AddSignerParams, AddSignerParamsJson>("AddSigner"),
+    );
+    methods.insert(
+        (BuiltinActor::Multisig, Method::RemoveSigner as _),
+        ParamsCodec::new::<RemoveSignerParams, RemoveSignerParamsJson>("RemoveSigner"),
+    );
+    methods.insert(
+        (BuiltinActor::Multisig, Method::SwapSigner as _),
+        ParamsCodec::new::<SwapSignerParams, SwapSignerParamsJson>("SwapSigner"),
+    );
+    methods.insert(
+        (BuiltinActor::Multisig, Method::ChangeNumApprovalsThreshold as _),
+        ParamsCodec::new::<ChangeNumApprovalsThresholdParams, ChangeNumApprovalsThresholdParamsJson>(
+            "ChangeNumApprovalsThreshold",
+        ),
+    );
+}
+
+/// The params of the proposed message are left encoded, as they depend on
+/// the actor it is sent to.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ProposeParamsJson {
+    #[serde(with = "crate::lotus_json")]
+    to: Address,
+    #[serde(with = "crate::lotus_json")]
+    value: TokenAmount,
+    method: MethodNum,
+    #[serde(with = "crate::lotus_json")]
+    params: RawBytes,
+}
+
+impl From<ProposeParams> for ProposeParamsJson {
+    fn from(params: ProposeParams) -> Self {
+        Self {
+            to: params.to.into(),
+            value: params.value.into(),
+            method: params.method,
+            params: params.params,
+        }
+    }
+}
+
+impl TryFrom<ProposeParamsJson> for ProposeParams {
+    type Error = anyhow::Error;
+
+    fn try_from(json: ProposeParamsJson) -> anyhow::Result<Self> {
+        Ok(Self {
+            to: json.to.into(),
+            value: json.value.into(),
+            method: json.method,
+            params: json.params,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TxnIDParamsJson {
+    #[serde(rename = "ID")]
+    id: i64,
+    #[serde(with = "crate::lotus_json")]
+    proposal_hash: Vec<u8>,
+}
+
+impl From<TxnIDParams> for TxnIDParamsJson {
+    fn from(params: TxnIDParams) -> Self {
+        Self {
+            id: params.id.0,
+            proposal_hash: params.proposal_hash,
+        }
+    }
+}
+
+impl TryFrom<TxnIDParamsJson> for TxnIDParams {
+    type Error = anyhow::Error;
+
+    fn try_from(json: TxnIDParamsJson) -> anyhow::Result<Self> {
+        Ok(Self {
+            id: TxnID(json.id),
+            proposal_hash: json.proposal_hash,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AddSignerParamsJson {
+    #[serde(with = "crate::lotus_json")]
+    signer: Address,
+    increase: bool,
+}
+
+impl From<AddSignerParams> for AddSignerParamsJson {
+    fn from(params: AddSignerParams) -> Self {
+        Self {
+            signer: params.signer.into(),
+            increase: params.increase,
+        }
+    }
+}
+
+impl TryFrom<AddSignerParamsJson> for AddSignerParams {
+    type Error = anyhow::Error;
+
+    fn try_from(json: AddSignerParamsJson) -> anyhow::Result<Self> {
+        Ok(Self {
+            signer: json.signer.into(),
+            increase: json.increase,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RemoveSignerParamsJson {
+    #[serde(with = "crate::lotus_json")]
+    signer: Address,
+    decrease: bool,
+}
+
+impl From<RemoveSignerParams> for RemoveSignerParamsJson {
+    fn from(params: RemoveSignerParams) -> Self {
+        Self {
+            signer: params.signer.into(),
+            decrease: params.decrease,
+        }
+    }
+}
+
+impl TryFrom<RemoveSignerParamsJson> for RemoveSignerParams {
+    type Error = anyhow::Error;
+
+    fn try_from(json: RemoveSignerParamsJson) -> anyhow::Result<Self> {
+        Ok(Self {
+            signer: json.signer.into(),
+            decrease: json.decrease,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SwapSignerParamsJson {
+    #[serde(with = "crate::lotus_json")]
+    from: Address,
+    #[serde(with = "crate::lotus_json")]
+    to: Address,
+}
+
+impl From<SwapSignerParams> for SwapSignerParamsJson {
+    fn from(params: SwapSignerParams) -> Self {
+        Self {
+            from: params.from.into(),
+            to: params.to.into(),
+        }
+    }
+}
+
+impl TryFrom<SwapSignerParamsJson> for SwapSignerParams {
+    type Error = anyhow::Error;
+
+    fn try_from(json: SwapSignerParamsJson) -> anyhow::Result<Self> {
+        Ok(Self {
+            from: json.from.into(),
+            to: json.to.into(),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ChangeNumApprovalsThresholdParamsJson {
+    new_threshold: u64,
+}
+
+impl From<ChangeNumApprovalsThresholdParams> for ChangeNumApprovalsThresholdParamsJson {
+    fn from(params: ChangeNumApprovalsThresholdParams) -> Self {
+        Self {
+            new_threshold: params.new_threshold,
+        }
+    }
+}
+
+impl TryFrom<ChangeNumApprovalsThresholdParamsJson> for ChangeNumApprovalsThresholdParams {
+    type Error = anyhow::Error;
+
+    fn try_from(json: ChangeNumApprovalsThresholdParamsJson) -> anyhow::Result<Self> {
+        Ok(Self {
+            new_threshold: json.new_threshold,
+        })
+    }
+}
@@ -0,0 +1,60 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::*;
+use crate::shim::{address::Address, sector::RegisteredPoStProof};
+use fil_actor_power_state::v16::{CreateMinerParams, Method};
+use fvm_ipld_encoding::BytesDe;
+use serde::Deserialize;
+
+pub(super) fn register(methods: &mut BTreeMap<(BuiltinActor, MethodNum), ParamsCodec>) {
+    methods.insert(
+        (BuiltinActor::Power, Method::CreateMiner as _),
+        ParamsCodec::new::<CreateMinerParams, CreateMinerParamsJson>("CreateMiner"),
+    );
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CreateMinerParamsJson {
+    #[serde(with = "crate::lotus_json")]
+    owner: Address,
+    #[serde(with = "crate::lotus_json")]
+    worker: Address,
+    #[serde(rename = "WindowPoStProofType", with = "crate::lotus_json")]
+    window_post_proof_type: RegisteredPoStProof,
+    #[serde(with = "crate::lotus_json")]
+    peer: Vec<u8>,
+    #[serde(with = "crate::lotus_json")]
+    multiaddrs: Vec<Vec<u8>>,
+}
+
+impl From<CreateMinerParams> for CreateMinerParamsJson {
+    fn from(params: CreateMinerParams) -> Self {
+        Self {
+            owner: params.owner.into(),
+            worker: params.worker.into(),
+            window_post_proof_type: params.window_post_proof_type.into(),
+            peer: params.peer,
+            multiaddrs: params
+                .multiaddrs
+                .into_iter()
+                .map(|BytesDe(bytes)| bytes)
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<CreateMinerParamsJson> for CreateMinerParams {
+    type Error = anyhow::Error;
+
+    fn try_from(json: CreateMinerParamsJson) -> anyhow::Result<Self> {
+        Ok(Self {
+            owner: json.owner.into(),
+            worker: json.worker.into(),
+            window_post_proof_type: *json.window_post_proof_type,
+            peer: json.peer,
+            multiaddrs: json.multiaddrs.into_iter().map(BytesDe).collect(),
+        })
+    }
+}
@@ -43,7 +43,7 @@ impl From<DealProposal> for ApiDealProposal {
             verified_deal,
             client: client.into(),
             provider: provider.into(),
-            label,
+            label: label.into_lotus_label(),
             start_epoch,
             end_epoch,
             storage_price_per_epoch: storage_price_per_epoch.into(),
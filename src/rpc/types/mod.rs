@@ -18,7 +18,7 @@ use crate::blocks::TipsetKey;
 use crate::libp2p::Multihash;
 use crate::lotus_json::{lotus_json_with_self, LotusJson};
 use crate::shim::actors::market::AllocationID;
-use crate::shim::actors::market::{DealProposal, DealState};
+use crate::shim::actors::market::{DealLabel, DealProposal, DealState};
 use crate::shim::actors::miner::DeadlineInfo;
 use crate::shim::{
     address::Address,
@@ -40,6 +40,7 @@ use num_bigint::BigInt;
 use nunny::Vec as NonEmpty;
 use schemars::JsonSchema;
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
 use std::str::FromStr;
 
 // Chain API
@@ -93,7 +94,9 @@ pub struct ApiDealProposal {
     #[schemars(with = "LotusJson<Address>")]
     #[serde(with = "crate::lotus_json")]
     pub provider: Address,
-    pub label: String,
+    #[schemars(with = "LotusJson<DealLabel>")]
+    #[serde(with = "crate::lotus_json")]
+    pub label: DealLabel,
     pub start_epoch: ChainEpoch,
     pub end_epoch: ChainEpoch,
     #[schemars(with = "LotusJson<TokenAmount>")]
@@ -118,6 +121,29 @@ pub struct ApiMarketDeal {
 
 lotus_json_with_self!(ApiMarketDeal);
 
+/// A page of market deals, ordered by deal ID.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct ApiMarketDealsPage {
+    pub deals: BTreeMap<DealID, ApiMarketDeal>,
+    /// Offset of the next page, or `None` if this is the last one.
+    pub next_offset: Option<DealID>,
+}
+
+lotus_json_with_self!(ApiMarketDealsPage);
+
+/// Network baseline power, in bytes, at an epoch.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct ApiBaselinePower {
+    pub epoch: ChainEpoch,
+    #[schemars(with = "LotusJson<BigInt>")]
+    #[serde(with = "crate::lotus_json")]
+    pub baseline_power: BigInt,
+}
+
+lotus_json_with_self!(ApiBaselinePower);
+
 #[derive(Serialize, Clone)]
 #[serde(rename_all = "PascalCase")]
 pub struct MarketDeal {
@@ -365,7 +391,7 @@ pub struct CirculatingSupply {
 
 lotus_json_with_self!(CirculatingSupply);
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "PascalCase")]
 pub struct MinerSectors {
     live: u64,
@@ -522,7 +548,7 @@ pub struct MiningBaseInfo {
 
 lotus_json_with_self!(MiningBaseInfo);
 
-#[derive(Debug, Clone, JsonSchema, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, JsonSchema, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct EventEntry {
     pub flags: u64,
@@ -387,6 +387,60 @@ where
         }
     }
 
+    /// Calls `f` for at most `limit` proposals in deal ID order, starting at
+    /// `start_at`, until it returns `false`. Returns the ID of the next
+    /// proposal, if any.
+    pub fn for_each_while_ranged(
+        &self,
+        start_at: Option<u64>,
+        limit: Option<u64>,
+        mut f: impl FnMut(u64, DealProposal) -> anyhow::Result<bool>,
+    ) -> anyhow::Result<Option<u64>> {
+        let (_, next_key) = match self {
+            DealProposals::V9(deal_array) => {
+                deal_array.for_each_while_ranged(start_at, limit, |key, deal_proposal| {
+                    f(key, DealProposal::try_from(deal_proposal)?)
+                })?
+            }
+            DealProposals::V10(deal_array) => {
+                deal_array.for_each_while_ranged(start_at, limit, |key, deal_proposal| {
+                    f(key, DealProposal::try_from(deal_proposal)?)
+                })?
+            }
+            DealProposals::V11(deal_array) => {
+                deal_array.for_each_while_ranged(start_at, limit, |key, deal_proposal| {
+                    f(key, DealProposal::try_from(deal_proposal)?)
+                })?
+            }
+            DealProposals::V12(deal_array) => {
+                deal_array.for_each_while_ranged(start_at, limit, |key, deal_proposal| {
+                    f(key, DealProposal::try_from(deal_proposal)?)
+                })?
+            }
+            DealProposals::V13(deal_array) => {
+                deal_array.for_each_while_ranged(start_at, limit, |key, deal_proposal| {
+                    f(key, DealProposal::try_from(deal_proposal)?)
+                })?
+            }
+            DealProposals::V14(deal_array) => {
+                deal_array.for_each_while_ranged(start_at, limit, |key, deal_proposal| {
+                    f(key, DealProposal::try_from(deal_proposal)?)
+                })?
+            }
+            DealProposals::V15(deal_array) => {
+                deal_array.for_each_while_ranged(start_at, limit, |key, deal_proposal| {
+                    f(key, DealProposal::try_from(deal_proposal)?)
+                })?
+            }
+            DealProposals::V16(deal_array) => {
+                deal_array.for_each_while_ranged(start_at, limit, |key, deal_proposal| {
+                    f(key, DealProposal::try_from(deal_proposal)?)
+                })?
+            }
+        };
+        Ok(next_key)
+    }
+
     pub fn get(&self, key: u64) -> anyhow::Result<Option<DealProposal>> {
         match self {
             DealProposals::V9(deal_array) => deal_array.get(key)?.map(TryFrom::try_from),
@@ -402,6 +456,35 @@ where
     }
 }
 
+/// Label of a deal proposal, which is either a UTF-8 string or arbitrary bytes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
+pub enum DealLabel {
+    String(String),
+    Bytes(Vec<u8>),
+}
+
+impl DealLabel {
+    /// Reads the label as Lotus does in its JSON API, where byte labels are
+    /// read as UTF-8. The bytes of labels that are not valid UTF-8 are handed
+    /// back.
+    pub fn into_lotus_string(self) -> Result<String, Vec<u8>> {
+        match self {
+            DealLabel::String(s) => Ok(s),
+            DealLabel::Bytes(b) => String::from_utf8(b).map_err(|e| e.into_bytes()),
+        }
+    }
+
+    /// Returns the label as rendered by Lotus, a string unless its bytes are
+    /// not valid UTF-8.
+    pub fn into_lotus_label(self) -> Self {
+        match self.into_lotus_string() {
+            Ok(s) => DealLabel::String(s),
+            Err(b) => DealLabel::Bytes(b),
+        }
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "PascalCase")]
 pub struct DealProposal {
@@ -411,8 +494,7 @@ pub struct DealProposal {
     pub verified_deal: bool,
     pub client: Address,
     pub provider: Address,
-    // ! This is the field that requires unsafe unchecked utf8 deserialization
-    pub label: String,
+    pub label: DealLabel,
     pub start_epoch: ChainEpoch,
     pub end_epoch: ChainEpoch,
     pub storage_price_per_epoch: TokenAmount,
@@ -433,11 +515,8 @@ impl TryFrom<&fil_actor_market_state::v9::DealProposal> for DealProposal {
             client: deal_proposal.client,
             provider: deal_proposal.provider,
             label: match &deal_proposal.label {
-                fil_actor_market_state::v9::Label::String(s) => s.clone(),
-                fil_actor_market_state::v9::Label::Bytes(b) if b.is_empty() => Default::default(),
-                fil_actor_market_state::v9::Label::Bytes(b) => {
-                    String::from_utf8(b.clone()).unwrap_or_default()
-                }
+                fil_actor_market_state::v9::Label::String(s) => DealLabel::String(s.clone()),
+                fil_actor_market_state::v9::Label::Bytes(b) => DealLabel::Bytes(b.clone()),
             },
             start_epoch: deal_proposal.start_epoch,
             end_epoch: deal_proposal.end_epoch,
@@ -461,11 +540,8 @@ impl TryFrom<&fil_actor_market_state::v10::DealProposal> for DealProposal {
             client: from_address_v3_to_v2(deal_proposal.client),
             provider: from_address_v3_to_v2(deal_proposal.provider),
             label: match &deal_proposal.label {
-                fil_actor_market_state::v10::Label::String(s) => s.clone(),
-                fil_actor_market_state::v10::Label::Bytes(b) if b.is_empty() => Default::default(),
-                fil_actor_market_state::v10::Label::Bytes(b) => {
-                    String::from_utf8(b.clone()).unwrap_or_default()
-                }
+                fil_actor_market_state::v10::Label::String(s) => DealLabel::String(s.clone()),
+                fil_actor_market_state::v10::Label::Bytes(b) => DealLabel::Bytes(b.clone()),
             },
             start_epoch: deal_proposal.start_epoch,
             end_epoch: deal_proposal.end_epoch,
@@ -489,11 +565,8 @@ impl TryFrom<&fil_actor_market_state::v11::DealProposal> for DealProposal {
             client: from_address_v3_to_v2(deal_proposal.client),
             provider: from_address_v3_to_v2(deal_proposal.provider),
             label: match &deal_proposal.label {
-                fil_actor_market_state::v11::Label::String(s) => s.clone(),
-                fil_actor_market_state::v11::Label::Bytes(b) if b.is_empty() => Default::default(),
-                fil_actor_market_state::v11::Label::Bytes(b) => {
-                    String::from_utf8(b.clone()).unwrap_or_default()
-                }
+                fil_actor_market_state::v11::Label::String(s) => DealLabel::String(s.clone()),
+                fil_actor_market_state::v11::Label::Bytes(b) => DealLabel::Bytes(b.clone()),
             },
             start_epoch: deal_proposal.start_epoch,
             end_epoch: deal_proposal.end_epoch,
@@ -517,11 +590,8 @@ impl TryFrom<&fil_actor_market_state::v12::DealProposal> for DealProposal {
             client: from_address_v4_to_v2(deal_proposal.client),
             provider: from_address_v4_to_v2(deal_proposal.provider),
             label: match &deal_proposal.label {
-                fil_actor_market_state::v12::Label::String(s) => s.clone(),
-                fil_actor_market_state::v12::Label::Bytes(b) if b.is_empty() => Default::default(),
-                fil_actor_market_state::v12::Label::Bytes(b) => {
-                    String::from_utf8(b.clone()).unwrap_or_default()
-                }
+                fil_actor_market_state::v12::Label::String(s) => DealLabel::String(s.clone()),
+                fil_actor_market_state::v12::Label::Bytes(b) => DealLabel::Bytes(b.clone()),
             },
             start_epoch: deal_proposal.start_epoch,
             end_epoch: deal_proposal.end_epoch,
@@ -545,11 +615,8 @@ impl TryFrom<&fil_actor_market_state::v13::DealProposal> for DealProposal {
             client: from_address_v4_to_v2(deal_proposal.client),
             provider: from_address_v4_to_v2(deal_proposal.provider),
             label: match &deal_proposal.label {
-                fil_actor_market_state::v13::Label::String(s) => s.clone(),
-                fil_actor_market_state::v13::Label::Bytes(b) if b.is_empty() => Default::default(),
-                fil_actor_market_state::v13::Label::Bytes(b) => {
-                    String::from_utf8(b.clone()).unwrap_or_default()
-                }
+                fil_actor_market_state::v13::Label::String(s) => DealLabel::String(s.clone()),
+                fil_actor_market_state::v13::Label::Bytes(b) => DealLabel::Bytes(b.clone()),
             },
             start_epoch: deal_proposal.start_epoch,
             end_epoch: deal_proposal.end_epoch,
@@ -573,11 +640,8 @@ impl TryFrom<&fil_actor_market_state::v14::DealProposal> for DealProposal {
             client: from_address_v4_to_v2(deal_proposal.client),
             provider: from_address_v4_to_v2(deal_proposal.provider),
             label: match &deal_proposal.label {
-                fil_actor_market_state::v14::Label::String(s) => s.clone(),
-                fil_actor_market_state::v14::Label::Bytes(b) if b.is_empty() => Default::default(),
-                fil_actor_market_state::v14::Label::Bytes(b) => {
-                    String::from_utf8(b.clone()).unwrap_or_default()
-                }
+                fil_actor_market_state::v14::Label::String(s) => DealLabel::String(s.clone()),
+                fil_actor_market_state::v14::Label::Bytes(b) => DealLabel::Bytes(b.clone()),
             },
             start_epoch: deal_proposal.start_epoch,
             end_epoch: deal_proposal.end_epoch,
@@ -601,11 +665,8 @@ impl TryFrom<&fil_actor_market_state::v15::DealProposal> for DealProposal {
             client: from_address_v4_to_v2(deal_proposal.client),
             provider: from_address_v4_to_v2(deal_proposal.provider),
             label: match &deal_proposal.label {
-                fil_actor_market_state::v15::Label::String(s) => s.clone(),
-                fil_actor_market_state::v15::Label::Bytes(b) if b.is_empty() => Default::default(),
-                fil_actor_market_state::v15::Label::Bytes(b) => {
-                    String::from_utf8(b.clone()).unwrap_or_default()
-                }
+                fil_actor_market_state::v15::Label::String(s) => DealLabel::String(s.clone()),
+                fil_actor_market_state::v15::Label::Bytes(b) => DealLabel::Bytes(b.clone()),
             },
             start_epoch: deal_proposal.start_epoch,
             end_epoch: deal_proposal.end_epoch,
@@ -629,11 +690,8 @@ impl TryFrom<&fil_actor_market_state::v16::DealProposal> for DealProposal {
             client: from_address_v4_to_v2(deal_proposal.client),
             provider: from_address_v4_to_v2(deal_proposal.provider),
             label: match &deal_proposal.label {
-                fil_actor_market_state::v16::Label::String(s) => s.clone(),
-                fil_actor_market_state::v16::Label::Bytes(b) if b.is_empty() => Default::default(),
-                fil_actor_market_state::v16::Label::Bytes(b) => {
-                    String::from_utf8(b.clone()).unwrap_or_default()
-                }
+                fil_actor_market_state::v16::Label::String(s) => DealLabel::String(s.clone()),
+                fil_actor_market_state::v16::Label::Bytes(b) => DealLabel::Bytes(b.clone()),
             },
             start_epoch: deal_proposal.start_epoch,
             end_epoch: deal_proposal.end_epoch,
@@ -5,7 +5,7 @@ mod partition;
 mod state;
 
 use crate::shim::actors::{
-    miner::{DeadlineInfo, State},
+    miner::{DeadlineInfo, Partition, State},
     Policy,
 };
 use cid::Cid;
@@ -36,7 +36,22 @@ pub trait MinerStateExt {
         sector_number: u64,
     ) -> anyhow::Result<Option<SectorPreCommitOnChainInfo>>;
 
+    /// Loads the precommit-on-chain infos of all pending precommits
+    fn load_all_precommit_on_chain_infos<BS: Blockstore>(
+        &self,
+        store: &BS,
+    ) -> anyhow::Result<Vec<SectorPreCommitOnChainInfo>>;
+
     fn recorded_deadline_info(&self, policy: &Policy, current_epoch: ChainEpoch) -> DeadlineInfo;
+
+    /// Unions the sectors selected by `get_sectors` from each partition of
+    /// each deadline
+    fn partition_sectors_union<BS: Blockstore>(
+        &self,
+        policy: &Policy,
+        store: &BS,
+        get_sectors: impl FnMut(Partition<'_>) -> BitField,
+    ) -> anyhow::Result<BitField>;
 }
 
 pub trait PartitionExt {
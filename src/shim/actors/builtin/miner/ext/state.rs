@@ -3,8 +3,11 @@
 
 use crate::shim::actors::{convert::*, Policy};
 use anyhow::Context as _;
+use fil_actors_shared::v8::{make_map_with_root_and_bitwidth, HAMT_BIT_WIDTH};
+use serde::{de::DeserializeOwned, Serialize};
 
 use crate::shim::clock::ChainEpoch;
+use crate::utils::bitfield::union_all;
 
 use super::*;
 
@@ -228,6 +231,50 @@ impl MinerStateExt for State {
         })
     }
 
+    fn load_all_precommit_on_chain_infos<BS: Blockstore>(
+        &self,
+        store: &BS,
+    ) -> anyhow::Result<Vec<SectorPreCommitOnChainInfo>> {
+        match self {
+            Self::V8(s) => load_precommits::<
+                _,
+                fil_actor_miner_state::v8::SectorPreCommitOnChainInfo,
+            >(store, &s.pre_committed_sectors),
+            Self::V9(s) => load_precommits::<
+                _,
+                fil_actor_miner_state::v9::SectorPreCommitOnChainInfo,
+            >(store, &s.pre_committed_sectors),
+            Self::V10(s) => load_precommits::<
+                _,
+                fil_actor_miner_state::v10::SectorPreCommitOnChainInfo,
+            >(store, &s.pre_committed_sectors),
+            Self::V11(s) => load_precommits::<
+                _,
+                fil_actor_miner_state::v11::SectorPreCommitOnChainInfo,
+            >(store, &s.pre_committed_sectors),
+            Self::V12(s) => load_precommits::<
+                _,
+                fil_actor_miner_state::v12::SectorPreCommitOnChainInfo,
+            >(store, &s.pre_committed_sectors),
+            Self::V13(s) => load_precommits::<
+                _,
+                fil_actor_miner_state::v13::SectorPreCommitOnChainInfo,
+            >(store, &s.pre_committed_sectors),
+            Self::V14(s) => load_precommits::<
+                _,
+                fil_actor_miner_state::v14::SectorPreCommitOnChainInfo,
+            >(store, &s.pre_committed_sectors),
+            Self::V15(s) => load_precommits::<
+                _,
+                fil_actor_miner_state::v15::SectorPreCommitOnChainInfo,
+            >(store, &s.pre_committed_sectors),
+            Self::V16(s) => load_precommits::<
+                _,
+                fil_actor_miner_state::v16::SectorPreCommitOnChainInfo,
+            >(store, &s.pre_committed_sectors),
+        }
+    }
+
     /// Returns deadline calculations for the state recorded proving period and deadline.
     /// This is out of date if the a miner does not have an active miner cron
     fn recorded_deadline_info(&self, policy: &Policy, current_epoch: ChainEpoch) -> DeadlineInfo {
@@ -259,4 +306,90 @@ impl MinerStateExt for State {
                 .into(),
         }
     }
+
+    fn partition_sectors_union<BS: Blockstore>(
+        &self,
+        policy: &Policy,
+        store: &BS,
+        mut get_sectors: impl FnMut(Partition<'_>) -> BitField,
+    ) -> anyhow::Result<BitField> {
+        let mut sectors = vec![];
+        self.for_each_deadline(policy, store, |_, deadline| {
+            deadline.for_each(store, |_, partition| {
+                sectors.push(get_sectors(partition));
+                Ok(())
+            })
+        })?;
+        Ok(union_all(sectors))
+    }
+}
+
+/// Loads all entries of a precommitted sectors `HAMT`, its layout is the same
+/// across actor versions.
+fn load_precommits<BS, V>(store: &BS, root: &Cid) -> anyhow::Result<Vec<SectorPreCommitOnChainInfo>>
+where
+    BS: Blockstore,
+    V: Serialize + DeserializeOwned + Clone + Into<SectorPreCommitOnChainInfo>,
+{
+    let precommits = make_map_with_root_and_bitwidth::<_, V>(root, store, HAMT_BIT_WIDTH)?;
+    let mut infos = vec![];
+    precommits.for_each(|_, info| {
+        infos.push(info.clone().into());
+        Ok(())
+    })?;
+    Ok(infos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MemoryDB;
+    use crate::shim::{clock::EPOCHS_IN_DAY, econ::TokenAmount};
+    use crate::state_manager::PreCommitDepositInfo;
+    use fil_actor_miner_state::v16::{
+        SectorPreCommitInfo, SectorPreCommitOnChainInfo as PreCommitV16,
+    };
+    use fil_actors_shared::v8::{make_empty_map, u64_key};
+    use fvm_shared4::sector::RegisteredSealProof;
+
+    #[test]
+    fn test_load_precommit_deposits() {
+        let store = MemoryDB::default();
+        let mut precommits = make_empty_map::<_, PreCommitV16>(&store, HAMT_BIT_WIDTH);
+        for sector_number in 1..=3 {
+            let precommit = PreCommitV16 {
+                info: SectorPreCommitInfo {
+                    seal_proof: RegisteredSealProof::StackedDRG32GiBV1P1,
+                    sector_number,
+                    ..Default::default()
+                },
+                pre_commit_deposit: TokenAmount::from_whole(sector_number).into(),
+                pre_commit_epoch: 100 * sector_number as ChainEpoch,
+            };
+            precommits.set(u64_key(sector_number), precommit).unwrap();
+        }
+        let root = precommits.flush().unwrap();
+
+        let mut infos = load_precommits::<_, PreCommitV16>(&store, &root).unwrap();
+        infos.sort_by_key(|info| info.info.sector_number);
+        assert_eq!(infos.len(), 3);
+        for (info, sector_number) in infos.iter().zip(1..) {
+            assert_eq!(info.info.sector_number, sector_number);
+            assert_eq!(
+                info.pre_commit_deposit,
+                TokenAmount::from_whole(sector_number)
+            );
+            assert_eq!(info.pre_commit_epoch, 100 * sector_number as ChainEpoch);
+        }
+
+        let policy = Policy::default();
+        for info in infos {
+            let pre_commit_epoch = info.pre_commit_epoch;
+            let deposit = PreCommitDepositInfo::new(&policy, info).unwrap();
+            assert_eq!(
+                deposit.expiry_epoch,
+                pre_commit_epoch + 30 * EPOCHS_IN_DAY + policy.pre_commit_challenge_delay
+            );
+        }
+    }
 }
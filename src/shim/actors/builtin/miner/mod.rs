@@ -6,7 +6,10 @@ pub mod ext;
 use crate::shim::actors::convert::*;
 use crate::shim::actors::Policy;
 use cid::Cid;
-use fil_actor_miner_state::v12::{BeneficiaryTerm, PendingBeneficiaryChange};
+use fil_actor_miner_state::v10::qa_power_for_weight;
+use fil_actor_miner_state::v12::{
+    BeneficiaryTerm, PendingBeneficiaryChange, PowerPair, WorkerKeyChange,
+};
 use fil_actors_shared::fvm_ipld_bitfield::BitField;
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::{serde_bytes, BytesDe};
@@ -17,7 +20,7 @@ use fvm_shared2::{
     econ::TokenAmount,
     sector::{RegisteredPoStProof, RegisteredSealProof, SectorNumber, SectorSize},
 };
-use num::BigInt;
+use num::{BigInt, Zero as _};
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 
@@ -348,6 +351,84 @@ impl State {
         }
     }
 
+    /// Gets locked vesting funds of miner state
+    pub fn locked_funds(&self) -> TokenAmount {
+        match self {
+            State::V8(st) => st.locked_funds.clone(),
+            State::V9(st) => st.locked_funds.clone(),
+            State::V10(st) => from_token_v3_to_v2(&st.locked_funds),
+            State::V11(st) => from_token_v3_to_v2(&st.locked_funds),
+            State::V12(st) => from_token_v4_to_v2(&st.locked_funds),
+            State::V13(st) => from_token_v4_to_v2(&st.locked_funds),
+            State::V14(st) => from_token_v4_to_v2(&st.locked_funds),
+            State::V15(st) => from_token_v4_to_v2(&st.locked_funds),
+            State::V16(st) => from_token_v4_to_v2(&st.locked_funds),
+        }
+    }
+
+    /// Loads the vesting schedule of the locked funds, as `(epoch, amount)`
+    /// pairs in ascending epoch order.
+    pub fn load_vesting_schedule<BS: Blockstore>(
+        &self,
+        store: &BS,
+    ) -> anyhow::Result<Vec<(ChainEpoch, TokenAmount)>> {
+        Ok(match self {
+            State::V8(st) => st
+                .load_vesting_funds(store)?
+                .funds
+                .into_iter()
+                .map(|fund| (fund.epoch, fund.amount))
+                .collect(),
+            State::V9(st) => st
+                .load_vesting_funds(store)?
+                .funds
+                .into_iter()
+                .map(|fund| (fund.epoch, fund.amount))
+                .collect(),
+            State::V10(st) => st
+                .load_vesting_funds(store)?
+                .funds
+                .into_iter()
+                .map(|fund| (fund.epoch, from_token_v3_to_v2(&fund.amount)))
+                .collect(),
+            State::V11(st) => st
+                .load_vesting_funds(store)?
+                .funds
+                .into_iter()
+                .map(|fund| (fund.epoch, from_token_v3_to_v2(&fund.amount)))
+                .collect(),
+            State::V12(st) => st
+                .load_vesting_funds(store)?
+                .funds
+                .into_iter()
+                .map(|fund| (fund.epoch, from_token_v4_to_v2(&fund.amount)))
+                .collect(),
+            State::V13(st) => st
+                .load_vesting_funds(store)?
+                .funds
+                .into_iter()
+                .map(|fund| (fund.epoch, from_token_v4_to_v2(&fund.amount)))
+                .collect(),
+            State::V14(st) => st
+                .load_vesting_funds(store)?
+                .funds
+                .into_iter()
+                .map(|fund| (fund.epoch, from_token_v4_to_v2(&fund.amount)))
+                .collect(),
+            State::V15(st) => st
+                .load_vesting_funds(store)?
+                .funds
+                .into_iter()
+                .map(|fund| (fund.epoch, from_token_v4_to_v2(&fund.amount)))
+                .collect(),
+            State::V16(st) => st
+                .vesting_funds
+                .load(store)?
+                .map(|fund| (fund.epoch, from_token_v4_to_v2(&fund.amount)))
+                .collect(),
+        })
+    }
+
     /// Unclaimed funds. Actor balance - (locked funds, precommit deposit, ip requirement) Can go negative if the miner is in IP debt.
     pub fn available_balance(&self, balance: &BigInt) -> anyhow::Result<TokenAmount> {
         let balance: TokenAmount = TokenAmount::from_atto(balance.clone());
@@ -366,6 +447,82 @@ impl State {
         }
     }
 
+    /// Network fee burnt for an aggregated proof of `aggregate_size` sectors
+    /// at `base_fee`. The batch fees were removed in v16 (FIP-0100).
+    pub fn aggregate_prove_commit_network_fee(
+        &self,
+        aggregate_size: usize,
+        base_fee: &TokenAmount,
+    ) -> anyhow::Result<TokenAmount> {
+        let base_fee_v3 = from_token_v2_to_v3(base_fee);
+        let base_fee_v4 = from_token_v2_to_v4(base_fee);
+        match self {
+            State::V8(_) => anyhow::bail!("unimplemented"),
+            State::V9(_) => anyhow::bail!("unimplemented"),
+            State::V10(_) => anyhow::bail!("unimplemented"),
+            State::V11(_) => Ok(from_token_v3_to_v2(
+                &fil_actor_miner_state::v11::aggregate_prove_commit_network_fee(
+                    aggregate_size as _,
+                    &base_fee_v3,
+                ),
+            )),
+            State::V12(_) => Ok(from_token_v4_to_v2(
+                &fil_actor_miner_state::v12::aggregate_prove_commit_network_fee(
+                    aggregate_size as _,
+                    &base_fee_v4,
+                ),
+            )),
+            State::V13(_) => Ok(from_token_v4_to_v2(
+                &fil_actor_miner_state::v13::aggregate_prove_commit_network_fee(
+                    aggregate_size as _,
+                    &base_fee_v4,
+                ),
+            )),
+            State::V14(_) => Ok(from_token_v4_to_v2(
+                &fil_actor_miner_state::v14::aggregate_prove_commit_network_fee(
+                    aggregate_size as _,
+                    &base_fee_v4,
+                ),
+            )),
+            State::V15(_) => Ok(from_token_v4_to_v2(
+                &fil_actor_miner_state::v15::aggregate_prove_commit_network_fee(
+                    aggregate_size as _,
+                    &base_fee_v4,
+                ),
+            )),
+            State::V16(_) => Ok(TokenAmount::zero()),
+        }
+    }
+
+    /// Returns the proving period start recorded in the state. It may lag
+    /// behind if the miner has no active cron.
+    pub fn proving_period_start(&self) -> ChainEpoch {
+        match self {
+            State::V8(st) => st.proving_period_start,
+            State::V9(st) => st.proving_period_start,
+            State::V10(st) => st.proving_period_start,
+            State::V11(st) => st.proving_period_start,
+            State::V12(st) => st.proving_period_start,
+            State::V13(st) => st.proving_period_start,
+            State::V14(st) => st.proving_period_start,
+            State::V15(st) => st.proving_period_start,
+            State::V16(st) => st.proving_period_start,
+        }
+    }
+
+    /// Returns the start of the proving period containing `current_epoch`.
+    pub fn current_proving_period_start(
+        &self,
+        policy: &Policy,
+        current_epoch: ChainEpoch,
+    ) -> ChainEpoch {
+        QuantSpec {
+            unit: policy.wpost_proving_period,
+            offset: self.proving_period_start(),
+        }
+        .quantize_down(current_epoch)
+    }
+
     /// Returns deadline calculations for the current (according to state) proving period.
     pub fn deadline_info(&self, policy: &Policy, current_epoch: ChainEpoch) -> DeadlineInfo {
         match self {
@@ -794,6 +951,14 @@ impl MinerInfo {
     pub fn sector_size(&self) -> SectorSize {
         self.sector_size
     }
+
+    /// Returns the staged change of the worker key, if any.
+    pub fn pending_worker_key(&self) -> Option<WorkerKeyChange> {
+        self.new_worker.map(|new_worker| WorkerKeyChange {
+            new_worker: from_address_v2_to_v4(new_worker),
+            effective_at: self.worker_change_epoch,
+        })
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize, Eq, PartialEq, PartialOrd, Ord, Hash)]
@@ -926,6 +1091,20 @@ impl Partition<'_> {
             Partition::V16(dl) => &dl.faults,
         }
     }
+    /// Quality-adjusted power of the faulty sectors
+    pub fn faulty_qa_power(&self) -> BigInt {
+        match self {
+            Partition::V8(dl) => dl.faulty_power.qa.clone(),
+            Partition::V9(dl) => dl.faulty_power.qa.clone(),
+            Partition::V10(dl) => dl.faulty_power.qa.clone(),
+            Partition::V11(dl) => dl.faulty_power.qa.clone(),
+            Partition::V12(dl) => dl.faulty_power.qa.clone(),
+            Partition::V13(dl) => dl.faulty_power.qa.clone(),
+            Partition::V14(dl) => dl.faulty_power.qa.clone(),
+            Partition::V15(dl) => dl.faulty_power.qa.clone(),
+            Partition::V16(dl) => dl.faulty_power.qa.clone(),
+        }
+    }
     pub fn live_sectors(&self) -> BitField {
         match self {
             Partition::V8(dl) => dl.live_sectors(),
@@ -980,6 +1159,9 @@ pub struct SectorOnChainInfo {
     pub activation: ChainEpoch,
     /// Epoch during which the sector expires
     pub expiration: ChainEpoch,
+    /// Epoch from which the power of the sector is computed, its activation
+    /// unless the sector was extended or updated since
+    pub power_base_epoch: ChainEpoch,
     /// Integral of active deals over sector lifetime
     pub deal_weight: BigInt,
     /// Integral of active verified deals over sector lifetime
@@ -1011,6 +1193,7 @@ impl From<fil_actor_miner_state::v8::SectorOnChainInfo> for SectorOnChainInfo {
             deal_ids: info.deal_ids,
             activation: info.activation,
             expiration: info.expiration,
+            power_base_epoch: info.activation,
             deal_weight: info.deal_weight,
             verified_deal_weight: info.verified_deal_weight,
             initial_pledge: info.initial_pledge,
@@ -1033,6 +1216,7 @@ impl From<fil_actor_miner_state::v9::SectorOnChainInfo> for SectorOnChainInfo {
             deal_ids: info.deal_ids,
             activation: info.activation,
             expiration: info.expiration,
+            power_base_epoch: info.activation,
             deal_weight: info.deal_weight,
             verified_deal_weight: info.verified_deal_weight,
             initial_pledge: info.initial_pledge,
@@ -1055,6 +1239,7 @@ impl From<fil_actor_miner_state::v10::SectorOnChainInfo> for SectorOnChainInfo {
             deal_ids: info.deal_ids,
             activation: info.activation,
             expiration: info.expiration,
+            power_base_epoch: info.activation,
             deal_weight: info.deal_weight,
             verified_deal_weight: info.verified_deal_weight,
             initial_pledge: from_token_v3_to_v2(&info.initial_pledge),
@@ -1077,6 +1262,7 @@ impl From<fil_actor_miner_state::v11::SectorOnChainInfo> for SectorOnChainInfo {
             deal_ids: info.deal_ids,
             activation: info.activation,
             expiration: info.expiration,
+            power_base_epoch: info.activation,
             deal_weight: info.deal_weight,
             verified_deal_weight: info.verified_deal_weight,
             initial_pledge: from_token_v3_to_v2(&info.initial_pledge),
@@ -1099,6 +1285,7 @@ impl From<fil_actor_miner_state::v12::SectorOnChainInfo> for SectorOnChainInfo {
             deal_ids: info.deal_ids,
             activation: info.activation,
             expiration: info.expiration,
+            power_base_epoch: info.power_base_epoch,
             deal_weight: info.deal_weight,
             verified_deal_weight: info.verified_deal_weight,
             initial_pledge: from_token_v4_to_v2(&info.initial_pledge),
@@ -1121,6 +1308,7 @@ impl From<fil_actor_miner_state::v13::SectorOnChainInfo> for SectorOnChainInfo {
             deal_ids: info.deprecated_deal_ids,
             activation: info.activation,
             expiration: info.expiration,
+            power_base_epoch: info.power_base_epoch,
             deal_weight: info.deal_weight,
             verified_deal_weight: info.verified_deal_weight,
             initial_pledge: from_token_v4_to_v2(&info.initial_pledge),
@@ -1143,6 +1331,7 @@ impl From<fil_actor_miner_state::v14::SectorOnChainInfo> for SectorOnChainInfo {
             deal_ids: info.deprecated_deal_ids,
             activation: info.activation,
             expiration: info.expiration,
+            power_base_epoch: info.power_base_epoch,
             deal_weight: info.deal_weight,
             verified_deal_weight: info.verified_deal_weight,
             initial_pledge: from_token_v4_to_v2(&info.initial_pledge),
@@ -1165,6 +1354,7 @@ impl From<fil_actor_miner_state::v15::SectorOnChainInfo> for SectorOnChainInfo {
             deal_ids: info.deprecated_deal_ids,
             activation: info.activation,
             expiration: info.expiration,
+            power_base_epoch: info.power_base_epoch,
             deal_weight: info.deal_weight,
             verified_deal_weight: info.verified_deal_weight,
             initial_pledge: from_token_v4_to_v2(&info.initial_pledge),
@@ -1187,6 +1377,7 @@ impl From<fil_actor_miner_state::v16::SectorOnChainInfo> for SectorOnChainInfo {
             deal_ids: info.deprecated_deal_ids,
             activation: info.activation,
             expiration: info.expiration,
+            power_base_epoch: info.power_base_epoch,
             deal_weight: info.deal_weight,
             verified_deal_weight: info.verified_deal_weight,
             initial_pledge: from_token_v4_to_v2(&info.initial_pledge),
@@ -1200,6 +1391,28 @@ impl From<fil_actor_miner_state::v16::SectorOnChainInfo> for SectorOnChainInfo {
     }
 }
 
+/// Returns the raw byte and quality adjusted power of a sector, as computed by
+/// `power_for_sector` of the miner actor. The quality is averaged from the
+/// power base epoch of the sector, its activation or last update, to its
+/// expiration.
+pub fn power_for_sector(sector_size: SectorSize, sector: &SectorOnChainInfo) -> PowerPair {
+    PowerPair {
+        raw: BigInt::from(sector_size as u64),
+        qa: qa_power_for_sector(sector_size, sector),
+    }
+}
+
+/// Quality adjusted power of a sector, from its deal weights since its power
+/// base epoch. Verified deals count ten times their size.
+pub fn qa_power_for_sector(sector_size: SectorSize, sector: &SectorOnChainInfo) -> BigInt {
+    qa_power_for_weight(
+        crate::shim::sector::SectorSize::from(sector_size).into(),
+        sector.expiration - sector.power_base_epoch,
+        &sector.deal_weight,
+        &sector.verified_deal_weight,
+    )
+}
+
 /// Deadline calculations with respect to a current epoch.
 /// "Deadline" refers to the window during which proofs may be submitted.
 /// Windows are non-overlapping ranges [Open, Close), but the challenge epoch for a window occurs
@@ -1348,6 +1561,20 @@ impl DeadlineInfo {
         )
     }
 
+    /// Returns deadline calculations for another deadline of the same proving period.
+    pub fn for_index(&self, index: u64) -> Self {
+        Self::new(
+            self.period_start,
+            index,
+            self.current_epoch,
+            self.w_post_period_deadlines,
+            self.w_post_proving_period,
+            self.w_post_challenge_window,
+            self.w_post_challenge_lookback,
+            self.fault_declaration_cutoff,
+        )
+    }
+
     pub fn quant_spec(&self) -> QuantSpec {
         QuantSpec {
             unit: self.w_post_proving_period,
@@ -1652,3 +1879,94 @@ impl From<fil_actor_miner_state::v16::DeadlineInfo> for DeadlineInfo {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    fn sector(deal_weight: u64, verified_deal_weight: u64) -> SectorOnChainInfo {
+        let duration = 1000;
+        fil_actor_miner_state::v16::SectorOnChainInfo {
+            activation: 100,
+            power_base_epoch: 100,
+            expiration: 100 + duration,
+            deal_weight: BigInt::from(deal_weight) * duration,
+            verified_deal_weight: BigInt::from(verified_deal_weight) * duration,
+            ..Default::default()
+        }
+        .into()
+    }
+
+    #[test]
+    fn power_for_cc_sector() {
+        let size = SectorSize::_32GiB;
+        let power = power_for_sector(size, &sector(0, 0));
+        assert_eq!(power.raw, BigInt::from(size as u64));
+        assert_eq!(power.qa, power.raw);
+    }
+
+    #[test]
+    fn power_for_verified_sector() {
+        let size = SectorSize::_32GiB;
+        let power = power_for_sector(size, &sector(0, size as u64));
+        assert_eq!(power.raw, BigInt::from(size as u64));
+        assert_eq!(power.qa, power.raw * 10);
+    }
+
+    #[quickcheck]
+    fn qa_power_is_at_least_raw_power(deal_weight: u32, verified_deal_weight: u32) {
+        // Deal weights per epoch cannot exceed the 32GiB sector size
+        let size = SectorSize::_32GiB;
+        let raw = BigInt::from(size as u64);
+        let qa = qa_power_for_sector(
+            size,
+            &sector(deal_weight.into(), verified_deal_weight.into()),
+        );
+        assert!(qa >= raw, "{qa} < {raw}");
+        assert!(qa <= raw * 10);
+    }
+
+    #[quickcheck]
+    fn qa_power_of_fully_verified_sector(duration: u16) {
+        let size = SectorSize::_2KiB;
+        let duration = ChainEpoch::from(duration) + 1;
+        let sector: SectorOnChainInfo = fil_actor_miner_state::v16::SectorOnChainInfo {
+            activation: 100,
+            power_base_epoch: 100,
+            expiration: 100 + duration,
+            verified_deal_weight: BigInt::from(size as u64) * duration,
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(
+            qa_power_for_sector(size, &sector),
+            BigInt::from(size as u64) * 10
+        );
+    }
+
+    #[test]
+    fn qa_power_since_power_base_epoch() {
+        // Sector updated at epoch 600 with verified deals until its expiration
+        let size = SectorSize::_32GiB;
+        let sector: SectorOnChainInfo = fil_actor_miner_state::v16::SectorOnChainInfo {
+            activation: 100,
+            power_base_epoch: 600,
+            expiration: 1100,
+            verified_deal_weight: BigInt::from(size as u64) * 500,
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(
+            power_for_sector(size, &sector).qa,
+            BigInt::from(size as u64) * 10
+        );
+    }
+
+    #[test]
+    fn power_for_half_verified_sector() {
+        let size = SectorSize::_32GiB;
+        let power = power_for_sector(size, &sector(0, size as u64 / 2));
+        assert_eq!(power.qa, BigInt::from(size as u64) * 11 / 2);
+    }
+}
@@ -20,6 +20,7 @@ use fil_actor_miner_state::v15::initial_pledge_for_power as initial_pledge_for_p
 use fil_actor_miner_state::v16::initial_pledge_for_power as initial_pledge_for_power_v16;
 use fvm_shared2::address::Address;
 use fvm_shared2::bigint::Integer;
+use fvm_shared2::clock::ChainEpoch;
 use fvm_shared2::sector::StoragePower;
 use fvm_shared2::smooth::FilterEstimate;
 use fvm_shared2::{econ::TokenAmount, piece::PaddedPieceSize, TOTAL_FILECOIN};
@@ -27,6 +28,7 @@ use num::BigInt;
 use serde::Serialize;
 use std::cmp::max;
 
+use crate::shim::actors::miner::SectorOnChainInfo;
 use crate::shim::actors::Policy;
 
 /// Reward actor address
@@ -81,6 +83,22 @@ impl State {
         }
     }
 
+    /// The reward split between the blocks of this state's epoch, in
+    /// proportion to their win counts.
+    pub fn this_epoch_reward(&self) -> TokenAmount {
+        match self {
+            State::V8(st) => st.this_epoch_reward.clone(),
+            State::V9(st) => st.this_epoch_reward.clone(),
+            State::V10(st) => from_token_v3_to_v2(&st.this_epoch_reward),
+            State::V11(st) => from_token_v3_to_v2(&st.this_epoch_reward),
+            State::V12(st) => from_token_v4_to_v2(&st.this_epoch_reward),
+            State::V13(st) => from_token_v4_to_v2(&st.this_epoch_reward),
+            State::V14(st) => from_token_v4_to_v2(&st.this_epoch_reward),
+            State::V15(st) => from_token_v4_to_v2(&st.this_epoch_reward),
+            State::V16(st) => from_token_v4_to_v2(&st.this_epoch_reward),
+        }
+    }
+
     pub fn pre_commit_deposit_for_power(
         &self,
         network_qa_power: FilterEstimate,
@@ -153,6 +171,268 @@ impl State {
         }
     }
 
+    /// Penalty charged each proving period for sectors of the given
+    /// quality-adjusted power that remain faulty.
+    pub fn pledge_penalty_for_continued_fault(
+        &self,
+        network_qa_power: FilterEstimate,
+        qa_sector_power: &StoragePower,
+    ) -> anyhow::Result<TokenAmount> {
+        match self {
+            State::V8(_st) => anyhow::bail!("unimplemented"),
+            State::V9(_st) => anyhow::bail!("unimplemented"),
+            State::V10(_st) => anyhow::bail!("unimplemented"),
+            State::V11(st) => Ok(from_token_v3_to_v2(
+                &fil_actor_miner_state::v11::pledge_penalty_for_continued_fault(
+                    &st.this_epoch_reward_smoothed,
+                    &fvm_shared3::smooth::FilterEstimate {
+                        position: network_qa_power.position,
+                        velocity: network_qa_power.velocity,
+                    },
+                    qa_sector_power,
+                ),
+            )),
+            State::V12(st) => Ok(from_token_v4_to_v2(
+                &fil_actor_miner_state::v12::pledge_penalty_for_continued_fault(
+                    &st.this_epoch_reward_smoothed,
+                    &fvm_shared4::smooth::FilterEstimate {
+                        position: network_qa_power.position,
+                        velocity: network_qa_power.velocity,
+                    },
+                    qa_sector_power,
+                ),
+            )),
+            State::V13(st) => Ok(from_token_v4_to_v2(
+                &fil_actor_miner_state::v13::pledge_penalty_for_continued_fault(
+                    &st.this_epoch_reward_smoothed,
+                    &fvm_shared4::smooth::FilterEstimate {
+                        position: network_qa_power.position,
+                        velocity: network_qa_power.velocity,
+                    },
+                    qa_sector_power,
+                ),
+            )),
+            State::V14(st) => Ok(from_token_v4_to_v2(
+                &fil_actor_miner_state::v14::pledge_penalty_for_continued_fault(
+                    &st.this_epoch_reward_smoothed,
+                    &fil_actors_shared::v14::reward::FilterEstimate {
+                        position: network_qa_power.position,
+                        velocity: network_qa_power.velocity,
+                    },
+                    qa_sector_power,
+                ),
+            )),
+            State::V15(st) => Ok(from_token_v4_to_v2(
+                &fil_actor_miner_state::v15::pledge_penalty_for_continued_fault(
+                    &st.this_epoch_reward_smoothed,
+                    &fil_actors_shared::v15::reward::FilterEstimate {
+                        position: network_qa_power.position,
+                        velocity: network_qa_power.velocity,
+                    },
+                    qa_sector_power,
+                ),
+            )),
+            State::V16(st) => Ok(from_token_v4_to_v2(
+                &fil_actor_miner_state::v16::pledge_penalty_for_continued_fault(
+                    &st.this_epoch_reward_smoothed,
+                    &fil_actors_shared::v16::reward::FilterEstimate {
+                        position: network_qa_power.position,
+                        velocity: network_qa_power.velocity,
+                    },
+                    qa_sector_power,
+                ),
+            )),
+        }
+    }
+
+    /// Penalty charged for the early termination of `sector`, of the given
+    /// quality-adjusted power, at `current_epoch`, as computed by the miner
+    /// actor when terminating sectors.
+    pub fn pledge_penalty_for_termination(
+        &self,
+        network_qa_power: FilterEstimate,
+        sector: &SectorOnChainInfo,
+        qa_sector_power: &StoragePower,
+        current_epoch: ChainEpoch,
+    ) -> anyhow::Result<TokenAmount> {
+        match self {
+            State::V8(_st) => anyhow::bail!("unimplemented"),
+            State::V9(_st) => anyhow::bail!("unimplemented"),
+            State::V10(_st) => anyhow::bail!("unimplemented"),
+            State::V11(st) => Ok(from_token_v3_to_v2(
+                &fil_actor_miner_state::v11::pledge_penalty_for_termination(
+                    &from_token_v2_to_v3(&sector.expected_day_reward),
+                    current_epoch - sector.power_base_epoch,
+                    &from_token_v2_to_v3(&sector.expected_storage_pledge),
+                    &fvm_shared3::smooth::FilterEstimate {
+                        position: network_qa_power.position,
+                        velocity: network_qa_power.velocity,
+                    },
+                    qa_sector_power,
+                    &st.this_epoch_reward_smoothed,
+                    &from_token_v2_to_v3(&sector.replaced_day_reward),
+                    sector.replaced_sector_age,
+                ),
+            )),
+            State::V12(st) => Ok(from_token_v4_to_v2(
+                &fil_actor_miner_state::v12::pledge_penalty_for_termination(
+                    &from_token_v2_to_v4(&sector.expected_day_reward),
+                    current_epoch - sector.power_base_epoch,
+                    &from_token_v2_to_v4(&sector.expected_storage_pledge),
+                    &fvm_shared4::smooth::FilterEstimate {
+                        position: network_qa_power.position,
+                        velocity: network_qa_power.velocity,
+                    },
+                    qa_sector_power,
+                    &st.this_epoch_reward_smoothed,
+                    &from_token_v2_to_v4(&sector.replaced_day_reward),
+                    sector.power_base_epoch - sector.activation,
+                ),
+            )),
+            State::V13(st) => Ok(from_token_v4_to_v2(
+                &fil_actor_miner_state::v13::pledge_penalty_for_termination(
+                    &from_token_v2_to_v4(&sector.expected_day_reward),
+                    current_epoch - sector.power_base_epoch,
+                    &from_token_v2_to_v4(&sector.expected_storage_pledge),
+                    &fvm_shared4::smooth::FilterEstimate {
+                        position: network_qa_power.position,
+                        velocity: network_qa_power.velocity,
+                    },
+                    qa_sector_power,
+                    &st.this_epoch_reward_smoothed,
+                    &from_token_v2_to_v4(&sector.replaced_day_reward),
+                    sector.power_base_epoch - sector.activation,
+                ),
+            )),
+            State::V14(st) => Ok(from_token_v4_to_v2(
+                &fil_actor_miner_state::v14::pledge_penalty_for_termination(
+                    &from_token_v2_to_v4(&sector.expected_day_reward),
+                    current_epoch - sector.power_base_epoch,
+                    &from_token_v2_to_v4(&sector.expected_storage_pledge),
+                    &fil_actors_shared::v14::reward::FilterEstimate {
+                        position: network_qa_power.position,
+                        velocity: network_qa_power.velocity,
+                    },
+                    qa_sector_power,
+                    &st.this_epoch_reward_smoothed,
+                    &from_token_v2_to_v4(&sector.replaced_day_reward),
+                    sector.power_base_epoch - sector.activation,
+                ),
+            )),
+            State::V15(st) => Ok(from_token_v4_to_v2(
+                &fil_actor_miner_state::v15::pledge_penalty_for_termination(
+                    &from_token_v2_to_v4(&sector.expected_day_reward),
+                    current_epoch - sector.power_base_epoch,
+                    &from_token_v2_to_v4(&sector.expected_storage_pledge),
+                    &fil_actors_shared::v15::reward::FilterEstimate {
+                        position: network_qa_power.position,
+                        velocity: network_qa_power.velocity,
+                    },
+                    qa_sector_power,
+                    &st.this_epoch_reward_smoothed,
+                    &from_token_v2_to_v4(&sector.replaced_day_reward),
+                    sector.power_base_epoch - sector.activation,
+                ),
+            )),
+            State::V16(_) => {
+                // Since FIP-0098, the penalty is derived from the initial
+                // pledge and the continued fault fee
+                let fault_fee = from_token_v2_to_v4(
+                    &self.pledge_penalty_for_continued_fault(network_qa_power, qa_sector_power)?,
+                );
+                Ok(from_token_v4_to_v2(
+                    &fil_actor_miner_state::v16::pledge_penalty_for_termination(
+                        &from_token_v2_to_v4(&sector.initial_pledge),
+                        current_epoch - sector.power_base_epoch,
+                        &fault_fee,
+                    ),
+                ))
+            }
+        }
+    }
+
+    /// Block reward expected to be earned by sectors of the given
+    /// quality-adjusted power over `projection_duration` epochs, projected from
+    /// the smoothed reward and network power estimates.
+    pub fn expected_reward_for_power(
+        &self,
+        network_qa_power: FilterEstimate,
+        qa_sector_power: &StoragePower,
+        projection_duration: ChainEpoch,
+    ) -> anyhow::Result<TokenAmount> {
+        match self {
+            State::V8(_st) => anyhow::bail!("unimplemented"),
+            State::V9(_st) => anyhow::bail!("unimplemented"),
+            State::V10(_st) => anyhow::bail!("unimplemented"),
+            State::V11(st) => Ok(from_token_v3_to_v2(
+                &fil_actor_miner_state::v11::expected_reward_for_power(
+                    &st.this_epoch_reward_smoothed,
+                    &fvm_shared3::smooth::FilterEstimate {
+                        position: network_qa_power.position,
+                        velocity: network_qa_power.velocity,
+                    },
+                    qa_sector_power,
+                    projection_duration,
+                ),
+            )),
+            State::V12(st) => Ok(from_token_v4_to_v2(
+                &fil_actor_miner_state::v12::expected_reward_for_power(
+                    &st.this_epoch_reward_smoothed,
+                    &fvm_shared4::smooth::FilterEstimate {
+                        position: network_qa_power.position,
+                        velocity: network_qa_power.velocity,
+                    },
+                    qa_sector_power,
+                    projection_duration,
+                ),
+            )),
+            State::V13(st) => Ok(from_token_v4_to_v2(
+                &fil_actor_miner_state::v13::expected_reward_for_power(
+                    &st.this_epoch_reward_smoothed,
+                    &fvm_shared4::smooth::FilterEstimate {
+                        position: network_qa_power.position,
+                        velocity: network_qa_power.velocity,
+                    },
+                    qa_sector_power,
+                    projection_duration,
+                ),
+            )),
+            State::V14(st) => Ok(from_token_v4_to_v2(
+                &fil_actor_miner_state::v14::expected_reward_for_power(
+                    &st.this_epoch_reward_smoothed,
+                    &fil_actors_shared::v14::reward::FilterEstimate {
+                        position: network_qa_power.position,
+                        velocity: network_qa_power.velocity,
+                    },
+                    qa_sector_power,
+                    projection_duration,
+                ),
+            )),
+            State::V15(st) => Ok(from_token_v4_to_v2(
+                &fil_actor_miner_state::v15::expected_reward_for_power(
+                    &st.this_epoch_reward_smoothed,
+                    &fil_actors_shared::v15::reward::FilterEstimate {
+                        position: network_qa_power.position,
+                        velocity: network_qa_power.velocity,
+                    },
+                    qa_sector_power,
+                    projection_duration,
+                ),
+            )),
+            State::V16(st) => Ok(from_token_v4_to_v2(
+                &fil_actor_miner_state::v16::expected_reward_for_power(
+                    &st.this_epoch_reward_smoothed,
+                    &fil_actors_shared::v16::reward::FilterEstimate {
+                        position: network_qa_power.position,
+                        velocity: network_qa_power.velocity,
+                    },
+                    qa_sector_power,
+                    projection_duration,
+                ),
+            )),
+        }
+    }
+
     // The code for versions lower than `v11` does not exist in the original Rust repo, but it does
     // exist for Lotus. The logic is exactly the same for all the versions, therefore it has been
     // decided to introduce a shared helper for all of these versions to match Lotus behaviour.
@@ -22,10 +22,16 @@ static [<$actor:upper _ACTOR_CIDS>]: Lazy<Vec<(u64, Cid)>> = Lazy::new(|| {
         })
         .collect();
 
-    // we need to add manually init actors for V0.
-    if Type::$actor_type == Type::Init {
-        let init = Cid::new_v1(fvm_ipld_encoding::IPLD_RAW, MultihashCode::Identity.digest(b"fil/1/init"));
-        actors.push((0, init));
+    // we need to add manually init and miner actors for V0, found in the
+    // genesis states.
+    let v0_name = match Type::$actor_type {
+        Type::Init => Some("fil/1/init"),
+        Type::Miner => Some("fil/1/storageminer"),
+        _ => None,
+    };
+    if let Some(name) = v0_name {
+        let code = Cid::new_v1(fvm_ipld_encoding::IPLD_RAW, MultihashCode::Identity.digest(name.as_bytes()));
+        actors.push((0, code));
     }
     actors
 
@@ -21,7 +21,7 @@ use fvm_shared4::event::ActorEvent as ActorEvent_v4;
 use fvm_shared4::event::Entry as Entry_v4;
 use fvm_shared4::event::StampedEvent as StampedEvent_v4;
 use fvm_shared4::receipt::Receipt as Receipt_v4;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug)]
 pub enum ApplyRet {
@@ -304,7 +304,7 @@ impl ActorEvent {
 }
 
 /// Event with extra information stamped by the FVM.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum StampedEvent {
     V3(StampedEvent_v3),
     V4(StampedEvent_v4),
@@ -322,7 +322,63 @@ impl From<StampedEvent_v4> for StampedEvent {
     }
 }
 
+impl crate::utils::cache::EstimateSize for StampedEvent {
+    fn estimated_size(&self) -> usize {
+        let entries_size: usize = match self {
+            Self::V3(v3) => v3
+                .event
+                .entries
+                .iter()
+                .map(|e| std::mem::size_of_val(e) + e.key.len() + e.value.len())
+                .sum(),
+            Self::V4(v4) => v4
+                .event
+                .entries
+                .iter()
+                .map(|e| std::mem::size_of_val(e) + e.key.len() + e.value.len())
+                .sum(),
+        };
+        std::mem::size_of::<Self>() + entries_size
+    }
+}
+
+// The encodings of all the versions are identical.
+impl Serialize for StampedEvent {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::V3(v3) => v3.serialize(serializer),
+            Self::V4(v4) => v4.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for StampedEvent {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        StampedEvent_v4::deserialize(deserializer).map(Self::V4)
+    }
+}
+
 impl StampedEvent {
+    #[cfg(test)]
+    pub fn new(emitter: ActorID, entries: Vec<Entry>) -> Self {
+        let entries = entries
+            .into_iter()
+            .map(|entry| {
+                let (flags, key, codec, value) = entry.into_parts();
+                Entry_v4 {
+                    flags: crate::shim::fvm_shared_latest::event::Flags::from_bits_truncate(flags),
+                    key,
+                    codec,
+                    value,
+                }
+            })
+            .collect();
+        Self::V4(StampedEvent_v4 {
+            emitter,
+            event: ActorEvent_v4 { entries },
+        })
+    }
+
     /// Returns the ID of the actor that emitted this event.
     pub fn emitter(&self) -> ActorID {
         match self {
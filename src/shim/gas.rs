@@ -217,6 +217,26 @@ impl GasOutputs {
     pub fn total_spent(self) -> TokenAmount {
         (self.0.base_fee_burn + self.0.miner_tip + self.0.over_estimation_burn).into()
     }
+
+    pub fn base_fee_burn(&self) -> TokenAmount {
+        self.0.base_fee_burn.clone().into()
+    }
+
+    pub fn over_estimation_burn(&self) -> TokenAmount {
+        self.0.over_estimation_burn.clone().into()
+    }
+
+    pub fn miner_penalty(&self) -> TokenAmount {
+        self.0.miner_penalty.clone().into()
+    }
+
+    pub fn miner_tip(&self) -> TokenAmount {
+        self.0.miner_tip.clone().into()
+    }
+
+    pub fn refund(&self) -> TokenAmount {
+        self.0.refund.clone().into()
+    }
 }
 
 impl From<GasOutputsV4> for GasOutputs {
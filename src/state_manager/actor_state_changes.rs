@@ -0,0 +1,259 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Follows the head changes to report the state changes of a set of actors.
+
+use std::sync::Arc;
+
+use super::StateManager;
+use crate::blocks::Tipset;
+use crate::chain::{index::ResolveNullTipset, HeadChange};
+use crate::shim::{address::Address, clock::ChainEpoch, state_tree::ActorState};
+use fvm_ipld_blockstore::Blockstore;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::warn;
+
+/// Event of [`StateManager::subscribe_to_actor_state_changes`]. The new state
+/// is `None` if the actor does not exist anymore.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActorStateChange {
+    /// The state of the actor changed in the parent state of the tipset at
+    /// `epoch`, extending the chain
+    Applied {
+        epoch: ChainEpoch,
+        addr: Address,
+        new_state: Option<ActorState>,
+    },
+    /// The state of the actor changed as a re-org switched the head to the
+    /// tipset at `epoch`, on another fork
+    Reverted {
+        epoch: ChainEpoch,
+        addr: Address,
+        new_state: Option<ActorState>,
+    },
+}
+
+struct ActorStateWatch<DB> {
+    state_manager: Arc<StateManager<DB>>,
+    addrs: Vec<Address>,
+    /// Head the states were read at
+    head: Arc<Tipset>,
+    states: Vec<Option<ActorState>>,
+}
+
+impl<DB> ActorStateWatch<DB>
+where
+    DB: Blockstore + Send + Sync + 'static,
+{
+    fn actor_states(&self, head: &Tipset) -> anyhow::Result<Vec<Option<ActorState>>> {
+        self.addrs
+            .iter()
+            .map(|addr| self.state_manager.get_actor(addr, *head.parent_state()))
+            .collect()
+    }
+
+    fn on_head(&mut self, head: Arc<Tipset>) -> Vec<ActorStateChange> {
+        let states = match self.actor_states(&head) {
+            Ok(states) => states,
+            Err(e) => {
+                warn!("Failed to read actor states at tipset {}: {e}", head.key());
+                return vec![];
+            }
+        };
+        let extends = head.epoch() > self.head.epoch()
+            && self
+                .state_manager
+                .cs
+                .chain_index
+                .tipset_by_height(
+                    self.head.epoch(),
+                    head.clone(),
+                    ResolveNullTipset::TakeOlder,
+                )
+                .is_ok_and(|ts| ts.key() == self.head.key());
+        let epoch = head.epoch();
+        let changes = self
+            .addrs
+            .iter()
+            .zip(self.states.iter().zip(&states))
+            .filter(|(_, (old, new))| old != new)
+            .map(|(&addr, (_, new_state))| {
+                let new_state = new_state.clone();
+                if extends {
+                    ActorStateChange::Applied {
+                        epoch,
+                        addr,
+                        new_state,
+                    }
+                } else {
+                    ActorStateChange::Reverted {
+                        epoch,
+                        addr,
+                        new_state,
+                    }
+                }
+            })
+            .collect();
+        self.head = head;
+        self.states = states;
+        changes
+    }
+}
+
+impl<DB> StateManager<DB>
+where
+    DB: Blockstore + Send + Sync + 'static,
+{
+    /// Follows the head changes, starting from the current head, and reports
+    /// the changes of the states of `addrs`, as of the parent state of each
+    /// new head. At most `buffer` changes are kept for a lagging receiver.
+    /// Following stops once all the receivers are dropped.
+    pub fn subscribe_to_actor_state_changes(
+        self: &Arc<Self>,
+        addrs: Vec<Address>,
+        buffer: usize,
+    ) -> broadcast::Receiver<ActorStateChange> {
+        let (sender, receiver) = broadcast::channel(buffer.max(1));
+        let mut head_changes = self.cs.publisher().subscribe();
+        let head = self.cs.heaviest_tipset();
+        let mut watch = ActorStateWatch {
+            state_manager: self.clone(),
+            states: vec![None; addrs.len()],
+            addrs,
+            head: head.clone(),
+        };
+        match watch.actor_states(&head) {
+            Ok(states) => watch.states = states,
+            Err(e) => warn!("Failed to read actor states at tipset {}: {e}", head.key()),
+        }
+        tokio::task::spawn(async move {
+            while sender.receiver_count() > 0 {
+                match head_changes.recv().await {
+                    Ok(HeadChange::Apply(head)) => {
+                        for change in watch.on_head(head) {
+                            if sender.send(change).is_err() {
+                                return;
+                            }
+                        }
+                    }
+                    Err(RecvError::Lagged(i)) => {
+                        warn!("actor state head change subscriber lagged, skipped {i} events");
+                    }
+                    Err(RecvError::Closed) => return,
+                }
+            }
+        });
+        receiver
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::{chain4u, CachingBlockHeader, Chain4U, HeaderBuilder};
+    use crate::chain::ChainStore;
+    use crate::chain_sync::{SyncConfig, TipsetValidator};
+    use crate::db::MemoryDB;
+    use crate::networks::{ChainConfig, NetworkChain, ACTOR_BUNDLES_METADATA};
+    use crate::shim::{
+        econ::TokenAmount,
+        machine::BuiltinActor,
+        state_tree::{StateTree, StateTreeVersion},
+    };
+    use cid::Cid;
+
+    fn miner_actor(balance: u64) -> ActorState {
+        let code = ACTOR_BUNDLES_METADATA
+            .get(&(NetworkChain::Calibnet, "v13.0.0".into()))
+            .unwrap()
+            .manifest
+            .get(BuiltinActor::Miner)
+            .unwrap();
+        ActorState::new(
+            code,
+            Cid::default(),
+            TokenAmount::from_atto(balance),
+            0,
+            None,
+        )
+    }
+
+    fn state_root(db: &Arc<MemoryDB>, addr: &Address, actor: ActorState) -> Cid {
+        let mut state_tree = StateTree::new(db.clone(), StateTreeVersion::V5).unwrap();
+        state_tree.set_actor(addr, actor).unwrap();
+        state_tree.flush().unwrap()
+    }
+
+    #[tokio::test]
+    async fn actor_state_changes() {
+        let db = Arc::new(MemoryDB::default());
+        let miner = Address::new_id(1000);
+        let no_messages = TipsetValidator::compute_msg_root_from_cids(&db, vec![], vec![]).unwrap();
+        let before = state_root(&db, &miner, miner_actor(1000));
+        // State after a `WithdrawBalance` of 400 from the miner
+        let after = state_root(&db, &miner, miner_actor(600));
+
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            [genesis = HeaderBuilder::new()
+                .with_epoch(0)
+                .with_messages(no_messages)
+                .with_state_root(before)]
+            -> [b1 = HeaderBuilder::new()
+                .with_epoch(1)
+                .with_messages(no_messages)
+                .with_state_root(after)]
+        };
+        chain4u! {
+            from [genesis] in c4u;
+            [fork = HeaderBuilder::new()
+                .with_epoch(1)
+                .with_messages(no_messages)
+                .with_state_root(before)]
+        };
+        let chain_config = Arc::new(ChainConfig::calibnet());
+        let chain_store = Arc::new(
+            ChainStore::new(
+                db.clone(),
+                db.clone(),
+                db,
+                chain_config.clone(),
+                CachingBlockHeader::new(genesis.clone()),
+            )
+            .unwrap(),
+        );
+        let state_manager = Arc::new(
+            StateManager::new(
+                chain_store.clone(),
+                chain_config,
+                Arc::new(SyncConfig::default()),
+            )
+            .unwrap(),
+        );
+
+        let mut changes = state_manager.subscribe_to_actor_state_changes(vec![miner], 10);
+        for header in [b1, fork] {
+            chain_store
+                .set_heaviest_tipset(Arc::new(Tipset::from(header.clone())))
+                .unwrap();
+        }
+
+        assert_eq!(
+            changes.recv().await.unwrap(),
+            ActorStateChange::Applied {
+                epoch: 1,
+                addr: miner,
+                new_state: Some(miner_actor(600)),
+            }
+        );
+        assert_eq!(
+            changes.recv().await.unwrap(),
+            ActorStateChange::Reverted {
+                epoch: 1,
+                addr: miner,
+                new_state: Some(miner_actor(1000)),
+            }
+        );
+    }
+}
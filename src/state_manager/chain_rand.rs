@@ -9,6 +9,7 @@ use crate::chain::index::{ChainIndex, ResolveNullTipset};
 use crate::networks::ChainConfig;
 use crate::shim::clock::ChainEpoch;
 use crate::shim::externs::Rand;
+use crate::shim::version::NetworkVersion;
 use crate::utils::encoding::blake2b_256;
 use anyhow::{bail, Context as _};
 use blake2b_simd::Params;
@@ -85,11 +86,21 @@ where
         ))
     }
 
+    /// before network version 13; with look-back
+    pub fn get_chain_randomness_v1(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]> {
+        self.get_chain_randomness(round, true)
+    }
+
     /// network version 13 onward
     pub fn get_chain_randomness_v2(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]> {
         self.get_chain_randomness(round, false)
     }
 
+    /// before network version 13; with look-back
+    pub fn get_beacon_randomness_v1(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]> {
+        self.get_beacon_randomness(round, true)
+    }
+
     /// network version 13; without look-back
     pub fn get_beacon_randomness_v2(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]> {
         self.get_beacon_randomness(round, false)
@@ -172,11 +183,22 @@ where
     DB: Blockstore,
 {
     fn get_chain_randomness(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]> {
-        self.get_chain_randomness_v2(round)
+        if self.chain_config.network_version(round) >= NetworkVersion::V13 {
+            self.get_chain_randomness_v2(round)
+        } else {
+            self.get_chain_randomness_v1(round)
+        }
     }
 
     fn get_beacon_randomness(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]> {
-        self.get_beacon_randomness_v3(round)
+        let network_version = self.chain_config.network_version(round);
+        if network_version >= NetworkVersion::V14 {
+            self.get_beacon_randomness_v3(round)
+        } else if network_version == NetworkVersion::V13 {
+            self.get_beacon_randomness_v2(round)
+        } else {
+            self.get_beacon_randomness_v1(round)
+        }
     }
 }
 
@@ -220,3 +242,132 @@ pub fn draw_randomness_from_digest(
 pub fn digest(rbase: &[u8]) -> [u8; 32] {
     blake2b_256(rbase)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fil_actors_shared::v10::runtime::DomainSeparationTag;
+
+    #[test]
+    fn draw_randomness_from_digest_test() {
+        let digest = digest(b"ticket vrf proof");
+        let randomness = draw_randomness_from_digest(
+            &digest,
+            DomainSeparationTag::ElectionProofProduction as i64,
+            2_000_000,
+            b"dead beef",
+        )
+        .unwrap();
+        assert_eq!(
+            hex::encode(randomness),
+            "f19bc713e805b4388a2b126ef97e948c26e26169d8c3bb6773c3c4bc3d44af04"
+        );
+        // Without entropy
+        let randomness = draw_randomness_from_digest(
+            &digest,
+            DomainSeparationTag::SealRandomness as i64,
+            -1,
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            hex::encode(randomness),
+            "b8f3e6ca44080bd41e69b37f3a20cbac9d62d0062c72e98e59700be099d92a72"
+        );
+        // `draw_randomness` digests the base itself
+        assert_eq!(
+            draw_randomness(
+                b"ticket vrf proof",
+                DomainSeparationTag::SealRandomness as i64,
+                -1,
+                &[]
+            )
+            .unwrap(),
+            randomness
+        );
+    }
+
+    #[test]
+    fn randomness_follows_the_network_version() {
+        use crate::beacon::{mock_beacon::MockBeacon, BeaconPoint};
+        use crate::blocks::{chain4u, Chain4U, HeaderBuilder, Ticket, VRFProof};
+        use crate::db::MemoryDB;
+
+        // Each tipset has a ticket of its epoch and the beacon entries of the
+        // given rounds, the mock beacon has a round per epoch.
+        fn block(epoch: ChainEpoch, rounds: &[ChainEpoch]) -> HeaderBuilder {
+            let mut header = HeaderBuilder::new();
+            header
+                .with_epoch(epoch)
+                .with_ticket(Some(Ticket::new(VRFProof::new(
+                    epoch.to_be_bytes().to_vec(),
+                ))))
+                .with_beacon_entries(rounds.iter().map(|&round| entry(round)).collect());
+            header
+        }
+        fn entry(round: ChainEpoch) -> BeaconEntry {
+            BeaconEntry::new(round as u64, round.to_le_bytes().to_vec())
+        }
+
+        // A null round on mainnet under nv12, nv13 and nv14
+        let (nv12, nv13, nv14) = (800_000, 1_000_000, 1_300_000);
+        let chain_config = Arc::new(ChainConfig::mainnet());
+        assert_eq!(chain_config.network_version(nv12), NetworkVersion::V12);
+        assert_eq!(chain_config.network_version(nv13), NetworkVersion::V13);
+        assert_eq!(chain_config.network_version(nv14), NetworkVersion::V14);
+
+        let db = Arc::new(MemoryDB::default());
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            [_genesis = block(0, &[0])]
+            -> [_b1 = block(nv12 - 1, &[nv12 - 1])]
+            -> [_b2 = block(nv12 + 1, &[nv12, nv12 + 1])]
+            -> [_b3 = block(nv13 - 1, &[nv13 - 1])]
+            -> [_b4 = block(nv13 + 1, &[nv13, nv13 + 1])]
+            -> [_b5 = block(nv14 - 1, &[nv14 - 1])]
+            -> head @ [_b6 = block(nv14 + 1, &[nv14, nv14 + 1])]
+        };
+        let rand = ChainRand::new(
+            chain_config,
+            Arc::new(head.clone()),
+            Arc::new(ChainIndex::new(db)),
+            Arc::new(BeaconSchedule(vec![BeaconPoint {
+                height: 0,
+                beacon: Box::<MockBeacon>::default(),
+            }])),
+        );
+        let ticket = |epoch: ChainEpoch| digest(&epoch.to_be_bytes());
+        let beacon = |round: ChainEpoch| digest(entry(round).signature());
+
+        // Before nv13, the tickets and the beacon of the tipset before a null
+        // round are used
+        assert_eq!(
+            Rand::get_chain_randomness(&rand, nv12).unwrap(),
+            ticket(nv12 - 1)
+        );
+        assert_eq!(
+            Rand::get_beacon_randomness(&rand, nv12).unwrap(),
+            beacon(nv12 - 1)
+        );
+        // From nv13, those of the tipset after it
+        assert_eq!(
+            Rand::get_chain_randomness(&rand, nv13).unwrap(),
+            ticket(nv13 + 1)
+        );
+        assert_eq!(
+            Rand::get_beacon_randomness(&rand, nv13).unwrap(),
+            beacon(nv13 + 1)
+        );
+        // From nv14, the beacon entry of the round of the epoch
+        assert_eq!(
+            Rand::get_chain_randomness(&rand, nv14).unwrap(),
+            ticket(nv14 + 1)
+        );
+        assert_eq!(
+            Rand::get_beacon_randomness(&rand, nv14).unwrap(),
+            beacon(nv14)
+        );
+        assert!(Rand::get_chain_randomness(&rand, nv14 + 2).is_err());
+    }
+}
@@ -0,0 +1,289 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Follows the head changes to report when a message lands on chain, or is
+//! reverted by a re-org.
+
+use std::sync::Arc;
+
+use super::{Error, StateManager};
+use crate::blocks::Tipset;
+use crate::chain::{index::ResolveNullTipset, HeadChange};
+use crate::message::ChainMessage;
+use crate::shim::{clock::ChainEpoch, executor::Receipt};
+use cid::Cid;
+use futures::Stream;
+use fvm_ipld_blockstore::Blockstore;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::warn;
+
+/// Event of [`StateManager::stream_message_landing`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum LandingEvent {
+    /// The message was executed, with its receipt in the tipset at `epoch`
+    Landed { epoch: ChainEpoch, receipt: Receipt },
+    /// The message did not land within the timeout
+    Timeout,
+    /// The tipset the message landed in left the heaviest chain
+    Reverted,
+}
+
+struct LandingWatch<DB> {
+    state_manager: Arc<StateManager<DB>>,
+    message: ChainMessage,
+    head_changes: broadcast::Receiver<HeadChange>,
+    /// Tipset to check before waiting for the next head change
+    pending: Option<Arc<Tipset>>,
+    /// Last tipset checked for the message, whose ancestors need not be
+    /// checked again
+    checked: Option<Arc<Tipset>>,
+    /// Tipset holding the receipt of the message, once landed
+    landed: Option<Arc<Tipset>>,
+    timeout_epochs: u64,
+    null_results: u64,
+    done: bool,
+}
+
+impl<DB> LandingWatch<DB>
+where
+    DB: Blockstore + Send + Sync + 'static,
+{
+    async fn next_head(&mut self) -> Option<Arc<Tipset>> {
+        if let Some(tipset) = self.pending.take() {
+            return Some(tipset);
+        }
+        loop {
+            match self.head_changes.recv().await {
+                Ok(HeadChange::Apply(tipset)) => return Some(tipset),
+                Err(RecvError::Lagged(i)) => {
+                    warn!("message landing head change subscriber lagged, skipped {i} events");
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// Returns the tipsets of the chain of `head` that were not checked yet,
+    /// oldest first: those above the last checked tipset, or above the common
+    /// ancestor after a re-org.
+    fn unchecked_tipsets(&self, head: Arc<Tipset>) -> Result<Vec<Arc<Tipset>>, Error> {
+        let Some(mut checked) = self.checked.clone() else {
+            return Ok(vec![head]);
+        };
+        let chain_index = &self.state_manager.cs.chain_index;
+        let mut unchecked = vec![];
+        for ts in chain_index.chain(head) {
+            while checked.epoch() > ts.epoch() {
+                checked = chain_index
+                    .load_required_tipset(checked.parents())
+                    .map_err(Error::other)?;
+            }
+            if ts.key() == checked.key() {
+                break;
+            }
+            unchecked.push(ts);
+        }
+        unchecked.reverse();
+        Ok(unchecked)
+    }
+
+    fn on_head(&mut self, head: Arc<Tipset>) -> Result<Option<LandingEvent>, Error> {
+        if let Some(landed) = self.landed.clone() {
+            let on_chain = head.epoch() >= landed.epoch()
+                && self
+                    .state_manager
+                    .cs
+                    .chain_index
+                    .tipset_by_height(landed.epoch(), head.clone(), ResolveNullTipset::TakeOlder)
+                    .is_ok_and(|ts| ts.key() == landed.key());
+            if !on_chain {
+                // The new chain may include the message again, above the
+                // tipset the receipt was looked up from
+                self.checked = Some(
+                    self.state_manager
+                        .cs
+                        .chain_index
+                        .load_required_tipset(landed.parents())
+                        .map_err(Error::other)?,
+                );
+                self.landed = None;
+                self.null_results = 0;
+                self.pending = Some(head);
+                return Ok(Some(LandingEvent::Reverted));
+            }
+            let finality = self.state_manager.chain_config().policy.chain_finality;
+            if head.epoch() >= landed.epoch() + finality {
+                self.done = true;
+            }
+            return Ok(None);
+        }
+
+        for ts in self.unchecked_tipsets(head.clone())? {
+            self.checked = Some(ts.clone());
+            let receipt = self
+                .state_manager
+                .tipset_executed_message(&ts, &self.message, true)?;
+            if let Some(receipt) = receipt {
+                let epoch = ts.epoch();
+                if ts.key() != head.key() {
+                    // Check the head again for finality or a re-org
+                    self.pending = Some(head);
+                }
+                self.landed = Some(ts);
+                return Ok(Some(LandingEvent::Landed { epoch, receipt }));
+            }
+            self.null_results += 1;
+            if self.null_results >= self.timeout_epochs {
+                self.done = true;
+                return Ok(Some(LandingEvent::Timeout));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<DB> StateManager<DB>
+where
+    DB: Blockstore + Send + Sync + 'static,
+{
+    /// Follows the head changes, starting with the current head, and reports
+    /// when the message lands on chain or is reverted. Every tipset of the
+    /// heaviest chain is checked, including those skipped by a head change
+    /// more than one epoch ahead. The stream ends with
+    /// [`LandingEvent::Timeout`] after `timeout_epochs` tipsets without the
+    /// message, once the tipset it landed in is final, or after an error.
+    pub fn stream_message_landing(
+        self: &Arc<Self>,
+        msg_cid: Cid,
+        timeout_epochs: u64,
+    ) -> Result<impl Stream<Item = Result<LandingEvent, Error>>, Error> {
+        let head_changes = self.cs.publisher().subscribe();
+        let message = crate::chain::get_chain_message(self.blockstore(), &msg_cid)
+            .map_err(|err| Error::Other(format!("failed to load message {err:}")))?;
+        let watch = LandingWatch {
+            state_manager: self.clone(),
+            message,
+            head_changes,
+            pending: Some(self.cs.heaviest_tipset()),
+            checked: None,
+            landed: None,
+            timeout_epochs,
+            null_results: 0,
+            done: false,
+        };
+        Ok(futures::stream::unfold(watch, |mut watch| async move {
+            while !watch.done {
+                let head = watch.next_head().await?;
+                match watch.on_head(head) {
+                    Ok(Some(event)) => return Some((Ok(event), watch)),
+                    Ok(None) => {}
+                    Err(e) => {
+                        watch.done = true;
+                        return Some((Err(e), watch));
+                    }
+                }
+            }
+            None
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::{chain4u, CachingBlockHeader, Chain4U, HeaderBuilder};
+    use crate::chain::ChainStore;
+    use crate::chain_sync::{SyncConfig, TipsetValidator};
+    use crate::db::MemoryDB;
+    use crate::networks::ChainConfig;
+    use crate::shim::{address::Address, econ::TokenAmount, message::Message};
+    use crate::utils::db::CborStoreExt as _;
+    use fil_actors_shared::fvm_ipld_amt::Amtv0 as Amt;
+    use futures::StreamExt as _;
+    use fvm_ipld_encoding::RawBytes;
+    use fvm_shared4::error::ExitCode;
+
+    #[tokio::test]
+    async fn message_landing() {
+        let db = Arc::new(MemoryDB::default());
+        let message = Message {
+            from: Address::new_id(1000),
+            to: Address::new_id(1001),
+            sequence: 3,
+            value: TokenAmount::from_atto(42),
+            ..Default::default()
+        };
+        let msg_cid = db.put_cbor_default(&message).unwrap();
+        let no_messages = TipsetValidator::compute_msg_root_from_cids(&db, vec![], vec![]).unwrap();
+        let messages =
+            TipsetValidator::compute_msg_root_from_cids(&db, vec![msg_cid], vec![]).unwrap();
+        let receipt = fvm_shared4::receipt::Receipt {
+            exit_code: ExitCode::OK,
+            return_data: RawBytes::default(),
+            gas_used: 1234,
+            events_root: None,
+        };
+        let receipts = Amt::new_from_iter(&db, [receipt.clone()]).unwrap();
+
+        // The message is included in `b1`, and its receipt in `b2`
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            [genesis = HeaderBuilder::new().with_epoch(0).with_messages(no_messages)]
+            -> [b1 = HeaderBuilder::new().with_epoch(1).with_messages(messages)]
+            -> [b2 = HeaderBuilder::new()
+                .with_epoch(2)
+                .with_messages(no_messages)
+                .with_message_receipts(receipts)]
+            -> [b3 = HeaderBuilder::new().with_epoch(3).with_messages(no_messages)]
+        };
+        let chain_config = Arc::new(ChainConfig::calibnet());
+        let chain_store = Arc::new(
+            ChainStore::new(
+                db.clone(),
+                db.clone(),
+                db,
+                chain_config.clone(),
+                CachingBlockHeader::new(genesis.clone()),
+            )
+            .unwrap(),
+        );
+        let state_manager = Arc::new(
+            StateManager::new(
+                chain_store.clone(),
+                chain_config,
+                Arc::new(SyncConfig::default()),
+            )
+            .unwrap(),
+        );
+
+        let landing = state_manager.stream_message_landing(msg_cid, 10).unwrap();
+        let timeout = state_manager.stream_message_landing(msg_cid, 2).unwrap();
+        for header in [b1, b2] {
+            chain_store
+                .set_heaviest_tipset(Arc::new(Tipset::from(header.clone())))
+                .unwrap();
+        }
+
+        let landed = LandingEvent::Landed {
+            epoch: 2,
+            receipt: Receipt::V4(receipt),
+        };
+        futures::pin_mut!(landing, timeout);
+        assert_eq!(landing.next().await, Some(Ok(landed.clone())));
+        // Neither the genesis nor `b1` hold the receipt
+        assert_eq!(timeout.next().await, Some(Ok(LandingEvent::Timeout)));
+        assert_eq!(timeout.next().await, None);
+
+        // The head skips over the tipset holding the receipt
+        chain_store
+            .set_heaviest_tipset(Arc::new(Tipset::from(genesis.clone())))
+            .unwrap();
+        let skipped = state_manager.stream_message_landing(msg_cid, 10).unwrap();
+        chain_store
+            .set_heaviest_tipset(Arc::new(Tipset::from(b3.clone())))
+            .unwrap();
+        futures::pin_mut!(skipped);
+        assert_eq!(skipped.next().await, Some(Ok(landed)));
+    }
+}
@@ -1,10 +1,14 @@
 // Copyright 2019-2025 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+mod actor_state_changes;
 pub mod chain_rand;
 pub mod circulating_supply;
 mod errors;
+mod message_landing;
 mod metrics;
+pub mod shadow_execution;
+mod state_tree_cache;
 pub mod utils;
 pub use self::errors::*;
 use self::utils::structured;
@@ -16,46 +20,56 @@ use crate::chain::{
     ChainStore, HeadChange,
 };
 use crate::chain_sync::SyncConfig;
+use crate::cid_collections::CidHashSet;
+use crate::db::{OverlayDB, SettingsStore as _, SettingsStoreExt as _};
 use crate::interpreter::{
     resolve_to_key_addr, ApplyResult, BlockMessages, CalledAt, ExecutionContext, VMEvent,
     IMPLICIT_MESSAGE_GAS_LIMIT, VM,
 };
 use crate::interpreter::{MessageCallbackCtx, VMTrace};
-use crate::lotus_json::{lotus_json_with_self, LotusJson};
-use crate::message::{ChainMessage, Message as MessageTrait};
-use crate::metrics::HistogramTimerExt;
+use crate::lotus_json::{lotus_json, HasLotusJson, LotusJson};
+use crate::message::{ChainMessage, Message as MessageTrait, SignedMessage};
+use crate::metrics::{HistogramTimerExt, KindLabel};
 use crate::networks::ChainConfig;
-use crate::rpc::state::{ApiInvocResult, InvocResult, MessageGasCost};
-use crate::rpc::types::{MiningBaseInfo, SectorOnChainInfo};
+use crate::rpc::state::{ApiInvocResult, ExecutionTrace, InvocResult, MessageGasCost};
+use crate::rpc::types::{
+    CirculatingSupply, MinerSectors, MiningBaseInfo, SectorOnChainInfo, SectorPreCommitOnChainInfo,
+};
 use crate::shim::actors::init::{self, State};
 use crate::shim::actors::miner::{MinerInfo, MinerPower, Partition};
 use crate::shim::actors::verifreg::{Allocation, AllocationID, Claim};
 use crate::shim::actors::*;
 use crate::shim::{
     actors::{
-        miner::ext::MinerStateExt as _, verifreg::ext::VerifiedRegistryStateExt as _,
-        LoadActorStateFromBlockstore,
+        miner::ext::MinerStateExt as _, power::ext::PowerStateExt as _,
+        verifreg::ext::VerifiedRegistryStateExt as _, LoadActorStateFromBlockstore,
     },
     executor::{ApplyRet, Receipt, StampedEvent},
 };
 use crate::shim::{
     address::{Address, Payload, Protocol},
-    clock::ChainEpoch,
+    clock::{ChainEpoch, BLOCKS_PER_EPOCH},
+    deal::DealID,
     econ::TokenAmount,
     message::Message,
     randomness::Randomness,
+    sector::SectorNumber,
     state_tree::{ActorState, StateTree},
     version::NetworkVersion,
 };
 use crate::state_manager::chain_rand::draw_randomness;
 use crate::state_migration::run_state_migrations;
+use crate::utils::bitfield::bitfield_of;
+use crate::utils::cache::{CacheConfig, EstimateSize, SizeTrackingLruCache};
+pub use actor_state_changes::ActorStateChange;
 use ahash::{HashMap, HashMapExt};
 use anyhow::{bail, Context as _};
 use bls_signatures::{PublicKey as BlsPublicKey, Serialize as _};
 use chain_rand::ChainRand;
 use cid::Cid;
 pub use circulating_supply::GenesisInfo;
-use fil_actor_verifreg_state::v12::DataCap;
+use fil_actor_miner_state::v10::qa_power_for_weight;
+use fil_actor_miner_state::v12::{PowerPair, WorkerKeyChange};
 use fil_actor_verifreg_state::v13::ClaimID;
 use fil_actors_shared::fvm_ipld_amt::Amtv0 as Amt;
 use fil_actors_shared::fvm_ipld_bitfield::BitField;
@@ -66,6 +80,7 @@ use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::to_vec;
 use itertools::Itertools as _;
 use lru::LruCache;
+pub use message_landing::LandingEvent;
 use nonzero_ext::nonzero;
 use num::BigInt;
 use num_traits::identities::Zero;
@@ -73,15 +88,116 @@ use parking_lot::Mutex as SyncMutex;
 use rayon::prelude::ParallelBridge;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use state_tree_cache::StateTreeCache;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
 use std::ops::RangeInclusive;
-use std::{num::NonZeroUsize, sync::Arc};
+use std::sync::{Arc, OnceLock};
 use tokio::sync::{broadcast::error::RecvError, Mutex as TokioMutex, RwLock};
 use tracing::{error, info, instrument, trace, warn};
 pub use utils::is_valid_for_sending;
 
-const DEFAULT_TIPSET_CACHE_SIZE: NonZeroUsize = nonzero!(1024usize);
+/// Maximum number of in-flight tipset computations shared between callers.
+/// Beyond it, concurrent callers compute the same tipset independently.
+const MAX_PENDING_TIPSET_COMPUTATIONS: usize = 1024;
+
+/// Prefix of the cached miner termination history keys in the settings store,
+/// followed by the miner ID address.
+const MINER_TERMINATIONS_KEY_PREFIX: &str = "/miner_terminations/";
+
+/// Maximum number of pending messages of the sender applied before the
+/// message passed to [`StateManager::call_with_gas`].
+pub const MAX_PRIOR_MESSAGES: usize = 256;
+
+/// Sectors terminated by a miner with `TerminateSectors` messages included in
+/// the tipset at `epoch`, and the early termination penalty burnt for them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TerminationRecord {
+    pub epoch: ChainEpoch,
+    pub sectors: Vec<SectorNumber>,
+    #[serde(with = "crate::lotus_json")]
+    pub penalty: TokenAmount,
+}
+
+/// Termination history of a miner over a range of finalized epochs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CachedTerminations {
+    from: ChainEpoch,
+    to: ChainEpoch,
+    records: BTreeMap<ChainEpoch, TerminationRecord>,
+}
+
+/// Source of the pending messages of a sender, i.e. the message pool.
+pub trait PendingMessageSource {
+    /// Returns the pending messages sent from the key address.
+    fn pending_messages(&self, from: &Address) -> Vec<SignedMessage>;
+}
+
+/// Messages applied before the message passed to
+/// [`StateManager::call_with_gas`].
+#[derive(Clone, Copy)]
+pub enum PriorMessages<'a> {
+    /// The given messages, in order.
+    Given(&'a [ChainMessage]),
+    /// Up to [`MAX_PRIOR_MESSAGES`] pending messages of the sender, ordered by
+    /// sequence.
+    Pending(&'a (dyn PendingMessageSource + Send + Sync)),
+}
+
+impl CachedTerminations {
+    /// Adds the `records` of `from..=to` to the `cached` history, extending
+    /// its range if they are contiguous, or replacing it otherwise.
+    fn merge(
+        cached: Option<Self>,
+        from: ChainEpoch,
+        to: ChainEpoch,
+        records: BTreeMap<ChainEpoch, TerminationRecord>,
+    ) -> Self {
+        match cached {
+            Some(mut cached) if cached.from <= to + 1 && from <= cached.to + 1 => {
+                cached.from = cached.from.min(from);
+                cached.to = cached.to.max(to);
+                cached.records.extend(records);
+                cached
+            }
+            _ => Self { from, to, records },
+        }
+    }
+}
+
+/// Returns the first `limit` pending messages of `from`, ordered by sequence.
+pub fn pending_prior_messages(
+    source: &(dyn PendingMessageSource + Send + Sync),
+    from: &Address,
+    limit: usize,
+) -> Vec<ChainMessage> {
+    source
+        .pending_messages(from)
+        .into_iter()
+        .sorted_by_key(|msg| msg.sequence())
+        .take(limit)
+        .map(ChainMessage::Signed)
+        .collect()
+}
 
-const DEFAULT_EVENT_CACHE_SIZE: NonZeroUsize = nonzero!(4096usize);
+/// Returns the funds sent by `miner` to the burnt funds actor in the execution
+/// trace, e.g. the penalties of its early terminated sectors.
+fn funds_burnt_by(trace: &ExecutionTrace, miner: &Address) -> TokenAmount {
+    let burnt = if trace.msg.from == *miner
+        && trace.msg.to == Address::BURNT_FUNDS_ACTOR
+        && trace.msg_rct.exit_code.is_success()
+    {
+        trace.msg.value.clone()
+    } else {
+        TokenAmount::zero()
+    };
+    trace.subcalls.iter().fold(burnt, |burnt, subcall| {
+        burnt + funds_burnt_by(subcall, miner)
+    })
+}
 
 /// Intermediary for retrieving state objects and updating actor states.
 type CidPair = (Cid, Cid);
@@ -118,38 +234,44 @@ impl From<StateOutput> for StateOutputValue {
     }
 }
 
+impl EstimateSize for StateOutputValue {
+    fn estimated_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+    }
+}
+
 #[derive(Clone)]
 pub struct StateEvents {
     pub events: Vec<Vec<StampedEvent>>,
 }
 
+impl EstimateSize for StateEvents {
+    fn estimated_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self
+                .events
+                .iter()
+                .map(|events| {
+                    std::mem::size_of_val(events)
+                        + events
+                            .iter()
+                            .map(EstimateSize::estimated_size)
+                            .sum::<usize>()
+                })
+                .sum::<usize>()
+    }
+}
+
 // Various structures for implementing the tipset state cache
 
 struct TipsetStateCacheInner<V> {
-    values: LruCache<TipsetKey, V>,
+    values: SizeTrackingLruCache<TipsetKey, V>,
     pending: Vec<(TipsetKey, Arc<TokioMutex<()>>)>,
 }
 
-impl<V: Clone> Default for TipsetStateCacheInner<V> {
-    fn default() -> Self {
-        Self {
-            values: LruCache::new(DEFAULT_TIPSET_CACHE_SIZE),
-            pending: Vec::with_capacity(8),
-        }
-    }
-}
-
-impl<V: Clone> TipsetStateCacheInner<V> {
-    pub fn with_size(cache_size: NonZeroUsize) -> Self {
-        Self {
-            values: LruCache::new(cache_size),
-            pending: Vec::with_capacity(8),
-        }
-    }
-}
-
 struct TipsetStateCache<V> {
     cache: Arc<SyncMutex<TipsetStateCacheInner<V>>>,
+    kind: KindLabel,
 }
 
 enum Status<V> {
@@ -157,16 +279,14 @@ enum Status<V> {
     Empty(Arc<TokioMutex<()>>),
 }
 
-impl<V: Clone> TipsetStateCache<V> {
-    pub fn new() -> Self {
-        Self {
-            cache: Arc::new(SyncMutex::new(TipsetStateCacheInner::default())),
-        }
-    }
-
-    pub fn with_size(cache_size: NonZeroUsize) -> Self {
+impl<V: Clone + EstimateSize> TipsetStateCache<V> {
+    pub fn new(kind: KindLabel, budget_bytes: usize) -> Self {
         Self {
-            cache: Arc::new(SyncMutex::new(TipsetStateCacheInner::with_size(cache_size))),
+            cache: Arc::new(SyncMutex::new(TipsetStateCacheInner {
+                values: SizeTrackingLruCache::new(kind.kind(), budget_bytes),
+                pending: Vec::with_capacity(8),
+            })),
+            kind,
         }
     }
 
@@ -195,7 +315,16 @@ impl<V: Clone> TipsetStateCache<V> {
                     Some(mutex) => Status::Empty(mutex.clone()),
                     None => {
                         let mutex = Arc::new(TokioMutex::new(()));
-                        inner.pending.push((key.clone(), mutex.clone()));
+                        if inner.pending.len() >= MAX_PENDING_TIPSET_COMPUTATIONS {
+                            // Forget the computations nobody is waiting for anymore,
+                            // e.g. because their callers were cancelled.
+                            inner
+                                .pending
+                                .retain(|(_, mutex)| Arc::strong_count(mutex) > 1);
+                        }
+                        if inner.pending.len() < MAX_PENDING_TIPSET_COMPUTATIONS {
+                            inner.pending.push((key.clone(), mutex.clone()));
+                        }
                         Status::Empty(mutex)
                     }
                 }
@@ -204,7 +333,7 @@ impl<V: Clone> TipsetStateCache<V> {
         match status {
             Status::Done(x) => {
                 crate::metrics::LRU_CACHE_HIT
-                    .get_or_create(&crate::metrics::values::STATE_MANAGER_TIPSET)
+                    .get_or_create(&self.kind)
                     .inc();
                 Ok(x)
             }
@@ -214,7 +343,7 @@ impl<V: Clone> TipsetStateCache<V> {
                     Some(v) => {
                         // While locking someone else computed the pending task
                         crate::metrics::LRU_CACHE_HIT
-                            .get_or_create(&crate::metrics::values::STATE_MANAGER_TIPSET)
+                            .get_or_create(&self.kind)
                             .inc();
 
                         Ok(v)
@@ -222,10 +351,17 @@ impl<V: Clone> TipsetStateCache<V> {
                     None => {
                         // Entry does not have state computed yet, compute value and fill the cache
                         crate::metrics::LRU_CACHE_MISS
-                            .get_or_create(&crate::metrics::values::STATE_MANAGER_TIPSET)
+                            .get_or_create(&self.kind)
                             .inc();
 
-                        let value = compute().await?;
+                        let value = match compute().await {
+                            Ok(value) => value,
+                            Err(e) => {
+                                // Let the next caller retry the computation
+                                self.remove_pending(key);
+                                return Err(e);
+                            }
+                        };
 
                         // Write back to cache, release lock and return value
                         self.insert(key.clone(), value.clone());
@@ -240,6 +376,10 @@ impl<V: Clone> TipsetStateCache<V> {
         self.with_inner(|inner| inner.values.get(key).cloned())
     }
 
+    fn remove_pending(&self, key: &TipsetKey) {
+        self.with_inner(|inner| inner.pending.retain(|(k, _)| k != key));
+    }
+
     fn insert(&self, key: TipsetKey, value: V) {
         self.with_inner(|inner| {
             inner.pending.retain(|(k, _)| k != &key);
@@ -248,18 +388,701 @@ impl<V: Clone> TipsetStateCache<V> {
     }
 }
 
-/// External format for returning market balance from state.
-#[derive(Default, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord, JsonSchema)]
-#[serde(rename_all = "PascalCase")]
-pub struct MarketBalance {
-    #[schemars(with = "LotusJson<TokenAmount>")]
-    #[serde(with = "crate::lotus_json")]
+lotus_json! {
+    /// External format for returning market balance from state.
+    #[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
+    #[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
+    pub struct MarketBalance {
+        pub escrow: TokenAmount,
+        pub locked: TokenAmount,
+    }
+    snapshots {
+        vec![(
+            serde_json::json!({
+                "Escrow": "2000000000000000000",
+                "Locked": "0",
+            }),
+            MarketBalance {
+                escrow: TokenAmount::from_whole(2),
+                locked: TokenAmount::default(),
+            },
+        )]
+    }
+}
+
+/// Storage market balances of a participant, see
+/// [`StateManager::get_market_participant_info`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MarketParticipantInfo {
+    /// Funds deposited in the market escrow table.
     pub escrow: TokenAmount,
-    #[schemars(with = "LotusJson<TokenAmount>")]
-    #[serde(with = "crate::lotus_json")]
+    /// Part of the escrow locked as deal collateral and storage fees.
     pub locked: TokenAmount,
+    /// Part of the escrow that can be withdrawn.
+    pub available_to_withdraw: TokenAmount,
+}
+
+impl MarketParticipantInfo {
+    pub fn new(escrow: TokenAmount, locked: TokenAmount) -> Self {
+        let available_to_withdraw = if escrow > locked {
+            &escrow - &locked
+        } else {
+            TokenAmount::default()
+        };
+        Self {
+            escrow,
+            locked,
+            available_to_withdraw,
+        }
+    }
+}
+
+impl From<MarketParticipantInfo> for MarketBalance {
+    fn from(info: MarketParticipantInfo) -> Self {
+        Self {
+            escrow: info.escrow,
+            locked: info.locked,
+        }
+    }
+}
+
+/// Libp2p connectivity of a miner, see [`StateManager::get_miner_peer_info`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinerPeerInfo {
+    /// Encoded libp2p peer ID, empty if not set by the miner.
+    pub peer_id: Vec<u8>,
+    /// Encoded multiaddresses the miner can be reached at.
+    pub multiaddrs: Vec<Vec<u8>>,
+    /// Key address of the worker.
+    pub worker_key: Address,
+}
+
+lotus_json! {
+    /// Collateral deposited for a pending sector pre-commit, see
+    /// [`StateManager::get_miner_pre_commit_deposits`].
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
+    pub struct PreCommitDepositInfo {
+        pub sector_number: SectorNumber,
+        pub deposit: TokenAmount,
+        /// Last epoch at which the sector can be proven before the deposit is lost
+        pub expiry_epoch: ChainEpoch,
+    }
+    snapshots {
+        vec![(
+            serde_json::json!({
+                "SectorNumber": 7,
+                "Deposit": "1000",
+                "ExpiryEpoch": 2000,
+            }),
+            PreCommitDepositInfo {
+                sector_number: 7,
+                deposit: TokenAmount::from_atto(1000),
+                expiry_epoch: 2000,
+            },
+        )]
+    }
+}
+
+impl PreCommitDepositInfo {
+    pub fn new(policy: &Policy, precommit: SectorPreCommitOnChainInfo) -> anyhow::Result<Self> {
+        let max_prove_commit_duration = fil_actor_miner_state::v13::max_prove_commit_duration(
+            policy,
+            *precommit.info.seal_proof,
+        )
+        .with_context(|| {
+            format!(
+                "unsupported seal proof type {:?} for sector {}",
+                precommit.info.seal_proof, precommit.info.sector_number
+            )
+        })?;
+        Ok(Self {
+            sector_number: precommit.info.sector_number,
+            deposit: precommit.pre_commit_deposit,
+            expiry_epoch: precommit.pre_commit_epoch + max_prove_commit_duration,
+        })
+    }
+}
+
+lotus_json! {
+    /// Forecast of a miner's fee debt if its faulty sectors stay faulty, see
+    /// [`StateManager::get_miner_fee_debt_projection`].
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
+    pub struct FeeDebtProjection {
+        pub current_debt: TokenAmount,
+        /// Fee debt at the end of the projection window
+        pub projected_debt_at_epoch: TokenAmount,
+        /// Balance not locked for vesting, pre-commit deposits or initial pledge
+        pub unlocked_balance: TokenAmount,
+        /// First epoch at which the debt exceeds the unlocked balance plus the
+        /// funds vested so far, if within the projection window
+        pub insolvency_epoch: Option<ChainEpoch>,
+    }
+    snapshots {
+        vec![
+            (
+                serde_json::json!({
+                    "CurrentDebt": "0",
+                    "ProjectedDebtAtEpoch": "0",
+                    "UnlockedBalance": "1000",
+                    "InsolvencyEpoch": null,
+                }),
+                FeeDebtProjection {
+                    current_debt: TokenAmount::default(),
+                    projected_debt_at_epoch: TokenAmount::default(),
+                    unlocked_balance: TokenAmount::from_atto(1000),
+                    insolvency_epoch: None,
+                },
+            ),
+            (
+                serde_json::json!({
+                    "CurrentDebt": "10",
+                    "ProjectedDebtAtEpoch": "2000",
+                    "UnlockedBalance": "1000",
+                    "InsolvencyEpoch": 1600,
+                }),
+                FeeDebtProjection {
+                    current_debt: TokenAmount::from_atto(10),
+                    projected_debt_at_epoch: TokenAmount::from_atto(2000),
+                    unlocked_balance: TokenAmount::from_atto(1000),
+                    insolvency_epoch: Some(1600),
+                },
+            ),
+        ]
+    }
+}
+
+lotus_json! {
+    /// Projected block reward of a sector, see
+    /// [`StateManager::compute_sector_reward_estimate`].
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
+    pub struct SectorRewardEstimate {
+        /// Daily reward recorded for the sector when it was activated
+        pub expected_daily_reward: TokenAmount,
+        /// Reward expected over the projection window at the current network
+        /// reward and power estimates
+        pub total_projected_reward: TokenAmount,
+        /// Storage pledge recorded for the sector when it was activated
+        pub expected_pledge: TokenAmount,
+    }
+    snapshots {
+        vec![(
+            serde_json::json!({
+                "ExpectedDailyReward": "100",
+                "TotalProjectedReward": "2000",
+                "ExpectedPledge": "300",
+            }),
+            SectorRewardEstimate {
+                expected_daily_reward: TokenAmount::from_atto(100),
+                total_projected_reward: TokenAmount::from_atto(2000),
+                expected_pledge: TokenAmount::from_atto(300),
+            },
+        )]
+    }
+}
+
+lotus_json! {
+    /// Penalties a miner would pay for a sector, see
+    /// [`StateManager::miner_sector_penalties`].
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
+    pub struct SectorPenalty {
+        pub sector_number: SectorNumber,
+        /// Fee charged each proving period, a day, while the sector is faulty
+        pub daily_fault_fee: TokenAmount,
+        /// Penalty charged for terminating the sector now
+        pub termination_penalty: TokenAmount,
+    }
+    snapshots {
+        vec![(
+            serde_json::json!({
+                "SectorNumber": 7,
+                "DailyFaultFee": "100",
+                "TerminationPenalty": "5000",
+            }),
+            SectorPenalty {
+                sector_number: 7,
+                daily_fault_fee: TokenAmount::from_atto(100),
+                termination_penalty: TokenAmount::from_atto(5000),
+            },
+        )]
+    }
+}
+
+lotus_json! {
+    /// Penalties of a set of sectors of a miner, see
+    /// [`StateManager::miner_sector_penalties`].
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
+    pub struct SectorPenalties {
+        pub sectors: Vec<SectorPenalty>,
+        pub total_daily_fault_fee: TokenAmount,
+        pub total_termination_penalty: TokenAmount,
+    }
+    snapshots {
+        vec![(
+            serde_json::json!({
+                "Sectors": [{
+                    "SectorNumber": 7,
+                    "DailyFaultFee": "100",
+                    "TerminationPenalty": "5000",
+                }],
+                "TotalDailyFaultFee": "100",
+                "TotalTerminationPenalty": "5000",
+            }),
+            SectorPenalties {
+                sectors: vec![SectorPenalty {
+                    sector_number: 7,
+                    daily_fault_fee: TokenAmount::from_atto(100),
+                    termination_penalty: TokenAmount::from_atto(5000),
+                }],
+                total_daily_fault_fee: TokenAmount::from_atto(100),
+                total_termination_penalty: TokenAmount::from_atto(5000),
+            },
+        )]
+    }
+}
+
+impl SectorPenalty {
+    /// Computes the penalties of `sector` at `current_epoch` with the formulas
+    /// of the miner actor, from the smoothed network reward of `reward_state`
+    /// and the smoothed network power `network_qa_power`.
+    fn new(
+        reward_state: &reward::State,
+        network_qa_power: &FilterEstimate,
+        sector: &miner::SectorOnChainInfo,
+        current_epoch: ChainEpoch,
+    ) -> anyhow::Result<Self> {
+        let sector_size = sector
+            .seal_proof
+            .sector_size()
+            .map_err(|e| anyhow::anyhow!("failed to get sector size: {e}"))?;
+        let qa_power = miner::qa_power_for_sector(sector_size, sector);
+        let daily_fault_fee = reward_state
+            .pledge_penalty_for_continued_fault(network_qa_power.clone(), &qa_power)?
+            .into();
+        let termination_penalty = reward_state
+            .pledge_penalty_for_termination(
+                network_qa_power.clone(),
+                sector,
+                &qa_power,
+                current_epoch,
+            )?
+            .into();
+        Ok(Self {
+            sector_number: sector.sector_number,
+            daily_fault_fee,
+            termination_penalty,
+        })
+    }
+}
+
+lotus_json! {
+    /// Funds required to upgrade a committed capacity sector with deals, see
+    /// [`StateManager::get_sector_upgrade_cost`].
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
+    pub struct UpgradeCost {
+        /// Pledge locked on top of the initial pledge of the sector
+        pub additional_pledge: TokenAmount,
+        /// Network fee burnt for the proof of the update
+        pub aggregate_fee: TokenAmount,
+        /// Whether the available balance of the miner covers both
+        pub has_sufficient_funds: bool,
+    }
+    snapshots {
+        vec![(
+            serde_json::json!({
+                "AdditionalPledge": "3000",
+                "AggregateFee": "200",
+                "HasSufficientFunds": true,
+            }),
+            UpgradeCost {
+                additional_pledge: TokenAmount::from_atto(3000),
+                aggregate_fee: TokenAmount::from_atto(200),
+                has_sufficient_funds: true,
+            },
+        )]
+    }
+}
+
+lotus_json! {
+    /// Power of a single sector, see
+    /// [`StateManager::get_sector_quality_adj_power`].
+    #[derive(Debug, Default, Clone, PartialEq, Eq)]
+    #[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
+    pub struct SectorQualityAdjPower {
+        /// Size of the sector
+        pub raw_byte_power: BigInt,
+        pub quality_adj_power: BigInt,
+    }
+    snapshots {
+        vec![(
+            serde_json::json!({
+                "RawBytePower": "34359738368",
+                "QualityAdjPower": "343597383680",
+            }),
+            SectorQualityAdjPower {
+                raw_byte_power: BigInt::from(34359738368_u64),
+                quality_adj_power: BigInt::from(343597383680_u64),
+            },
+        )]
+    }
+}
+
+lotus_json! {
+    /// Blocks produced by a miner over a range of epochs, see
+    /// [`StateManager::get_block_producer_stats`].
+    #[derive(Debug, Default, Clone, PartialEq, Eq)]
+    #[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
+    pub struct BlockProducerStats {
+        pub blocks_won: u64,
+        /// Sum of the win counts of the election proofs of the blocks
+        pub win_count_total: i64,
+        /// Average block reward, excluding gas rewards
+        pub avg_epoch_reward: TokenAmount,
+    }
+    snapshots {
+        vec![(
+            serde_json::json!({
+                "BlocksWon": 3,
+                "WinCountTotal": 4,
+                "AvgEpochReward": "1000",
+            }),
+            BlockProducerStats {
+                blocks_won: 3,
+                win_count_total: 4,
+                avg_epoch_reward: TokenAmount::from_atto(1000),
+            },
+        )]
+    }
+}
+
+lotus_json! {
+    /// Funds locked in the vesting schedules of the miners, see
+    /// [`StateManager::get_token_vesting_stats`].
+    #[derive(Debug, Default, Clone, PartialEq, Eq)]
+    #[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
+    pub struct VestingStats {
+        pub total_vesting: TokenAmount,
+        /// Funds vesting at each epoch
+        pub vesting_by_bucket: BTreeMap<ChainEpoch, TokenAmount>,
+    }
+    snapshots {
+        vec![(
+            serde_json::json!({
+                "TotalVesting": "30",
+                "VestingByBucket": {"100": "10", "200": "20"},
+            }),
+            VestingStats {
+                total_vesting: TokenAmount::from_atto(30),
+                vesting_by_bucket: BTreeMap::from([
+                    (100, TokenAmount::from_atto(10)),
+                    (200, TokenAmount::from_atto(20)),
+                ]),
+            },
+        )]
+    }
+}
+
+lotus_json! {
+    /// A FIL+ claim on data committed in a sector, see
+    /// [`StateManager::get_sector_active_claims`].
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct ClaimInfo {
+        pub claim_id: ClaimID,
+        pub claim: Claim,
+    }
+    snapshots {
+        vec![(
+            serde_json::json!({
+                "ClaimId": 1,
+                "Claim": {
+                    "Provider": 1000,
+                    "Client": 1001,
+                    "Data": {"/": "baeaaaaa"},
+                    "Size": 2048,
+                    "TermMin": 518400,
+                    "TermMax": 5256000,
+                    "TermStart": 100,
+                    "Sector": 7,
+                },
+            }),
+            ClaimInfo {
+                claim_id: 1,
+                claim: Claim {
+                    provider: 1000,
+                    client: 1001,
+                    data: Cid::default(),
+                    size: fvm_shared4::piece::PaddedPieceSize(2048),
+                    term_min: 518400,
+                    term_max: 5256000,
+                    term_start: 100,
+                    sector: 7,
+                },
+            },
+        )]
+    }
+}
+
+lotus_json! {
+    /// The ID form of an address along with its robust form, see
+    /// [`MinerControlInfo`].
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
+    pub struct AddressForms {
+        pub id: Address,
+        /// The key or delegated address of the actor, `None` for actors
+        /// without one, e.g. multisigs
+        pub robust: Option<Address>,
+    }
+    snapshots {
+        vec![(
+            serde_json::json!({
+                "Id": "f01001",
+                "Robust": "f410fpoidg73f7krlfohnla52dotowde5p2sejxnd4mq",
+            }),
+            AddressForms {
+                id: Address::new_id(1001),
+                robust: Some("f410fpoidg73f7krlfohnla52dotowde5p2sejxnd4mq".parse().unwrap()),
+            },
+        )]
+    }
+}
+
+lotus_json! {
+    /// The addresses controlling a miner, see
+    /// [`StateManager::get_miner_control_address_info`].
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct MinerControlInfo {
+        pub owner: AddressForms,
+        pub worker: AddressForms,
+        pub control_addresses: Vec<AddressForms>,
+        /// Worker key change staged with `ChangeWorkerAddress`
+        pub pending_worker: Option<WorkerKeyChange>,
+        /// Owner proposed with `ChangeOwnerAddress`
+        pub pending_owner: Option<AddressForms>,
+    }
+    snapshots {
+        vec![(
+            serde_json::json!({
+                "Owner": {"Id": "f01001", "Robust": null},
+                "Worker": {"Id": "f01002", "Robust": null},
+                "ControlAddresses": [{"Id": "f01003", "Robust": null}],
+                "PendingWorker": {"NewWorker": "f01004", "EffectiveAt": 2000},
+                "PendingOwner": null,
+            }),
+            MinerControlInfo {
+                owner: AddressForms {
+                    id: Address::new_id(1001),
+                    robust: None,
+                },
+                worker: AddressForms {
+                    id: Address::new_id(1002),
+                    robust: None,
+                },
+                control_addresses: vec![AddressForms {
+                    id: Address::new_id(1003),
+                    robust: None,
+                }],
+                pending_worker: Some(WorkerKeyChange {
+                    new_worker: Address::new_id(1004).into(),
+                    effective_at: 2000,
+                }),
+                pending_owner: None,
+            },
+        )]
+    }
+}
+
+impl FeeDebtProjection {
+    /// Accrues `fee_per_window` every `window` epochs from `start` until
+    /// `start + future_epochs`, crediting the vesting entries as they unlock.
+    fn project(
+        current_debt: TokenAmount,
+        unlocked_balance: TokenAmount,
+        fee_per_window: &TokenAmount,
+        vesting: &[(ChainEpoch, TokenAmount)],
+        start: ChainEpoch,
+        window: ChainEpoch,
+        future_epochs: ChainEpoch,
+    ) -> Self {
+        let mut debt = current_debt.clone();
+        let mut vested = TokenAmount::zero();
+        let mut insolvency_epoch = None;
+        let mut vesting = vesting
+            .iter()
+            .skip_while(|(epoch, _)| *epoch <= start)
+            .peekable();
+        let end = start.saturating_add(future_epochs.max(0));
+        let mut epoch = start;
+        while epoch <= end {
+            while let Some((_, amount)) = vesting.next_if(|(vest_epoch, _)| *vest_epoch <= epoch) {
+                vested += amount.clone();
+            }
+            if insolvency_epoch.is_none() && debt > &unlocked_balance + &vested {
+                insolvency_epoch = Some(epoch);
+            }
+            epoch += window.max(1);
+            if epoch <= end {
+                debt += fee_per_window.clone();
+            }
+        }
+        Self {
+            current_debt,
+            projected_debt_at_epoch: debt,
+            unlocked_balance,
+            insolvency_epoch,
+        }
+    }
+}
+
+/// Standing of a miner in consensus at a tipset, see
+/// [`StateManager::miner_consensus_status`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "Status")]
+pub enum MinerConsensusStatus {
+    /// The power actor holds no claim for the miner, either because it never
+    /// registered power or because the claim was removed when it was slashed.
+    NoClaim,
+    /// The miner is excluded from consensus after a consensus fault, up to
+    /// and including the `elapsed` epoch.
+    ConsensusFault {
+        #[serde(rename = "Elapsed")]
+        elapsed: ChainEpoch,
+    },
+    /// The miner's nominal power is below the consensus minimum.
+    BelowMinimumPower {
+        #[schemars(with = "LotusJson<BigInt>")]
+        #[serde(rename = "QualityAdjPower", with = "crate::lotus_json")]
+        quality_adj_power: BigInt,
+    },
+    /// The miner's nominal power meets the consensus minimum.
+    Active {
+        #[schemars(with = "LotusJson<BigInt>")]
+        #[serde(rename = "QualityAdjPower", with = "crate::lotus_json")]
+        quality_adj_power: BigInt,
+    },
+}
+
+impl HasLotusJson for MinerConsensusStatus {
+    type LotusJson = Self;
+
+    #[cfg(test)]
+    fn snapshots() -> Vec<(serde_json::Value, Self)> {
+        vec![
+            (
+                serde_json::json!({"Status": "NoClaim"}),
+                MinerConsensusStatus::NoClaim,
+            ),
+            (
+                serde_json::json!({"Status": "ConsensusFault", "Elapsed": 1000}),
+                MinerConsensusStatus::ConsensusFault { elapsed: 1000 },
+            ),
+            (
+                serde_json::json!({"Status": "BelowMinimumPower", "QualityAdjPower": "2048"}),
+                MinerConsensusStatus::BelowMinimumPower {
+                    quality_adj_power: BigInt::from(2048),
+                },
+            ),
+            (
+                serde_json::json!({"Status": "Active", "QualityAdjPower": "10995116277760"}),
+                MinerConsensusStatus::Active {
+                    quality_adj_power: BigInt::from(10995116277760_u64),
+                },
+            ),
+        ]
+    }
+
+    fn into_lotus_json(self) -> Self::LotusJson {
+        self
+    }
+
+    fn from_lotus_json(lotus_json: Self::LotusJson) -> Self {
+        lotus_json
+    }
+}
+
+/// Details of the genesis state, see [`StateManager::get_genesis_info`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenesisStateInfo {
+    /// Timestamp of the genesis block
+    pub timestamp: u64,
+    /// Sum of the balances of the genesis actors
+    pub initial_supply: TokenAmount,
+    /// ID addresses of the genesis miners, in ascending order
+    pub genesis_miners: Vec<Address>,
+    /// Genesis actors by ID address
+    pub genesis_actors: HashMap<Address, ActorState>,
+}
+
+/// Actor states that replace the ones in the state tree when simulating
+/// messages, see [`StateManager::simulate_message_batch`].
+pub type StateOverride = HashMap<Address, ActorState>;
+
+/// Writes the state tree at `state_root` with the actors in `state_override`
+/// replaced to `store`, and returns its root.
+fn apply_state_override<S: Blockstore>(
+    store: &Arc<S>,
+    state_root: Cid,
+    state_override: StateOverride,
+) -> Result<Cid, Error> {
+    if state_override.is_empty() {
+        return Ok(state_root);
+    }
+    let mut state_tree = StateTree::new_from_root(Arc::clone(store), &state_root)?;
+    for (address, actor) in state_override {
+        state_tree
+            .set_actor(&address, actor)
+            .with_context(|| format!("Could not override the state of {address}"))?;
+    }
+    Ok(state_tree.flush()?)
+}
+
+const BEACON_ENTRIES_CACHE_SIZE: NonZeroUsize = nonzero!(4096usize);
+
+/// Beacon entries by epoch, parent epoch and round of the previous entry, the
+/// inputs of [`BeaconSchedule::beacon_entries_for_block`] besides the network
+/// version, which follows from the epoch.
+type BeaconEntriesCache = SyncMutex<LruCache<(ChainEpoch, ChainEpoch, u64), Vec<BeaconEntry>>>;
+
+const MINER_SECTOR_COUNT_CACHE_SIZE: NonZeroUsize = nonzero!(2048usize);
+
+/// Sector counts by miner and tipset whose resulting state they were read
+/// from, see [`StateManager::get_miner_sector_count_at_epoch`].
+type MinerSectorCountCache = SyncMutex<LruCache<(Address, TipsetKey), MinerSectors>>;
+
+const BASELINE_POWER_CACHE_SIZE: NonZeroUsize = nonzero!(4096usize);
+
+/// Network baseline power by the state root it was read from, see
+/// [`StateManager::get_network_baseline_power_history`].
+type BaselinePowerCache = SyncMutex<LruCache<Cid, BigInt>>;
+
+async fn beacon_entries_for_epoch(
+    beacon: &BeaconSchedule,
+    cache: &BeaconEntriesCache,
+    network_version: NetworkVersion,
+    epoch: ChainEpoch,
+    parent_epoch: ChainEpoch,
+    prev_entry: &BeaconEntry,
+) -> Result<Vec<BeaconEntry>, Error> {
+    let key = (epoch, parent_epoch, prev_entry.round());
+    if let Some(entries) = cache.lock().get(&key) {
+        return Ok(entries.clone());
+    }
+    let entries = beacon
+        .beacon_entries_for_block(network_version, epoch, parent_epoch, prev_entry)
+        .await
+        .map_err(|e| {
+            Error::Other(format!(
+                "failed to get the beacon entries at epoch {epoch}: {e}"
+            ))
+        })?;
+    cache.lock().put(key, entries.clone());
+    Ok(entries)
 }
-lotus_json_with_self!(MarketBalance);
 
 /// State manager handles all interactions with the internal Filecoin actors
 /// state. This encapsulates the [`ChainStore`] functionality, which only
@@ -273,12 +1096,25 @@ pub struct StateManager<DB> {
     cache: TipsetStateCache<StateOutputValue>,
     /// This is a cache dedicated to tipset events.
     events_cache: TipsetStateCache<StateEvents>,
+    /// Actors at the state of the heaviest tipset.
+    state_tree_cache: StateTreeCache,
     // Beacon can be cheaply crated from the `chain_config`. The only reason we
     // store it here is because it has a look-up cache.
     beacon: Arc<crate::beacon::BeaconSchedule>,
+    beacon_entries_cache: BeaconEntriesCache,
+    miner_sector_count_cache: MinerSectorCountCache,
+    baseline_power_cache: BaselinePowerCache,
     chain_config: Arc<ChainConfig>,
     sync_config: Arc<SyncConfig>,
     engine: crate::shim::machine::MultiEngine,
+    /// Separate engine executing every tipset a second time, in shadow
+    /// execution mode.
+    shadow_execution: Option<shadow_execution::ShadowExecution>,
+    /// Most recent state root available in lite mode, where no new states are
+    /// computed, with the epoch of the tipset it is the parent state of.
+    lite_state_anchor: SyncMutex<Option<(ChainEpoch, Cid)>>,
+    /// Details of the genesis state, which never changes.
+    genesis_info: OnceLock<Arc<GenesisStateInfo>>,
 }
 
 #[allow(clippy::type_complexity)]
@@ -298,15 +1134,41 @@ where
 
         Ok(Self {
             cs,
-            cache: TipsetStateCache::new(),
-            events_cache: TipsetStateCache::with_size(DEFAULT_EVENT_CACHE_SIZE),
+            cache: TipsetStateCache::new(
+                crate::metrics::values::STATE_MANAGER_TIPSET,
+                CacheConfig::global().tipset_state_bytes,
+            ),
+            events_cache: TipsetStateCache::new(
+                crate::metrics::values::STATE_MANAGER_EVENTS,
+                CacheConfig::global().tipset_events_bytes,
+            ),
+            state_tree_cache: StateTreeCache::new(),
             beacon,
+            beacon_entries_cache: SyncMutex::new(LruCache::new(BEACON_ENTRIES_CACHE_SIZE)),
+            miner_sector_count_cache: SyncMutex::new(LruCache::new(MINER_SECTOR_COUNT_CACHE_SIZE)),
+            baseline_power_cache: SyncMutex::new(LruCache::new(BASELINE_POWER_CACHE_SIZE)),
             chain_config,
             sync_config,
             engine: crate::shim::machine::MultiEngine::default(),
+            shadow_execution: None,
+            lite_state_anchor: SyncMutex::new(None),
+            genesis_info: OnceLock::new(),
         })
     }
 
+    /// Enables the shadow execution of every computed tipset if configured,
+    /// see [`shadow_execution`]. A divergence is reported on `shutdown`.
+    pub fn with_shadow_execution(
+        mut self,
+        config: &shadow_execution::DiagnosticsConfig,
+        shutdown: tokio::sync::mpsc::Sender<()>,
+    ) -> Self {
+        self.shadow_execution = config
+            .reference_engine()
+            .map(|engine| shadow_execution::ShadowExecution { engine, shutdown });
+        self
+    }
+
     pub fn beacon_schedule(&self) -> &Arc<BeaconSchedule> {
         &self.beacon
     }
@@ -362,6 +1224,62 @@ where
         })
     }
 
+    /// Returns the code CID of the actor at `addr` in the state of the tipset at
+    /// `epoch` on the chain of `tipset`. Actor code CIDs change with network
+    /// upgrades, so this may differ from the code in the current state.
+    pub fn get_actor_code_cid_at_epoch(
+        &self,
+        addr: &Address,
+        epoch: ChainEpoch,
+        tipset: &Arc<Tipset>,
+    ) -> Result<Option<Cid>, Error> {
+        let ts = self
+            .cs
+            .chain_index
+            .tipset_by_height(epoch, tipset.clone(), ResolveNullTipset::TakeOlder)
+            .map_err(|e| Error::Other(format!("Failed to load tipset at epoch {epoch}: {e}")))?;
+        Ok(self
+            .get_actor(addr, *ts.parent_state())?
+            .map(|actor| actor.code))
+    }
+
+    /// Returns the network baseline power, in bytes, at each of the `epochs`
+    /// on the chain of `tipset`. Null rounds resolve to the previous tipset.
+    pub fn get_network_baseline_power_history(
+        &self,
+        epochs: &[ChainEpoch],
+        tipset: &Arc<Tipset>,
+    ) -> Result<Vec<(ChainEpoch, BigInt)>, Error> {
+        epochs
+            .iter()
+            .map(|&epoch| {
+                let ts = self
+                    .cs
+                    .chain_index
+                    .tipset_by_height(epoch, tipset.clone(), ResolveNullTipset::TakeOlder)
+                    .map_err(|e| {
+                        Error::Other(format!("Failed to load tipset at epoch {epoch}: {e}"))
+                    })?;
+                Ok((epoch, self.baseline_power(ts.parent_state())?))
+            })
+            .collect()
+    }
+
+    /// Reads `this_epoch_baseline_power` from the reward actor state, the value
+    /// its `ThisEpochReward` method returns. Results are cached in memory by
+    /// state root, so entries from different forks never collide.
+    fn baseline_power(&self, state_root: &Cid) -> Result<BigInt, Error> {
+        if let Some(power) = self.baseline_power_cache.lock().get(state_root) {
+            return Ok(power.clone());
+        }
+        let state: reward::State = self.get_state_tree(state_root)?.get_actor_state()?;
+        let power = state.this_epoch_baseline_power().clone();
+        self.baseline_power_cache
+            .lock()
+            .put(*state_root, power.clone());
+        Ok(power)
+    }
+
     /// Returns a reference to the state manager's [`Blockstore`].
     pub fn blockstore(&self) -> &DB {
         self.cs.blockstore()
@@ -395,7 +1313,37 @@ where
         Ok(state.into_network_name())
     }
 
+    /// Returns the details of the genesis state, read once from the genesis
+    /// tipset.
+    pub fn get_genesis_info(&self) -> Result<Arc<GenesisStateInfo>, Error> {
+        if let Some(info) = self.genesis_info.get() {
+            return Ok(info.clone());
+        }
+        let genesis = self.cs.genesis_tipset();
+        let state_tree = self.get_state_tree(genesis.parent_state())?;
+        let mut initial_supply = TokenAmount::zero();
+        let mut genesis_miners = vec![];
+        let mut genesis_actors = HashMap::new();
+        state_tree.for_each(|address, actor| {
+            initial_supply += TokenAmount::from(actor.balance.clone());
+            if is_miner_actor(&actor.code) {
+                genesis_miners.push(address);
+            }
+            genesis_actors.insert(address, actor.clone());
+            Ok(())
+        })?;
+        genesis_miners.sort();
+        let info = Arc::new(GenesisStateInfo {
+            timestamp: genesis.min_timestamp(),
+            initial_supply,
+            genesis_miners,
+            genesis_actors,
+        });
+        Ok(self.genesis_info.get_or_init(|| info).clone())
+    }
+
     /// Returns true if miner has been slashed or is considered invalid.
+    #[deprecated(note = "use `miner_consensus_status`, which tells the cases apart")]
     pub fn is_miner_slashed(&self, addr: &Address, state_cid: &Cid) -> anyhow::Result<bool, Error> {
         let actor = self
             .get_actor(&Address::POWER_ACTOR, *state_cid)?
@@ -406,6 +1354,53 @@ where
         Ok(spas.miner_power(self.blockstore(), &addr.into())?.is_none())
     }
 
+    /// Returns the standing of a miner in consensus at `tipset`. A consensus
+    /// fault takes precedence over the power of the miner, which is only
+    /// checked against the minimum outside of the exclusion window.
+    pub fn miner_consensus_status(
+        &self,
+        addr: &Address,
+        tipset: &Tipset,
+    ) -> Result<MinerConsensusStatus, Error> {
+        let actor = self
+            .get_actor(&Address::POWER_ACTOR, *tipset.parent_state())?
+            .ok_or_else(|| Error::State("Power actor address could not be resolved".to_string()))?;
+        let power_state = power::State::load(self.blockstore(), actor.code, actor.state)?;
+        let Some(claim) = power_state.miner_power(self.blockstore(), &addr.into())? else {
+            return Ok(MinerConsensusStatus::NoClaim);
+        };
+
+        let actor = self
+            .get_actor(addr, *tipset.parent_state())?
+            .ok_or_else(|| Error::State("Miner actor address could not be resolved".to_string()))?;
+        let miner_state = miner::State::load(self.blockstore(), actor.code, actor.state)?;
+        let info = miner_state.info(self.blockstore())?;
+        if tipset.epoch() <= info.consensus_fault_elapsed {
+            return Ok(MinerConsensusStatus::ConsensusFault {
+                elapsed: info.consensus_fault_elapsed,
+            });
+        }
+
+        let quality_adj_power = claim.quality_adj_power;
+        if power_state.miner_nominal_power_meets_consensus_minimum(
+            &self.chain_config.policy,
+            self.blockstore(),
+            &addr.into(),
+        )? {
+            Ok(MinerConsensusStatus::Active { quality_adj_power })
+        } else {
+            Ok(MinerConsensusStatus::BelowMinimumPower { quality_adj_power })
+        }
+    }
+
+    /// Retrieves the info of the miner in the given state.
+    pub fn get_miner_info_at(&self, addr: &Address, state_cid: Cid) -> Result<MinerInfo, Error> {
+        let state =
+            StateTree::new_from_root(self.blockstore_owned(), &state_cid).map_err(Error::other)?;
+        let ms: miner::State = state.get_actor_state_from_address(addr)?;
+        Ok(ms.info(self.blockstore())?)
+    }
+
     /// Returns raw work address of a miner given the state root.
     pub fn get_miner_work_addr(&self, state_cid: Cid, addr: &Address) -> Result<Address, Error> {
         let state =
@@ -416,16 +1411,334 @@ where
         Ok(addr)
     }
 
-    /// Returns specified actor's claimed power and total network power as a
-    /// tuple.
-    pub fn get_power(
+    /// Returns the libp2p peer ID and multiaddresses advertised by the miner
+    /// in the given state, along with the key address of its worker.
+    pub fn get_miner_peer_info(
         &self,
-        state_cid: &Cid,
-        addr: Option<&Address>,
-    ) -> anyhow::Result<Option<(power::Claim, power::Claim)>, Error> {
-        let actor = self
-            .get_actor(&Address::POWER_ACTOR, *state_cid)?
-            .ok_or_else(|| Error::State("Power actor address could not be resolved".to_string()))?;
+        addr: &Address,
+        state_cid: Cid,
+    ) -> Result<MinerPeerInfo, Error> {
+        let info = self.get_miner_info_at(addr, state_cid)?;
+        Ok(MinerPeerInfo {
+            peer_id: info.peer_id,
+            multiaddrs: info
+                .multiaddrs
+                .into_iter()
+                .map(|multiaddr| multiaddr.0)
+                .collect(),
+            worker_key: self.get_miner_work_addr(state_cid, addr)?,
+        })
+    }
+
+    /// Returns the most recent state root at or below `ts` that is present in
+    /// the blockstore. In lite mode, this is the state imported from the
+    /// snapshot, which is used in place of lookback states that are never
+    /// computed. As no more recent state appears in lite mode, the anchor of a
+    /// previous head is reused as long as it is on the chain of `ts` and still
+    /// in the blockstore, and searched again otherwise, e.g. after a reorg or
+    /// a garbage collection.
+    pub fn lite_state_anchor(&self, ts: &Tipset) -> Result<Cid, Error> {
+        let cached = *self.lite_state_anchor.lock();
+        if let Some((epoch, state_root)) = cached {
+            if epoch <= ts.epoch() && self.blockstore().has(&state_root)? {
+                let anchor_ts = self.chain_store().chain_index.tipset_by_height(
+                    epoch,
+                    Arc::new(ts.clone()),
+                    ResolveNullTipset::TakeOlder,
+                )?;
+                if anchor_ts.epoch() == epoch && anchor_ts.parent_state() == &state_root {
+                    return Ok(state_root);
+                }
+            }
+        }
+        let (epoch, state_root) = ts
+            .clone()
+            .chain(self.blockstore())
+            .map(|ts| (ts.epoch(), *ts.parent_state()))
+            .find(|(_, state_root)| self.blockstore().has(state_root).unwrap_or_default())
+            .ok_or_else(|| {
+                Error::Other(format!(
+                    "no state available at or below epoch {}",
+                    ts.epoch()
+                ))
+            })?;
+        *self.lite_state_anchor.lock() = Some((epoch, state_root));
+        Ok(state_root)
+    }
+
+    /// Returns the collateral deposited for each pending pre-commit of a miner.
+    pub fn get_miner_pre_commit_deposits(
+        &self,
+        addr: &Address,
+        state_cid: Cid,
+    ) -> Result<Vec<PreCommitDepositInfo>, Error> {
+        let state =
+            StateTree::new_from_root(self.blockstore_owned(), &state_cid).map_err(Error::other)?;
+        let ms: miner::State = state.get_actor_state_from_address(addr)?;
+        let policy = &self.chain_config().policy;
+        let mut deposits = ms
+            .load_all_precommit_on_chain_infos(self.blockstore())
+            .map_err(Error::other)?
+            .into_iter()
+            .map(|precommit| PreCommitDepositInfo::new(policy, precommit))
+            .collect::<anyhow::Result<Vec<_>>>()
+            .map_err(Error::other)?;
+        deposits.sort_by_key(|deposit| deposit.sector_number);
+        Ok(deposits)
+    }
+
+    /// Returns the start of the miner's proving period containing `curr_epoch`.
+    pub fn get_miner_proving_period_start(
+        &self,
+        addr: &Address,
+        curr_epoch: ChainEpoch,
+        state_cid: Cid,
+    ) -> Result<ChainEpoch, Error> {
+        let actor = self.get_required_actor(addr, state_cid)?;
+        let ms = miner::State::load(self.blockstore(), actor.code, actor.state)?;
+        Ok(ms.current_proving_period_start(&self.chain_config().policy, curr_epoch))
+    }
+
+    /// Projects the fee debt of the miner over the next `future_epochs`,
+    /// assuming its currently faulty sectors remain faulty. The continued fault
+    /// fee is spread evenly over the deadline windows of a proving period and
+    /// the projection starts at the deadline recorded in the miner state.
+    pub fn get_miner_fee_debt_projection(
+        &self,
+        addr: &Address,
+        state_cid: Cid,
+        future_epochs: ChainEpoch,
+    ) -> Result<FeeDebtProjection, Error> {
+        let state =
+            StateTree::new_from_root(self.blockstore_owned(), &state_cid).map_err(Error::other)?;
+        let actor = state
+            .get_actor(addr)?
+            .ok_or_else(|| Error::State(format!("Miner actor {addr} not found")))?;
+        let ms = miner::State::load(self.blockstore(), actor.code, actor.state)?;
+        let policy = &self.chain_config().policy;
+
+        let mut faulty_qa_power = BigInt::zero();
+        ms.for_each_deadline(policy, self.blockstore(), |_, deadline| {
+            deadline.for_each(self.blockstore(), |_, partition| {
+                faulty_qa_power += partition.faulty_qa_power();
+                Ok(())
+            })
+        })?;
+        let power_state: power::State = state.get_actor_state()?;
+        let reward_state: reward::State = state.get_actor_state()?;
+        let fee_per_period: TokenAmount = reward_state
+            .pledge_penalty_for_continued_fault(
+                power_state.total_power_smoothed(),
+                &faulty_qa_power,
+            )?
+            .into();
+        let fee_per_window = fee_per_period.div_floor(policy.wpost_period_deadlines);
+
+        let current_debt: TokenAmount = ms.fee_debt().into();
+        let available: TokenAmount = ms.available_balance(actor.balance.atto())?.into();
+        let vesting = ms
+            .load_vesting_schedule(self.blockstore())?
+            .into_iter()
+            .map(|(epoch, amount)| (epoch, TokenAmount::from(amount)))
+            .collect_vec();
+        // The deadline opening epoch does not depend on the epoch argument.
+        let start = ms.recorded_deadline_info(policy, 0).open;
+        Ok(FeeDebtProjection::project(
+            current_debt.clone(),
+            available + current_debt,
+            &fee_per_window,
+            &vesting,
+            start,
+            policy.wpost_challenge_window,
+            future_epochs,
+        ))
+    }
+
+    /// Returns the components of the circulating supply of the given state at
+    /// `epoch`, as computed by the VM, see
+    /// [`GenesisInfo::get_vm_circulating_supply_detailed`].
+    pub fn compute_circulating_supply_breakdown(
+        &self,
+        state_cid: Cid,
+        epoch: ChainEpoch,
+    ) -> Result<CirculatingSupply, Error> {
+        GenesisInfo::from_chain_config(self.chain_config().clone())
+            .get_vm_circulating_supply_detailed(epoch, &self.blockstore_owned(), &state_cid)
+            .map_err(Error::other)
+    }
+
+    /// Returns the funds locked in the vesting schedules of all the miners in
+    /// the given state, in total and by vesting epoch. The miner states are
+    /// loaded in parallel.
+    pub fn get_token_vesting_stats(&self, state_cid: Cid) -> Result<VestingStats, Error> {
+        use rayon::iter::{IntoParallelIterator as _, ParallelIterator as _};
+
+        let state =
+            StateTree::new_from_root(self.blockstore_owned(), &state_cid).map_err(Error::other)?;
+        let power_state: power::State = state.get_actor_state()?;
+        let miners = power_state
+            .list_all_miners(self.blockstore())?
+            .into_iter()
+            .map(|miner| {
+                state
+                    .get_actor(&miner)?
+                    .ok_or_else(|| Error::State(format!("Miner actor {miner} not found")))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let schedules = miners
+            .into_par_iter()
+            .map(|actor| {
+                miner::State::load(self.blockstore(), actor.code, actor.state)?
+                    .load_vesting_schedule(self.blockstore())
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let mut stats = VestingStats::default();
+        for (epoch, amount) in schedules.into_iter().flatten() {
+            let amount = TokenAmount::from(amount);
+            stats.total_vesting += amount.clone();
+            *stats.vesting_by_bucket.entry(epoch).or_default() += amount;
+        }
+        Ok(stats)
+    }
+
+    /// Returns the sector terminations of the miner within `from..=to` in
+    /// the chain of `tipset`, by replaying the tipsets including
+    /// `TerminateSectors` messages sent to it and summing the funds it burnt
+    /// while executing them. Terminations deferred to cron are not included. The history of finalized epochs is
+    /// cached in the settings store.
+    pub async fn get_miner_termination_history(
+        self: &Arc<Self>,
+        miner: &Address,
+        from: ChainEpoch,
+        to: ChainEpoch,
+        tipset: &Arc<Tipset>,
+    ) -> Result<Vec<TerminationRecord>, Error> {
+        if from > to {
+            return Err(Error::Other(format!("Invalid epoch range {from}..={to}")));
+        }
+        let miner = self.lookup_required_id(miner, tipset)?;
+        let settings = self.cs.settings();
+        let key = format!("{MINER_TERMINATIONS_KEY_PREFIX}{miner}");
+        let cached = settings.read_obj::<CachedTerminations>(&key)?;
+        if let Some(cached) = cached
+            .as_ref()
+            .filter(|cached| cached.from <= from && to <= cached.to)
+        {
+            return Ok(cached
+                .records
+                .range(from..=to)
+                .map(|(_, r)| r.clone())
+                .collect());
+        }
+
+        let top = to.min(tipset.epoch());
+        let top = self
+            .cs
+            .chain_index
+            .tipset_by_height(top, tipset.clone(), ResolveNullTipset::TakeOlder)
+            .map_err(|e| Error::Other(format!("Failed to load tipset at epoch {top}: {e}")))?;
+        let mut records = BTreeMap::new();
+        for ts in self
+            .cs
+            .chain_index
+            .chain(top)
+            .take_while(|ts| ts.epoch() >= from)
+        {
+            let mut terminations = HashMap::new();
+            for message in self.cs.messages_for_tipset(&ts).map_err(Error::other)? {
+                if message.message().method_num != miner::Method::TerminateSectors as u64
+                    || self.lookup_id(&message.message().to, &ts)? != Some(miner)
+                {
+                    continue;
+                }
+                let Ok(params) = fvm_ipld_encoding::from_slice::<
+                    fil_actor_miner_state::v16::TerminateSectorsParams,
+                >(message.message().params.bytes()) else {
+                    continue;
+                };
+                let sectors = params
+                    .terminations
+                    .iter()
+                    .flat_map(|declaration| declaration.sectors.iter())
+                    .collect_vec();
+                terminations.insert(message.cid(), sectors);
+            }
+            if terminations.is_empty() {
+                continue;
+            }
+            for (sectors, penalty) in self
+                .replay_terminations(ts.clone(), miner, terminations)
+                .await?
+            {
+                let record = records
+                    .entry(ts.epoch())
+                    .or_insert_with(|| TerminationRecord {
+                        epoch: ts.epoch(),
+                        sectors: vec![],
+                        penalty: TokenAmount::zero(),
+                    });
+                record.sectors.extend(sectors);
+                record.penalty += penalty;
+            }
+        }
+
+        if to <= tipset.epoch() - self.chain_config().policy.chain_finality {
+            let entry = CachedTerminations::merge(cached, from, to, records.clone());
+            if let Err(e) = settings.write_obj(&key, &entry) {
+                warn!("Failed to cache the termination history of {miner}: {e}");
+            }
+        }
+        Ok(records.into_values().collect())
+    }
+
+    /// Executes the messages of `tipset` once, and returns the sectors and the
+    /// funds burnt by `miner` for each of the given `TerminateSectors`
+    /// messages, keyed by CID, that succeeded. The other messages may fail
+    /// like they did on chain.
+    async fn replay_terminations(
+        self: &Arc<Self>,
+        tipset: Arc<Tipset>,
+        miner: Address,
+        mut terminations: HashMap<Cid, Vec<SectorNumber>>,
+    ) -> Result<Vec<(Vec<SectorNumber>, TokenAmount)>, Error> {
+        let this = Arc::clone(self);
+        tokio::task::spawn_blocking(move || {
+            let mut replayed = vec![];
+            let callback = |ctx: MessageCallbackCtx<'_>| {
+                if matches!(ctx.at, CalledAt::Applied) && ctx.apply_ret.exit_code().is_success() {
+                    if let Some(sectors) = terminations.remove(&ctx.message.cid()) {
+                        let penalty = structured::parse_events(ctx.apply_ret.exec_trace())
+                            .ok()
+                            .flatten()
+                            .map(|trace| funds_burnt_by(&trace, &miner))
+                            .unwrap_or_default();
+                        replayed.push((sectors, penalty));
+                    }
+                }
+                Ok(())
+            };
+            this.compute_tipset_state_blocking(
+                tipset,
+                Some(callback),
+                VMTrace::Traced,
+                VMEvent::NotPushed,
+            )?;
+            Ok(replayed)
+        })
+        .await?
+    }
+
+    /// Returns specified actor's claimed power and total network power as a
+    /// tuple.
+    pub fn get_power(
+        &self,
+        state_cid: &Cid,
+        addr: Option<&Address>,
+    ) -> anyhow::Result<Option<(power::Claim, power::Claim)>, Error> {
+        let actor = self
+            .get_actor(&Address::POWER_ACTOR, *state_cid)?
+            .ok_or_else(|| Error::State("Power actor address could not be resolved".to_string()))?;
 
         let spas = power::State::load(self.blockstore(), actor.code, actor.state)?;
 
@@ -480,6 +1793,34 @@ where
         Ok((state_root, receipt_root))
     }
 
+    /// Returns the sector counts of a miner in the state resulting from the
+    /// tipset at `epoch` on the chain of `chain_head`, or from the closest
+    /// tipset before it on null rounds. The counts are cached per miner and
+    /// tipset.
+    pub async fn get_miner_sector_count_at_epoch(
+        self: &Arc<Self>,
+        miner: &Address,
+        epoch: ChainEpoch,
+        chain_head: &Arc<Tipset>,
+    ) -> Result<MinerSectors, Error> {
+        let ts = self
+            .cs
+            .chain_index
+            .tipset_by_height(epoch, chain_head.clone(), ResolveNullTipset::TakeOlder)
+            .map_err(|e| Error::Other(format!("Failed to load tipset at epoch {epoch}: {e}")))?;
+        let key = (*miner, ts.key().clone());
+        if let Some(count) = self.miner_sector_count_cache.lock().get(&key) {
+            return Ok(count.clone());
+        }
+        let (state_root, _) = self
+            .tipset_state(&ts)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to compute the tipset state: {e}")))?;
+        let count = self.get_miner_sector_count(miner, state_root)?;
+        self.miner_sector_count_cache.lock().put(key, count.clone());
+        Ok(count)
+    }
+
     #[instrument(skip(self))]
     pub async fn tipset_state_output(
         self: &Arc<Self>,
@@ -512,6 +1853,10 @@ where
         let key = tipset.key();
         self.events_cache
             .get_or_else(key, || async move {
+                let event_index = self.chain_store().event_index();
+                if let Some(events) = event_index.get(tipset)? {
+                    return Ok(StateEvents { events });
+                }
                 let ts_state = self
                     .compute_tipset_state(
                         Arc::clone(tipset),
@@ -521,6 +1866,12 @@ where
                     )
                     .await?;
                 trace!("Completed tipset state calculation {:?}", tipset.cids());
+                if let Err(e) = event_index.put(tipset, &ts_state.events) {
+                    warn!(
+                        "Failed to index the events of epoch {}: {e}",
+                        tipset.epoch()
+                    );
+                }
                 Ok(StateEvents {
                     events: ts_state.events,
                 })
@@ -528,16 +1879,47 @@ where
             .await
     }
 
-    #[instrument(skip(self, rand))]
+    /// Applies `msg` on top of the parent state of `tipset`, with the actors
+    /// in `state_override` replaced. The overridden state and the changes of
+    /// the call are then written to an in-memory overlay of the blockstore,
+    /// dropped once the call returns.
+    #[instrument(skip(self, rand, state_override))]
     fn call_raw(
         self: &Arc<Self>,
         msg: &Message,
         rand: ChainRand<DB>,
         tipset: &Arc<Tipset>,
+        state_override: StateOverride,
     ) -> Result<ApiInvocResult, Error> {
-        let mut msg = msg.clone();
+        if state_override.is_empty() {
+            return self.call_on_store(
+                msg,
+                rand,
+                tipset,
+                *tipset.parent_state(),
+                &self.blockstore_owned(),
+                &self.chain_store().chain_index,
+            );
+        }
+        let store = Arc::new(OverlayDB::new(self.blockstore_owned()));
+        let state_cid = apply_state_override(&store, *tipset.parent_state(), state_override)?;
+        let chain_index = Arc::new(
+            ChainIndex::new(Arc::clone(&store))
+                .with_checkpoints(self.chain_store().checkpoint_index().clone()),
+        );
+        self.call_on_store(msg, rand, tipset, state_cid, &store, &chain_index)
+    }
 
-        let state_cid = tipset.parent_state();
+    fn call_on_store<S: Blockstore + Send + Sync + 'static>(
+        &self,
+        msg: &Message,
+        rand: ChainRand<DB>,
+        tipset: &Arc<Tipset>,
+        state_cid: Cid,
+        store: &Arc<S>,
+        chain_index: &Arc<ChainIndex<Arc<S>>>,
+    ) -> Result<ApiInvocResult, Error> {
+        let mut msg = msg.clone();
 
         let tipset_messages = self
             .chain_store()
@@ -556,17 +1938,13 @@ where
         let mut vm = VM::new(
             ExecutionContext {
                 heaviest_tipset: Arc::clone(tipset),
-                state_tree_root: *state_cid,
+                state_tree_root: state_cid,
                 epoch: height,
                 rand: Box::new(rand),
                 base_fee: tipset.block_headers().first().parent_base_fee.clone(),
-                circ_supply: genesis_info.get_vm_circulating_supply(
-                    height,
-                    &self.blockstore_owned(),
-                    state_cid,
-                )?,
+                circ_supply: genesis_info.get_vm_circulating_supply(height, store, &state_cid)?,
                 chain_config: self.chain_config().clone(),
-                chain_index: Arc::clone(&self.chain_store().chain_index),
+                chain_index: Arc::clone(chain_index),
                 timestamp: tipset.min_timestamp(),
             },
             &self.engine,
@@ -581,7 +1959,7 @@ where
         // This is needed to get the correct nonce from the actor state to match the VM
         let state_cid = vm.flush()?;
 
-        let state = StateTree::new_from_root(self.blockstore_owned(), &state_cid)?;
+        let state = StateTree::new_from_root(Arc::clone(store), &state_cid)?;
 
         let from_actor = state
             .get_actor(&msg.from())?
@@ -617,7 +1995,43 @@ where
     ) -> Result<ApiInvocResult, Error> {
         let ts = tipset.unwrap_or_else(|| self.cs.heaviest_tipset());
         let chain_rand = self.chain_rand(Arc::clone(&ts));
-        self.call_raw(message, chain_rand, &ts)
+        self.call_raw(message, chain_rand, &ts, StateOverride::default())
+    }
+
+    /// Runs the given message as [`StateManager::call`] does, with the actor
+    /// states in `state_override` replacing the ones in the parent state of
+    /// `tipset`, e.g. to test a message against a hypothetical balance. No
+    /// changes are persisted.
+    pub fn call_with_state_override(
+        self: &Arc<Self>,
+        message: &Message,
+        state_override: StateOverride,
+        tipset: &Arc<Tipset>,
+    ) -> Result<ApiInvocResult, Error> {
+        let chain_rand = self.chain_rand(Arc::clone(tipset));
+        self.call_raw(message, chain_rand, tipset, state_override)
+    }
+
+    /// Returns the messages applied before a message of `from` by
+    /// [`PriorMessages::Pending`], i.e. the first [`MAX_PRIOR_MESSAGES`] pending
+    /// messages of its key address. Callers simulating several messages of the
+    /// same sender should fetch them once and pass them as
+    /// [`PriorMessages::Given`].
+    pub async fn pending_prior_messages_of(
+        self: &Arc<Self>,
+        source: &(dyn PendingMessageSource + Send + Sync),
+        from: &Address,
+        tipset: &Arc<Tipset>,
+    ) -> Vec<ChainMessage> {
+        match self.resolve_to_key_addr(from, tipset).await {
+            Ok(key) => pending_prior_messages(source, &key, MAX_PRIOR_MESSAGES),
+            Err(e) => {
+                warn!(
+                    "Not applying the pending messages of {from}, could not resolve its key address: {e}"
+                );
+                vec![]
+            }
+        }
     }
 
     /// Computes message on the given [Tipset] state, after applying other
@@ -625,11 +2039,18 @@ where
     pub async fn call_with_gas(
         self: &Arc<Self>,
         message: &mut ChainMessage,
-        prior_messages: &[ChainMessage],
+        prior_messages: PriorMessages<'_>,
         tipset: Option<Arc<Tipset>>,
         trace_config: VMTrace,
     ) -> Result<(InvocResult, ApplyRet), Error> {
         let ts = tipset.unwrap_or_else(|| self.cs.heaviest_tipset());
+        let prior_messages = match prior_messages {
+            PriorMessages::Given(messages) => Cow::Borrowed(messages),
+            PriorMessages::Pending(source) => Cow::Owned(
+                self.pending_prior_messages_of(source, &message.from(), &ts)
+                    .await,
+            ),
+        };
         let (st, _) = self
             .tipset_state(&ts)
             .await
@@ -663,7 +2084,7 @@ where
                 trace_config,
             )?;
 
-            for msg in prior_messages {
+            for msg in prior_messages.iter() {
                 vm.apply_message(msg)?;
             }
             let from_actor = vm
@@ -678,6 +2099,59 @@ where
         Ok((InvocResult::new(message.message().clone(), &ret), ret))
     }
 
+    /// Applies `messages` in order on top of the state computed for `tipset`,
+    /// as if they were included in the next tipset, and returns the result of
+    /// each. The sequence of every message is set from the nonce of its sender
+    /// when it's applied. The actor states in `state_override` replace the
+    /// ones in the state tree beforehand. No changes are persisted.
+    pub async fn simulate_message_batch(
+        self: &Arc<Self>,
+        messages: Vec<Message>,
+        state_override: Option<StateOverride>,
+        tipset: Arc<Tipset>,
+    ) -> Result<Vec<InvocResult>, Error> {
+        let (st, _) = self
+            .tipset_state(&tipset)
+            .await
+            .map_err(|_| Error::Other("Could not load tipset state".to_string()))?;
+        let store = Arc::new(OverlayDB::new(self.blockstore_owned()));
+        let st = apply_state_override(&store, st, state_override.unwrap_or_default())?;
+        let chain_rand = self.chain_rand(Arc::clone(&tipset));
+
+        let epoch = tipset.epoch() + 1;
+        let genesis_info = GenesisInfo::from_chain_config(self.chain_config().clone());
+        stacker::grow(64 << 20, || -> Result<Vec<InvocResult>, Error> {
+            let mut vm = VM::new(
+                ExecutionContext {
+                    heaviest_tipset: Arc::clone(&tipset),
+                    state_tree_root: st,
+                    epoch,
+                    rand: Box::new(chain_rand),
+                    base_fee: tipset.block_headers().first().parent_base_fee.clone(),
+                    circ_supply: genesis_info.get_vm_circulating_supply(epoch, &store, &st)?,
+                    chain_config: self.chain_config().clone(),
+                    chain_index: Arc::new(ChainIndex::new(Arc::clone(&store))),
+                    timestamp: tipset.min_timestamp(),
+                },
+                &self.engine,
+                VMTrace::NotTraced,
+            )?;
+
+            messages
+                .into_iter()
+                .map(|mut msg| {
+                    let from_actor = vm
+                        .get_actor(&msg.from())
+                        .map_err(|e| Error::Other(format!("Could not get actor from state: {e}")))?
+                        .ok_or_else(|| Error::Other("cant find actor in state tree".to_string()))?;
+                    msg.set_sequence(from_actor.sequence);
+                    let (ret, _) = vm.apply_message(&ChainMessage::Unsigned(msg.clone()))?;
+                    Ok(InvocResult::new(msg, &ret))
+                })
+                .collect()
+        })
+    }
+
     /// Replays the given message and returns the result of executing the
     /// indicated message, assuming it was executed in the indicated tipset.
     pub async fn replay(
@@ -699,6 +2173,19 @@ where
     ) -> Result<ApiInvocResult, Error> {
         const REPLAY_HALT: &str = "replay_halt";
 
+        if !self
+            .cs
+            .unique_messages_for_tipset(&ts)
+            .map_err(|err| Error::Other(format!("Failed to load messages for tipset: {err}")))?
+            .iter()
+            .any(|(message, _)| message.cid() == mcid)
+        {
+            return Err(Error::Other(format!(
+                "message {mcid} is not applied in tipset {}",
+                ts.key()
+            )));
+        }
+
         let mut api_invoc_result = None;
         let callback = |ctx: MessageCallbackCtx<'_>| {
             match ctx.at {
@@ -736,6 +2223,72 @@ where
         api_invoc_result.ok_or_else(|| Error::Other("failed to replay".into()))
     }
 
+    /// Replays each message of `pairs` in its tipset, executing every tipset
+    /// only once however many of its messages are requested. The results are
+    /// returned in the order of `pairs`.
+    pub async fn replay_batch(
+        self: &Arc<Self>,
+        pairs: &[(Cid, Arc<Tipset>)],
+    ) -> Result<Vec<(Message, ApplyRet)>, Error> {
+        let this = Arc::clone(self);
+        let pairs = pairs.to_vec();
+        tokio::task::spawn_blocking(move || this.replay_batch_blocking(&pairs))
+            .await
+            .map_err(|e| Error::Other(format!("{e}")))?
+    }
+
+    /// Blocking version of `replay_batch`
+    pub fn replay_batch_blocking(
+        self: &Arc<Self>,
+        pairs: &[(Cid, Arc<Tipset>)],
+    ) -> Result<Vec<(Message, ApplyRet)>, Error> {
+        const REPLAY_HALT: &str = "replay_halt";
+
+        let mut results = HashMap::new();
+        for (ts, mut pending) in group_by_tipset(pairs) {
+            let tsk = ts.key().clone();
+            let callback = |ctx: MessageCallbackCtx<'_>| {
+                match ctx.at {
+                    CalledAt::Applied | CalledAt::Reward if pending.remove(&ctx.cid) => {
+                        results.insert(
+                            (tsk.clone(), ctx.cid),
+                            (ctx.message.message().clone(), ctx.apply_ret.clone()),
+                        );
+                        // Skip the rest of the tipset once all its messages are found
+                        if pending.is_empty() {
+                            anyhow::bail!(REPLAY_HALT);
+                        }
+                        Ok(())
+                    }
+                    _ => Ok(()), // ignored
+                }
+            };
+            let result = self.compute_tipset_state_blocking(
+                ts,
+                Some(callback),
+                VMTrace::Traced,
+                VMEvent::NotPushed,
+            );
+            if let Err(error_message) = result {
+                if error_message.to_string() != REPLAY_HALT {
+                    return Err(Error::Other(format!(
+                        "unexpected error during execution : {error_message:}"
+                    )));
+                }
+            }
+        }
+
+        pairs
+            .iter()
+            .map(|(mcid, ts)| {
+                results
+                    .get(&(ts.key().clone(), *mcid))
+                    .cloned()
+                    .ok_or_else(|| Error::Other(format!("failed to replay message {mcid}")))
+            })
+            .collect()
+    }
+
     /// Checks the eligibility of the miner. This is used in the validation that
     /// a block's miner has the requirements to mine a block.
     pub fn eligible_to_mine(
@@ -755,38 +2308,36 @@ where
             return Ok(false);
         }
 
-        let actor = self
-            .get_actor(&Address::POWER_ACTOR, *base_tipset.parent_state())?
-            .ok_or_else(|| Error::State("Power actor address could not be resolved".to_string()))?;
-
-        let power_state = power::State::load(self.blockstore(), actor.code, actor.state)?;
-
-        let actor = self
-            .get_actor(address, *base_tipset.parent_state())?
-            .ok_or_else(|| Error::State("Miner actor address could not be resolved".to_string()))?;
-
-        let miner_state = miner::State::load(self.blockstore(), actor.code, actor.state)?;
-
-        // Non-empty power claim.
-        let claim = power_state
-            .miner_power(self.blockstore(), &address.into())?
-            .ok_or_else(|| Error::Other("Could not get claim".to_string()))?;
-        if claim.quality_adj_power <= BigInt::zero() {
-            return Ok(false);
-        }
-
-        // No fee debt.
-        if !miner_state.fee_debt().is_zero() {
+        // The address must have been a miner actor at the lookback epoch.
+        let lookback_code = self.get_actor_code_cid_at_epoch(
+            address,
+            lookback_tipset.epoch(),
+            &Arc::new(base_tipset.clone()),
+        )?;
+        if !lookback_code.is_some_and(|code| is_miner_actor(&code)) {
             return Ok(false);
         }
 
-        // No active consensus faults.
-        let info = miner_state.info(self.blockstore())?;
-        if base_tipset.epoch() <= info.consensus_fault_elapsed {
+        // Non-empty power claim and no active consensus faults. The claim
+        // only needs to meet the minimum at the lookback epoch.
+        let quality_adj_power = match self.miner_consensus_status(address, base_tipset)? {
+            MinerConsensusStatus::NoClaim => {
+                return Err(Error::Other("Could not get claim".to_string()))
+            }
+            MinerConsensusStatus::ConsensusFault { .. } => return Ok(false),
+            MinerConsensusStatus::BelowMinimumPower { quality_adj_power }
+            | MinerConsensusStatus::Active { quality_adj_power } => quality_adj_power,
+        };
+        if quality_adj_power <= BigInt::zero() {
             return Ok(false);
         }
 
-        Ok(true)
+        // No fee debt.
+        let actor = self
+            .get_actor(address, *base_tipset.parent_state())?
+            .ok_or_else(|| Error::State("Miner actor address could not be resolved".to_string()))?;
+        let miner_state = miner::State::load(self.blockstore(), actor.code, actor.state)?;
+        Ok(miner_state.fee_debt().is_zero())
     }
 
     /// Conceptually, a [`Tipset`] consists of _blocks_ which share an _epoch_.
@@ -839,6 +2390,14 @@ where
         enable_tracing: VMTrace,
         enable_event_pushing: VMEvent,
     ) -> Result<StateOutput, Error> {
+        if let Some(shadow) = &self.shadow_execution {
+            return self.compute_tipset_state_with_shadow(
+                tipset,
+                callback,
+                enable_event_pushing,
+                shadow,
+            );
+        }
         Ok(apply_block_messages(
             self.chain_store().genesis_block_header().timestamp,
             Arc::clone(&self.chain_store().chain_index),
@@ -852,6 +2411,77 @@ where
         )?)
     }
 
+    /// Computes the tipset state with both engines, always traced, and shuts
+    /// the node down if the executions diverge, failing the computation. Both
+    /// runs share the inputs derived from the chain: randomness, circulating
+    /// supply and base fee.
+    fn compute_tipset_state_with_shadow(
+        &self,
+        tipset: Arc<Tipset>,
+        mut callback: Option<impl FnMut(MessageCallbackCtx<'_>) -> anyhow::Result<()>>,
+        enable_event_pushing: VMEvent,
+        shadow: &shadow_execution::ShadowExecution,
+    ) -> Result<StateOutput, Error> {
+        use shadow_execution::{ExecutedMessage, ExecutionRecord};
+
+        let execute = |engine: &crate::shim::machine::MultiEngine,
+                       callback: &mut dyn FnMut(MessageCallbackCtx<'_>) -> anyhow::Result<()>,
+                       enable_event_pushing| {
+            apply_block_messages(
+                self.chain_store().genesis_block_header().timestamp,
+                Arc::clone(&self.chain_store().chain_index),
+                Arc::clone(&self.chain_config),
+                self.beacon_schedule().clone(),
+                engine,
+                Arc::clone(&tipset),
+                Some(callback),
+                VMTrace::Traced,
+                enable_event_pushing,
+            )
+        };
+
+        let mut primary_messages = vec![];
+        let output = execute(
+            &self.engine,
+            &mut |ctx: MessageCallbackCtx<'_>| {
+                primary_messages.push(ExecutedMessage::new(&ctx));
+                match callback.as_mut() {
+                    Some(callback) => callback(ctx),
+                    None => Ok(()),
+                }
+            },
+            enable_event_pushing,
+        )?;
+        let mut reference_messages = vec![];
+        let reference_output = execute(
+            &shadow.engine,
+            &mut |ctx: MessageCallbackCtx<'_>| {
+                reference_messages.push(ExecutedMessage::new(&ctx));
+                Ok(())
+            },
+            VMEvent::NotPushed,
+        )?;
+
+        let primary = ExecutionRecord {
+            state_root: output.state_root,
+            receipt_root: output.receipt_root,
+            messages: primary_messages,
+        };
+        let reference = ExecutionRecord {
+            state_root: reference_output.state_root,
+            receipt_root: reference_output.receipt_root,
+            messages: reference_messages,
+        };
+        if let Some(divergence) = shadow_execution::find_divergence(&tipset, &primary, &reference) {
+            shadow_execution::shut_down_on_divergence(divergence, &shadow.shutdown);
+            return Err(Error::Other(format!(
+                "Shadow execution of tipset {} diverged",
+                tipset.key()
+            )));
+        }
+        Ok(output)
+    }
+
     /// Check if tipset had executed the message, by loading the receipt based
     /// on the index of the message in the block.
     fn tipset_executed_message(
@@ -871,12 +2501,15 @@ where
             .chain_index
             .load_required_tipset(tipset.parents())
             .map_err(|err| Error::Other(format!("Failed to load tipset: {err}")))?;
+        // Receipts are indexed by the position of the message once the
+        // messages included in several blocks are deduplicated
         let messages = self
             .cs
-            .messages_for_tipset(&pts)
+            .unique_messages_for_tipset(&pts)
             .map_err(|err| Error::Other(format!("Failed to load messages for tipset: {err}")))?;
         messages
             .iter()
+            .map(|(message, _)| message)
             .enumerate()
             // iterate in reverse because we going backwards through the chain
             .rev()
@@ -968,9 +2601,55 @@ where
         look_back_limit: Option<i64>,
         allow_replaced: Option<bool>,
     ) -> Result<Option<(Arc<Tipset>, Receipt)>, Error> {
+        if let Some(found) =
+            self.search_message_index(&current, message, look_back_limit, allow_replaced)?
+        {
+            return Ok(Some(found));
+        }
         self.check_search(current, message, look_back_limit, allow_replaced)
     }
 
+    /// Looks the message up in the message index, which maps it to the tipset
+    /// including it, and returns the child of that tipset on the chain of
+    /// `current` along with the receipt. Returns `None` when the index can't
+    /// answer, e.g. for messages out of its retention window or replaced ones,
+    /// which are then searched by walking the chain.
+    fn search_message_index(
+        &self,
+        current: &Arc<Tipset>,
+        message: &ChainMessage,
+        look_back_limit: Option<i64>,
+        allow_replaced: Option<bool>,
+    ) -> Result<Option<(Arc<Tipset>, Receipt)>, Error> {
+        let Some(including) = self
+            .cs
+            .tipset_for_message(&message.cid())
+            .map_err(|e| Error::Other(e.to_string()))?
+        else {
+            return Ok(None);
+        };
+        if including.epoch() >= current.epoch() {
+            return Ok(None);
+        }
+        let child = self
+            .cs
+            .chain_index
+            .tipset_by_height(
+                including.epoch() + 1,
+                current.clone(),
+                ResolveNullTipset::TakeNewer,
+            )
+            .map_err(|e| Error::Other(e.to_string()))?;
+        if child.parents() != including.key()
+            || child.epoch() <= look_back_limit.unwrap_or_default()
+        {
+            return Ok(None);
+        }
+        Ok(self
+            .tipset_executed_message(&child, message, allow_replaced.unwrap_or(true))?
+            .map(|receipt| (child, receipt)))
+    }
+
     /// Returns a message receipt from a given tipset and message CID.
     pub fn get_receipt(&self, tipset: Arc<Tipset>, msg: Cid) -> Result<Receipt, Error> {
         let m = crate::chain::get_chain_message(self.blockstore(), &msg)
@@ -1136,8 +2815,35 @@ where
         }
     }
 
-    /// Returns a BLS public key from provided address
-    pub fn get_bls_public_key(
+    /// Breaks down what the message cost its sender, replicating the fee
+    /// arithmetic of the VM from its receipt and the base fee of the tipset
+    /// that executed it. The message is searched back from `from`.
+    pub async fn message_gas_cost(
+        self: &Arc<Self>,
+        msg_cid: Cid,
+        from: Arc<Tipset>,
+    ) -> Result<MessageGasCost, Error> {
+        let (ts, receipt) = self
+            .search_for_message(Some(from), msg_cid, None, Some(false))
+            .await?
+            .ok_or_else(|| Error::Other(format!("message {msg_cid} not found")))?;
+        // The receipts are in the tipset after the one executing the message
+        let executed = self
+            .cs
+            .chain_index
+            .load_required_tipset(ts.parents())
+            .map_err(|err| Error::Other(format!("Failed to load tipset: {err}")))?;
+        let message = crate::chain::get_chain_message(self.blockstore(), &msg_cid)
+            .map_err(|err| Error::Other(format!("failed to load message {err}")))?;
+        Ok(MessageGasCost::from_receipt(
+            message.message(),
+            &receipt,
+            &executed.block_headers().first().parent_base_fee,
+        ))
+    }
+
+    /// Returns a BLS public key from provided address
+    pub fn get_bls_public_key(
         db: &Arc<DB>,
         addr: &Address,
         state_cid: Cid,
@@ -1172,6 +2878,26 @@ where
             .ok_or_else(|| Error::Other(format!("Failed to lookup the id address {addr}")))
     }
 
+    /// Returns the sequence (nonce) of the actor in the state computed for the
+    /// given [Tipset]. Robust addresses are resolved through the init actor.
+    /// The state may have to be computed: the next sequence at the head is
+    /// cheaper to get from [`crate::message_pool::MessagePool::get_nonce`].
+    pub async fn get_actor_sequence(
+        self: &Arc<Self>,
+        addr: &Address,
+        tipset: &Arc<Tipset>,
+    ) -> Result<u64, Error> {
+        let (state_root, _) = self.tipset_state(tipset).await?;
+        let state_tree =
+            StateTree::new_from_root(self.blockstore_owned(), &state_root).map_err(Error::other)?;
+        let id = state_tree
+            .lookup_id(addr)?
+            .ok_or_else(|| Error::State(format!("Failed to lookup the id address {addr}")))?;
+        Ok(state_tree
+            .get_required_actor(&Address::new_id(id))?
+            .sequence)
+    }
+
     /// Retrieves market state
     pub fn market_state(&self, ts: &Tipset) -> Result<market::State, Error> {
         let actor = self.get_required_actor(&Address::MARKET_ACTOR, *ts.parent_state())?;
@@ -1179,26 +2905,55 @@ where
         Ok(market_state)
     }
 
+    /// Returns the proposal of the deal in the given state at `epoch`, or
+    /// `None` if the deal does not exist or expired, i.e. its end epoch has
+    /// passed, even if the market has not removed it yet.
+    pub fn get_deal_proposal(
+        &self,
+        deal_id: DealID,
+        state_cid: Cid,
+        epoch: ChainEpoch,
+    ) -> Result<Option<market::DealProposal>, Error> {
+        let actor = self.get_required_actor(&Address::MARKET_ACTOR, state_cid)?;
+        let market_state = market::State::load(self.blockstore(), actor.code, actor.state)?;
+        Ok(market_state
+            .proposals(self.blockstore())?
+            .get(deal_id)?
+            .filter(|proposal| proposal.end_epoch >= epoch))
+    }
+
     /// Retrieves market balance in escrow and locked tables.
     pub fn market_balance(&self, addr: &Address, ts: &Tipset) -> Result<MarketBalance, Error> {
-        let market_state = self.market_state(ts)?;
-        let new_addr = self.lookup_required_id(addr, ts)?;
-        let out = MarketBalance {
-            escrow: {
-                market_state
-                    .escrow_table(self.blockstore())?
-                    .get(&new_addr.into())?
-                    .into()
-            },
-            locked: {
-                market_state
-                    .locked_table(self.blockstore())?
-                    .get(&new_addr.into())?
-                    .into()
-            },
-        };
+        Ok(self
+            .get_market_participant_info(addr, *ts.parent_state())?
+            .into())
+    }
 
-        Ok(out)
+    /// Retrieves the escrow and locked balances of a storage market participant
+    /// in the given state, along with the amount it can withdraw.
+    pub fn get_market_participant_info(
+        &self,
+        addr: &Address,
+        state_cid: Cid,
+    ) -> Result<MarketParticipantInfo, Error> {
+        let state_tree =
+            StateTree::new_from_root(self.blockstore_owned(), &state_cid).map_err(Error::other)?;
+        let id = state_tree
+            .lookup_id(addr)
+            .map_err(Error::other)?
+            .ok_or_else(|| Error::Other(format!("Failed to lookup the id address {addr}")))?;
+        let id_addr = Address::new_id(id);
+        let actor = state_tree.get_required_actor(&Address::MARKET_ACTOR)?;
+        let market_state = market::State::load(self.blockstore(), actor.code, actor.state)?;
+        let escrow = market_state
+            .escrow_table(self.blockstore())?
+            .get(&id_addr.into())?
+            .into();
+        let locked = market_state
+            .locked_table(self.blockstore())?
+            .get(&id_addr.into())?
+            .into();
+        Ok(MarketParticipantInfo::new(escrow, locked))
     }
 
     /// Retrieves miner info.
@@ -1211,6 +2966,354 @@ where
         Ok(state.info(self.blockstore())?)
     }
 
+    /// Returns the number of live, active and faulty sectors of a miner,
+    /// summed over the partitions of all its deadlines.
+    pub fn get_miner_sector_count(
+        &self,
+        addr: &Address,
+        state_cid: Cid,
+    ) -> Result<MinerSectors, Error> {
+        let actor = self
+            .get_actor(addr, state_cid)?
+            .ok_or_else(|| Error::State("Miner actor not found".to_string()))?;
+        let state = miner::State::load(self.blockstore(), actor.code, actor.state)?;
+        let (mut live_count, mut active_count, mut faulty_count) = (0, 0, 0);
+        state.for_each_deadline(
+            &self.chain_config.policy,
+            self.blockstore(),
+            |_dlidx, deadline| {
+                deadline.for_each(self.blockstore(), |_partidx, partition| {
+                    live_count += partition.live_sectors().len();
+                    active_count += partition.active_sectors().len();
+                    faulty_count += partition.faulty_sectors().len();
+                    Ok(())
+                })
+            },
+        )?;
+        Ok(MinerSectors::new(live_count, active_count, faulty_count))
+    }
+
+    /// Returns the worker key change staged by the miner with
+    /// `ChangeWorkerAddress`, until it is confirmed.
+    pub fn get_miner_worker_key_change(
+        &self,
+        addr: &Address,
+        state_cid: Cid,
+    ) -> Result<Option<WorkerKeyChange>, Error> {
+        let actor = self
+            .get_actor(addr, state_cid)?
+            .ok_or_else(|| Error::State("Miner actor not found".to_string()))?;
+        let state = miner::State::load(self.blockstore(), actor.code, actor.state)?;
+        Ok(state.info(self.blockstore())?.pending_worker_key())
+    }
+
+    /// Returns the owner, worker and control addresses of a miner, along with
+    /// its pending worker and owner changes. Every address is returned in its
+    /// ID form, and in its robust form when the actor has one.
+    pub fn get_miner_control_address_info(
+        &self,
+        addr: &Address,
+        state_cid: Cid,
+    ) -> Result<MinerControlInfo, Error> {
+        let state_tree = self.get_state_tree(&state_cid)?;
+        let actor = state_tree
+            .get_actor(addr)?
+            .ok_or_else(|| Error::State("Miner actor not found".to_string()))?;
+        let state = miner::State::load(self.blockstore(), actor.code, actor.state)?;
+        let info = state.info(self.blockstore())?;
+        let forms = |addr: &Address| -> Result<AddressForms, Error> {
+            let id = state_tree
+                .lookup_id(addr)?
+                .map(Address::new_id)
+                .ok_or_else(|| Error::State(format!("Failed to lookup the id address {addr}")))?;
+            // Only accounts and actors with a delegated address have a robust
+            // form, resolving the others fails.
+            let robust = match state_tree.get_actor(&id)? {
+                Some(actor)
+                    if actor.delegated_address.is_some() || is_account_actor(&actor.code) =>
+                {
+                    Some(state_tree.resolve_to_deterministic_addr(self.blockstore(), id)?)
+                }
+                _ => None,
+            };
+            Ok(AddressForms { id, robust })
+        };
+        Ok(MinerControlInfo {
+            owner: forms(&info.owner)?,
+            worker: forms(&info.worker)?,
+            control_addresses: info.control_addresses.iter().map(forms).try_collect()?,
+            pending_worker: info.pending_worker_key(),
+            pending_owner: info.pending_owner_address.as_ref().map(forms).transpose()?,
+        })
+    }
+
+    /// Returns the raw byte and quality adjusted power that `sector` contributes
+    /// to the miner, i.e. the power it would lose if the sector expired.
+    pub fn get_sector_power_contribution(
+        &self,
+        miner: &Address,
+        sector: SectorNumber,
+        state_cid: Cid,
+    ) -> Result<PowerPair, Error> {
+        let (sector_size, info) = self.load_sector_info(miner, sector, state_cid)?;
+        Ok(miner::power_for_sector(sector_size, &info))
+    }
+
+    /// Returns the raw byte power and the quality adjusted power of `sector`,
+    /// see [`StateManager::get_sector_power_contribution`].
+    pub fn get_sector_quality_adj_power(
+        &self,
+        miner: &Address,
+        sector: SectorNumber,
+        state_cid: Cid,
+    ) -> Result<SectorQualityAdjPower, Error> {
+        let PowerPair { raw, qa } = self.get_sector_power_contribution(miner, sector, state_cid)?;
+        Ok(SectorQualityAdjPower {
+            raw_byte_power: raw,
+            quality_adj_power: qa,
+        })
+    }
+
+    /// Loads the on-chain info of a miner sector, along with its size.
+    fn load_sector_info(
+        &self,
+        miner: &Address,
+        sector: SectorNumber,
+        state_cid: Cid,
+    ) -> Result<(fvm_shared2::sector::SectorSize, miner::SectorOnChainInfo), Error> {
+        let actor = self
+            .get_actor(miner, state_cid)?
+            .ok_or_else(|| Error::State("Miner actor not found".to_string()))?;
+        let state = miner::State::load(self.blockstore(), actor.code, actor.state)?;
+        let info = state
+            .load_sectors(self.blockstore(), Some(&bitfield_of([sector])))?
+            .pop()
+            .ok_or_else(|| Error::State(format!("Sector {sector} not found")))?;
+        let sector_size = info
+            .seal_proof
+            .sector_size()
+            .map_err(|e| Error::Other(format!("failed to get sector size: {e}")))?;
+        Ok((sector_size, info))
+    }
+
+    /// Estimates the block reward of `sector` over the next `projection_epochs`
+    /// from its quality adjusted power and the smoothed network reward and
+    /// power in the given state.
+    pub fn compute_sector_reward_estimate(
+        &self,
+        miner: &Address,
+        sector: SectorNumber,
+        projection_epochs: ChainEpoch,
+        state_cid: Cid,
+    ) -> Result<SectorRewardEstimate, Error> {
+        if projection_epochs < 0 {
+            return Err(Error::Other(format!(
+                "projection epochs must not be negative, got {projection_epochs}"
+            )));
+        }
+        let (sector_size, info) = self.load_sector_info(miner, sector, state_cid)?;
+        let qa_power = miner::qa_power_for_sector(sector_size, &info);
+
+        let state =
+            StateTree::new_from_root(self.blockstore_owned(), &state_cid).map_err(Error::other)?;
+        let power_state: power::State = state.get_actor_state()?;
+        let reward_state: reward::State = state.get_actor_state()?;
+        let total_projected_reward = reward_state
+            .expected_reward_for_power(
+                power_state.total_power_smoothed(),
+                &qa_power,
+                projection_epochs,
+            )?
+            .into();
+        Ok(SectorRewardEstimate {
+            expected_daily_reward: info.expected_day_reward,
+            total_projected_reward,
+            expected_pledge: info.expected_storage_pledge,
+        })
+    }
+
+    /// Returns the fee charged each day while each of `sectors` is faulty and
+    /// the penalty for terminating it at the epoch of `tipset`, as computed by
+    /// the miner actor from the smoothed network reward and power in the
+    /// parent state of `tipset`, along with their totals.
+    pub fn miner_sector_penalties(
+        &self,
+        miner: &Address,
+        sectors: &BitField,
+        tipset: &Tipset,
+    ) -> Result<SectorPenalties, Error> {
+        let state = StateTree::new_from_root(self.blockstore_owned(), tipset.parent_state())
+            .map_err(Error::other)?;
+        let actor = state
+            .get_actor(miner)?
+            .ok_or_else(|| Error::State(format!("Miner actor {miner} not found")))?;
+        let ms = miner::State::load(self.blockstore(), actor.code, actor.state)?;
+        let infos = ms.load_sectors(self.blockstore(), Some(sectors))?;
+        if infos.len() as u64 != sectors.len() {
+            return Err(Error::State(format!(
+                "{} of the {} sectors not found",
+                sectors.len() - infos.len() as u64,
+                sectors.len()
+            )));
+        }
+
+        let power_state: power::State = state.get_actor_state()?;
+        let reward_state: reward::State = state.get_actor_state()?;
+        let network_qa_power = power_state.total_power_smoothed();
+        let sectors = infos
+            .iter()
+            .map(|info| SectorPenalty::new(&reward_state, &network_qa_power, info, tipset.epoch()))
+            .collect::<anyhow::Result<Vec<_>>>()
+            .map_err(Error::other)?;
+        let mut penalties = SectorPenalties {
+            sectors: vec![],
+            total_daily_fault_fee: TokenAmount::zero(),
+            total_termination_penalty: TokenAmount::zero(),
+        };
+        for sector in sectors {
+            penalties.total_daily_fault_fee += sector.daily_fault_fee.clone();
+            penalties.total_termination_penalty += sector.termination_penalty.clone();
+            penalties.sectors.push(sector);
+        }
+        Ok(penalties)
+    }
+
+    /// Returns the funds a `ProveReplicaUpdates` message upgrading `sector`
+    /// with `new_deals` would require at the epoch of `tipset`. The miner
+    /// actor raises the initial pledge of the sector to that of its new
+    /// quality adjusted power, from the smoothed network reward and power and
+    /// the circulating supply in the parent state of `tipset`, and never
+    /// lowers it.
+    pub fn get_sector_upgrade_cost(
+        &self,
+        miner: &Address,
+        sector: SectorNumber,
+        new_deals: &[DealID],
+        tipset: &Tipset,
+    ) -> Result<UpgradeCost, Error> {
+        let state_cid = tipset.parent_state();
+        let state =
+            StateTree::new_from_root(self.blockstore_owned(), state_cid).map_err(Error::other)?;
+        let actor = state
+            .get_actor(miner)?
+            .ok_or_else(|| Error::State(format!("Miner actor {miner} not found")))?;
+        let ms = miner::State::load(self.blockstore(), actor.code, actor.state)?;
+        let info = ms
+            .load_sectors(self.blockstore(), Some(&bitfield_of([sector])))?
+            .pop()
+            .ok_or_else(|| Error::State(format!("Sector {sector} not found")))?;
+        let sector_size = info
+            .seal_proof
+            .sector_size()
+            .map_err(|e| Error::Other(format!("failed to get sector size: {e}")))?;
+
+        let epoch = tipset.epoch();
+        let market_state: market::State = state.get_actor_state()?;
+        let (deal_weight, verified_deal_weight) = market_state.verify_deals_for_activation(
+            self.blockstore(),
+            (*miner).into(),
+            new_deals.to_vec(),
+            epoch,
+            info.expiration,
+        )?;
+        let qa_power = qa_power_for_weight(
+            crate::shim::sector::SectorSize::from(sector_size).into(),
+            info.expiration - epoch,
+            &deal_weight,
+            &verified_deal_weight,
+        );
+
+        let power_state: power::State = state.get_actor_state()?;
+        let reward_state: reward::State = state.get_actor_state()?;
+        let circ_supply = GenesisInfo::from_chain_config(self.chain_config().clone())
+            .get_vm_circulating_supply(epoch, &self.blockstore_owned(), state_cid)?;
+        let (epochs_since_ramp_start, ramp_duration_epochs) = match power_state.ramp_start_epoch() {
+            ramp_start if ramp_start > 0 => {
+                (epoch - ramp_start, power_state.ramp_duration_epochs())
+            }
+            _ => (0, 0),
+        };
+        let new_pledge: TokenAmount = reward_state
+            .initial_pledge_for_power(
+                &qa_power,
+                power_state.total_locked(),
+                power_state.total_power_smoothed(),
+                &circ_supply.into(),
+                epochs_since_ramp_start,
+                ramp_duration_epochs,
+            )?
+            .into();
+        let initial_pledge: TokenAmount = info.initial_pledge.into();
+        let additional_pledge = if new_pledge > initial_pledge {
+            new_pledge - initial_pledge
+        } else {
+            TokenAmount::zero()
+        };
+
+        let base_fee = &tipset.block_headers().first().parent_base_fee;
+        let aggregate_fee: TokenAmount = ms
+            .aggregate_prove_commit_network_fee(1, &base_fee.into())?
+            .into();
+        let available: TokenAmount = ms.available_balance(actor.balance.atto())?.into();
+        Ok(UpgradeCost {
+            has_sufficient_funds: available >= additional_pledge.clone() + aggregate_fee.clone(),
+            additional_pledge,
+            aggregate_fee,
+        })
+    }
+
+    /// Returns the statistics of the miners that produced blocks within
+    /// `from..=to` in the chain of `tipset`. The reward of each block is its
+    /// share of the epoch reward recorded in the reward actor of its parent
+    /// state, according to its win count.
+    pub fn get_block_producer_stats(
+        &self,
+        from: ChainEpoch,
+        to: ChainEpoch,
+        tipset: &Arc<Tipset>,
+    ) -> Result<HashMap<Address, BlockProducerStats>, Error> {
+        if from > to || to > tipset.epoch() {
+            return Err(Error::Other(format!(
+                "Invalid epoch range {from}..={to}, the tipset is at epoch {}",
+                tipset.epoch()
+            )));
+        }
+        let top = self
+            .cs
+            .chain_index
+            .tipset_by_height(to, tipset.clone(), ResolveNullTipset::TakeOlder)
+            .map_err(|e| Error::Other(format!("Failed to load tipset at epoch {to}: {e}")))?;
+        let mut stats = HashMap::<Address, BlockProducerStats>::new();
+        let mut rewards = HashMap::<Address, TokenAmount>::new();
+        for ts in self
+            .cs
+            .chain_index
+            .chain(top)
+            .take_while(|ts| ts.epoch() >= from)
+        {
+            let reward_state: reward::State =
+                self.get_state_tree(ts.parent_state())?.get_actor_state()?;
+            let epoch_reward = TokenAmount::from(reward_state.this_epoch_reward());
+            for header in ts.block_headers() {
+                let win_count = header
+                    .election_proof
+                    .as_ref()
+                    .map_or(0, |proof| proof.win_count);
+                let entry = stats.entry(header.miner_address).or_default();
+                entry.blocks_won += 1;
+                entry.win_count_total += win_count;
+                *rewards.entry(header.miner_address).or_default() +=
+                    (epoch_reward.clone() * win_count).div_floor(BLOCKS_PER_EPOCH);
+            }
+        }
+        for (miner, entry) in stats.iter_mut() {
+            if let Some(reward) = rewards.get(miner) {
+                entry.avg_epoch_reward = reward.div_floor(entry.blocks_won);
+            }
+        }
+        Ok(stats)
+    }
+
     /// Retrieves miner faults.
     pub fn miner_faults(&self, addr: &Address, ts: &Tipset) -> Result<BitField, Error> {
         self.all_partition_sectors(addr, ts, |partition| partition.faulty_sectors().clone())
@@ -1232,21 +3335,11 @@ where
             .ok_or_else(|| Error::State("Miner actor not found".to_string()))?;
 
         let state = miner::State::load(self.blockstore(), actor.code, actor.state)?;
-
-        let mut partitions = Vec::new();
-
-        state.for_each_deadline(
+        Ok(state.partition_sectors_union(
             &self.chain_config.policy,
             self.blockstore(),
-            |_, deadline| {
-                deadline.for_each(self.blockstore(), |_, partition| {
-                    partitions.push(get_sector(partition));
-                    Ok(())
-                })
-            },
-        )?;
-
-        Ok(BitField::union(partitions.iter()))
+            get_sector,
+        )?)
     }
 
     /// Retrieves miner power.
@@ -1297,9 +3390,28 @@ where
         resolve_to_key_addr(&state, self.blockstore(), addr)
     }
 
+    /// Returns the beacon entries of a block at `epoch` on a parent at
+    /// `parent_epoch`, following `prev_entry`, see
+    /// [`BeaconSchedule::beacon_entries_for_block`]. The entries are cached.
+    pub async fn get_beacon_entries_for_epoch(
+        &self,
+        epoch: ChainEpoch,
+        parent_epoch: ChainEpoch,
+        prev_entry: &BeaconEntry,
+    ) -> Result<Vec<BeaconEntry>, Error> {
+        beacon_entries_for_epoch(
+            &self.beacon,
+            &self.beacon_entries_cache,
+            self.chain_config.network_version(epoch),
+            epoch,
+            parent_epoch,
+            prev_entry,
+        )
+        .await
+    }
+
     pub async fn miner_get_base_info(
         self: &Arc<Self>,
-        beacon_schedule: &BeaconSchedule,
         tipset: Arc<Tipset>,
         addr: Address,
         epoch: ChainEpoch,
@@ -1309,13 +3421,8 @@ where
             .chain_index
             .latest_beacon_entry(tipset.clone())?;
 
-        let entries: Vec<BeaconEntry> = beacon_schedule
-            .beacon_entries_for_block(
-                self.chain_config.network_version(epoch),
-                epoch,
-                tipset.epoch(),
-                &prev_beacon,
-            )
+        let entries = self
+            .get_beacon_entries_for_epoch(epoch, tipset.epoch(), &prev_beacon)
             .await?;
 
         let base = entries.last().unwrap_or(&prev_beacon);
@@ -1481,6 +3588,28 @@ where
         state.get_claim(self.blockstore(), id_address.into(), claim_id)
     }
 
+    /// Returns the FIL+ claims of the miner on data committed in the sector,
+    /// sorted by claim ID. The claims are read from the verified registry
+    /// state rather than through its `GetClaims` method, which only looks
+    /// claims up by ID.
+    pub fn get_sector_active_claims(
+        &self,
+        miner: &Address,
+        sector: SectorNumber,
+        state_cid: Cid,
+    ) -> Result<Vec<ClaimInfo>, Error> {
+        let state_tree = StateTree::new_from_root(self.blockstore_owned(), &state_cid)?;
+        let state: verifreg::State = state_tree.get_actor_state()?;
+        let miner_id = state_tree.lookup_required_id(miner)?;
+        Ok(state
+            .get_claims(self.blockstore(), &Address::new_id(miner_id))?
+            .into_iter()
+            .filter(|(_, claim)| claim.sector == sector)
+            .map(|(claim_id, claim)| ClaimInfo { claim_id, claim })
+            .sorted_by_key(|info| info.claim_id)
+            .collect())
+    }
+
     pub fn get_all_claims(&self, ts: &Tipset) -> anyhow::Result<HashMap<ClaimID, Claim>> {
         let state = self.get_verified_registry_actor_state(ts)?;
         state.get_all_claims(self.blockstore())
@@ -1505,30 +3634,30 @@ where
         state.get_all_allocations(self.blockstore())
     }
 
-    pub fn verified_client_status(
+    /// Returns the remaining FIL+ datacap allowance of `client` in the state
+    /// `state_cid`, in whole datacap tokens of one byte each. Clients without
+    /// datacap have a zero allowance.
+    pub fn get_verified_client_allowance(
         &self,
-        addr: &Address,
-        ts: &Tipset,
-    ) -> anyhow::Result<Option<DataCap>> {
-        let id = self.lookup_required_id(addr, ts)?;
-        let network_version = self.get_network_version(ts.epoch());
-
-        // This is a copy of Lotus code, we need to treat all the actors below version 9
-        // differently. Which maps to network below version 17.
-        // Original: https://github.com/filecoin-project/lotus/blob/5e76b05b17771da6939c7b0bf65127c3dc70ee23/node/impl/full/state.go#L1627-L1664.
-        if (u32::from(network_version.0)) < 17 {
-            let state = self.get_verified_registry_actor_state(ts)?;
-            return state.verified_client_data_cap(self.blockstore(), id.into());
-        }
-
-        let act = self
-            .get_actor(&Address::DATACAP_TOKEN_ACTOR, *ts.parent_state())
-            .map_err(|e| Error::State(e.to_string()))?
-            .ok_or_else(|| Error::State("Miner actor not found".to_string()))?;
-
-        let state = datacap::State::load(self.blockstore(), act.code, act.state)?;
-
-        state.verified_client_data_cap(self.blockstore(), id.into())
+        client: &Address,
+        state_cid: Cid,
+    ) -> Result<TokenAmount, Error> {
+        let state_tree = StateTree::new_from_root(self.blockstore_owned(), &state_cid)?;
+        let Some(id) = state_tree.lookup_id(client)? else {
+            return Ok(TokenAmount::zero());
+        };
+        let id = Address::new_id(id);
+        // Since actors v9, the datacap is held as tokens by the datacap actor
+        // rather than in the `DataCaps` table of the verified registry
+        let data_cap = match state_tree.get_actor(&Address::DATACAP_TOKEN_ACTOR)? {
+            Some(act) => datacap::State::load(self.blockstore(), act.code, act.state)?
+                .verified_client_data_cap(self.blockstore(), id)?,
+            None => {
+                let state: verifreg::State = state_tree.get_actor_state()?;
+                state.verified_client_data_cap(self.blockstore(), id)?
+            }
+        };
+        Ok(data_cap.map(TokenAmount::from_whole).unwrap_or_default())
     }
 
     pub async fn resolve_to_deterministic_address(
@@ -1598,6 +3727,82 @@ where
 
         Ok((state_root, invoc_trace))
     }
+
+    /// Applies `messages` at `epoch` on top of the state computed for
+    /// `tipset`, after the state migrations of the epochs in between. Like
+    /// Lotus, neither the block reward nor the cron is run. Nothing is
+    /// persisted in the chain. Returns the resulting state root and the
+    /// traced result of each message.
+    pub async fn state_compute(
+        self: &Arc<Self>,
+        epoch: ChainEpoch,
+        messages: Vec<Message>,
+        tipset: Arc<Tipset>,
+    ) -> Result<(Cid, Vec<ApiInvocResult>), Error> {
+        if epoch < tipset.epoch() {
+            return Err(Error::Other(format!(
+                "cannot compute the state at epoch {epoch} on top of a tipset at epoch {}",
+                tipset.epoch()
+            )));
+        }
+        let (mut state_root, _) = self.tipset_state(&tipset).await?;
+
+        let this = Arc::clone(self);
+        tokio::task::spawn_blocking(move || {
+            let store = this.blockstore_owned();
+            for epoch_i in tipset.epoch()..epoch {
+                if let Some(new_state) =
+                    run_state_migrations(epoch_i, this.chain_config(), &store, &state_root)?
+                {
+                    state_root = new_state;
+                }
+            }
+
+            let genesis_info = GenesisInfo::from_chain_config(this.chain_config().clone());
+            let timestamp = this.chain_store().genesis_block_header().timestamp
+                + this.chain_config().block_delay_secs as u64 * epoch as u64;
+            // FVM requires a stack size of 64MiB. The alternative is to use `ThreadedExecutor` from
+            // FVM, but that introduces some constraints, and possible deadlocks.
+            stacker::grow(64 << 20, || -> Result<(Cid, Vec<ApiInvocResult>), Error> {
+                let mut vm = VM::new(
+                    ExecutionContext {
+                        heaviest_tipset: Arc::clone(&tipset),
+                        state_tree_root: state_root,
+                        epoch,
+                        rand: Box::new(this.chain_rand(Arc::clone(&tipset))),
+                        base_fee: tipset.min_ticket_block().parent_base_fee.clone(),
+                        circ_supply: genesis_info.get_vm_circulating_supply(
+                            epoch,
+                            &store,
+                            &state_root,
+                        )?,
+                        chain_config: this.chain_config().clone(),
+                        chain_index: Arc::clone(&this.chain_store().chain_index),
+                        timestamp,
+                    },
+                    &this.engine,
+                    VMTrace::Traced,
+                )?;
+                let mut invoc_trace = vec![];
+                for msg in messages {
+                    let message = ChainMessage::Unsigned(msg);
+                    let (ret, duration) = vm.apply_message(&message)?;
+                    invoc_trace.push(ApiInvocResult {
+                        msg_cid: message.cid(),
+                        msg: message.message().clone(),
+                        msg_rct: Some(ret.msg_receipt()),
+                        error: ret.failure_info().unwrap_or_default(),
+                        duration: duration.as_nanos().clamp(0, u64::MAX as u128) as u64,
+                        gas_cost: MessageGasCost::new(message.message(), &ret)?,
+                        execution_trace: structured::parse_events(ret.exec_trace())
+                            .unwrap_or_default(),
+                    });
+                }
+                Ok((vm.flush()?, invoc_trace))
+            })
+        })
+        .await?
+    }
 }
 
 pub fn validate_tipsets<DB, T>(
@@ -1848,3 +4053,2661 @@ where
         })
     })
 }
+
+/// Groups the messages of `pairs` by tipset, the tipsets in the order they
+/// first appear in.
+fn group_by_tipset(pairs: &[(Cid, Arc<Tipset>)]) -> Vec<(Arc<Tipset>, CidHashSet)> {
+    let mut groups: Vec<(Arc<Tipset>, CidHashSet)> = vec![];
+    let mut positions: HashMap<&TipsetKey, usize> = HashMap::new();
+    for (mcid, ts) in pairs {
+        let position = *positions.entry(ts.key()).or_insert_with(|| {
+            groups.push((ts.clone(), CidHashSet::default()));
+            groups.len() - 1
+        });
+        groups[position].1.insert(*mcid);
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::{chain4u, CachingBlockHeader, Chain4U, HeaderBuilder};
+    use crate::db::MemoryDB;
+    use crate::lotus_json::{assert_all_snapshots, assert_unchanged_via_json};
+    use crate::networks::{Height, NetworkChain, ACTOR_BUNDLES_METADATA};
+    use crate::shim::machine::BuiltinActor;
+    use crate::shim::state_tree::StateTreeVersion;
+    use crate::utils::db::CborStoreExt as _;
+
+    fn calibnet_actor_code(bundle_version: &str, actor: BuiltinActor) -> Cid {
+        ACTOR_BUNDLES_METADATA
+            .get(&(NetworkChain::Calibnet, bundle_version.into()))
+            .unwrap()
+            .manifest
+            .get(actor)
+            .unwrap()
+    }
+
+    fn calibnet_miner_code(bundle_version: &str) -> Cid {
+        calibnet_actor_code(bundle_version, BuiltinActor::Miner)
+    }
+
+    fn state_with_actor(db: &Arc<MemoryDB>, addr: &Address, code: Cid) -> Cid {
+        let mut state_tree = StateTree::new(db.clone(), StateTreeVersion::V4).unwrap();
+        state_tree
+            .set_actor(
+                addr,
+                ActorState::new(code, Cid::default(), TokenAmount::zero(), 0, None),
+            )
+            .unwrap();
+        state_tree.flush().unwrap()
+    }
+
+    /// Returns the root of a state tree holding the given actors.
+    fn state_with_actors(
+        db: &Arc<MemoryDB>,
+        actors: impl IntoIterator<Item = (Address, ActorState)>,
+    ) -> Cid {
+        let mut state_tree = StateTree::new(db.clone(), StateTreeVersion::V5).unwrap();
+        for (addr, actor) in actors {
+            state_tree.set_actor(&addr, actor).unwrap();
+        }
+        state_tree.flush().unwrap()
+    }
+
+    /// Returns an actor of the given code holding `state`, without funds.
+    fn actor_with_state(db: &Arc<MemoryDB>, code: Cid, state: &impl Serialize) -> ActorState {
+        ActorState::new(
+            code,
+            db.put_cbor_default(state).unwrap(),
+            TokenAmount::zero(),
+            0,
+            None,
+        )
+    }
+
+    /// Returns a state manager over the chain of `genesis`, with the settings
+    /// held in memory.
+    fn state_manager_with_genesis<DB: Blockstore + Send + Sync + 'static>(
+        db: &Arc<DB>,
+        chain_config: Arc<ChainConfig>,
+        genesis: impl Into<CachingBlockHeader>,
+    ) -> Arc<StateManager<DB>> {
+        let settings = Arc::new(MemoryDB::default());
+        let chain_store = Arc::new(
+            ChainStore::new(
+                db.clone(),
+                settings.clone(),
+                settings,
+                chain_config.clone(),
+                genesis.into(),
+            )
+            .unwrap(),
+        );
+        Arc::new(
+            StateManager::new(chain_store, chain_config, Arc::new(SyncConfig::default())).unwrap(),
+        )
+    }
+
+    /// A state manager over a chain made of a genesis block at epoch 0, with
+    /// `state_root` as its state if given. Returns the genesis tipset along.
+    #[allow(unused_variables)]
+    fn state_manager_at_genesis(
+        db: &Arc<MemoryDB>,
+        chain_config: Arc<ChainConfig>,
+        state_root: Option<Cid>,
+    ) -> (Arc<StateManager<MemoryDB>>, Arc<Tipset>) {
+        let header = match state_root {
+            Some(state_root) => HeaderBuilder::new()
+                .with_epoch(0)
+                .with_state_root(state_root),
+            None => HeaderBuilder::new().with_epoch(0),
+        };
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            [genesis = header]
+        };
+        (
+            state_manager_with_genesis(db, chain_config, genesis.clone()),
+            Arc::new(Tipset::from(genesis.clone())),
+        )
+    }
+
+    #[test]
+    #[allow(unused_variables)]
+    fn test_actor_code_cid_at_nv17_boundary() {
+        let db = Arc::new(MemoryDB::default());
+        let chain_config = Arc::new(ChainConfig::calibnet());
+        let shark = chain_config.epoch(Height::Shark);
+        let miner = Address::new_id(1000);
+        let code_v8 = calibnet_miner_code("8.0.0-rc.1");
+        let code_v9 = calibnet_miner_code("v9.0.3");
+        let state_v8 = state_with_actor(&db, &miner, code_v8);
+        let state_v9 = state_with_actor(&db, &miner, code_v9);
+
+        // The migration runs at the upgrade epoch, so its result is the parent
+        // state of the following tipset.
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            [genesis = HeaderBuilder::new().with_epoch(shark - 2).with_state_root(state_v8)]
+            -> [b1 = HeaderBuilder::new().with_state_root(state_v8)]
+            -> [b2 = HeaderBuilder::new().with_state_root(state_v8)]
+            -> [b3 = HeaderBuilder::new().with_state_root(state_v9)]
+            -> head @ [b4 = HeaderBuilder::new().with_state_root(state_v9)]
+        };
+        let state_manager = state_manager_with_genesis(&db, chain_config, genesis.clone());
+        let head = Arc::new(head.clone());
+
+        assert_ne!(code_v8, code_v9);
+        for (epoch, code) in [
+            (shark - 1, code_v8),
+            (shark, code_v8),
+            (shark + 1, code_v9),
+            (shark + 2, code_v9),
+        ] {
+            assert_eq!(
+                state_manager
+                    .get_actor_code_cid_at_epoch(&miner, epoch, &head)
+                    .unwrap(),
+                Some(code),
+                "epoch {epoch}"
+            );
+        }
+        assert_eq!(
+            state_manager
+                .get_actor_code_cid_at_epoch(&Address::new_id(1001), shark + 1, &head)
+                .unwrap(),
+            None
+        );
+        assert!(state_manager
+            .get_actor_code_cid_at_epoch(&miner, shark + 3, &head)
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_beacon_entries_for_epoch_are_cached() {
+        use crate::beacon::{mock_beacon::MockBeacon, Beacon as _, BeaconPoint};
+
+        let beacon = BeaconSchedule(vec![BeaconPoint {
+            height: 0,
+            beacon: Box::<MockBeacon>::default(),
+        }]);
+        let cache = SyncMutex::new(LruCache::new(BEACON_ENTRIES_CACHE_SIZE));
+        let mock = MockBeacon::default();
+        let prev = mock.entry(9).await.unwrap();
+
+        let entries = beacon_entries_for_epoch(&beacon, &cache, NetworkVersion::V21, 10, 9, &prev)
+            .await
+            .unwrap();
+        assert_eq!(entries, [mock.entry(10).await.unwrap()]);
+        assert_eq!(cache.lock().peek(&(10, 9, 9)), Some(&entries));
+
+        // Served from the cache
+        cache.lock().put((10, 9, 9), vec![]);
+        assert!(
+            beacon_entries_for_epoch(&beacon, &cache, NetworkVersion::V21, 10, 9, &prev)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_apply_state_override() {
+        let db = Arc::new(MemoryDB::default());
+        let miner = Address::new_id(1000);
+        let miner_actor = |balance| {
+            ActorState::new(
+                calibnet_miner_code("v13.0.0"),
+                Cid::default(),
+                TokenAmount::from_whole(balance),
+                0,
+                None,
+            )
+        };
+        let mut state_tree = StateTree::new(db.clone(), StateTreeVersion::V5).unwrap();
+        state_tree.set_actor(&miner, miner_actor(10)).unwrap();
+        let state_root = state_tree.flush().unwrap();
+        fn read_actor(store: Arc<impl Blockstore>, root: Cid, addr: &Address) -> ActorState {
+            StateTree::new_from_root(store, &root)
+                .unwrap()
+                .get_required_actor(addr)
+                .unwrap()
+        }
+
+        // Double the balance of the miner
+        let store = Arc::new(OverlayDB::new(db.clone()));
+        let overridden = apply_state_override(
+            &store,
+            state_root,
+            [(miner, miner_actor(20))].into_iter().collect(),
+        )
+        .unwrap();
+        assert_eq!(
+            read_actor(store.clone(), overridden, &miner),
+            miner_actor(20)
+        );
+
+        // The base blockstore is left untouched
+        assert!(!db.has(&overridden).unwrap());
+        assert_eq!(read_actor(db.clone(), state_root, &miner), miner_actor(10));
+        assert_eq!(
+            apply_state_override(&store, state_root, StateOverride::default()).unwrap(),
+            state_root
+        );
+    }
+
+    /// Calibnet state before the Lightning upgrade, see the state migration
+    /// tests, along with the actor bundles.
+    async fn calibnet_pre_lightning_state() -> (
+        Arc<OverlayDB<crate::db::car::PlainCar<positioned_io::RandomAccessFile>>>,
+        Cid,
+    ) {
+        use crate::db::car::PlainCar;
+        use positioned_io::RandomAccessFile;
+        use std::str::FromStr as _;
+
+        let state_root =
+            Cid::from_str("bafy2bzacedgamjgha75e7w2cgklfdgtmumsj7nadqppnpz3wexl2wl6dexsle")
+                .unwrap();
+        let car_path = crate::state_migration::tests::fetch_state_car(&state_root).await;
+        let db = Arc::new(OverlayDB::new(Arc::new(
+            PlainCar::new(RandomAccessFile::open(&car_path).unwrap()).unwrap(),
+        )));
+        crate::daemon::bundle::load_actor_bundles(&db, &NetworkChain::Calibnet)
+            .await
+            .unwrap();
+        (db, state_root)
+    }
+
+    #[ignore = "flaky"]
+    #[tokio::test]
+    async fn test_call_with_state_override_withdraw_balance() {
+        use crate::blocks::RawBlockHeader;
+        use crate::chain_sync::TipsetValidator;
+        use fil_actor_miner_state::v16::{Method as MinerMethod, WithdrawBalanceParams};
+        use fvm_ipld_encoding::RawBytes;
+
+        let (db, state_root) = calibnet_pre_lightning_state().await;
+        let chain_config = Arc::new(ChainConfig::calibnet());
+        let header = CachingBlockHeader::new(RawBlockHeader {
+            epoch: chain_config.epoch(Height::Lightning) - 1,
+            state_root,
+            messages: TipsetValidator::compute_msg_root_from_cids(&db, vec![], vec![]).unwrap(),
+            ..Default::default()
+        });
+        db.put_cbor_default(&header).unwrap();
+        let state_manager = state_manager_with_genesis(&db, chain_config, header.clone());
+        let tipset = Arc::new(Tipset::from(header));
+
+        // Any miner with a balance
+        let store = db.as_ref();
+        let state_tree = StateTree::new_from_root(db.clone(), &state_root).unwrap();
+        let power_state: power::State = state_tree.get_actor_state().unwrap();
+        let (miner, miner_actor, miner_state) = power_state
+            .list_all_miners(store)
+            .unwrap()
+            .into_iter()
+            .find_map(|miner| {
+                let actor = state_tree.get_required_actor(&miner).unwrap();
+                let state = miner::State::load(store, actor.code, actor.state).unwrap();
+                actor.balance.is_positive().then_some((miner, actor, state))
+            })
+            .unwrap();
+        let owner = miner_state.info(store).unwrap().owner;
+        let available = miner_state
+            .available_balance(miner_actor.balance.atto())
+            .unwrap();
+        let mut doubled_actor = miner_actor.clone();
+        doubled_actor.balance = TokenAmount::from_atto(miner_actor.balance.atto() * 2).into();
+        let requested = miner_state
+            .available_balance(doubled_actor.balance.atto())
+            .unwrap();
+        assert!(requested > available);
+
+        let withdraw = Message {
+            from: owner,
+            to: miner,
+            method_num: MinerMethod::WithdrawBalance as u64,
+            params: RawBytes::serialize(WithdrawBalanceParams {
+                amount_requested: requested.clone().into(),
+            })
+            .unwrap(),
+            ..Default::default()
+        };
+        let withdrawn = |result: ApiInvocResult| {
+            let receipt = result.msg_rct.unwrap();
+            assert!(receipt.exit_code().is_success(), "{}", result.error);
+            receipt.return_data().deserialize::<TokenAmount>().unwrap()
+        };
+
+        // The withdrawal is capped by the available balance
+        let result = state_manager
+            .call_with_state_override(&withdraw, StateOverride::default(), &tipset)
+            .unwrap();
+        assert_eq!(withdrawn(result), available);
+        let result = state_manager
+            .call_with_state_override(
+                &withdraw,
+                [(miner, doubled_actor)].into_iter().collect(),
+                &tipset,
+            )
+            .unwrap();
+        assert_eq!(withdrawn(result), requested);
+    }
+
+    #[test]
+    fn test_lotus_json_snapshots() {
+        assert_all_snapshots::<MarketBalance>();
+        assert_all_snapshots::<PreCommitDepositInfo>();
+        assert_all_snapshots::<FeeDebtProjection>();
+        assert_all_snapshots::<SectorRewardEstimate>();
+        assert_all_snapshots::<SectorQualityAdjPower>();
+        assert_all_snapshots::<SectorPenalty>();
+        assert_all_snapshots::<SectorPenalties>();
+        assert_all_snapshots::<UpgradeCost>();
+        assert_all_snapshots::<MinerConsensusStatus>();
+        assert_all_snapshots::<BlockProducerStats>();
+        assert_all_snapshots::<VestingStats>();
+        assert_all_snapshots::<AddressForms>();
+        // `Claim` is not `quickcheck::Arbitrary`
+        assert_all_snapshots::<ClaimInfo>();
+        // `WorkerKeyChange` is not `quickcheck::Arbitrary`
+        assert_all_snapshots::<MinerControlInfo>();
+    }
+
+    quickcheck::quickcheck! {
+        fn market_balance_roundtrip(val: MarketBalance) -> () {
+            assert_unchanged_via_json(val)
+        }
+
+        fn pre_commit_deposit_info_roundtrip(val: PreCommitDepositInfo) -> () {
+            assert_unchanged_via_json(val)
+        }
+
+        fn fee_debt_projection_roundtrip(val: FeeDebtProjection) -> () {
+            assert_unchanged_via_json(val)
+        }
+
+        fn sector_reward_estimate_roundtrip(val: SectorRewardEstimate) -> () {
+            assert_unchanged_via_json(val)
+        }
+
+        fn sector_quality_adj_power_roundtrip(val: SectorQualityAdjPower) -> () {
+            assert_unchanged_via_json(val)
+        }
+
+        fn sector_penalties_roundtrip(val: SectorPenalties) -> () {
+            assert_unchanged_via_json(val)
+        }
+
+        fn upgrade_cost_roundtrip(val: UpgradeCost) -> () {
+            assert_unchanged_via_json(val)
+        }
+
+        fn block_producer_stats_roundtrip(val: BlockProducerStats) -> () {
+            assert_unchanged_via_json(val)
+        }
+
+        fn vesting_stats_roundtrip(val: VestingStats) -> () {
+            assert_unchanged_via_json(val)
+        }
+
+        fn address_forms_roundtrip(val: AddressForms) -> () {
+            assert_unchanged_via_json(val)
+        }
+    }
+
+    fn tipset_key(i: u64) -> TipsetKey {
+        use crate::utils::multihash::prelude::*;
+        TipsetKey::from(nunny::vec![Cid::new_v1(
+            fvm_ipld_encoding::DAG_CBOR,
+            MultihashCode::Blake2b256.digest(&i.to_be_bytes())
+        )])
+    }
+
+    #[tokio::test]
+    async fn test_tipset_state_cache_budget() {
+        const BUDGET: usize = 16 * 1024;
+        let cache = TipsetStateCache::new(KindLabel::new("test_sm_budget"), BUDGET);
+        for i in 0..10_000 {
+            let value = cache
+                .get_or_else(&tipset_key(i), || async {
+                    Ok(StateOutputValue {
+                        state_root: Cid::default(),
+                        receipt_root: Cid::default(),
+                    })
+                })
+                .await
+                .unwrap();
+            assert_eq!(value.state_root, Cid::default());
+            cache.with_inner(|inner| {
+                assert!(inner.values.size_bytes() <= BUDGET);
+                assert!(inner.pending.is_empty());
+            });
+        }
+        let entry_size = tipset_key(0).estimated_size() + std::mem::size_of::<StateOutputValue>();
+        cache.with_inner(|inner| assert_eq!(inner.values.len(), BUDGET / entry_size));
+    }
+
+    #[tokio::test]
+    async fn test_tipset_state_cache_pending_cleanup() {
+        let cache =
+            TipsetStateCache::<StateOutputValue>::new(KindLabel::new("test_sm_pending"), 1024);
+
+        // Failed computations are not left pending
+        let result = cache
+            .get_or_else(&tipset_key(0), || async { Err(anyhow::anyhow!("failed")) })
+            .await;
+        assert!(result.is_err());
+        cache.with_inner(|inner| assert!(inner.pending.is_empty()));
+
+        // Abandoned computations are dropped once the cap is reached
+        cache.with_inner(|inner| {
+            for i in 0..MAX_PENDING_TIPSET_COMPUTATIONS as u64 {
+                inner
+                    .pending
+                    .push((tipset_key(i), Arc::new(TokioMutex::new(()))));
+            }
+        });
+        let key = tipset_key(u64::MAX);
+        let (cache_ref, key_ref) = (&cache, &key);
+        cache
+            .get_or_else(&key, move || async move {
+                cache_ref.with_inner(|inner| {
+                    assert_eq!(inner.pending.len(), 1);
+                    assert_eq!(&inner.pending[0].0, key_ref);
+                });
+                Ok(StateOutputValue {
+                    state_root: Cid::default(),
+                    receipt_root: Cid::default(),
+                })
+            })
+            .await
+            .unwrap();
+        cache.with_inner(|inner| assert!(inner.pending.is_empty()));
+    }
+
+    #[test]
+    fn test_market_participant_info() {
+        let fil = |n: i64| TokenAmount::from_whole(n);
+        let info = MarketParticipantInfo::new(fil(10), fil(0));
+        assert_eq!(info.available_to_withdraw, fil(10));
+
+        // Publishing a deal locks part of the escrow
+        let info = MarketParticipantInfo::new(info.escrow, info.locked + fil(4));
+        assert_eq!(info.locked, fil(4));
+        assert_eq!(info.available_to_withdraw, fil(6));
+
+        // Withdrawing funds decreases the escrow but not the locked amount
+        let info = MarketParticipantInfo::new(info.escrow - fil(6), info.locked);
+        assert_eq!(info.available_to_withdraw, fil(0));
+        let info = MarketParticipantInfo::new(fil(3), info.locked);
+        assert_eq!(info.available_to_withdraw, fil(0));
+
+        assert_eq!(
+            MarketBalance::from(MarketParticipantInfo::new(fil(7), fil(2))),
+            MarketBalance {
+                escrow: fil(7),
+                locked: fil(2),
+            }
+        );
+    }
+
+    #[test]
+    fn test_fee_debt_projection() {
+        let fil = |n: i64| TokenAmount::from_whole(n);
+        let vesting = [(900, fil(100)), (1120, fil(2))];
+        let projection =
+            FeeDebtProjection::project(fil(1), fil(5), &fil(1), &vesting, 1000, 60, 600);
+        assert_eq!(projection.current_debt, fil(1));
+        assert_eq!(projection.projected_debt_at_epoch, fil(11));
+        assert_eq!(projection.unlocked_balance, fil(5));
+        assert_eq!(projection.insolvency_epoch, Some(1420));
+
+        let projection =
+            FeeDebtProjection::project(fil(0), fil(5), &fil(0), &vesting, 1000, 60, 600);
+        assert_eq!(projection.projected_debt_at_epoch, fil(0));
+        assert_eq!(projection.insolvency_epoch, None);
+    }
+
+    #[test]
+    fn test_replay_batch_groups_by_tipset() {
+        let c4u = Chain4U::new();
+        chain4u! {
+            in c4u;
+            [_genesis]
+            -> t1 @ [_b1]
+            -> t2 @ [_b2]
+        };
+        let (t1, t2) = (Arc::new(t1.clone()), Arc::new(t2.clone()));
+        let cid = |n: u64| tipset_key(n).into_cids().first().copied().unwrap();
+        let pairs = [
+            (cid(1), t2.clone()),
+            (cid(2), t1.clone()),
+            (cid(3), t2.clone()),
+            // Requested twice
+            (cid(1), t2.clone()),
+        ];
+
+        // Each tipset is executed once, in the order of the requests
+        let groups = group_by_tipset(&pairs);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].0, t2);
+        assert_eq!(groups[0].1.len(), 2);
+        assert!(groups[0].1.contains(&cid(1)) && groups[0].1.contains(&cid(3)));
+        assert_eq!(groups[1].0, t1);
+        assert!(groups[1].1.contains(&cid(2)) && !groups[1].1.contains(&cid(1)));
+        assert!(group_by_tipset(&[]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_state_compute_rejects_past_epoch() {
+        let db = Arc::new(MemoryDB::default());
+        let chain_config = Arc::new(ChainConfig::calibnet());
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            [genesis = HeaderBuilder::new().with_epoch(0)]
+            -> head @ [b1 = HeaderBuilder::new().with_epoch(10)]
+        };
+        let state_manager = state_manager_with_genesis(&db, chain_config, genesis.clone());
+
+        let err = state_manager
+            .state_compute(9, vec![], Arc::new(head.clone()))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("epoch 9"), "{err}");
+    }
+
+    /// Returns a state manager whose heaviest tipset is a child of the
+    /// calibnet state before the Lightning upgrade, mined by a miner with a
+    /// funded account owner, and that owner.
+    async fn calibnet_pre_lightning_tipset() -> (
+        Arc<OverlayDB<crate::db::car::PlainCar<positioned_io::RandomAccessFile>>>,
+        Arc<StateManager<OverlayDB<crate::db::car::PlainCar<positioned_io::RandomAccessFile>>>>,
+        Arc<Tipset>,
+        Address,
+    ) {
+        use crate::blocks::{ElectionProof, RawBlockHeader, VRFProof};
+        use crate::chain_sync::TipsetValidator;
+
+        let (db, state_root) = calibnet_pre_lightning_state().await;
+        let chain_config = Arc::new(ChainConfig::calibnet());
+        let state_tree = StateTree::new_from_root(db.clone(), &state_root).unwrap();
+        let store = db.as_ref();
+
+        // A miner to reward for the block, and an account with some funds
+        let account_code = calibnet_actor_code("v12.0.0", BuiltinActor::Account);
+        let power_state: power::State = state_tree.get_actor_state().unwrap();
+        let (miner, owner) = power_state
+            .list_all_miners(store)
+            .unwrap()
+            .into_iter()
+            .find_map(|miner| {
+                let actor = state_tree.get_required_actor(&miner).unwrap();
+                let state = miner::State::load(store, actor.code, actor.state).unwrap();
+                let owner = state.info(store).unwrap().owner;
+                let owner_actor = state_tree.get_required_actor(&owner).unwrap();
+                (owner_actor.code == account_code
+                    && TokenAmount::from(owner_actor.balance) > TokenAmount::from_whole(2))
+                .then_some((miner, owner))
+            })
+            .unwrap();
+
+        // A parent for the tipset, the genesis of the store
+        let messages = TipsetValidator::compute_msg_root_from_cids(&db, vec![], vec![]).unwrap();
+        let parent = CachingBlockHeader::new(RawBlockHeader {
+            epoch: chain_config.epoch(Height::Lightning) - 2,
+            state_root,
+            messages,
+            ..Default::default()
+        });
+        db.put_cbor_default(&parent).unwrap();
+        let header = CachingBlockHeader::new(RawBlockHeader {
+            epoch: parent.epoch + 1,
+            parents: TipsetKey::from(nunny::vec![*parent.cid()]),
+            state_root,
+            messages,
+            miner_address: miner,
+            election_proof: Some(ElectionProof {
+                win_count: 1,
+                vrfproof: VRFProof::new(vec![]),
+            }),
+            ..Default::default()
+        });
+        db.put_cbor_default(&header).unwrap();
+        let state_manager = state_manager_with_genesis(&db, chain_config, parent);
+        let tipset = Arc::new(Tipset::from(header));
+        (db, state_manager, tipset, owner)
+    }
+
+    #[ignore = "flaky"]
+    #[tokio::test]
+    async fn test_state_compute_send() {
+        let (db, state_manager, tipset, sender) = calibnet_pre_lightning_tipset().await;
+        let recipient = Address::new_secp256k1(&[7; 65]).unwrap();
+        let value = TokenAmount::from_whole(1);
+
+        let (base_state, _) = state_manager.tipset_state(&tipset).await.unwrap();
+        let state_tree = StateTree::new_from_root(db.clone(), &base_state).unwrap();
+        assert!(state_tree.get_actor(&recipient).unwrap().is_none());
+        let sender_actor = state_tree.get_required_actor(&sender).unwrap();
+        let send = Message {
+            from: sender,
+            to: recipient,
+            sequence: sender_actor.sequence,
+            value: value.clone(),
+            gas_limit: 100_000_000,
+            gas_fee_cap: TokenAmount::from_atto(1_000_000_000),
+            ..Default::default()
+        };
+        let (root, results) = state_manager
+            .state_compute(tipset.epoch(), vec![send.clone()], tipset.clone())
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].msg_cid, send.cid());
+        assert!(
+            results[0]
+                .msg_rct
+                .as_ref()
+                .unwrap()
+                .exit_code()
+                .is_success(),
+            "{}",
+            results[0].error
+        );
+
+        // Only the message is applied on top of the base state, without a
+        // reward or a cron
+        let state_tree = StateTree::new_from_root(db.clone(), &root).unwrap();
+        assert_eq!(
+            TokenAmount::from(state_tree.get_required_actor(&recipient).unwrap().balance),
+            value
+        );
+        let sender_after = state_tree.get_required_actor(&sender).unwrap();
+        assert_eq!(sender_after.sequence, sender_actor.sequence + 1);
+        assert_eq!(
+            TokenAmount::from(sender_after.balance),
+            TokenAmount::from(sender_actor.balance)
+                - &value
+                - results[0].gas_cost.total_cost.clone()
+        );
+        assert!(state_manager
+            .state_compute(tipset.epoch(), vec![], tipset)
+            .await
+            .is_ok_and(|(root, results)| root == base_state && results.is_empty()));
+    }
+
+    #[ignore = "flaky"]
+    #[tokio::test]
+    async fn test_call_with_gas_pending_messages() {
+        use crate::shim::crypto::Signature;
+
+        /// Pending messages of a single key address.
+        struct Pending(Address, Vec<SignedMessage>);
+
+        impl PendingMessageSource for Pending {
+            fn pending_messages(&self, from: &Address) -> Vec<SignedMessage> {
+                if *from == self.0 {
+                    self.1.clone()
+                } else {
+                    vec![]
+                }
+            }
+        }
+
+        let (db, state_manager, tipset, sender) = calibnet_pre_lightning_tipset().await;
+        let (base_state, _) = state_manager.tipset_state(&tipset).await.unwrap();
+        let sequence = StateTree::new_from_root(db, &base_state)
+            .unwrap()
+            .get_required_actor(&sender)
+            .unwrap()
+            .sequence;
+        let key = state_manager
+            .resolve_to_key_addr(&sender, &tipset)
+            .await
+            .unwrap();
+        let send = |sequence| Message {
+            from: key,
+            to: Address::new_secp256k1(&[7; 65]).unwrap(),
+            sequence,
+            value: TokenAmount::from_atto(1),
+            gas_limit: 100_000_000,
+            gas_fee_cap: TokenAmount::from_atto(1_000_000_000),
+            ..Default::default()
+        };
+        // Signatures are not checked by the VM
+        let pending = Pending(
+            key,
+            [sequence + 1, sequence]
+                .into_iter()
+                .map(|sequence| {
+                    SignedMessage::new_unchecked(
+                        send(sequence),
+                        Signature::new_secp256k1(vec![0; 65]),
+                    )
+                })
+                .collect(),
+        );
+
+        let call = |prior_messages| {
+            let state_manager = state_manager.clone();
+            let tipset = tipset.clone();
+            let mut message = ChainMessage::Unsigned(Message {
+                from: sender,
+                ..send(0)
+            });
+            async move {
+                let (invoc, ret) = state_manager
+                    .call_with_gas(
+                        &mut message,
+                        prior_messages,
+                        Some(tipset),
+                        VMTrace::NotTraced,
+                    )
+                    .await
+                    .unwrap();
+                assert!(
+                    ret.msg_receipt().exit_code().is_success(),
+                    "{:?}",
+                    invoc.error
+                );
+                invoc.msg.sequence
+            }
+        };
+        // The pending messages of the sender are applied first, by sequence,
+        // so the message follows them
+        assert_eq!(call(PriorMessages::Pending(&pending)).await, sequence + 2);
+        assert_eq!(call(PriorMessages::Given(&[])).await, sequence);
+        let given = state_manager
+            .pending_prior_messages_of(&pending, &sender, &tipset)
+            .await;
+        assert_eq!(given.len(), 2);
+        assert_eq!(call(PriorMessages::Given(&given)).await, sequence + 2);
+        // Senders without pending messages
+        assert!(state_manager
+            .pending_prior_messages_of(&pending, &Address::SYSTEM_ACTOR, &tipset)
+            .await
+            .is_empty());
+    }
+
+    #[ignore = "flaky"]
+    #[tokio::test]
+    async fn test_simulate_message_batch() {
+        let (db, state_manager, tipset, sender) = calibnet_pre_lightning_tipset().await;
+        let (base_state, _) = state_manager.tipset_state(&tipset).await.unwrap();
+        let sender_actor = StateTree::new_from_root(db.clone(), &base_state)
+            .unwrap()
+            .get_required_actor(&sender)
+            .unwrap();
+        let recipient = Address::new_secp256k1(&[7; 65]).unwrap();
+        let send = |from, to| Message {
+            from,
+            to,
+            value: TokenAmount::from_whole(1),
+            gas_limit: 100_000_000,
+            gas_fee_cap: TokenAmount::from_atto(1_000_000_000),
+            ..Default::default()
+        };
+        let succeeded = |result: &InvocResult| {
+            result
+                .msg_rct
+                .as_ref()
+                .is_some_and(|receipt| receipt.exit_code().is_success())
+        };
+
+        // The recipient only exists, and can send funds back, once the first
+        // message is applied
+        let results = state_manager
+            .simulate_message_batch(
+                vec![send(sender, recipient), send(recipient, sender)],
+                None,
+                tipset.clone(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(
+            results.iter().all(succeeded),
+            "{:?}",
+            results.iter().map(|result| &result.error).collect_vec()
+        );
+        assert_eq!(results[0].msg.sequence, sender_actor.sequence);
+        assert_eq!(results[1].msg.sequence, 0);
+        assert!(state_manager
+            .simulate_message_batch(vec![send(recipient, sender)], None, tipset.clone())
+            .await
+            .is_err());
+
+        // Messages of the same sender get consecutive sequences
+        let results = state_manager
+            .simulate_message_batch(
+                vec![send(sender, recipient), send(sender, recipient)],
+                None,
+                tipset.clone(),
+            )
+            .await
+            .unwrap();
+        assert!(
+            results.iter().all(succeeded),
+            "{:?}",
+            results.iter().map(|result| &result.error).collect_vec()
+        );
+        assert_eq!(results[1].msg.sequence, sender_actor.sequence + 1);
+
+        // A sender without funds in the overridden state cannot pay for gas
+        let mut broke = sender_actor.clone();
+        broke.balance = TokenAmount::default().into();
+        let results = state_manager
+            .simulate_message_batch(
+                vec![send(sender, recipient)],
+                Some([(sender, broke)].into_iter().collect()),
+                tipset.clone(),
+            )
+            .await
+            .unwrap();
+        assert!(!succeeded(&results[0]));
+
+        // Nothing is persisted
+        assert_eq!(
+            state_manager.tipset_state(&tipset).await.unwrap().0,
+            base_state
+        );
+        assert!(StateTree::new_from_root(db, &base_state)
+            .unwrap()
+            .get_actor(&recipient)
+            .unwrap()
+            .is_none());
+    }
+
+    #[ignore = "flaky"]
+    #[tokio::test]
+    async fn test_get_sector_upgrade_cost() {
+        use fvm_shared4::econ::TokenAmount as TokenAmountV4;
+
+        let (db, state_manager, tipset, _) = calibnet_pre_lightning_tipset().await;
+        let store = db.as_ref();
+        let state_root = *tipset.parent_state();
+        let epoch = tipset.epoch();
+        let state_tree = StateTree::new_from_root(db.clone(), &state_root).unwrap();
+
+        // Any miner with an active sector
+        let policy = &state_manager.chain_config().policy;
+        let power_state: power::State = state_tree.get_actor_state().unwrap();
+        let (miner, miner_actor, sector) = power_state
+            .list_all_miners(store)
+            .unwrap()
+            .into_iter()
+            .find_map(|miner| {
+                let actor = state_tree.get_required_actor(&miner).unwrap();
+                let state = miner::State::load(store, actor.code, actor.state).unwrap();
+                let active = state
+                    .partition_sectors_union(policy, store, |partition| partition.active_sectors())
+                    .unwrap();
+                let sector = active.iter().next()?;
+                Some((miner, actor, sector))
+            })
+            .unwrap();
+
+        // The miner actor formulas, on the v12 actor states
+        let miner_state: fil_actor_miner_state::v12::State =
+            store.get_cbor_required(&miner_actor.state).unwrap();
+        let info = miner_state.get_sector(store, sector).unwrap().unwrap();
+        let reward_state: fil_actor_reward_state::v12::State = store
+            .get_cbor_required(
+                &state_tree
+                    .get_required_actor(&Address::REWARD_ACTOR)
+                    .unwrap()
+                    .state,
+            )
+            .unwrap();
+        let power_state: fil_actor_power_state::v12::State = store
+            .get_cbor_required(
+                &state_tree
+                    .get_required_actor(&Address::POWER_ACTOR)
+                    .unwrap()
+                    .state,
+            )
+            .unwrap();
+        let circ_supply = GenesisInfo::from_chain_config(state_manager.chain_config().clone())
+            .get_vm_circulating_supply(epoch, &db, &state_root)
+            .unwrap();
+        // Without deals, the quality adjusted power is the sector size
+        let new_pledge = fil_actor_miner_state::v12::initial_pledge_for_power(
+            &BigInt::from(info.seal_proof.sector_size().unwrap() as u64),
+            &reward_state.this_epoch_baseline_power,
+            &reward_state.this_epoch_reward_smoothed,
+            &power_state.this_epoch_qa_power_smoothed,
+            &TokenAmountV4::from_atto(circ_supply.atto().clone()),
+        );
+        let additional_pledge = if new_pledge > info.initial_pledge {
+            new_pledge - &info.initial_pledge
+        } else {
+            TokenAmountV4::zero()
+        };
+        let aggregate_fee = fil_actor_miner_state::v12::aggregate_prove_commit_network_fee(
+            1,
+            &TokenAmountV4::from_atto(
+                tipset
+                    .block_headers()
+                    .first()
+                    .parent_base_fee
+                    .atto()
+                    .clone(),
+            ),
+        );
+        let available = miner_state
+            .get_available_balance(&TokenAmountV4::from_atto(
+                miner_actor.balance.atto().clone(),
+            ))
+            .unwrap();
+
+        let cost = state_manager
+            .get_sector_upgrade_cost(&miner, sector, &[], &tipset)
+            .unwrap();
+        assert_eq!(
+            cost,
+            UpgradeCost {
+                has_sufficient_funds: available >= &additional_pledge + &aggregate_fee,
+                additional_pledge: additional_pledge.into(),
+                aggregate_fee: aggregate_fee.into(),
+            }
+        );
+        assert!(cost.aggregate_fee.is_positive());
+
+        // Unknown sectors and deals
+        assert!(state_manager
+            .get_sector_upgrade_cost(&miner, u64::MAX >> 1, &[], &tipset)
+            .is_err());
+        assert!(state_manager
+            .get_sector_upgrade_cost(&miner, sector, &[u64::MAX >> 1], &tipset)
+            .is_err());
+    }
+
+    #[test]
+    fn test_get_sector_upgrade_cost_synthetic() {
+        use fil_actor_miner_state::v13::{
+            MinerInfo as MinerInfoV13, SectorOnChainInfo as SectorOnChainInfoV13,
+            State as MinerStateV13,
+        };
+        use fvm_shared4::econ::TokenAmount as TokenAmountV4;
+        use fvm_shared4::sector::{RegisteredPoStProof, RegisteredSealProof, SectorSize};
+
+        let db = Arc::new(MemoryDB::default());
+        let chain_config = Arc::new(ChainConfig::calibnet());
+        let miner = Address::new_id(1000);
+        let epoch = 1000;
+        let base_fee = TokenAmount::from_nano(100);
+        let info = MinerInfoV13::new(
+            1,
+            1,
+            vec![],
+            vec![],
+            vec![],
+            RegisteredPoStProof::StackedDRGWindow32GiBV1P1,
+        )
+        .unwrap();
+        let reward_state = fil_actor_reward_state::v13::State::new(BigInt::from(1u64 << 50));
+        let power_state = fil_actor_power_state::v13::State::new(&db).unwrap();
+        let market_state = fil_actor_market_state::v13::State::new(&db).unwrap();
+        let account_code = calibnet_actor_code("v13.0.0", BuiltinActor::Account);
+        // State root with the miner holding sector 7, pledged with
+        // `initial_pledge`, and `balance` in its actor
+        let state_at = |initial_pledge: &TokenAmountV4, balance: TokenAmount| {
+            let sector = SectorOnChainInfoV13 {
+                sector_number: 7,
+                seal_proof: RegisteredSealProof::StackedDRG32GiBV1P1,
+                activation: 0,
+                expiration: epoch + 540 * crate::shim::clock::EPOCHS_IN_DAY,
+                initial_pledge: initial_pledge.clone(),
+                ..Default::default()
+            };
+            let mut miner_state = MinerStateV13::new(
+                &chain_config.policy,
+                &db,
+                db.put_cbor_default(&info).unwrap(),
+                0,
+                0,
+            )
+            .unwrap();
+            miner_state.put_sectors(&db, vec![sector]).unwrap();
+            let mut miner_actor =
+                actor_with_state(&db, calibnet_miner_code("v13.0.0"), &miner_state);
+            miner_actor.balance = balance.into();
+            state_with_actors(
+                &db,
+                [
+                    (miner, miner_actor),
+                    (
+                        Address::REWARD_ACTOR,
+                        actor_with_state(
+                            &db,
+                            calibnet_actor_code("v13.0.0", BuiltinActor::Reward),
+                            &reward_state,
+                        ),
+                    ),
+                    (
+                        Address::POWER_ACTOR,
+                        actor_with_state(
+                            &db,
+                            calibnet_actor_code("v13.0.0", BuiltinActor::Power),
+                            &power_state,
+                        ),
+                    ),
+                    (
+                        Address::MARKET_ACTOR,
+                        actor_with_state(
+                            &db,
+                            calibnet_actor_code("v13.0.0", BuiltinActor::Market),
+                            &market_state,
+                        ),
+                    ),
+                    (
+                        Address::BURNT_FUNDS_ACTOR,
+                        ActorState::new_empty(account_code, None),
+                    ),
+                    (
+                        Address::RESERVE_ACTOR,
+                        ActorState::new_empty(account_code, None),
+                    ),
+                ],
+            )
+        };
+
+        // The miner actor formulas, without deals the quality adjusted power
+        // is the sector size
+        let circ_supply = |state_root: &Cid| {
+            GenesisInfo::from_chain_config(chain_config.clone())
+                .get_vm_circulating_supply(epoch, &db, state_root)
+                .unwrap()
+        };
+        let unpledged = state_at(&TokenAmountV4::zero(), TokenAmount::zero());
+        let new_pledge = fil_actor_miner_state::v13::initial_pledge_for_power(
+            &BigInt::from(SectorSize::_32GiB as u64),
+            &reward_state.this_epoch_baseline_power,
+            &reward_state.this_epoch_reward_smoothed,
+            &power_state.this_epoch_qa_power_smoothed,
+            &TokenAmountV4::from_atto(circ_supply(&unpledged).atto().clone()),
+        );
+        assert!(new_pledge.is_positive());
+        let aggregate_fee: TokenAmount =
+            fil_actor_miner_state::v13::aggregate_prove_commit_network_fee(
+                1,
+                &TokenAmountV4::from_atto(base_fee.atto().clone()),
+            )
+            .into();
+        assert!(aggregate_fee.is_positive());
+        let required: TokenAmount = TokenAmount::from(new_pledge.clone()) + aggregate_fee.clone();
+        let funded = state_at(&TokenAmountV4::zero(), required.clone());
+        let underfunded = state_at(
+            &TokenAmountV4::zero(),
+            required.clone() - TokenAmount::from_atto(1),
+        );
+        // Sectors pledged above the new requirement are never refunded, the
+        // fee is still due
+        let overpledged = state_at(&(new_pledge.clone() * 2), TokenAmount::zero());
+
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            [genesis = HeaderBuilder::new().with_epoch(0)]
+            -> t1 @ [_b1 = HeaderBuilder::new().with_epoch(epoch).with_state_root(unpledged).with_parent_base_fee(base_fee.clone())]
+            -> t2 @ [_b2 = HeaderBuilder::new().with_epoch(epoch).with_state_root(funded).with_parent_base_fee(base_fee.clone())]
+            -> t3 @ [_b3 = HeaderBuilder::new().with_epoch(epoch).with_state_root(underfunded).with_parent_base_fee(base_fee.clone())]
+            -> t4 @ [_b4 = HeaderBuilder::new().with_epoch(epoch).with_state_root(overpledged).with_parent_base_fee(base_fee.clone())]
+        };
+        let state_manager = state_manager_with_genesis(&db, chain_config.clone(), genesis.clone());
+        let cost = |tipset: &Tipset| {
+            state_manager
+                .get_sector_upgrade_cost(&miner, 7, &[], tipset)
+                .unwrap()
+        };
+
+        let upgrade = |has_sufficient_funds| UpgradeCost {
+            additional_pledge: new_pledge.clone().into(),
+            aggregate_fee: aggregate_fee.clone(),
+            has_sufficient_funds,
+        };
+        // The circulating supply does not depend on the miner balance
+        assert_eq!(circ_supply(&unpledged), circ_supply(&funded));
+        assert_eq!(cost(t1), upgrade(false));
+        assert_eq!(cost(t2), upgrade(true));
+        assert_eq!(cost(t3), upgrade(false));
+        assert_eq!(
+            cost(t4),
+            UpgradeCost {
+                additional_pledge: TokenAmount::zero(),
+                aggregate_fee: aggregate_fee.clone(),
+                has_sufficient_funds: false,
+            }
+        );
+
+        // Unknown sectors and deals
+        assert!(state_manager
+            .get_sector_upgrade_cost(&miner, 8, &[], t1)
+            .is_err());
+        assert!(state_manager
+            .get_sector_upgrade_cost(&miner, 7, &[1], t1)
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_actor_sequence() {
+        let db = Arc::new(MemoryDB::default());
+        let robust = Address::new_secp256k1(&[7; 65]).unwrap();
+        let mut init_state =
+            fil_actor_init_state::v11::State::new(&db, "sequencetest".into()).unwrap();
+        let id = init_state
+            .map_address_to_new_id(&db, &robust.into())
+            .unwrap();
+        let mut state_tree = StateTree::new(db.clone(), StateTreeVersion::V5).unwrap();
+        state_tree
+            .set_actor(
+                &Address::INIT_ACTOR,
+                ActorState::new(
+                    Cid::default(),
+                    db.put_cbor_default(&init_state).unwrap(),
+                    TokenAmount::zero(),
+                    0,
+                    None,
+                ),
+            )
+            .unwrap();
+        state_tree
+            .set_actor(
+                &Address::new_id(id),
+                ActorState::new(Cid::default(), Cid::default(), TokenAmount::zero(), 7, None),
+            )
+            .unwrap();
+        let state_root = state_tree.flush().unwrap();
+
+        let (state_manager, ts) =
+            state_manager_at_genesis(&db, Arc::new(ChainConfig::calibnet()), Some(state_root));
+
+        for addr in [robust, Address::new_id(id)] {
+            assert_eq!(
+                state_manager.get_actor_sequence(&addr, &ts).await.unwrap(),
+                7
+            );
+        }
+        assert!(state_manager
+            .get_actor_sequence(&Address::new_id(id + 1), &ts)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_get_miner_proving_period_start() {
+        let db = Arc::new(MemoryDB::default());
+        let chain_config = Arc::new(ChainConfig::calibnet());
+        let policy = &chain_config.policy;
+        let miner = Address::new_id(1000);
+        let miner_state =
+            fil_actor_miner_state::v13::State::new(policy, &db, Cid::default(), 1234, 0).unwrap();
+        let state_root = state_with_actors(
+            &db,
+            [(
+                miner,
+                actor_with_state(&db, calibnet_miner_code("v13.0.0"), &miner_state),
+            )],
+        );
+
+        let (state_manager, _) = state_manager_at_genesis(&db, chain_config, Some(state_root));
+        let ms = miner::State::load(
+            &db,
+            calibnet_miner_code("v13.0.0"),
+            db.put_cbor_default(&miner_state).unwrap(),
+        )
+        .unwrap();
+
+        for curr_epoch in [1234, 1235, 1234 + policy.wpost_proving_period, 100_000] {
+            let period_start = state_manager
+                .get_miner_proving_period_start(&miner, curr_epoch, state_root)
+                .unwrap();
+            assert_eq!(
+                period_start,
+                ms.deadline_info(policy, curr_epoch).period_start,
+                "epoch {curr_epoch}"
+            );
+            assert!(period_start <= curr_epoch);
+            assert!(curr_epoch < period_start + policy.wpost_proving_period);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_miner_sector_count_at_epoch() {
+        use fil_actor_miner_state::v13::{Deadline, Partition, State as MinerStateV13};
+        use fil_actors_shared::v13::Array;
+
+        let db = Arc::new(MemoryDB::default());
+        let chain_config = Arc::new(ChainConfig::calibnet());
+        let policy = &chain_config.policy;
+        let miner = Address::new_id(1000);
+        // State root with the miner holding `sectors` in a single partition
+        let state_root = |sectors: &[u64], terminated: &[u64]| {
+            let mut partition = Partition::new(&db).unwrap();
+            partition.sectors = bitfield_of(sectors.iter().copied());
+            partition.terminated = bitfield_of(terminated.iter().copied());
+            let mut partitions = Array::<Partition, _>::new_with_bit_width(&db, 3);
+            partitions.set(0, partition).unwrap();
+            let mut deadline = Deadline::new(&db).unwrap();
+            deadline.partitions = partitions.flush().unwrap();
+            let mut miner_state = MinerStateV13::new(policy, &db, Cid::default(), 0, 0).unwrap();
+            let mut deadlines = miner_state.load_deadlines(&db).unwrap();
+            deadlines.due[0] = db.put_cbor_default(&deadline).unwrap();
+            miner_state.deadlines = db.put_cbor_default(&deadlines).unwrap();
+            state_with_actors(
+                &db,
+                [(
+                    miner,
+                    actor_with_state(&db, calibnet_miner_code("v13.0.0"), &miner_state),
+                )],
+            )
+        };
+        let before = state_root(&[1, 2], &[]);
+        // `ProveCommitSector` adds sector 3
+        let proven = state_root(&[1, 2, 3], &[]);
+        // `TerminateSectors` terminates sector 2
+        let terminated = state_root(&[1, 2, 3], &[2]);
+
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            [genesis = HeaderBuilder::new().with_epoch(0)]
+            -> t1 @ [_b1 = HeaderBuilder::new().with_epoch(1)]
+            -> t2 @ [_b2 = HeaderBuilder::new().with_epoch(2)]
+            -> t3 @ [_b3 = HeaderBuilder::new().with_epoch(3)]
+        };
+        let state_manager = state_manager_with_genesis(&db, chain_config.clone(), genesis.clone());
+        // The tipsets have no messages to execute, seed their resulting states
+        for (ts, state_root) in [(t1, before), (t2, proven), (t3, terminated)] {
+            state_manager
+                .cache
+                .get_or_else(ts.key(), || async move {
+                    Ok(StateOutputValue {
+                        state_root,
+                        receipt_root: Cid::default(),
+                    })
+                })
+                .await
+                .unwrap();
+        }
+
+        let head = Arc::new(t3.clone());
+        let count = |epoch| {
+            let (state_manager, head) = (&state_manager, &head);
+            async move {
+                state_manager
+                    .get_miner_sector_count_at_epoch(&miner, epoch, head)
+                    .await
+                    .unwrap()
+            }
+        };
+        assert_eq!(count(1).await, MinerSectors::new(2, 2, 0));
+        assert_eq!(count(2).await, MinerSectors::new(3, 3, 0));
+        assert_eq!(count(3).await, MinerSectors::new(2, 2, 0));
+        assert_eq!(state_manager.miner_sector_count_cache.lock().len(), 3);
+        // Served from the cache
+        assert_eq!(count(2).await, MinerSectors::new(3, 3, 0));
+        assert_eq!(state_manager.miner_sector_count_cache.lock().len(), 3);
+    }
+
+    #[test]
+    fn test_get_sector_active_claims() {
+        use fil_actor_verifreg_state::v13::{Claim as ClaimV13, State as VerifregStateV13};
+
+        let db = Arc::new(MemoryDB::default());
+        let chain_config = Arc::new(ChainConfig::calibnet());
+        let miner = Address::new_id(1000);
+        let claim = |client, term_start, sector| ClaimV13 {
+            provider: 1000,
+            client,
+            data: Cid::default(),
+            size: fvm_shared4::piece::PaddedPieceSize(2048),
+            term_min: 100,
+            term_max: 200,
+            term_start,
+            sector,
+        };
+        let mut verifreg_state = VerifregStateV13::new(&db, Address::new_id(80).into()).unwrap();
+        let mut claims = verifreg_state.load_claims(&db).unwrap();
+        for (claim_id, claim) in [
+            (3, claim(2001, 20, 7)),
+            (1, claim(2000, 10, 7)),
+            (2, claim(2000, 10, 8)),
+        ] {
+            claims.put(1000, claim_id, claim).unwrap();
+        }
+        verifreg_state.save_claims(&mut claims).unwrap();
+        let state_root = state_with_actors(
+            &db,
+            [(
+                Address::VERIFIED_REGISTRY_ACTOR,
+                actor_with_state(
+                    &db,
+                    calibnet_actor_code("v13.0.0", BuiltinActor::VerifiedRegistry),
+                    &verifreg_state,
+                ),
+            )],
+        );
+
+        let (state_manager, _) = state_manager_at_genesis(&db, chain_config, None);
+
+        let claims = state_manager
+            .get_sector_active_claims(&miner, 7, state_root)
+            .unwrap();
+        assert_eq!(
+            claims
+                .iter()
+                .map(|info| (
+                    info.claim_id,
+                    info.claim.client,
+                    info.claim.term_start,
+                    info.claim.term_max
+                ))
+                .collect_vec(),
+            vec![(1, 2000, 10, 200), (3, 2001, 20, 200)]
+        );
+        assert!(state_manager
+            .get_sector_active_claims(&miner, 9, state_root)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_get_verified_client_allowance() {
+        use fil_actor_datacap_state::v13::State as DataCapStateV13;
+
+        let db = Arc::new(MemoryDB::default());
+        let chain_config = Arc::new(ChainConfig::calibnet());
+        let client = Address::new_id(2000);
+        let mut datacap_state =
+            DataCapStateV13::new(&db, Address::VERIFIED_REGISTRY_ACTOR.into()).unwrap();
+        // Minted 10 bytes of datacap, then spent 3 on an allocation
+        let minted = fvm_shared4::econ::TokenAmount::from_whole(10);
+        let spent = fvm_shared4::econ::TokenAmount::from_whole(3);
+        datacap_state
+            .token
+            .change_balance_by(&db, client.id().unwrap(), &minted)
+            .unwrap();
+        datacap_state.token.change_supply_by(&minted).unwrap();
+        datacap_state
+            .token
+            .change_balance_by(&db, client.id().unwrap(), &-spent.clone())
+            .unwrap();
+        datacap_state.token.change_supply_by(&-spent).unwrap();
+        let state_root = state_with_actors(
+            &db,
+            [(
+                Address::DATACAP_TOKEN_ACTOR,
+                actor_with_state(
+                    &db,
+                    calibnet_actor_code("v13.0.0", BuiltinActor::DataCap),
+                    &datacap_state,
+                ),
+            )],
+        );
+
+        let (state_manager, _) = state_manager_at_genesis(&db, chain_config, None);
+
+        assert_eq!(
+            state_manager
+                .get_verified_client_allowance(&client, state_root)
+                .unwrap(),
+            TokenAmount::from_whole(7)
+        );
+        // Not a verified client
+        assert!(state_manager
+            .get_verified_client_allowance(&Address::new_id(2001), state_root)
+            .unwrap()
+            .is_zero());
+    }
+
+    #[test]
+    fn test_network_baseline_power_history() {
+        use fil_actor_reward_state::v13::State as RewardStateV13;
+
+        let db = Arc::new(MemoryDB::default());
+        let chain_config = Arc::new(ChainConfig::calibnet());
+        let state_with_baseline = |baseline: u64| {
+            let mut reward_state = RewardStateV13::new(BigInt::zero());
+            reward_state.this_epoch_baseline_power = BigInt::from(baseline);
+            state_with_actors(
+                &db,
+                [(
+                    Address::REWARD_ACTOR,
+                    actor_with_state(
+                        &db,
+                        calibnet_actor_code("v13.0.0", BuiltinActor::Reward),
+                        &reward_state,
+                    ),
+                )],
+            )
+        };
+
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            [genesis = HeaderBuilder::new().with_epoch(0).with_state_root(state_with_baseline(1000))]
+            -> [b1 = HeaderBuilder::new().with_epoch(10).with_state_root(state_with_baseline(2000))]
+            -> head @ [b2 = HeaderBuilder::new().with_epoch(20).with_state_root(state_with_baseline(3000))]
+        };
+        let state_manager = state_manager_with_genesis(&db, chain_config, genesis.clone());
+        let head = Arc::new(head.clone());
+
+        let history = state_manager
+            .get_network_baseline_power_history(&[0, 5, 10, 20], &head)
+            .unwrap();
+        assert_eq!(
+            history,
+            vec![
+                (0, BigInt::from(1000)),
+                // Null rounds resolve to the previous tipset
+                (5, BigInt::from(1000)),
+                (10, BigInt::from(2000)),
+                (20, BigInt::from(3000)),
+            ]
+        );
+        assert!(state_manager
+            .get_network_baseline_power_history(&[21], &head)
+            .is_err());
+        // The baseline is cached by the state root it was read from
+        assert_eq!(
+            state_manager
+                .baseline_power_cache
+                .lock()
+                .get(head.parent_state()),
+            Some(&BigInt::from(3000))
+        );
+    }
+
+    #[test]
+    fn test_network_baseline_power_growth() {
+        use fil_actor_reward_state::v13::State as RewardStateV13;
+        use num_traits::ToPrimitive as _;
+
+        // Per the specification, the baseline starts at 2.5 EiB and each epoch
+        // is multiplied by the Q.128 `BASELINE_EXPONENT`, doubling every year.
+        const YEAR: ChainEpoch = 365 * 2880;
+        let init_baseline = BigInt::from(2_888_888_880_000_000_000u64);
+        let exponent = "340282591298641078465964189926313473653"
+            .parse::<BigInt>()
+            .unwrap();
+        let baseline_at = |epoch: ChainEpoch| {
+            // `exponent ^ epoch` in Q.128 by squaring
+            let (mut result, mut base, mut n) = (BigInt::from(1) << 128, exponent.clone(), epoch);
+            while n > 0 {
+                if n & 1 == 1 {
+                    result = (result * &base) >> 128;
+                }
+                base = (&base * &base) >> 128;
+                n >>= 1;
+            }
+            (&init_baseline * result) >> 128
+        };
+
+        let db = Arc::new(MemoryDB::default());
+        let chain_config = Arc::new(ChainConfig::calibnet());
+        let state_at = |epoch: ChainEpoch| {
+            let mut reward_state = RewardStateV13::new(BigInt::zero());
+            reward_state.this_epoch_baseline_power = baseline_at(epoch);
+            state_with_actors(
+                &db,
+                [(
+                    Address::REWARD_ACTOR,
+                    actor_with_state(
+                        &db,
+                        calibnet_actor_code("v13.0.0", BuiltinActor::Reward),
+                        &reward_state,
+                    ),
+                )],
+            )
+        };
+
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            [genesis = HeaderBuilder::new().with_epoch(0).with_state_root(state_at(0))]
+            -> [b1 = HeaderBuilder::new().with_epoch(YEAR / 2).with_state_root(state_at(YEAR / 2))]
+            -> [b2 = HeaderBuilder::new().with_epoch(YEAR).with_state_root(state_at(YEAR))]
+            -> head @ [b3 = HeaderBuilder::new().with_epoch(2 * YEAR).with_state_root(state_at(2 * YEAR))]
+        };
+        let state_manager = state_manager_with_genesis(&db, chain_config, genesis.clone());
+        let head = Arc::new(head.clone());
+
+        let history = state_manager
+            .get_network_baseline_power_history(&[0, YEAR / 2, YEAR, 2 * YEAR], &head)
+            .unwrap();
+        // The growth is checked against the exponential rate of the
+        // specification, independently of the fixed-point arithmetic
+        let init = init_baseline.to_f64().unwrap();
+        for (epoch, baseline) in history {
+            let expected = 2f64.powf(epoch as f64 / YEAR as f64);
+            let ratio = baseline.to_f64().unwrap() / init;
+            assert!(
+                (ratio - expected).abs() < 1e-6,
+                "baseline grew by {ratio} at epoch {epoch}, expected {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_get_block_producer_stats() {
+        use crate::blocks::{ElectionProof, VRFProof};
+        use fil_actor_reward_state::v13::State as RewardStateV13;
+
+        let db = Arc::new(MemoryDB::default());
+        let chain_config = Arc::new(ChainConfig::calibnet());
+        let mut reward_state = RewardStateV13::new(BigInt::zero());
+        reward_state.this_epoch_reward = TokenAmount::from_atto(5000).into();
+        let state_root = state_with_actors(
+            &db,
+            [(
+                Address::REWARD_ACTOR,
+                actor_with_state(
+                    &db,
+                    calibnet_actor_code("v13.0.0", BuiltinActor::Reward),
+                    &reward_state,
+                ),
+            )],
+        );
+
+        let (alice, bob) = (Address::new_id(1000), Address::new_id(1001));
+        let block = |miner: Address, win_count: i64| {
+            HeaderBuilder::new()
+                .with_state_root(state_root)
+                .with_miner_address(miner)
+                .with_election_proof(Some(ElectionProof {
+                    win_count,
+                    vrfproof: VRFProof::new(vec![]),
+                }))
+                .clone()
+        };
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            [genesis = block(alice, 1)]
+            -> [_b1 = block(alice, 1), _b2 = block(bob, 2)]
+            -> head @ [_b3 = block(alice, 3)]
+        };
+        let state_manager = state_manager_with_genesis(&db, chain_config, genesis.clone());
+        let head = Arc::new(head.clone());
+
+        // Each win earns a fifth of the epoch reward, the genesis block is
+        // out of range
+        let stats = state_manager.get_block_producer_stats(1, 2, &head).unwrap();
+        assert_eq!(
+            stats,
+            HashMap::from_iter([
+                (
+                    alice,
+                    BlockProducerStats {
+                        blocks_won: 2,
+                        win_count_total: 4,
+                        avg_epoch_reward: TokenAmount::from_atto(2000),
+                    }
+                ),
+                (
+                    bob,
+                    BlockProducerStats {
+                        blocks_won: 1,
+                        win_count_total: 2,
+                        avg_epoch_reward: TokenAmount::from_atto(2000),
+                    }
+                ),
+            ])
+        );
+        let stats = state_manager.get_block_producer_stats(2, 2, &head).unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[&alice].avg_epoch_reward, TokenAmount::from_atto(3000));
+
+        assert!(state_manager.get_block_producer_stats(2, 1, &head).is_err());
+        assert!(state_manager.get_block_producer_stats(1, 3, &head).is_err());
+    }
+
+    #[test]
+    fn test_cached_terminations_merge() {
+        let record = |epoch| {
+            (
+                epoch,
+                TerminationRecord {
+                    epoch,
+                    sectors: vec![epoch as u64],
+                    penalty: TokenAmount::from_atto(epoch),
+                },
+            )
+        };
+        let cached = CachedTerminations {
+            from: 10,
+            to: 20,
+            records: BTreeMap::from_iter([record(12)]),
+        };
+        // Contiguous ranges are extended
+        let merged = CachedTerminations::merge(
+            Some(cached.clone()),
+            21,
+            30,
+            BTreeMap::from_iter([record(25)]),
+        );
+        assert_eq!(
+            merged,
+            CachedTerminations {
+                from: 10,
+                to: 30,
+                records: BTreeMap::from_iter([record(12), record(25)]),
+            }
+        );
+        let merged = CachedTerminations::merge(Some(cached.clone()), 5, 9, BTreeMap::new());
+        assert_eq!((merged.from, merged.to, merged.records.len()), (5, 20, 1));
+        // Disjoint ranges replace the history
+        let merged =
+            CachedTerminations::merge(Some(cached), 22, 30, BTreeMap::from_iter([record(25)]));
+        assert_eq!(
+            merged,
+            CachedTerminations {
+                from: 22,
+                to: 30,
+                records: BTreeMap::from_iter([record(25)]),
+            }
+        );
+        let merged = CachedTerminations::merge(None, 1, 2, BTreeMap::new());
+        assert_eq!((merged.from, merged.to), (1, 2));
+    }
+
+    #[tokio::test]
+    async fn test_get_miner_termination_history() {
+        use crate::chain_sync::TipsetValidator;
+        use fil_actor_miner_state::v16::{TerminateSectorsParams, TerminationDeclaration};
+        use fil_actors_shared::fvm_ipld_bitfield::BitField;
+        use fvm_ipld_encoding::RawBytes;
+
+        let db = Arc::new(MemoryDB::default());
+        let chain_config = Arc::new(ChainConfig::calibnet());
+        let state_root = StateTree::new(db.clone(), StateTreeVersion::V5)
+            .unwrap()
+            .flush()
+            .unwrap();
+        let (miner, other) = (Address::new_id(1000), Address::new_id(1001));
+        let terminate = |to: Address, params: RawBytes| Message {
+            from: Address::new_id(100),
+            to,
+            method_num: miner::Method::TerminateSectors as u64,
+            params,
+            ..Default::default()
+        };
+        let params = RawBytes::serialize(TerminateSectorsParams {
+            terminations: vec![TerminationDeclaration {
+                deadline: 0,
+                partition: 0,
+                sectors: BitField::try_from_bits([1, 2]).unwrap(),
+            }],
+        })
+        .unwrap();
+        // None of the messages terminates sectors of the miner, so none is
+        // replayed, which would fail on the empty state
+        let messages = [
+            terminate(other, params.clone()),
+            terminate(miner, RawBytes::new(vec![1, 2, 3])),
+            Message {
+                method_num: miner::Method::WithdrawBalance as u64,
+                ..terminate(miner, params)
+            },
+        ]
+        .iter()
+        .map(|message| db.put_cbor_default(message).unwrap())
+        .collect();
+        let empty = TipsetValidator::compute_msg_root_from_cids(&db, vec![], vec![]).unwrap();
+        let messages = TipsetValidator::compute_msg_root_from_cids(&db, messages, vec![]).unwrap();
+        let block = |epoch, messages| {
+            HeaderBuilder::new()
+                .with_epoch(epoch)
+                .with_state_root(state_root)
+                .with_messages(messages)
+                .clone()
+        };
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            [genesis = block(0, empty)]
+            -> [_b1 = block(1, messages)]
+            -> head @ [_b2 = block(1000, empty)]
+        };
+        let state_manager = state_manager_with_genesis(&db, chain_config, genesis.clone());
+        let head = Arc::new(head.clone());
+
+        assert!(state_manager
+            .get_miner_termination_history(&miner, 2, 1, &head)
+            .await
+            .is_err());
+        assert!(state_manager
+            .get_miner_termination_history(&miner, 0, 1, &head)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // The history of finalized epochs is cached, and served from the cache
+        let key = format!("{MINER_TERMINATIONS_KEY_PREFIX}{miner}");
+        let settings = state_manager.chain_store().settings();
+        let mut cached: CachedTerminations = settings.read_obj(&key).unwrap().unwrap();
+        assert_eq!((cached.from, cached.to), (0, 1));
+        assert!(cached.records.is_empty());
+        let record = TerminationRecord {
+            epoch: 1,
+            sectors: vec![1, 2],
+            penalty: TokenAmount::from_atto(10),
+        };
+        cached.records.insert(1, record.clone());
+        settings.write_obj(&key, &cached).unwrap();
+        assert_eq!(
+            state_manager
+                .get_miner_termination_history(&miner, 1, 1, &head)
+                .await
+                .unwrap(),
+            vec![record]
+        );
+        // Epochs within finality are not cached
+        assert!(state_manager
+            .get_miner_termination_history(&miner, 0, 999, &head)
+            .await
+            .unwrap()
+            .is_empty());
+        let cached: CachedTerminations = settings.read_obj(&key).unwrap().unwrap();
+        assert_eq!((cached.from, cached.to), (0, 1));
+    }
+
+    #[test]
+    fn test_funds_burnt_by() {
+        use crate::rpc::state::{MessageTrace, ReturnTrace};
+        use crate::shim::error::ExitCode;
+        use fvm_ipld_encoding::RawBytes;
+
+        let trace =
+            |from: Address, to: Address, atto: u64, exit_code: u32, subcalls| ExecutionTrace {
+                msg: MessageTrace {
+                    from,
+                    to,
+                    value: TokenAmount::from_atto(atto),
+                    method: 0,
+                    params: RawBytes::default(),
+                    params_codec: 0,
+                    gas_limit: None,
+                    read_only: None,
+                },
+                msg_rct: ReturnTrace {
+                    exit_code: ExitCode::from(exit_code),
+                    r#return: RawBytes::default(),
+                    return_codec: 0,
+                },
+                invoked_actor: None,
+                gas_charges: vec![],
+                subcalls,
+            };
+        let miner = Address::new_id(1000);
+        let other = Address::new_id(1001);
+        let burnt = Address::BURNT_FUNDS_ACTOR;
+        let root = trace(
+            Address::new_id(100),
+            miner,
+            0,
+            0,
+            vec![
+                trace(miner, burnt, 10, 0, vec![]),
+                trace(
+                    miner,
+                    Address::new_id(4),
+                    0,
+                    0,
+                    vec![trace(miner, burnt, 5, 0, vec![])],
+                ),
+                // Failed, from another actor, or to another actor
+                trace(miner, burnt, 100, 16, vec![]),
+                trace(other, burnt, 100, 0, vec![]),
+                trace(miner, other, 100, 0, vec![]),
+            ],
+        );
+        assert_eq!(funds_burnt_by(&root, &miner), TokenAmount::from_atto(15));
+        assert_eq!(funds_burnt_by(&root, &other), TokenAmount::from_atto(100));
+    }
+
+    #[test]
+    fn test_get_deal_proposal() {
+        use crate::shim::actors::market::DealLabel;
+        use fil_actor_market_state::v13::{DealProposal as DealProposalV13, Label, State};
+        use fvm_shared4::piece::PaddedPieceSize;
+
+        let db = Arc::new(MemoryDB::default());
+        let chain_config = Arc::new(ChainConfig::calibnet());
+        let proposal = DealProposalV13 {
+            piece_cid: Cid::default(),
+            piece_size: PaddedPieceSize(2048),
+            verified_deal: true,
+            client: Address::new_id(1001).into(),
+            provider: Address::new_id(1000).into(),
+            label: Label::String("label".into()),
+            start_epoch: 100,
+            end_epoch: 600_000,
+            storage_price_per_epoch: TokenAmount::from_atto(7).into(),
+            provider_collateral: TokenAmount::from_atto(8).into(),
+            client_collateral: TokenAmount::from_atto(9).into(),
+        };
+        // A deal that ended, but was not removed from the market yet
+        let expired = DealProposalV13 {
+            label: Label::String("expired".into()),
+            start_epoch: 10,
+            end_epoch: 90,
+            ..proposal.clone()
+        };
+        let mut market_state = State::new(&db).unwrap();
+        let mut proposals = market_state.load_proposals(&db).unwrap();
+        proposals.set(42, proposal).unwrap();
+        proposals.set(43, expired).unwrap();
+        market_state.save_proposals(&mut proposals).unwrap();
+        let state_root = state_with_actors(
+            &db,
+            [(
+                Address::MARKET_ACTOR,
+                actor_with_state(
+                    &db,
+                    calibnet_actor_code("v13.0.0", BuiltinActor::Market),
+                    &market_state,
+                ),
+            )],
+        );
+
+        let (state_manager, _) = state_manager_at_genesis(&db, chain_config, None);
+
+        let found = state_manager
+            .get_deal_proposal(42, state_root, 100)
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.piece_cid, Cid::default());
+        assert_eq!(found.piece_size.0, 2048);
+        assert!(found.verified_deal);
+        assert_eq!(Address::from(found.client), Address::new_id(1001));
+        assert_eq!(Address::from(found.provider), Address::new_id(1000));
+        assert_eq!(found.label, DealLabel::String("label".into()));
+        assert_eq!(found.start_epoch, 100);
+        assert_eq!(found.end_epoch, 600_000);
+        assert_eq!(
+            TokenAmount::from(found.storage_price_per_epoch),
+            TokenAmount::from_atto(7)
+        );
+        assert_eq!(
+            TokenAmount::from(found.provider_collateral),
+            TokenAmount::from_atto(8)
+        );
+        assert_eq!(
+            TokenAmount::from(found.client_collateral),
+            TokenAmount::from_atto(9)
+        );
+        // Deals are not returned past their end epoch
+        assert!(state_manager
+            .get_deal_proposal(43, state_root, 90)
+            .unwrap()
+            .is_some());
+        assert!(state_manager
+            .get_deal_proposal(43, state_root, 91)
+            .unwrap()
+            .is_none());
+        assert!(state_manager
+            .get_deal_proposal(44, state_root, 0)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_get_miner_worker_key_change() {
+        use fil_actor_miner_state::v13::{MinerInfo as MinerInfoV13, State as MinerStateV13};
+
+        let db = Arc::new(MemoryDB::default());
+        let chain_config = Arc::new(ChainConfig::calibnet());
+        let miner = Address::new_id(1000);
+        let state_with_info = |info: &MinerInfoV13| {
+            let miner_state = MinerStateV13::new(
+                &chain_config.policy,
+                &db,
+                db.put_cbor_default(info).unwrap(),
+                0,
+                0,
+            )
+            .unwrap();
+            state_with_actors(
+                &db,
+                [(
+                    miner,
+                    actor_with_state(&db, calibnet_miner_code("v13.0.0"), &miner_state),
+                )],
+            )
+        };
+
+        let (state_manager, _) = state_manager_at_genesis(&db, chain_config, None);
+
+        let mut info = MinerInfoV13::new(
+            1001,
+            1001,
+            vec![],
+            vec![],
+            vec![],
+            fvm_shared4::sector::RegisteredPoStProof::StackedDRGWindow2KiBV1P1,
+        )
+        .unwrap();
+        assert_eq!(
+            state_manager
+                .get_miner_worker_key_change(&miner, state_with_info(&info))
+                .unwrap(),
+            None
+        );
+
+        // As staged by `ChangeWorkerAddress`
+        let change = WorkerKeyChange {
+            new_worker: Address::new_id(1002).into(),
+            effective_at: 2000,
+        };
+        info.pending_worker_key = Some(fil_actor_miner_state::v13::WorkerKeyChange {
+            new_worker: change.new_worker,
+            effective_at: change.effective_at,
+        });
+        assert_eq!(
+            state_manager
+                .get_miner_worker_key_change(&miner, state_with_info(&info))
+                .unwrap(),
+            Some(change)
+        );
+
+        // As applied by `ConfirmChangeWorkerAddress` once effective
+        info.worker = change.new_worker;
+        info.pending_worker_key = None;
+        assert_eq!(
+            state_manager
+                .get_miner_worker_key_change(&miner, state_with_info(&info))
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_get_miner_control_address_info() {
+        use fil_actor_miner_state::v13::{MinerInfo as MinerInfoV13, State as MinerStateV13};
+
+        let db = Arc::new(MemoryDB::default());
+        let chain_config = Arc::new(ChainConfig::calibnet());
+        let miner = Address::new_id(1000);
+        let (owner, worker, control, new_worker, new_owner) = (1001, 1002, 1003, 1004, 1005);
+        let owner_key = Address::new_secp256k1(&[4; 65]).unwrap();
+        let worker_delegated = Address::new_delegated(10, &[1; 20]).unwrap();
+
+        let mut info = MinerInfoV13::new(
+            owner,
+            worker,
+            vec![control],
+            vec![],
+            vec![],
+            fvm_shared4::sector::RegisteredPoStProof::StackedDRGWindow2KiBV1P1,
+        )
+        .unwrap();
+        info.pending_worker_key = Some(fil_actor_miner_state::v13::WorkerKeyChange {
+            new_worker: Address::new_id(new_worker).into(),
+            effective_at: 2000,
+        });
+        info.pending_owner_address = Some(Address::new_id(new_owner).into());
+        let miner_state = MinerStateV13::new(
+            &chain_config.policy,
+            &db,
+            db.put_cbor_default(&info).unwrap(),
+            0,
+            0,
+        )
+        .unwrap();
+        let mut state_tree = StateTree::new(db.clone(), StateTreeVersion::V5).unwrap();
+        state_tree
+            .set_actor(
+                &miner,
+                ActorState::new(
+                    calibnet_miner_code("v13.0.0"),
+                    db.put_cbor_default(&miner_state).unwrap(),
+                    TokenAmount::zero(),
+                    0,
+                    None,
+                ),
+            )
+            .unwrap();
+        // An account owner, resolved to its key address
+        state_tree
+            .set_actor(
+                &Address::new_id(owner),
+                ActorState::new(
+                    calibnet_actor_code("v13.0.0", BuiltinActor::Account),
+                    db.put_cbor_default(&fil_actor_account_state::v13::State {
+                        address: owner_key.into(),
+                    })
+                    .unwrap(),
+                    TokenAmount::zero(),
+                    0,
+                    None,
+                ),
+            )
+            .unwrap();
+        // A worker with a delegated address
+        state_tree
+            .set_actor(
+                &Address::new_id(worker),
+                ActorState::new(
+                    calibnet_actor_code("v13.0.0", BuiltinActor::EthAccount),
+                    Cid::default(),
+                    TokenAmount::zero(),
+                    0,
+                    Some(worker_delegated),
+                ),
+            )
+            .unwrap();
+        // A pending owner without a robust address
+        state_tree
+            .set_actor(
+                &Address::new_id(new_owner),
+                ActorState::new(
+                    calibnet_actor_code("v13.0.0", BuiltinActor::Multisig),
+                    Cid::default(),
+                    TokenAmount::zero(),
+                    0,
+                    None,
+                ),
+            )
+            .unwrap();
+        let state_cid = state_tree.flush().unwrap();
+
+        let (state_manager, _) = state_manager_at_genesis(&db, chain_config, None);
+
+        let control_info = state_manager
+            .get_miner_control_address_info(&miner, state_cid)
+            .unwrap();
+        let id_forms = [&control_info.owner, &control_info.worker]
+            .into_iter()
+            .chain(&control_info.control_addresses)
+            .chain(&control_info.pending_owner)
+            .collect_vec();
+        assert_eq!(id_forms.len(), 4);
+        assert!(id_forms
+            .iter()
+            .all(|forms| forms.id.protocol() == Protocol::ID));
+        assert_eq!(
+            control_info.owner,
+            AddressForms {
+                id: Address::new_id(owner),
+                robust: Some(owner_key),
+            }
+        );
+        assert_eq!(control_info.worker.robust, Some(worker_delegated));
+        // The control address has no actor
+        assert_eq!(
+            control_info.control_addresses,
+            vec![AddressForms {
+                id: Address::new_id(control),
+                robust: None,
+            }]
+        );
+        assert_eq!(
+            control_info.pending_owner,
+            Some(AddressForms {
+                id: Address::new_id(new_owner),
+                robust: None,
+            })
+        );
+        assert_eq!(
+            control_info.pending_worker,
+            Some(WorkerKeyChange {
+                new_worker: Address::new_id(new_worker).into(),
+                effective_at: 2000,
+            })
+        );
+    }
+
+    #[test]
+    fn test_get_miner_peer_info() {
+        use crate::libp2p::{Keypair, Multiaddr, PeerId};
+        use fil_actor_miner_state::v13::{MinerInfo as MinerInfoV13, State as MinerStateV13};
+        use fvm_ipld_encoding::BytesDe;
+
+        let db = Arc::new(MemoryDB::default());
+        let chain_config = Arc::new(ChainConfig::calibnet());
+        let (miner, worker) = (Address::new_id(1000), Address::new_id(1001));
+        let worker_key = Address::new_bls(&[7; 48]).unwrap();
+        let peer_id = Keypair::generate_ed25519().public().to_peer_id();
+        let multiaddr: Multiaddr = "/ip4/10.0.0.1/tcp/24001".parse().unwrap();
+
+        let info = MinerInfoV13::new(
+            1001,
+            1001,
+            vec![],
+            peer_id.to_bytes(),
+            vec![BytesDe(multiaddr.to_vec())],
+            fvm_shared4::sector::RegisteredPoStProof::StackedDRGWindow2KiBV1P1,
+        )
+        .unwrap();
+        let miner_state = MinerStateV13::new(
+            &chain_config.policy,
+            &db,
+            db.put_cbor_default(&info).unwrap(),
+            0,
+            0,
+        )
+        .unwrap();
+        let mut state_tree = StateTree::new(db.clone(), StateTreeVersion::V5).unwrap();
+        state_tree
+            .set_actor(
+                &miner,
+                ActorState::new(
+                    calibnet_miner_code("v13.0.0"),
+                    db.put_cbor_default(&miner_state).unwrap(),
+                    TokenAmount::zero(),
+                    0,
+                    None,
+                ),
+            )
+            .unwrap();
+        state_tree
+            .set_actor(
+                &worker,
+                ActorState::new(
+                    calibnet_actor_code("v13.0.0", BuiltinActor::Account),
+                    db.put_cbor_default(&fil_actor_account_state::v13::State {
+                        address: worker_key.into(),
+                    })
+                    .unwrap(),
+                    TokenAmount::zero(),
+                    0,
+                    None,
+                ),
+            )
+            .unwrap();
+        let state_root = state_tree.flush().unwrap();
+
+        let (state_manager, _) = state_manager_at_genesis(&db, chain_config, None);
+
+        let peer_info = state_manager
+            .get_miner_peer_info(&miner, state_root)
+            .unwrap();
+        assert_eq!(PeerId::from_bytes(&peer_info.peer_id).unwrap(), peer_id);
+        assert_eq!(
+            peer_info
+                .multiaddrs
+                .into_iter()
+                .map(|bytes| Multiaddr::try_from(bytes).unwrap())
+                .collect::<Vec<_>>(),
+            vec![multiaddr]
+        );
+        assert_eq!(peer_info.worker_key, worker_key);
+    }
+
+    #[test]
+    fn test_miner_consensus_status() {
+        use fil_actor_miner_state::v13::{MinerInfo as MinerInfoV13, State as MinerStateV13};
+        use fil_actor_power_state::v13::{Claim as ClaimV13, State as PowerStateV13};
+        use fvm_shared4::sector::RegisteredPoStProof;
+
+        let db = Arc::new(MemoryDB::default());
+        let chain_config = Arc::new(ChainConfig::calibnet());
+        let policy = &chain_config.policy;
+        let min_power = policy.minimum_consensus_power.clone();
+        let (active, below_minimum, unclaimed, faulty) = (
+            Address::new_id(1000),
+            Address::new_id(1001),
+            Address::new_id(1002),
+            Address::new_id(1003),
+        );
+        const EPOCH: ChainEpoch = 10;
+
+        // Enough miners meet the minimum for it to be enforced
+        let mut power_state = PowerStateV13::new(&db).unwrap();
+        power_state.miner_above_min_power_count = 10;
+        let mut claims = power_state.load_claims(&db).unwrap();
+        let mut state_tree = StateTree::new(db.clone(), StateTreeVersion::V5).unwrap();
+        for (miner, raw_byte_power, consensus_fault_elapsed) in [
+            (active, min_power.clone(), -1),
+            (below_minimum, BigInt::from(2048), -1),
+            (faulty, min_power.clone(), EPOCH + 5),
+        ] {
+            claims
+                .set(
+                    &miner.into(),
+                    ClaimV13 {
+                        window_post_proof_type: RegisteredPoStProof::StackedDRGWindow32GiBV1P1,
+                        raw_byte_power: raw_byte_power.clone(),
+                        quality_adj_power: raw_byte_power * 10,
+                    },
+                )
+                .unwrap();
+            let mut info = MinerInfoV13::new(
+                1,
+                1,
+                vec![],
+                vec![],
+                vec![],
+                RegisteredPoStProof::StackedDRGWindow32GiBV1P1,
+            )
+            .unwrap();
+            // As set by `ReportConsensusFault`
+            info.consensus_fault_elapsed = consensus_fault_elapsed;
+            let miner_state =
+                MinerStateV13::new(policy, &db, db.put_cbor_default(&info).unwrap(), 0, 0).unwrap();
+            state_tree
+                .set_actor(
+                    &miner,
+                    ActorState::new(
+                        calibnet_miner_code("v13.0.0"),
+                        db.put_cbor_default(&miner_state).unwrap(),
+                        TokenAmount::zero(),
+                        0,
+                        None,
+                    ),
+                )
+                .unwrap();
+        }
+        power_state.save_claims(&mut claims).unwrap();
+        state_tree
+            .set_actor(
+                &Address::POWER_ACTOR,
+                ActorState::new(
+                    calibnet_actor_code("v13.0.0", BuiltinActor::Power),
+                    db.put_cbor_default(&power_state).unwrap(),
+                    TokenAmount::zero(),
+                    0,
+                    None,
+                ),
+            )
+            .unwrap();
+        let state_root = state_tree.flush().unwrap();
+
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            [genesis = HeaderBuilder::new().with_epoch(0)]
+            -> [head = HeaderBuilder::new().with_epoch(EPOCH).with_state_root(state_root)]
+        };
+        let state_manager = state_manager_with_genesis(&db, chain_config.clone(), genesis.clone());
+        let ts = Tipset::from(head.clone());
+
+        let status = |miner: &Address| state_manager.miner_consensus_status(miner, &ts).unwrap();
+        assert_eq!(
+            status(&active),
+            MinerConsensusStatus::Active {
+                quality_adj_power: &min_power * 10
+            }
+        );
+        assert_eq!(
+            status(&below_minimum),
+            MinerConsensusStatus::BelowMinimumPower {
+                quality_adj_power: BigInt::from(20480)
+            }
+        );
+        assert_eq!(status(&unclaimed), MinerConsensusStatus::NoClaim);
+        assert_eq!(
+            status(&faulty),
+            MinerConsensusStatus::ConsensusFault { elapsed: EPOCH + 5 }
+        );
+        assert_unchanged_via_json(status(&faulty));
+
+        #[allow(deprecated)]
+        let slashed = state_manager
+            .is_miner_slashed(&unclaimed, &state_root)
+            .unwrap();
+        assert!(slashed);
+    }
+
+    #[test]
+    fn test_compute_sector_reward_estimate() {
+        use fil_actor_miner_state::v13::{
+            MinerInfo as MinerInfoV13, SectorOnChainInfo as SectorOnChainInfoV13,
+            State as MinerStateV13,
+        };
+        use fvm_shared4::econ::TokenAmount as TokenAmountV4;
+        use fvm_shared4::sector::{RegisteredPoStProof, RegisteredSealProof, SectorSize};
+
+        let db = Arc::new(MemoryDB::default());
+        let chain_config = Arc::new(ChainConfig::calibnet());
+        let miner = Address::new_id(1000);
+        // Extended at epoch 1500, so that its power is weighted from then on
+        let duration = 540 * crate::shim::clock::EPOCHS_IN_DAY;
+        let sector = SectorOnChainInfoV13 {
+            sector_number: 7,
+            seal_proof: RegisteredSealProof::StackedDRG32GiBV1P1,
+            activation: 1000,
+            power_base_epoch: 1500,
+            expiration: 1500 + duration,
+            verified_deal_weight: BigInt::from(SectorSize::_32GiB as u64 / 2) * duration,
+            expected_day_reward: TokenAmountV4::from_nano(20_000_000),
+            expected_storage_pledge: TokenAmountV4::from_nano(400_000_000),
+            ..Default::default()
+        };
+
+        let info = MinerInfoV13::new(
+            1,
+            1,
+            vec![],
+            vec![],
+            vec![],
+            RegisteredPoStProof::StackedDRGWindow32GiBV1P1,
+        )
+        .unwrap();
+        let mut miner_state = MinerStateV13::new(
+            &chain_config.policy,
+            &db,
+            db.put_cbor_default(&info).unwrap(),
+            0,
+            0,
+        )
+        .unwrap();
+        miner_state.put_sectors(&db, vec![sector.clone()]).unwrap();
+        let reward_state = fil_actor_reward_state::v13::State::new(BigInt::from(1u64 << 50));
+        let power_state = fil_actor_power_state::v13::State::new(&db).unwrap();
+        let state_root = state_with_actors(
+            &db,
+            [
+                (
+                    miner,
+                    actor_with_state(&db, calibnet_miner_code("v13.0.0"), &miner_state),
+                ),
+                (
+                    Address::REWARD_ACTOR,
+                    actor_with_state(
+                        &db,
+                        calibnet_actor_code("v13.0.0", BuiltinActor::Reward),
+                        &reward_state,
+                    ),
+                ),
+                (
+                    Address::POWER_ACTOR,
+                    actor_with_state(
+                        &db,
+                        calibnet_actor_code("v13.0.0", BuiltinActor::Power),
+                        &power_state,
+                    ),
+                ),
+            ],
+        );
+
+        let (state_manager, _) = state_manager_at_genesis(&db, chain_config, None);
+
+        // The miner actor formulas
+        let projection_epochs = 180 * crate::shim::clock::EPOCHS_IN_DAY;
+        let total_projected_reward = fil_actor_miner_state::v13::expected_reward_for_power(
+            &reward_state.this_epoch_reward_smoothed,
+            &power_state.this_epoch_qa_power_smoothed,
+            &fil_actor_miner_state::v13::qa_power_for_sector(SectorSize::_32GiB, &sector),
+            projection_epochs,
+        );
+
+        let estimate = state_manager
+            .compute_sector_reward_estimate(&miner, 7, projection_epochs, state_root)
+            .unwrap();
+        assert_eq!(
+            estimate,
+            SectorRewardEstimate {
+                expected_daily_reward: sector.expected_day_reward.into(),
+                total_projected_reward: total_projected_reward.into(),
+                expected_pledge: sector.expected_storage_pledge.into(),
+            }
+        );
+        assert!(estimate.total_projected_reward.is_positive());
+
+        assert!(state_manager
+            .compute_sector_reward_estimate(&miner, 7, -1, state_root)
+            .is_err());
+        assert!(state_manager
+            .compute_sector_reward_estimate(&miner, 8, projection_epochs, state_root)
+            .is_err());
+    }
+
+    /// Sector activated at epoch 1000, its power rebased at epoch 1500 by an
+    /// extension, with half of its space in verified deals.
+    fn penalty_fixture_sector() -> fil_actor_miner_state::v16::SectorOnChainInfo {
+        use fvm_shared4::econ::TokenAmount as TokenAmountV4;
+        let duration = 540 * crate::shim::clock::EPOCHS_IN_DAY;
+        let size = fvm_shared4::sector::SectorSize::_32GiB as u64;
+        fil_actor_miner_state::v16::SectorOnChainInfo {
+            sector_number: 7,
+            seal_proof: fvm_shared4::sector::RegisteredSealProof::StackedDRG32GiBV1P1,
+            activation: 1000,
+            power_base_epoch: 1500,
+            expiration: 1500 + duration,
+            verified_deal_weight: BigInt::from(size / 2) * duration,
+            initial_pledge: TokenAmountV4::from_whole(5),
+            expected_day_reward: TokenAmountV4::from_nano(20_000_000),
+            expected_storage_pledge: TokenAmountV4::from_nano(400_000_000),
+            replaced_day_reward: TokenAmountV4::from_nano(10_000_000),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sector_penalty_matches_miner_actor_v16() {
+        use fil_actors_shared::v16::reward::FilterEstimate as FilterEstimateV16;
+
+        let sector = penalty_fixture_sector();
+        let epoch = 100_000;
+        let reward_state = fil_actor_reward_state::v16::State::new(BigInt::from(1u64 << 50));
+        let network_qa_power = FilterEstimate {
+            position: BigInt::from(1u64 << 60),
+            velocity: BigInt::zero(),
+        };
+
+        // The miner actor formulas
+        let qa_power = fil_actor_miner_state::v16::qa_power_for_sector(
+            fvm_shared4::sector::SectorSize::_32GiB,
+            &sector,
+        );
+        let network_qa_power_v16 = FilterEstimateV16 {
+            position: network_qa_power.position.clone(),
+            velocity: network_qa_power.velocity.clone(),
+        };
+        let fault_fee = fil_actor_miner_state::v16::pledge_penalty_for_continued_fault(
+            &reward_state.this_epoch_reward_smoothed,
+            &network_qa_power_v16,
+            &qa_power,
+        );
+        let termination_penalty = fil_actor_miner_state::v16::pledge_penalty_for_termination(
+            &sector.initial_pledge,
+            epoch - sector.power_base_epoch,
+            &fault_fee,
+        );
+
+        let penalty = SectorPenalty::new(
+            &reward::State::V16(reward_state),
+            &network_qa_power,
+            &sector.into(),
+            epoch,
+        )
+        .unwrap();
+        assert_eq!(
+            penalty,
+            SectorPenalty {
+                sector_number: 7,
+                daily_fault_fee: fault_fee.into(),
+                termination_penalty: termination_penalty.into(),
+            }
+        );
+        assert!(penalty.daily_fault_fee.is_positive());
+        assert!(penalty.termination_penalty >= penalty.daily_fault_fee);
+    }
+
+    #[test]
+    fn test_sector_penalty_matches_miner_actor_v15() {
+        use fil_actors_shared::v15::reward::FilterEstimate as FilterEstimateV15;
+
+        let sector = penalty_fixture_sector();
+        let epoch = 100_000;
+        let reward_state = fil_actor_reward_state::v15::State::new(BigInt::from(1u64 << 50));
+        let network_qa_power = FilterEstimate {
+            position: BigInt::from(1u64 << 60),
+            velocity: BigInt::zero(),
+        };
+
+        // The miner actor formulas, before FIP-0098
+        let qa_power = fil_actor_miner_state::v16::qa_power_for_sector(
+            fvm_shared4::sector::SectorSize::_32GiB,
+            &sector,
+        );
+        let network_qa_power_v15 = FilterEstimateV15 {
+            position: network_qa_power.position.clone(),
+            velocity: network_qa_power.velocity.clone(),
+        };
+        let fault_fee = fil_actor_miner_state::v15::pledge_penalty_for_continued_fault(
+            &reward_state.this_epoch_reward_smoothed,
+            &network_qa_power_v15,
+            &qa_power,
+        );
+        let termination_penalty = fil_actor_miner_state::v15::pledge_penalty_for_termination(
+            &sector.expected_day_reward,
+            epoch - sector.power_base_epoch,
+            &sector.expected_storage_pledge,
+            &network_qa_power_v15,
+            &qa_power,
+            &reward_state.this_epoch_reward_smoothed,
+            &sector.replaced_day_reward,
+            sector.power_base_epoch - sector.activation,
+        );
+
+        let penalty = SectorPenalty::new(
+            &reward::State::V15(reward_state),
+            &network_qa_power,
+            &sector.into(),
+            epoch,
+        )
+        .unwrap();
+        assert_eq!(
+            penalty,
+            SectorPenalty {
+                sector_number: 7,
+                daily_fault_fee: fault_fee.into(),
+                termination_penalty: termination_penalty.into(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_token_vesting_stats() {
+        use fil_actor_miner_state::v13::{State as MinerStateV13, VestingFund, VestingFunds};
+        use fil_actor_power_state::v13::{Claim as ClaimV13, State as PowerStateV13};
+        use fvm_shared4::econ::TokenAmount as TokenAmountV4;
+        use fvm_shared4::sector::RegisteredPoStProof;
+
+        let db = Arc::new(MemoryDB::default());
+        let chain_config = Arc::new(ChainConfig::calibnet());
+        let policy = &chain_config.policy;
+
+        // Three miners with overlapping vesting schedules
+        let mut power_state = PowerStateV13::new(&db).unwrap();
+        let mut claims = power_state.load_claims(&db).unwrap();
+        let mut state_tree = StateTree::new(db.clone(), StateTreeVersion::V5).unwrap();
+        for (id, schedule) in [
+            (1000, vec![(100, 10), (200, 20)]),
+            (1001, vec![(200, 5), (300, 7)]),
+            (1002, vec![(100, 1), (200, 2), (300, 3), (400, 4)]),
+        ] {
+            let miner = Address::new_id(id);
+            claims
+                .set(
+                    &miner.into(),
+                    ClaimV13 {
+                        window_post_proof_type: RegisteredPoStProof::StackedDRGWindow32GiBV1P1,
+                        raw_byte_power: BigInt::zero(),
+                        quality_adj_power: BigInt::zero(),
+                    },
+                )
+                .unwrap();
+            let mut vesting_funds = VestingFunds::new();
+            vesting_funds.funds = schedule
+                .into_iter()
+                .map(|(epoch, amount)| VestingFund {
+                    epoch,
+                    amount: TokenAmountV4::from_atto(amount),
+                })
+                .collect();
+            let mut miner_state = MinerStateV13::new(policy, &db, Cid::default(), 0, 0).unwrap();
+            miner_state.vesting_funds = db.put_cbor_default(&vesting_funds).unwrap();
+            state_tree
+                .set_actor(
+                    &miner,
+                    ActorState::new(
+                        calibnet_miner_code("v13.0.0"),
+                        db.put_cbor_default(&miner_state).unwrap(),
+                        TokenAmount::zero(),
+                        0,
+                        None,
+                    ),
+                )
+                .unwrap();
+        }
+        power_state.save_claims(&mut claims).unwrap();
+        state_tree
+            .set_actor(
+                &Address::POWER_ACTOR,
+                ActorState::new(
+                    calibnet_actor_code("v13.0.0", BuiltinActor::Power),
+                    db.put_cbor_default(&power_state).unwrap(),
+                    TokenAmount::zero(),
+                    0,
+                    None,
+                ),
+            )
+            .unwrap();
+        let state_root = state_tree.flush().unwrap();
+
+        let (state_manager, _) = state_manager_at_genesis(&db, chain_config, Some(state_root));
+
+        let stats = state_manager.get_token_vesting_stats(state_root).unwrap();
+        assert_eq!(
+            stats,
+            VestingStats {
+                total_vesting: TokenAmount::from_atto(52),
+                vesting_by_bucket: BTreeMap::from([
+                    (100, TokenAmount::from_atto(11)),
+                    (200, TokenAmount::from_atto(27)),
+                    (300, TokenAmount::from_atto(10)),
+                    (400, TokenAmount::from_atto(4)),
+                ]),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_genesis_info_mainnet() {
+        use crate::ipld::Ipld;
+        use fvm_ipld_encoding::CborStore as _;
+
+        let db = Arc::new(MemoryDB::default());
+        let genesis = crate::genesis::read_genesis_header(
+            None,
+            Some(crate::networks::mainnet::DEFAULT_GENESIS),
+            &db,
+        )
+        .await
+        .unwrap();
+        let chain_config = Arc::new(ChainConfig::mainnet());
+        let state_manager = state_manager_with_genesis(&db, chain_config, genesis.clone());
+
+        let info = state_manager.get_genesis_info().unwrap();
+        assert_eq!(info.timestamp, genesis.timestamp);
+        assert_eq!(info.initial_supply, *crate::shim::econ::TOTAL_FILECOIN);
+        for miner in &info.genesis_miners {
+            assert!(is_miner_actor(&info.genesis_actors[miner].code));
+        }
+
+        // The miner count recorded by the v0 power actor of the genesis state
+        let power = &info.genesis_actors[&Address::POWER_ACTOR];
+        let Some(Ipld::List(fields)) = db.get_cbor::<Ipld>(&power.state).unwrap() else {
+            panic!("invalid power actor state");
+        };
+        assert_eq!(fields[9], Ipld::Integer(info.genesis_miners.len() as i128));
+
+        // Cached
+        assert!(Arc::ptr_eq(
+            &info,
+            &state_manager.get_genesis_info().unwrap()
+        ));
+    }
+}
@@ -0,0 +1,257 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Opt-in shadow execution, meant for canary nodes: every tipset is executed a
+//! second time with a separate reference engine, on the same inputs, and the
+//! state roots, receipts roots and message receipts of both runs are compared.
+//! A divergence is a consensus bug waiting to happen, it is dumped to the
+//! journal and the node is shut down.
+
+use std::time::Duration;
+
+use crate::blocks::Tipset;
+use crate::interpreter::MessageCallbackCtx;
+use crate::journal::JournalEvent;
+use crate::shim::executor::Receipt;
+use crate::shim::machine::MultiEngine;
+use crate::shim::trace::ExecutionEvent;
+use cid::Cid;
+use itertools::{EitherOrBoth, Itertools as _};
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+use tokio::sync::mpsc;
+use tracing::error;
+
+/// Maximum number of lines of the trace diff written to the journal.
+const MAX_TRACE_DIFF_LINES: usize = 200;
+
+/// Settings of the diagnostics, in the `[diagnostics]` section of the
+/// configuration.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
+#[serde(default)]
+pub struct DiagnosticsConfig {
+    /// Executes every tipset twice, with separate engines, and shuts the node
+    /// down if the results diverge
+    pub shadow_execution: bool,
+    /// Number of concurrent executions of the reference engine. Defaults to
+    /// one, so that its configuration differs from the primary engine's.
+    pub shadow_engine_concurrency: Option<u32>,
+}
+
+impl DiagnosticsConfig {
+    /// Returns the reference engine of the shadow execution, if enabled.
+    pub fn reference_engine(&self) -> Option<MultiEngine> {
+        self.shadow_execution
+            .then(|| MultiEngine::new(Ok(self.shadow_engine_concurrency.unwrap_or(1))))
+    }
+}
+
+/// Reference engine of the shadow execution, and the channel to request the
+/// daemon shutdown on a divergence.
+pub struct ShadowExecution {
+    pub engine: MultiEngine,
+    pub shutdown: mpsc::Sender<()>,
+}
+
+/// A message executed by the VM, including the implicit ones.
+#[derive(Debug, Clone)]
+pub struct ExecutedMessage {
+    pub cid: Cid,
+    pub receipt: Receipt,
+    pub trace: Vec<ExecutionEvent>,
+}
+
+impl ExecutedMessage {
+    pub fn new(ctx: &MessageCallbackCtx<'_>) -> Self {
+        Self {
+            cid: ctx.cid,
+            receipt: ctx.apply_ret.msg_receipt(),
+            trace: ctx.apply_ret.exec_trace(),
+        }
+    }
+}
+
+/// Outcome of one of the two executions of a tipset.
+#[derive(Debug, Clone)]
+pub struct ExecutionRecord {
+    pub state_root: Cid,
+    pub receipt_root: Cid,
+    /// In execution order
+    pub messages: Vec<ExecutedMessage>,
+}
+
+/// Compares the primary and reference executions of `tipset`, returning the
+/// journal event describing the first diverging message, if any.
+pub fn find_divergence(
+    tipset: &Tipset,
+    primary: &ExecutionRecord,
+    reference: &ExecutionRecord,
+) -> Option<JournalEvent> {
+    let diverging_message = primary
+        .messages
+        .iter()
+        .zip_longest(&reference.messages)
+        .find(|pair| {
+            !matches!(pair, EitherOrBoth::Both(primary, reference)
+                if primary.cid == reference.cid && primary.receipt == reference.receipt)
+        });
+    if diverging_message.is_none()
+        && primary.state_root == reference.state_root
+        && primary.receipt_root == reference.receipt_root
+    {
+        return None;
+    }
+    let (primary_message, reference_message) = diverging_message
+        .map(|pair| (pair.clone().left(), pair.right()))
+        .unwrap_or_default();
+    Some(JournalEvent::ShadowExecutionDivergence {
+        key: tipset.key().clone(),
+        epoch: tipset.epoch(),
+        primary_state_root: primary.state_root,
+        reference_state_root: reference.state_root,
+        primary_receipt_root: primary.receipt_root,
+        reference_receipt_root: reference.receipt_root,
+        message: primary_message
+            .or(reference_message)
+            .map(|message| message.cid),
+        primary_receipt: primary_message.map(|message| message.receipt.clone()),
+        reference_receipt: reference_message.map(|message| message.receipt.clone()),
+        trace_diff: trace_diff(
+            primary_message.map(|message| &message.trace[..]),
+            reference_message.map(|message| &message.trace[..]),
+        ),
+    })
+}
+
+/// Changed lines between the pretty-printed traces, `-` for the primary
+/// execution and `+` for the reference one.
+fn trace_diff(
+    primary: Option<&[ExecutionEvent]>,
+    reference: Option<&[ExecutionEvent]>,
+) -> Vec<String> {
+    let primary = format!("{:#?}", primary.unwrap_or_default());
+    let reference = format!("{:#?}", reference.unwrap_or_default());
+    TextDiff::from_lines(&primary, &reference)
+        .iter_all_changes()
+        .filter_map(|change| match change.tag() {
+            ChangeTag::Delete => Some(format!("-{}", change.value().trim_end())),
+            ChangeTag::Insert => Some(format!("+{}", change.value().trim_end())),
+            ChangeTag::Equal => None,
+        })
+        .take(MAX_TRACE_DIFF_LINES)
+        .collect()
+}
+
+/// Writes the divergence to the journal and requests the shutdown of the
+/// node, which closes the database cleanly.
+pub fn shut_down_on_divergence(divergence: JournalEvent, shutdown: &mpsc::Sender<()>) {
+    error!("Shadow execution diverged, shutting down: {divergence:?}");
+    crate::journal::record(divergence);
+    crate::journal::flush(Duration::from_secs(10));
+    // The channel is only full if a shutdown is already requested
+    let _ = shutdown.try_send(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::{chain4u, Chain4U};
+    use crate::utils::multihash::prelude::*;
+    use fvm_ipld_encoding::{RawBytes, DAG_CBOR};
+
+    fn cid(data: &[u8]) -> Cid {
+        Cid::new_v1(DAG_CBOR, MultihashCode::Blake2b256.digest(data))
+    }
+
+    fn receipt(gas_used: u64) -> Receipt {
+        Receipt::V4(fvm_shared4::receipt::Receipt {
+            exit_code: fvm_shared4::error::ExitCode::new(0),
+            return_data: RawBytes::default(),
+            gas_used,
+            events_root: None,
+        })
+    }
+
+    fn executed(name: &[u8], gas_used: u64, log: &str) -> ExecutedMessage {
+        ExecutedMessage {
+            cid: cid(name),
+            receipt: receipt(gas_used),
+            trace: vec![
+                ExecutionEvent::Log("start".into()),
+                ExecutionEvent::Log(log.into()),
+            ],
+        }
+    }
+
+    #[test]
+    fn shadow_execution_divergence_dump() {
+        let c4u = Chain4U::new();
+        chain4u! {
+            in c4u;
+            [_genesis] -> t1 @ [_b1]
+        };
+        let primary = ExecutionRecord {
+            state_root: cid(b"state"),
+            receipt_root: cid(b"receipts"),
+            messages: vec![executed(b"m1", 10, "ok"), executed(b"m2", 20, "ok")],
+        };
+        assert!(find_divergence(t1, &primary, &primary.clone()).is_none());
+
+        // The reference VM charges more gas for the second message
+        let reference = ExecutionRecord {
+            state_root: cid(b"other state"),
+            receipt_root: cid(b"other receipts"),
+            messages: vec![executed(b"m1", 10, "ok"), executed(b"m2", 25, "more gas")],
+        };
+        let Some(JournalEvent::ShadowExecutionDivergence {
+            key,
+            epoch,
+            primary_state_root,
+            reference_state_root,
+            primary_receipt_root,
+            reference_receipt_root,
+            message,
+            primary_receipt,
+            reference_receipt,
+            trace_diff,
+        }) = find_divergence(t1, &primary, &reference)
+        else {
+            panic!("divergence expected");
+        };
+        assert_eq!(&key, t1.key());
+        assert_eq!(epoch, t1.epoch());
+        assert_eq!(primary_state_root, cid(b"state"));
+        assert_eq!(reference_state_root, cid(b"other state"));
+        assert_eq!(primary_receipt_root, cid(b"receipts"));
+        assert_eq!(reference_receipt_root, cid(b"other receipts"));
+        assert_eq!(message, Some(cid(b"m2")));
+        assert_eq!(primary_receipt, Some(receipt(20)));
+        assert_eq!(reference_receipt, Some(receipt(25)));
+        assert_eq!(
+            trace_diff,
+            vec![
+                r#"-        "ok","#.to_string(),
+                r#"+        "more gas","#.to_string(),
+            ]
+        );
+
+        // A message missing from the reference execution
+        let reference = ExecutionRecord {
+            messages: vec![executed(b"m1", 10, "ok")],
+            ..primary.clone()
+        };
+        let Some(JournalEvent::ShadowExecutionDivergence {
+            message,
+            primary_receipt,
+            reference_receipt,
+            ..
+        }) = find_divergence(t1, &primary, &reference)
+        else {
+            panic!("divergence expected");
+        };
+        assert_eq!(message, Some(cid(b"m2")));
+        assert_eq!(primary_receipt, Some(receipt(20)));
+        assert_eq!(reference_receipt, None);
+    }
+}
@@ -0,0 +1,198 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Cache of the actors at the parent state of the heaviest tipset, read
+//! repeatedly by the RPC methods and the message pool.
+
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+
+use super::StateManager;
+use crate::blocks::Tipset;
+use crate::metrics::{self, values::STATE_MANAGER_ACTOR};
+use crate::shim::{
+    address::Address,
+    state_tree::{ActorState, StateTree},
+};
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use lru::LruCache;
+use nonzero_ext::nonzero;
+use parking_lot::Mutex;
+
+const ACTOR_CACHE_SIZE: NonZeroUsize = nonzero!(4096usize);
+
+/// Actors read at a state root.
+struct CachedState {
+    state_root: Cid,
+    actors: LruCache<Address, Option<ActorState>>,
+}
+
+/// Holds the actors of a single state root, that of the head. Reading at
+/// another root, once the head changed, replaces them. The lock is only held
+/// to look up and insert actors, not while reading them from the blockstore.
+pub(super) struct StateTreeCache {
+    state: Mutex<Option<CachedState>>,
+}
+
+impl StateTreeCache {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(None),
+        }
+    }
+
+    fn get_actor<DB: Blockstore>(
+        &self,
+        db: Arc<DB>,
+        state_root: Cid,
+        addr: &Address,
+    ) -> anyhow::Result<Option<ActorState>> {
+        let cached = self
+            .state
+            .lock()
+            .as_mut()
+            .filter(|state| state.state_root == state_root)
+            .and_then(|state| state.actors.get(addr).cloned());
+        if let Some(actor) = cached {
+            metrics::LRU_CACHE_HIT
+                .get_or_create(&STATE_MANAGER_ACTOR)
+                .inc();
+            return Ok(actor);
+        }
+        metrics::LRU_CACHE_MISS
+            .get_or_create(&STATE_MANAGER_ACTOR)
+            .inc();
+        let actor = StateTree::new_from_root(db, &state_root)?.get_actor(addr)?;
+        let mut state = self.state.lock();
+        let state = match &mut *state {
+            Some(state) if state.state_root == state_root => state,
+            state => state.insert(CachedState {
+                state_root,
+                actors: LruCache::new(ACTOR_CACHE_SIZE),
+            }),
+        };
+        state.actors.put(*addr, actor.clone());
+        Ok(actor)
+    }
+}
+
+impl<DB: Blockstore> StateManager<DB> {
+    /// Gets the actor at the parent state of the heaviest tipset, cached until
+    /// the head changes.
+    pub fn get_actor_cached(&self, addr: &Address) -> anyhow::Result<Option<ActorState>> {
+        let head = self.cs.heaviest_tipset();
+        self.state_tree_cache
+            .get_actor(self.blockstore_owned(), *head.parent_state(), addr)
+    }
+
+    /// Gets the actor at the parent state of `ts`, through the cache of
+    /// [`StateManager::get_actor_cached`] if it is the state of the heaviest
+    /// tipset. Reads at other tipsets bypass the cache.
+    pub fn get_actor_at(&self, addr: &Address, ts: &Tipset) -> anyhow::Result<Option<ActorState>> {
+        if self.cs.heaviest_tipset().parent_state() == ts.parent_state() {
+            self.state_tree_cache
+                .get_actor(self.blockstore_owned(), *ts.parent_state(), addr)
+        } else {
+            self.get_actor(addr, *ts.parent_state())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::{CachingBlockHeader, RawBlockHeader, TipsetKey};
+    use crate::chain::ChainStore;
+    use crate::chain_sync::{SyncConfig, TipsetValidator};
+    use crate::db::MemoryDB;
+    use crate::networks::ChainConfig;
+    use crate::shim::{econ::TokenAmount, state_tree::StateTreeVersion};
+    use crate::utils::db::CborStoreExt as _;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[test]
+    fn get_actor_cached_follows_head() {
+        const HEADS: u64 = 20;
+        let db = Arc::new(MemoryDB::default());
+        let addr = Address::new_id(1000);
+        let no_messages = TipsetValidator::compute_msg_root_from_cids(&db, vec![], vec![]).unwrap();
+        // The sequence of the actor is the epoch of the head
+        let mut headers: Vec<CachingBlockHeader> = vec![];
+        for epoch in 0..HEADS {
+            let mut state_tree = StateTree::new(db.clone(), StateTreeVersion::V5).unwrap();
+            let actor = ActorState::new(
+                Cid::default(),
+                Cid::default(),
+                TokenAmount::default(),
+                epoch,
+                None,
+            );
+            state_tree.set_actor(&addr, actor).unwrap();
+            let mut header = RawBlockHeader {
+                epoch: epoch as _,
+                state_root: state_tree.flush().unwrap(),
+                messages: no_messages,
+                ..Default::default()
+            };
+            if let Some(parent) = headers.last() {
+                header.parents = TipsetKey::from(nunny::vec![*parent.cid()]);
+            }
+            let header = CachingBlockHeader::new(header);
+            db.put_cbor_default(&header).unwrap();
+            headers.push(header);
+        }
+        let chain_config = Arc::new(ChainConfig::default());
+        let chain_store = Arc::new(
+            ChainStore::new(
+                db.clone(),
+                db.clone(),
+                db,
+                chain_config.clone(),
+                headers[0].clone(),
+            )
+            .unwrap(),
+        );
+        let state_manager = StateManager::new(
+            chain_store.clone(),
+            chain_config,
+            Arc::new(SyncConfig::default()),
+        )
+        .unwrap();
+        let sequence = || {
+            state_manager
+                .get_actor_cached(&addr)
+                .unwrap()
+                .unwrap()
+                .sequence
+        };
+
+        let done = AtomicBool::new(false);
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    let mut last = 0;
+                    while !done.load(Ordering::Relaxed) {
+                        // The head only moves forward
+                        let read = sequence();
+                        assert!(read >= last);
+                        last = read;
+                    }
+                });
+            }
+            for header in &headers[1..] {
+                chain_store
+                    .set_heaviest_tipset(Arc::new(Tipset::from(header)))
+                    .unwrap();
+                assert_eq!(sequence(), header.epoch as u64);
+            }
+            done.store(true, Ordering::Relaxed);
+        });
+
+        // Reads at another tipset bypass the cache
+        let genesis = Tipset::from(&headers[0]);
+        let actor = state_manager.get_actor_at(&addr, &genesis).unwrap();
+        assert_eq!(actor.unwrap().sequence, 0);
+        assert_eq!(sequence(), HEADS - 1);
+    }
+}
@@ -1,7 +1,7 @@
 // Copyright 2019-2025 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use crate::shim::actors::miner;
+use crate::shim::actors::miner::{self, ext::MinerStateExt as _};
 use crate::shim::{
     actors::{is_account_actor, is_ethaccount_actor, is_placeholder_actor},
     address::{Address, Payload},
@@ -10,6 +10,7 @@ use crate::shim::{
     state_tree::ActorState,
     version::NetworkVersion,
 };
+use crate::utils::bitfield::difference;
 use crate::utils::encoding::prover_id_from_u64;
 use cid::Cid;
 use fil_actors_shared::filecoin_proofs_api::post;
@@ -41,31 +42,17 @@ where
             .ok_or_else(|| Error::State("Miner actor address could not be resolved".to_string()))?;
         let mas = miner::State::load(self.blockstore(), actor.code, actor.state)?;
 
-        let proving_sectors = {
-            let mut proving_sectors = BitField::new();
-
-            if nv < NetworkVersion::V7 {
-                mas.for_each_deadline(&self.chain_config.policy, store, |_, deadline| {
-                    let mut fault_sectors = BitField::new();
-                    deadline.for_each(store, |_, partition: miner::Partition| {
-                        proving_sectors |= partition.all_sectors();
-                        fault_sectors |= partition.faulty_sectors();
-                        Ok(())
-                    })?;
-
-                    proving_sectors -= &fault_sectors;
-                    Ok(())
-                })?;
-            } else {
-                mas.for_each_deadline(&self.chain_config.policy, store, |_, deadline| {
-                    deadline.for_each(store, |_, partition: miner::Partition| {
-                        proving_sectors |= &partition.active_sectors();
-                        Ok(())
-                    })?;
-                    Ok(())
-                })?;
-            }
-            proving_sectors
+        let policy = &self.chain_config.policy;
+        let proving_sectors = if nv < NetworkVersion::V7 {
+            let all = mas.partition_sectors_union(policy, store, |partition| {
+                partition.all_sectors().clone()
+            })?;
+            let faulty = mas.partition_sectors_union(policy, store, |partition| {
+                partition.faulty_sectors().clone()
+            })?;
+            difference(&all, &faulty)
+        } else {
+            mas.partition_sectors_union(policy, store, |partition| partition.active_sectors())?
         };
 
         let num_prov_sect = proving_sectors.len();
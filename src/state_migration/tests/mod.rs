@@ -69,14 +69,11 @@ async fn test_nv19_state_migration_calibnet() {
     .await
 }
 
-async fn test_state_migration(
-    height: Height,
-    network: NetworkChain,
-    old_state: Cid,
-    expected_new_state: Cid,
-) {
+/// Returns the path of a CAR file holding the state tree at `state`, downloaded
+/// from the CI bucket unless it is already cached.
+pub(crate) async fn fetch_state_car(state: &Cid) -> PathBuf {
     // Car files are cached under data folder for Go test to pick up without network access
-    let car_path = PathBuf::from(format!("./src/state_migration/tests/data/{old_state}.car"));
+    let car_path = PathBuf::from(format!("./src/state_migration/tests/data/{state}.car"));
     if !car_path.is_file() {
         let tmp: tempfile::TempPath = tempfile::NamedTempFile::new_in(car_path.parent().unwrap())
             .unwrap()
@@ -90,7 +87,7 @@ async fn test_state_migration(
             },
             || async {
                 let response = global_http_client().get(format!(
-                    "https://forest-continuous-integration.fra1.digitaloceanspaces.com/state_migration/state/{old_state}.car"
+                    "https://forest-continuous-integration.fra1.digitaloceanspaces.com/state_migration/state/{state}.car"
                 )).timeout(timeout).send().await.unwrap();
                 let reader = response
                     .bytes_stream()
@@ -108,6 +105,16 @@ async fn test_state_migration(
         tmp.persist(&car_path).unwrap();
     }
 
+    car_path
+}
+
+async fn test_state_migration(
+    height: Height,
+    network: NetworkChain,
+    old_state: Cid,
+    expected_new_state: Cid,
+) {
+    let car_path = fetch_state_car(&old_state).await;
     let store = Arc::new(
         crate::db::car::plain::PlainCar::new(RandomAccessFile::open(&car_path).unwrap()).unwrap(),
     );
@@ -31,9 +31,12 @@ where
                 Subcommand::Fetch(cmd) => cmd.run().await,
                 Subcommand::Archive(cmd) => cmd.run().await,
                 Subcommand::DB(cmd) => cmd.run().await,
+                Subcommand::Index(cmd) => cmd.run().await,
+                Subcommand::Journal(cmd) => cmd.run().await,
                 Subcommand::Car(cmd) => cmd.run().await,
                 Subcommand::Api(cmd) => cmd.run().await,
                 Subcommand::Net(cmd) => cmd.run().await,
+                Subcommand::MinerState(cmd) => cmd.run().await,
                 Subcommand::Shed(cmd) => cmd.run(client).await,
             }
         })
@@ -136,11 +136,14 @@ pub async fn start_offline_server(
         mpool: Arc::new(message_pool),
         bad_blocks: Default::default(),
         sync_state: Arc::new(parking_lot::RwLock::new(Default::default())),
+        sync_watchdog: Default::default(),
         eth_event_handler: Arc::new(EthEventHandler::new()),
         sync_network_context,
         network_name,
         start_time: chrono::Utc::now(),
         shutdown,
+        config_reloader: None,
+        db_statistics: None,
         tipset_send,
     };
     rpc_state.sync_state.write().set_stage(SyncStage::Idle);
@@ -1273,6 +1273,7 @@ fn state_tests_with_tipset<DB: Blockstore>(
                 validate_message_lookup(StateSearchMsgLimited::request((msg_cid, 800))?),
             ]);
         }
+        let state = StateTree::new_from_root(store.clone(), tipset.parent_state())?;
         for msg in sample_messages(bls_messages.iter(), secp_messages.iter()) {
             tests.extend([
                 RpcTest::identity(StateAccountKey::request((msg.from(), tipset.key().into()))?),
@@ -1302,8 +1303,34 @@ fn state_tests_with_tipset<DB: Blockstore>(
                     tipset.key().into(),
                     tipset.epoch(),
                 ))?),
-                RpcTest::identity(StateCall::request((msg.clone(), tipset.key().into()))?),
+                RpcTest::identity(StateCall::request((
+                    msg.clone(),
+                    tipset.key().into(),
+                    None,
+                ))?),
             ]);
+            // Only the methods known to the params registry can be compared.
+            if let Some(actor) = state.get_actor(&msg.to())? {
+                if let Ok(params) = crate::rpc::registry::decode_params(
+                    &actor.code,
+                    msg.method_num(),
+                    msg.params().bytes(),
+                ) {
+                    tests.extend([
+                        RpcTest::identity(StateDecodeParams::request((
+                            msg.to(),
+                            msg.method_num(),
+                            msg.params().bytes().to_vec(),
+                            tipset.key().into(),
+                        ))?),
+                        RpcTest::identity(StateEncodeParams::request((
+                            actor.code,
+                            msg.method_num(),
+                            params,
+                        ))?),
+                    ]);
+                }
+            }
         }
     }
 
@@ -1314,6 +1341,8 @@ fn wallet_tests(worker_address: Option<Address>) -> Vec<RpcTest> {
     // This address has been funded by the calibnet faucet and the private keys
     // has been discarded. It should always have a non-zero balance.
     let known_wallet = Address::from_str("t1c4dkec3qhrnrsa4mccy7qntkyq2hhsma4sq7lui").unwrap();
+    // An address that never received funds, so that no actor exists for it.
+    let unknown_wallet = Address::new_secp256k1(&[0; 65]).unwrap();
     // "Hello world!" signed with the above address:
     let signature = "44364ca78d85e53dda5ac6f719a4f2de3261c17f58558ab7730f80c478e6d43775244e7d6855afad82e4a1fd6449490acfa88e3fcfe7c1fe96ed549c100900b400";
     let text = "Hello world!".as_bytes().to_vec();
@@ -1326,6 +1355,10 @@ fn wallet_tests(worker_address: Option<Address>) -> Vec<RpcTest> {
 
     let mut tests = vec![
         RpcTest::identity(WalletBalance::request((known_wallet,)).unwrap()),
+        // Actors that don't exist have a zero balance
+        RpcTest::identity(WalletBalance::request((unknown_wallet,)).unwrap()),
+        RpcTest::basic(WalletList::request(()).unwrap()),
+        RpcTest::identity(MpoolGetNonce::request((known_wallet,)).unwrap()),
         RpcTest::identity(WalletValidateAddress::request((known_wallet.to_string(),)).unwrap()),
         RpcTest::identity(WalletVerify::request((known_wallet, text, signature)).unwrap()),
     ];
@@ -115,11 +115,14 @@ async fn ctx(
         mpool: Arc::new(message_pool),
         bad_blocks: Default::default(),
         sync_state: Arc::new(RwLock::new(Default::default())),
+        sync_watchdog: Default::default(),
         eth_event_handler: Arc::new(EthEventHandler::new()),
         sync_network_context,
         network_name,
         start_time: chrono::Utc::now(),
         shutdown,
+        config_reloader: None,
+        db_statistics: None,
         tipset_send,
     });
     rpc_state.sync_state.write().set_stage(SyncStage::Idle);
@@ -210,6 +213,10 @@ impl<T: SettingsStore> SettingsStore for ReadOpsTrackingStore<T> {
     fn setting_keys(&self) -> anyhow::Result<Vec<String>> {
         self.inner.setting_keys()
     }
+
+    fn delete(&self, key: &str) -> anyhow::Result<()> {
+        self.inner.delete(key)
+    }
 }
 
 impl<T: BitswapStoreRead> BitswapStoreRead for ReadOpsTrackingStore<T> {
@@ -129,11 +129,14 @@ async fn ctx(
         mpool: Arc::new(message_pool),
         bad_blocks: Default::default(),
         sync_state: Arc::new(RwLock::new(Default::default())),
+        sync_watchdog: Default::default(),
         eth_event_handler: Arc::new(EthEventHandler::new()),
         sync_network_context,
         network_name,
         start_time: chrono::Utc::now(),
         shutdown,
+        config_reloader: None,
+        db_statistics: None,
         tipset_send,
     });
     rpc_state.sync_state.write().set_stage(SyncStage::Idle);
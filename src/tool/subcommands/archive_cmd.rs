@@ -444,7 +444,8 @@ async fn do_export(
     pb.enable_steady_tick(std::time::Duration::from_secs_f32(0.1));
     let writer = pb.wrap_async_write(writer);
 
-    crate::chain::export::<Sha256>(store.clone(), &ts, depth, writer, seen, true).await?;
+    crate::chain::export::<Sha256>(store.clone(), &ts, depth, None, writer, seen, true, true)
+        .await?;
 
     Ok(())
 }
@@ -1,27 +1,37 @@
 // Copyright 2019-2025 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use crate::chain::ChainStore;
 use crate::chain::{
     index::{ChainIndex, ResolveNullTipset},
     ChainEpochDelta,
 };
+use crate::chain_sync::SyncConfig;
 use crate::db::car::forest::DEFAULT_FOREST_CAR_FRAME_SIZE;
 use crate::db::car::ManyCar;
-use crate::ipld::{stream_chain, stream_graph, unordered_stream_graph};
+use crate::ipld::{stream_chain, stream_graph, unordered_stream_chain, unordered_stream_graph};
+use crate::networks::{ChainConfig, NetworkChain};
+use crate::shim::address::CurrentNetwork;
 use crate::shim::clock::ChainEpoch;
+use crate::shim::fvm_shared_latest::address::Network;
+use crate::state_manager::StateManager;
 use crate::utils::db::car_stream::{CarBlock, CarStream};
 use crate::utils::encoding::extract_cids;
+use crate::utils::monitoring::MemStatsTracker;
 use crate::utils::stream::par_buffer;
 use anyhow::Context as _;
 use cid::Cid;
 use clap::Subcommand;
+use futures::future::Either;
 use futures::{StreamExt, TryStreamExt};
 use fvm_ipld_encoding::DAG_CBOR;
+use human_repr::HumanCount as _;
 use indicatif::{ProgressBar, ProgressStyle};
 use itertools::Itertools;
 use std::ops::Deref;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::{
     fs::File,
     io::{AsyncWrite, AsyncWriteExt, BufReader},
@@ -77,6 +87,22 @@ pub enum BenchmarkCommands {
         /// How many state-roots to include. Lower limit is 900 for `calibnet` and `mainnet`.
         #[arg(short, long, default_value_t = 2000)]
         depth: ChainEpochDelta,
+        /// Walk the graph with all the CPUs, as `snapshot export --parallel` does, instead of
+        /// depth-first with a single task
+        #[arg(long)]
+        parallel: bool,
+    },
+    /// Replaying historical messages one by one and as a batch
+    Replay {
+        /// Snapshot input files (`.car.`, `.car.zst`, `.forest.car.zst`)
+        #[arg(required = true)]
+        snapshot_files: Vec<PathBuf>,
+        /// Epoch of the most recent tipset to replay messages from, defaults to the head
+        #[arg(short, long)]
+        epoch: Option<ChainEpoch>,
+        /// Number of messages to replay
+        #[arg(short, long, default_value_t = 100)]
+        messages: usize,
     },
 }
 
@@ -107,10 +133,23 @@ impl BenchmarkCommands {
                 frame_size,
                 epoch,
                 depth,
+                parallel,
             } => {
-                benchmark_exporting(snapshot_files, compression_level, frame_size, epoch, depth)
-                    .await
+                benchmark_exporting(
+                    snapshot_files,
+                    compression_level,
+                    frame_size,
+                    epoch,
+                    depth,
+                    parallel,
+                )
+                .await
             }
+            Self::Replay {
+                snapshot_files,
+                epoch,
+                messages,
+            } => benchmark_replay(snapshot_files, epoch, messages).await,
         }
     }
 }
@@ -223,6 +262,7 @@ async fn benchmark_exporting(
     frame_size: usize,
     epoch: Option<ChainEpoch>,
     depth: ChainEpochDelta,
+    parallel: bool,
 ) -> anyhow::Result<()> {
     let store = Arc::new(open_store(input)?);
     let heaviest = store.heaviest_tipset()?;
@@ -237,12 +277,29 @@ async fn benchmark_exporting(
     let stateroot_lookup_limit = ts.epoch() - depth;
 
     let mut dest = indicatif_sink("exported");
+    // The visited set of the walk makes up most of the memory used by the
+    // export, on top of the store
+    let rss_before = memory_stats::memory_stats().map_or(0, |usage| usage.physical_mem);
+    let mem_stats_tracker = Arc::new(MemStatsTracker::new(Duration::from_millis(100)));
+    let mem_stats_task = tokio::spawn({
+        let mem_stats_tracker = mem_stats_tracker.clone();
+        async move { mem_stats_tracker.run_loop().await }
+    });
 
-    let blocks = stream_chain(
-        Arc::clone(&store),
-        ts.deref().clone().chain_owned(Arc::clone(&store)),
-        stateroot_lookup_limit,
-    );
+    let tipsets = ts.deref().clone().chain_owned(Arc::clone(&store));
+    let blocks = if parallel {
+        Either::Left(unordered_stream_chain(
+            Arc::clone(&store),
+            tipsets,
+            stateroot_lookup_limit,
+        ))
+    } else {
+        Either::Right(stream_chain(
+            Arc::clone(&store),
+            tipsets,
+            stateroot_lookup_limit,
+        ))
+    };
 
     let frames = crate::db::car::forest::Encoder::compress_stream(
         frame_size,
@@ -251,6 +308,92 @@ async fn benchmark_exporting(
     );
     crate::db::car::forest::Encoder::write(&mut dest, ts.key().to_cids(), frames).await?;
     dest.flush().await?;
+    mem_stats_task.abort();
+    println!(
+        "Extra peak physical memory usage: {}",
+        mem_stats_tracker
+            .peak_physical_mem()
+            .saturating_sub(rss_before)
+            .human_count_bytes()
+    );
+    Ok(())
+}
+
+// Replays the most recent messages up to `epoch`, first one at a time, which
+// executes their tipset for each of them, then as a batch, which executes each
+// tipset once.
+async fn benchmark_replay(
+    input: Vec<PathBuf>,
+    epoch: Option<ChainEpoch>,
+    messages: usize,
+) -> anyhow::Result<()> {
+    let store = Arc::new(open_store(input)?);
+    let heaviest = store.heaviest_tipset()?;
+    let genesis = heaviest.genesis(&store)?;
+    let network = NetworkChain::from_genesis_or_devnet_placeholder(genesis.cid());
+    let chain_config = Arc::new(ChainConfig::from_chain(&network));
+    if chain_config.is_testnet() {
+        CurrentNetwork::set_global(Network::Testnet);
+    }
+    let chain_store = Arc::new(ChainStore::new(
+        store.clone(),
+        store.clone(),
+        store.clone(),
+        chain_config.clone(),
+        genesis,
+    )?);
+    let state_manager = Arc::new(StateManager::new(
+        chain_store.clone(),
+        chain_config,
+        Arc::new(SyncConfig::default()),
+    )?);
+
+    let ts = chain_store.chain_index.tipset_by_height(
+        epoch.unwrap_or(heaviest.epoch()),
+        Arc::new(heaviest),
+        ResolveNullTipset::TakeOlder,
+    )?;
+    let mut pairs = vec![];
+    let mut tipsets = 0;
+    for ts in chain_store.chain_index.chain(ts) {
+        if pairs.len() >= messages {
+            break;
+        }
+        let msgs = chain_store.messages_for_tipset(&ts)?;
+        if !msgs.is_empty() {
+            tipsets += 1;
+        }
+        pairs.extend(
+            msgs.iter()
+                .take(messages - pairs.len())
+                .map(|msg| (msg.cid(), ts.clone())),
+        );
+    }
+    println!("Replaying {} messages from {tipsets} tipsets", pairs.len());
+
+    let start = Instant::now();
+    let mut receipts = Vec::with_capacity(pairs.len());
+    for (mcid, ts) in &pairs {
+        receipts.push(state_manager.replay(ts.clone(), *mcid).await?.msg_rct);
+    }
+    println!(
+        "One by one: {}",
+        humantime::format_duration(start.elapsed())
+    );
+
+    let start = Instant::now();
+    let results = state_manager.replay_batch(&pairs).await?;
+    println!(
+        "As a batch: {}",
+        humantime::format_duration(start.elapsed())
+    );
+
+    for (receipt, (_, apply_ret)) in receipts.into_iter().zip(results) {
+        anyhow::ensure!(
+            receipt == Some(apply_ret.msg_receipt()),
+            "batch replay results differ"
+        );
+    }
     Ok(())
 }
 
@@ -3,6 +3,8 @@
 
 use std::path::{Path, PathBuf};
 
+use anyhow::Context as _;
+use cid::Cid;
 use clap::Subcommand;
 use futures::{StreamExt, TryStreamExt};
 use fvm_ipld_blockstore::Blockstore;
@@ -14,7 +16,13 @@ use tokio::{
     io::{AsyncWriteExt, BufReader},
 };
 
-use crate::db::car::ForestCar;
+use crate::blocks::TipsetKey;
+use crate::daemon::db_util::load_all_forest_cars;
+use crate::db::car::{ForestCar, ManyCar};
+use crate::db::db_engine::open_db;
+use crate::db::setting_keys::HEAD_KEY;
+use crate::db::{SettingsStoreExt as _, CAR_DB_DIR_NAME};
+use crate::ipld::reachability::{check_reachability, ReachabilityReport};
 use crate::utils::db::{
     car_stream::CarStream,
     car_util::{dedup_block_stream, merge_car_streams},
@@ -43,6 +51,28 @@ pub enum CarCommands {
         #[arg(long)]
         ignore_forest_index: bool,
     },
+    /// Check that a snapshot is complete: all headers back to genesis, and the
+    /// messages and state trees of the most recent epochs must be present and
+    /// hashed correctly
+    Verify {
+        /// CAR archives. Supported extensions: `.car`, `.car.zst`, `.forest.car.zst`
+        #[arg(required_unless_present = "db", conflicts_with = "db")]
+        car_files: Vec<PathBuf>,
+        /// Forest database directory to check instead of CAR archives
+        #[arg(long)]
+        db: Option<PathBuf>,
+        /// Block header CIDs of the tipset to start from. Defaults to the
+        /// heaviest tipset of the archives, or the head of the database
+        #[arg(long, num_args = 1..)]
+        root: Vec<Cid>,
+        /// Number of recent epochs to check messages and state trees for
+        #[arg(long, default_value_t = 2000)]
+        depth: u32,
+        /// Memory, in MiB, used to keep track of visited blocks. Lower values
+        /// increase the chance of skipping blocks
+        #[arg(long, default_value_t = 1024)]
+        visited_set_mib: usize,
+    },
 }
 
 impl CarCommands {
@@ -78,6 +108,39 @@ impl CarCommands {
                 ignore_block_validity,
                 ignore_forest_index,
             } => validate(&car_file, ignore_block_validity, ignore_forest_index).await?,
+            Self::Verify {
+                car_files,
+                db,
+                root,
+                depth,
+                visited_set_mib,
+            } => {
+                let root = NonEmpty::new(root).ok().map(TipsetKey::from);
+                let visited_filter_bytes = visited_set_mib * 1024 * 1024;
+                let report = match db {
+                    Some(db_root) => {
+                        let store = ManyCar::new(open_db(db_root.clone(), Default::default())?);
+                        load_all_forest_cars(&store, &db_root.join(CAR_DB_DIR_NAME))?;
+                        let root = match root {
+                            Some(root) => root,
+                            None => store
+                                .read_obj::<TipsetKey>(HEAD_KEY)?
+                                .context("database has no head")?,
+                        };
+                        verify(&store, &root, depth, visited_filter_bytes)?
+                    }
+                    None => {
+                        let store = ManyCar::try_from(car_files)?;
+                        let root = match root {
+                            Some(root) => root,
+                            None => store.heaviest_tipset()?.key().clone(),
+                        };
+                        verify(&store, &root, depth, visited_filter_bytes)?
+                    }
+                };
+                println!("{report}");
+                anyhow::ensure!(report.is_complete(), "snapshot is incomplete");
+            }
         }
         Ok(())
     }
@@ -122,6 +185,22 @@ async fn validate(
     Ok(())
 }
 
+fn verify(
+    db: &impl Blockstore,
+    root: &TipsetKey,
+    depth: u32,
+    visited_filter_bytes: usize,
+) -> anyhow::Result<ReachabilityReport> {
+    let pb = ProgressBar::new_spinner()
+        .with_style(ProgressStyle::with_template("{spinner} {msg}").expect("infallible"));
+    pb.enable_steady_tick(std::time::Duration::from_millis(100));
+    let report = check_reachability(db, root, depth.into(), visited_filter_bytes, |tipset| {
+        pb.set_message(format!("checking epoch {}", tipset.epoch()))
+    })?;
+    pb.finish_and_clear();
+    Ok(report)
+}
+
 #[cfg(test)]
 mod tests {
     use super::validate;
@@ -0,0 +1,147 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::blocks::Tipset;
+use crate::chain::ChainStore;
+use crate::chain_sync::SyncConfig;
+use crate::cli_shared::{car_db_path, chain_path, read_config};
+use crate::daemon::bundle::load_actor_bundles;
+use crate::daemon::db_util::{backfill_event_index, index_imported_chain, load_all_forest_cars};
+use crate::db::car::ManyCar;
+use crate::db::db_engine::{db_root, open_db, Db};
+use crate::genesis::read_genesis_header;
+use crate::interpreter::{VMEvent, VMTrace};
+use crate::networks::NetworkChain;
+use crate::shim::clock::ChainEpoch;
+use crate::state_manager::{StateManager, NO_CALLBACK};
+use clap::Subcommand;
+
+#[derive(Debug, Subcommand)]
+pub enum IndexCommands {
+    /// Build the chain indexes (epoch checkpoints and Ethereum mappings) of
+    /// the node database, e.g. after importing a snapshot with
+    /// `--skip-indexing`. The node must not be running.
+    Rebuild {
+        /// Optional TOML file containing forest daemon configuration
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+        /// Optional chain, will override the chain section of configuration file if used
+        #[arg(long)]
+        chain: Option<NetworkChain>,
+    },
+    /// Index the actor events of a range of epochs of the node database, by
+    /// executing their tipsets. Epochs already indexed are skipped, and an
+    /// interrupted backfill of the same range resumes where it stopped. The
+    /// node must not be running.
+    BackfillEvents {
+        /// Optional TOML file containing forest daemon configuration
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+        /// Optional chain, will override the chain section of configuration file if used
+        #[arg(long)]
+        chain: Option<NetworkChain>,
+        /// Lowest epoch to index
+        #[arg(long)]
+        from: ChainEpoch,
+        /// Highest epoch to index, defaults to the head
+        #[arg(long)]
+        to: Option<ChainEpoch>,
+        /// Number of tipsets executed in parallel
+        #[arg(long, default_value_t = 4)]
+        concurrency: usize,
+    },
+}
+
+impl IndexCommands {
+    pub async fn run(&self) -> anyhow::Result<()> {
+        match self {
+            Self::Rebuild { config, chain } => {
+                let chain_store = open_chain_store(config.as_ref(), chain.clone(), false).await?;
+                let head = chain_store.heaviest_tipset();
+                println!("Indexing chain from epoch {}", head.epoch());
+                index_imported_chain(&chain_store, &head)
+            }
+            Self::BackfillEvents {
+                config,
+                chain,
+                from,
+                to,
+                concurrency,
+            } => {
+                let chain_store = open_chain_store(config.as_ref(), chain.clone(), true).await?;
+                let chain_config = chain_store.chain_config.clone();
+                let head = chain_store.heaviest_tipset();
+                let to = to.unwrap_or(head.epoch());
+                let state_manager = Arc::new(StateManager::new(
+                    chain_store.clone(),
+                    chain_config,
+                    Arc::new(SyncConfig::default()),
+                )?);
+                println!("Backfilling the events of epochs {from}..={to}");
+                let stats = backfill_event_index(
+                    &chain_store,
+                    head,
+                    *from..=to,
+                    *concurrency,
+                    |ts: Arc<Tipset>| {
+                        let state_manager = state_manager.clone();
+                        async move {
+                            Ok(state_manager
+                                .compute_tipset_state(
+                                    ts,
+                                    NO_CALLBACK,
+                                    VMTrace::NotTraced,
+                                    VMEvent::Pushed,
+                                )
+                                .await?
+                                .events)
+                        }
+                    },
+                )
+                .await?;
+                println!(
+                    "Indexed the events of {} tipsets, {} were already indexed",
+                    stats.indexed, stats.skipped
+                );
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Opens the chain store of the node database, loading the actor bundles if
+/// tipsets are to be executed.
+async fn open_chain_store(
+    config: Option<&PathBuf>,
+    chain: Option<NetworkChain>,
+    load_actors: bool,
+) -> anyhow::Result<Arc<ChainStore<ManyCar<Arc<Db>>>>> {
+    let (_, config) = read_config(config, chain)?;
+    let db_writer = Arc::new(open_db(
+        db_root(&chain_path(&config))?,
+        config.db_config().clone(),
+    )?);
+    let db = Arc::new(ManyCar::new(db_writer.clone()));
+    load_all_forest_cars(&db, &car_db_path(&config)?)?;
+    if load_actors {
+        load_actor_bundles(&db, config.chain()).await?;
+    }
+
+    let chain_config = Arc::new(config.chain_config()?);
+    let genesis_header = read_genesis_header(
+        config.client.genesis_file.as_deref(),
+        chain_config.genesis_bytes(&db).await?.as_deref(),
+        &db,
+    )
+    .await?;
+    Ok(Arc::new(ChainStore::new(
+        db,
+        db_writer.clone(),
+        db_writer,
+        chain_config,
+        genesis_header,
+    )?))
+}
@@ -0,0 +1,65 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::io::Write as _;
+use std::path::PathBuf;
+
+use crate::cli_shared::{chain_path, read_config};
+use crate::journal::{read_entries, JournalEventType, JournalFilter, JOURNAL_DIR_NAME};
+use crate::networks::NetworkChain;
+use anyhow::Context as _;
+use chrono::{DateTime, TimeDelta, Utc};
+use clap::Subcommand;
+
+#[derive(Debug, Subcommand)]
+pub enum JournalCommands {
+    /// Print the journal events of the node matching the filters, oldest
+    /// first, as JSON lines
+    Grep {
+        /// Types of the events to print, all if omitted
+        #[arg(long = "type", value_delimiter = ',')]
+        event_types: Vec<JournalEventType>,
+        /// Print the events recorded since this RFC 3339 time, or this long
+        /// ago, e.g. `2h 30m`
+        #[arg(long, value_parser = parse_since)]
+        since: Option<DateTime<Utc>>,
+        /// Optional TOML file containing forest daemon configuration
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+        /// Optional chain, will override the chain section of configuration file if used
+        #[arg(long)]
+        chain: Option<NetworkChain>,
+    },
+}
+
+impl JournalCommands {
+    pub async fn run(self) -> anyhow::Result<()> {
+        match self {
+            Self::Grep {
+                event_types,
+                since,
+                config,
+                chain,
+            } => {
+                let (_, config) = read_config(config.as_ref(), chain)?;
+                let dir = chain_path(&config).join(JOURNAL_DIR_NAME);
+                anyhow::ensure!(dir.is_dir(), "no journal found at {}", dir.display());
+                let mut stdout = std::io::stdout().lock();
+                for entry in read_entries(&dir, JournalFilter { event_types, since })? {
+                    serde_json::to_writer(&mut stdout, &entry?)?;
+                    writeln!(stdout)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn parse_since(s: &str) -> anyhow::Result<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    let ago =
+        humantime::parse_duration(s).with_context(|| format!("invalid time or duration: {s}"))?;
+    Ok(Utc::now() - TimeDelta::from_std(ago)?)
+}
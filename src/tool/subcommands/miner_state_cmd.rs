@@ -0,0 +1,452 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::blocks::Tipset;
+use crate::chain::ChainStore;
+use crate::chain_sync::SyncConfig;
+use crate::cli::humantoken::TokenAmountPretty as _;
+use crate::db::car::ManyCar;
+use crate::networks::{ChainConfig, NetworkChain};
+use crate::shim::actors::miner::{self, ext::MinerStateExt as _};
+use crate::shim::address::{Address, CurrentNetwork, StrictAddress};
+use crate::shim::clock::ChainEpoch;
+use crate::shim::econ::TokenAmount;
+use crate::shim::fvm_shared_latest::address::Network;
+use crate::state_manager::StateManager;
+use fvm_ipld_blockstore::Blockstore;
+use serde::Serialize;
+use tabled::{builder::Builder, settings::Style};
+
+/// Print a human-readable overview of a miner's state
+#[derive(Debug, clap::Args)]
+pub struct MinerStateCommand {
+    /// Miner address
+    miner: StrictAddress,
+    /// Snapshot files to read the miner state from
+    #[arg(long, required = true, num_args = 1..)]
+    snapshot_files: Vec<PathBuf>,
+    /// Print the overview as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+impl MinerStateCommand {
+    pub async fn run(self) -> anyhow::Result<()> {
+        let store = Arc::new(ManyCar::try_from(self.snapshot_files)?);
+        let head = store.heaviest_tipset()?;
+        let genesis = head.genesis(&store)?;
+        let network = NetworkChain::from_genesis_or_devnet_placeholder(genesis.cid());
+        let chain_config = Arc::new(ChainConfig::from_chain(&network));
+        if chain_config.is_testnet() {
+            CurrentNetwork::set_global(Network::Testnet);
+        }
+        let chain_store = Arc::new(ChainStore::new(
+            store.clone(),
+            store.clone(),
+            store.clone(),
+            chain_config.clone(),
+            genesis,
+        )?);
+        let state_manager =
+            StateManager::new(chain_store, chain_config, Arc::new(SyncConfig::default()))?;
+
+        let summary = MinerStateSummary::load(&state_manager, &head, self.miner.into())?;
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&summary)?);
+        } else {
+            println!("{}", summary.to_table());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct MinerStateSummary {
+    #[serde(with = "crate::lotus_json")]
+    miner: Address,
+    epoch: ChainEpoch,
+    #[serde(with = "crate::lotus_json")]
+    available_balance: TokenAmount,
+    #[serde(with = "crate::lotus_json")]
+    fee_debt: TokenAmount,
+    #[serde(with = "crate::lotus_json")]
+    locked_funds: TokenAmount,
+    live_sectors: u64,
+    faulty_sectors: u64,
+    recovering_sectors: u64,
+    /// Number of deadlines with at least one partition
+    active_deadlines: u64,
+    partitions: u64,
+    next_deadline: NextDeadline,
+    pending_worker_change: Option<PendingWorkerChange>,
+    beneficiary: Beneficiary,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct NextDeadline {
+    index: u64,
+    open: ChainEpoch,
+    close: ChainEpoch,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct PendingWorkerChange {
+    #[serde(with = "crate::lotus_json")]
+    new_worker: Address,
+    effective_at: ChainEpoch,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct Beneficiary {
+    #[serde(with = "crate::lotus_json")]
+    address: Address,
+    #[serde(with = "crate::lotus_json")]
+    quota: TokenAmount,
+    #[serde(with = "crate::lotus_json")]
+    used_quota: TokenAmount,
+    expiration: ChainEpoch,
+}
+
+impl MinerStateSummary {
+    fn load<DB: Blockstore + Send + Sync + 'static>(
+        state_manager: &StateManager<DB>,
+        ts: &Tipset,
+        miner: Address,
+    ) -> anyhow::Result<Self> {
+        let store = state_manager.blockstore();
+        let policy = &state_manager.chain_config().policy;
+        let actor = state_manager.get_required_actor(&miner, *ts.parent_state())?;
+        let state = miner::State::load(store, actor.code, actor.state)?;
+        let info = state_manager.miner_info(&miner, ts)?;
+
+        let mut live_sectors = 0;
+        let mut active_deadlines = 0;
+        let mut partitions = 0;
+        state.for_each_deadline(policy, store, |_idx, deadline| {
+            let mut deadline_partitions = 0;
+            deadline.for_each(store, |_idx, partition| {
+                live_sectors += partition.live_sectors().len();
+                deadline_partitions += 1;
+                Ok(())
+            })?;
+            if deadline_partitions > 0 {
+                active_deadlines += 1;
+            }
+            partitions += deadline_partitions;
+            Ok(())
+        })?;
+        let next_deadline = state
+            .recorded_deadline_info(policy, ts.epoch())
+            .next_not_elapsed();
+
+        Ok(Self {
+            miner,
+            epoch: ts.epoch(),
+            available_balance: state.available_balance(actor.balance.atto())?.into(),
+            fee_debt: state.fee_debt().into(),
+            locked_funds: state.locked_funds().into(),
+            live_sectors,
+            faulty_sectors: state_manager.miner_faults(&miner, ts)?.len(),
+            recovering_sectors: state_manager.miner_recoveries(&miner, ts)?.len(),
+            active_deadlines,
+            partitions,
+            next_deadline: NextDeadline {
+                index: next_deadline.index,
+                open: next_deadline.open,
+                close: next_deadline.close,
+            },
+            pending_worker_change: info.new_worker.map(|new_worker| PendingWorkerChange {
+                new_worker: new_worker.into(),
+                effective_at: info.worker_change_epoch,
+            }),
+            beneficiary: Beneficiary {
+                address: info.beneficiary.into(),
+                quota: info.beneficiary_term.quota.into(),
+                used_quota: info.beneficiary_term.used_quota.into(),
+                expiration: info.beneficiary_term.expiration,
+            },
+        })
+    }
+
+    fn to_table(&self) -> String {
+        let mut builder = Builder::default();
+        builder.push_record(["Miner".into(), self.miner.to_string()]);
+        builder.push_record(["Epoch".into(), self.epoch.to_string()]);
+        builder.push_record([
+            "Available balance".into(),
+            format!("{:.4}", self.available_balance.pretty()),
+        ]);
+        builder.push_record(["Fee debt".into(), format!("{:.4}", self.fee_debt.pretty())]);
+        builder.push_record([
+            "Locked funds".into(),
+            format!("{:.4}", self.locked_funds.pretty()),
+        ]);
+        builder.push_record([
+            "Sectors".into(),
+            format!(
+                "{} live, {} faulty, {} recovering",
+                self.live_sectors, self.faulty_sectors, self.recovering_sectors
+            ),
+        ]);
+        builder.push_record([
+            "Deadlines".into(),
+            format!(
+                "{} active, {} partitions",
+                self.active_deadlines, self.partitions
+            ),
+        ]);
+        builder.push_record([
+            "Next deadline".into(),
+            format!(
+                "#{} (epochs {}..{})",
+                self.next_deadline.index, self.next_deadline.open, self.next_deadline.close
+            ),
+        ]);
+        builder.push_record([
+            "Pending worker change".into(),
+            match &self.pending_worker_change {
+                Some(change) => format!("{} at epoch {}", change.new_worker, change.effective_at),
+                None => "none".into(),
+            },
+        ]);
+        builder.push_record([
+            "Beneficiary".into(),
+            format!(
+                "{} (used {:.4} of {:.4}, expires at epoch {})",
+                self.beneficiary.address,
+                self.beneficiary.used_quota.pretty(),
+                self.beneficiary.quota.pretty(),
+                self.beneficiary.expiration
+            ),
+        ]);
+        builder.build().with(Style::rounded()).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary() -> MinerStateSummary {
+        MinerStateSummary {
+            miner: Address::new_id(1000),
+            epoch: 4000,
+            available_balance: TokenAmount::from_whole(10),
+            fee_debt: TokenAmount::from_whole(1),
+            locked_funds: TokenAmount::from_whole(20),
+            live_sectors: 30,
+            faulty_sectors: 2,
+            recovering_sectors: 1,
+            active_deadlines: 5,
+            partitions: 7,
+            next_deadline: NextDeadline {
+                index: 3,
+                open: 4020,
+                close: 4080,
+            },
+            pending_worker_change: Some(PendingWorkerChange {
+                new_worker: Address::new_id(1001),
+                effective_at: 4900,
+            }),
+            beneficiary: Beneficiary {
+                address: Address::new_id(1002),
+                quota: TokenAmount::from_whole(5),
+                used_quota: TokenAmount::from_whole(2),
+                expiration: 100000,
+            },
+        }
+    }
+
+    #[test]
+    fn test_miner_state_table() {
+        let table = summary().to_table();
+        for expected in [
+            "f01000",
+            "10 FIL",
+            "1 FIL",
+            "20 FIL",
+            "30 live, 2 faulty, 1 recovering",
+            "5 active, 7 partitions",
+            "#3 (epochs 4020..4080)",
+            "f01001 at epoch 4900",
+            "f01002 (used 2 FIL of 5 FIL, expires at epoch 100000)",
+        ] {
+            assert!(table.contains(expected), "missing {expected:?} in\n{table}");
+        }
+    }
+
+    #[test]
+    fn test_miner_state_json() {
+        let json = serde_json::to_value(summary()).unwrap();
+        let fields = json.as_object().unwrap();
+        for field in [
+            "Miner",
+            "Epoch",
+            "AvailableBalance",
+            "FeeDebt",
+            "LockedFunds",
+            "LiveSectors",
+            "FaultySectors",
+            "RecoveringSectors",
+            "ActiveDeadlines",
+            "Partitions",
+            "NextDeadline",
+            "PendingWorkerChange",
+            "Beneficiary",
+        ] {
+            assert!(
+                fields.get(field).is_some_and(|value| !value.is_null()),
+                "missing {field}"
+            );
+        }
+        assert_eq!(fields["AvailableBalance"], "10000000000000000000");
+        assert_eq!(fields["PendingWorkerChange"]["NewWorker"], "f01001");
+    }
+
+    #[test]
+    fn test_miner_state_load() {
+        use crate::blocks::{chain4u, CachingBlockHeader, Chain4U, HeaderBuilder};
+        use crate::db::MemoryDB;
+        use crate::networks::ACTOR_BUNDLES_METADATA;
+        use crate::shim::machine::BuiltinActor;
+        use crate::shim::state_tree::{ActorState, StateTree, StateTreeVersion};
+        use crate::utils::bitfield::bitfield_of;
+        use crate::utils::db::CborStoreExt as _;
+        use fil_actor_miner_state::v13::{
+            BeneficiaryTerm, Deadline, MinerInfo, Partition, State as MinerStateV13,
+            WorkerKeyChange,
+        };
+        use fil_actors_shared::v13::Array;
+        use fvm_shared4::econ::TokenAmount as TokenAmountV4;
+        use fvm_shared4::sector::RegisteredPoStProof;
+
+        let db = Arc::new(MemoryDB::default());
+        let chain_config = Arc::new(ChainConfig::calibnet());
+        let policy = &chain_config.policy;
+        let miner = Address::new_id(1000);
+        const EPOCH: ChainEpoch = 10;
+
+        let mut info = MinerInfo::new(
+            1001,
+            1001,
+            vec![],
+            vec![],
+            vec![],
+            RegisteredPoStProof::StackedDRGWindow2KiBV1P1,
+        )
+        .unwrap();
+        info.pending_worker_key = Some(WorkerKeyChange {
+            new_worker: Address::new_id(1002).into(),
+            effective_at: 4900,
+        });
+        info.beneficiary = Address::new_id(1003).into();
+        info.beneficiary_term = BeneficiaryTerm {
+            quota: TokenAmountV4::from_whole(5),
+            used_quota: TokenAmountV4::from_whole(2),
+            expiration: 100_000,
+        };
+        // Three sectors in a single partition of the first deadline, one of
+        // them faulty and recovering
+        let mut partition = Partition::new(&db).unwrap();
+        partition.sectors = bitfield_of([1, 2, 3]);
+        partition.faults = bitfield_of([2]);
+        partition.recoveries = bitfield_of([2]);
+        let mut partitions = Array::<Partition, _>::new_with_bit_width(&db, 3);
+        partitions.set(0, partition).unwrap();
+        let mut deadline = Deadline::new(&db).unwrap();
+        deadline.partitions = partitions.flush().unwrap();
+        let mut miner_state =
+            MinerStateV13::new(policy, &db, db.put_cbor_default(&info).unwrap(), 0, 0).unwrap();
+        let mut deadlines = miner_state.load_deadlines(&db).unwrap();
+        deadlines.due[0] = db.put_cbor_default(&deadline).unwrap();
+        miner_state.deadlines = db.put_cbor_default(&deadlines).unwrap();
+        miner_state.fee_debt = TokenAmountV4::from_whole(1);
+        miner_state.locked_funds = TokenAmountV4::from_whole(20);
+
+        let miner_code = ACTOR_BUNDLES_METADATA
+            .get(&(NetworkChain::Calibnet, "v13.0.0".into()))
+            .unwrap()
+            .manifest
+            .get(BuiltinActor::Miner)
+            .unwrap();
+        let mut state_tree = StateTree::new(db.clone(), StateTreeVersion::V5).unwrap();
+        state_tree
+            .set_actor(
+                &miner,
+                ActorState::new(
+                    miner_code,
+                    db.put_cbor_default(&miner_state).unwrap(),
+                    TokenAmount::from_whole(50),
+                    0,
+                    None,
+                ),
+            )
+            .unwrap();
+        let state_root = state_tree.flush().unwrap();
+
+        let c4u = Chain4U::with_blockstore(db.clone());
+        chain4u! {
+            in c4u;
+            [genesis = HeaderBuilder::new().with_epoch(0)]
+            -> [head = HeaderBuilder::new().with_epoch(EPOCH).with_state_root(state_root)]
+        };
+        let chain_store = Arc::new(
+            ChainStore::new(
+                db.clone(),
+                db.clone(),
+                db.clone(),
+                chain_config.clone(),
+                CachingBlockHeader::new(genesis.clone()),
+            )
+            .unwrap(),
+        );
+        let state_manager = StateManager::new(
+            chain_store,
+            chain_config.clone(),
+            Arc::new(SyncConfig::default()),
+        )
+        .unwrap();
+
+        let summary =
+            MinerStateSummary::load(&state_manager, &Tipset::from(head.clone()), miner).unwrap();
+        assert_eq!(
+            summary,
+            MinerStateSummary {
+                miner,
+                epoch: EPOCH,
+                // The locked funds are not available
+                available_balance: TokenAmount::from_whole(30),
+                fee_debt: TokenAmount::from_whole(1),
+                locked_funds: TokenAmount::from_whole(20),
+                live_sectors: 3,
+                faulty_sectors: 1,
+                recovering_sectors: 1,
+                active_deadlines: 1,
+                partitions: 1,
+                // The first deadline of the proving period starting at genesis
+                next_deadline: NextDeadline {
+                    index: 0,
+                    open: 0,
+                    close: policy.wpost_challenge_window,
+                },
+                pending_worker_change: Some(PendingWorkerChange {
+                    new_worker: Address::new_id(1002),
+                    effective_at: 4900,
+                }),
+                beneficiary: Beneficiary {
+                    address: Address::new_id(1003),
+                    quota: TokenAmount::from_whole(5),
+                    used_quota: TokenAmount::from_whole(2),
+                    expiration: 100_000,
+                },
+            }
+        );
+    }
+}
@@ -8,6 +8,9 @@ mod benchmark_cmd;
 mod car_cmd;
 mod db_cmd;
 mod fetch_params_cmd;
+mod index_cmd;
+mod journal_cmd;
+mod miner_state_cmd;
 mod net_cmd;
 mod shed_cmd;
 mod snapshot_cmd;
@@ -59,6 +62,14 @@ pub enum Subcommand {
     #[command(subcommand)]
     DB(db_cmd::DBCommands),
 
+    /// Chain index management
+    #[command(subcommand)]
+    Index(index_cmd::IndexCommands),
+
+    /// Inspect the event journal of the node
+    #[command(subcommand)]
+    Journal(journal_cmd::JournalCommands),
+
     /// Utilities for manipulating CAR files
     #[command(subcommand)]
     Car(car_cmd::CarCommands),
@@ -71,6 +82,9 @@ pub enum Subcommand {
     #[command(subcommand)]
     Net(net_cmd::NetCommands),
 
+    /// Print a human-readable overview of a miner's state
+    MinerState(miner_state_cmd::MinerStateCommand),
+
     /// Miscellaneous, semver-exempt commands for developer use.
     #[command(subcommand)]
     Shed(shed_cmd::ShedCommands),
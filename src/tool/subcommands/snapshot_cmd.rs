@@ -101,6 +101,17 @@ pub enum SnapshotCommands {
         #[arg(long, default_value_t = false)]
         force: bool,
     },
+    /// Merge overlapping snapshots into a single CAR file, keeping one copy of
+    /// each block. Fails if a block header's parent is in none of the
+    /// snapshots.
+    Merge {
+        /// Output CAR file
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Path to a snapshot CAR, which may be zstd compressed
+        #[arg(required = true)]
+        snapshot_files: Vec<PathBuf>,
+    },
     /// Filecoin keeps track of "the state of the world", including:
     /// wallets and their balances;
     /// storage providers and their deals;
@@ -262,6 +273,20 @@ impl SnapshotCommands {
                 dest.flush().await?;
                 Ok(())
             }
+            Self::Merge {
+                output,
+                snapshot_files,
+            } => {
+                let stats = crate::chain::merge_car_files(&snapshot_files, &output).await?;
+                println!(
+                    "Wrote {} blocks ({}) to {}, skipped {} duplicates",
+                    stats.blocks_written,
+                    human_bytes::human_bytes(stats.total_bytes as f64),
+                    output.display(),
+                    stats.blocks_deduplicated
+                );
+                Ok(())
+            }
             SnapshotCommands::ComputeState {
                 snapshot,
                 epoch,
@@ -1,8 +1,20 @@
 // Copyright 2019-2025 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use crate::networks::{generate_actor_bundle, get_actor_bundles_metadata};
+use crate::daemon::bundle::load_actor_bundles;
+use crate::db::car::ManyCar;
+use crate::db::OverlayDB;
+use crate::networks::{
+    generate_actor_bundle, get_actor_bundles_metadata, ChainConfig, Height, NetworkChain,
+};
+use crate::shim::state_tree::StateTree;
+use crate::state_migration::run_state_migrations;
+use anyhow::Context as _;
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 #[derive(Debug, clap::Subcommand)]
 pub enum StateMigrationCommands {
@@ -13,6 +25,19 @@ pub enum StateMigrationCommands {
     },
     /// Generate actors metadata from required bundles list
     GenerateActorsMetadata,
+    /// Run the migration of an upgrade against the head state of a snapshot,
+    /// keeping all writes in memory
+    DryRun {
+        /// Upgrade height, e.g. `teep`
+        #[arg(long)]
+        height: Height,
+        /// Snapshot files to read the head state from
+        #[arg(long, required = true, num_args = 1..)]
+        snapshot_files: Vec<PathBuf>,
+        /// Fail if the migrated state root differs from this one
+        #[arg(long)]
+        expected_root: Option<Cid>,
+    },
 }
 
 impl StateMigrationCommands {
@@ -30,6 +55,176 @@ impl StateMigrationCommands {
 
                 Ok(())
             }
+            Self::DryRun {
+                height,
+                snapshot_files,
+                expected_root,
+            } => {
+                let store = Arc::new(ManyCar::try_from(snapshot_files)?);
+                let head = store.heaviest_tipset()?;
+                let genesis = head.genesis(&store)?;
+                let network = NetworkChain::from_genesis_or_devnet_placeholder(genesis.cid());
+                let chain_config = ChainConfig::from_chain(&network);
+                let epoch = chain_config.epoch(height);
+
+                let db = Arc::new(OverlayDB::new(store));
+                load_actor_bundles(&db, &network).await?;
+                println!(
+                    "Running {height} migration (epoch {epoch}) on the state of epoch {}",
+                    head.epoch()
+                );
+                let report = MigrationDryRun::run(&db, head.parent_state(), |db, state| {
+                    run_state_migrations(epoch, &chain_config, db, state)
+                })?
+                .with_context(|| format!("no migration registered at {height} on {network}"))?;
+                println!("{report}");
+                report.check_expected_root(expected_root.as_ref())
+            }
+        }
+    }
+}
+
+/// Outcome of a migration run on top of an [`OverlayDB`].
+#[derive(Debug)]
+struct MigrationDryRun {
+    old_root: Cid,
+    new_root: Cid,
+    /// Number of actors that are new or whose head, code or balance changed
+    actors_migrated: usize,
+    elapsed: Duration,
+}
+
+impl MigrationDryRun {
+    /// Runs `migrate` on the state and compares the result with the original
+    /// state. Returns `None` if no migration ran.
+    fn run<DB: Blockstore>(
+        db: &Arc<OverlayDB<DB>>,
+        state: &Cid,
+        migrate: impl FnOnce(&Arc<OverlayDB<DB>>, &Cid) -> anyhow::Result<Option<Cid>>,
+    ) -> anyhow::Result<Option<Self>> {
+        let start = Instant::now();
+        let Some(new_root) = migrate(db, state)? else {
+            return Ok(None);
+        };
+        let elapsed = start.elapsed();
+
+        let old_tree = StateTree::new_from_root(db.clone(), state)?;
+        let new_tree = StateTree::new_from_root(db.clone(), &new_root)?;
+        let mut actors_migrated = 0;
+        new_tree.for_each(|address, actor| {
+            if old_tree.get_actor(&address)?.as_ref() != Some(actor) {
+                actors_migrated += 1;
+            }
+            Ok(())
+        })?;
+
+        Ok(Some(Self {
+            old_root: *state,
+            new_root,
+            actors_migrated,
+            elapsed,
+        }))
+    }
+
+    fn check_expected_root(&self, expected: Option<&Cid>) -> anyhow::Result<()> {
+        match expected {
+            Some(expected) if *expected != self.new_root => anyhow::bail!(
+                "state root mismatch: expected {expected}, got {}",
+                self.new_root
+            ),
+            Some(_) => {
+                println!("State root matches the expected one");
+                Ok(())
+            }
+            None => Ok(()),
         }
     }
 }
+
+impl std::fmt::Display for MigrationDryRun {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Previous state root: {}", self.old_root)?;
+        writeln!(f, "New state root:      {}", self.new_root)?;
+        writeln!(f, "Actors migrated:     {}", self.actors_migrated)?;
+        write!(
+            f,
+            "Elapsed:             {}",
+            humantime::format_duration(Duration::from_millis(self.elapsed.as_millis() as u64))
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MemoryDB;
+    use crate::shim::address::Address;
+    use crate::shim::econ::TokenAmount;
+    use crate::shim::state_tree::{ActorState, StateTreeVersion};
+
+    fn state(db: &Arc<MemoryDB>) -> Cid {
+        let mut state_tree = StateTree::new(db.clone(), StateTreeVersion::V5).unwrap();
+        for id in [1000, 1001] {
+            state_tree
+                .set_actor(
+                    &Address::new_id(id),
+                    ActorState::new(
+                        Cid::default(),
+                        Cid::default(),
+                        TokenAmount::from_whole(1),
+                        0,
+                        None,
+                    ),
+                )
+                .unwrap();
+        }
+        state_tree.flush().unwrap()
+    }
+
+    /// Sets the balance of actor `f01000` to 2 FIL.
+    fn migrate(db: &Arc<OverlayDB<MemoryDB>>, state: &Cid) -> anyhow::Result<Option<Cid>> {
+        let mut state_tree = StateTree::new_from_root(db.clone(), state)?;
+        let addr = Address::new_id(1000);
+        let mut actor = state_tree.get_required_actor(&addr)?;
+        actor.balance = TokenAmount::from_whole(2).into();
+        state_tree.set_actor(&addr, actor)?;
+        Ok(Some(state_tree.flush()?))
+    }
+
+    #[test]
+    fn test_dry_run_does_not_modify_base() {
+        let base = Arc::new(MemoryDB::default());
+        let root = state(&base);
+        let db = Arc::new(OverlayDB::new(base.clone()));
+
+        let report = MigrationDryRun::run(&db, &root, migrate).unwrap().unwrap();
+        assert_eq!(report.old_root, root);
+        assert_ne!(report.new_root, root);
+        assert_eq!(report.actors_migrated, 1);
+        assert!(db.has(&report.new_root).unwrap());
+        assert!(!base.has(&report.new_root).unwrap());
+    }
+
+    #[test]
+    fn test_dry_run_without_migration() {
+        let base = Arc::new(MemoryDB::default());
+        let root = state(&base);
+        let db = Arc::new(OverlayDB::new(base));
+
+        assert!(MigrationDryRun::run(&db, &root, |_, _| Ok(None))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_dry_run_expected_root() {
+        let base = Arc::new(MemoryDB::default());
+        let root = state(&base);
+        let db = Arc::new(OverlayDB::new(base));
+
+        let report = MigrationDryRun::run(&db, &root, migrate).unwrap().unwrap();
+        report.check_expected_root(None).unwrap();
+        report.check_expected_root(Some(&report.new_root)).unwrap();
+        assert!(report.check_expected_root(Some(&root)).is_err());
+    }
+}
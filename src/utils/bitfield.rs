@@ -0,0 +1,55 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Set operations on [`BitField`]s, e.g. for aggregating sector numbers across
+//! the partitions of a miner.
+
+use fil_actors_shared::fvm_ipld_bitfield::BitField;
+
+/// Returns the bitfield with the given bits set.
+pub fn bitfield_of(bits: impl IntoIterator<Item = u64>) -> BitField {
+    let mut bitfield = BitField::new();
+    for bit in bits {
+        bitfield.set(bit);
+    }
+    bitfield
+}
+
+/// Returns the bits set in any of the bitfields.
+pub fn union_all(bitfields: impl IntoIterator<Item = BitField>) -> BitField {
+    bitfields
+        .into_iter()
+        .fold(BitField::new(), |mut union, bitfield| {
+            union |= &bitfield;
+            union
+        })
+}
+
+/// Returns the bits set in `bitfield` but not in `removed`.
+pub fn difference(bitfield: &BitField, removed: &BitField) -> BitField {
+    bitfield - removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_operations() {
+        let a = bitfield_of([1, 2, 3, 10]);
+        let b = bitfield_of([3, 4, 11]);
+        let c = bitfield_of([]);
+
+        assert_eq!(
+            union_all([a.clone(), b.clone(), c.clone()])
+                .iter()
+                .collect::<Vec<_>>(),
+            [1, 2, 3, 4, 10, 11]
+        );
+        assert!(union_all([]).is_empty());
+        assert_eq!(difference(&a, &b).iter().collect::<Vec<_>>(), [1, 2, 10]);
+        assert_eq!(difference(&b, &a).iter().collect::<Vec<_>>(), [4, 11]);
+        assert_eq!(difference(&a, &c), a);
+        assert!(difference(&c, &a).is_empty());
+    }
+}
@@ -0,0 +1,347 @@
+// Copyright 2019-2025 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! LRU caches bounded by an estimate of the memory they hold rather than by
+//! their number of entries. Every cache registers itself under a `kind` so
+//! that the entry counts and byte estimates can be reported via Prometheus
+//! and the `Forest.CacheStats` RPC method.
+
+use std::{
+    hash::Hash,
+    mem::size_of,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, OnceLock, Weak,
+    },
+};
+
+use crate::lotus_json::lotus_json;
+use crate::metrics::KindLabel;
+use ahash::{HashMap, HashMapExt as _};
+use itertools::Itertools as _;
+use lru::LruCache;
+use num::BigInt;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use prometheus_client::{
+    collector::Collector,
+    encoding::{DescriptorEncoder, EncodeMetric},
+    metrics::{family::Family, gauge::Gauge, TypedMetric as _},
+};
+use serde::{Deserialize, Serialize};
+
+const MIB: usize = 1024 * 1024;
+
+/// Memory budgets of the caches, in bytes.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(test, derive(derive_quickcheck_arbitrary::Arbitrary))]
+#[serde(default)]
+pub struct CacheConfig {
+    /// Tipsets loaded by the chain index.
+    pub tipset_bytes: usize,
+    /// Tipset weights computed by the chain store.
+    pub tipset_weight_bytes: usize,
+    /// State and receipt roots computed by the state manager.
+    pub tipset_state_bytes: usize,
+    /// Events emitted while computing tipset states.
+    pub tipset_events_bytes: usize,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            tipset_bytes: 256 * MIB,
+            tipset_weight_bytes: 4 * MIB,
+            tipset_state_bytes: 4 * MIB,
+            tipset_events_bytes: 64 * MIB,
+        }
+    }
+}
+
+static CACHE_CONFIG: OnceLock<CacheConfig> = OnceLock::new();
+
+impl CacheConfig {
+    /// Sets the budgets of the caches created from now on. Only the first call
+    /// has an effect, it should happen at startup before the chain store is
+    /// created.
+    pub fn set_global(self) {
+        let _ = CACHE_CONFIG.set(self);
+    }
+
+    /// Budgets set with [`CacheConfig::set_global`], or the default ones.
+    pub fn global() -> &'static CacheConfig {
+        CACHE_CONFIG.get_or_init(Default::default)
+    }
+}
+
+/// Estimate of the memory held by a value, including its inline size.
+pub trait EstimateSize {
+    fn estimated_size(&self) -> usize;
+}
+
+impl EstimateSize for BigInt {
+    fn estimated_size(&self) -> usize {
+        size_of::<Self>() + self.bits().div_ceil(8) as usize
+    }
+}
+
+impl<T: EstimateSize> EstimateSize for Arc<T> {
+    fn estimated_size(&self) -> usize {
+        size_of::<Self>() + T::estimated_size(self)
+    }
+}
+
+/// Entry count and byte estimate of a cache, shared with the registry.
+#[derive(Debug)]
+struct CacheUsage {
+    kind: &'static str,
+    entries: AtomicUsize,
+    size_bytes: AtomicUsize,
+    budget_bytes: usize,
+}
+
+static CACHE_REGISTRY: Lazy<Mutex<Vec<Weak<CacheUsage>>>> = Lazy::new(Default::default);
+
+/// An LRU cache evicting its least recently used entries once the estimated
+/// size of its keys and values exceeds a byte budget.
+pub struct SizeTrackingLruCache<K, V> {
+    /// Values along with the estimated size of their entry.
+    inner: LruCache<K, (V, usize)>,
+    usage: Arc<CacheUsage>,
+}
+
+impl<K, V> SizeTrackingLruCache<K, V>
+where
+    K: Hash + Eq + EstimateSize,
+    V: EstimateSize,
+{
+    pub fn new(kind: &'static str, budget_bytes: usize) -> Self {
+        let usage = Arc::new(CacheUsage {
+            kind,
+            entries: AtomicUsize::new(0),
+            size_bytes: AtomicUsize::new(0),
+            budget_bytes,
+        });
+        let mut registry = CACHE_REGISTRY.lock();
+        registry.retain(|usage| usage.strong_count() > 0);
+        registry.push(Arc::downgrade(&usage));
+        Self {
+            inner: LruCache::unbounded(),
+            usage,
+        }
+    }
+
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        self.inner.get(key).map(|(value, _)| value)
+    }
+
+    /// Inserts an entry, evicting the least recently used ones that no longer
+    /// fit in the budget. An entry larger than the whole budget is not kept.
+    pub fn put(&mut self, key: K, value: V) {
+        let entry_size = key.estimated_size() + value.estimated_size();
+        let mut size = self.size_bytes() + entry_size;
+        if let Some((_, replaced_size)) = self.inner.put(key, (value, entry_size)) {
+            size -= replaced_size;
+        }
+        while size > self.usage.budget_bytes {
+            match self.inner.pop_lru() {
+                Some((_, (_, evicted_size))) => size -= evicted_size,
+                None => break,
+            }
+        }
+        self.update_usage(size);
+    }
+
+    pub fn pop(&mut self, key: &K) -> Option<V> {
+        let (value, entry_size) = self.inner.pop(key)?;
+        self.update_usage(self.size_bytes() - entry_size);
+        Some(value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Estimated size of the cached keys and values.
+    pub fn size_bytes(&self) -> usize {
+        self.usage.size_bytes.load(Ordering::Relaxed)
+    }
+
+    fn update_usage(&self, size: usize) {
+        self.usage
+            .entries
+            .store(self.inner.len(), Ordering::Relaxed);
+        self.usage.size_bytes.store(size, Ordering::Relaxed);
+    }
+}
+
+lotus_json! {
+    /// Usage of the live caches of a kind.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct CacheStats {
+        pub kind: String,
+        pub entries: u64,
+        pub size_bytes: u64,
+        pub budget_bytes: u64,
+    }
+    snapshots {
+        vec![(
+            serde_json::json!({
+                "Kind": "tipset",
+                "Entries": 8192,
+                "SizeBytes": 4194304,
+                "BudgetBytes": 8388608,
+            }),
+            CacheStats {
+                kind: "tipset".into(),
+                entries: 8192,
+                size_bytes: 4194304,
+                budget_bytes: 8388608,
+            },
+        )]
+    }
+}
+
+/// Returns the usage of the live caches, summed by kind.
+pub fn cache_stats() -> Vec<CacheStats> {
+    let mut by_kind: HashMap<&'static str, CacheStats> = HashMap::new();
+    for usage in CACHE_REGISTRY.lock().iter().filter_map(Weak::upgrade) {
+        let stats = by_kind.entry(usage.kind).or_insert_with(|| CacheStats {
+            kind: usage.kind.to_string(),
+            entries: 0,
+            size_bytes: 0,
+            budget_bytes: 0,
+        });
+        stats.entries += usage.entries.load(Ordering::Relaxed) as u64;
+        stats.size_bytes += usage.size_bytes.load(Ordering::Relaxed) as u64;
+        stats.budget_bytes += usage.budget_bytes as u64;
+    }
+    by_kind
+        .into_values()
+        .sorted_by(|a, b| a.kind.cmp(&b.kind))
+        .collect()
+}
+
+/// Reports [`cache_stats`] as Prometheus gauges labeled by cache kind.
+#[derive(Debug, Default)]
+pub struct CacheCollector;
+
+impl Collector for CacheCollector {
+    fn encode(&self, mut encoder: DescriptorEncoder) -> Result<(), std::fmt::Error> {
+        let entries = Family::<KindLabel, Gauge>::default();
+        let size_bytes = Family::<KindLabel, Gauge>::default();
+        for usage in CACHE_REGISTRY.lock().iter().filter_map(Weak::upgrade) {
+            let label = KindLabel::new(usage.kind);
+            entries
+                .get_or_create(&label)
+                .inc_by(usage.entries.load(Ordering::Relaxed) as _);
+            size_bytes
+                .get_or_create(&label)
+                .inc_by(usage.size_bytes.load(Ordering::Relaxed) as _);
+        }
+        entries.encode(encoder.encode_descriptor(
+            "cache_entries",
+            "Number of entries in the caches",
+            None,
+            entries.metric_type(),
+        )?)?;
+        size_bytes.encode(encoder.encode_descriptor(
+            "cache_size_bytes",
+            "Estimated memory held by the caches in bytes",
+            None,
+            size_bytes.metric_type(),
+        )?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    struct Bytes(Vec<u8>);
+
+    impl EstimateSize for Bytes {
+        fn estimated_size(&self) -> usize {
+            size_of::<Self>() + self.0.len()
+        }
+    }
+
+    impl EstimateSize for u64 {
+        fn estimated_size(&self) -> usize {
+            size_of::<Self>()
+        }
+    }
+
+    #[test]
+    fn budget_is_never_exceeded() {
+        const BUDGET: usize = 64 * 1024;
+        let mut cache = SizeTrackingLruCache::new("test_budget", BUDGET);
+        for i in 0..100_000_u64 {
+            cache.put(i, Bytes(vec![0; (i % 512) as usize]));
+            assert!(cache.size_bytes() <= BUDGET);
+        }
+        // The tracked size is the sum of the estimates of the remaining entries
+        let tracked: usize = cache
+            .inner
+            .iter()
+            .map(|(k, (v, _))| k.estimated_size() + v.estimated_size())
+            .sum();
+        assert_eq!(cache.size_bytes(), tracked);
+        assert!(cache.len() > 100);
+        // Most recent entries are kept
+        assert!(cache.get(&99_999).is_some());
+        assert!(cache.get(&0).is_none());
+    }
+
+    #[test]
+    fn replaced_and_popped_entries_are_accounted() {
+        let mut cache = SizeTrackingLruCache::new("test_replace", 1024);
+        cache.put(1_u64, Bytes(vec![0; 100]));
+        let size = cache.size_bytes();
+        cache.put(1, Bytes(vec![0; 10]));
+        assert_eq!(cache.size_bytes(), size - 90);
+        assert_eq!(cache.pop(&1), Some(Bytes(vec![0; 10])));
+        assert_eq!(cache.size_bytes(), 0);
+        assert_eq!(cache.len(), 0);
+
+        // Entries larger than the budget are not kept
+        cache.put(2, Bytes(vec![0; 2048]));
+        assert_eq!(cache.len(), 0);
+        assert_eq!(cache.size_bytes(), 0);
+    }
+
+    #[test]
+    fn stats_are_summed_by_kind() {
+        let mut a = SizeTrackingLruCache::new("test_stats", 1024);
+        let mut b = SizeTrackingLruCache::new("test_stats", 1024);
+        a.put(1_u64, 2_u64);
+        b.put(1_u64, 2_u64);
+        b.put(2_u64, 2_u64);
+        let stats = cache_stats()
+            .into_iter()
+            .find(|stats| stats.kind == "test_stats")
+            .unwrap();
+        assert_eq!(
+            stats,
+            CacheStats {
+                kind: "test_stats".into(),
+                entries: 3,
+                size_bytes: 3 * 2 * size_of::<u64>() as u64,
+                budget_bytes: 2048,
+            }
+        );
+        drop(b);
+        let stats = cache_stats()
+            .into_iter()
+            .find(|stats| stats.kind == "test_stats")
+            .unwrap();
+        assert_eq!(stats.entries, 1);
+    }
+
+    #[test]
+    fn snapshots() {
+        crate::lotus_json::assert_all_snapshots::<CacheStats>();
+    }
+}
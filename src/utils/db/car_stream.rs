@@ -1,5 +1,6 @@
 // Copyright 2019-2025 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
+use crate::db::car::indexed::{CarV2Header, CARV2_PRAGMA};
 use crate::utils::multihash::prelude::*;
 use async_compression::tokio::bufread::ZstdDecoder;
 use bytes::{Buf, BufMut, Bytes, BytesMut};
@@ -14,7 +15,7 @@ use serde::{Deserialize, Serialize};
 use std::io;
 use std::pin::Pin;
 use std::task::{Context, Poll};
-use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncWrite};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, Take};
 use tokio_util::codec::Encoder;
 use tokio_util::codec::FramedRead;
 use tokio_util::either::Either;
@@ -82,10 +83,11 @@ impl CarBlock {
 
 pin_project! {
     /// Stream of CAR blocks. If the input data is compressed with zstd, it will
-    /// automatically be decompressed.
+    /// automatically be decompressed. The blocks of a CARv2 file are read from
+    /// its CARv1 data payload.
     pub struct CarStream<ReaderT> {
         #[pin]
-        reader: FramedRead<Either<ReaderT, ZstdDecoder<ReaderT>>, UviBytes>,
+        reader: FramedRead<Either<Take<ReaderT>, ZstdDecoder<Take<ReaderT>>>, UviBytes>,
         pub header: CarHeader,
         first_block: Option<CarBlock>,
     }
@@ -100,6 +102,26 @@ fn is_zstd(buf: &[u8]) -> bool {
 
 impl<ReaderT: AsyncBufRead + Unpin> CarStream<ReaderT> {
     pub async fn new(mut reader: ReaderT) -> io::Result<Self> {
+        let data_size = if reader.fill_buf().await?.starts_with(&CARV2_PRAGMA) {
+            let mut pragma_and_header = [0; CARV2_PRAGMA.len() + CarV2Header::SIZE];
+            reader.read_exact(&mut pragma_and_header).await?;
+            let header = CarV2Header::from_le_bytes(
+                pragma_and_header[CARV2_PRAGMA.len()..]
+                    .try_into()
+                    .expect("header size"),
+            );
+            let padding = header
+                .data_offset
+                .checked_sub(pragma_and_header.len() as u64)
+                .ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, "invalid CARv2 header")
+                })?;
+            tokio::io::copy(&mut (&mut reader).take(padding), &mut tokio::io::sink()).await?;
+            header.data_size
+        } else {
+            u64::MAX
+        };
+        let mut reader = reader.take(data_size);
         let is_compressed = is_zstd(reader.fill_buf().await?);
         let mut reader = if is_compressed {
             let mut zstd = ZstdDecoder::new(reader);
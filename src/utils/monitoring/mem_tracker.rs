@@ -27,6 +27,11 @@ impl MemStatsTracker {
         }
     }
 
+    /// Returns the peak resident set size recorded so far.
+    pub fn peak_physical_mem(&self) -> usize {
+        self.peak_physical_mem.load(atomic::Ordering::Relaxed)
+    }
+
     /// A blocking loop that records peak resident set size periodically
     pub async fn run_loop(&self) {
         while !self.cancelled.load(atomic::Ordering::Relaxed) {
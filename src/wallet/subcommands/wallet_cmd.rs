@@ -302,6 +302,10 @@ pub enum WalletCommands {
         #[arg(long, value_parser = humantoken::parse, default_value_t = TokenAmount::zero())]
         gas_premium: TokenAmount,
     },
+    /// Re-encrypt the local keystore under a new passphrase
+    RotatePassphrase,
+    /// Show the format version and key derivation parameters of the local keystore
+    KeystoreInfo,
 }
 impl WalletCommands {
     pub async fn run(
@@ -537,6 +541,42 @@ impl WalletCommands {
 
                 Ok(())
             }
+            Self::RotatePassphrase => {
+                let keystore = backend
+                    .local
+                    .as_mut()
+                    .context("passphrase rotation is only supported for local wallets")?;
+                let passphrase = tokio::task::spawn_blocking(|| {
+                    Password::with_theme(&ColorfulTheme::default())
+                        .with_prompt("Enter the new password for the wallet keystore")
+                        .with_confirmation("Confirm the new password", "Passwords do not match")
+                        .interact()
+                })
+                .await??;
+                keystore.rotate_passphrase(&passphrase)?;
+                println!("Keystore passphrase rotated");
+                Ok(())
+            }
+            Self::KeystoreInfo => {
+                let keystore = backend
+                    .local
+                    .as_ref()
+                    .context("keystore info is only available for local wallets")?;
+                match keystore.kdf_params() {
+                    Some(kdf) => {
+                        if let Some(version) = keystore.file_format_version()? {
+                            println!("Format version: {version}");
+                        }
+                        println!("KDF: Argon2id");
+                        println!("Memory cost: {} KiB", kdf.m_cost);
+                        println!("Time cost: {}", kdf.t_cost);
+                        println!("Parallelism: {}", kdf.p_cost);
+                        println!("Salt: {}", hex::encode(&kdf.salt));
+                    }
+                    None => println!("Keystore is not encrypted"),
+                }
+                Ok(())
+            }
         }
     }
 }